@@ -0,0 +1,145 @@
+//! Performance of the scan, estimate, and export paths, run with `cargo xtask bench`.
+//!
+//! Each benchmark builds its fixture once and lets criterion time the measured step. The xtask
+//! saves a run as a named baseline and compares later runs with it, failing when a median
+//! slows down by more than its threshold, so a redesign that slows one of these paths down
+//! fails loudly on the machine that measured the baseline.
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::Result;
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use llmctx::prelude::*;
+use tempfile::TempDir;
+
+/// Files in the synthetic tree walked by `scan`.
+const SCAN_FILES: usize = 50_000;
+/// Files in the bundle estimated by `estimate` and rendered by `export`.
+const BUNDLE_FILES: usize = 500;
+/// Lines in each bundled file.
+const BUNDLE_LINES: usize = 200;
+
+/// The steps take tenths of a second, so fewer samples than criterion's default of 100.
+fn config() -> Criterion {
+    Criterion::default()
+        .sample_size(10)
+        .measurement_time(Duration::from_secs(10))
+}
+
+/// A tree of `SCAN_FILES` small source files, 100 per directory, two levels deep.
+fn synthetic_tree() -> Result<TempDir> {
+    let dir = tempfile::tempdir()?;
+    for index in 0..SCAN_FILES {
+        let parent = dir
+            .path()
+            .join(format!("pkg{:02}", index / 5_000))
+            .join(format!("mod{:02}", index / 100 % 50));
+        if index % 100 == 0 {
+            fs::create_dir_all(&parent)?;
+        }
+        fs::write(
+            parent.join(format!("file{index}.rs")),
+            format!("pub fn f{index}() -> usize {{ {index} }}\n"),
+        )?;
+    }
+    Ok(dir)
+}
+
+/// `BUNDLE_FILES` Rust files of `BUNDLE_LINES` lines each.
+fn synthetic_bundle() -> Result<(TempDir, Vec<PathBuf>)> {
+    let dir = tempfile::tempdir()?;
+    let mut files = Vec::with_capacity(BUNDLE_FILES);
+    for index in 0..BUNDLE_FILES {
+        let path = dir.path().join(format!("file{index}.rs"));
+        fs::write(&path, source_file(index))?;
+        files.push(path);
+    }
+    Ok((dir, files))
+}
+
+fn source_file(seed: usize) -> String {
+    let mut source = String::new();
+    for line in 0..BUNDLE_LINES {
+        let text = match line % 4 {
+            0 => format!("/// Computes value {line} for item {seed}.\n"),
+            1 => format!("pub fn value_{line}(input: &[u64]) -> u64 {{\n"),
+            2 => format!("    input.iter().map(|x| x * {line} + {seed}).sum::<u64>()\n"),
+            _ => "}\n".to_string(),
+        };
+        source.push_str(&text);
+    }
+    source
+}
+
+fn selection(files: &[PathBuf]) -> SelectionManager {
+    let mut selection = SelectionManager::new();
+    for path in files {
+        selection.add_selection(path, None, None);
+    }
+    selection
+}
+
+fn scan(c: &mut Criterion) {
+    let tree = synthetic_tree().expect("synthetic tree");
+    let cfg = ScannerConfig::from_root(tree.path().to_path_buf(), Config::default());
+    let scanner = Scanner::new();
+    let files = scanner
+        .scan(&cfg)
+        .expect("scan")
+        .files
+        .iter()
+        .filter(|file| !file.is_dir)
+        .count();
+    assert_eq!(files, SCAN_FILES, "scanned {files} files");
+
+    c.bench_function("scan_50k_files", |b| {
+        b.iter(|| scanner.scan(&cfg).expect("scan"))
+    });
+}
+
+fn estimate(c: &mut Criterion) {
+    let (_dir, files) = synthetic_bundle().expect("synthetic bundle");
+    let config = Config::default();
+    let selection = selection(&files);
+
+    c.bench_function("estimate_500_files", |b| {
+        // A fresh estimator each run, so cached counts from the previous run don't help.
+        b.iter_batched(
+            || TokenEstimator::from_config(&config),
+            |estimator| {
+                let summary = selection.summarize_tokens(&estimator).expect("estimate");
+                assert!(summary.is_some_and(|summary| summary.items.len() == BUNDLE_FILES));
+            },
+            BatchSize::PerIteration,
+        )
+    });
+}
+
+fn export(c: &mut Criterion) {
+    let (_dir, files) = synthetic_bundle().expect("synthetic bundle");
+    let config = Config::default();
+    let selection = selection(&files);
+    let estimator = TokenEstimator::from_config(&config);
+    let summary = selection.summarize_tokens(&estimator).expect("estimate");
+    let exporter = Exporter::new().expect("exporter");
+    let options = ExportOptions::from_config(&config);
+    let bundle = selection.to_bundle();
+
+    c.bench_function("export_500_files", |b| {
+        b.iter(|| {
+            let result = exporter
+                .export(&bundle, summary.as_ref(), &options)
+                .expect("export");
+            assert!(!result.rendered.is_empty());
+        })
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = config();
+    targets = scan, estimate, export
+}
+criterion_main!(benches);
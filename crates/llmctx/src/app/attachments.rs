@@ -0,0 +1,94 @@
+//! Loading task descriptions (issues, tickets, notes) attached to export bundles.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow};
+
+use crate::domain::model::Attachment;
+use crate::infra::{github, jira};
+
+/// Load an attachment from a GitHub issue/PR URL, a Jira browse URL, or a local text file.
+pub fn load_attachment(source: &str) -> Result<Attachment> {
+    if github::is_issue_url(source) {
+        let issue = github::fetch_issue(source)
+            .with_context(|| format!("failed to fetch GitHub issue {source}"))?;
+        return Ok(Attachment {
+            title: format!("#{} {}", issue.number, issue.title),
+            source: source.to_string(),
+            body: issue.body.unwrap_or_default().trim().to_string(),
+        });
+    }
+    if jira::is_issue_url(source) {
+        let issue = jira::fetch_issue(source)
+            .with_context(|| format!("failed to fetch Jira issue {source}"))?;
+        return Ok(Attachment {
+            title: format!("{} {}", issue.key, issue.summary),
+            source: source.to_string(),
+            body: issue.description.unwrap_or_default().trim().to_string(),
+        });
+    }
+    if source.starts_with("https://") || source.starts_with("http://") {
+        return Err(anyhow!(
+            "unsupported attachment URL {source}; expected a GitHub issue/PR or Jira issue"
+        ));
+    }
+    load_file(Path::new(source))
+}
+
+/// A local document; a leading Markdown heading becomes the title, otherwise the file name.
+fn load_file(path: &Path) -> Result<Attachment> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("failed to read attachment {}", path.display()))?;
+    let contents = contents.trim();
+    let heading = contents
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix("# "))
+        .map(str::trim);
+    let (title, body) = match heading {
+        Some(heading) => (
+            heading.to_string(),
+            contents
+                .split_once('\n')
+                .map_or("", |(_, body)| body)
+                .trim(),
+        ),
+        None => (
+            path.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.display().to_string()),
+            contents,
+        ),
+    };
+    Ok(Attachment {
+        title,
+        source: path.display().to_string(),
+        body: body.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn local_files_use_their_heading_or_name_as_title() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let issue = temp.path().join("issue.md");
+        fs::write(&issue, "# Crash on empty config\n\nSteps:\n1. run it\n")?;
+        let notes = temp.path().join("notes.txt");
+        fs::write(&notes, "remember the edge case\n")?;
+
+        let attachment = load_attachment(issue.to_str().unwrap())?;
+        assert_eq!(attachment.title, "Crash on empty config");
+        assert_eq!(attachment.body, "Steps:\n1. run it");
+
+        let attachment = load_attachment(notes.to_str().unwrap())?;
+        assert_eq!(attachment.title, "notes.txt");
+        assert_eq!(attachment.body, "remember the edge case");
+
+        assert!(load_attachment("https://example.com/ticket/1").is_err());
+        Ok(())
+    }
+}
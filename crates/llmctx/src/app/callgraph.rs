@@ -0,0 +1,562 @@
+//! One-hop call graph expansion of selections.
+//!
+//! Function definitions and the calls inside them are found with tree-sitter queries for Rust,
+//! Go, JavaScript, and Python. TypeScript, which has no grammar here, falls back to a pattern
+//! for definitions whose bodies end at the matching `}`. Calls resolve by name alone, so names
+//! defined in many places are left out.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use rayon::prelude::*;
+use regex::Regex;
+use streaming_iterator::StreamingIterator;
+use tree_sitter::{Language, Parser, Query, QueryCursor};
+
+use crate::app::scan::FileMetadata;
+use crate::domain::model::SelectionItem;
+
+/// A tree-sitter grammar and a query capturing `@definition` nodes with their `@name`, and the
+/// callee names of calls as `@call`.
+struct Grammar {
+    language: Language,
+    query: Query,
+}
+
+impl Grammar {
+    fn new(language: impl Into<Language>, query: &str) -> Self {
+        let language = language.into();
+        let query = Query::new(&language, query).expect("call graph query compiles");
+        Self { language, query }
+    }
+}
+
+static RUST: LazyLock<Grammar> = LazyLock::new(|| {
+    Grammar::new(
+        tree_sitter_rust::LANGUAGE,
+        r#"
+        (function_item name: (identifier) @name) @definition
+        (call_expression
+          function: [
+            (identifier) @call
+            (scoped_identifier name: (identifier) @call)
+            (field_expression field: (field_identifier) @call)
+            (generic_function
+              function: [
+                (identifier) @call
+                (scoped_identifier name: (identifier) @call)
+                (field_expression field: (field_identifier) @call)
+              ])
+          ])
+        "#,
+    )
+});
+static GO: LazyLock<Grammar> = LazyLock::new(|| {
+    Grammar::new(
+        tree_sitter_go::LANGUAGE,
+        r#"
+        (function_declaration name: (identifier) @name) @definition
+        (method_declaration name: (field_identifier) @name) @definition
+        (call_expression
+          function: [
+            (identifier) @call
+            (selector_expression field: (field_identifier) @call)
+          ])
+        "#,
+    )
+});
+static JAVASCRIPT: LazyLock<Grammar> = LazyLock::new(|| {
+    Grammar::new(
+        tree_sitter_javascript::LANGUAGE,
+        r#"
+        (function_declaration name: (identifier) @name) @definition
+        (generator_function_declaration name: (identifier) @name) @definition
+        (method_definition name: (property_identifier) @name) @definition
+        (variable_declarator
+          name: (identifier) @name
+          value: [(arrow_function) (function_expression)]) @definition
+        (call_expression
+          function: [
+            (identifier) @call
+            (member_expression property: (property_identifier) @call)
+          ])
+        "#,
+    )
+});
+static PYTHON: LazyLock<Grammar> = LazyLock::new(|| {
+    Grammar::new(
+        tree_sitter_python::LANGUAGE,
+        r#"
+        (function_definition name: (identifier) @name) @definition
+        (call
+          function: [
+            (identifier) @call
+            (attribute attribute: (identifier) @call)
+          ])
+        "#,
+    )
+});
+
+static SCRIPT_FUNCTION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^\s*(?:export\s+)?(?:default\s+)?(?:async\s+)?function\s*\*?\s*([A-Za-z_$][\w$]*)|^\s*(?:export\s+)?(?:const|let|var)\s+([A-Za-z_$][\w$]*)\s*=\s*(?:async\s+)?(?:function\b|\([^)]*\)\s*=>|[A-Za-z_$][\w$]*\s*=>)|^\s+(?:static\s+)?(?:async\s+)?([A-Za-z_$][\w$]*)\s*\([^)]*\)\s*\{",
+    )
+    .unwrap()
+});
+static CALL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b([A-Za-z_]\w*)\s*(?:::<[^>]*>\s*)?\(").unwrap());
+
+/// Words followed by `(` that are not calls.
+const NOT_CALLS: &[&str] = &[
+    "if", "while", "for", "match", "return", "fn", "def", "function", "switch", "catch", "loop",
+    "elif", "and", "or", "not", "in", "await", "yield", "typeof", "new", "sizeof", "super",
+];
+/// Names defined more often than this are too ambiguous to follow.
+const MAX_DEFINITIONS: usize = 3;
+/// Lines scanned for the end of a brace-delimited function.
+const MAX_FUNCTION_LINES: usize = 2_000;
+
+/// A function definition and the names it calls.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    pub path: PathBuf,
+    /// 1-based inclusive line range of the definition.
+    pub start: usize,
+    pub end: usize,
+    calls: BTreeSet<String>,
+}
+
+/// Function definitions across the scanned files.
+#[derive(Debug, Clone, Default)]
+pub struct SymbolIndex {
+    symbols: Vec<Symbol>,
+    by_name: HashMap<String, Vec<usize>>,
+}
+
+/// How an expansion relates to the selected code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallDirection {
+    /// Called by a selected function.
+    Callee,
+    /// Calls a selected function.
+    Caller,
+}
+
+/// A function to add to the selection, and the selected function it is linked to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallExpansion {
+    pub symbol: Symbol,
+    pub direction: CallDirection,
+    pub via: String,
+}
+
+impl CallExpansion {
+    /// Whole-definition selection for the function.
+    pub fn selection(&self) -> (PathBuf, (usize, usize)) {
+        (
+            self.symbol.path.clone(),
+            (self.symbol.start, self.symbol.end),
+        )
+    }
+}
+
+impl SymbolIndex {
+    /// Read every scanned file in a supported language and record its functions.
+    pub fn build(files: &[FileMetadata]) -> Self {
+        let symbols: Vec<Symbol> = files
+            .par_iter()
+            .filter(|meta| !meta.is_dir && meta.skipped.is_none())
+            .flat_map_iter(|meta| {
+                let Some(language) = meta.language.as_deref() else {
+                    return Vec::new();
+                };
+                match fs::read_to_string(&meta.path) {
+                    Ok(contents) => extract_symbols(&meta.path, language, &contents),
+                    Err(_) => Vec::new(),
+                }
+            })
+            .collect();
+        let mut by_name: HashMap<String, Vec<usize>> = HashMap::new();
+        for (index, symbol) in symbols.iter().enumerate() {
+            by_name.entry(symbol.name.clone()).or_default().push(index);
+        }
+        Self { symbols, by_name }
+    }
+
+    /// Number of functions found.
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// Functions one call away from `selections`, callees first, each listed once.
+    ///
+    /// A selection covers the functions it overlaps; whole-file selections cover every function
+    /// in the file. Functions already covered are not repeated.
+    pub fn expand(&self, selections: &[SelectionItem]) -> Vec<CallExpansion> {
+        let covered = |symbol: &Symbol| {
+            selections.iter().any(|item| {
+                item.path == symbol.path
+                    && item
+                        .range
+                        .is_none_or(|(start, end)| start <= symbol.end && symbol.start <= end)
+            })
+        };
+        let selected: Vec<&Symbol> = self
+            .symbols
+            .iter()
+            .filter(|symbol| covered(symbol))
+            .collect();
+
+        let mut seen: HashSet<(&Path, usize)> = HashSet::new();
+        let mut callees = Vec::new();
+        let mut callers = Vec::new();
+        for origin in &selected {
+            for name in &origin.calls {
+                for symbol in self.definitions(name) {
+                    if !covered(symbol) && seen.insert((&symbol.path, symbol.start)) {
+                        callees.push(CallExpansion {
+                            symbol: symbol.clone(),
+                            direction: CallDirection::Callee,
+                            via: origin.name.clone(),
+                        });
+                    }
+                }
+            }
+        }
+        for origin in &selected {
+            if self.definitions(&origin.name).is_empty() {
+                continue;
+            }
+            for symbol in &self.symbols {
+                if symbol.calls.contains(&origin.name)
+                    && !covered(symbol)
+                    && seen.insert((&symbol.path, symbol.start))
+                {
+                    callers.push(CallExpansion {
+                        symbol: symbol.clone(),
+                        direction: CallDirection::Caller,
+                        via: origin.name.clone(),
+                    });
+                }
+            }
+        }
+        callees.extend(callers);
+        callees
+    }
+
+    /// Definitions of `name`, or none when it is defined too often to tell them apart.
+    fn definitions(&self, name: &str) -> Vec<&Symbol> {
+        match self.by_name.get(name) {
+            Some(indices) if indices.len() <= MAX_DEFINITIONS => {
+                indices.iter().map(|&index| &self.symbols[index]).collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Functions defined in `contents`, written in `language` (the file extension).
+fn extract_symbols(path: &Path, language: &str, contents: &str) -> Vec<Symbol> {
+    let grammar = match language {
+        "rs" => &*RUST,
+        "go" => &*GO,
+        "js" | "jsx" | "mjs" | "cjs" => &*JAVASCRIPT,
+        "py" | "pyi" => &*PYTHON,
+        "ts" | "tsx" | "mts" | "cts" => return extract_script_symbols(path, contents),
+        _ => return Vec::new(),
+    };
+    let mut parser = Parser::new();
+    if parser.set_language(&grammar.language).is_err() {
+        return Vec::new();
+    }
+    let Some(tree) = parser.parse(contents, None) else {
+        return Vec::new();
+    };
+
+    let names = grammar.query.capture_names();
+    let source = contents.as_bytes();
+    let mut definitions = Vec::new();
+    let mut calls = Vec::new();
+    let mut cursor = QueryCursor::new();
+    let mut matches = cursor.matches(&grammar.query, tree.root_node(), source);
+    while let Some(found) = matches.next() {
+        let mut definition = None;
+        let mut name = None;
+        for capture in found.captures {
+            let Ok(text) = capture.node.utf8_text(source) else {
+                continue;
+            };
+            match names[capture.index as usize] {
+                "definition" => definition = Some(capture.node),
+                "name" => name = Some(text.to_string()),
+                "call" => calls.push((capture.node.start_byte(), text.to_string())),
+                _ => {}
+            }
+        }
+        if let (Some(node), Some(name)) = (definition, name) {
+            definitions.push((node, name));
+        }
+    }
+
+    definitions
+        .into_iter()
+        .map(|(node, name)| {
+            let calls = calls
+                .iter()
+                .filter(|(at, call)| node.byte_range().contains(at) && *call != name)
+                .filter(|(_, call)| !NOT_CALLS.contains(&call.as_str()))
+                .map(|(_, call)| call.clone())
+                .collect();
+            Symbol {
+                name,
+                path: path.to_path_buf(),
+                start: node.start_position().row + 1,
+                end: node.end_position().row + 1,
+                calls,
+            }
+        })
+        .collect()
+}
+
+/// TypeScript functions found line by line, for lack of a grammar.
+fn extract_script_symbols(path: &Path, contents: &str) -> Vec<Symbol> {
+    let lines: Vec<&str> = contents.lines().collect();
+    let mut symbols = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        let Some(captures) = SCRIPT_FUNCTION.captures(line) else {
+            continue;
+        };
+        let Some(name) = (1..=3).find_map(|group| captures.get(group)) else {
+            continue;
+        };
+        let name = name.as_str().to_string();
+        if NOT_CALLS.contains(&name.as_str()) {
+            continue;
+        }
+        let end = brace_end(&lines, index);
+        let calls = lines[index..=end]
+            .iter()
+            .flat_map(|line| CALL.captures_iter(line))
+            .map(|captures| captures[1].to_string())
+            .filter(|call| *call != name && !NOT_CALLS.contains(&call.as_str()))
+            .collect();
+        symbols.push(Symbol {
+            name,
+            path: path.to_path_buf(),
+            start: index + 1,
+            end: end + 1,
+            calls,
+        });
+    }
+    symbols
+}
+
+/// Index of the line closing the body that opens at or after `start`.
+///
+/// String literals and `//` comments are skipped roughly; a `;` before any `{` means a
+/// declaration without a body.
+fn brace_end(lines: &[&str], start: usize) -> usize {
+    let mut depth = 0usize;
+    let mut opened = false;
+    for (index, line) in lines
+        .iter()
+        .enumerate()
+        .skip(start)
+        .take(MAX_FUNCTION_LINES)
+    {
+        let mut quote = None;
+        let mut previous = '\0';
+        for ch in line.chars() {
+            match quote {
+                Some(open) if ch == open && previous != '\\' => quote = None,
+                Some(_) => {}
+                None => match ch {
+                    '"' | '`' => quote = Some(ch),
+                    '/' if previous == '/' => break,
+                    '{' => {
+                        depth += 1;
+                        opened = true;
+                    }
+                    '}' => {
+                        depth = depth.saturating_sub(1);
+                        if opened && depth == 0 {
+                            return index;
+                        }
+                    }
+                    ';' if !opened => return index,
+                    _ => {}
+                },
+            }
+            previous = if previous == '\\' { '\0' } else { ch };
+        }
+    }
+    start
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use anyhow::Result;
+
+    use crate::app::scan::FileClass;
+
+    fn scanned(root: &Path, paths: &[&str]) -> Vec<FileMetadata> {
+        paths
+            .iter()
+            .map(|display| FileMetadata {
+                path: root.join(display),
+                display_path: display.to_string(),
+                is_dir: false,
+                size: None,
+                modified: None,
+                language: Path::new(display)
+                    .extension()
+                    .map(|ext| ext.to_string_lossy().into_owned()),
+                skipped: None,
+                class: FileClass::Source,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn expands_selected_functions_by_one_hop() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = temp.path();
+        let files = [
+            (
+                "src/retry.rs",
+                "pub fn retry(attempts: u32) {\n    let delay = backoff(attempts);\n    if delay > 0 {\n        sleep_for(delay);\n    }\n}\n\nfn backoff(attempts: u32) -> u64 {\n    let s = \"}\";\n    2u64.pow(attempts)\n}\n",
+            ),
+            (
+                "src/client.rs",
+                "pub fn send() {\n    retry(3);\n}\n\npub fn unrelated() {}\n",
+            ),
+            (
+                "worker.py",
+                "def sleep_for(delay):\n    if delay:\n        wait(delay)\n\n\ndef wait(delay):\n    pass\n",
+            ),
+        ];
+        for (path, contents) in files {
+            fs::create_dir_all(root.join(path).parent().unwrap())?;
+            fs::write(root.join(path), contents)?;
+        }
+        let paths: Vec<&str> = files.iter().map(|(path, _)| *path).collect();
+        let index = SymbolIndex::build(&scanned(root, &paths));
+        assert_eq!(index.len(), 6);
+
+        let selection = SelectionItem {
+            path: root.join("src/retry.rs"),
+            range: Some((2, 4)),
+            note: None,
+            source: Default::default(),
+        };
+        let expansions: Vec<(String, CallDirection, (usize, usize))> = index
+            .expand(&[selection])
+            .iter()
+            .map(|expansion| {
+                (
+                    expansion.symbol.name.clone(),
+                    expansion.direction,
+                    expansion.selection().1,
+                )
+            })
+            .collect();
+        assert_eq!(
+            expansions,
+            vec![
+                ("backoff".to_string(), CallDirection::Callee, (8, 11)),
+                ("sleep_for".to_string(), CallDirection::Callee, (1, 3)),
+                ("send".to_string(), CallDirection::Caller, (1, 3)),
+            ]
+        );
+
+        let whole_file = SelectionItem {
+            path: root.join("src/retry.rs"),
+            range: None,
+            note: None,
+            source: Default::default(),
+        };
+        let names: Vec<String> = index
+            .expand(&[whole_file])
+            .into_iter()
+            .map(|expansion| expansion.symbol.name)
+            .collect();
+        assert_eq!(names, vec!["sleep_for", "send"]);
+        Ok(())
+    }
+
+    #[test]
+    fn finds_definitions_in_each_language() {
+        let cases = [
+            (
+                "go",
+                "package main\n\nfunc (s *Server) Handle() {\n\ts.reply()\n}\n\nfunc reply() {\n\tfmt.Println(\"ok\")\n}\n",
+            ),
+            (
+                "js",
+                "export function load(path) {\n  return parse(read(path));\n}\n\nconst parse = (text) => {\n  return JSON.parse(text);\n};\n\nclass Store {\n  save() {\n    this.flush();\n  }\n}\n",
+            ),
+            (
+                "ts",
+                "export function load(path: string): Config {\n  return parse(path);\n}\n",
+            ),
+            (
+                "rs",
+                "impl Store {\n    fn save(&self) {\n        self.flush::<u8>();\n    }\n}\n",
+            ),
+            (
+                "py",
+                "class Store:\n    def save(self):\n        self.flush()\n",
+            ),
+        ];
+        type Found = (String, usize, usize, Vec<String>);
+        let found: Vec<(&str, Vec<Found>)> = cases
+            .iter()
+            .map(|(language, contents)| {
+                let symbols = extract_symbols(Path::new("file"), language, contents)
+                    .into_iter()
+                    .map(|symbol| {
+                        let calls = symbol.calls.into_iter().collect();
+                        (symbol.name, symbol.start, symbol.end, calls)
+                    })
+                    .collect();
+                (*language, symbols)
+            })
+            .collect();
+        let symbol = |name: &str, start, end, calls: &[&str]| {
+            let calls = calls.iter().map(|call| call.to_string()).collect();
+            (name.to_string(), start, end, calls)
+        };
+        assert_eq!(
+            found,
+            vec![
+                (
+                    "go",
+                    vec![
+                        symbol("Handle", 3, 5, &["reply"]),
+                        symbol("reply", 7, 9, &["Println"]),
+                    ]
+                ),
+                (
+                    "js",
+                    vec![
+                        symbol("load", 1, 3, &["parse", "read"]),
+                        symbol("parse", 5, 7, &[]),
+                        symbol("save", 10, 12, &["flush"]),
+                    ]
+                ),
+                ("ts", vec![symbol("load", 1, 3, &["parse"])]),
+                ("rs", vec![symbol("save", 2, 4, &["flush"])]),
+                ("py", vec![symbol("save", 2, 3, &["flush"])]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,40 @@
+//! Cooperative cancellation for scans, searches, token estimates, and exports.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::domain::errors::DomainError;
+
+/// Shared flag that long-running operations poll between units of work.
+///
+/// Clones observe the same flag, so a front end keeps one clone and hands the others to the
+/// work it may abort. A default token is never cancelled.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask every operation holding a clone to stop.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether [`Self::cancel`] has been called on any clone.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Fail with [`DomainError::Cancelled`] once cancelled.
+    pub fn check(&self) -> Result<(), DomainError> {
+        if self.is_cancelled() {
+            Err(DomainError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
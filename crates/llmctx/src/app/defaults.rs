@@ -0,0 +1,107 @@
+//! Workspace default selections listed in `.llmctx/always-include.toml`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use globset::{Glob, GlobSetBuilder};
+use serde::Deserialize;
+
+use crate::app::scan::FileMetadata;
+
+/// Location of the defaults file relative to the workspace root.
+pub const ALWAYS_INCLUDE_FILE: &str = ".llmctx/always-include.toml";
+
+/// Paths and globs selected automatically in every new session.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct AlwaysInclude {
+    /// Workspace-relative paths or globs, e.g. `ARCHITECTURE.md` or `db/*.sql`.
+    #[serde(default)]
+    pub paths: Vec<String>,
+}
+
+impl AlwaysInclude {
+    /// Read the defaults file under `root`, if present.
+    pub fn load(root: &Path) -> Result<Option<Self>> {
+        let path = root.join(ALWAYS_INCLUDE_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let data = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let defaults =
+            toml::from_str(&data).with_context(|| format!("invalid {}", path.display()))?;
+        Ok(Some(defaults))
+    }
+
+    /// Scanned files under `root` matching any listed path or glob, in scan order.
+    ///
+    /// Directories and files skipped by the scan (binary or too large) are never included.
+    pub fn matching(&self, root: &Path, files: &[FileMetadata]) -> Result<Vec<PathBuf>> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in &self.paths {
+            let pattern = pattern.trim().trim_start_matches("./");
+            builder.add(
+                Glob::new(pattern)
+                    .with_context(|| format!("invalid always-include pattern '{pattern}'"))?,
+            );
+        }
+        let globs = builder.build().context("invalid always-include patterns")?;
+        Ok(files
+            .iter()
+            .filter(|meta| !meta.is_dir && meta.skipped.is_none())
+            .filter(|meta| {
+                meta.path
+                    .strip_prefix(root)
+                    .is_ok_and(|relative| globs.is_match(relative))
+            })
+            .map(|meta| meta.path.clone())
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::app::scan::{FileClass, SkipReason};
+
+    fn file(root: &Path, relative: &str, skipped: Option<SkipReason>) -> FileMetadata {
+        FileMetadata {
+            path: root.join(relative),
+            display_path: relative.to_string(),
+            is_dir: false,
+            size: Some(1),
+            modified: None,
+            language: None,
+            skipped,
+            class: FileClass::Source,
+        }
+    }
+
+    #[test]
+    fn listed_paths_and_globs_match_scanned_files() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = temp.path();
+        assert_eq!(AlwaysInclude::load(root)?, None);
+
+        fs::create_dir(root.join(".llmctx"))?;
+        fs::write(
+            root.join(ALWAYS_INCLUDE_FILE),
+            "paths = [\"./ARCHITECTURE.md\", \"db/*.sql\"]\n",
+        )?;
+        let defaults = AlwaysInclude::load(root)?.expect("defaults file");
+
+        let files = vec![
+            file(root, "ARCHITECTURE.md", None),
+            file(root, "README.md", None),
+            file(root, "db/schema.sql", None),
+            file(root, "db/dump.sql", Some(SkipReason::LargeFile)),
+        ];
+        assert_eq!(
+            defaults.matching(root, &files)?,
+            vec![root.join("ARCHITECTURE.md"), root.join("db/schema.sql")]
+        );
+        Ok(())
+    }
+}
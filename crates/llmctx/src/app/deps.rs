@@ -0,0 +1,352 @@
+//! File-level dependency graph built from import statements.
+//!
+//! Imports are read with per-language patterns rather than a parser, and only imports that
+//! resolve to a scanned file become edges: Rust `mod` declarations and `use crate::` paths,
+//! relative JavaScript and TypeScript imports, and Python imports.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::LazyLock;
+
+use rayon::prelude::*;
+use regex::Regex;
+
+use crate::app::scan::FileMetadata;
+
+static RUST_MOD: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*(?:pub(?:\([^)]*\))?\s+)?mod\s+([A-Za-z_]\w*)\s*;").unwrap());
+static RUST_USE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\buse\s+crate::((?:\w+::)*)(\w+|\{[^}]*\})").unwrap());
+static SCRIPT_IMPORT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?:\bfrom\s+|\bimport\s+|\brequire\(\s*|\bimport\(\s*)['"](\.{1,2}/[^'"]*)['"]"#)
+        .unwrap()
+});
+static PYTHON_FROM: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*from\s+(\.*)([\w.]*)\s+import\s+\(?([\w\s,]+)").unwrap());
+static PYTHON_IMPORT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*import\s+([\w.]+(?:\s*,\s*[\w.]+)*)").unwrap());
+
+const SCRIPT_EXTENSIONS: &[&str] = &["ts", "tsx", "js", "jsx", "mjs", "cjs"];
+
+/// Which scanned files import which.
+#[derive(Debug, Clone, Default)]
+pub struct DependencyGraph {
+    imports: HashMap<PathBuf, BTreeSet<PathBuf>>,
+    importers: HashMap<PathBuf, BTreeSet<PathBuf>>,
+}
+
+impl DependencyGraph {
+    /// Read every scanned file and link it to the scanned files it imports.
+    pub fn build(files: &[FileMetadata]) -> Self {
+        let known: HashSet<&Path> = files
+            .iter()
+            .filter(|meta| !meta.is_dir)
+            .map(|meta| meta.path.as_path())
+            .collect();
+        let edges: Vec<(PathBuf, BTreeSet<PathBuf>)> = files
+            .par_iter()
+            .filter(|meta| !meta.is_dir && meta.skipped.is_none())
+            .filter_map(|meta| {
+                let language = meta.language.as_deref()?;
+                let contents = fs::read_to_string(&meta.path).ok()?;
+                let targets: BTreeSet<PathBuf> =
+                    import_candidates(&meta.path, language, &contents, &known)
+                        .into_iter()
+                        .filter(|target| *target != meta.path)
+                        .collect();
+                (!targets.is_empty()).then(|| (meta.path.clone(), targets))
+            })
+            .collect();
+
+        let mut graph = Self::default();
+        for (path, targets) in edges {
+            for target in &targets {
+                graph
+                    .importers
+                    .entry(target.clone())
+                    .or_default()
+                    .insert(path.clone());
+            }
+            graph.imports.insert(path, targets);
+        }
+        graph
+    }
+
+    /// Files imported by `path`.
+    pub fn imports(&self, path: &Path) -> impl Iterator<Item = &Path> {
+        self.imports
+            .get(path)
+            .into_iter()
+            .flatten()
+            .map(PathBuf::as_path)
+    }
+
+    /// Files importing `path`.
+    pub fn importers(&self, path: &Path) -> impl Iterator<Item = &Path> {
+        self.importers
+            .get(path)
+            .into_iter()
+            .flatten()
+            .map(PathBuf::as_path)
+    }
+
+    /// Number of import edges.
+    pub fn edge_count(&self) -> usize {
+        self.imports.values().map(BTreeSet::len).sum()
+    }
+}
+
+/// Scanned files that `path`, written in `language` (its extension), imports.
+fn import_candidates(
+    path: &Path,
+    language: &str,
+    contents: &str,
+    known: &HashSet<&Path>,
+) -> Vec<PathBuf> {
+    let Some(dir) = path.parent() else {
+        return Vec::new();
+    };
+    let first_known = |candidates: Vec<PathBuf>| {
+        candidates
+            .into_iter()
+            .map(|candidate| normalize(&candidate))
+            .find(|candidate| known.contains(candidate.as_path()))
+    };
+    let mut found = Vec::new();
+    match language {
+        "rs" => {
+            let module_dir = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some("mod" | "lib" | "main") | None => dir.to_path_buf(),
+                Some(stem) => dir.join(stem),
+            };
+            let crate_root = dir.ancestors().find(|ancestor| {
+                known.contains(ancestor.join("lib.rs").as_path())
+                    || known.contains(ancestor.join("main.rs").as_path())
+            });
+            for line in contents.lines() {
+                if let Some(captures) = RUST_MOD.captures(line) {
+                    let name = &captures[1];
+                    found.extend(first_known(vec![
+                        module_dir.join(format!("{name}.rs")),
+                        module_dir.join(name).join("mod.rs"),
+                    ]));
+                }
+                let Some(crate_root) = crate_root else {
+                    continue;
+                };
+                for captures in RUST_USE.captures_iter(line) {
+                    let prefix: Vec<&str> =
+                        captures[1].split("::").filter(|s| !s.is_empty()).collect();
+                    let last = &captures[2];
+                    let names: Vec<&str> = match last.strip_prefix('{') {
+                        Some(group) => group
+                            .trim_end_matches('}')
+                            .split(',')
+                            .filter_map(|name| name.trim().split("::").next())
+                            .filter(|name| !name.is_empty() && *name != "self")
+                            .collect(),
+                        None => vec![last],
+                    };
+                    for name in names {
+                        let mut segments = prefix.clone();
+                        segments.push(name);
+                        found.extend(first_known(rust_module_candidates(crate_root, &segments)));
+                    }
+                }
+            }
+        }
+        ext if SCRIPT_EXTENSIONS.contains(&ext) => {
+            for captures in SCRIPT_IMPORT.captures_iter(contents) {
+                let target = dir.join(&captures[1]);
+                let mut candidates = vec![target.clone()];
+                for ext in SCRIPT_EXTENSIONS {
+                    let mut with_ext = target.clone().into_os_string();
+                    with_ext.push(format!(".{ext}"));
+                    candidates.push(PathBuf::from(with_ext));
+                }
+                for ext in SCRIPT_EXTENSIONS {
+                    candidates.push(target.join(format!("index.{ext}")));
+                }
+                found.extend(first_known(candidates));
+            }
+        }
+        "py" | "pyi" => {
+            for line in contents.lines() {
+                if let Some(captures) = PYTHON_FROM.captures(line) {
+                    let dots = captures[1].len();
+                    let module: Vec<&str> =
+                        captures[2].split('.').filter(|s| !s.is_empty()).collect();
+                    let bases: Vec<PathBuf> = if dots > 0 {
+                        dir.ancestors()
+                            .nth(dots - 1)
+                            .map(Path::to_path_buf)
+                            .into_iter()
+                            .collect()
+                    } else {
+                        dir.ancestors().map(Path::to_path_buf).collect()
+                    };
+                    for base in &bases {
+                        // `from pkg import name` may name a submodule rather than an attribute.
+                        for name in captures[3].split(',') {
+                            let name = name.split_whitespace().next().unwrap_or_default();
+                            if name.is_empty() {
+                                continue;
+                            }
+                            let mut segments = module.clone();
+                            segments.push(name);
+                            found.extend(first_known(python_module_candidates(base, &segments)));
+                        }
+                        if !module.is_empty() {
+                            found.extend(first_known(python_module_candidates(base, &module)));
+                        }
+                    }
+                } else if let Some(captures) = PYTHON_IMPORT.captures(line) {
+                    for module in captures[1].split(',') {
+                        let segments: Vec<&str> = module.trim().split('.').collect();
+                        for base in dir.ancestors() {
+                            found.extend(first_known(python_module_candidates(base, &segments)));
+                        }
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+    found
+}
+
+/// Files that may define `crate::a::b::c`, longest module path first.
+fn rust_module_candidates(crate_root: &Path, segments: &[&str]) -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+    for len in (1..=segments.len()).rev() {
+        let module: PathBuf = segments[..len].iter().collect();
+        let module = crate_root.join(module);
+        candidates.push(module.with_extension("rs"));
+        candidates.push(module.join("mod.rs"));
+    }
+    candidates
+}
+
+/// Files that may define the Python module `a.b.c` beneath `base`, longest module path first.
+fn python_module_candidates(base: &Path, segments: &[&str]) -> Vec<PathBuf> {
+    let module: PathBuf = segments.iter().collect();
+    let module = base.join(module);
+    vec![module.with_extension("py"), module.join("__init__.py")]
+}
+
+/// Resolve `.` and `..` components without touching the filesystem.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use anyhow::Result;
+
+    use crate::app::scan::FileClass;
+
+    fn scanned(root: &Path, paths: &[&str]) -> Vec<FileMetadata> {
+        paths
+            .iter()
+            .map(|display| FileMetadata {
+                path: root.join(display),
+                display_path: display.to_string(),
+                is_dir: false,
+                size: None,
+                modified: None,
+                language: Path::new(display)
+                    .extension()
+                    .map(|ext| ext.to_string_lossy().into_owned()),
+                skipped: None,
+                class: FileClass::Source,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn links_rust_script_and_python_imports() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = temp.path();
+        let files = [
+            ("src/lib.rs", "pub mod app;\nmod util;\n"),
+            ("src/app/mod.rs", "pub mod search;\n"),
+            (
+                "src/app/search.rs",
+                "use crate::util::{self, trim};\nuse crate::app::scan::FileMetadata;\nuse std::fs;\n",
+            ),
+            ("src/util.rs", "pub fn trim() {}\n"),
+            (
+                "web/index.ts",
+                "import { api } from './lib/api';\nconst x = require(\"../x\");\n",
+            ),
+            ("web/lib/api/index.ts", "export const api = 1;\n"),
+            (
+                "svc/handlers.py",
+                "from .models import User\nimport svc.db\n",
+            ),
+            ("svc/models.py", "class User: pass\n"),
+            ("svc/db/__init__.py", "\n"),
+        ];
+        for (path, contents) in files {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap())?;
+            fs::write(path, contents)?;
+        }
+        let paths: Vec<&str> = files.iter().map(|(path, _)| *path).collect();
+        let graph = DependencyGraph::build(&scanned(root, &paths));
+
+        let imports = |path: &str| -> Vec<PathBuf> {
+            graph
+                .imports(&root.join(path))
+                .map(|target| target.strip_prefix(root).unwrap().to_path_buf())
+                .collect()
+        };
+        assert_eq!(
+            imports("src/lib.rs"),
+            vec![
+                PathBuf::from("src/app/mod.rs"),
+                PathBuf::from("src/util.rs")
+            ]
+        );
+        assert_eq!(
+            imports("src/app/mod.rs"),
+            vec![PathBuf::from("src/app/search.rs")]
+        );
+        assert_eq!(
+            imports("src/app/search.rs"),
+            vec![
+                PathBuf::from("src/app/mod.rs"),
+                PathBuf::from("src/util.rs")
+            ]
+        );
+        assert_eq!(
+            imports("web/index.ts"),
+            vec![PathBuf::from("web/lib/api/index.ts")]
+        );
+        assert_eq!(
+            imports("svc/handlers.py"),
+            vec![
+                PathBuf::from("svc/db/__init__.py"),
+                PathBuf::from("svc/models.py")
+            ]
+        );
+
+        let importers: Vec<&Path> = graph.importers(&root.join("src/util.rs")).collect();
+        assert_eq!(importers.len(), 2);
+        assert_eq!(graph.edge_count(), 8);
+        Ok(())
+    }
+}
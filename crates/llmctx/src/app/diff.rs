@@ -0,0 +1,344 @@
+//! Line diffs between two versions of a file, used when exports compare against a revision.
+//!
+//! Lines are matched with Myers' algorithm after trimming the common prefix and suffix. Past
+//! [`MAX_EDITS`] differences the search gives up and the changed middle is replaced wholesale,
+//! which keeps unrelated rewrites cheap at the cost of a less minimal diff.
+
+use std::fmt::Write as _;
+
+/// Edits searched for before falling back to replacing the changed middle wholesale.
+const MAX_EDITS: usize = 1_000;
+
+/// One line of a diff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffLine<'a> {
+    /// Present in both versions.
+    Same(&'a str),
+    /// Only in the old version.
+    Removed(&'a str),
+    /// Only in the new version.
+    Added(&'a str),
+}
+
+impl DiffLine<'_> {
+    fn is_change(&self) -> bool {
+        !matches!(self, DiffLine::Same(_))
+    }
+}
+
+/// The lines of an old and a new version of a text, in order, matched up.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LineDiff<'a> {
+    lines: Vec<DiffLine<'a>>,
+}
+
+impl<'a> LineDiff<'a> {
+    pub fn new(old: &'a str, new: &'a str) -> Self {
+        let old: Vec<&str> = old.lines().collect();
+        let new: Vec<&str> = new.lines().collect();
+        let prefix = old
+            .iter()
+            .zip(&new)
+            .take_while(|(old, new)| old == new)
+            .count();
+        let suffix = old[prefix..]
+            .iter()
+            .rev()
+            .zip(new[prefix..].iter().rev())
+            .take_while(|(old, new)| old == new)
+            .count();
+
+        let mut lines: Vec<DiffLine<'a>> = old[..prefix]
+            .iter()
+            .map(|line| DiffLine::Same(line))
+            .collect();
+        let old_middle = &old[prefix..old.len() - suffix];
+        let new_middle = &new[prefix..new.len() - suffix];
+        lines.extend(middle(old_middle, new_middle));
+        lines.extend(
+            old[old.len() - suffix..]
+                .iter()
+                .map(|line| DiffLine::Same(line)),
+        );
+        Self { lines }
+    }
+
+    pub fn lines(&self) -> &[DiffLine<'a>] {
+        &self.lines
+    }
+
+    /// Whether any line within `range` of the new version changed, counting lines removed
+    /// just before a line of the range. `None` covers the whole file.
+    pub fn changed(&self, range: Option<(usize, usize)>) -> bool {
+        self.positions()
+            .any(|(line, position)| line.is_change() && in_range(position.new_anchor, range))
+    }
+
+    /// The old lines matching `range` of the new version: those kept within it and those
+    /// removed between its lines, with the old line number of the first one.
+    pub fn old_lines(&self, range: Option<(usize, usize)>) -> (Option<usize>, Vec<&'a str>) {
+        let mut first = None;
+        let mut lines = Vec::new();
+        for (line, position) in self.positions() {
+            let text = match line {
+                DiffLine::Same(text) | DiffLine::Removed(text) => *text,
+                DiffLine::Added(_) => continue,
+            };
+            if in_range(position.new_anchor, range) {
+                first.get_or_insert(position.old_line);
+                lines.push(text);
+            }
+        }
+        (first, lines)
+    }
+
+    /// Unified diff of the changes within `range` of the new version, with `context` unchanged
+    /// lines around each hunk; empty when nothing there changed.
+    pub fn unified(&self, range: Option<(usize, usize)>, context: usize) -> String {
+        let positions: Vec<Position> = self.positions().map(|(_, position)| position).collect();
+        let changes: Vec<usize> = self
+            .lines
+            .iter()
+            .zip(&positions)
+            .enumerate()
+            .filter(|(_, (line, position))| {
+                line.is_change() && in_range(position.new_anchor, range)
+            })
+            .map(|(index, _)| index)
+            .collect();
+
+        // Spans of lines to print, widened by the context and merged where they meet.
+        let mut hunks: Vec<(usize, usize)> = Vec::new();
+        for index in changes {
+            let start = index.saturating_sub(context);
+            let end = (index + context).min(self.lines.len() - 1);
+            match hunks.last_mut() {
+                Some((_, last_end)) if start <= *last_end + 1 => *last_end = end,
+                _ => hunks.push((start, end)),
+            }
+        }
+
+        let mut out = String::new();
+        for (start, end) in hunks {
+            let lines = &self.lines[start..=end];
+            let old_len = lines
+                .iter()
+                .filter(|line| !matches!(line, DiffLine::Added(_)))
+                .count();
+            let new_len = lines
+                .iter()
+                .filter(|line| !matches!(line, DiffLine::Removed(_)))
+                .count();
+            // Empty sides name the line before them, as `diff -u` does.
+            let old_start = positions[start].old_line - usize::from(old_len == 0);
+            let new_start = positions[start].new_anchor - usize::from(new_len == 0);
+            let _ = writeln!(out, "@@ -{old_start},{old_len} +{new_start},{new_len} @@");
+            for line in lines {
+                let _ = match line {
+                    DiffLine::Same(text) => writeln!(out, " {text}"),
+                    DiffLine::Removed(text) => writeln!(out, "-{text}"),
+                    DiffLine::Added(text) => writeln!(out, "+{text}"),
+                };
+            }
+        }
+        out.truncate(out.trim_end_matches('\n').len());
+        out
+    }
+
+    /// Each line with the old line it is or follows and the new line it is or precedes.
+    fn positions(&self) -> impl Iterator<Item = (&DiffLine<'a>, Position)> {
+        let mut old = 0;
+        let mut new = 0;
+        self.lines.iter().map(move |line| {
+            let position = Position {
+                old_line: old + 1,
+                new_anchor: new + 1,
+            };
+            match line {
+                DiffLine::Same(_) => {
+                    old += 1;
+                    new += 1;
+                }
+                DiffLine::Removed(_) => old += 1,
+                DiffLine::Added(_) => new += 1,
+            }
+            (line, position)
+        })
+    }
+}
+
+/// 1-based line numbers locating a diff line in both versions.
+#[derive(Debug, Clone, Copy)]
+struct Position {
+    old_line: usize,
+    /// The new line itself, or for removed lines the new line they were removed before.
+    new_anchor: usize,
+}
+
+fn in_range(line: usize, range: Option<(usize, usize)>) -> bool {
+    range.is_none_or(|(start, end)| (start..=end).contains(&line))
+}
+
+/// Shortest edit script turning `old` into `new`, or a wholesale replacement once it would take
+/// more than [`MAX_EDITS`] edits.
+fn middle<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<DiffLine<'a>> {
+    let replace = || {
+        old.iter()
+            .map(|line| DiffLine::Removed(line))
+            .chain(new.iter().map(|line| DiffLine::Added(line)))
+            .collect()
+    };
+    if old.is_empty() || new.is_empty() {
+        return replace();
+    }
+
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let limit = (old.len() + new.len()).min(MAX_EDITS) as isize;
+    let offset = limit + 1;
+    // Furthest x reached on each diagonal k = x - y, indexed by k + offset.
+    let mut furthest = vec![0isize; 2 * limit as usize + 3];
+    // The diagonals -d..=d of `furthest` as they were before step d.
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+    let mut reached = false;
+    'search: for d in 0..=limit {
+        trace.push(furthest[(offset - d) as usize..=(offset + d) as usize].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let index = (k + offset) as usize;
+            let mut x = if k == -d || (k != d && furthest[index - 1] < furthest[index + 1]) {
+                furthest[index + 1]
+            } else {
+                furthest[index - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            furthest[index] = x;
+            if x >= n && y >= m {
+                reached = true;
+                break 'search;
+            }
+        }
+    }
+    if !reached {
+        return replace();
+    }
+
+    let mut reversed = Vec::with_capacity(old.len() + new.len());
+    let (mut x, mut y) = (n, m);
+    for (d, before) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        if d == 0 {
+            break;
+        }
+        let k = x - y;
+        let at = |k: isize| before[(k + d) as usize];
+        let previous_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let previous_x = at(previous_k);
+        let previous_y = previous_x - previous_k;
+        while x > previous_x && y > previous_y {
+            x -= 1;
+            y -= 1;
+            reversed.push(DiffLine::Same(old[x as usize]));
+        }
+        if x == previous_x {
+            y -= 1;
+            reversed.push(DiffLine::Added(new[y as usize]));
+        } else {
+            x -= 1;
+            reversed.push(DiffLine::Removed(old[x as usize]));
+        }
+    }
+    while x > 0 && y > 0 {
+        x -= 1;
+        y -= 1;
+        reversed.push(DiffLine::Same(old[x as usize]));
+    }
+    reversed.reverse();
+    reversed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use proptest::prelude::*;
+
+    /// The old and new texts a diff was built from, rebuilt from its lines.
+    fn sides(diff: &LineDiff<'_>) -> (Vec<String>, Vec<String>) {
+        let mut old = Vec::new();
+        let mut new = Vec::new();
+        for line in diff.lines() {
+            match line {
+                DiffLine::Same(text) => {
+                    old.push(text.to_string());
+                    new.push(text.to_string());
+                }
+                DiffLine::Removed(text) => old.push(text.to_string()),
+                DiffLine::Added(text) => new.push(text.to_string()),
+            }
+        }
+        (old, new)
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(300))]
+
+        /// Up to six single-line removals or insertions of a small alphabet, so lines repeat.
+        #[test]
+        fn diffs_rebuild_both_versions_with_few_edits(
+            old in prop::collection::vec((0..6usize).prop_map(|line| line.to_string()), 0..30),
+            edits in prop::collection::vec(
+                (any::<bool>(), any::<prop::sample::Index>(), 0..6usize),
+                0..6,
+            ),
+        ) {
+            let mut new = old.clone();
+            for &(remove, index, line) in &edits {
+                if remove && !new.is_empty() {
+                    new.remove(index.index(new.len()));
+                } else {
+                    new.insert(index.index(new.len() + 1), line.to_string());
+                }
+            }
+            let (old_text, new_text) = (old.join("\n"), new.join("\n"));
+            let diff = LineDiff::new(&old_text, &new_text);
+            prop_assert_eq!(sides(&diff), (old.clone(), new.clone()));
+            let changes = diff.lines().iter().filter(|line| line.is_change()).count();
+            prop_assert!(changes <= 2 * edits.len(), "{} changes", changes);
+        }
+    }
+
+    #[test]
+    fn ranges_pick_matching_old_lines_and_hunks() {
+        let old = "fn a() {\n    one();\n}\n\nfn b() {\n    two();\n}\n";
+        let new = "fn a() {\n    uno();\n}\n\nfn b() {\n    two();\n    three();\n}\n";
+        let diff = LineDiff::new(old, new);
+
+        assert!(diff.changed(None));
+        assert!(diff.changed(Some((1, 3))));
+        assert!(!diff.changed(Some((4, 6))));
+        assert_eq!(
+            diff.old_lines(Some((1, 3))),
+            (Some(1), vec!["fn a() {", "    one();", "}"])
+        );
+        assert_eq!(
+            diff.old_lines(Some((5, 8))),
+            (Some(5), vec!["fn b() {", "    two();", "}"])
+        );
+        assert_eq!(
+            diff.unified(Some((5, 8)), 1),
+            "@@ -6,2 +6,3 @@\n     two();\n+    three();\n }"
+        );
+        assert_eq!(
+            diff.unified(None, 0),
+            "@@ -2,1 +2,1 @@\n-    one();\n+    uno();\n@@ -6,0 +7,1 @@\n+    three();"
+        );
+        assert_eq!(diff.unified(Some((4, 6)), 3), "");
+    }
+}
@@ -1,9 +1,11 @@
 //! Export bundle handling.
 
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 
 use anyhow::{Context, Result, anyhow};
 use clap::ValueEnum;
@@ -11,12 +13,27 @@ use minijinja::Environment;
 use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
 use time::format_description::well_known::Rfc3339;
+use time::macros::format_description;
 
-use crate::app::tokens::BundleTokenSummary;
-use crate::domain::model::{ContextBundle, SelectionItem};
-use crate::infra::clipboard::Clipboard;
+use crate::app::cancel::CancellationToken;
+use crate::app::diff::LineDiff;
+use crate::app::handoff::{self, HandoffItem};
+use crate::app::manifest::ExportManifest;
+use crate::app::scan::{WorkspaceRoot, slash_path};
+use crate::app::selection::SelectionManager;
+use crate::app::session::SelectionRecord;
+use crate::app::snippets::Snippet;
+use crate::app::strip::{self, StripOptions};
+use crate::app::tokens::{BundleTokenSummary, TokenEstimator};
+use crate::domain::errors::DomainError;
+use crate::domain::model::{
+    ContextBundle, SchemaVersion, SelectionItem, SelectionSource, language_of,
+};
+use crate::infra::clipboard::{Clipboard, ClipboardBackend};
 use crate::infra::config::Config;
-use crate::infra::git::{self, GitMetadata};
+use crate::infra::documents::{self, ExtractOptions};
+use crate::infra::fs::{RealFs, Vfs};
+use crate::infra::git::{self, CommitSummary, GitFileSource, GitMetadata};
 
 /// Supported export formats.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
@@ -27,6 +44,16 @@ pub enum ExportFormat {
     Markdown,
     /// Plain text report.
     Plain,
+    /// aider `/add` commands for the selected files, loaded with `/load`.
+    Aider,
+    /// continue.dev context items as JSON.
+    Continue,
+    /// Cursor rules file (`.mdc`) holding the selections.
+    Cursor,
+    /// `@path` mention per selected file, one per line, for chat tools.
+    Mentions,
+    /// `@path` mentions on a single line, separated by spaces.
+    MentionsInline,
 }
 
 impl ExportFormat {
@@ -35,6 +62,11 @@ impl ExportFormat {
         match self {
             ExportFormat::Markdown => "markdown",
             ExportFormat::Plain => "plain",
+            ExportFormat::Aider => "aider",
+            ExportFormat::Continue => "continue",
+            ExportFormat::Cursor => "cursor",
+            ExportFormat::Mentions => "mentions",
+            ExportFormat::MentionsInline => "mentions-inline",
         }
     }
 
@@ -43,6 +75,10 @@ impl ExportFormat {
         match self {
             ExportFormat::Markdown => "md",
             ExportFormat::Plain => "txt",
+            ExportFormat::Aider => "aider",
+            ExportFormat::Continue => "json",
+            ExportFormat::Cursor => "mdc",
+            ExportFormat::Mentions | ExportFormat::MentionsInline => "txt",
         }
     }
 }
@@ -54,6 +90,11 @@ impl FromStr for ExportFormat {
         match value.trim().to_ascii_lowercase().as_str() {
             "markdown" | "md" | "commonmark" => Ok(ExportFormat::Markdown),
             "plain" | "text" | "txt" => Ok(ExportFormat::Plain),
+            "aider" => Ok(ExportFormat::Aider),
+            "continue" | "continue-dev" => Ok(ExportFormat::Continue),
+            "cursor" | "cursor-rules" | "mdc" => Ok(ExportFormat::Cursor),
+            "mentions" => Ok(ExportFormat::Mentions),
+            "mentions-inline" => Ok(ExportFormat::MentionsInline),
             other => Err(ExportFormatParseError::UnknownFormat(other.to_string())),
         }
     }
@@ -66,6 +107,28 @@ pub enum ExportFormatParseError {
     UnknownFormat(String),
 }
 
+/// How selections that changed since [`ExportOptions::compare`] show their earlier version.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum CompareMode {
+    /// The lines as they were, followed by the current lines.
+    #[default]
+    Both,
+    /// A unified diff against the revision, followed by the current lines.
+    Diff,
+}
+
+/// What selections are grouped by in the template context's `groups`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum GroupBy {
+    /// The first directory of the display path, or `.` for files at a root.
+    #[default]
+    Directory,
+    /// The file extension, as in the bundle's language breakdown.
+    Language,
+}
+
 /// Runtime options controlling export behavior.
 #[derive(Debug, Clone)]
 pub struct ExportOptions {
@@ -73,8 +136,37 @@ pub struct ExportOptions {
     pub template: String,
     pub include_line_numbers: bool,
     pub include_git_metadata: bool,
+    /// Append notebook cell outputs when exporting `.ipynb` selections.
+    pub include_notebook_outputs: bool,
+    /// Number of recent commits to list per selected file; `0` disables history.
+    pub history_commits: usize,
+    /// Append an [`ExportManifest`] footer describing how the export was produced.
+    pub include_manifest: bool,
+    /// Lines of surrounding context added above and below each ranged selection.
+    pub context_lines: usize,
+    /// Revision whose version of each changed selection is exported alongside the current one.
+    pub compare: Option<String>,
+    pub compare_mode: CompareMode,
+    /// Key the template context's `groups` collect selections under.
+    pub group_by: GroupBy,
+    /// Instruction snippets appended after the selections, in order.
+    pub snippets: Vec<Snippet>,
+    /// Refuse to export when the token estimate is over this many tokens.
+    pub token_limit: Option<usize>,
+    /// Comments and blank runs removed from selection contents before rendering.
+    pub strip: StripOptions,
+    /// [`Config::fingerprint`] of the configuration the options were built from.
+    pub config_hash: String,
+    /// The configuration the options were built from, which templates read as `config`.
+    pub config: Config,
+    /// Workspace roots used to shorten display paths; labelled roots prefix their paths.
+    pub roots: Vec<WorkspaceRoot>,
     pub output_path: Option<PathBuf>,
+    /// Replace an existing file at `output_path` rather than refusing to write.
+    pub overwrite: bool,
     pub copy_to_clipboard: bool,
+    /// Abandons the export between selections; nothing is written or copied once cancelled.
+    pub cancel: CancellationToken,
 }
 
 impl ExportOptions {
@@ -87,8 +179,70 @@ impl ExportOptions {
             template: config.export.template(),
             include_line_numbers: config.export.include_line_numbers(),
             include_git_metadata: config.export.include_git_metadata(),
+            include_notebook_outputs: config.export.include_notebook_outputs(),
+            history_commits: config.export.history_commits(),
+            include_manifest: config.export.include_manifest(),
+            context_lines: config.export.context_lines(),
+            compare: None,
+            compare_mode: CompareMode::from_str(config.export.compare_mode(), true)
+                .unwrap_or_default(),
+            group_by: GroupBy::from_str(config.export.group_by(), true).unwrap_or_default(),
+            snippets: Vec::new(),
+            token_limit: (config.export.enforce_budget() && config.defaults.token_budget() > 0)
+                .then(|| config.defaults.token_budget() as usize),
+            strip: StripOptions::from_config(config),
+            config_hash: config.fingerprint(),
+            config: config.clone(),
+            roots: Vec::new(),
             output_path: None,
+            overwrite: false,
             copy_to_clipboard: false,
+            cancel: CancellationToken::default(),
+        }
+    }
+}
+
+/// What to do with an export file after it is written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PostAction {
+    #[default]
+    None,
+    /// Open the file in `$VISUAL` or `$EDITOR`, falling back to `vi`.
+    OpenEditor,
+    /// Page through the file with `$PAGER`, falling back to `less`.
+    OpenPager,
+}
+
+impl PostAction {
+    /// Program and leading arguments to run with the export path appended, if any.
+    ///
+    /// Environment values may carry arguments, as in `EDITOR="code --wait"`.
+    pub fn command(&self) -> Option<Vec<String>> {
+        let (vars, fallback): (&[&str], &str) = match self {
+            Self::None => return None,
+            Self::OpenEditor => (&["VISUAL", "EDITOR"], "vi"),
+            Self::OpenPager => (&["PAGER"], "less"),
+        };
+        let configured = vars
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.trim().is_empty());
+        let command = configured.unwrap_or_else(|| fallback.to_string());
+        Some(command.split_whitespace().map(str::to_owned).collect())
+    }
+}
+
+impl FromStr for PostAction {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "" | "none" => Ok(Self::None),
+            "open-editor" | "editor" => Ok(Self::OpenEditor),
+            "open-pager" | "pager" => Ok(Self::OpenPager),
+            other => Err(anyhow!(
+                "unknown export post action '{other}' (expected none, open-editor, or open-pager)"
+            )),
         }
     }
 }
@@ -104,7 +258,8 @@ pub struct ExportResult {
 /// Responsible for rendering bundles and writing artifacts.
 pub struct Exporter {
     env: Environment<'static>,
-    clipboard: Mutex<Clipboard>,
+    clipboard: Mutex<Box<dyn ClipboardBackend>>,
+    vfs: Arc<dyn Vfs>,
 }
 
 impl Exporter {
@@ -112,10 +267,26 @@ impl Exporter {
     pub fn new() -> Result<Self> {
         Ok(Self {
             env: default_environment()?,
-            clipboard: Mutex::new(Clipboard::new()),
+            clipboard: Mutex::new(Box::new(Clipboard::new())),
+            vfs: Arc::new(RealFs),
         })
     }
 
+    /// Copy to `clipboard` instead of the system clipboard.
+    pub fn with_clipboard(self, clipboard: Box<dyn ClipboardBackend>) -> Self {
+        Self {
+            clipboard: Mutex::new(clipboard),
+            ..self
+        }
+    }
+
+    /// Read selections from `vfs` instead of the local disk.
+    ///
+    /// Exports are still written to, and git metadata read from, the local disk.
+    pub fn with_vfs(self, vfs: Arc<dyn Vfs>) -> Self {
+        Self { vfs, ..self }
+    }
+
     /// Render the provided bundle into a string using the supplied options.
     pub fn render_bundle(
         &self,
@@ -123,38 +294,144 @@ impl Exporter {
         summary: Option<&BundleTokenSummary>,
         options: &ExportOptions,
     ) -> Result<String> {
+        // Handoff formats are read by other tools, so they skip templates and the manifest.
+        match options.format {
+            ExportFormat::Aider => {
+                return Ok(handoff::aider_commands(&handoff_items(
+                    self.vfs.as_ref(),
+                    bundle,
+                    options,
+                    false,
+                )?));
+            }
+            ExportFormat::Continue => {
+                return handoff::continue_context(&handoff_items(
+                    self.vfs.as_ref(),
+                    bundle,
+                    options,
+                    true,
+                )?);
+            }
+            ExportFormat::Cursor => {
+                return Ok(handoff::cursor_rule(&handoff_items(
+                    self.vfs.as_ref(),
+                    bundle,
+                    options,
+                    true,
+                )?));
+            }
+            ExportFormat::Mentions => {
+                return Ok(handoff::mentions(
+                    &handoff_items(self.vfs.as_ref(), bundle, options, false)?,
+                    "\n",
+                ));
+            }
+            ExportFormat::MentionsInline => {
+                return Ok(handoff::mentions(
+                    &handoff_items(self.vfs.as_ref(), bundle, options, false)?,
+                    " ",
+                ));
+            }
+            ExportFormat::Markdown | ExportFormat::Plain => {}
+        }
+
+        let vfs = self.vfs.as_ref();
         let git_metadata = if options.include_git_metadata {
             bundle
                 .items
                 .first()
                 .and_then(|item| git::metadata_for_path(&item.path))
+                .map(|mut metadata| {
+                    // Files read from history link to the commit they were read at.
+                    if let Some(commit) = vfs.commit() {
+                        metadata.commit = Some(commit);
+                    }
+                    metadata
+                })
         } else {
             None
         };
 
-        let context = build_template_context(bundle, summary, options, git_metadata)?;
-        self.render_with_template(&context, &options.template)
+        let merged = merge_selections(vfs, bundle, options)?;
+        let merged_summary = match summary {
+            Some(summary) if merged.items != bundle.items => {
+                Some(self.estimate_merged(&merged, summary, options)?)
+            }
+            _ => None,
+        };
+        let summary = merged_summary.as_ref().or(summary);
+        let base = match &options.compare {
+            Some(revision) => Some(compare_base(&merged, options, revision)?),
+            None => None,
+        };
+        let context =
+            build_template_context(vfs, base.as_ref(), &merged, summary, options, git_metadata)?;
+        let mut rendered = self.render_with_template(&context, &options.template)?;
+        // The manifest records the selections as made, so replaying it pads them only once.
+        if options.include_manifest {
+            let mut manifest = build_manifest(self.vfs.as_ref(), bundle, options, &context);
+            if let Some(commit) = vfs.commit() {
+                manifest.git_commit = Some(commit);
+            }
+            if !rendered.ends_with('\n') {
+                rendered.push('\n');
+            }
+            rendered.push_str(&manifest.to_footer());
+        }
+        Ok(rendered)
+    }
+
+    /// Estimate the selections as merged for rendering, with the model, budget, and stripping of
+    /// `summary`, so merged and padded sections keep a token count of their own.
+    fn estimate_merged(
+        &self,
+        merged: &ContextBundle,
+        summary: &BundleTokenSummary,
+        options: &ExportOptions,
+    ) -> Result<BundleTokenSummary> {
+        let mut estimator = TokenEstimator::from_config(&options.config);
+        estimator.set_model(summary.model);
+        estimator.set_token_budget(summary.token_budget);
+        estimator.set_strip(options.strip);
+        estimator.set_vfs(self.vfs.clone());
+        estimator.estimate_bundle(merged)
+    }
+
+    /// Copy arbitrary text using the exporter's clipboard handle.
+    pub fn copy_to_clipboard(&self, text: &str) -> Result<()> {
+        self.clipboard
+            .lock()
+            .unwrap()
+            .copy(text)
+            .context("failed to copy to clipboard")
     }
 
     /// Render the bundle and persist/copy outputs based on options.
+    ///
+    /// Fails with [`DomainError::BudgetExceeded`] before anything is written when the summary is
+    /// over [`ExportOptions::token_limit`], and with [`DomainError::OutputExists`] when the output
+    /// file exists and [`ExportOptions::overwrite`] is unset. The file is written to a temporary
+    /// file beside it and renamed into place, so readers never see a partial export.
     pub fn export(
         &self,
         bundle: &ContextBundle,
         summary: Option<&BundleTokenSummary>,
         options: &ExportOptions,
     ) -> Result<ExportResult> {
+        if let Some((limit, summary)) = options.token_limit.zip(summary) {
+            summary.check_limit(limit)?;
+        }
+        if let Some(path) = &options.output_path
+            && !options.overwrite
+            && path.exists()
+        {
+            return Err(DomainError::OutputExists { path: path.clone() }.into());
+        }
         let rendered = self.render_bundle(bundle, summary, options)?;
+        options.cancel.check()?;
 
         if let Some(path) = &options.output_path {
-            if let Some(parent) = path.parent()
-                && !parent.as_os_str().is_empty()
-            {
-                fs::create_dir_all(parent).with_context(|| {
-                    format!("failed to create export directory: {}", parent.display())
-                })?;
-            }
-            fs::write(path, &rendered)
-                .with_context(|| format!("failed to write export output to {}", path.display()))?;
+            write_atomically(path, &rendered, options.overwrite)?;
         }
 
         if options.copy_to_clipboard {
@@ -203,13 +480,322 @@ impl Exporter {
                 .map_err(|err| anyhow!("failed to render template '{template_name}': {err}"));
         }
 
-        Err(anyhow!(
-            "template '{}' not found (built-in or filesystem)",
-            template_name
-        ))
+        Err(DomainError::TemplateNotFound {
+            name: template_name.to_string(),
+        }
+        .into())
     }
 }
 
+/// Write `contents` to a temporary file next to `path` and rename it into place.
+///
+/// Without `overwrite`, the rename fails rather than replace a file created in the meantime.
+fn write_atomically(path: &Path, contents: &str, overwrite: bool) -> Result<()> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("failed to create export directory: {}", parent.display())
+            })?;
+            parent
+        }
+        _ => Path::new("."),
+    };
+    let mut temp = tempfile::Builder::new()
+        .prefix(".llmctx-export")
+        .tempfile_in(parent)
+        .with_context(|| format!("failed to create a temporary file in {}", parent.display()))?;
+    temp.write_all(contents.as_bytes())
+        .and_then(|()| temp.as_file().sync_all())
+        .with_context(|| format!("failed to write export output to {}", path.display()))?;
+    let persisted = if overwrite {
+        temp.persist(path)
+    } else {
+        temp.persist_noclobber(path)
+    };
+    match persisted {
+        Ok(_) => Ok(()),
+        Err(err) if err.error.kind() == io::ErrorKind::AlreadyExists => {
+            Err(DomainError::OutputExists {
+                path: path.to_path_buf(),
+            }
+            .into())
+        }
+        Err(err) => Err(anyhow::Error::new(err.error).context(format!(
+            "failed to write export output to {}",
+            path.display()
+        ))),
+    }
+}
+
+/// Placeholders accepted in [`expand_filename`] patterns.
+const FILENAME_PLACEHOLDERS: &[&str] = &["repo", "branch", "timestamp", "date", "format", "ext"];
+
+/// Expand an export filename pattern such as `{repo}-{branch}-{timestamp}.{ext}`.
+///
+/// `{repo}` and `{branch}` come from the git repository containing `root`, falling back to the
+/// directory name and `no-branch`. Values are reduced to characters that are safe in file names.
+pub fn expand_filename(
+    pattern: &str,
+    root: &Path,
+    format: ExportFormat,
+    now: OffsetDateTime,
+) -> Result<String> {
+    let metadata = git::metadata_for_path(root);
+    let mut expanded = String::with_capacity(pattern.len());
+    let mut rest = pattern;
+    while let Some(open) = rest.find('{') {
+        expanded.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let close = after
+            .find('}')
+            .ok_or_else(|| anyhow!("unclosed '{{' in export filename '{pattern}'"))?;
+        let value = match &after[..close] {
+            "repo" => {
+                let root = metadata
+                    .as_ref()
+                    .map(|metadata| metadata.root.clone())
+                    .or_else(|| std::path::absolute(root).ok())
+                    .unwrap_or_else(|| root.to_path_buf());
+                root.file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| "workspace".to_string())
+            }
+            "branch" => metadata
+                .as_ref()
+                .and_then(|metadata| metadata.branch.as_deref())
+                .map(|branch| branch.trim_start_matches("refs/heads/").to_string())
+                .unwrap_or_else(|| "no-branch".to_string()),
+            "timestamp" => now.format(format_description!(
+                "[year][month][day]-[hour][minute][second]"
+            ))?,
+            "date" => now.format(format_description!("[year]-[month]-[day]"))?,
+            "format" => format.as_str().to_string(),
+            "ext" => format.extension().to_string(),
+            other => {
+                let expected: Vec<String> = FILENAME_PLACEHOLDERS
+                    .iter()
+                    .map(|name| format!("{{{name}}}"))
+                    .collect();
+                return Err(anyhow!(
+                    "unknown placeholder '{{{other}}}' in export filename '{pattern}' (expected {})",
+                    expected.join(", ")
+                ));
+            }
+        };
+        expanded.push_str(&sanitize_filename_part(&value));
+        rest = &after[close + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// Replace characters that are awkward in file names, such as the `/` in `feature/x` branches.
+fn sanitize_filename_part(value: &str) -> String {
+    value
+        .chars()
+        .map(|ch| {
+            if ch.is_alphanumeric() || matches!(ch, '-' | '_' | '.') {
+                ch
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+/// `path` when it is free, else the next numbered variant of its name in the same directory.
+pub fn unused_output_path(path: PathBuf) -> Result<PathBuf> {
+    if !path.exists() {
+        return Ok(path);
+    }
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    numbered_output_path(dir, &stem, &extension)
+}
+
+/// The next free `{stem}-N.{extension}` in `dir`, numbering after the highest existing file.
+pub fn numbered_output_path(dir: &Path, stem: &str, extension: &str) -> Result<PathBuf> {
+    let prefix = format!("{stem}-");
+    let suffix = if extension.is_empty() {
+        String::new()
+    } else {
+        format!(".{extension}")
+    };
+    let mut highest = 0;
+    match fs::read_dir(dir) {
+        Ok(entries) => {
+            for entry in entries {
+                let name = entry?.file_name();
+                let Some(number) = name
+                    .to_str()
+                    .and_then(|name| name.strip_prefix(&prefix))
+                    .and_then(|rest| rest.strip_suffix(&suffix))
+                    .and_then(|number| number.parse::<usize>().ok())
+                else {
+                    continue;
+                };
+                highest = highest.max(number);
+            }
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => {
+            return Err(anyhow::Error::new(err)
+                .context(format!("failed to list export directory {}", dir.display())));
+        }
+    }
+    Ok(dir.join(format!("{stem}-{:03}{suffix}", highest + 1)))
+}
+
+/// Merged selections for the handoff formats, with their raw contents (no line numbers) when
+/// `with_contents` is set.
+fn handoff_items(
+    vfs: &dyn Vfs,
+    bundle: &ContextBundle,
+    options: &ExportOptions,
+    with_contents: bool,
+) -> Result<Vec<HandoffItem>> {
+    let merged = merge_selections(vfs, bundle, options)?;
+    let options = ExportOptions {
+        include_line_numbers: false,
+        ..options.clone()
+    };
+    let repository_root = merged
+        .items
+        .first()
+        .and_then(|item| git::metadata_for_path(&item.path))
+        .map(|metadata| metadata.root);
+    let mut items = Vec::with_capacity(merged.items.len());
+    for item in &merged.items {
+        let path = std::path::absolute(&item.path).unwrap_or_else(|_| item.path.clone());
+        let relative_path = slash_path(
+            repository_root
+                .as_deref()
+                .and_then(|root| path.strip_prefix(root).ok())
+                .unwrap_or(&path),
+        );
+        items.push(HandoffItem {
+            path,
+            relative_path,
+            range: item.range,
+            note: item.note.clone(),
+            contents: if with_contents {
+                extract_selection_contents(vfs, item, &options)?.contents
+            } else {
+                String::new()
+            },
+        });
+    }
+    Ok(items)
+}
+
+fn build_manifest(
+    vfs: &dyn Vfs,
+    bundle: &ContextBundle,
+    options: &ExportOptions,
+    context: &TemplateContext,
+) -> ExportManifest {
+    let git_commit = match &context.git {
+        Some(metadata) => metadata.commit.clone(),
+        None => bundle
+            .items
+            .first()
+            .and_then(|item| git::metadata_for_path(&item.path))
+            .and_then(|metadata| metadata.commit),
+    };
+    let selections = bundle
+        .items
+        .iter()
+        .map(|item| {
+            let mut record = SelectionRecord::from(item);
+            record.item.path = display_path(item, &options.roots, context.git.as_ref()).into();
+            record.capture_fingerprint(vfs, &item.path);
+            record
+        })
+        .collect();
+    let mut totals: HashMap<PathBuf, usize> = HashMap::new();
+    let mut stats = bundle.stats(|item| {
+        let total = *totals.entry(item.path.clone()).or_insert_with(|| {
+            vfs.read_text(&item.path)
+                .map_or(0, |decoded| decoded.text.lines().count())
+        });
+        item.selected_lines(total)
+    });
+    for entry in &mut stats.largest {
+        if let Some(item) = bundle
+            .items
+            .iter()
+            .find(|item| item.path == entry.path && item.range == entry.range)
+        {
+            entry.path = display_path(item, &options.roots, context.git.as_ref()).into();
+        }
+    }
+
+    ExportManifest {
+        schema_version: SchemaVersion::default(),
+        llmctx_version: env!("CARGO_PKG_VERSION").to_string(),
+        generated_at: context.generated_at.clone(),
+        config_hash: options.config_hash.clone(),
+        git_commit,
+        model: bundle.model.clone(),
+        format: options.format.as_str().to_string(),
+        template: options.template.clone(),
+        attachments: bundle
+            .attachments
+            .iter()
+            .map(|attachment| attachment.source.clone())
+            .collect(),
+        snippets: options
+            .snippets
+            .iter()
+            .map(|snippet| snippet.name.clone())
+            .collect(),
+        selections,
+        stats: Some(stats),
+    }
+}
+
+/// The bundle with every region emitted once: selections of the same file that overlap are
+/// merged with their notes joined, after widening ranges by [`ExportOptions::context_lines`].
+fn merge_selections(
+    vfs: &dyn Vfs,
+    bundle: &ContextBundle,
+    options: &ExportOptions,
+) -> Result<ContextBundle> {
+    let mut manager = SelectionManager::from_items(bundle.items.clone());
+    if options.context_lines == 0 {
+        manager.normalize(0);
+        return Ok(ContextBundle {
+            items: manager.items().to_vec(),
+            ..bundle.clone()
+        });
+    }
+    let extract_options = ExtractOptions {
+        include_notebook_outputs: options.include_notebook_outputs,
+    };
+    let mut line_counts: HashMap<PathBuf, usize> = HashMap::new();
+    manager.pad_ranges(options.context_lines, |path| {
+        if let Some(count) = line_counts.get(path) {
+            return Ok(*count);
+        }
+        let contents = documents::read_selection_text(vfs, path, extract_options)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let count = contents.lines().count();
+        line_counts.insert(path.to_path_buf(), count);
+        Ok(count)
+    })?;
+    Ok(ContextBundle {
+        items: manager.items().to_vec(),
+        ..bundle.clone()
+    })
+}
+
 fn default_environment() -> Result<Environment<'static>> {
     let mut env = Environment::new();
     env.set_trim_blocks(true);
@@ -218,10 +804,14 @@ fn default_environment() -> Result<Environment<'static>> {
         .map_err(|err| anyhow!("failed to register default markdown template: {err}"))?;
     env.add_template("plain_text", DEFAULT_PLAIN_TEMPLATE)
         .map_err(|err| anyhow!("failed to register default plain template: {err}"))?;
+    env.add_template("grouped_context", GROUPED_MARKDOWN_TEMPLATE)
+        .map_err(|err| anyhow!("failed to register grouped markdown template: {err}"))?;
     Ok(env)
 }
 
 fn build_template_context(
+    vfs: &dyn Vfs,
+    base: Option<&GitFileSource>,
     bundle: &ContextBundle,
     summary: Option<&BundleTokenSummary>,
     options: &ExportOptions,
@@ -231,22 +821,72 @@ fn build_template_context(
         .format(&Rfc3339)
         .context("failed to format export timestamp")?;
 
+    let mut repositories = RepositoryLookup::default();
     let mut selections = Vec::with_capacity(bundle.items.len());
-    for (index, item) in bundle.items.iter().enumerate() {
-        let summary_item = summary.and_then(|summary| summary.items.get(index));
-        let extracted = extract_selection_contents(item, options.include_line_numbers)?;
+    for item in &bundle.items {
+        let summary_item = summary.and_then(|summary| {
+            summary
+                .items
+                .iter()
+                .find(|entry| entry.item.path == item.path && entry.item.range == item.range)
+        });
+        let extracted = extract_selection_contents(vfs, item, options)?;
+        let comparison = match base {
+            Some(base) => compare_selection(vfs, base, item, options)?,
+            None => None,
+        };
+        let earlier = comparison
+            .iter()
+            .flat_map(|comparison| [&comparison.previous, &comparison.diff])
+            .flatten();
+        let fence = code_fence(
+            std::iter::once(&extracted.contents)
+                .chain(earlier)
+                .map(String::as_str),
+        );
+        let history = if options.history_commits > 0 {
+            git::history_for_path(&item.path, options.history_commits).with_context(|| {
+                format!("failed to read git history for {}", item.path.display())
+            })?
+        } else {
+            Vec::new()
+        };
+        let repository = git_metadata
+            .as_ref()
+            .and_then(|primary| repositories.foreign_repository(&item.path, primary));
+        // Whole-file selections link to the file itself rather than every line.
+        let link_range = item.range.and(extracted.start_line.zip(extracted.end_line));
+        // A link shows the committed file, so one to a locally edited file could anchor the
+        // wrong lines.
+        let permalink = repository
+            .as_ref()
+            .or(git_metadata.as_ref())
+            .filter(|metadata| !vfs.is_local() || !metadata.has_local_edits(&item.path))
+            .and_then(|metadata| metadata.permalink(&item.path, link_range));
         selections.push(TemplateSelection {
             path: item.path.display().to_string(),
-            display_path: display_path(item, git_metadata.as_ref()),
+            display_path: display_path(item, &options.roots, git_metadata.as_ref()),
             range: item.range.map(|(start, end)| SelectionRange { start, end }),
             start_line: extracted.start_line,
             end_line: extracted.end_line,
             contents: extracted.contents,
+            fence,
+            comparison,
             note: item.note.clone(),
+            source: item.source,
+            repository,
+            history,
+            permalink,
             tokens: summary_item.map(|entry| entry.tokens),
             characters: summary_item
                 .map(|entry| entry.characters)
                 .or(Some(extracted.character_count)),
+            words: summary_item
+                .map(|entry| entry.words)
+                .or(Some(extracted.word_count)),
+            lines: summary_item
+                .map(|entry| entry.lines)
+                .or(Some(extracted.line_count)),
         });
     }
 
@@ -255,45 +895,249 @@ fn build_template_context(
         token_budget: summary.token_budget,
         total_tokens: summary.total_tokens,
         total_characters: summary.total_characters,
+        total_words: summary.total_words,
+        total_lines: summary.total_lines,
+        stripped_tokens: summary.stripped_tokens,
     });
 
+    let groups = group_selections(&bundle.items, &selections, options.group_by);
     Ok(TemplateContext {
         generated_at,
         format: options.format.as_str().to_string(),
         model: bundle.model.clone(),
+        attachments: bundle
+            .attachments
+            .iter()
+            .map(|attachment| TemplateAttachment {
+                title: attachment.title.clone(),
+                source: attachment.source.clone(),
+                body: attachment.body.clone(),
+            })
+            .collect(),
         selections,
+        groups,
+        snippets: options
+            .snippets
+            .iter()
+            .map(|snippet| TemplateSnippet {
+                name: snippet.name.clone(),
+                body: snippet.body.clone(),
+            })
+            .collect(),
         tokens,
         git: git_metadata,
+        config: options.config.clone(),
+        metadata: options.config.export.metadata().clone(),
     })
 }
 
-fn display_path(item: &SelectionItem, git_metadata: Option<&GitMetadata>) -> String {
+/// A Markdown code fence that `texts` cannot close: at least three backticks, and one more than
+/// the longest backtick run in any of them, so exported code blocks and Markdown files with their
+/// own fences stay inside the block.
+pub(crate) fn code_fence<'a>(texts: impl IntoIterator<Item = &'a str>) -> String {
+    let longest = texts
+        .into_iter()
+        .flat_map(|text| text.split(|ch| ch != '`'))
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    "`".repeat((longest + 1).max(3))
+}
+
+/// `selections` collected under their `group_by` key, groups in order of first appearance.
+fn group_selections(
+    items: &[SelectionItem],
+    selections: &[TemplateSelection],
+    group_by: GroupBy,
+) -> Vec<TemplateGroup> {
+    let mut groups: Vec<TemplateGroup> = Vec::new();
+    for (item, selection) in items.iter().zip(selections) {
+        let name = match group_by {
+            GroupBy::Directory => match selection.display_path.split_once('/') {
+                Some((directory, _)) => directory.to_string(),
+                None => ".".to_string(),
+            },
+            GroupBy::Language => language_of(&item.path),
+        };
+        let index = match groups.iter().position(|group| group.name == name) {
+            Some(index) => index,
+            None => {
+                groups.push(TemplateGroup {
+                    name,
+                    ..TemplateGroup::default()
+                });
+                groups.len() - 1
+            }
+        };
+        let group = &mut groups[index];
+        if !group
+            .selections
+            .iter()
+            .any(|other| other.path == selection.path)
+        {
+            group.files += 1;
+        }
+        if let Some(tokens) = selection.tokens {
+            *group.tokens.get_or_insert(0) += tokens;
+        }
+        group.characters += selection.characters.unwrap_or(0);
+        group.words += selection.words.unwrap_or(0);
+        group.lines += selection.lines.unwrap_or(0);
+        group.selections.push(selection.clone());
+    }
+    groups
+}
+
+/// Per-directory cache of repository discovery, used to spot files inside submodules.
+#[derive(Default)]
+struct RepositoryLookup {
+    by_dir: HashMap<PathBuf, Option<GitMetadata>>,
+}
+
+impl RepositoryLookup {
+    /// Metadata for the repository owning `path`, if it differs from `primary`.
+    fn foreign_repository(&mut self, path: &Path, primary: &GitMetadata) -> Option<GitMetadata> {
+        let dir = path.parent().unwrap_or(path).to_path_buf();
+        let metadata = self
+            .by_dir
+            .entry(dir)
+            .or_insert_with_key(|dir| git::metadata_for_path(dir));
+        metadata
+            .as_ref()
+            .filter(|metadata| metadata.root != primary.root)
+            .cloned()
+    }
+}
+
+fn display_path(
+    item: &SelectionItem,
+    roots: &[WorkspaceRoot],
+    git_metadata: Option<&GitMetadata>,
+) -> String {
     let path = &item.path;
+    if let Some(display) = roots.iter().find_map(|root| root.display_path(path)) {
+        return display;
+    }
     if let Some(metadata) = git_metadata
         && let Ok(relative) = path.strip_prefix(&metadata.root)
     {
-        return relative.display().to_string();
+        return slash_path(relative);
     }
 
     if let Ok(cwd) = std::env::current_dir()
         && let Ok(relative) = path.strip_prefix(&cwd)
     {
-        return relative.display().to_string();
+        return slash_path(relative);
     }
 
-    path.display().to_string()
+    slash_path(path)
+}
+
+/// Unchanged lines shown around each hunk of a comparison diff.
+const DIFF_CONTEXT_LINES: usize = 3;
+
+/// The revision `options.compare` names, read from the repository holding the selections.
+fn compare_base(
+    bundle: &ContextBundle,
+    options: &ExportOptions,
+    revision: &str,
+) -> Result<GitFileSource> {
+    let start = bundle
+        .items
+        .first()
+        .map(|item| item.path.as_path())
+        .or_else(|| options.roots.first().map(|root| root.path.as_path()))
+        .unwrap_or(Path::new("."));
+    GitFileSource::open(start, revision)
+        .with_context(|| format!("failed to read revision '{revision}' to compare against"))
+}
+
+/// How `item` differs from its version in `base`, or `None` when none of its lines changed or
+/// the file lies outside the compared repository.
+fn compare_selection(
+    vfs: &dyn Vfs,
+    base: &GitFileSource,
+    item: &SelectionItem,
+    options: &ExportOptions,
+) -> Result<Option<TemplateComparison>> {
+    if !base.contains(&item.path) {
+        return Ok(None);
+    }
+    let extract_options = ExtractOptions {
+        include_notebook_outputs: options.include_notebook_outputs,
+    };
+    let read = |vfs: &dyn Vfs| {
+        documents::read_selection_text(vfs, &item.path, extract_options).with_context(|| {
+            format!(
+                "failed to read selection contents from {}",
+                item.path.display()
+            )
+        })
+    };
+    let current = read(vfs)?;
+    let mut comparison = TemplateComparison {
+        revision: base.revision().to_string(),
+        status: "added",
+        previous: None,
+        previous_start_line: None,
+        previous_end_line: None,
+        diff: None,
+    };
+    if !base.exists(&item.path) {
+        return Ok(Some(comparison));
+    }
+    let previous = read(base)?;
+    let diff = LineDiff::new(&previous, &current);
+    if !diff.changed(item.range) {
+        return Ok(None);
+    }
+    comparison.status = "modified";
+    match options.compare_mode {
+        CompareMode::Diff => comparison.diff = Some(diff.unified(item.range, DIFF_CONTEXT_LINES)),
+        CompareMode::Both => {
+            let (first, lines) = diff.old_lines(item.range);
+            // Lines that were all added since the revision have no earlier version to show.
+            if let Some(first) = first {
+                let last = first + lines.len() - 1;
+                let all: Vec<&str> = previous.lines().collect();
+                let width = last.to_string().len();
+                let kept: Vec<String> = strip::strip_lines(&item.path, &all, options.strip)
+                    .into_iter()
+                    .filter(|(line_no, _)| (first..=last).contains(line_no))
+                    .map(|(line_no, line)| {
+                        if options.include_line_numbers {
+                            format!("{line_no:>width$} │ {line}")
+                        } else {
+                            line.to_string()
+                        }
+                    })
+                    .collect();
+                comparison.previous = Some(kept.join("\n"));
+                comparison.previous_start_line = Some(first);
+                comparison.previous_end_line = Some(last);
+            }
+        }
+    }
+    Ok(Some(comparison))
 }
 
 fn extract_selection_contents(
+    vfs: &dyn Vfs,
     item: &SelectionItem,
-    include_line_numbers: bool,
+    options: &ExportOptions,
 ) -> Result<SelectionExtraction> {
-    let contents = fs::read_to_string(&item.path).with_context(|| {
-        format!(
-            "failed to read selection contents from {}",
-            item.path.display()
-        )
-    })?;
+    options.cancel.check()?;
+    let include_line_numbers = options.include_line_numbers;
+    let extract_options = ExtractOptions {
+        include_notebook_outputs: options.include_notebook_outputs,
+    };
+    let contents =
+        documents::read_selection_text(vfs, &item.path, extract_options).with_context(|| {
+            format!(
+                "failed to read selection contents from {}",
+                item.path.display()
+            )
+        })?;
 
     let lines: Vec<&str> = contents.lines().collect();
     let total_lines = lines.len();
@@ -307,6 +1151,8 @@ fn extract_selection_contents(
             start_line: None,
             end_line: None,
             character_count: 0,
+            word_count: 0,
+            line_count: 0,
         });
     }
 
@@ -325,11 +1171,12 @@ fn extract_selection_contents(
     let width = display_end.max(1).to_string().len();
 
     let mut extracted_lines = Vec::new();
-    for (idx, line) in contents.lines().enumerate() {
-        let line_no = idx + 1;
+    let mut word_count = 0;
+    for (line_no, line) in strip::strip_lines(&item.path, &lines, options.strip) {
         if line_no < clamped_start || line_no > clamped_end {
             continue;
         }
+        word_count += line.split_whitespace().count();
         if include_line_numbers {
             extracted_lines.push(format!("{line_no:>width$} │ {line}", width = width));
         } else {
@@ -343,6 +1190,8 @@ fn extract_selection_contents(
         start_line: Some(clamped_start),
         end_line: Some(clamped_end),
         character_count: joined.chars().count(),
+        word_count,
+        line_count: extracted_lines.len(),
     })
 }
 
@@ -351,12 +1200,35 @@ struct TemplateContext {
     generated_at: String,
     format: String,
     model: Option<String>,
+    /// Task documents attached to the bundle, rendered ahead of the selections.
+    attachments: Vec<TemplateAttachment>,
     selections: Vec<TemplateSelection>,
+    /// The selections again, grouped by [`ExportOptions::group_by`].
+    groups: Vec<TemplateGroup>,
+    /// Instruction snippets, rendered after the selections.
+    snippets: Vec<TemplateSnippet>,
     tokens: Option<TemplateTokenSummary>,
     git: Option<GitMetadata>,
+    /// Effective configuration, e.g. `config.defaults.token_budget`.
+    config: Config,
+    /// `[export.metadata]` labels such as a project or team name.
+    metadata: BTreeMap<String, String>,
 }
 
 #[derive(Serialize)]
+struct TemplateAttachment {
+    title: String,
+    source: String,
+    body: String,
+}
+
+#[derive(Serialize)]
+struct TemplateSnippet {
+    name: String,
+    body: String,
+}
+
+#[derive(Clone, Serialize)]
 struct TemplateSelection {
     path: String,
     display_path: String,
@@ -364,23 +1236,68 @@ struct TemplateSelection {
     start_line: Option<usize>,
     end_line: Option<usize>,
     contents: String,
+    /// Backtick fence for code blocks of the contents and comparison, longer than any
+    /// backtick run within them.
+    fence: String,
+    /// The selection as of [`ExportOptions::compare`], when it changed since.
+    comparison: Option<TemplateComparison>,
     note: Option<String>,
+    /// How the selection was made, e.g. `manual`, `glob`, or `diff`.
+    source: SelectionSource,
+    /// Set when the file belongs to a different repository (e.g. a submodule) than the bundle.
+    repository: Option<GitMetadata>,
+    /// Recent commits touching the file, newest first.
+    history: Vec<CommitSummary>,
+    /// Forge web link to the exported lines at the recorded commit.
+    permalink: Option<String>,
     tokens: Option<usize>,
     characters: Option<usize>,
+    words: Option<usize>,
+    lines: Option<usize>,
 }
 
-#[derive(Serialize)]
+#[derive(Clone, Serialize)]
+struct TemplateComparison {
+    revision: String,
+    /// `modified`, or `added` when the file did not exist at the revision.
+    status: &'static str,
+    /// The matching lines at the revision, in `both` mode.
+    previous: Option<String>,
+    previous_start_line: Option<usize>,
+    previous_end_line: Option<usize>,
+    /// Unified diff from the revision to the current lines, in `diff` mode.
+    diff: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
 struct SelectionRange {
     start: usize,
     end: usize,
 }
 
+/// Selections sharing a directory or language, with subtotals.
+#[derive(Default, Serialize)]
+struct TemplateGroup {
+    name: String,
+    selections: Vec<TemplateSelection>,
+    /// Distinct files among the selections.
+    files: usize,
+    /// Set when any of the selections has a token estimate.
+    tokens: Option<usize>,
+    characters: usize,
+    words: usize,
+    lines: usize,
+}
+
 #[derive(Serialize)]
 struct TemplateTokenSummary {
     model: String,
     token_budget: u32,
     total_tokens: usize,
     total_characters: usize,
+    total_words: usize,
+    total_lines: usize,
+    stripped_tokens: usize,
 }
 
 struct SelectionExtraction {
@@ -388,17 +1305,26 @@ struct SelectionExtraction {
     start_line: Option<usize>,
     end_line: Option<usize>,
     character_count: usize,
+    /// Words and lines of the selected text, excluding any line-number gutter.
+    word_count: usize,
+    line_count: usize,
 }
 
 const DEFAULT_MARKDOWN_TEMPLATE: &str = r#"# Curated Context
 
 Generated at: {{ generated_at }}
+{% for key, value in metadata|items %}
+- {{ key }}: {{ value }}
+{% endfor %}
 
 {% if tokens %}
 ## Token Summary
 - Model: {{ tokens.model }}
 - Usage: {{ tokens.total_tokens }} / {{ tokens.token_budget }} tokens
-- Characters: {{ tokens.total_characters }}
+{% if tokens.stripped_tokens %}
+- Saved by stripping: {{ tokens.stripped_tokens }} tokens
+{% endif %}
+- Characters: {{ tokens.total_characters }} ({{ tokens.total_words }} words, {{ tokens.total_lines }} lines)
 {% endif %}
 
 {% if git %}
@@ -408,36 +1334,596 @@ Generated at: {{ generated_at }}
 {% if git.commit %}- Commit: {{ git.commit }}{% endif %}
 {% endif %}
 
+{% if attachments %}
+## Task
+{% for attachment in attachments %}
+### {{ attachment.title }}
+_Source: {{ attachment.source }}_
+
+{{ attachment.body }}
+
+{% endfor %}
+{% endif %}
+
 {% for selection in selections %}
 ## {{ loop.index }}. {{ selection.display_path }}
 {% if selection.range %}_Lines {{ selection.range.start }}-{{ selection.range.end }}_{% endif %}
+{% if selection.repository %}_Repository: {{ selection.repository.root }}{% if selection.repository.commit %} @ {{ selection.repository.commit }}{% endif %}_
+{% endif %}
+{% if selection.permalink %}_Source: {{ selection.permalink }}_
+{% endif %}
 {% if selection.note %}> {{ selection.note }}
 
 {% endif %}
-```text
+{% if selection.history %}Recent commits:
+{% for commit in selection.history %}
+- `{{ commit.id }}` {{ commit.subject }} ({{ commit.author }}{% if commit.date %}, {{ commit.date }}{% endif %})
+{% endfor %}
+
+{% endif %}
+{% if selection.comparison %}
+{% set comparison = selection.comparison %}
+
+{% if comparison.previous %}
+**Before ({{ comparison.revision }}{% if comparison.previous_start_line %}, lines {{ comparison.previous_start_line }}-{{ comparison.previous_end_line }}{% endif %})**
+{{ selection.fence }}text
+{{ comparison.previous }}
+{{ selection.fence }}
+
+{% elif comparison.diff %}
+**Changes since {{ comparison.revision }}**
+{{ selection.fence }}diff
+{{ comparison.diff }}
+{{ selection.fence }}
+
+{% else %}
+_New since {{ comparison.revision }}_
+
+{% endif %}
+**After (current)**
+{% endif %}
+{{ selection.fence }}text
 {{ selection.contents }}
-```
+{{ selection.fence }}
 
 {% if selection.tokens %}- Tokens: {{ selection.tokens }}{% endif %}
-{% if selection.characters %}- Characters: {{ selection.characters }}{% endif %}
+{% if selection.characters %}- Characters: {{ selection.characters }}{% if selection.lines %} ({{ selection.words }} words, {{ selection.lines }} lines){% endif %}{% endif %}
+
+{% endfor %}
+{% for snippet in snippets %}
 
+{{ snippet.body }}
 {% endfor %}
 "#;
 
 const DEFAULT_PLAIN_TEMPLATE: &str = r#"Curated context generated at {{ generated_at }}
+{% for key, value in metadata|items %}
+{{ key }}: {{ value }}
+{% endfor %}
 
-{% if tokens %}Token summary: model {{ tokens.model }}, {{ tokens.total_tokens }}/{{ tokens.token_budget }} tokens, {{ tokens.total_characters }} characters.
+{% if tokens %}Token summary: model {{ tokens.model }}, {{ tokens.total_tokens }}/{{ tokens.token_budget }} tokens, {{ tokens.total_characters }} characters, {{ tokens.total_words }} words, {{ tokens.total_lines }} lines{% if tokens.stripped_tokens %}, {{ tokens.stripped_tokens }} tokens saved by stripping{% endif %}.
 {% endif %}
 {% if git %}Repository: {{ git.root }}{% if git.branch %} (branch {{ git.branch }}){% endif %}{% if git.commit %} commit {{ git.commit }}{% endif %}.
 {% endif %}
+{% for attachment in attachments %}
+
+== Task: {{ attachment.title }} ({{ attachment.source }})
+{{ attachment.body }}
+{% endfor %}
 
 {% for selection in selections %}
 -- {{ loop.index }}. {{ selection.display_path }}{% if selection.range %} (lines {{ selection.range.start }}-{{ selection.range.end }}){% endif %}
-{% if selection.note %}Note: {{ selection.note }}
+{% if selection.repository %}Repository: {{ selection.repository.root }}{% if selection.repository.commit %} @ {{ selection.repository.commit }}{% endif %}
+{% endif %}{% if selection.permalink %}Source: {{ selection.permalink }}
+{% endif %}{% if selection.note %}Note: {{ selection.note }}
+{% endif %}{% if selection.history %}Recent commits:
+{% for commit in selection.history %}
+  {{ commit.id }} {{ commit.subject }} ({{ commit.author }}{% if commit.date %}, {{ commit.date }}{% endif %})
+{% endfor %}
+{% endif %}{% if selection.comparison %}{% set comparison = selection.comparison %}
+
+{% if comparison.previous %}Before ({{ comparison.revision }}{% if comparison.previous_start_line %}, lines {{ comparison.previous_start_line }}-{{ comparison.previous_end_line }}{% endif %}):
+{{ comparison.previous }}
+
+{% elif comparison.diff %}Changes since {{ comparison.revision }}:
+{{ comparison.diff }}
+
+{% else %}New since {{ comparison.revision }}.
+{% endif %}After (current):
 {% endif %}
 {{ selection.contents }}
 
 {% if selection.tokens %}Tokens: {{ selection.tokens }}{% endif %}{% if selection.characters %} Characters: {{ selection.characters }}{% endif %}
 
+{% endfor %}
+{% for snippet in snippets %}
+
+{{ snippet.body }}
+{% endfor %}
+"#;
+
+const GROUPED_MARKDOWN_TEMPLATE: &str = r#"# Curated Context
+
+Generated at: {{ generated_at }}
+{% for key, value in metadata|items %}
+- {{ key }}: {{ value }}
+{% endfor %}
+
+{% if tokens %}
+## Token Summary
+- Model: {{ tokens.model }}
+- Usage: {{ tokens.total_tokens }} / {{ tokens.token_budget }} tokens
+{% if tokens.stripped_tokens %}
+- Saved by stripping: {{ tokens.stripped_tokens }} tokens
+{% endif %}
+- Characters: {{ tokens.total_characters }} ({{ tokens.total_words }} words, {{ tokens.total_lines }} lines)
+{% endif %}
+
+{% if git %}
+## Repository
+- Root: {{ git.root }}
+{% if git.branch %}
+- Branch: {{ git.branch }}
+{% endif %}
+{% if git.commit %}
+- Commit: {{ git.commit }}
+{% endif %}
+{% endif %}
+
+{% if attachments %}
+## Task
+{% for attachment in attachments %}
+### {{ attachment.title }}
+_Source: {{ attachment.source }}_
+
+{{ attachment.body }}
+
+{% endfor %}
+{% endif %}
+
+{% if groups|length > 1 %}
+## Contents
+{% for group in groups %}
+- {{ group.name }}: {{ group.files }} file{% if group.files != 1 %}s{% endif %}{% if group.tokens %}, {{ group.tokens }} tokens{% endif %}, {{ group.lines }} lines
+{% endfor %}
+
+{% endif %}
+{% for group in groups %}
+## {{ group.name }}
+_{{ group.selections|length }} selection{% if group.selections|length != 1 %}s{% endif %} in {{ group.files }} file{% if group.files != 1 %}s{% endif %}{% if group.tokens %}, {{ group.tokens }} tokens{% endif %}, {{ group.lines }} lines_
+
+{% for selection in group.selections %}
+### {{ selection.display_path }}
+{% if selection.range %}
+_Lines {{ selection.range.start }}-{{ selection.range.end }}_
+{% endif %}
+{% if selection.repository %}
+_Repository: {{ selection.repository.root }}{% if selection.repository.commit %} @ {{ selection.repository.commit }}{% endif %}_
+{% endif %}
+{% if selection.permalink %}
+_Source: {{ selection.permalink }}_
+{% endif %}
+{% if selection.note %}
+> {{ selection.note }}
+{% endif %}
+{% if selection.history %}
+
+Recent commits:
+{% for commit in selection.history %}
+- `{{ commit.id }}` {{ commit.subject }} ({{ commit.author }}{% if commit.date %}, {{ commit.date }}{% endif %})
+{% endfor %}
+{% endif %}
+{% if selection.comparison %}
+{% set comparison = selection.comparison %}
+
+{% if comparison.previous %}
+**Before ({{ comparison.revision }}{% if comparison.previous_start_line %}, lines {{ comparison.previous_start_line }}-{{ comparison.previous_end_line }}{% endif %})**
+{{ selection.fence }}text
+{{ comparison.previous }}
+{{ selection.fence }}
+{% elif comparison.diff %}
+**Changes since {{ comparison.revision }}**
+{{ selection.fence }}diff
+{{ comparison.diff }}
+{{ selection.fence }}
+{% else %}
+_New since {{ comparison.revision }}_
+{% endif %}
+
+**After (current)**
+{% endif %}
+
+{{ selection.fence }}text
+{{ selection.contents }}
+{{ selection.fence }}
+
+{% endfor %}
+{% endfor %}
+{% for snippet in snippets %}
+{{ snippet.body }}
+
 {% endfor %}
 "#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    use crate::infra::fs::{ContentFingerprint, MemoryFs};
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(300))]
+
+        #[test]
+        fn extraction_clamps_any_range_to_the_file(
+            line_count in 0..25usize,
+            range in prop::option::of((0..30usize, 0..30usize)),
+            include_line_numbers in any::<bool>(),
+        ) {
+            let lines: Vec<String> = (1..=line_count).map(|line| format!("line {line}")).collect();
+            let vfs = MemoryFs::new();
+            let path = PathBuf::from("/repo/file.txt");
+            vfs.write(
+                &path,
+                lines
+                    .iter()
+                    .map(|line| format!("{line}\n"))
+                    .collect::<String>(),
+            );
+            let mut options = ExportOptions::from_config(&Config::default());
+            options.include_line_numbers = include_line_numbers;
+            let item = SelectionItem {
+                path,
+                range,
+                note: None,
+                source: SelectionSource::Manual,
+            };
+
+            let extraction = extract_selection_contents(&vfs, &item, &options).unwrap();
+
+            let (raw_start, raw_end) = range.unwrap_or((1, lines.len().max(1)));
+            let start = raw_start.max(1);
+            let end = raw_end.max(start).min(lines.len());
+            if start > lines.len() {
+                prop_assert!(extraction.contents.is_empty());
+                prop_assert_eq!(extraction.start_line, None);
+                return Ok(());
+            }
+            prop_assert_eq!(
+                (extraction.start_line, extraction.end_line),
+                (Some(start), Some(end))
+            );
+            prop_assert_eq!(extraction.line_count, end - start + 1);
+            for (offset, text) in extraction.contents.lines().enumerate() {
+                let number = start + offset;
+                let expected = &lines[number - 1];
+                match text.split_once(" │ ") {
+                    Some((gutter, rest)) if include_line_numbers => {
+                        prop_assert_eq!(gutter.trim().parse(), Ok(number));
+                        prop_assert_eq!(gutter.len(), end.to_string().len());
+                        prop_assert_eq!(rest, expected);
+                    }
+                    _ => prop_assert_eq!(text, expected),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn changed_selections_carry_their_earlier_version() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = temp.path().canonicalize()?;
+        let repo = gix::init(&root)?;
+        let signature = gix::actor::SignatureRef {
+            name: "Dev".into(),
+            email: "dev@example.com".into(),
+            time: "1700000000 +0000",
+        };
+        let mut entries = Vec::new();
+        for (name, contents) in [
+            ("lib.rs", "fn a() {\n    one();\n}\n"),
+            ("notes.txt", "same\n"),
+        ] {
+            fs::write(root.join(name), contents)?;
+            entries.push(gix::objs::tree::Entry {
+                mode: gix::objs::tree::EntryKind::Blob.into(),
+                filename: name.into(),
+                oid: repo.write_blob(contents)?.detach(),
+            });
+        }
+        let tree = repo.write_object(gix::objs::Tree { entries })?.detach();
+        repo.commit_as(
+            signature,
+            signature,
+            "HEAD",
+            "Initial",
+            tree,
+            None::<gix::ObjectId>,
+        )?;
+        fs::write(root.join("lib.rs"), "fn a() {\n    uno();\n}\n")?;
+        fs::write(root.join("new.rs"), "fn b() {}\n")?;
+
+        let items = ["lib.rs", "notes.txt", "new.rs"]
+            .into_iter()
+            .map(|name| SelectionItem {
+                path: root.join(name),
+                range: None,
+                note: None,
+                source: SelectionSource::Manual,
+            })
+            .collect();
+        let bundle = SelectionManager::from_items(items).to_bundle();
+        let mut options = ExportOptions::from_config(&Config::default());
+        options.include_git_metadata = false;
+        options.include_line_numbers = false;
+        options.compare = Some("HEAD".to_string());
+        let exporter = Exporter::new()?;
+
+        let rendered = exporter.render_bundle(&bundle, None, &options)?;
+        assert!(
+            rendered
+                .contains("**Before (HEAD, lines 1-3)**\n```text\nfn a() {\n    one();\n}\n```")
+        );
+        assert!(rendered.contains("_New since HEAD_"));
+        assert_eq!(rendered.matches("**After (current)**").count(), 2);
+
+        options.compare_mode = CompareMode::Diff;
+        let rendered = exporter.render_bundle(&bundle, None, &options)?;
+        assert!(
+            rendered
+                .contains("```diff\n@@ -1,3 +1,3 @@\n fn a() {\n-    one();\n+    uno();\n }\n```")
+        );
+        assert!(!rendered.contains("**Before"));
+        Ok(())
+    }
+
+    #[test]
+    fn history_is_empty_before_the_first_commit() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = temp.path().canonicalize()?;
+        gix::init(&root)?;
+        fs::write(root.join("lib.rs"), "fn a() {}\n")?;
+        let bundle = SelectionManager::from_items(vec![SelectionItem {
+            path: root.join("lib.rs"),
+            range: None,
+            note: None,
+            source: SelectionSource::Manual,
+        }])
+        .to_bundle();
+        let mut options = ExportOptions::from_config(&Config::default());
+        options.include_git_metadata = false;
+        options.history_commits = 3;
+
+        let rendered = Exporter::new()?.render_bundle(&bundle, None, &options)?;
+        assert!(rendered.contains("fn a() {}"), "{rendered}");
+        Ok(())
+    }
+
+    #[test]
+    fn templates_read_config_and_metadata() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let file = temp.path().join("lib.rs");
+        fs::write(&file, "fn a() {}\n")?;
+        let template = temp.path().join("labels.j2");
+        fs::write(
+            &template,
+            "{{ metadata.project }} for {{ metadata.team }}, budget {{ config.defaults.token_budget }}",
+        )?;
+        let config = Config::from_toml_layer(
+            "[defaults]\ntoken_budget = 8000\n[export.metadata]\nproject = \"llmctx\"\nteam = \"core\"\n",
+        )?;
+        let bundle = SelectionManager::from_items(vec![SelectionItem {
+            path: file,
+            range: None,
+            note: None,
+            source: SelectionSource::Manual,
+        }])
+        .to_bundle();
+        let mut options = ExportOptions::from_config(&config);
+        options.include_git_metadata = false;
+        let exporter = Exporter::new()?;
+
+        let rendered = exporter.render_bundle(&bundle, None, &options)?;
+        assert!(rendered.contains("- project: llmctx\n- team: core\n"));
+
+        options.template = template.display().to_string();
+        let rendered = exporter.render_bundle(&bundle, None, &options)?;
+        assert_eq!(rendered, "llmctx for core, budget 8000");
+        Ok(())
+    }
+
+    #[test]
+    fn merged_selections_keep_a_token_estimate() -> Result<()> {
+        let vfs = Arc::new(MemoryFs::new());
+        let contents: String = (1..=20)
+            .map(|line| format!("let x{line} = {line};\n"))
+            .collect();
+        vfs.write("/repo/lib.rs", contents);
+        let selection = |range| SelectionItem {
+            path: PathBuf::from("/repo/lib.rs"),
+            range: Some(range),
+            note: None,
+            source: SelectionSource::Manual,
+        };
+        let bundle = ContextBundle {
+            items: vec![selection((1, 8)), selection((5, 12))],
+            model: None,
+            attachments: Vec::new(),
+        };
+        let config = Config::default();
+        let mut estimator = TokenEstimator::from_config(&config);
+        estimator.set_vfs(vfs.clone());
+        let summary = estimator.estimate_bundle(&bundle)?;
+        let merged = estimator.estimate_bundle(&ContextBundle {
+            items: vec![selection((1, 12))],
+            ..bundle.clone()
+        })?;
+        let mut options = ExportOptions::from_config(&config);
+        options.include_git_metadata = false;
+        let exporter = Exporter::new()?.with_vfs(vfs);
+
+        let rendered = exporter.render_bundle(&bundle, Some(&summary), &options)?;
+        assert!(
+            rendered.contains(&format!("- Tokens: {}", merged.total_tokens)),
+            "{rendered}"
+        );
+        assert!(rendered.contains(&format!(
+            "- Usage: {} / {} tokens",
+            merged.total_tokens, summary.token_budget
+        )));
+        Ok(())
+    }
+
+    #[test]
+    fn fences_outgrow_the_backticks_in_selections() -> Result<()> {
+        assert_eq!(code_fence(["fn a() {}"]), "```");
+        assert_eq!(code_fence(["`a` and ``b``"]), "```");
+        assert_eq!(code_fence(["```rust", "````"]), "`````");
+
+        let vfs = Arc::new(MemoryFs::new());
+        vfs.write(
+            "/repo/README.md",
+            "# Usage\n\n````markdown\n```rust\nfn main() {}\n```\n````\n\n{{ name }} {% if x %}\n",
+        );
+        let bundle = SelectionManager::from_items(vec![SelectionItem {
+            path: PathBuf::from("/repo/README.md"),
+            range: None,
+            note: None,
+            source: SelectionSource::Manual,
+        }])
+        .to_bundle();
+        let mut options = ExportOptions::from_config(&Config::default());
+        options.include_git_metadata = false;
+        options.include_line_numbers = false;
+        let exporter = Exporter::new()?.with_vfs(vfs);
+
+        let rendered = exporter.render_bundle(&bundle, None, &options)?;
+        assert!(
+            rendered.contains(
+                "`````text\n# Usage\n\n````markdown\n```rust\nfn main() {}\n```\n````\n\n{{ name }} {% if x %}\n`````\n"
+            ),
+            "{rendered}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn snippets_follow_the_selections_and_are_recorded() -> Result<()> {
+        let vfs = Arc::new(MemoryFs::new());
+        vfs.write("/repo/lib.rs", "fn a() {}\n");
+        let bundle = SelectionManager::from_items(vec![SelectionItem {
+            path: PathBuf::from("/repo/lib.rs"),
+            range: None,
+            note: None,
+            source: SelectionSource::Manual,
+        }])
+        .to_bundle();
+        let mut options = ExportOptions::from_config(&Config::default());
+        options.include_git_metadata = false;
+        options.include_line_numbers = false;
+        options.include_manifest = true;
+        options.snippets = vec![
+            Snippet {
+                name: "review-checklist".to_string(),
+                body: "## Review checklist\n- Are errors handled?".to_string(),
+            },
+            Snippet {
+                name: "answer-schema".to_string(),
+                body: "Answer with a JSON list of findings.".to_string(),
+            },
+        ];
+        let exporter = Exporter::new()?.with_vfs(vfs);
+
+        let rendered = exporter.render_bundle(&bundle, None, &options)?;
+        assert!(
+            rendered.contains(
+                "lines)\n\n## Review checklist\n- Are errors handled?\n\nAnswer with a JSON list of findings.\n"
+            ),
+            "{rendered}"
+        );
+        let manifest = ExportManifest::parse(&rendered)?;
+        assert_eq!(manifest.snippets, vec!["review-checklist", "answer-schema"]);
+
+        options.format = ExportFormat::Plain;
+        options.template = "plain_text".to_string();
+        let rendered = exporter.render_bundle(&bundle, None, &options)?;
+        assert!(
+            rendered.contains("Characters: 9\n\n## Review checklist"),
+            "{rendered}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn manifest_fingerprints_come_from_the_export_vfs() -> Result<()> {
+        // Only the vfs has this file, as when exporting at a git revision.
+        let vfs = Arc::new(MemoryFs::new());
+        vfs.write("/repo/lib.rs", "fn a() {}\n");
+        let bundle = SelectionManager::from_items(vec![SelectionItem {
+            path: PathBuf::from("/repo/lib.rs"),
+            range: None,
+            note: None,
+            source: SelectionSource::Manual,
+        }])
+        .to_bundle();
+        let mut options = ExportOptions::from_config(&Config::default());
+        options.include_git_metadata = false;
+        options.include_manifest = true;
+        let exporter = Exporter::new()?.with_vfs(vfs);
+
+        let rendered = exporter.render_bundle(&bundle, None, &options)?;
+        let manifest = ExportManifest::parse(&rendered)?;
+        assert_eq!(
+            manifest.selections[0].fingerprint,
+            Some(ContentFingerprint::of_bytes(b"fn a() {}\n"))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn grouped_template_collects_selections_with_subtotals() -> Result<()> {
+        let vfs = Arc::new(MemoryFs::new());
+        for (path, contents) in [
+            ("/repo/src/lib.rs", "fn a() {}\n\nfn b() {}\n"),
+            ("/repo/docs/guide.md", "# Guide\n"),
+            ("/repo/src/main.rs", "fn main() {}\n"),
+        ] {
+            vfs.write(path, contents);
+        }
+        let selection = |path: &str, range| SelectionItem {
+            path: PathBuf::from(path),
+            range,
+            note: None,
+            source: SelectionSource::Manual,
+        };
+        let bundle = ContextBundle {
+            items: vec![
+                selection("/repo/src/lib.rs", Some((1, 1))),
+                selection("/repo/docs/guide.md", None),
+                selection("/repo/src/lib.rs", Some((3, 3))),
+                selection("/repo/src/main.rs", None),
+            ],
+            model: None,
+            attachments: Vec::new(),
+        };
+        let mut options = ExportOptions::from_config(&Config::default());
+        options.include_git_metadata = false;
+        options.include_line_numbers = false;
+        options.template = "grouped_context".to_string();
+        options.roots = WorkspaceRoot::from_paths(&[PathBuf::from("/repo")]);
+        let exporter = Exporter::new()?.with_vfs(vfs);
+
+        let rendered = exporter.render_bundle(&bundle, None, &options)?;
+        assert!(rendered.contains("- src: 2 files, 3 lines\n- docs: 1 file, 1 lines\n"));
+        assert!(
+            rendered.contains("## src\n_3 selections in 2 files, 3 lines_\n\n### src/lib.rs\n")
+        );
+        assert!(rendered.find("## docs").unwrap() > rendered.find("### src/main.rs").unwrap());
+
+        options.group_by = GroupBy::Language;
+        let rendered = exporter.render_bundle(&bundle, None, &options)?;
+        assert!(rendered.contains("- rs: 2 files, 3 lines\n- md: 1 file, 1 lines\n"));
+        Ok(())
+    }
+}
@@ -0,0 +1,185 @@
+//! Suggestions for bringing a bundle that is over its token budget back under it.
+
+use std::path::Path;
+
+use crate::app::scan::slash_path;
+use crate::app::strip::StripOptions;
+use crate::app::tokens::BundleTokenSummary;
+use crate::domain::model::SelectionItem;
+
+/// Selections suggested for dropping at most.
+const MAX_DROPS: usize = 3;
+
+/// One change that saves tokens.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FitHint {
+    /// Remove a selection from the bundle.
+    Drop { item: SelectionItem, tokens: usize },
+    /// Estimate and export with comments and blank lines stripped.
+    Strip {
+        options: StripOptions,
+        tokens: usize,
+    },
+}
+
+impl FitHint {
+    /// Tokens the change saves.
+    pub fn tokens(&self) -> usize {
+        match self {
+            FitHint::Drop { tokens, .. } | FitHint::Strip { tokens, .. } => *tokens,
+        }
+    }
+
+    /// "drop src/api.rs to save 1200 tokens", with paths shown relative to `root`.
+    pub fn describe(&self, root: Option<&Path>) -> String {
+        match self {
+            FitHint::Drop { item, tokens } => {
+                let path = root
+                    .and_then(|root| item.path.strip_prefix(root).ok())
+                    .unwrap_or(&item.path);
+                let range = item
+                    .range
+                    .map(|(start, end)| format!(":{start}-{end}"))
+                    .unwrap_or_default();
+                format!("drop {}{range} to save {tokens} tokens", slash_path(path))
+            }
+            FitHint::Strip { tokens, .. } => {
+                format!("strip comments and blank lines to save {tokens} tokens")
+            }
+        }
+    }
+}
+
+/// The stripping a [`FitHint::Strip`] proposes on top of `current`, unless it is already on.
+pub fn strip_target(current: StripOptions) -> Option<StripOptions> {
+    let target = StripOptions {
+        comments: true,
+        blank_lines: true,
+        ..current
+    };
+    (target != current).then_some(target)
+}
+
+/// Hints for `summary` when it is over budget, changes that fit on their own first.
+///
+/// `strip` is a stripping proposal and the total the bundle would have with it.
+pub fn fit_hints(
+    summary: &BundleTokenSummary,
+    strip: Option<(StripOptions, usize)>,
+) -> Vec<FitHint> {
+    let budget = summary.token_budget as usize;
+    if budget == 0 || summary.total_tokens <= budget {
+        return Vec::new();
+    }
+    let overflow = summary.total_tokens - budget;
+
+    let mut candidates: Vec<FitHint> = summary
+        .items
+        .iter()
+        .filter(|estimate| estimate.tokens > 0)
+        .map(|estimate| FitHint::Drop {
+            item: estimate.item.clone(),
+            tokens: estimate.tokens,
+        })
+        .collect();
+    // Largest first, so the drops kept below are the ones that save the most.
+    candidates.sort_by_key(|hint| std::cmp::Reverse(hint.tokens()));
+
+    let mut hints = Vec::new();
+    if let Some((options, total)) = strip
+        && total < summary.total_tokens
+    {
+        hints.push(FitHint::Strip {
+            options,
+            tokens: summary.total_tokens - total,
+        });
+    }
+    // The smallest selection that fits the bundle on its own loses the least.
+    if let Some(index) = candidates
+        .iter()
+        .rposition(|hint| hint.tokens() >= overflow)
+    {
+        hints.push(candidates.remove(index));
+    }
+    let drops = hints
+        .iter()
+        .filter(|hint| matches!(hint, FitHint::Drop { .. }))
+        .count();
+    hints.extend(candidates.into_iter().take(MAX_DROPS - drops));
+
+    hints.sort_by_key(|hint| {
+        let fits = hint.tokens() >= overflow;
+        let lossless = matches!(hint, FitHint::Strip { .. });
+        (!fits, !lossless)
+    });
+    hints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::app::tokens::{ItemTokenEstimate, TokenModel};
+    use crate::domain::model::SelectionSource;
+
+    fn estimate(path: &str, tokens: usize) -> ItemTokenEstimate {
+        ItemTokenEstimate {
+            item: SelectionItem {
+                path: path.into(),
+                range: None,
+                note: None,
+                source: SelectionSource::Manual,
+            },
+            tokens,
+            characters: tokens * 4,
+            words: tokens,
+            lines: tokens / 10,
+        }
+    }
+
+    fn summary(budget: u32, items: Vec<ItemTokenEstimate>) -> BundleTokenSummary {
+        let total_tokens = items.iter().map(|item| item.tokens).sum();
+        BundleTokenSummary {
+            model: TokenModel::CharacterFallback,
+            token_budget: budget,
+            total_tokens,
+            total_characters: total_tokens * 4,
+            total_words: total_tokens,
+            total_lines: total_tokens / 10,
+            stripped_tokens: 0,
+            items,
+        }
+    }
+
+    #[test]
+    fn suggests_changes_that_fit_first() {
+        let bundle = summary(
+            1_300,
+            vec![
+                estimate("/repo/a.rs", 900),
+                estimate("/repo/b.rs", 600),
+                estimate("/repo/c.rs", 250),
+                estimate("/repo/d.rs", 50),
+            ],
+        );
+        let strip = strip_target(StripOptions::default()).unwrap();
+        let hints = fit_hints(&bundle, Some((strip, 1_650)));
+        let described: Vec<String> = hints
+            .iter()
+            .map(|hint| hint.describe(Some(Path::new("/repo"))))
+            .collect();
+        assert_eq!(
+            described,
+            vec![
+                "drop b.rs to save 600 tokens",
+                "drop a.rs to save 900 tokens",
+                "strip comments and blank lines to save 150 tokens",
+                "drop c.rs to save 250 tokens",
+            ]
+        );
+
+        assert!(fit_hints(&summary(0, vec![estimate("a.rs", 10)]), None).is_empty());
+        assert!(fit_hints(&summary(100, vec![estimate("a.rs", 10)]), None).is_empty());
+        assert_eq!(strip_target(strip), None);
+    }
+}
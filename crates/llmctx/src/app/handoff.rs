@@ -0,0 +1,253 @@
+//! Handoff formats that pass a bundle to coding agents: aider command lists, continue.dev
+//! context items, Cursor rules files, and `@path` mentions for chat tools.
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::app::export::code_fence;
+
+/// One exported selection, as the handoff formats see it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HandoffItem {
+    pub path: PathBuf,
+    /// Path relative to the repository root (or the absolute path outside one), which is how
+    /// agents running from the repository root refer to the file.
+    pub relative_path: String,
+    pub range: Option<(usize, usize)>,
+    pub note: Option<String>,
+    pub contents: String,
+}
+
+impl HandoffItem {
+    fn label(&self) -> String {
+        match self.range {
+            Some((start, end)) => format!("{}:{start}-{end}", self.relative_path),
+            None => self.relative_path.clone(),
+        }
+    }
+}
+
+/// aider chat commands adding each selected file, for use with `/load`.
+///
+/// aider adds whole files, so ranges and notes are kept as `#` comments, which `/load` skips.
+pub fn aider_commands(items: &[HandoffItem]) -> String {
+    let mut out =
+        String::from("# Context curated with llmctx; run `/load <this file>` in aider.\n");
+    let mut added: Vec<&str> = Vec::new();
+    for item in items {
+        if item.range.is_some() || item.note.is_some() {
+            let mut comment = format!("# {}", item.label());
+            if let Some(note) = &item.note {
+                comment.push_str(": ");
+                comment.push_str(&note.replace('\n', " "));
+            }
+            out.push_str(&comment);
+            out.push('\n');
+        }
+        if added.contains(&item.relative_path.as_str()) {
+            continue;
+        }
+        added.push(&item.relative_path);
+        if item.relative_path.contains(char::is_whitespace) {
+            out.push_str(&format!("/add \"{}\"\n", item.relative_path));
+        } else {
+            out.push_str(&format!("/add {}\n", item.relative_path));
+        }
+    }
+    out
+}
+
+/// continue.dev context items (`name`, `description`, `content`, `uri`) as a JSON array.
+pub fn continue_context(items: &[HandoffItem]) -> Result<String> {
+    #[derive(Serialize)]
+    struct ContextItem<'a> {
+        name: String,
+        description: String,
+        content: &'a str,
+        uri: ContextItemUri,
+    }
+
+    #[derive(Serialize)]
+    struct ContextItemUri {
+        #[serde(rename = "type")]
+        kind: &'static str,
+        value: String,
+    }
+
+    let context: Vec<ContextItem> = items
+        .iter()
+        .map(|item| {
+            let name = item
+                .path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| item.relative_path.clone());
+            let mut description = item.label();
+            if let Some(note) = &item.note {
+                description.push_str(" — ");
+                description.push_str(note);
+            }
+            ContextItem {
+                name,
+                description,
+                content: &item.contents,
+                uri: ContextItemUri {
+                    kind: "file",
+                    value: format!("file://{}", item.path.display()),
+                },
+            }
+        })
+        .collect();
+    let mut json =
+        serde_json::to_string_pretty(&context).context("failed to serialize context items")?;
+    json.push('\n');
+    Ok(json)
+}
+
+/// `@path` mention for each selected file, once per file, joined by `separator`.
+///
+/// Chat tools such as Cursor and Copilot Chat resolve the mentions to the files themselves.
+/// Paths containing whitespace are quoted (`@"docs/read me.md"`) so the mention stays whole.
+pub fn mentions(items: &[HandoffItem], separator: &str) -> String {
+    let mut paths: Vec<&str> = Vec::new();
+    for item in items {
+        if !paths.contains(&item.relative_path.as_str()) {
+            paths.push(&item.relative_path);
+        }
+    }
+    let mentions: Vec<String> = paths
+        .iter()
+        .map(|path| {
+            if path.contains(char::is_whitespace) {
+                format!("@\"{path}\"")
+            } else {
+                format!("@{path}")
+            }
+        })
+        .collect();
+    let mut out = mentions.join(separator);
+    out.push('\n');
+    out
+}
+
+/// Cursor rules file (`.mdc`) holding the selections, attached when any selected file is.
+pub fn cursor_rule(items: &[HandoffItem]) -> String {
+    let mut globs: Vec<&str> = Vec::new();
+    for item in items {
+        if !globs.contains(&item.relative_path.as_str()) {
+            globs.push(&item.relative_path);
+        }
+    }
+    let mut out = format!(
+        "---\ndescription: Context curated with llmctx\nglobs: {}\nalwaysApply: false\n---\n",
+        globs.join(",")
+    );
+    for item in items {
+        out.push_str(&format!("\n## {}\n\n", item.label()));
+        if let Some(note) = &item.note {
+            out.push_str(&format!("> {note}\n\n"));
+        }
+        let language = item
+            .path
+            .extension()
+            .map(|ext| ext.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let fence = code_fence([item.contents.as_str()]);
+        out.push_str(&format!("{fence}{language}\n{}\n{fence}\n", item.contents));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items() -> Vec<HandoffItem> {
+        vec![
+            HandoffItem {
+                path: PathBuf::from("/repo/src/lib.rs"),
+                relative_path: "src/lib.rs".to_string(),
+                range: Some((10, 20)),
+                note: Some("entry point".to_string()),
+                contents: "pub fn run() {}".to_string(),
+            },
+            HandoffItem {
+                path: PathBuf::from("/repo/src/lib.rs"),
+                relative_path: "src/lib.rs".to_string(),
+                range: Some((40, 45)),
+                note: None,
+                contents: "fn helper() {}".to_string(),
+            },
+            HandoffItem {
+                path: PathBuf::from("/repo/docs/read me.md"),
+                relative_path: "docs/read me.md".to_string(),
+                range: None,
+                note: None,
+                contents: "# Docs".to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    fn aider_adds_each_file_once_and_keeps_ranges_as_comments() {
+        let commands = aider_commands(&items());
+        let lines: Vec<&str> = commands.lines().skip(1).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "# src/lib.rs:10-20: entry point",
+                "/add src/lib.rs",
+                "# src/lib.rs:40-45",
+                "/add \"docs/read me.md\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn mentions_name_each_file_once() {
+        assert_eq!(
+            mentions(&items(), "\n"),
+            "@src/lib.rs\n@\"docs/read me.md\"\n"
+        );
+        assert_eq!(
+            mentions(&items(), " "),
+            "@src/lib.rs @\"docs/read me.md\"\n"
+        );
+    }
+
+    #[test]
+    fn continue_items_and_cursor_rules_carry_contents() -> Result<()> {
+        let json: serde_json::Value = serde_json::from_str(&continue_context(&items())?)?;
+        assert_eq!(json[0]["name"], "lib.rs");
+        assert_eq!(json[0]["description"], "src/lib.rs:10-20 — entry point");
+        assert_eq!(json[0]["content"], "pub fn run() {}");
+        assert_eq!(json[2]["uri"]["value"], "file:///repo/docs/read me.md");
+
+        let rule = cursor_rule(&items());
+        assert!(rule.starts_with("---\ndescription: Context curated with llmctx\n"));
+        assert!(rule.contains("globs: src/lib.rs,docs/read me.md\n"));
+        assert!(
+            rule.contains("## src/lib.rs:10-20\n\n> entry point\n\n```rs\npub fn run() {}\n```")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn cursor_rules_fence_markdown_with_its_own_code_blocks() {
+        let items = vec![HandoffItem {
+            path: PathBuf::from("/repo/README.md"),
+            relative_path: "README.md".to_string(),
+            range: None,
+            note: None,
+            contents: "Run:\n\n```sh\ncargo test\n```".to_string(),
+        }];
+
+        let rule = cursor_rule(&items);
+        assert!(
+            rule.ends_with("````md\nRun:\n\n```sh\ncargo test\n```\n````\n"),
+            "{rule}"
+        );
+    }
+}
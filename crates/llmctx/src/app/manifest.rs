@@ -0,0 +1,251 @@
+//! Machine-readable export manifests recording how a bundle was produced.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+use crate::app::session::{SelectionRecord, SessionSnapshot};
+use crate::domain::model::{BundleStats, SchemaVersion};
+
+/// Opening marker of the manifest block appended to exports.
+pub const MANIFEST_START: &str = "<!-- llmctx-manifest";
+const MANIFEST_END: &str = "-->";
+
+/// Everything needed to regenerate an export against a later working tree.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportManifest {
+    /// Schema the manifest was written with; manifests from a newer llmctx are refused.
+    #[serde(default)]
+    pub schema_version: SchemaVersion,
+    pub llmctx_version: String,
+    pub generated_at: String,
+    /// Hash of the effective configuration, to spot exports made with different settings.
+    pub config_hash: String,
+    pub git_commit: Option<String>,
+    pub model: Option<String>,
+    pub format: String,
+    pub template: String,
+    /// Sources of attached task documents, as passed to `--attach-issue`.
+    #[serde(default)]
+    pub attachments: Vec<String>,
+    /// Names of the instruction snippets appended, as passed to `--with-snippet`.
+    #[serde(default)]
+    pub snippets: Vec<String>,
+    /// Selections with display paths and the content fingerprints seen at export time.
+    pub selections: Vec<SelectionRecord>,
+    /// File, line, and language counts of the exported bundle, independent of any tokenizer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<BundleStats>,
+}
+
+impl ExportManifest {
+    /// Render the manifest as a trailing comment block (invisible in rendered Markdown).
+    ///
+    /// `>` only occurs inside JSON strings, where it is written as `\u003e` so a note such as
+    /// `a --> b` cannot close the comment early.
+    pub fn to_footer(&self) -> String {
+        let json = serde_json::to_string_pretty(self)
+            .unwrap_or_else(|_| "{}".to_string())
+            .replace('>', "\\u003e");
+        format!("{MANIFEST_START}\n{json}\n{MANIFEST_END}\n")
+    }
+
+    /// Read the manifest from an export carrying a footer, or from a bare manifest JSON document.
+    pub fn parse(text: &str) -> Result<Self> {
+        let json = match text.rfind(MANIFEST_START) {
+            Some(start) => {
+                let block = &text[start + MANIFEST_START.len()..];
+                let end = block
+                    .find(MANIFEST_END)
+                    .ok_or_else(|| anyhow!("manifest block is not terminated"))?;
+                &block[..end]
+            }
+            None => text,
+        };
+        serde_json::from_str(json).context("invalid export manifest")
+    }
+}
+
+/// Selections and settings recovered from a manifest or session file for re-export.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Replay {
+    pub selections: Vec<SelectionRecord>,
+    pub model: Option<String>,
+    pub format: Option<String>,
+    pub template: Option<String>,
+    pub attachments: Vec<String>,
+    pub snippets: Vec<String>,
+    pub config_hash: Option<String>,
+    pub git_commit: Option<String>,
+    /// Directory that relative selection paths are anchored to, when known (session files).
+    pub base: Option<PathBuf>,
+}
+
+impl Replay {
+    /// Load an export with a manifest footer, a bare manifest, or a `.llmctx/session.json` file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("failed to read manifest {}", path.display()))?;
+        match ExportManifest::parse(&text) {
+            Ok(manifest) => return Ok(manifest.into()),
+            // An export's footer can only be a manifest, so its error is the one to report.
+            Err(err) if text.contains(MANIFEST_START) => {
+                return Err(err.context(format!("failed to read manifest {}", path.display())));
+            }
+            Err(_) => {}
+        }
+        let snapshot: SessionSnapshot = serde_json::from_str(&text).with_context(|| {
+            format!(
+                "{} is neither an export manifest nor a session file",
+                path.display()
+            )
+        })?;
+        // Session paths are relative to the workspace holding `.llmctx/`.
+        let dir = path.parent().unwrap_or(Path::new("."));
+        let base = if dir.file_name().is_some_and(|name| name == ".llmctx") {
+            dir.parent().unwrap_or(dir)
+        } else {
+            dir
+        };
+        Ok(Self {
+            selections: snapshot.selections,
+            model: snapshot.model,
+            base: Some(base.to_path_buf()),
+            ..Self::default()
+        })
+    }
+
+    /// Resolve a recorded selection path, preferring the recorded base directory.
+    pub fn resolve(&self, path: &Path, fallback: impl FnOnce(&Path) -> PathBuf) -> PathBuf {
+        match &self.base {
+            Some(base) if path.is_relative() => base.join(path),
+            _ => fallback(path),
+        }
+    }
+}
+
+impl From<ExportManifest> for Replay {
+    fn from(manifest: ExportManifest) -> Self {
+        Self {
+            selections: manifest.selections,
+            model: manifest.model,
+            format: Some(manifest.format),
+            template: Some(manifest.template),
+            attachments: manifest.attachments,
+            snippets: manifest.snippets,
+            config_hash: Some(manifest.config_hash),
+            git_commit: manifest.git_commit,
+            base: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::domain::model::{
+        ItemStats, LanguageStats, SCHEMA_VERSION, SelectionItem, SelectionSource,
+    };
+
+    #[test]
+    fn footer_wraps_json_in_a_comment_block() {
+        let manifest = ExportManifest {
+            schema_version: SchemaVersion::default(),
+            llmctx_version: "0.1.0".to_string(),
+            generated_at: "2024-01-01T00:00:00Z".to_string(),
+            config_hash: "00ff".to_string(),
+            git_commit: None,
+            model: Some("openai:gpt-4o".to_string()),
+            format: "markdown".to_string(),
+            template: "concise_context".to_string(),
+            attachments: Vec::new(),
+            snippets: Vec::new(),
+            selections: vec![SelectionRecord {
+                item: SelectionItem {
+                    path: "src/lib.rs".into(),
+                    range: Some((1, 5)),
+                    note: Some("parse --> render".to_string()),
+                    source: SelectionSource::Glob,
+                },
+                fingerprint: None,
+            }],
+            stats: Some(BundleStats {
+                files: 1,
+                total_lines: 5,
+                languages: vec![LanguageStats {
+                    language: "rs".to_string(),
+                    files: 1,
+                    lines: 5,
+                }],
+                largest: vec![ItemStats {
+                    path: "src/lib.rs".into(),
+                    range: Some((1, 5)),
+                    lines: 5,
+                }],
+            }),
+        };
+
+        let footer = manifest.to_footer();
+        assert!(footer.starts_with(MANIFEST_START));
+        assert!(footer.trim_end().ends_with("-->"));
+        assert_eq!(footer.matches("-->").count(), 1);
+        assert!(footer.contains("\"source\": \"glob\""));
+        assert!(footer.contains("\"total_lines\": 5"));
+        let export = format!("# Curated Context\n\nbody\n{footer}");
+        let parsed = ExportManifest::parse(&export).expect("valid manifest");
+        assert_eq!(parsed, manifest);
+    }
+
+    #[test]
+    fn manifests_from_a_newer_schema_are_refused() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let export = temp.path().join("export.md");
+        fs::write(
+            &export,
+            format!(
+                "body\n{MANIFEST_START}\n{{\"schema_version\": {}}}\n-->\n",
+                SCHEMA_VERSION + 1
+            ),
+        )?;
+
+        let err = Replay::load(&export).unwrap_err();
+        assert!(
+            format!("{err:#}").contains("newer than the supported version"),
+            "{err:#}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn session_files_replay_relative_to_their_workspace() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let dir = temp.path().join(".llmctx");
+        fs::create_dir(&dir)?;
+        let snapshot = SessionSnapshot {
+            selections: vec![SelectionRecord {
+                item: SelectionItem {
+                    path: "src/main.rs".into(),
+                    range: None,
+                    note: None,
+                    source: SelectionSource::Manual,
+                },
+                fingerprint: None,
+            }],
+            model: Some("openai:gpt-4o".to_string()),
+            ..SessionSnapshot::default()
+        };
+        fs::write(dir.join("session.json"), serde_json::to_string(&snapshot)?)?;
+
+        let replay = Replay::load(&dir.join("session.json"))?;
+        assert_eq!(replay.model.as_deref(), Some("openai:gpt-4o"));
+        assert_eq!(replay.format, None);
+        assert_eq!(
+            replay.resolve(Path::new("src/main.rs"), Path::to_path_buf),
+            temp.path().join("src/main.rs")
+        );
+        Ok(())
+    }
+}
@@ -1,9 +1,27 @@
 //! Application layer orchestrating domain logic and infrastructure.
 
+pub mod attachments;
+pub mod callgraph;
+pub mod cancel;
+pub mod defaults;
+pub mod deps;
+pub mod diff;
 pub mod export;
+pub mod fit;
+pub mod frecency;
+pub mod handoff;
+pub mod manifest;
+pub mod pipeline;
 pub mod preview;
+pub mod recipes;
+pub mod review;
 pub mod scan;
 pub mod search;
 pub mod selection;
+pub mod semantic;
 pub mod session;
+pub mod snippets;
+pub mod stats;
+pub mod strip;
+pub mod suggest;
 pub mod tokens;
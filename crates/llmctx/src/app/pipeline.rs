@@ -0,0 +1,310 @@
+//! Headless scan → select → estimate → export pipeline for embedding llmctx in other tools.
+//!
+//! [`Pipeline`] wires the same pieces the CLI uses without touching the terminal, the clipboard,
+//! or the process-wide tracing subscriber: nothing is copied unless asked for, and log events go
+//! to the caller's subscriber, a [`Dispatch`] of their choosing, or nowhere with
+//! [`PipelineBuilder::quiet`].
+
+use std::env;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use tracing::Dispatch;
+
+use crate::app::cancel::CancellationToken;
+use crate::app::export::{ExportFormat, ExportOptions, ExportResult, Exporter};
+use crate::app::scan::{ScanResult, Scanner, WorkspaceRoot};
+use crate::app::selection::{self, SelectionManager};
+use crate::app::strip::StripOptions;
+use crate::app::tokens::{BundleTokenSummary, TokenEstimator};
+use crate::domain::model::SelectionItem;
+use crate::infra::config::Config;
+use crate::infra::fs::RealFs;
+
+/// Builder for a [`Pipeline`]; every setting falls back to the loaded configuration.
+#[derive(Debug, Default)]
+pub struct PipelineBuilder {
+    roots: Vec<PathBuf>,
+    config: Option<Config>,
+    model: Option<String>,
+    format: Option<ExportFormat>,
+    template: Option<String>,
+    strip: Option<StripOptions>,
+    output: Option<PathBuf>,
+    overwrite: bool,
+    cancel: CancellationToken,
+    dispatch: Option<Dispatch>,
+}
+
+impl PipelineBuilder {
+    /// Add a workspace root; without any, the working directory is used.
+    pub fn root(mut self, path: impl Into<PathBuf>) -> Self {
+        self.roots.push(path.into());
+        self
+    }
+
+    /// Use `config` instead of discovering configuration from the first root.
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Model recorded on the bundle and used for token estimates.
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// Output format of rendered exports.
+    pub fn format(mut self, format: ExportFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Template name or path for template-based formats.
+    pub fn template(mut self, template: impl Into<String>) -> Self {
+        self.template = Some(template.into());
+        self
+    }
+
+    /// Transforms applied to selection contents before estimating and rendering.
+    pub fn strip(mut self, strip: StripOptions) -> Self {
+        self.strip = Some(strip);
+        self
+    }
+
+    /// File [`Pipeline::export`] writes to; without one, exports are only rendered.
+    pub fn output(mut self, path: impl Into<PathBuf>) -> Self {
+        self.output = Some(path.into());
+        self
+    }
+
+    /// Replace an existing output file instead of failing.
+    pub fn overwrite(mut self, overwrite: bool) -> Self {
+        self.overwrite = overwrite;
+        self
+    }
+
+    /// Abort scans, estimates, and exports once `cancel` is cancelled, e.g. from another thread.
+    pub fn cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Send the pipeline's log events to `dispatch` rather than the current default subscriber.
+    pub fn tracing(mut self, dispatch: Dispatch) -> Self {
+        self.dispatch = Some(dispatch);
+        self
+    }
+
+    /// Discard the pipeline's log events.
+    pub fn quiet(self) -> Self {
+        self.tracing(Dispatch::none())
+    }
+
+    /// Load configuration if none was given and assemble the pipeline.
+    pub fn build(self) -> Result<Pipeline> {
+        let paths = if self.roots.is_empty() {
+            vec![env::current_dir().context("unable to determine working directory")?]
+        } else {
+            self.roots
+        };
+        let roots = WorkspaceRoot::from_paths(&paths);
+        let config = match self.config {
+            Some(config) => config,
+            None => with_dispatch(self.dispatch.as_ref(), || Config::load_for(&roots[0].path))?,
+        };
+
+        let strip = self
+            .strip
+            .unwrap_or_else(|| StripOptions::from_config(&config));
+        let mut estimator = TokenEstimator::from_config(&config);
+        estimator.set_strip(strip);
+        estimator.set_cancellation(self.cancel.clone());
+        let mut selections = SelectionManager::new();
+        selections.set_model(
+            self.model
+                .unwrap_or_else(|| config.defaults.model().to_string()),
+        );
+
+        let mut options = ExportOptions::from_config(&config);
+        options.strip = strip;
+        options.roots = roots.clone();
+        options.output_path = self.output;
+        options.overwrite = self.overwrite;
+        options.cancel = self.cancel.clone();
+        if let Some(format) = self.format {
+            options.format = format;
+        }
+        if let Some(template) = self.template {
+            options.template = template;
+        }
+
+        Ok(Pipeline {
+            roots,
+            config,
+            selections,
+            estimator,
+            options,
+            exporter: Exporter::new()?,
+            cancel: self.cancel,
+            dispatch: self.dispatch,
+        })
+    }
+}
+
+/// Configured scanner, selection set, token estimator, and exporter for one workspace.
+pub struct Pipeline {
+    roots: Vec<WorkspaceRoot>,
+    config: Config,
+    selections: SelectionManager,
+    estimator: TokenEstimator,
+    options: ExportOptions,
+    exporter: Exporter,
+    cancel: CancellationToken,
+    dispatch: Option<Dispatch>,
+}
+
+impl Pipeline {
+    /// Start building a pipeline.
+    pub fn builder() -> PipelineBuilder {
+        PipelineBuilder::default()
+    }
+
+    /// Effective configuration.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Workspace roots selections are resolved against.
+    pub fn roots(&self) -> &[WorkspaceRoot] {
+        &self.roots
+    }
+
+    /// Current selections.
+    pub fn selections(&self) -> &SelectionManager {
+        &self.selections
+    }
+
+    /// Selections, for edits beyond [`Self::select`].
+    pub fn selections_mut(&mut self) -> &mut SelectionManager {
+        &mut self.selections
+    }
+
+    /// Export options, for settings the builder does not cover.
+    pub fn options_mut(&mut self) -> &mut ExportOptions {
+        &mut self.options
+    }
+
+    /// Scan every root with the configured ignore rules.
+    pub fn scan(&self) -> Result<ScanResult> {
+        self.traced(|| {
+            Scanner::new()
+                .with_cancellation(self.cancel.clone())
+                .scan_roots(&self.roots, &self.config)
+        })
+    }
+
+    /// Select `path`, relative to the roots or absolute, optionally limited to a line range.
+    ///
+    /// Fails with a [`crate::domain::errors::DomainError`] when the file is missing or the range
+    /// starts past its end.
+    pub fn select(
+        &mut self,
+        path: impl AsRef<Path>,
+        range: Option<(usize, usize)>,
+    ) -> Result<SelectionItem> {
+        let path = WorkspaceRoot::resolve(&self.roots, path.as_ref());
+        selection::check_bounds(&RealFs, &path, range)?;
+        Ok(self.selections.add_selection(path, range, None))
+    }
+
+    /// Token estimate of the current selections, or `None` when nothing is selected.
+    pub fn estimate(&self) -> Result<Option<BundleTokenSummary>> {
+        self.traced(|| self.selections.summarize_tokens(&self.estimator))
+    }
+
+    /// Render the current selections without writing anything.
+    pub fn render(&self) -> Result<String> {
+        self.traced(|| {
+            let summary = self.selections.summarize_tokens(&self.estimator)?;
+            self.exporter.render_bundle(
+                &self.selections.to_bundle(),
+                summary.as_ref(),
+                &self.options,
+            )
+        })
+    }
+
+    /// Render the current selections and write them to the configured output, if any.
+    pub fn export(&self) -> Result<ExportResult> {
+        self.traced(|| {
+            let summary = self.selections.summarize_tokens(&self.estimator)?;
+            self.exporter.export(
+                &self.selections.to_bundle(),
+                summary.as_ref(),
+                &self.options,
+            )
+        })
+    }
+
+    fn traced<T>(&self, f: impl FnOnce() -> T) -> T {
+        with_dispatch(self.dispatch.as_ref(), f)
+    }
+}
+
+/// Run `f` with `dispatch` as the thread's default subscriber, if one is given.
+fn with_dispatch<T>(dispatch: Option<&Dispatch>, f: impl FnOnce() -> T) -> T {
+    match dispatch {
+        Some(dispatch) => tracing::dispatcher::with_default(dispatch, f),
+        None => f(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+
+    #[test]
+    fn builds_and_exports_without_global_state() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = temp.path();
+        fs::write(root.join("lib.rs"), "pub fn one() {}\npub fn two() {}\n")?;
+        let output = root.join("out").join("context.txt");
+        fs::create_dir(root.join("out"))?;
+
+        let mut pipeline = Pipeline::builder()
+            .root(root)
+            .config(Config::default())
+            .format(ExportFormat::Plain)
+            .output(&output)
+            .quiet()
+            .build()?;
+        assert!(pipeline.select("missing.rs", None).is_err());
+        pipeline.select("lib.rs", Some((2, 2)))?;
+
+        let scan = pipeline.scan()?;
+        assert!(scan.files.iter().any(|file| file.path.ends_with("lib.rs")));
+        assert!(
+            pipeline
+                .estimate()?
+                .is_some_and(|summary| summary.total_tokens > 0)
+        );
+
+        let rendered = pipeline.render()?;
+        assert!(rendered.contains("pub fn two"));
+        assert!(!rendered.contains("pub fn one"));
+        assert!(!output.exists());
+
+        let result = pipeline.export()?;
+        assert_eq!(fs::read_to_string(&output)?, result.rendered);
+        assert!(!result.copied_to_clipboard);
+        assert!(
+            pipeline.export().is_err(),
+            "existing output is not replaced"
+        );
+        Ok(())
+    }
+}
@@ -1,17 +1,29 @@
 //! Preview service producing syntax highlighted, chunked views of files.
 
 use std::borrow::Cow;
-use std::fs::File;
-use std::io::{BufRead, BufReader, Read};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
 
-use anyhow::{Context, Result, anyhow};
+use anyhow::{Context, Result};
+use lru::LruCache;
 
+use crate::domain::errors::DomainError;
 use crate::infra::config::Config;
+use crate::infra::documents::{self, DocumentKind, ExtractOptions, ExtractedDocument};
+use crate::infra::fs::{ContentFingerprint, RealFs, TextEncoding, Vfs, VfsMetadata};
 use crate::infra::highlight::{HighlightResult, Highlighter};
 
 /// Default continuation size when previewing large files if configuration is zero.
 const DEFAULT_CHUNK_SIZE: usize = 200;
+/// Read buffer used while scanning a file for line offsets.
+const INDEX_READ_BUFFER: usize = 256 * 1024;
+/// Line indexes kept for revisits; one holds an offset per line, tens of MB for a large log.
+const LINE_INDEX_CACHE_FILES: usize = 16;
+/// Highlighted chunks kept for revisits; a few hundred lines each, across all previewed files.
+const HIGHLIGHT_CACHE_CHUNKS: usize = 128;
 
 /// A continuation token used for loading more preview content.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -29,21 +41,67 @@ pub struct PreviewSegment {
     pub truncated: bool,
     pub continuation: Option<ContinuationToken>,
     pub notice: Option<String>,
+    /// Number of lines in the whole file, not just this segment.
+    pub total_lines: usize,
+}
+
+/// Hit/miss counters and occupancy of the preview highlight cache.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HighlightCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
 }
 
 /// Service responsible for preparing preview data from files.
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct PreviewService {
     highlighter: Highlighter,
+    vfs: Arc<dyn Vfs>,
+    /// Line offsets of recently previewed files, reused across continuations and range jumps.
+    line_indexes: Mutex<LruCache<PathBuf, Arc<LineIndex>>>,
+    /// Highlighted chunks, so navigating back to a chunk skips the syntax pass.
+    highlights: Mutex<HighlightCache>,
+}
+
+impl Default for PreviewService {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl PreviewService {
     pub fn new() -> Self {
         Self {
             highlighter: Highlighter::new(),
+            vfs: Arc::new(RealFs),
+            line_indexes: Mutex::new(LruCache::new(
+                NonZeroUsize::new(LINE_INDEX_CACHE_FILES).expect("cache capacity is non-zero"),
+            )),
+            highlights: Mutex::new(HighlightCache::new()),
         }
     }
 
+    /// Read files from `vfs` instead of the local disk.
+    pub fn with_vfs(mut self, vfs: Arc<dyn Vfs>) -> Self {
+        self.vfs = vfs;
+        self
+    }
+
+    /// Snapshot of highlight cache hit/miss counters for diagnostics.
+    pub fn highlight_cache_stats(&self) -> HighlightCacheStats {
+        self.highlights.lock().unwrap().stats()
+    }
+
+    /// Names of the highlight themes previews can be rendered with.
+    pub fn available_themes(&self) -> Vec<String> {
+        self.highlighter.available_themes()
+    }
+
+    pub fn has_theme(&self, theme: &str) -> bool {
+        self.highlighter.has_theme(theme)
+    }
+
     /// Load a preview segment for the provided path.
     pub fn preview(
         &self,
@@ -51,13 +109,45 @@ impl PreviewService {
         range: Option<std::ops::Range<usize>>,
         config: &Config,
     ) -> Result<PreviewSegment> {
-        if !path.exists() {
-            return Err(anyhow!("file not found: {}", path.display()));
+        if !self.vfs.exists(path) {
+            return Err(DomainError::FileMissing {
+                path: path.to_path_buf(),
+            }
+            .into());
         }
 
         let start = range.as_ref().map_or(0, |r| r.start);
 
-        if Self::is_binary(path)? {
+        let configured_chunk = config.defaults.preview_max_lines();
+        let chunk_size = if configured_chunk == 0 {
+            DEFAULT_CHUNK_SIZE
+        } else {
+            configured_chunk
+        };
+
+        let limit = range
+            .as_ref()
+            .map(|r| r.end.saturating_sub(r.start))
+            .filter(|len| *len > 0)
+            .unwrap_or(chunk_size);
+
+        if let Some(kind) = DocumentKind::detect(path) {
+            match documents::extract(
+                self.vfs.as_ref(),
+                path,
+                kind,
+                ExtractOptions::from_config(config),
+            ) {
+                Ok(document) => {
+                    return Ok(self.document_segment(path, document, start, limit, config));
+                }
+                Err(err) => {
+                    tracing::debug!(path = %path.display(), error = %err, "document extraction failed");
+                }
+            }
+        }
+
+        let Some(encoding) = self.sniff_encoding(path)? else {
             let message = format!(
                 "Binary preview not available for {} (rendered as plain text).",
                 path.display()
@@ -72,24 +162,40 @@ impl PreviewService {
                 continuation: None,
                 notice: Some(message),
                 highlighted,
+                total_lines: 0,
             });
+        };
+        // Line offsets are indexed by byte, which only works for ASCII-compatible encodings.
+        if !encoding.is_ascii_compatible() {
+            let decoded = self
+                .vfs
+                .read_text(path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            let document = ExtractedDocument {
+                lines: decoded.text.lines().map(str::to_owned).collect(),
+                syntax_hint: path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned()),
+                notice: format!("Decoded from {}.", encoding.label()),
+            };
+            return Ok(self.document_segment(path, document, start, limit, config));
         }
 
-        let configured_chunk = config.defaults.preview_max_lines();
-        let chunk_size = if configured_chunk == 0 {
-            DEFAULT_CHUNK_SIZE
+        let ReadLines {
+            lines,
+            lossy,
+            has_more,
+            total_lines,
+            fingerprint,
+        } = self.read_lines(path, start, limit, encoding)?;
+        let mut notice = if encoding.is_guess() {
+            Some(format!(
+                "Not UTF-8; detected {}, so some characters may be wrong.",
+                encoding.label()
+            ))
         } else {
-            configured_chunk
+            (encoding != TextEncoding::Utf8).then(|| format!("Decoded from {}.", encoding.label()))
         };
-
-        let limit = range
-            .as_ref()
-            .map(|r| r.end.saturating_sub(r.start))
-            .filter(|len| *len > 0)
-            .unwrap_or(chunk_size);
-
-        let (lines, lossy, has_more) = Self::read_lines(path, start, limit)?;
-        let mut notice = None;
         let theme_name = config.defaults.theme().to_string();
 
         let highlighted = if lossy {
@@ -97,8 +203,7 @@ impl PreviewService {
                 Some("Preview rendered without syntax highlighting due to invalid UTF-8.".into());
             HighlightResult::plain(lines.clone(), theme_name)
         } else {
-            self.highlighter
-                .highlight(path, &lines, config.defaults.theme())
+            self.highlight_chunk(path, start, &lines, fingerprint, config.defaults.theme())
         };
 
         let end_line = start + lines.len();
@@ -114,73 +219,302 @@ impl PreviewService {
             highlighted,
             continuation,
             notice,
+            total_lines,
         })
     }
 
-    /// Determine if the file should be treated as binary and skipped.
-    fn is_binary(path: &Path) -> Result<bool> {
-        let mut file = File::open(path)?;
+    /// Highlight a chunk of a text file, reusing the cached result while its bytes are unchanged.
+    ///
+    /// Chunks are highlighted from a fresh parse state, so the chunk's own bytes are all the
+    /// result depends on besides the path (syntax) and theme.
+    fn highlight_chunk(
+        &self,
+        path: &Path,
+        start: usize,
+        lines: &[String],
+        fingerprint: ContentFingerprint,
+        theme: &str,
+    ) -> HighlightResult {
+        let key = ChunkKey {
+            path: path.to_path_buf(),
+            start,
+            theme: theme.to_string(),
+        };
+        if let Some(cached) = self.highlights.lock().unwrap().get(&key, fingerprint) {
+            return cached;
+        }
+        let highlighted = self.highlighter.highlight(path, lines, theme);
+        self.highlights
+            .lock()
+            .unwrap()
+            .insert(key, fingerprint, highlighted.clone());
+        highlighted
+    }
+
+    /// Build a segment from text extracted out of a non-text document.
+    fn document_segment(
+        &self,
+        path: &Path,
+        document: ExtractedDocument,
+        start: usize,
+        limit: usize,
+        config: &Config,
+    ) -> PreviewSegment {
+        let total_lines = document.lines.len();
+        let lines: Vec<String> = document.lines.into_iter().skip(start).take(limit).collect();
+        let end_line = start + lines.len();
+        let has_more = end_line < total_lines;
+
+        let highlighted = match &document.syntax_hint {
+            Some(hint) => {
+                self.highlighter
+                    .highlight(Path::new(hint), &lines, config.defaults.theme())
+            }
+            None => HighlightResult::plain(lines, config.defaults.theme().to_string()),
+        };
+
+        PreviewSegment {
+            path: path.to_path_buf(),
+            start_line: start + 1,
+            end_line,
+            truncated: has_more,
+            highlighted,
+            continuation: has_more.then_some(ContinuationToken {
+                start_line: end_line,
+            }),
+            notice: Some(document.notice),
+            total_lines,
+        }
+    }
+
+    /// Guess the file's text encoding from its first bytes; `None` means it looks binary.
+    fn sniff_encoding(&self, path: &Path) -> Result<Option<TextEncoding>> {
+        let mut file = self.vfs.open(path)?;
         let mut buf = [0u8; 1024];
         let read = file.read(&mut buf)?;
-        Ok(buf[..read].contains(&0))
+        Ok(TextEncoding::sniff(&buf[..read]))
+    }
+
+    /// Return the line index for `path`, rebuilding it when the file changed since last use.
+    fn line_index(&self, path: &Path) -> Result<Arc<LineIndex>> {
+        let metadata = self
+            .vfs
+            .metadata(path)
+            .with_context(|| format!("failed to stat {}", path.display()))?;
+        let mut indexes = self.line_indexes.lock().unwrap();
+        if let Some(index) = indexes.get(path)
+            && index.is_current(&metadata)
+        {
+            return Ok(Arc::clone(index));
+        }
+        let index = Arc::new(LineIndex::build(self.vfs.as_ref(), path, &metadata)?);
+        indexes.put(path.to_path_buf(), Arc::clone(&index));
+        Ok(index)
     }
 
     fn read_lines(
+        &self,
         path: &Path,
         start: usize,
         max_lines: usize,
-    ) -> Result<(Vec<String>, bool, bool)> {
-        let file =
-            File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
-        let mut reader = BufReader::new(file);
-        let mut raw = Vec::new();
-        let mut lines = Vec::new();
-        let mut lossy = false;
-        let mut reached_eof = false;
-        let mut index = 0;
-
-        while index < start + max_lines {
-            raw.clear();
-            let bytes = reader.read_until(b'\n', &mut raw)?;
-            if bytes == 0 {
-                reached_eof = true;
-                break;
-            }
+        encoding: TextEncoding,
+    ) -> Result<ReadLines> {
+        let index = self.line_index(path)?;
+        let total_lines = index.line_count();
+        let end = start.saturating_add(max_lines).min(total_lines);
+        if start >= end {
+            return Ok(ReadLines {
+                lines: Vec::new(),
+                lossy: false,
+                has_more: false,
+                total_lines,
+                fingerprint: ContentFingerprint::of_bytes(&[]),
+            });
+        }
 
-            if index >= start && lines.len() < max_lines {
-                if raw.ends_with(b"\n") {
-                    raw.pop();
-                    if raw.ends_with(b"\r") {
-                        raw.pop();
-                    }
-                }
-                let text = String::from_utf8_lossy(&raw);
-                if matches!(text, Cow::Owned(_)) {
+        let (from, to) = index.byte_range(start, end);
+        let mut file = self
+            .vfs
+            .open(path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        file.seek(SeekFrom::Start(from))?;
+        let mut raw = Vec::with_capacity((to - from) as usize);
+        file.take(to - from).read_to_end(&mut raw)?;
+        let fingerprint = ContentFingerprint::of_bytes(&raw);
+
+        let raw = raw.strip_suffix(b"\n").unwrap_or(&raw);
+        let raw = match start {
+            0 => raw.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(raw),
+            _ => raw,
+        };
+        let mut lossy = false;
+        let lines = raw
+            .split(|byte| *byte == b'\n')
+            .map(|line| {
+                let line = line.strip_suffix(b"\r").unwrap_or(line);
+                let text = encoding.decode(line);
+                if encoding == TextEncoding::Utf8 && matches!(text, Cow::Owned(_)) {
                     lossy = true;
                 }
-                lines.push(text.into_owned());
+                text.into_owned()
+            })
+            .collect();
+
+        Ok(ReadLines {
+            lines,
+            lossy,
+            has_more: end < total_lines,
+            total_lines,
+            fingerprint,
+        })
+    }
+}
+
+struct ReadLines {
+    lines: Vec<String>,
+    lossy: bool,
+    has_more: bool,
+    total_lines: usize,
+    /// Fingerprint of the raw bytes the lines were decoded from.
+    fingerprint: ContentFingerprint,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct ChunkKey {
+    path: PathBuf,
+    /// Zero-based first line of the chunk.
+    start: usize,
+    theme: String,
+}
+
+#[derive(Debug)]
+struct CachedChunk {
+    fingerprint: ContentFingerprint,
+    highlighted: HighlightResult,
+}
+
+/// Least recently used highlighted chunks, invalidated when a chunk's content fingerprint changes.
+#[derive(Debug)]
+struct HighlightCache {
+    chunks: LruCache<ChunkKey, CachedChunk>,
+    hits: u64,
+    misses: u64,
+}
+
+impl HighlightCache {
+    fn new() -> Self {
+        Self {
+            chunks: LruCache::new(
+                NonZeroUsize::new(HIGHLIGHT_CACHE_CHUNKS).expect("cache capacity is non-zero"),
+            ),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    fn get(&mut self, key: &ChunkKey, fingerprint: ContentFingerprint) -> Option<HighlightResult> {
+        match self.chunks.get(key) {
+            Some(chunk) if chunk.fingerprint == fingerprint => {
+                self.hits += 1;
+                Some(chunk.highlighted.clone())
+            }
+            _ => {
+                self.misses += 1;
+                None
             }
+        }
+    }
 
-            index += 1;
-            if lines.len() >= max_lines {
+    fn insert(
+        &mut self,
+        key: ChunkKey,
+        fingerprint: ContentFingerprint,
+        highlighted: HighlightResult,
+    ) {
+        self.chunks.put(
+            key,
+            CachedChunk {
+                fingerprint,
+                highlighted,
+            },
+        );
+    }
+
+    fn stats(&self) -> HighlightCacheStats {
+        HighlightCacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            entries: self.chunks.len(),
+        }
+    }
+}
+
+/// Byte offsets of every line start, so any range of lines is one seek away.
+#[derive(Debug)]
+struct LineIndex {
+    len: u64,
+    modified: Option<SystemTime>,
+    starts: Vec<u64>,
+}
+
+impl LineIndex {
+    fn build(vfs: &dyn Vfs, path: &Path, metadata: &VfsMetadata) -> Result<Self> {
+        let file = vfs
+            .open(path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        let len = metadata.len;
+        let mut reader = BufReader::with_capacity(INDEX_READ_BUFFER, file);
+
+        let mut starts = Vec::new();
+        if len > 0 {
+            starts.push(0);
+        }
+        let mut offset = 0u64;
+        loop {
+            let buf = reader.fill_buf()?;
+            if buf.is_empty() {
                 break;
             }
+            for position in memchr::memchr_iter(b'\n', buf) {
+                let next = offset + position as u64 + 1;
+                if next < len {
+                    starts.push(next);
+                }
+            }
+            let consumed = buf.len();
+            offset += consumed as u64;
+            reader.consume(consumed);
         }
 
-        let mut has_more = false;
-        if !reached_eof && lines.len() == max_lines {
-            let mut peek = [0u8; 1];
-            has_more = reader.read(&mut peek)? > 0;
-        }
+        Ok(Self {
+            len,
+            modified: metadata.modified,
+            starts,
+        })
+    }
 
-        Ok((lines, lossy, has_more))
+    fn is_current(&self, metadata: &VfsMetadata) -> bool {
+        self.len == metadata.len && self.modified == metadata.modified
+    }
+
+    /// Number of lines, counting a trailing unterminated line.
+    fn line_count(&self) -> usize {
+        self.starts.len()
+    }
+
+    /// Byte span covering the zero-based, end-exclusive line range `start..end`.
+    fn byte_range(&self, start: usize, end: usize) -> (u64, u64) {
+        let to = self.starts.get(end).copied().unwrap_or(self.len);
+        (self.starts[start], to)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::infra::fs::MemoryFs;
     use crate::infra::highlight::HighlightMode;
+    use std::fs::{self, File};
     use std::io::Write;
     use tempfile::tempdir;
 
@@ -220,11 +554,101 @@ mod tests {
 
         assert_eq!(segment.start_line, 101);
         assert_eq!(segment.end_line, 150);
+        assert_eq!(segment.total_lines, 500);
         assert!(segment.truncated);
         assert!(segment.continuation.is_some());
         Ok(())
     }
 
+    #[test]
+    fn deep_ranges_use_line_index() -> Result<()> {
+        let vfs = MemoryFs::new();
+        let file = Path::new("/repo/app.log");
+        let content: String = (1..=20_000).map(|i| format!("entry {i}\r\n")).collect();
+        vfs.write(file, content);
+
+        let service = PreviewService::new().with_vfs(Arc::new(vfs.clone()));
+        let segment = service.preview(file, Some(15_000..15_002), &config())?;
+        let text: Vec<String> = segment
+            .highlighted
+            .lines
+            .iter()
+            .map(|line| {
+                line.spans
+                    .iter()
+                    .map(|span| span.content.as_str())
+                    .collect()
+            })
+            .collect();
+        assert_eq!(text, vec!["entry 15001", "entry 15002"]);
+        assert_eq!(segment.total_lines, 20_000);
+
+        vfs.write(file, "replaced\nfile");
+        let segment = service.preview(file, None, &config())?;
+        assert_eq!(segment.total_lines, 2);
+        assert!(!segment.truncated);
+
+        vfs.remove(file);
+        let missing = service.preview(file, None, &config()).unwrap_err();
+        assert!(matches!(
+            missing.downcast_ref::<DomainError>(),
+            Some(DomainError::FileMissing { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn line_indexes_keep_only_recent_files() -> Result<()> {
+        let vfs = MemoryFs::new();
+        let service = PreviewService::new().with_vfs(Arc::new(vfs.clone()));
+        for i in 0..LINE_INDEX_CACHE_FILES + 4 {
+            let file = PathBuf::from(format!("/repo/{i}.log"));
+            vfs.write(&file, "one\ntwo\n");
+            service.preview(&file, None, &config())?;
+        }
+
+        let indexes = service.line_indexes.lock().unwrap();
+        assert_eq!(indexes.len(), LINE_INDEX_CACHE_FILES);
+        assert!(!indexes.contains(Path::new("/repo/0.log")));
+        assert!(indexes.contains(Path::new(&format!(
+            "/repo/{}.log",
+            LINE_INDEX_CACHE_FILES + 3
+        ))));
+        Ok(())
+    }
+
+    #[test]
+    fn repeated_chunks_reuse_cached_highlighting() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("big.rs");
+        let content: String = (0..5_000)
+            .map(|i| format!("fn item{i}() -> u32 {{ {i} }}\n"))
+            .collect();
+        std::fs::write(&file, &content)?;
+
+        let service = PreviewService::new();
+        let config = config();
+        let first = service.preview(&file, Some(0..2_000), &config)?;
+        let second = service.preview(&file, Some(0..2_000), &config)?;
+        assert_eq!(first.highlighted, second.highlighted);
+        assert_eq!(
+            service.highlight_cache_stats(),
+            HighlightCacheStats {
+                hits: 1,
+                misses: 1,
+                entries: 1
+            }
+        );
+
+        // Editing the chunk invalidates it even though the line count is unchanged.
+        std::fs::write(&file, content.replacen("item0", "renamed", 1))?;
+        let edited = service.preview(&file, Some(0..2_000), &config)?;
+        assert_eq!(edited.highlighted.lines[0].spans[2].content, "renamed");
+        let stats = service.highlight_cache_stats();
+        assert_eq!((stats.hits, stats.misses), (1, 2));
+        Ok(())
+    }
+
     #[test]
     fn binary_file_returns_notice() -> Result<()> {
         let dir = tempdir()?;
@@ -246,16 +670,44 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn notebook_preview_shows_extracted_cells() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("analysis.ipynb");
+        std::fs::write(
+            &file,
+            r#"{"metadata": {}, "cells": [
+                {"cell_type": "markdown", "source": "Notes"},
+                {"cell_type": "code", "source": ["x = 1\n", "x + 1"]}
+            ]}"#,
+        )?;
+
+        let service = PreviewService::new();
+        let segment = service.preview(&file, None, &config())?;
+
+        assert_eq!(segment.total_lines, 6);
+        assert_eq!(segment.highlighted.mode, HighlightMode::Highlighted);
+        assert!(
+            segment
+                .notice
+                .as_ref()
+                .is_some_and(|n| n.contains("2 cells"))
+        );
+        Ok(())
+    }
+
     #[test]
     fn lossy_content_falls_back_to_plain() -> Result<()> {
         let dir = tempdir()?;
         let file = dir.path().join("lossy.txt");
         let mut handle = File::create(&file)?;
+        // Invalid bytes past the sniffed head cannot change the guessed encoding.
+        handle.write_all("ok\n".repeat(600).as_bytes())?;
         handle.write_all(b"hello\xffworld\n")?;
         drop(handle);
 
         let service = PreviewService::new();
-        let segment = service.preview(&file, None, &config())?;
+        let segment = service.preview(&file, Some(600..601), &config())?;
 
         assert_eq!(segment.highlighted.mode, HighlightMode::Plain);
         assert!(
@@ -264,7 +716,49 @@ mod tests {
                 .as_ref()
                 .is_some_and(|n| n.contains("invalid UTF-8"))
         );
-        assert_eq!(segment.end_line, 1);
+        assert_eq!(segment.end_line, 601);
+        Ok(())
+    }
+
+    #[test]
+    fn decodes_latin1_and_utf16_files() -> Result<()> {
+        let dir = tempdir()?;
+        let latin1 = dir.path().join("latin1.txt");
+        fs::write(&latin1, b"caf\xe9\r\nbar\n")?;
+        let utf16 = dir.path().join("utf16.txt");
+        let bytes: Vec<u8> = [0xFF, 0xFE]
+            .into_iter()
+            .chain("one\r\ntwo\n".encode_utf16().flat_map(u16::to_le_bytes))
+            .collect();
+        fs::write(&utf16, bytes)?;
+
+        let service = PreviewService::new();
+        for (path, encoding, expected) in [
+            (&latin1, "windows-1252", "café"),
+            (&utf16, "UTF-16LE", "one"),
+        ] {
+            let segment = service.preview(path, None, &config())?;
+            let first: String = segment.highlighted.lines[0]
+                .spans
+                .iter()
+                .map(|span| span.content.as_str())
+                .collect();
+            assert_eq!(segment.total_lines, 2);
+            assert_eq!(first, expected);
+            assert_eq!(
+                segment
+                    .notice
+                    .as_ref()
+                    .is_some_and(|n| n.contains("may be wrong")),
+                path == &latin1
+            );
+            assert!(
+                segment
+                    .notice
+                    .as_ref()
+                    .is_some_and(|n| n.contains(encoding))
+            );
+        }
         Ok(())
     }
 }
@@ -0,0 +1,149 @@
+//! Expanding configured export recipes into selections and export options.
+
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow};
+use globset::{Glob, GlobSetBuilder};
+
+use crate::app::export::{ExportFormat, ExportOptions};
+use crate::app::scan::{ScanResult, Scanner, WorkspaceRoot};
+use crate::app::selection::SelectionSpec;
+use crate::domain::model::{SelectionItem, SelectionSource};
+use crate::infra::config::{Config, Recipe};
+
+/// Selections produced by a recipe, plus the specs that matched nothing.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecipeSelections {
+    pub items: Vec<SelectionItem>,
+    pub unmatched: Vec<String>,
+}
+
+impl RecipeSelections {
+    /// Expand the recipe's selection specs against `roots`.
+    ///
+    /// Plain paths resolve like `--select`; paths containing glob characters are matched against
+    /// the display paths of a workspace scan, so ignored, binary, and oversized files are left out.
+    pub fn resolve(recipe: &Recipe, roots: &[WorkspaceRoot], config: &Config) -> Result<Self> {
+        let mut selections = Self::default();
+        let mut scan: Option<ScanResult> = None;
+        for raw in &recipe.select {
+            let spec = SelectionSpec::parse(raw)
+                .map_err(|err| anyhow!("invalid recipe selection '{raw}': {err}"))?;
+            let pattern = spec.path.to_string_lossy();
+            if !pattern.contains(['*', '?', '[', '{']) {
+                let path = WorkspaceRoot::resolve(roots, &spec.path);
+                if path.is_file() {
+                    selections.items.push(SelectionItem {
+                        path,
+                        range: spec.range,
+                        note: spec.note,
+                        source: SelectionSource::Manual,
+                    });
+                } else {
+                    selections.unmatched.push(raw.clone());
+                }
+                continue;
+            }
+
+            let glob = GlobSetBuilder::new()
+                .add(Glob::new(&pattern).with_context(|| format!("invalid recipe glob '{raw}'"))?)
+                .build()?;
+            let scan = match &mut scan {
+                Some(scan) => scan,
+                None => scan.insert(Scanner::new().scan_roots(roots, config)?),
+            };
+            let before = selections.items.len();
+            for meta in &scan.files {
+                if meta.is_dir || meta.skipped.is_some() || !glob.is_match(&meta.display_path) {
+                    continue;
+                }
+                selections.items.push(SelectionItem {
+                    path: meta.path.clone(),
+                    range: spec.range,
+                    note: spec.note.clone(),
+                    source: SelectionSource::Glob,
+                });
+            }
+            if selections.items.len() == before {
+                selections.unmatched.push(raw.clone());
+            }
+        }
+        Ok(selections)
+    }
+}
+
+/// Apply the recipe's template, format, and output path on top of `options`.
+///
+/// Relative output paths are anchored at `root`, so recipes behave the same from any directory.
+/// Recipes regenerate their output, so an existing file there is replaced.
+pub fn apply_recipe_options(
+    recipe: &Recipe,
+    options: &mut ExportOptions,
+    root: &Path,
+) -> Result<()> {
+    if let Some(format) = &recipe.format {
+        options.format = format.parse::<ExportFormat>()?;
+    }
+    if let Some(template) = &recipe.template {
+        options.template = template.clone();
+    }
+    if let Some(output) = &recipe.output {
+        options.output_path = Some(root.join(output));
+    }
+    options.overwrite = true;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn specs_and_globs_expand_to_selections() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = temp.path().canonicalize()?;
+        fs::create_dir_all(root.join("src/api"))?;
+        fs::write(root.join("src/api/users.rs"), "fn users() {}\n")?;
+        fs::write(root.join("src/api/orders.rs"), "fn orders() {}\n")?;
+        fs::write(root.join("schema.sql"), "create table t();\n")?;
+
+        let recipe = Recipe {
+            select: vec![
+                "schema.sql:1-1#tables".to_string(),
+                "src/api/*.rs".to_string(),
+                "docs/*.md".to_string(),
+                "missing.rs".to_string(),
+            ],
+            format: Some("plain".to_string()),
+            output: Some("out/review.txt".into()),
+            ..Recipe::default()
+        };
+        let roots = WorkspaceRoot::from_paths(std::slice::from_ref(&root));
+        let selections = RecipeSelections::resolve(&recipe, &roots, &Config::default())?;
+
+        let mut paths: Vec<_> = selections
+            .items
+            .iter()
+            .map(|item| item.path.strip_prefix(&root).unwrap().to_path_buf())
+            .collect();
+        paths[1..].sort();
+        assert_eq!(
+            paths,
+            vec![
+                Path::new("schema.sql"),
+                Path::new("src/api/orders.rs"),
+                Path::new("src/api/users.rs")
+            ]
+        );
+        assert_eq!(selections.items[0].range, Some((1, 1)));
+        assert_eq!(selections.items[0].note.as_deref(), Some("tables"));
+        assert_eq!(selections.unmatched, vec!["docs/*.md", "missing.rs"]);
+
+        let mut options = ExportOptions::from_config(&Config::default());
+        apply_recipe_options(&recipe, &mut options, &root)?;
+        assert_eq!(options.format, ExportFormat::Plain);
+        assert_eq!(options.output_path, Some(root.join("out/review.txt")));
+        Ok(())
+    }
+}
@@ -0,0 +1,104 @@
+//! Turn a pull request into selections for review context.
+
+use std::path::Path;
+
+use crate::domain::model::{SelectionItem, SelectionSource};
+use crate::infra::github::PullRequest;
+
+/// Selections built from a pull request, plus the files that could not be used.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReviewSelections {
+    pub items: Vec<SelectionItem>,
+    /// Changed files missing from the local checkout (e.g. the PR branch is not checked out).
+    pub missing: Vec<String>,
+}
+
+impl ReviewSelections {
+    /// One selection per diff hunk of each changed file under `root`.
+    ///
+    /// Deleted files are skipped and files without a textual patch are selected whole. The first
+    /// selection carries the PR title and description; the rest are tagged with the title.
+    pub fn from_pull_request(pull: &PullRequest, root: &Path) -> Self {
+        let heading = format!("PR #{}: {}", pull.number, pull.title);
+        let mut selections = Self::default();
+        for file in pull.files.iter().filter(|file| file.status != "removed") {
+            let path = root.join(&file.path);
+            if !path.is_file() {
+                selections.missing.push(file.path.clone());
+                continue;
+            }
+            let ranges = file.hunk_ranges();
+            let ranges = if ranges.is_empty() && file.patch.is_none() {
+                vec![None]
+            } else {
+                ranges.into_iter().map(Some).collect()
+            };
+            for range in ranges {
+                let note = match (&pull.body, selections.items.is_empty()) {
+                    (Some(body), true) => format!("{heading}\n\n{}", body.trim()),
+                    _ => heading.clone(),
+                };
+                selections.items.push(SelectionItem {
+                    path: path.clone(),
+                    range,
+                    note: Some(note),
+                    source: SelectionSource::Diff,
+                });
+            }
+        }
+        selections
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infra::github::PullRequestFile;
+    use anyhow::Result;
+    use std::fs;
+
+    fn file(path: &str, status: &str, patch: Option<&str>) -> PullRequestFile {
+        PullRequestFile {
+            path: path.to_string(),
+            status: status.to_string(),
+            patch: patch.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn hunks_become_ranged_selections() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        fs::create_dir(temp.path().join("src"))?;
+        fs::write(temp.path().join("src/lib.rs"), "fn a() {}\n")?;
+        fs::write(temp.path().join("logo.png"), [0u8; 4])?;
+
+        let pull = PullRequest {
+            number: 9,
+            title: "Tidy lib".to_string(),
+            body: Some("Why this matters.".to_string()),
+            url: "https://github.com/owner/repo/pull/9".to_string(),
+            head_sha: "abc".to_string(),
+            files: vec![
+                file(
+                    "src/lib.rs",
+                    "modified",
+                    Some("@@ -1,2 +1,3 @@\n@@ -10 +11,2 @@\n"),
+                ),
+                file("logo.png", "added", None),
+                file("old.rs", "removed", Some("@@ -1,2 +0,0 @@\n")),
+                file("src/new.rs", "added", Some("@@ -0,0 +1,5 @@\n")),
+            ],
+        };
+
+        let selections = ReviewSelections::from_pull_request(&pull, temp.path());
+        let ranges: Vec<_> = selections.items.iter().map(|item| item.range).collect();
+        assert_eq!(ranges, vec![Some((1, 3)), Some((11, 12)), None]);
+        assert_eq!(
+            selections.items[0].note.as_deref(),
+            Some("PR #9: Tidy lib\n\nWhy this matters.")
+        );
+        assert_eq!(selections.items[2].note.as_deref(), Some("PR #9: Tidy lib"));
+        assert_eq!(selections.missing, vec!["src/new.rs".to_string()]);
+        Ok(())
+    }
+}
@@ -1,16 +1,21 @@
 //! Repository scanning services.
 
-use std::fs::File;
-use std::io::{BufRead, BufReader, Read};
-use std::path::{Path, PathBuf};
+use std::collections::{BTreeMap, HashSet};
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, anyhow};
 use globset::{Glob, GlobSet, GlobSetBuilder};
-use ignore::{DirEntry, WalkBuilder, WalkState};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::{DirEntry, Match, WalkBuilder, WalkState};
 use time::OffsetDateTime;
 
+use crate::app::cancel::CancellationToken;
 use crate::infra::config::Config;
+use crate::infra::fs::{RealFs, TextEncoding, Vfs, VfsMetadata};
+use crate::infra::git;
 
 const LLMCTX_IGNORE: &str = ".llmctxignore";
 
@@ -24,6 +29,7 @@ pub struct FileMetadata {
     pub modified: Option<OffsetDateTime>,
     pub language: Option<String>,
     pub skipped: Option<SkipReason>,
+    pub class: FileClass,
 }
 
 /// Reason for excluding or marking a file as skipped.
@@ -33,11 +39,347 @@ pub enum SkipReason {
     BinaryFile,
 }
 
+/// What a file holds, guessed from its workspace path and first bytes.
+///
+/// The tree dims everything but source, and `export --no-tests`, `--no-generated`, and
+/// `--no-vendored` leave those files out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum FileClass {
+    #[default]
+    Source,
+    /// Test code or test data, e.g. under `tests/` or named `*_test.go` or `*.spec.ts`.
+    Test,
+    /// Produced by a tool: protobuf output, lockfiles, minified bundles, or files marked
+    /// `@generated` or `DO NOT EDIT` near the top.
+    Generated,
+    /// Third-party code checked in under `vendor/`, `third_party/`, or `node_modules/`.
+    Vendored,
+}
+
+const TEST_DIRS: &[&str] = &[
+    "test",
+    "tests",
+    "__tests__",
+    "spec",
+    "specs",
+    "testdata",
+    "test_data",
+    "fixtures",
+];
+const VENDORED_DIRS: &[&str] = &[
+    "vendor",
+    "vendored",
+    "third_party",
+    "third-party",
+    "thirdparty",
+    "node_modules",
+    "bower_components",
+];
+const GENERATED_DIRS: &[&str] = &["generated", "__generated__"];
+const GENERATED_SUFFIXES: &[&str] = &[
+    ".pb.go",
+    ".pb.cc",
+    ".pb.h",
+    "_pb2.py",
+    "_pb2_grpc.py",
+    "_pb2.pyi",
+    ".g.dart",
+    ".freezed.dart",
+    ".min.js",
+    ".min.css",
+    ".designer.cs",
+];
+const LOCKFILES: &[&str] = &[
+    "Cargo.lock",
+    "package-lock.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "poetry.lock",
+    "Gemfile.lock",
+    "composer.lock",
+    "go.sum",
+];
+/// Lowercase phrases that mark generated files when they appear near the top.
+const GENERATED_MARKERS: &[&str] = &[
+    "@generated",
+    "do not edit",
+    "code generated by",
+    "auto-generated",
+    "autogenerated",
+    "this file was generated",
+    "this file is generated",
+];
+/// Lines at the top of a file searched for [`GENERATED_MARKERS`].
+const MARKER_LINES: usize = 10;
+
+impl FileClass {
+    /// Tree and summary tag; `None` for source files.
+    pub fn label(self) -> Option<&'static str> {
+        match self {
+            Self::Source => None,
+            Self::Test => Some("test"),
+            Self::Generated => Some("generated"),
+            Self::Vendored => Some("vendored"),
+        }
+    }
+
+    /// Classify the file at `relative`, a path within its workspace root, whose contents start
+    /// with `head`. Vendored beats generated, which beats test.
+    pub fn detect(relative: &Path, head: &[u8]) -> Self {
+        let dirs: Vec<&str> = relative
+            .parent()
+            .into_iter()
+            .flat_map(Path::components)
+            .filter_map(|component| component.as_os_str().to_str())
+            .collect();
+        let name = relative
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default();
+        if dirs.iter().any(|dir| VENDORED_DIRS.contains(dir)) {
+            return Self::Vendored;
+        }
+        if dirs.iter().any(|dir| GENERATED_DIRS.contains(dir))
+            || LOCKFILES.contains(&name)
+            || GENERATED_SUFFIXES
+                .iter()
+                .any(|suffix| name.ends_with(suffix))
+            || name.contains(".generated.")
+            || has_generated_marker(head)
+        {
+            return Self::Generated;
+        }
+        if dirs.iter().any(|dir| TEST_DIRS.contains(dir)) || is_test_file_name(name) {
+            return Self::Test;
+        }
+        Self::Source
+    }
+
+    /// Classify the file at `path` in `vfs`, judging its location relative to whichever of
+    /// `roots` contains it so that a checkout under `~/tests/` is not all tests.
+    pub fn of_file(vfs: &dyn Vfs, path: &Path, roots: &[WorkspaceRoot]) -> Self {
+        let relative = roots
+            .iter()
+            .find_map(|root| path.strip_prefix(&root.path).ok())
+            .unwrap_or(path);
+        Self::detect(relative, &read_head(vfs, path))
+    }
+}
+
+fn is_test_file_name(name: &str) -> bool {
+    let stem = name.split('.').next().unwrap_or_default();
+    let script_test = [".test.", ".spec."]
+        .iter()
+        .any(|marker| name.contains(marker));
+    script_test
+        || name == "conftest.py"
+        || (name.ends_with(".py") && (stem.starts_with("test_") || stem.ends_with("_test")))
+        || name.ends_with("_test.go")
+        || name.ends_with("_spec.rb")
+        || ["Test", "Tests"].iter().any(|suffix| {
+            stem.ends_with(suffix)
+                && [".java", ".kt", ".cs", ".scala"]
+                    .iter()
+                    .any(|ext| name.ends_with(ext))
+        })
+}
+
+fn has_generated_marker(head: &[u8]) -> bool {
+    String::from_utf8_lossy(head)
+        .lines()
+        .take(MARKER_LINES)
+        .any(|line| {
+            let line = line.to_lowercase();
+            GENERATED_MARKERS.iter().any(|marker| line.contains(marker))
+        })
+}
+
+/// Entries a scan left out, by the rule that excluded them.
+///
+/// An excluded directory counts once; its contents are not walked.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IgnoredCounts {
+    /// `ignore.paths`, `ignore.globs`, and `.llmctxignore` patterns.
+    pub ignore_rules: usize,
+    /// `.gitignore`, `.ignore`, and git exclude files.
+    pub gitignore: usize,
+    /// Dotfiles, hidden unless `defaults.show_hidden` is set.
+    pub hidden: usize,
+    /// Submodules and nested clones, skipped unless `defaults.include_submodules` is set.
+    pub nested_repositories: usize,
+}
+
+impl IgnoredCounts {
+    pub fn total(&self) -> usize {
+        self.ignore_rules + self.gitignore + self.hidden + self.nested_repositories
+    }
+}
+
+/// Safeguard that cut a scan short.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanLimit {
+    /// `defaults.max_scan_files` entries were collected.
+    Files(usize),
+    /// Files totalling more than `defaults.max_scan_bytes` were collected.
+    Bytes(u64),
+}
+
+impl ScanLimit {
+    /// Status line explaining the truncated scan and how to avoid it.
+    pub fn describe(&self) -> String {
+        let reached = match self {
+            Self::Files(limit) => format!("{limit} files"),
+            Self::Bytes(limit) => format!("{} MiB", limit / (1024 * 1024)),
+        };
+        format!(
+            "Scan stopped after {reached}; the tree is incomplete. Narrow it with --root or add ignores"
+        )
+    }
+}
+
 /// Result of scanning a repository root.
 #[derive(Debug, Default)]
 pub struct ScanResult {
     pub files: Vec<FileMetadata>,
     pub root: PathBuf,
+    /// Set when a scan limit was hit before the walk finished.
+    pub truncated: Option<ScanLimit>,
+}
+
+impl ScanResult {
+    /// Merge per-root scans into one tree, nesting labelled roots under a directory entry.
+    ///
+    /// The combined `root` is the first root, which anchors session and export state.
+    pub fn combine(roots: &[WorkspaceRoot], results: Vec<ScanResult>) -> ScanResult {
+        let mut files = Vec::new();
+        let mut truncated = None;
+        for (root, result) in roots.iter().zip(results) {
+            truncated = truncated.or(result.truncated);
+            if let Some(label) = &root.label {
+                files.push(FileMetadata {
+                    path: root.path.clone(),
+                    display_path: label.clone(),
+                    is_dir: true,
+                    size: None,
+                    modified: None,
+                    language: None,
+                    skipped: None,
+                    class: FileClass::Source,
+                });
+            }
+            files.extend(result.files.into_iter().map(|mut meta| {
+                if let Some(display_path) = root.display_path(&meta.path) {
+                    meta.display_path = display_path;
+                }
+                meta
+            }));
+        }
+        files.sort_by(|a, b| a.display_path.cmp(&b.display_path));
+
+        ScanResult {
+            files,
+            root: roots
+                .first()
+                .map(|root| root.path.clone())
+                .unwrap_or_default(),
+            truncated,
+        }
+    }
+}
+
+/// A directory scanned into the workspace.
+///
+/// With several roots, each root's display paths are prefixed with its `label` so files from
+/// different roots stay distinguishable in one tree or bundle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkspaceRoot {
+    pub path: PathBuf,
+    pub label: Option<String>,
+}
+
+impl WorkspaceRoot {
+    /// Label `paths` by directory name, de-duplicating clashes; a single root gets no label.
+    pub fn from_paths(paths: &[PathBuf]) -> Vec<Self> {
+        if paths.len() == 1 {
+            return vec![Self {
+                path: paths[0].clone(),
+                label: None,
+            }];
+        }
+
+        let mut used: HashSet<String> = HashSet::new();
+        paths
+            .iter()
+            .map(|path| {
+                let base = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| "root".to_string());
+                let mut label = base.clone();
+                let mut suffix = 2;
+                while !used.insert(label.clone()) {
+                    label = format!("{base}-{suffix}");
+                    suffix += 1;
+                }
+                Self {
+                    path: path.clone(),
+                    label: Some(label),
+                }
+            })
+            .collect()
+    }
+
+    /// Display path of `path` within this root, or `None` if it lies outside it.
+    pub fn display_path(&self, path: &Path) -> Option<String> {
+        let relative = slash_path(path.strip_prefix(&self.path).ok()?);
+        Some(match &self.label {
+            Some(label) => format!("{label}/{relative}"),
+            None => relative,
+        })
+    }
+
+    /// Resolve a user-supplied relative path (optionally `label/`-prefixed) against `roots`.
+    ///
+    /// Falls back to the path unchanged when no root contains it.
+    pub fn resolve(roots: &[Self], path: &Path) -> PathBuf {
+        if path.is_absolute() {
+            return path.to_path_buf();
+        }
+        for root in roots {
+            if let Some(label) = &root.label
+                && let Ok(rest) = path.strip_prefix(label)
+            {
+                return root.path.join(native_path(rest));
+            }
+        }
+        let path = native_path(path);
+        roots
+            .iter()
+            .map(|root| root.path.join(&path))
+            .find(|candidate| candidate.exists())
+            .unwrap_or(path)
+    }
+}
+
+/// `path` with `/` between components on every platform, as display paths, exports, and saved
+/// sessions write it.
+///
+/// Only Windows treats `\` as a separator; elsewhere it is a legal file name character and kept.
+pub fn slash_path(path: &Path) -> String {
+    let display = path.display().to_string();
+    if cfg!(windows) {
+        display.replace('\\', "/")
+    } else {
+        display
+    }
+}
+
+/// `path` rebuilt with this platform's separator, for joining a [`slash_path`] read back from
+/// a session or typed by the user onto a root.
+pub fn native_path(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|component| *component != Component::CurDir)
+        .collect()
 }
 
 /// Configuration inputs for the scanner.
@@ -64,28 +406,119 @@ impl ScannerConfig {
 }
 
 /// Scanner walking the repository respecting ignore rules and producing metadata.
-#[derive(Debug, Default)]
-pub struct Scanner;
+#[derive(Debug)]
+pub struct Scanner {
+    cancel: CancellationToken,
+    vfs: Arc<dyn Vfs>,
+}
+
+impl Default for Scanner {
+    fn default() -> Self {
+        Self {
+            cancel: CancellationToken::default(),
+            vfs: Arc::new(RealFs),
+        }
+    }
+}
 
 impl Scanner {
     pub fn new() -> Self {
-        Self
+        Self::default()
+    }
+
+    /// Stop walking once `cancel` is cancelled; scans then fail with
+    /// [`crate::domain::errors::DomainError::Cancelled`].
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Walk and read `vfs` instead of the local disk.
+    ///
+    /// Only local file systems honour `.gitignore` files and detect nested repositories;
+    /// other sources apply the configured ignore rules and `.llmctxignore` alone.
+    pub fn with_vfs(mut self, vfs: Arc<dyn Vfs>) -> Self {
+        self.vfs = vfs;
+        self
+    }
+
+    /// Scan each of `roots` with `config` and combine the results like the TUI's file tree.
+    pub fn scan_roots(&self, roots: &[WorkspaceRoot], config: &Config) -> Result<ScanResult> {
+        let results = roots
+            .iter()
+            .map(|root| {
+                self.scan(&ScannerConfig::from_root(root.path.clone(), config.clone()))
+                    .with_context(|| format!("failed to scan {}", root.path.display()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(ScanResult::combine(roots, results))
+    }
+
+    /// The rules a scan of `roots` with `config` excludes files by, to check single paths
+    /// without walking the tree.
+    pub fn ignore_rules(&self, roots: &[WorkspaceRoot], config: &Config) -> Result<IgnoreRules> {
+        let roots = roots
+            .iter()
+            .map(|root| {
+                let cfg = ScannerConfig::from_root(root.path.clone(), config.clone());
+                Ok(RootIgnoreRules {
+                    matcher: build_ignore_matcher(self.vfs.as_ref(), &root.path, &cfg)?,
+                    root: root.path.clone(),
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(IgnoreRules {
+            roots,
+            gitignore: self.vfs.is_local(),
+        })
     }
 
     pub fn scan(&self, cfg: &ScannerConfig) -> Result<ScanResult> {
-        let matcher = Arc::new(build_ignore_matcher(&cfg.root, cfg)?);
+        let matcher = build_ignore_matcher(self.vfs.as_ref(), &cfg.root, cfg)?;
+        let (mut files, truncated) = if self.vfs.is_local() {
+            self.walk_local(cfg, Arc::new(matcher))
+        } else {
+            self.walk_vfs(cfg, &matcher)
+        };
+        self.cancel.check()?;
+
+        files.sort_by(|a, b| a.display_path.cmp(&b.display_path));
+        if let Some(limit) = truncated {
+            tracing::warn!(root = %cfg.root.display(), ?limit, "scan limit reached");
+        }
+
+        Ok(ScanResult {
+            files,
+            root: cfg.root.clone(),
+            truncated,
+        })
+    }
+
+    /// Walk the local disk in parallel, honouring gitignore files.
+    fn walk_local(
+        &self,
+        cfg: &ScannerConfig,
+        matcher: Arc<IgnoreMatcher>,
+    ) -> (Vec<FileMetadata>, Option<ScanLimit>) {
         let mut builder = WalkBuilder::new(&cfg.root);
         builder
             .git_ignore(true)
             .hidden(!cfg.config.defaults.show_hidden());
 
         let root = cfg.root.clone();
+        let include_submodules = cfg.config.defaults.include_submodules();
         builder.filter_entry({
             let matcher = matcher.clone();
             move |entry| {
                 if entry.depth() == 0 {
                     return true;
                 }
+                if !include_submodules
+                    && entry.file_type().is_some_and(|kind| kind.is_dir())
+                    && git::is_nested_repository(entry.path())
+                {
+                    return false;
+                }
                 let rel = entry.path().strip_prefix(&root).unwrap_or(entry.path());
                 !matcher.should_skip(rel)
             }
@@ -93,18 +526,35 @@ impl Scanner {
 
         let files = Mutex::new(Vec::new());
         let cfg_ref = Arc::new(cfg.clone());
+        let limits = ScanLimits::new(&cfg.config);
+        let truncated = Mutex::new(None);
 
         builder.build_parallel().run(|| {
             let files = &files;
+            let (limits, truncated) = (&limits, &truncated);
             let cfg = cfg_ref.clone();
+            let cancel = &self.cancel;
+            let vfs = self.vfs.as_ref();
             Box::new(move |result| match result {
+                _ if cancel.is_cancelled() => WalkState::Quit,
                 Ok(entry) => {
-                    if let Some(meta) = process_entry(&entry, &cfg)
-                        && let Ok(mut guard) = files.lock()
-                    {
+                    let Some(meta) = process_entry(vfs, &entry, &cfg) else {
+                        return WalkState::Continue;
+                    };
+                    let size = meta.size.unwrap_or(0);
+                    if let Ok(mut guard) = files.lock() {
                         guard.push(meta);
                     }
-                    WalkState::Continue
+
+                    match limits.count(size) {
+                        Some(limit) => {
+                            if let Ok(mut guard) = truncated.lock() {
+                                guard.get_or_insert(limit);
+                            }
+                            WalkState::Quit
+                        }
+                        None => WalkState::Continue,
+                    }
                 }
                 Err(err) => {
                     tracing::warn!(error = %err, "scanner error");
@@ -113,38 +563,276 @@ impl Scanner {
             })
         });
 
-        let mut files = files.into_inner().unwrap_or_default();
-        files.sort_by(|a, b| a.display_path.cmp(&b.display_path));
+        (
+            files.into_inner().unwrap_or_default(),
+            truncated.into_inner().unwrap_or_default(),
+        )
+    }
+
+    /// Walk a file system that is not the local disk, one directory at a time.
+    fn walk_vfs(
+        &self,
+        cfg: &ScannerConfig,
+        matcher: &IgnoreMatcher,
+    ) -> (Vec<FileMetadata>, Option<ScanLimit>) {
+        let show_hidden = cfg.config.defaults.show_hidden();
+        let limits = ScanLimits::new(&cfg.config);
+        let mut files = Vec::new();
+        let mut pending = vec![cfg.root.clone()];
+        while let Some(dir) = pending.pop() {
+            let entries = match self.vfs.read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(err) => {
+                    tracing::warn!(path = %dir.display(), error = %err, "scanner error");
+                    continue;
+                }
+            };
+            for path in entries {
+                if self.cancel.is_cancelled() {
+                    return (files, None);
+                }
+                let rel = path.strip_prefix(&cfg.root).unwrap_or(&path);
+                if matcher.should_skip(rel) || (!show_hidden && is_hidden(&path)) {
+                    continue;
+                }
+                let Ok(metadata) = self.vfs.metadata(&path) else {
+                    continue;
+                };
+                if metadata.is_dir {
+                    pending.push(path.clone());
+                }
+                let meta = describe_path(self.vfs.as_ref(), &path, &metadata, cfg);
+                let size = meta.size.unwrap_or(0);
+                files.push(meta);
+                if let Some(limit) = limits.count(size) {
+                    return (files, Some(limit));
+                }
+            }
+        }
+        (files, None)
+    }
+
+    /// Count the entries beneath `roots` that `scan` left out, by the rule that excluded them.
+    ///
+    /// Only the scanned tree and the excluded entries directly inside it are visited. Exclusions
+    /// by ignore files are inferred from entries the other rules do not explain, so the counts
+    /// are only meaningful for a scan that was not truncated.
+    pub fn ignored_entries(
+        &self,
+        roots: &[WorkspaceRoot],
+        config: &Config,
+        scan: &ScanResult,
+    ) -> Result<IgnoredCounts> {
+        let scanned: Arc<HashSet<PathBuf>> =
+            Arc::new(scan.files.iter().map(|meta| meta.path.clone()).collect());
+        let counts = Arc::new(Mutex::new(IgnoredCounts::default()));
+        for root in roots {
+            let cfg = ScannerConfig::from_root(root.path.clone(), config.clone());
+            let matcher = build_ignore_matcher(self.vfs.as_ref(), &cfg.root, &cfg)?;
+            let show_hidden = config.defaults.show_hidden();
+            if !self.vfs.is_local() {
+                let mut counts = counts.lock().unwrap();
+                self.count_ignored_vfs(&root.path, &matcher, show_hidden, &scanned, &mut counts)?;
+                continue;
+            }
+            let include_submodules = config.defaults.include_submodules();
+            let (scanned, counts) = (scanned.clone(), counts.clone());
+            let root_path = root.path.clone();
+
+            let mut builder = WalkBuilder::new(&root.path);
+            builder.standard_filters(false).filter_entry(move |entry| {
+                if entry.depth() == 0 || scanned.contains(entry.path()) {
+                    return true;
+                }
+                if entry.file_name() == ".git" {
+                    return false;
+                }
+                let Ok(mut counts) = counts.lock() else {
+                    return false;
+                };
+                let rel = entry
+                    .path()
+                    .strip_prefix(&root_path)
+                    .unwrap_or(entry.path());
+                let is_dir = entry.file_type().is_some_and(|kind| kind.is_dir());
+                if is_dir && !include_submodules && git::is_nested_repository(entry.path()) {
+                    counts.nested_repositories += 1;
+                } else if matcher.should_skip(rel) {
+                    counts.ignore_rules += 1;
+                } else if !show_hidden && entry.file_name().to_string_lossy().starts_with('.') {
+                    counts.hidden += 1;
+                } else {
+                    counts.gitignore += 1;
+                }
+                false
+            });
+            for entry in builder.build() {
+                self.cancel.check()?;
+                if let Err(err) = entry {
+                    tracing::debug!(error = %err, "ignored entry walk error");
+                }
+            }
+        }
+        let counts = *counts.lock().unwrap();
+        Ok(counts)
+    }
+
+    /// Count what [`Self::walk_vfs`] left out beneath `root`: only ignore rules and hidden
+    /// files exclude entries there.
+    fn count_ignored_vfs(
+        &self,
+        root: &Path,
+        matcher: &IgnoreMatcher,
+        show_hidden: bool,
+        scanned: &HashSet<PathBuf>,
+        counts: &mut IgnoredCounts,
+    ) -> Result<()> {
+        let mut pending = vec![root.to_path_buf()];
+        while let Some(dir) = pending.pop() {
+            self.cancel.check()?;
+            let Ok(entries) = self.vfs.read_dir(&dir) else {
+                continue;
+            };
+            for path in entries {
+                if scanned.contains(&path) {
+                    if self
+                        .vfs
+                        .metadata(&path)
+                        .is_ok_and(|metadata| metadata.is_dir)
+                    {
+                        pending.push(path);
+                    }
+                } else if matcher.should_skip(path.strip_prefix(root).unwrap_or(&path)) {
+                    counts.ignore_rules += 1;
+                } else if !show_hidden && is_hidden(&path) {
+                    counts.hidden += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Describe an explicit list of files (plus their ancestor directories) without walking.
+    ///
+    /// Ignore rules and size limits do not apply: the caller asked for these files by name.
+    pub fn scan_paths(&self, cfg: &ScannerConfig, paths: &[PathBuf]) -> Result<ScanResult> {
+        let mut entries: BTreeMap<String, FileMetadata> = BTreeMap::new();
+        for path in paths {
+            self.cancel.check()?;
+            let path = if path.is_relative() {
+                cfg.root.join(path)
+            } else {
+                path.clone()
+            };
+            let metadata = self
+                .vfs
+                .metadata(&path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            if !metadata.is_file() {
+                return Err(anyhow!("{} is not a file", path.display()));
+            }
+
+            let mut meta = describe_path(self.vfs.as_ref(), &path, &metadata, cfg);
+            meta.skipped = None;
+            entries.insert(meta.display_path.clone(), meta);
+
+            for ancestor in path.ancestors().skip(1) {
+                if ancestor == cfg.root || !ancestor.starts_with(&cfg.root) {
+                    break;
+                }
+                let display_path = to_display_path(&cfg.root, ancestor);
+                if entries.contains_key(&display_path) {
+                    break;
+                }
+                let metadata = self.vfs.metadata(ancestor)?;
+                entries.insert(
+                    display_path,
+                    describe_path(self.vfs.as_ref(), ancestor, &metadata, cfg),
+                );
+            }
+        }
 
         Ok(ScanResult {
-            files,
+            files: entries.into_values().collect(),
             root: cfg.root.clone(),
+            truncated: None,
         })
     }
 }
 
-fn process_entry(entry: &DirEntry, cfg: &ScannerConfig) -> Option<FileMetadata> {
+/// Running totals checked against `defaults.max_scan_files` and `defaults.max_scan_bytes`.
+struct ScanLimits {
+    max_files: usize,
+    max_bytes: u64,
+    files: AtomicUsize,
+    bytes: AtomicU64,
+}
+
+impl ScanLimits {
+    fn new(config: &Config) -> Self {
+        Self {
+            max_files: config.defaults.max_scan_files(),
+            max_bytes: config.defaults.max_scan_bytes(),
+            files: AtomicUsize::new(0),
+            bytes: AtomicU64::new(0),
+        }
+    }
+
+    /// Count one more entry of `size` bytes, returning the limit that reached, if any.
+    fn count(&self, size: u64) -> Option<ScanLimit> {
+        let files = self.files.fetch_add(1, Ordering::Relaxed) + 1;
+        let bytes = self.bytes.fetch_add(size, Ordering::Relaxed) + size;
+        if self.max_files > 0 && files >= self.max_files {
+            Some(ScanLimit::Files(self.max_files))
+        } else if self.max_bytes > 0 && bytes >= self.max_bytes {
+            Some(ScanLimit::Bytes(self.max_bytes))
+        } else {
+            None
+        }
+    }
+}
+
+fn process_entry(vfs: &dyn Vfs, entry: &DirEntry, cfg: &ScannerConfig) -> Option<FileMetadata> {
     let path = entry.path();
     if path == cfg.root {
         return None;
     }
 
     let metadata = entry.metadata().ok()?;
-    let is_dir = metadata.is_dir();
-    let file_size = metadata.is_file().then_some(metadata.len());
+    Some(describe_path(vfs, path, &VfsMetadata::from(&metadata), cfg))
+}
+
+fn describe_path(
+    vfs: &dyn Vfs,
+    path: &Path,
+    metadata: &VfsMetadata,
+    cfg: &ScannerConfig,
+) -> FileMetadata {
+    let is_dir = metadata.is_dir;
+    let file_size = metadata.is_file().then_some(metadata.len);
 
+    let head = if file_size.is_some() {
+        read_head(vfs, path)
+    } else {
+        Vec::new()
+    };
     let mut skipped = None;
     if let Some(size) = file_size {
         if size > cfg.max_file_size {
             skipped = Some(SkipReason::LargeFile);
-        } else if is_probably_binary(path) {
+        } else if is_probably_binary(&head) {
             skipped = Some(SkipReason::BinaryFile);
         }
     }
+    let class = if is_dir {
+        FileClass::Source
+    } else {
+        FileClass::detect(path.strip_prefix(&cfg.root).unwrap_or(path), &head)
+    };
 
-    let modified = metadata.modified().ok().map(OffsetDateTime::from);
+    let modified = metadata.modified.map(OffsetDateTime::from);
 
-    Some(FileMetadata {
+    FileMetadata {
         path: path.to_path_buf(),
         display_path: to_display_path(&cfg.root, path),
         is_dir,
@@ -152,14 +840,12 @@ fn process_entry(entry: &DirEntry, cfg: &ScannerConfig) -> Option<FileMetadata>
         modified,
         language: if is_dir { None } else { guess_language(path) },
         skipped,
-    })
+        class,
+    }
 }
 
 fn to_display_path(root: &Path, path: &Path) -> String {
-    path.strip_prefix(root)
-        .unwrap_or(path)
-        .display()
-        .to_string()
+    slash_path(path.strip_prefix(root).unwrap_or(path))
 }
 
 fn guess_language(path: &Path) -> Option<String> {
@@ -168,19 +854,106 @@ fn guess_language(path: &Path) -> Option<String> {
         .map(|ext| ext.to_lowercase())
 }
 
-fn is_probably_binary(path: &Path) -> bool {
-    let mut file = match File::open(path) {
-        Ok(file) => file,
-        Err(_) => return false,
+/// Up to the first KiB of the file at `path`; empty when it cannot be read.
+pub(crate) fn read_head(vfs: &dyn Vfs, path: &Path) -> Vec<u8> {
+    let Ok(mut file) = vfs.open(path) else {
+        return Vec::new();
     };
     let mut buf = [0u8; 1024];
     match file.read(&mut buf) {
-        Ok(0) => false,
-        Ok(n) => {
-            let slice = &buf[..n];
-            slice.contains(&0) || std::str::from_utf8(slice).is_err()
+        Ok(n) => buf[..n].to_vec(),
+        Err(_) => Vec::new(),
+    }
+}
+
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+}
+
+pub(crate) fn is_probably_binary(head: &[u8]) -> bool {
+    TextEncoding::sniff(head).is_none()
+}
+
+/// Ignore rules of the workspace roots, from [`Scanner::ignore_rules`].
+///
+/// Hidden files and nested repositories are left out of scans too, but not by a rule: selecting
+/// one by name is deliberate, so they do not count as ignored here.
+#[derive(Debug, Clone)]
+pub struct IgnoreRules {
+    roots: Vec<RootIgnoreRules>,
+    /// Whether gitignore files apply, as they do when walking the local disk.
+    gitignore: bool,
+}
+
+#[derive(Debug, Clone)]
+struct RootIgnoreRules {
+    root: PathBuf,
+    matcher: IgnoreMatcher,
+}
+
+impl IgnoreRules {
+    /// Whether a scan leaves out `path` (absolute, beneath a root) because of `ignore.paths`,
+    /// `ignore.globs`, `.llmctxignore`, or, on the local disk, gitignore files.
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        let Some(rules) = self
+            .roots
+            .iter()
+            .find(|rules| path.starts_with(&rules.root))
+        else {
+            return false;
+        };
+        let rel = path.strip_prefix(&rules.root).unwrap_or(path);
+        // Scans prune ignored directories, which hides everything beneath them.
+        let by_rules = rel
+            .ancestors()
+            .filter(|ancestor| !ancestor.as_os_str().is_empty())
+            .any(|ancestor| rules.matcher.should_skip(ancestor));
+        by_rules || (self.gitignore && is_gitignored(&rules.root, path))
+    }
+}
+
+/// Whether `.ignore`, `.gitignore`, or git exclude files exclude `path` beneath `root`, with the
+/// precedence the directory walk gives them: deeper files first, `.ignore` over `.gitignore`.
+fn is_gitignored(root: &Path, path: &Path) -> bool {
+    let repository = root.ancestors().find(|dir| dir.join(".git").exists());
+    let Some(parent) = path.parent() else {
+        return false;
+    };
+    for dir in parent.ancestors().take_while(|dir| dir.starts_with(root)) {
+        // `.gitignore` files only apply inside a git repository.
+        let names: &[&str] = match repository {
+            Some(_) => &[".ignore", ".gitignore"],
+            None => &[".ignore"],
+        };
+        for name in names {
+            let file = dir.join(name);
+            if !file.is_file() {
+                continue;
+            }
+            let (matcher, _) = Gitignore::new(&file);
+            match matcher.matched_path_or_any_parents(path, false) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => {}
+            }
         }
-        Err(_) => false,
+    }
+    let Some(repository) = repository else {
+        return false;
+    };
+    let mut builder = GitignoreBuilder::new(repository);
+    builder.add(repository.join(".git/info/exclude"));
+    let Ok(exclude) = builder.build() else {
+        return false;
+    };
+    let rel = path.strip_prefix(repository).unwrap_or(path);
+    match exclude.matched_path_or_any_parents(rel, false) {
+        Match::None => Gitignore::global()
+            .0
+            .matched_path_or_any_parents(rel, false)
+            .is_ignore(),
+        exclusion => exclusion.is_ignore(),
     }
 }
 
@@ -195,7 +968,7 @@ impl IgnoreMatcher {
     }
 }
 
-fn build_ignore_matcher(root: &Path, cfg: &ScannerConfig) -> Result<IgnoreMatcher> {
+fn build_ignore_matcher(vfs: &dyn Vfs, root: &Path, cfg: &ScannerConfig) -> Result<IgnoreMatcher> {
     let mut builder = GlobSetBuilder::new();
 
     for pattern in &cfg.config.ignore.paths {
@@ -210,7 +983,7 @@ fn build_ignore_matcher(root: &Path, cfg: &ScannerConfig) -> Result<IgnoreMatche
         builder.add(glob);
     }
 
-    for pattern in load_llmctxignore(root)? {
+    for pattern in load_llmctxignore(vfs, root)? {
         for expanded in expand_dir_pattern(&pattern) {
             let glob = Glob::new(&expanded).context("invalid .llmctxignore pattern")?;
             builder.add(glob);
@@ -238,17 +1011,17 @@ fn expand_dir_pattern(raw: &str) -> Vec<String> {
     ]
 }
 
-fn load_llmctxignore(root: &Path) -> Result<Vec<String>> {
+fn load_llmctxignore(vfs: &dyn Vfs, root: &Path) -> Result<Vec<String>> {
     let path = root.join(LLMCTX_IGNORE);
-    if !path.exists() {
+    if !vfs.exists(&path) {
         return Ok(Vec::new());
     }
 
-    let file = File::open(&path).with_context(|| format!("failed to open {}", path.display()))?;
-    let reader = BufReader::new(file);
+    let contents = vfs
+        .read_text(&path)
+        .with_context(|| format!("failed to open {}", path.display()))?;
     let mut patterns = Vec::new();
-    for line in reader.lines() {
-        let line = line?;
+    for line in contents.text.lines() {
         let trimmed = line.trim();
         if trimmed.is_empty() || trimmed.starts_with('#') {
             continue;
@@ -261,12 +1034,60 @@ fn load_llmctxignore(root: &Path) -> Result<Vec<String>> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::infra::fs::MemoryFs;
     use std::fs;
 
     fn build_config() -> Config {
         Config::default()
     }
 
+    #[cfg(windows)]
+    #[test]
+    fn windows_paths_display_with_forward_slashes() -> Result<()> {
+        assert_eq!(slash_path(Path::new(r"src\app\mod.rs")), "src/app/mod.rs");
+        assert_eq!(
+            native_path(Path::new("src/app/mod.rs")),
+            PathBuf::from(r"src\app\mod.rs")
+        );
+
+        let temp = tempfile::tempdir()?;
+        fs::create_dir_all(temp.path().join(r"api\src"))?;
+        fs::write(temp.path().join(r"api\src\lib.rs"), "pub fn api() {}\n")?;
+        let roots = WorkspaceRoot::from_paths(&[temp.path().join("api"), temp.path().join("web")]);
+        let lib = temp.path().join(r"api\src\lib.rs");
+        assert_eq!(
+            roots[0].display_path(&lib).as_deref(),
+            Some("api/src/lib.rs")
+        );
+        assert_eq!(
+            WorkspaceRoot::resolve(&roots, Path::new("api/src/lib.rs")),
+            lib
+        );
+
+        let cfg = ScannerConfig::from_root(temp.path().to_path_buf(), build_config());
+        let result = Scanner::new().scan(&cfg)?;
+        let displayed: Vec<&str> = result
+            .files
+            .iter()
+            .map(|f| f.display_path.as_str())
+            .collect();
+        assert!(displayed.contains(&"api/src/lib.rs"), "{displayed:?}");
+        Ok(())
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn backslashes_stay_part_of_unix_file_names() {
+        assert_eq!(
+            slash_path(Path::new(r"src/odd\name.rs")),
+            r"src/odd\name.rs"
+        );
+        assert_eq!(
+            native_path(Path::new("./src//lib.rs")),
+            PathBuf::from("src/lib.rs")
+        );
+    }
+
     #[test]
     fn respects_ignore_paths_and_globs() -> Result<()> {
         let temp = tempfile::tempdir()?;
@@ -295,6 +1116,191 @@ mod tests {
         assert!(paths.contains(&"src/lib.rs".to_string()));
         assert!(!paths.iter().any(|p| p.contains("skipme")));
         assert!(!paths.iter().any(|p| p.ends_with("Cargo.lock")));
+
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let err = Scanner::new()
+            .with_cancellation(cancel)
+            .scan(&scanner_cfg)
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<crate::domain::errors::DomainError>(),
+            Some(&crate::domain::errors::DomainError::Cancelled)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn scan_paths_lists_files_with_ancestors() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = temp.path();
+        fs::create_dir_all(root.join("src/app"))?;
+        fs::write(root.join("src/app/main.rs"), b"fn main() {}")?;
+        fs::write(root.join("README.md"), b"# readme")?;
+
+        let scanner_cfg = ScannerConfig::from_root(root.to_path_buf(), build_config());
+        let scanner = Scanner::new();
+        let result = scanner.scan_paths(
+            &scanner_cfg,
+            &[PathBuf::from("src/app/main.rs"), root.join("README.md")],
+        )?;
+
+        let paths: Vec<_> = result
+            .files
+            .iter()
+            .map(|f| (f.display_path.as_str(), f.is_dir))
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                ("README.md", false),
+                ("src", true),
+                ("src/app", true),
+                ("src/app/main.rs", false),
+            ]
+        );
+
+        assert!(
+            scanner
+                .scan_paths(&scanner_cfg, &[root.join("src")])
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn multiple_roots_are_prefixed_by_label() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let api = temp.path().join("api");
+        let web = temp.path().join("nested/api");
+        fs::create_dir_all(api.join("src"))?;
+        fs::create_dir_all(&web)?;
+        fs::write(api.join("src/lib.rs"), b"fn lib() {}")?;
+        fs::write(web.join("index.ts"), b"export {}")?;
+
+        let roots = WorkspaceRoot::from_paths(&[api.clone(), web.clone()]);
+        assert_eq!(roots[1].label.as_deref(), Some("api-2"));
+
+        let scanner = Scanner::new();
+        let results = roots
+            .iter()
+            .map(|root| scanner.scan(&ScannerConfig::from_root(root.path.clone(), build_config())))
+            .collect::<Result<Vec<_>>>()?;
+        let combined = ScanResult::combine(&roots, results);
+
+        let paths: Vec<_> = combined
+            .files
+            .iter()
+            .map(|f| f.display_path.as_str())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                "api",
+                "api-2",
+                "api-2/index.ts",
+                "api/src",
+                "api/src/lib.rs"
+            ]
+        );
+        assert_eq!(
+            WorkspaceRoot::resolve(&roots, Path::new("api-2/index.ts")),
+            web.join("index.ts")
+        );
+        assert_eq!(
+            WorkspaceRoot::resolve(&roots, Path::new("src/lib.rs")),
+            api.join("src/lib.rs")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn submodules_are_skipped_unless_enabled() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = temp.path();
+        fs::create_dir_all(root.join("vendor/lib"))?;
+        fs::write(
+            root.join("vendor/lib/.git"),
+            b"gitdir: ../../.git/modules/lib\n",
+        )?;
+        fs::write(root.join("vendor/lib/lib.rs"), b"fn vendored() {}")?;
+        fs::write(root.join("main.rs"), b"fn main() {}")?;
+
+        let scan = |config: Config| -> Result<Vec<String>> {
+            let result =
+                Scanner::new().scan(&ScannerConfig::from_root(root.to_path_buf(), config))?;
+            Ok(result.files.into_iter().map(|f| f.display_path).collect())
+        };
+
+        let paths = scan(build_config())?;
+        assert!(paths.contains(&"main.rs".to_string()));
+        assert!(!paths.iter().any(|p| p.starts_with("vendor/lib")));
+
+        let config: Config = toml::from_str("[defaults]\ninclude_submodules = true")?;
+        let paths = scan(Config::default().merge_with(config))?;
+        assert!(paths.contains(&"vendor/lib/lib.rs".to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn ignored_entries_are_counted_by_rule() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = temp.path().canonicalize()?;
+        fs::create_dir_all(root.join(".git"))?;
+        fs::create_dir_all(root.join("build"))?;
+        fs::create_dir_all(root.join("target/debug"))?;
+        fs::create_dir_all(root.join("vendor/lib"))?;
+        fs::write(root.join(".gitignore"), b"build/\n*.log\n")?;
+        fs::write(root.join(".env"), b"KEY=1")?;
+        fs::write(root.join("build/out.o"), b"obj")?;
+        fs::write(root.join("debug.log"), b"log")?;
+        fs::write(root.join("target/debug/app"), b"bin")?;
+        fs::write(
+            root.join("vendor/lib/.git"),
+            b"gitdir: ../../.git/modules/lib\n",
+        )?;
+        fs::write(root.join("main.rs"), b"fn main() {}")?;
+
+        let roots = WorkspaceRoot::from_paths(std::slice::from_ref(&root));
+        let scanner = Scanner::new();
+        let scan = scanner.scan_roots(&roots, &build_config())?;
+        let counts = scanner.ignored_entries(&roots, &build_config(), &scan)?;
+        assert_eq!(
+            counts,
+            IgnoredCounts {
+                ignore_rules: 1,
+                gitignore: 2,
+                hidden: 2,
+                nested_repositories: 1,
+            }
+        );
+        assert_eq!(counts.total(), 6);
+        Ok(())
+    }
+
+    #[test]
+    fn scans_stop_at_file_and_size_limits() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = temp.path();
+        for idx in 0..20 {
+            fs::write(root.join(format!("file{idx}.txt")), vec![b'x'; 100])?;
+        }
+
+        let scan = |overlay: &str| -> Result<ScanResult> {
+            let config = build_config().merge_with(toml::from_str(overlay)?);
+            Scanner::new().scan(&ScannerConfig::from_root(root.to_path_buf(), config))
+        };
+
+        let result = scan("[defaults]\nmax_scan_files = 5")?;
+        assert_eq!(result.truncated, Some(ScanLimit::Files(5)));
+        assert!(result.files.len() < 20);
+
+        let result = scan("[defaults]\nmax_scan_bytes = 1000")?;
+        assert_eq!(result.truncated, Some(ScanLimit::Bytes(1000)));
+
+        let result = scan("[defaults]\nmax_scan_files = 0\nmax_scan_bytes = 0")?;
+        assert_eq!(result.truncated, None);
+        assert_eq!(result.files.len(), 20);
         Ok(())
     }
 
@@ -361,4 +1367,120 @@ mod tests {
         assert!(!paths.iter().any(|p| p.starts_with("generated")));
         Ok(())
     }
+
+    #[test]
+    fn scans_an_in_memory_file_system() -> Result<()> {
+        let vfs = MemoryFs::new();
+        vfs.write("/repo/src/main.rs", "fn main() {}\n");
+        vfs.write("/repo/src/data.bin", b"abc\0def".to_vec());
+        vfs.write("/repo/tests/cli.rs", "#[test] fn run() {}\n");
+        vfs.write("/repo/docs/big.md", vec![b'x'; 4096]);
+        vfs.write("/repo/.env", "KEY=1\n");
+        vfs.write("/repo/skipme/file.txt", "ignored\n");
+        vfs.write("/repo/generated/output.txt", "not included\n");
+        vfs.write(format!("/repo/{LLMCTX_IGNORE}"), "generated/\n");
+
+        let mut config = build_config();
+        config.ignore.paths.push("skipme/".into());
+        let cfg = ScannerConfig::from_root("/repo".into(), config.clone()).with_max_file_size(1024);
+        let scanner = Scanner::new().with_vfs(Arc::new(vfs));
+        let result = scanner.scan(&cfg)?;
+
+        let files: Vec<(&str, Option<SkipReason>, FileClass)> = result
+            .files
+            .iter()
+            .map(|f| (f.display_path.as_str(), f.skipped, f.class))
+            .collect();
+        assert_eq!(
+            files,
+            vec![
+                ("docs", None, FileClass::Source),
+                (
+                    "docs/big.md",
+                    Some(SkipReason::LargeFile),
+                    FileClass::Source
+                ),
+                ("src", None, FileClass::Source),
+                (
+                    "src/data.bin",
+                    Some(SkipReason::BinaryFile),
+                    FileClass::Source
+                ),
+                ("src/main.rs", None, FileClass::Source),
+                ("tests", None, FileClass::Source),
+                ("tests/cli.rs", None, FileClass::Test),
+            ]
+        );
+
+        let roots = WorkspaceRoot::from_paths(&[PathBuf::from("/repo")]);
+        let counts = scanner.ignored_entries(&roots, &config, &result)?;
+        assert_eq!((counts.ignore_rules, counts.hidden), (3, 1));
+
+        let listed = scanner.scan_paths(&cfg, &[PathBuf::from("src/main.rs")])?;
+        let listed: Vec<&str> = listed
+            .files
+            .iter()
+            .map(|f| f.display_path.as_str())
+            .collect();
+        assert_eq!(listed, vec!["src", "src/main.rs"]);
+        Ok(())
+    }
+
+    #[test]
+    fn classifies_tests_generated_and_vendored_files() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = temp.path().join("tests").join("checkout");
+        let files: [(&str, &str); 7] = [
+            ("src/lib.rs", "fn lib() {}"),
+            ("src/lib_test.go", "package lib"),
+            ("tests/cli.rs", "#[test] fn run() {}"),
+            ("web/app.spec.ts", "it('runs')"),
+            ("api/user.pb.go", "package api"),
+            (
+                "src/schema.rs",
+                "// @generated by build.rs\npub struct Schema;",
+            ),
+            ("vendor/dep/generated.rs", "// DO NOT EDIT"),
+        ];
+        for (path, contents) in files {
+            fs::create_dir_all(root.join(path).parent().unwrap())?;
+            fs::write(root.join(path), contents)?;
+        }
+
+        let scanner_cfg = ScannerConfig::from_root(root.clone(), build_config());
+        let result = Scanner::new().scan(&scanner_cfg)?;
+        let class_of = |display: &str| {
+            result
+                .files
+                .iter()
+                .find(|meta| meta.display_path == display)
+                .map(|meta| meta.class)
+        };
+        assert_eq!(class_of("src/lib.rs"), Some(FileClass::Source));
+        assert_eq!(class_of("src"), Some(FileClass::Source));
+        assert_eq!(class_of("src/lib_test.go"), Some(FileClass::Test));
+        assert_eq!(class_of("tests/cli.rs"), Some(FileClass::Test));
+        assert_eq!(class_of("web/app.spec.ts"), Some(FileClass::Test));
+        assert_eq!(class_of("api/user.pb.go"), Some(FileClass::Generated));
+        assert_eq!(class_of("src/schema.rs"), Some(FileClass::Generated));
+        assert_eq!(
+            class_of("vendor/dep/generated.rs"),
+            Some(FileClass::Vendored)
+        );
+        assert_eq!(
+            FileClass::detect(Path::new("web/yarn.lock"), b""),
+            FileClass::Generated
+        );
+
+        let roots = WorkspaceRoot::from_paths(std::slice::from_ref(&root));
+        assert_eq!(
+            FileClass::of_file(&RealFs, &root.join("src/lib.rs"), &roots),
+            FileClass::Source
+        );
+        assert_eq!(
+            FileClass::of_file(&RealFs, &root.join("src/lib.rs"), &[]),
+            FileClass::Test
+        );
+        Ok(())
+    }
 }
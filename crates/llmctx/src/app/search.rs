@@ -1,14 +1,522 @@
 //! Repository search services.
 
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{Context, Result, anyhow, bail};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use rayon::prelude::*;
+use regex::{Regex, RegexBuilder};
+use time::{Duration, OffsetDateTime};
+
+use crate::app::cancel::CancellationToken;
+use crate::app::scan::FileMetadata;
+use crate::app::selection::SelectionManager;
+use crate::domain::model::{SelectionItem, SelectionSource};
+use crate::infra::fs as infra_fs;
+use crate::infra::ripgrep;
+
+/// Hits collected before a search stops early.
+const MAX_HITS: usize = 2_000;
+/// Characters of a matching line kept for display.
+const MAX_LINE_CHARS: usize = 200;
+
+/// One line, or for semantic matches one chunk, matching a search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchHit {
+    pub path: PathBuf,
+    pub display_path: String,
+    /// 1-based line number.
+    pub line: usize,
+    /// Last line covered; the same as `line` for pattern matches.
+    pub end: usize,
+    /// The matching line, trimmed and shortened for display.
+    pub text: String,
+}
+
+/// Hits for one query, ordered by file then line.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchResults {
+    /// The query as written, filters included.
+    pub pattern: String,
+    pub hits: Vec<SearchHit>,
+    /// Set when the search stopped after [`MAX_HITS`] hits.
+    pub truncated: bool,
+}
+
+/// How file contents are searched, configured with `search.backend`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SearchBackend {
+    /// ripgrep when it is installed, the built-in search otherwise.
+    #[default]
+    Auto,
+    /// Read and match every scanned file in-process.
+    Builtin,
+    /// Shell out to `rg --json`, failing when it is not installed.
+    Ripgrep,
+}
+
+impl FromStr for SearchBackend {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "auto" => Ok(Self::Auto),
+            "builtin" | "rust" => Ok(Self::Builtin),
+            "ripgrep" | "rg" => Ok(Self::Ripgrep),
+            other => Err(anyhow!(
+                "unknown search backend '{other}' (expected auto, builtin, or ripgrep)"
+            )),
+        }
+    }
+}
+
+/// A search pattern plus the `lang:`, `path:`, and `modified:` filters written alongside it.
+#[derive(Debug, Clone)]
+pub struct SearchQuery {
+    /// Regular expression matched against each line.
+    pub pattern: String,
+    /// Accepted file extensions; empty accepts every language.
+    languages: Vec<String>,
+    paths: Option<GlobSet>,
+    modified: Option<ModifiedFilter>,
+}
+
+/// `modified:<7d` keeps files changed within the period, `modified:>7d` files older than it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ModifiedFilter {
+    within: bool,
+    age: Duration,
+}
+
+impl SearchQuery {
+    /// Split `query` into filters and the pattern made of the remaining words.
+    ///
+    /// `lang:` takes a language name or extension and `path:` a glob over workspace paths; both
+    /// may repeat, and a file passes when it matches any of them. `modified:` takes `<` or `>`
+    /// and an age in minutes, hours, days, or weeks (`30m`, `12h`, `7d`, `2w`); without a
+    /// comparison it means within the age.
+    pub fn parse(query: &str) -> Result<Self> {
+        let mut words = Vec::new();
+        let mut languages = Vec::new();
+        let mut paths = GlobSetBuilder::new();
+        let mut has_paths = false;
+        let mut modified = None;
+        for word in query.split_whitespace() {
+            if let Some(language) = word.strip_prefix("lang:") {
+                languages.extend(language_extensions(language));
+            } else if let Some(glob) = word.strip_prefix("path:") {
+                paths
+                    .add(Glob::new(glob).with_context(|| format!("invalid path filter '{word}'"))?);
+                has_paths = true;
+            } else if let Some(age) = word.strip_prefix("modified:") {
+                modified = Some(
+                    ModifiedFilter::parse(age)
+                        .with_context(|| format!("invalid modified filter '{word}'"))?,
+                );
+            } else {
+                words.push(word);
+            }
+        }
+        if words.is_empty() {
+            bail!("search requires a pattern besides its filters");
+        }
+        Ok(Self {
+            pattern: words.join(" "),
+            languages,
+            paths: has_paths.then(|| paths.build()).transpose()?,
+            modified,
+        })
+    }
+
+    /// Whether the filters accept `meta`, judging modification times against `now`.
+    pub fn matches(&self, meta: &FileMetadata, now: OffsetDateTime) -> bool {
+        if !self.languages.is_empty()
+            && !meta
+                .language
+                .as_ref()
+                .is_some_and(|language| self.languages.contains(language))
+        {
+            return false;
+        }
+        if let Some(paths) = &self.paths
+            && !paths.is_match(&meta.display_path)
+        {
+            return false;
+        }
+        match (self.modified, meta.modified) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some(filter), Some(modified)) => (modified >= now - filter.age) == filter.within,
+        }
+    }
+}
+
+impl ModifiedFilter {
+    fn parse(value: &str) -> Result<Self> {
+        let (within, age) = match value.strip_prefix('>') {
+            Some(age) => (false, age),
+            None => (true, value.strip_prefix('<').unwrap_or(value)),
+        };
+        let split = age
+            .find(|ch: char| !ch.is_ascii_digit())
+            .ok_or_else(|| anyhow!("add a unit: m, h, d, or w"))?;
+        let amount: i64 = age[..split]
+            .parse()
+            .map_err(|_| anyhow!("expected a number before the unit"))?;
+        let age = match &age[split..] {
+            "m" => Duration::minutes(amount),
+            "h" => Duration::hours(amount),
+            "d" => Duration::days(amount),
+            "w" => Duration::weeks(amount),
+            other => bail!("unknown unit '{other}'; use m, h, d, or w"),
+        };
+        Ok(Self { within, age })
+    }
+}
+
+/// Extensions recorded for files of `language`, which may already be an extension.
+fn language_extensions(language: &str) -> Vec<String> {
+    let language = language.to_ascii_lowercase();
+    let extensions: &[&str] = match language.as_str() {
+        "rust" => &["rs"],
+        "python" => &["py", "pyi"],
+        "javascript" | "js" => &["js", "mjs", "cjs", "jsx"],
+        "typescript" | "ts" => &["ts", "mts", "cts", "tsx"],
+        "golang" => &["go"],
+        "kotlin" => &["kt", "kts"],
+        "c" => &["c", "h"],
+        "cpp" | "c++" => &["cc", "cpp", "cxx", "hh", "hpp", "hxx", "h"],
+        "csharp" | "c#" => &["cs"],
+        "ruby" => &["rb"],
+        "shell" | "bash" | "sh" => &["sh", "bash", "zsh"],
+        "markdown" => &["md", "markdown"],
+        "yaml" | "yml" => &["yaml", "yml"],
+        "html" => &["html", "htm"],
+        _ => return vec![language],
+    };
+    extensions.iter().map(|ext| ext.to_string()).collect()
+}
+
 #[derive(Default)]
-pub struct Search;
+pub struct Search {
+    backend: SearchBackend,
+    cancel: CancellationToken,
+}
 
 impl Search {
-    pub fn new() -> Self {
-        Self
+    pub fn new(backend: SearchBackend) -> Self {
+        Self {
+            backend,
+            cancel: CancellationToken::default(),
+        }
+    }
+
+    /// Stop reading files once `cancel` is cancelled; queries then fail with
+    /// [`crate::domain::errors::DomainError::Cancelled`]. A running ripgrep finishes first.
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
     }
 
-    pub fn query(&self, _pattern: &str) {
-        // TODO: implement content search
+    /// Search the contents of `files`, which were scanned from `roots`, for lines matching
+    /// `query`.
+    ///
+    /// The query's pattern is a regular expression, matched case-insensitively unless it contains
+    /// an uppercase letter, and its filters (see [`SearchQuery::parse`]) narrow the files before
+    /// any are read. Directories and skipped files are left out, as are files that cannot be
+    /// read as UTF-8. Both backends report the same hits in the same order; ripgrep is faster on
+    /// large trees because it skips gitignored directories without reading them.
+    pub fn query(
+        &self,
+        files: &[FileMetadata],
+        roots: &[PathBuf],
+        query: &str,
+    ) -> Result<SearchResults> {
+        let parsed = SearchQuery::parse(query)?;
+        let now = OffsetDateTime::now_utc();
+        let files: Vec<FileMetadata> = files
+            .iter()
+            .filter(|meta| parsed.matches(meta, now))
+            .cloned()
+            .collect();
+        let files = files.as_slice();
+        let pattern = parsed.pattern.as_str();
+        let case_insensitive = !pattern.chars().any(char::is_uppercase);
+        let regex = RegexBuilder::new(pattern)
+            .case_insensitive(case_insensitive)
+            .build()
+            .with_context(|| format!("invalid search pattern '{pattern}'"))?;
+
+        let builtin = || builtin_hits(files, &regex, &self.cancel);
+        let mut hits = match self.backend {
+            SearchBackend::Builtin => builtin(),
+            SearchBackend::Ripgrep => ripgrep_hits(files, roots, pattern, case_insensitive)?
+                .ok_or_else(|| {
+                    anyhow!("ripgrep (rg) is not installed; set search.backend = \"builtin\"")
+                })?,
+            SearchBackend::Auto => match ripgrep_hits(files, roots, pattern, case_insensitive) {
+                Ok(Some(hits)) => hits,
+                Ok(None) => builtin(),
+                Err(err) => {
+                    tracing::debug!(error = %err, "ripgrep search failed, using built-in search");
+                    builtin()
+                }
+            },
+        };
+        self.cancel.check()?;
+        let truncated = hits.len() > MAX_HITS;
+        hits.truncate(MAX_HITS);
+        Ok(SearchResults {
+            pattern: query.to_string(),
+            hits,
+            truncated,
+        })
+    }
+}
+
+fn searchable(meta: &FileMetadata) -> bool {
+    !meta.is_dir && meta.skipped.is_none()
+}
+
+fn hit(meta: &FileMetadata, line: usize, text: &str) -> SearchHit {
+    SearchHit {
+        path: meta.path.clone(),
+        display_path: meta.display_path.clone(),
+        line,
+        end: line,
+        text: text.trim().chars().take(MAX_LINE_CHARS).collect(),
+    }
+}
+
+fn builtin_hits(
+    files: &[FileMetadata],
+    regex: &Regex,
+    cancel: &CancellationToken,
+) -> Vec<SearchHit> {
+    let per_file: Vec<Vec<SearchHit>> = files
+        .par_iter()
+        .filter(|meta| searchable(meta) && !cancel.is_cancelled())
+        .map(|meta| {
+            let Ok(decoded) = infra_fs::read_text(&meta.path) else {
+                return Vec::new();
+            };
+            decoded
+                .text
+                .lines()
+                .enumerate()
+                .filter(|(_, line)| regex.is_match(line))
+                .take(MAX_HITS + 1)
+                .map(|(index, line)| hit(meta, index + 1, line))
+                .collect()
+        })
+        .collect();
+    per_file.into_iter().flatten().collect()
+}
+
+/// ripgrep matches within `files`, ordered like the built-in search. `None` without ripgrep.
+fn ripgrep_hits(
+    files: &[FileMetadata],
+    roots: &[PathBuf],
+    pattern: &str,
+    case_insensitive: bool,
+) -> Result<Option<Vec<SearchHit>>> {
+    let Some(matches) = ripgrep::search(roots, pattern, case_insensitive)? else {
+        return Ok(None);
+    };
+    let positions: HashMap<&Path, (usize, &FileMetadata)> = files
+        .iter()
+        .filter(|meta| searchable(meta))
+        .enumerate()
+        .map(|(index, meta)| (meta.path.as_path(), (index, meta)))
+        .collect();
+    let mut hits: Vec<(usize, SearchHit)> = matches
+        .iter()
+        .filter_map(|found| {
+            let (index, meta) = positions.get(found.path.as_path())?;
+            Some((*index, hit(meta, found.line, &found.text)))
+        })
+        .collect();
+    hits.sort_by_key(|(index, hit)| (*index, hit.line));
+    Ok(Some(hits.into_iter().map(|(_, hit)| hit).collect()))
+}
+
+/// Selections covering `hits`, one merged span per run of overlapping or nearby hits in a file.
+///
+/// Hits on touching lines, or fewer than `merge_gap` lines apart, share a span.
+pub fn hit_selections<'a>(
+    hits: impl IntoIterator<Item = &'a SearchHit>,
+    merge_gap: usize,
+) -> Vec<SelectionItem> {
+    let mut manager = SelectionManager::new();
+    for hit in hits {
+        manager.add_item(SelectionItem {
+            path: hit.path.clone(),
+            range: Some((hit.line, hit.end)),
+            note: None,
+            source: SelectionSource::SearchMatch,
+        });
+    }
+    manager.normalize(merge_gap);
+    manager.items().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+
+    use crate::app::scan::FileClass;
+
+    fn meta(path: PathBuf, display_path: &str) -> FileMetadata {
+        FileMetadata {
+            path,
+            display_path: display_path.to_string(),
+            is_dir: false,
+            size: None,
+            modified: None,
+            language: None,
+            skipped: None,
+            class: FileClass::Source,
+        }
+    }
+
+    #[test]
+    fn finds_lines_with_smart_case_and_merges_hits_per_file() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let lib = temp.path().join("lib.rs");
+        let main = temp.path().join("main.rs");
+        fs::write(
+            &lib,
+            "fn run() {}\nfn helper() {\n    run();\n    run();\n}\n\n\n\n    Run();\n",
+        )?;
+        fs::write(&main, "fn main() {\n    lib::run();\n}\n")?;
+        let files = vec![meta(lib.clone(), "lib.rs"), meta(main.clone(), "main.rs")];
+
+        let search = Search::new(SearchBackend::Builtin);
+        let roots = [temp.path().to_path_buf()];
+        let results = search.query(&files, &roots, "run\\(")?;
+        let lines: Vec<_> = results
+            .hits
+            .iter()
+            .map(|hit| (hit.display_path.as_str(), hit.line))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                ("lib.rs", 1),
+                ("lib.rs", 3),
+                ("lib.rs", 4),
+                ("lib.rs", 9),
+                ("main.rs", 2)
+            ]
+        );
+        assert_eq!(results.hits[1].text, "run();");
+        assert_eq!(search.query(&files, &roots, "Run\\(")?.hits.len(), 1);
+        assert!(search.query(&files, &roots, "run(").is_err());
+
+        let selections = hit_selections(&results.hits[1..], 0);
+        let ranges: Vec<_> = selections
+            .iter()
+            .map(|item| (item.path.clone(), item.range))
+            .collect();
+        assert_eq!(
+            ranges,
+            vec![
+                (lib.clone(), Some((3, 4))),
+                (lib.clone(), Some((9, 9))),
+                (main, Some((2, 2)))
+            ]
+        );
+        assert_eq!(
+            hit_selections(&results.hits[1..4], 5)[0].range,
+            Some((3, 9))
+        );
+        assert!(
+            selections
+                .iter()
+                .all(|item| item.source == SelectionSource::SearchMatch)
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[ignore = "needs ripgrep (rg) on PATH; run with --ignored"]
+    fn ripgrep_backend_matches_builtin_hits() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = temp.path().canonicalize()?;
+        fs::create_dir_all(root.join("src"))?;
+        fs::write(
+            root.join("src/lib.rs"),
+            "fn run() {}\n\nfn go() { run(); }\r\n",
+        )?;
+        fs::write(root.join(".env.example"), "RUN=1\n")?;
+        fs::write(root.join("notes.md"), "run it\n")?;
+        fs::write(root.join("skipped.txt"), "run\n")?;
+        let mut skipped = meta(root.join("skipped.txt"), "skipped.txt");
+        skipped.skipped = Some(crate::app::scan::SkipReason::LargeFile);
+        let files = vec![
+            meta(root.join("src/lib.rs"), "src/lib.rs"),
+            meta(root.join(".env.example"), ".env.example"),
+            skipped,
+        ];
+        let roots = [root];
+
+        let rg = ripgrep_hits(&files, &roots, "run", true)?.expect("rg is not on PATH");
+        let regex = RegexBuilder::new("run").case_insensitive(true).build()?;
+        let builtin = builtin_hits(&files, &regex, &CancellationToken::default());
+        assert_eq!(rg, builtin);
+        assert_eq!(rg.len(), 3);
+        assert_eq!(rg[1].text, "fn go() { run(); }");
+        Ok(())
+    }
+
+    #[test]
+    fn filters_narrow_files_by_language_path_and_age() -> Result<()> {
+        let now = OffsetDateTime::now_utc();
+        let file = |display_path: &str, language: &str, age: Duration| FileMetadata {
+            language: Some(language.to_string()),
+            modified: Some(now - age),
+            ..meta(PathBuf::from("/repo").join(display_path), display_path)
+        };
+        let files = [
+            file("src/app/mod.rs", "rs", Duration::hours(2)),
+            file("src/old.rs", "rs", Duration::days(30)),
+            file("web/index.ts", "ts", Duration::hours(1)),
+            file("README.md", "md", Duration::minutes(5)),
+        ];
+        let kept = |query: &str| -> Result<Vec<String>> {
+            let query = SearchQuery::parse(query)?;
+            Ok(files
+                .iter()
+                .filter(|meta| query.matches(meta, now))
+                .map(|meta| meta.display_path.clone())
+                .collect())
+        };
+
+        let query = SearchQuery::parse("fn  lang:rust  run path:src/**")?;
+        assert_eq!(query.pattern, "fn run");
+        assert_eq!(
+            kept("fn lang:rust path:src/**")?,
+            vec!["src/app/mod.rs", "src/old.rs"]
+        );
+        assert_eq!(
+            kept("x lang:typescript lang:md")?,
+            vec!["web/index.ts", "README.md"]
+        );
+        assert_eq!(
+            kept("x modified:<1d")?,
+            vec!["src/app/mod.rs", "web/index.ts", "README.md"]
+        );
+        assert_eq!(kept("x modified:>1w")?, vec!["src/old.rs"]);
+        assert_eq!(kept("x modified:30m path:*.md")?, vec!["README.md"]);
+
+        assert!(SearchQuery::parse("lang:rust").is_err());
+        assert!(SearchQuery::parse("x modified:7").is_err());
+        assert!(SearchQuery::parse("x modified:7y").is_err());
+        assert!(SearchQuery::parse("x path:src/[").is_err());
+        Ok(())
     }
 }
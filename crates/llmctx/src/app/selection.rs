@@ -1,17 +1,26 @@
 //! Managing selections and context bundles.
 
+use std::fmt;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
 use anyhow::Result;
 
+use crate::app::scan::{IgnoreRules, is_probably_binary, read_head};
 use crate::app::tokens::{BundleTokenSummary, TokenEstimator};
-use crate::domain::model::{ContextBundle, SelectionItem};
+use crate::domain::errors::DomainError;
+use crate::domain::model::{ContextBundle, SelectionItem, SelectionSource};
+use crate::infra::fs::{DecodedText, Vfs};
 
 /// Tracks the active selection set and produces export-ready bundles.
 #[derive(Debug, Default, Clone)]
 pub struct SelectionManager {
     items: Vec<SelectionItem>,
     model: Option<String>,
+    /// Bumped by every change to the selections or model.
+    revision: u64,
+    /// Revision passed to the last [`Self::mark_saved`].
+    saved_revision: u64,
 }
 
 impl SelectionManager {
@@ -20,6 +29,14 @@ impl SelectionManager {
         Self::default()
     }
 
+    /// Track `items` as given, without merging, so [`Self::normalize`] sees every selection.
+    pub fn from_items(items: Vec<SelectionItem>) -> Self {
+        Self {
+            items,
+            ..Self::default()
+        }
+    }
+
     /// Returns the number of tracked selections.
     pub fn len(&self) -> usize {
         self.items.len()
@@ -33,11 +50,32 @@ impl SelectionManager {
     /// Replace the associated model for bundle generation.
     pub fn set_model<S: Into<String>>(&mut self, model: S) {
         self.model = Some(model.into());
+        self.touch();
     }
 
     /// Clear the configured model, falling back to estimator defaults.
     pub fn clear_model(&mut self) {
         self.model = None;
+        self.touch();
+    }
+
+    /// Whether the selections or model changed since they were last saved or restored.
+    pub fn is_dirty(&self) -> bool {
+        self.revision != self.saved_revision
+    }
+
+    /// Record that the current selections and model are saved.
+    pub fn mark_saved(&mut self) {
+        self.saved_revision = self.revision;
+    }
+
+    /// Counter that changes whenever the selections or model do, to detect changes cheaply.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    fn touch(&mut self) {
+        self.revision += 1;
     }
 
     /// Access the configured model if set.
@@ -61,12 +99,23 @@ impl SelectionManager {
         range: Option<(usize, usize)>,
         note: Option<String>,
     ) -> SelectionItem {
-        let item = SelectionItem {
+        self.add_item(SelectionItem {
             path: path.into(),
-            range: range.map(normalize_range),
-            note: note.and_then(clean_note),
+            range,
+            note,
+            source: SelectionSource::Manual,
+        })
+    }
+
+    /// Append or merge `item` like [`Self::add_selection`], keeping its [`SelectionSource`].
+    pub fn add_item(&mut self, item: SelectionItem) -> SelectionItem {
+        let item = SelectionItem {
+            range: item.range.map(normalize_range),
+            note: item.note.and_then(clean_note),
+            ..item
         };
 
+        self.touch();
         match item.range {
             None => self.insert_entire_file(item),
             Some(range) => self.insert_range(item, range),
@@ -86,7 +135,11 @@ impl SelectionManager {
                 item.range != Some(target)
             }),
         }
-        self.items.len() != original_len
+        let removed = self.items.len() != original_len;
+        if removed {
+            self.touch();
+        }
+        removed
     }
 
     /// Update the note associated with a selection. Returns `true` when a matching selection is
@@ -109,16 +162,92 @@ impl SelectionManager {
                 }
         }) {
             item.note = note;
+            self.touch();
             return true;
         }
 
         false
     }
 
+    /// Merge each file's selections into ordered, non-overlapping spans.
+    ///
+    /// Ranges that overlap or touch are always merged; ranges separated by fewer than
+    /// `merge_gap` lines are coalesced into one span that includes the gap. A whole-file
+    /// selection absorbs every range of its file. Files keep the position of their first
+    /// selection, and the notes of merged selections are joined.
+    pub fn normalize(&mut self, merge_gap: usize) {
+        self.touch();
+        let mut files: Vec<(PathBuf, Vec<SelectionItem>)> = Vec::new();
+        for item in self.items.drain(..) {
+            match files.iter_mut().find(|(path, _)| *path == item.path) {
+                Some((_, items)) => items.push(item),
+                None => files.push((item.path.clone(), vec![item])),
+            }
+        }
+
+        for (path, mut items) in files {
+            if let Some(whole) = items.iter().find(|item| item.range.is_none()) {
+                let source = whole.source;
+                let note = join_notes(items.iter().map(|item| item.note.as_deref()));
+                self.items.push(SelectionItem {
+                    path,
+                    range: None,
+                    note,
+                    source,
+                });
+                continue;
+            }
+
+            items.sort_by_key(|item| item.range);
+            let mut spans: Vec<SelectionItem> = Vec::new();
+            for item in items {
+                let previous = spans.last_mut().and_then(|span| {
+                    let merged = within_gap(span.range?, item.range?, merge_gap);
+                    merged.then_some(span)
+                });
+                match (previous, item.range) {
+                    (Some(span), Some(range)) => {
+                        span.range = span.range.map(|(start, end)| (start, end.max(range.1)));
+                        span.note =
+                            join_notes([span.note.as_deref(), item.note.as_deref()].into_iter());
+                    }
+                    _ => spans.push(item),
+                }
+            }
+            self.items.extend(spans);
+        }
+    }
+
+    /// Widen every ranged selection by `context_lines` above and below, then merge spans that
+    /// now overlap or touch.
+    ///
+    /// `line_count` reports a file's length so padding stops at the last line.
+    pub fn pad_ranges(
+        &mut self,
+        context_lines: usize,
+        mut line_count: impl FnMut(&Path) -> Result<usize>,
+    ) -> Result<()> {
+        if context_lines == 0 {
+            return Ok(());
+        }
+        for item in &mut self.items {
+            if let Some((start, end)) = item.range {
+                let last_line = line_count(&item.path)?.max(end);
+                item.range = Some((
+                    start.saturating_sub(context_lines).max(1),
+                    end.saturating_add(context_lines).min(last_line),
+                ));
+            }
+        }
+        self.normalize(0);
+        Ok(())
+    }
+
     /// Remove all selections.
     pub fn clear(&mut self) {
         self.items.clear();
         self.model = None;
+        self.touch();
     }
 
     /// Build a [`ContextBundle`] from the tracked selections, using an optional override model.
@@ -126,6 +255,7 @@ impl SelectionManager {
         ContextBundle {
             items: self.items.clone(),
             model: override_model.or_else(|| self.model.clone()),
+            attachments: Vec::new(),
         }
     }
 
@@ -134,6 +264,23 @@ impl SelectionManager {
         self.to_bundle_with_model(None)
     }
 
+    /// Check every selection against the files in `vfs` and the workspace's ignore `rules`.
+    ///
+    /// Large files are not reported since they can be selected deliberately.
+    pub fn validate(&self, vfs: &dyn Vfs, rules: &IgnoreRules) -> Vec<SelectionWarning> {
+        self.items
+            .iter()
+            .filter_map(|item| {
+                let issue = selection_issue(vfs, item, rules)?;
+                Some(SelectionWarning {
+                    path: item.path.clone(),
+                    range: item.range,
+                    issue,
+                })
+            })
+            .collect()
+    }
+
     /// Estimate tokens for the active bundle using the provided estimator.
     pub fn summarize_tokens(
         &self,
@@ -208,11 +355,11 @@ impl SelectionManager {
             }
         }
 
-        if !merged_indices.is_empty() {
-            for idx in merged_indices.iter().rev() {
-                self.items.remove(*idx);
-            }
+        for idx in merged_indices.iter().rev() {
+            self.items.remove(*idx);
         }
+        // With every range of the file merged away, the span takes the place of the first one.
+        let fallback = merged_indices.first().copied().unwrap_or(self.items.len());
 
         item.range = Some(range);
         if item.note.is_none() {
@@ -238,7 +385,7 @@ impl SelectionManager {
                     .rev()
                     .find(|(_, existing)| existing.path == item.path)
                     .map(|(idx, _)| idx + 1)
-                    .unwrap_or(self.items.len())
+                    .unwrap_or(fallback)
             });
 
         self.items.insert(position, item.clone());
@@ -246,6 +393,207 @@ impl SelectionManager {
     }
 }
 
+/// Cloneable handle to one [`SelectionManager`] shared by the UI and background tasks.
+///
+/// Clones see each other's changes. Hold a guard only as long as a call needs it: a task that
+/// waits on a write while holding a read guard deadlocks. A lock poisoned by a panicking writer
+/// is still handed out, since every manager method leaves the selections consistent.
+#[derive(Debug, Default, Clone)]
+pub struct SharedSelection {
+    inner: Arc<RwLock<SelectionManager>>,
+}
+
+impl SharedSelection {
+    pub fn new(manager: SelectionManager) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(manager)),
+        }
+    }
+
+    /// Lock the selections for reading; other readers may hold them at the same time.
+    pub fn read(&self) -> RwLockReadGuard<'_, SelectionManager> {
+        self.inner.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Lock the selections for changing them.
+    pub fn write(&self) -> RwLockWriteGuard<'_, SelectionManager> {
+        self.inner.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Copy of the selections as they are now, to work on without holding the lock.
+    pub fn snapshot(&self) -> SelectionManager {
+        self.read().clone()
+    }
+}
+
+/// A selection flagged by [`SelectionManager::validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectionWarning {
+    pub path: PathBuf,
+    pub range: Option<(usize, usize)>,
+    pub issue: SelectionIssue,
+}
+
+impl SelectionWarning {
+    /// Whether the warning is about the selection `path` and `range`.
+    pub fn concerns(&self, path: &Path, range: Option<(usize, usize)>) -> bool {
+        self.path == path && self.range == range
+    }
+}
+
+impl fmt::Display for SelectionWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.display())?;
+        if let Some((start, end)) = self.range {
+            write!(f, ":{start}-{end}")?;
+        }
+        write!(f, ": {}", self.issue)
+    }
+}
+
+/// Why a selection may not export what was intended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectionIssue {
+    /// The file no longer exists.
+    Deleted,
+    /// The file exists but ignore rules exclude it from the workspace scan.
+    Ignored,
+    /// The file looks binary, so its contents will not export meaningfully.
+    Binary,
+    /// The range ends after the last line of the file and will be clamped.
+    RangePastEnd { line_count: usize },
+    /// The range selects no lines.
+    EmptyRange,
+}
+
+impl fmt::Display for SelectionIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Deleted => write!(f, "file was deleted"),
+            Self::Ignored => write!(f, "file is excluded by ignore rules"),
+            Self::Binary => write!(f, "file is binary"),
+            Self::RangePastEnd { line_count } => {
+                write!(f, "range ends past the last line ({line_count})")
+            }
+            Self::EmptyRange => write!(f, "range selects no lines"),
+        }
+    }
+}
+
+fn selection_issue(
+    vfs: &dyn Vfs,
+    item: &SelectionItem,
+    rules: &IgnoreRules,
+) -> Option<SelectionIssue> {
+    if !vfs.exists(&item.path) {
+        return Some(SelectionIssue::Deleted);
+    }
+    let canonical = item
+        .path
+        .canonicalize()
+        .or_else(|_| std::path::absolute(&item.path))
+        .unwrap_or_else(|_| item.path.clone());
+    if rules.is_ignored(&canonical) {
+        return Some(SelectionIssue::Ignored);
+    }
+    if is_probably_binary(&read_head(vfs, &item.path)) {
+        return Some(SelectionIssue::Binary);
+    }
+
+    let (start, end) = item.range?;
+    let bytes = vfs.read(&item.path).ok()?;
+    let line_count = DecodedText::decode(&bytes).text.lines().count();
+    if start == 0 || start > end || start > line_count {
+        Some(SelectionIssue::EmptyRange)
+    } else if end > line_count {
+        Some(SelectionIssue::RangePastEnd { line_count })
+    } else {
+        None
+    }
+}
+
+/// Check that `path` exists in `vfs` and, when a range is given, that the range starts inside
+/// the file.
+///
+/// Ranges that run past the end of the file are allowed; exports clamp them.
+pub fn check_bounds(
+    vfs: &dyn Vfs,
+    path: &Path,
+    range: Option<(usize, usize)>,
+) -> Result<(), DomainError> {
+    if !vfs.exists(path) {
+        return Err(DomainError::FileMissing {
+            path: path.to_path_buf(),
+        });
+    }
+    let Some((start, end)) = range else {
+        return Ok(());
+    };
+    let Ok(bytes) = vfs.read(path) else {
+        return Ok(());
+    };
+    let line_count = DecodedText::decode(&bytes).text.lines().count();
+    if start > line_count {
+        return Err(DomainError::SelectionOutOfBounds {
+            path: path.to_path_buf(),
+            start,
+            end,
+            line_count,
+        });
+    }
+    Ok(())
+}
+
+/// A selection written as `path[:start-end][#note]`, as accepted by `--select` and recipes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectionSpec {
+    pub path: PathBuf,
+    pub range: Option<(usize, usize)>,
+    pub note: Option<String>,
+}
+
+impl SelectionSpec {
+    /// Parse a `path[:start-end][#note]` specification.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let (target, note) = match value.split_once('#') {
+            Some((target, note)) => (target.trim(), clean_note(note.to_string())),
+            None => (value.trim(), None),
+        };
+
+        if target.is_empty() {
+            return Err("selection specification is empty".to_string());
+        }
+
+        let mut path_part = target;
+        let mut range = None;
+
+        if let Some(colon_idx) = target.rfind(':') {
+            let (candidate_path, candidate_range) = target.split_at(colon_idx);
+            if let Some(parsed_range) = parse_range(&candidate_range[1..]) {
+                path_part = candidate_path;
+                range = Some(parsed_range);
+            }
+        }
+
+        if path_part.is_empty() {
+            return Err("selection path is empty".to_string());
+        }
+
+        Ok(Self {
+            path: PathBuf::from(path_part),
+            range,
+            note,
+        })
+    }
+}
+
+fn parse_range(spec: &str) -> Option<(usize, usize)> {
+    let (start, end) = spec.split_once('-')?;
+    let start = start.trim().parse::<usize>().ok()?;
+    let end = end.trim().parse::<usize>().ok()?;
+    Some((start, end))
+}
+
 fn normalize_range(range: (usize, usize)) -> (usize, usize) {
     let start = range.0.min(range.1).max(1);
     let end = range.0.max(range.1).max(1);
@@ -258,6 +606,24 @@ fn ranges_mergeable(a: (usize, usize), b: (usize, usize)) -> bool {
     a_start <= b_end.saturating_add(1) && b_start <= a_end.saturating_add(1)
 }
 
+/// Whether `next`, starting at or after `span`, overlaps it, touches it, or begins fewer than
+/// `merge_gap` lines after it.
+fn within_gap(span: (usize, usize), next: (usize, usize), merge_gap: usize) -> bool {
+    let gap = next.0.saturating_sub(span.1.saturating_add(1));
+    gap == 0 || gap < merge_gap
+}
+
+/// Distinct notes in order, joined with `; `.
+fn join_notes<'a>(notes: impl Iterator<Item = Option<&'a str>>) -> Option<String> {
+    let mut distinct: Vec<&str> = Vec::new();
+    for note in notes.flatten() {
+        if !distinct.contains(&note) {
+            distinct.push(note);
+        }
+    }
+    (!distinct.is_empty()).then(|| distinct.join("; "))
+}
+
 fn clean_note(note: String) -> Option<String> {
     let trimmed = note.trim();
     if trimmed.is_empty() {
@@ -271,10 +637,35 @@ fn clean_note(note: String) -> Option<String> {
 mod tests {
     use super::*;
 
+    use std::collections::BTreeSet;
     use std::io::Write;
 
+    use proptest::prelude::*;
     use tempfile::NamedTempFile;
 
+    use crate::app::scan::{Scanner, WorkspaceRoot};
+    use crate::infra::config::Config;
+    use crate::infra::fs::RealFs;
+
+    #[test]
+    fn shared_handles_see_changes_from_other_threads() {
+        let shared = SharedSelection::new(SelectionManager::new());
+        let worker = shared.clone();
+        std::thread::spawn(move || {
+            worker
+                .write()
+                .add_selection(PathBuf::from("src/lib.rs"), None, None);
+        })
+        .join()
+        .unwrap();
+
+        assert_eq!(shared.read().len(), 1);
+        let snapshot = shared.snapshot();
+        shared.write().clear();
+        assert_eq!(snapshot.len(), 1);
+        assert!(shared.read().is_empty());
+    }
+
     #[test]
     fn adds_entire_file_and_replaces_existing_ranges() {
         let mut manager = SelectionManager::new();
@@ -303,6 +694,117 @@ mod tests {
         assert_eq!(manager.len(), 1);
     }
 
+    #[test]
+    fn changes_mark_the_selection_dirty_until_saved() {
+        let path: PathBuf = "src/lib.rs".into();
+        let mut manager = SelectionManager::from_items(vec![SelectionItem {
+            path: path.clone(),
+            range: Some((1, 4)),
+            note: None,
+            source: SelectionSource::Manual,
+        }]);
+        assert!(!manager.is_dirty());
+
+        assert!(!manager.remove_selection(Path::new("src/main.rs"), None));
+        assert!(!manager.set_note(&path, Some((2, 3)), Some("missing".into())));
+        assert!(!manager.is_dirty(), "no-op edits leave it clean");
+
+        manager.set_note(&path, Some((1, 4)), Some("parser".into()));
+        assert!(manager.is_dirty());
+        manager.mark_saved();
+        let revision = manager.revision();
+        manager.set_model("openai:gpt-4o");
+        assert_ne!(manager.revision(), revision);
+        assert!(manager.is_dirty());
+        manager.mark_saved();
+        manager.remove_selection(&path, None);
+        assert!(manager.is_dirty());
+    }
+
+    #[test]
+    fn normalize_coalesces_nearby_ranges_per_file() {
+        let mut manager = SelectionManager::new();
+        let lib: PathBuf = "src/lib.rs".into();
+        let main: PathBuf = "src/main.rs".into();
+        manager.add_selection(lib.clone(), Some((30, 40)), Some("parser".into()));
+        manager.add_selection(main.clone(), Some((1, 5)), None);
+        manager.add_selection(lib.clone(), Some((10, 20)), Some("lexer".into()));
+        manager.add_selection(lib.clone(), Some((23, 25)), None);
+        manager.add_selection(lib.clone(), Some((60, 70)), None);
+
+        let mut unchanged = manager.clone();
+        unchanged.normalize(0);
+        let ranges: Vec<_> = unchanged.items().iter().map(|item| item.range).collect();
+        assert_eq!(
+            ranges,
+            vec![
+                Some((10, 20)),
+                Some((23, 25)),
+                Some((30, 40)),
+                Some((60, 70)),
+                Some((1, 5))
+            ]
+        );
+
+        manager.normalize(5);
+        let items: Vec<_> = manager
+            .items()
+            .iter()
+            .map(|item| (item.path.clone(), item.range, item.note.clone()))
+            .collect();
+        assert_eq!(
+            items,
+            vec![
+                (
+                    lib.clone(),
+                    Some((10, 40)),
+                    Some("lexer; parser".to_string())
+                ),
+                (lib.clone(), Some((60, 70)), None),
+                (main.clone(), Some((1, 5)), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn normalize_emits_each_region_once_with_all_notes() {
+        let path: PathBuf = "src/lib.rs".into();
+        let item = |range, note: &str| SelectionItem {
+            path: path.clone(),
+            range,
+            note: Some(note.to_string()),
+            source: SelectionSource::Manual,
+        };
+        let mut manager = SelectionManager::from_items(vec![
+            item(Some((5, 10)), "parser"),
+            item(None, "whole file"),
+            item(Some((8, 12)), "parser"),
+        ]);
+        manager.normalize(0);
+        assert_eq!(manager.items(), &[item(None, "parser; whole file")][..]);
+
+        let mut manager = SelectionManager::from_items(vec![
+            item(Some((8, 12)), "lexer"),
+            item(Some((5, 10)), "parser"),
+        ]);
+        manager.normalize(0);
+        assert_eq!(manager.items(), &[item(Some((5, 12)), "parser; lexer")][..]);
+    }
+
+    #[test]
+    fn pad_ranges_clamps_to_file_bounds_and_merges() -> Result<()> {
+        let mut manager = SelectionManager::new();
+        let path: PathBuf = "src/lib.rs".into();
+        manager.add_selection(path.clone(), Some((2, 4)), None);
+        manager.add_selection(path.clone(), Some((10, 12)), None);
+        manager.add_selection(path.clone(), Some((40, 48)), None);
+
+        manager.pad_ranges(3, |_| Ok(50))?;
+        let ranges: Vec<_> = manager.items().iter().map(|item| item.range).collect();
+        assert_eq!(ranges, vec![Some((1, 15)), Some((37, 50))]);
+        Ok(())
+    }
+
     #[test]
     fn set_note_updates_existing_selection() {
         let mut manager = SelectionManager::new();
@@ -335,4 +837,211 @@ mod tests {
         assert_eq!(summary.items.len(), 1);
         assert!(summary.total_tokens > 0);
     }
+
+    #[test]
+    fn validate_flags_deleted_ignored_binary_and_bad_ranges() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::create_dir_all(root.join(".github")).unwrap();
+        std::fs::create_dir_all(root.join("target")).unwrap();
+        for (name, contents) in [
+            ("lib.rs", "one\ntwo\nthree\n"),
+            ("blob.bin", "\0\x01\x02\x03\0\0\0\x7f"),
+            ("debug.log", "log\n"),
+            (".gitignore", "target/\n"),
+            ("target/out.txt", "built\n"),
+            (".github/ci.yml", "on: push\n"),
+        ] {
+            std::fs::write(root.join(name), contents).unwrap();
+        }
+        let mut config = Config::default();
+        config.ignore.globs.push("*.log".into());
+        let rules = Scanner::new()
+            .ignore_rules(
+                &WorkspaceRoot::from_paths(std::slice::from_ref(&root)),
+                &config,
+            )
+            .unwrap();
+
+        let selection = |name: &str, range| SelectionItem {
+            path: root.join(name),
+            range,
+            note: None,
+            source: SelectionSource::Manual,
+        };
+        let manager = SelectionManager::from_items(vec![
+            selection("lib.rs", Some((1, 3))),
+            selection("lib.rs", Some((2, 8))),
+            selection("lib.rs", Some((7, 9))),
+            selection("blob.bin", None),
+            selection("debug.log", None),
+            selection("target/out.txt", None),
+            selection(".github/ci.yml", None),
+            selection("gone.rs", None),
+        ]);
+
+        let issues: Vec<_> = manager
+            .validate(&RealFs, &rules)
+            .into_iter()
+            .map(|warning| (warning.path, warning.range, warning.issue))
+            .collect();
+        assert_eq!(
+            issues,
+            vec![
+                (
+                    root.join("lib.rs"),
+                    Some((2, 8)),
+                    SelectionIssue::RangePastEnd { line_count: 3 }
+                ),
+                (
+                    root.join("lib.rs"),
+                    Some((7, 9)),
+                    SelectionIssue::EmptyRange
+                ),
+                (root.join("blob.bin"), None, SelectionIssue::Binary),
+                (root.join("debug.log"), None, SelectionIssue::Ignored),
+                (root.join("target/out.txt"), None, SelectionIssue::Ignored),
+                (root.join("gone.rs"), None, SelectionIssue::Deleted),
+            ]
+        );
+    }
+
+    #[test]
+    fn check_bounds_rejects_missing_files_and_ranges_past_the_end() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "one").unwrap();
+        writeln!(file, "two").unwrap();
+
+        assert_eq!(check_bounds(&RealFs, file.path(), Some((2, 40))), Ok(()));
+        assert_eq!(
+            check_bounds(&RealFs, file.path(), Some((3, 4))),
+            Err(DomainError::SelectionOutOfBounds {
+                path: file.path().to_path_buf(),
+                start: 3,
+                end: 4,
+                line_count: 2,
+            })
+        );
+        let missing = file.path().with_extension("missing");
+        assert_eq!(
+            check_bounds(&RealFs, &missing, None),
+            Err(DomainError::FileMissing { path: missing })
+        );
+    }
+
+    /// Lines a file's selections cover, or `None` when the whole file is selected.
+    type Coverage = Option<BTreeSet<usize>>;
+
+    const CASES: u32 = 500;
+
+    /// Up to 12 adds across three files, mostly of ranges given in either order and
+    /// sometimes starting at line 0, now and then of a whole file.
+    fn adds() -> impl Strategy<Value = Vec<(PathBuf, Option<(usize, usize)>)>> {
+        let path = prop::sample::select(vec!["a.rs", "b.rs", "c.rs"]).prop_map(PathBuf::from);
+        let range = prop::option::weighted(0.9, (0..60usize, 0..60usize));
+        prop::collection::vec((path, range), 1..=12)
+    }
+
+    fn coverage(items: &[SelectionItem], path: &Path) -> Coverage {
+        let mut lines = BTreeSet::new();
+        for item in items.iter().filter(|item| item.path == path) {
+            let (start, end) = item.range?;
+            lines.extend(start..=end);
+        }
+        Some(lines)
+    }
+
+    /// Paths in order of first appearance, checking that each path's items are adjacent and
+    /// its ranges ascend with at least `min_gap` lines between them.
+    fn grouped_paths(items: &[SelectionItem], min_gap: usize) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = Vec::new();
+        for pair in items.windows(2) {
+            let [previous, item] = pair else { continue };
+            if previous.path != item.path {
+                continue;
+            }
+            let (Some(previous), Some(range)) = (previous.range, item.range) else {
+                panic!("whole file selected alongside a range");
+            };
+            assert!(
+                range.0 > previous.1 + min_gap,
+                "{range:?} overlaps or is too close to {previous:?}"
+            );
+        }
+        for item in items {
+            if paths.last() != Some(&item.path) {
+                assert!(!paths.contains(&item.path), "items not grouped");
+                paths.push(item.path.clone());
+            }
+        }
+        paths
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(CASES))]
+
+        #[test]
+        fn random_adds_keep_each_files_ranges_disjoint_ordered_and_complete(adds in adds()) {
+            let mut manager = SelectionManager::new();
+            let mut expected: Vec<(PathBuf, Coverage)> = Vec::new();
+            for (path, range) in adds {
+                manager.add_selection(path.clone(), range, None);
+
+                let index = match expected.iter().position(|(known, _)| *known == path) {
+                    Some(index) => index,
+                    None => {
+                        expected.push((path, Some(BTreeSet::new())));
+                        expected.len() - 1
+                    }
+                };
+                match (range, &mut expected[index].1) {
+                    (None, coverage) => *coverage = None,
+                    (Some((a, b)), Some(lines)) => lines.extend(a.min(b).max(1)..=a.max(b).max(1)),
+                    (Some(_), None) => {}
+                }
+            }
+
+            let paths = grouped_paths(manager.items(), 1);
+            let expected_paths: Vec<PathBuf> =
+                expected.iter().map(|(path, _)| path.clone()).collect();
+            prop_assert_eq!(paths, expected_paths);
+            for (path, lines) in &expected {
+                prop_assert_eq!(&coverage(manager.items(), path), lines, "{:?}", path);
+            }
+        }
+
+        #[test]
+        fn normalize_only_fills_gaps_narrower_than_the_merge_gap(
+            adds in adds(),
+            merge_gap in 0..6usize,
+        ) {
+            let mut manager = SelectionManager::new();
+            for (path, range) in adds {
+                manager.add_selection(path, range, None);
+            }
+            let before = manager.clone();
+
+            manager.normalize(merge_gap);
+            let paths = grouped_paths(manager.items(), merge_gap.max(1));
+            prop_assert_eq!(&paths, &grouped_paths(before.items(), 1));
+            for path in &paths {
+                let (old, new) = (
+                    coverage(before.items(), path),
+                    coverage(manager.items(), path),
+                );
+                let (Some(old), Some(new)) = (old.clone(), new.clone()) else {
+                    prop_assert_eq!(old, new, "whole file selection changed");
+                    continue;
+                };
+                prop_assert!(new.is_superset(&old), "lines dropped");
+                prop_assert_eq!(new.first(), old.first(), "start moved");
+                prop_assert_eq!(new.last(), old.last(), "end moved");
+            }
+
+            let once = manager.items().to_vec();
+            manager.normalize(merge_gap);
+            prop_assert_eq!(manager.items(), once.as_slice(), "not idempotent");
+        }
+    }
 }
@@ -0,0 +1,560 @@
+//! Similarity search over an index of file chunk vectors kept in `.llmctx/index/`.
+//!
+//! Chunks are turned into vectors by an [`Embedder`] and ranked by cosine similarity to the
+//! query. Built with the `embeddings` feature, llmctx embeds chunks with a local sentence
+//! embedding model ([`ModelEmbedder`]), which also finds code that means the same thing as the
+//! query in other words. Without it, the [`LexicalEmbedder`] fallback compares words and word
+//! fragments, so it only finds code that shares vocabulary with the query.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use xxhash_rust::xxh3::xxh3_64;
+
+use crate::app::scan::FileMetadata;
+use crate::infra::fs::ContentFingerprint;
+
+const INDEX_DIR: &str = ".llmctx/index";
+const INDEX_FILE: &str = "semantic.json";
+/// Lines per chunk, and how far apart chunks start, so neighbouring chunks overlap.
+const CHUNK_LINES: usize = 40;
+const CHUNK_STEP: usize = 30;
+/// Characters of a chunk's first line kept to describe it.
+const SUMMARY_CHARS: usize = 120;
+
+/// Turns text into a fixed-length vector whose cosine similarity tracks relatedness.
+pub trait Embedder: Sync {
+    /// Identifies the model; an index built with another model is rebuilt from scratch.
+    fn id(&self) -> &str;
+    /// What the similarity measures, for messages: `semantic` or `lexical`.
+    fn kind(&self) -> &'static str;
+    /// One unit vector per text, in order.
+    fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>>;
+}
+
+/// The embedder new indexes are built with: the local model with the `embeddings` feature,
+/// [`LexicalEmbedder`] without it.
+pub fn default_embedder() -> Result<Box<dyn Embedder>> {
+    #[cfg(feature = "embeddings")]
+    return Ok(Box::new(ModelEmbedder::new()?));
+    #[cfg(not(feature = "embeddings"))]
+    return Ok(Box::new(LexicalEmbedder));
+}
+
+/// Sentence embeddings from a local ONNX model (BGE small, English) run by fastembed.
+///
+/// The model is downloaded to the llmctx cache directory on first use. ONNX Runtime is loaded
+/// at run time from the shared library named by `ORT_DYLIB_PATH`, or found on the library path.
+#[cfg(feature = "embeddings")]
+pub struct ModelEmbedder {
+    model: std::sync::Mutex<fastembed::TextEmbedding>,
+}
+
+#[cfg(feature = "embeddings")]
+impl ModelEmbedder {
+    const ID: &str = "fastembed-bge-small-en-v1.5";
+
+    pub fn new() -> Result<Self> {
+        let cache = dirs_next::cache_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join("llmctx")
+            .join("models");
+        let options = fastembed::InitOptions::new(fastembed::EmbeddingModel::BGESmallENV15)
+            .with_cache_dir(cache)
+            .with_show_download_progress(false);
+        let model = fastembed::TextEmbedding::try_new(options)
+            .context("failed to load the embedding model")?;
+        Ok(Self {
+            model: std::sync::Mutex::new(model),
+        })
+    }
+}
+
+#[cfg(feature = "embeddings")]
+impl Embedder for ModelEmbedder {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn kind(&self) -> &'static str {
+        "semantic"
+    }
+
+    fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        let mut vectors = self
+            .model
+            .lock()
+            .unwrap()
+            .embed(texts, None)
+            .context("failed to embed text")?;
+        vectors.iter_mut().for_each(|vector| normalize(vector));
+        Ok(vectors)
+    }
+}
+
+/// Lexical similarity, the fallback without the `embeddings` feature: identifier words and
+/// their trigrams hashed into a fixed vector, with no model or downloads.
+///
+/// `parseConfigFile` contributes `parse`, `config`, and `file` plus trigrams such as `con`, so
+/// `load_configuration` still lands near it, while `read_settings` does not.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LexicalEmbedder;
+
+impl LexicalEmbedder {
+    const ID: &str = "hashing-v1";
+    const DIMENSIONS: usize = 256;
+
+    fn embed_one(&self, text: &str) -> Vec<f32> {
+        let mut features: HashMap<String, f32> = HashMap::new();
+        for word in identifier_words(text) {
+            if word.chars().count() >= 4 {
+                let chars: Vec<char> = word.chars().collect();
+                for trigram in chars.windows(3) {
+                    *features
+                        .entry(format!("t:{}", trigram.iter().collect::<String>()))
+                        .or_default() += 0.5;
+                }
+            }
+            *features.entry(format!("w:{word}")).or_default() += 1.0;
+        }
+
+        let mut vector = vec![0.0f32; Self::DIMENSIONS];
+        for (feature, weight) in features {
+            let hash = xxh3_64(feature.as_bytes());
+            let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+            // Sublinear weighting keeps repeated words from dominating a chunk.
+            vector[hash as usize % Self::DIMENSIONS] += sign * (1.0 + weight.ln().max(0.0));
+        }
+        normalize(&mut vector);
+        vector
+    }
+}
+
+impl Embedder for LexicalEmbedder {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn kind(&self) -> &'static str {
+        "lexical"
+    }
+
+    fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        Ok(texts.iter().map(|text| self.embed_one(text)).collect())
+    }
+}
+
+fn normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|value| value * value).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|value| *value /= norm);
+    }
+}
+
+/// Lowercase words of `text`, with identifiers split at `_`, digits, and camelCase humps.
+pub(crate) fn identifier_words(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    for token in text.split(|ch: char| !ch.is_alphanumeric()) {
+        let mut word = String::new();
+        let mut previous_lower = false;
+        for ch in token.chars() {
+            if ch.is_ascii_digit() || (ch.is_uppercase() && previous_lower) {
+                push_word(&mut words, &mut word);
+            }
+            if !ch.is_ascii_digit() {
+                word.extend(ch.to_lowercase());
+            }
+            previous_lower = ch.is_lowercase();
+        }
+        push_word(&mut words, &mut word);
+    }
+    words
+}
+
+fn push_word(words: &mut Vec<String>, word: &mut String) {
+    if word.chars().count() >= 2 {
+        words.push(std::mem::take(word));
+    } else {
+        word.clear();
+    }
+}
+
+/// Embedded span of lines in an indexed file.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IndexedChunk {
+    /// 1-based inclusive line range.
+    pub start: usize,
+    pub end: usize,
+    /// First non-blank line, shortened, to describe the chunk in results.
+    pub summary: String,
+    /// Unit vector quantized to `i8` to keep the index small.
+    pub vector: Vec<i8>,
+}
+
+/// Chunks of one file and the contents they were computed from.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IndexedFile {
+    /// Path relative to the indexed root, or absolute outside it.
+    pub path: PathBuf,
+    pub fingerprint: ContentFingerprint,
+    pub chunks: Vec<IndexedChunk>,
+}
+
+/// Counts from one [`SemanticIndex::update`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IndexUpdate {
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+    pub unchanged: usize,
+}
+
+enum Outcome {
+    Added,
+    Updated,
+    Unchanged,
+}
+
+/// Chunk ranked against a query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SemanticMatch {
+    pub path: PathBuf,
+    pub start: usize,
+    pub end: usize,
+    pub summary: String,
+    /// Cosine similarity to the query, from -1 to 1.
+    pub score: f32,
+}
+
+/// Embeddings of workspace file chunks.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SemanticIndex {
+    /// [`Embedder::id`] of the model that produced the vectors.
+    pub embedder: String,
+    pub files: Vec<IndexedFile>,
+}
+
+impl SemanticIndex {
+    /// Bring the index in line with `files`, re-embedding only files whose contents changed.
+    ///
+    /// Directories, skipped files, and files that are not UTF-8 text are left out; files no
+    /// longer scanned are dropped.
+    pub fn update(
+        &mut self,
+        root: &Path,
+        files: &[FileMetadata],
+        embedder: &dyn Embedder,
+    ) -> Result<IndexUpdate> {
+        if self.embedder != embedder.id() {
+            self.embedder = embedder.id().to_string();
+            self.files.clear();
+        }
+        let mut previous: HashMap<PathBuf, IndexedFile> = self
+            .files
+            .drain(..)
+            .map(|file| (file.path.clone(), file))
+            .collect();
+
+        let candidates: Vec<(PathBuf, &Path, Option<IndexedFile>)> = files
+            .iter()
+            .filter(|meta| !meta.is_dir && meta.skipped.is_none())
+            .map(|meta| {
+                let relative = meta
+                    .path
+                    .strip_prefix(root)
+                    .unwrap_or(&meta.path)
+                    .to_path_buf();
+                let existing = previous.remove(&relative);
+                (relative, meta.path.as_path(), existing)
+            })
+            .collect();
+        let mut update = IndexUpdate {
+            removed: previous.len(),
+            ..IndexUpdate::default()
+        };
+
+        let indexed: Vec<Option<(IndexedFile, Outcome)>> = candidates
+            .into_par_iter()
+            .map(|(relative, path, existing)| {
+                let Ok(fingerprint) = ContentFingerprint::of_file(path) else {
+                    return Ok(None);
+                };
+                let outcome = match existing {
+                    Some(existing) if existing.fingerprint == fingerprint => {
+                        return Ok(Some((existing, Outcome::Unchanged)));
+                    }
+                    Some(_) => Outcome::Updated,
+                    None => Outcome::Added,
+                };
+                let Ok(contents) = fs::read_to_string(path) else {
+                    return Ok(None);
+                };
+                let file = IndexedFile {
+                    path: relative,
+                    fingerprint,
+                    chunks: chunk_file(&contents, embedder)?,
+                };
+                Ok(Some((file, outcome)))
+            })
+            .collect::<Result<_>>()?;
+        for (file, outcome) in indexed.into_iter().flatten() {
+            match outcome {
+                Outcome::Added => update.added += 1,
+                Outcome::Updated => update.updated += 1,
+                Outcome::Unchanged => update.unchanged += 1,
+            }
+            self.files.push(file);
+        }
+        Ok(update)
+    }
+
+    /// An embedder producing the same kind of vectors the index holds, for querying it.
+    pub fn embedder(&self) -> Result<Box<dyn Embedder>> {
+        match self.embedder.as_str() {
+            LexicalEmbedder::ID => Ok(Box::new(LexicalEmbedder)),
+            #[cfg(feature = "embeddings")]
+            ModelEmbedder::ID => Ok(Box::new(ModelEmbedder::new()?)),
+            other => bail!(
+                "the index was built with the '{other}' embedder, which this build of llmctx \
+                 lacks; rebuild it with `llmctx index --rebuild`"
+            ),
+        }
+    }
+
+    /// Number of embedded chunks across all files.
+    pub fn chunk_count(&self) -> usize {
+        self.files.iter().map(|file| file.chunks.len()).sum()
+    }
+
+    /// Up to `limit` chunks most similar to `query`, best first, with paths resolved
+    /// against `root`.
+    pub fn query(
+        &self,
+        root: &Path,
+        embedder: &dyn Embedder,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<SemanticMatch>> {
+        let query = embedder.embed(&[query])?.pop().unwrap_or_default();
+        let mut matches: Vec<SemanticMatch> = self
+            .files
+            .iter()
+            .flat_map(|file| {
+                let query = &query;
+                file.chunks.iter().map(move |chunk| SemanticMatch {
+                    path: root.join(&file.path),
+                    start: chunk.start,
+                    end: chunk.end,
+                    summary: chunk.summary.clone(),
+                    score: cosine(query, &chunk.vector),
+                })
+            })
+            .filter(|found| found.score > 0.0)
+            .collect();
+        matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+        matches.truncate(limit);
+        Ok(matches)
+    }
+}
+
+/// Split `contents` into overlapping chunks and embed them in one batch.
+fn chunk_file(contents: &str, embedder: &dyn Embedder) -> Result<Vec<IndexedChunk>> {
+    let lines: Vec<&str> = contents.lines().collect();
+    let mut chunks = Vec::new();
+    let mut texts = Vec::new();
+    let mut start = 0;
+    while start < lines.len() {
+        let end = (start + CHUNK_LINES).min(lines.len());
+        let text = lines[start..end].join("\n");
+        if !text.trim().is_empty() {
+            let summary = lines[start..end]
+                .iter()
+                .map(|line| line.trim())
+                .find(|line| !line.is_empty())
+                .unwrap_or_default()
+                .chars()
+                .take(SUMMARY_CHARS)
+                .collect();
+            chunks.push(IndexedChunk {
+                start: start + 1,
+                end,
+                summary,
+                vector: Vec::new(),
+            });
+            texts.push(text);
+        }
+        if end == lines.len() {
+            break;
+        }
+        start += CHUNK_STEP;
+    }
+    if texts.is_empty() {
+        return Ok(chunks);
+    }
+    let texts: Vec<&str> = texts.iter().map(String::as_str).collect();
+    for (chunk, vector) in chunks.iter_mut().zip(embedder.embed(&texts)?) {
+        chunk.vector = quantize(&vector);
+    }
+    Ok(chunks)
+}
+
+fn quantize(vector: &[f32]) -> Vec<i8> {
+    vector
+        .iter()
+        .map(|value| (value.clamp(-1.0, 1.0) * 127.0).round() as i8)
+        .collect()
+}
+
+fn cosine(query: &[f32], chunk: &[i8]) -> f32 {
+    let (mut dot, mut norm) = (0.0f32, 0.0f32);
+    for (a, b) in query.iter().zip(chunk) {
+        let b = f32::from(*b);
+        dot += a * b;
+        norm += b * b;
+    }
+    if norm == 0.0 { 0.0 } else { dot / norm.sqrt() }
+}
+
+/// Persists the index to `.llmctx/index/semantic.json`.
+#[derive(Debug, Clone)]
+pub struct SemanticIndexStore {
+    path: PathBuf,
+}
+
+impl SemanticIndexStore {
+    /// Create a store for the workspace rooted at `root`.
+    pub fn new(root: impl AsRef<Path>) -> Self {
+        Self {
+            path: root.as_ref().join(INDEX_DIR).join(INDEX_FILE),
+        }
+    }
+
+    /// Location of the index file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Load the index, or `None` when it has not been built yet.
+    pub fn load(&self) -> Result<Option<SemanticIndex>> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+        let data = fs::read(&self.path).with_context(|| {
+            format!("failed to read similarity index at {}", self.path.display())
+        })?;
+        let index = serde_json::from_slice(&data)
+            .with_context(|| format!("invalid similarity index in {}", self.path.display()))?;
+        Ok(Some(index))
+    }
+
+    /// Write `index`, creating `.llmctx/index/` as needed.
+    pub fn save(&self, index: &SemanticIndex) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("failed to create index directory {}", dir.display()))?;
+        }
+        let data = serde_json::to_vec(index).context("failed to serialize similarity index")?;
+        fs::write(&self.path, data).with_context(|| {
+            format!(
+                "failed to write similarity index to {}",
+                self.path.display()
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::app::scan::FileClass;
+
+    fn meta(path: PathBuf) -> FileMetadata {
+        FileMetadata {
+            display_path: path.display().to_string(),
+            path,
+            is_dir: false,
+            size: None,
+            modified: None,
+            language: None,
+            skipped: None,
+            class: FileClass::Source,
+        }
+    }
+
+    #[test]
+    fn splits_identifiers_into_words() {
+        assert_eq!(
+            identifier_words("parseConfigFile(load_HTTP2_settings)"),
+            vec!["parse", "config", "file", "load", "http", "settings"]
+        );
+    }
+
+    #[test]
+    fn ranks_related_chunks_and_reindexes_only_changes() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = temp.path();
+        fs::write(
+            root.join("config.rs"),
+            "fn load_configuration(path: &Path) -> Config {\n    parse_config_file(path)\n}\n",
+        )?;
+        fs::write(
+            root.join("render.rs"),
+            "fn draw_frame(frame: &mut Frame) {\n    frame.render_widget(block);\n}\n",
+        )?;
+        let files = vec![meta(root.join("config.rs")), meta(root.join("render.rs"))];
+
+        let embedder = LexicalEmbedder;
+        let mut index = SemanticIndex::default();
+        let update = index.update(root, &files, &embedder)?;
+        assert_eq!((update.added, update.unchanged), (2, 0));
+        assert_eq!(index.embedder()?.id(), embedder.id());
+
+        let matches = index.query(root, &embedder, "where is the config file parsed", 5)?;
+        assert_eq!(matches[0].path, root.join("config.rs"));
+        assert_eq!((matches[0].start, matches[0].end), (1, 3));
+        assert_eq!(
+            matches[0].summary,
+            "fn load_configuration(path: &Path) -> Config {"
+        );
+
+        fs::write(root.join("render.rs"), "fn draw() {}\n")?;
+        let update = index.update(root, &files[1..], &embedder)?;
+        assert_eq!(
+            update,
+            IndexUpdate {
+                added: 0,
+                updated: 1,
+                removed: 1,
+                unchanged: 0
+            }
+        );
+
+        let store = SemanticIndexStore::new(root);
+        assert!(store.load()?.is_none());
+        store.save(&index)?;
+        assert_eq!(store.load()?, Some(index));
+
+        let foreign = SemanticIndex {
+            embedder: "some-other-model".to_string(),
+            files: Vec::new(),
+        };
+        assert!(foreign.embedder().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn long_files_are_split_into_overlapping_chunks() {
+        let contents: String = (1..=75).map(|line| format!("line {line}\n")).collect();
+        let ranges: Vec<_> = chunk_file(&contents, &LexicalEmbedder)
+            .unwrap()
+            .iter()
+            .map(|chunk| (chunk.start, chunk.end))
+            .collect();
+        assert_eq!(ranges, vec![(1, 40), (31, 70), (61, 75)]);
+    }
+}
@@ -1,12 +1,14 @@
 //! Session persistence utilities.
 
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
-use crate::domain::model::SelectionItem;
+use crate::domain::model::{SchemaVersion, SelectionItem, SelectionSource};
+use crate::infra::fs::{ContentFingerprint, Vfs};
 
 const SESSION_DIR: &str = ".llmctx";
 const SESSION_FILE: &str = "session.json";
@@ -14,6 +16,9 @@ const SESSION_FILE: &str = "session.json";
 /// Snapshot of interactive UI state persisted between sessions.
 #[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
 pub struct SessionSnapshot {
+    /// Schema the session was written with; sessions from a newer llmctx are refused.
+    #[serde(default)]
+    pub schema_version: SchemaVersion,
     /// Previously selected items restored into the selection manager.
     pub selections: Vec<SelectionRecord>,
     /// Path of the file that was focused when the session closed.
@@ -22,33 +27,63 @@ pub struct SessionSnapshot {
     pub filter: Option<String>,
     /// User configured model override if any.
     pub model: Option<String>,
+    /// Jump marks set with `m<letter>`, by letter; `'` is the place before the last jump.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub marks: BTreeMap<char, JumpMark>,
 }
 
-/// Serializable representation of a [`SelectionItem`].
-#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
-pub struct SelectionRecord {
+/// A place to jump back to: a tree entry, or a line when set from the preview.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct JumpMark {
+    /// Path as displayed in the file tree.
     pub path: String,
-    pub range: Option<(usize, usize)>,
-    pub note: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+}
+
+/// A persisted [`SelectionItem`] plus what is needed to notice the file changed since.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SelectionRecord {
+    /// The selection, usually with its path relative to the workspace or repository.
+    #[serde(flatten)]
+    pub item: SelectionItem,
+    /// Contents of the file when the session was saved, used to flag stale selections.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<ContentFingerprint>,
 }
 
 impl From<&SelectionItem> for SelectionRecord {
     fn from(value: &SelectionItem) -> Self {
         Self {
-            path: value.path.display().to_string(),
-            range: value.range,
-            note: value.note.clone(),
+            item: value.clone(),
+            fingerprint: None,
         }
     }
 }
 
 impl SelectionRecord {
-    /// Convert the record back into a domain [`SelectionItem`].
+    /// Convert the record back into a domain [`SelectionItem`] marked as restored.
     pub fn into_selection_item(self) -> SelectionItem {
         SelectionItem {
-            path: PathBuf::from(self.path),
-            range: self.range,
-            note: self.note,
+            source: SelectionSource::SessionRestore,
+            ..self.item
+        }
+    }
+
+    /// Remember the contents of `path`, as `vfs` reads it, so later restores can detect edits.
+    pub fn capture_fingerprint(&mut self, vfs: &dyn Vfs, path: &Path) {
+        self.fingerprint = ContentFingerprint::of_vfs(vfs, path).ok();
+    }
+
+    /// Whether `path` no longer matches the contents recorded at save time.
+    ///
+    /// Records saved without a fingerprint are never reported as stale.
+    pub fn is_stale(&self, vfs: &dyn Vfs, path: &Path) -> bool {
+        match self.fingerprint {
+            Some(expected) => ContentFingerprint::of_vfs(vfs, path)
+                .map(|current| current != expected)
+                .unwrap_or(true),
+            None => false,
         }
     }
 }
@@ -86,6 +121,16 @@ impl SessionStore {
         Ok(Some(snapshot))
     }
 
+    /// Remove the persisted session, if there is one.
+    pub fn delete(&self) -> Result<()> {
+        match fs::remove_file(&self.path) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err).with_context(|| {
+                format!("failed to delete session file at {}", self.path.display())
+            }),
+            _ => Ok(()),
+        }
+    }
+
     /// Persist the provided snapshot to disk, creating parent directories as needed.
     pub fn save(&self, snapshot: &SessionSnapshot) -> Result<()> {
         let dir = self.path.parent().unwrap_or(&self.root);
@@ -99,3 +144,64 @@ impl SessionStore {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::domain::model::SCHEMA_VERSION;
+
+    #[test]
+    fn marks_round_trip_keyed_by_letter() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let store = SessionStore::new(temp.path());
+        let mut snapshot = SessionSnapshot::default();
+        snapshot.marks.insert(
+            'a',
+            JumpMark {
+                path: "src/lib.rs".into(),
+                line: Some(42),
+            },
+        );
+        snapshot.marks.insert(
+            '\'',
+            JumpMark {
+                path: "README.md".into(),
+                line: None,
+            },
+        );
+        store.save(&snapshot)?;
+
+        let data = fs::read_to_string(store.path())?;
+        assert!(data.contains(r#""a": {"#), "{data}");
+        assert_eq!(store.load()?, Some(snapshot));
+        Ok(())
+    }
+
+    #[test]
+    fn sessions_carry_a_schema_version_and_refuse_newer_ones() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let store = SessionStore::new(temp.path());
+        store.save(&SessionSnapshot::default())?;
+        let data: serde_json::Value = serde_json::from_str(&fs::read_to_string(store.path())?)?;
+        assert_eq!(data["schema_version"], SCHEMA_VERSION);
+
+        // Sessions written before the tag existed still load.
+        fs::write(store.path(), r#"{"selections": []}"#)?;
+        assert_eq!(store.load()?, Some(SessionSnapshot::default()));
+
+        fs::write(
+            store.path(),
+            format!(
+                r#"{{"schema_version": {}, "selections": []}}"#,
+                SCHEMA_VERSION + 1
+            ),
+        )?;
+        let err = store.load().unwrap_err();
+        assert!(
+            format!("{err:#}").contains("newer than the supported version"),
+            "{err:#}"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,114 @@
+//! Reusable instruction snippets kept in `.llmctx/snippets/` and appended to exports by name.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow};
+
+/// Location of the snippets directory relative to the workspace root.
+pub const SNIPPETS_DIR: &str = ".llmctx/snippets";
+
+/// A Markdown snippet, e.g. a review checklist or an answer schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snippet {
+    /// File stem under [`SNIPPETS_DIR`], as passed to `--with-snippet`.
+    pub name: String,
+    pub body: String,
+}
+
+/// Read the snippets called `names` from the workspace at `root`, in order, skipping repeats.
+pub fn load_snippets(root: &Path, names: &[String]) -> Result<Vec<Snippet>> {
+    let dir = root.join(SNIPPETS_DIR);
+    let mut snippets: Vec<Snippet> = Vec::new();
+    for name in names {
+        let name = name.trim();
+        if snippets.iter().any(|snippet| snippet.name == name) {
+            continue;
+        }
+        if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+            return Err(anyhow!("invalid snippet name '{name}'"));
+        }
+        let path = dir.join(format!("{name}.md"));
+        if !path.is_file() {
+            return Err(unknown_snippet(&dir, name));
+        }
+        let body = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read snippet {}", path.display()))?;
+        snippets.push(Snippet {
+            name: name.to_string(),
+            body: body.trim().to_string(),
+        });
+    }
+    Ok(snippets)
+}
+
+/// Names of the snippets in `dir`, sorted.
+fn snippet_names(dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "md"))
+        .filter_map(|path| Some(path.file_stem()?.to_string_lossy().into_owned()))
+        .collect();
+    names.sort();
+    names
+}
+
+fn unknown_snippet(dir: &Path, name: &str) -> anyhow::Error {
+    let available = snippet_names(dir);
+    if available.is_empty() {
+        anyhow!(
+            "unknown snippet '{name}'; add it as {}/{name}.md",
+            dir.display()
+        )
+    } else {
+        anyhow!(
+            "unknown snippet '{name}' (available: {})",
+            available.join(", ")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snippets_load_in_order_once_each() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let dir = temp.path().join(SNIPPETS_DIR);
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join("review-checklist.md"), "## Review\n- tests?\n\n")?;
+        fs::write(dir.join("answer-schema.md"), "Answer as JSON.\n")?;
+
+        let names: Vec<String> = ["answer-schema", "review-checklist", "answer-schema"]
+            .map(String::from)
+            .to_vec();
+        let snippets = load_snippets(temp.path(), &names)?;
+        assert_eq!(
+            snippets,
+            vec![
+                Snippet {
+                    name: "answer-schema".to_string(),
+                    body: "Answer as JSON.".to_string(),
+                },
+                Snippet {
+                    name: "review-checklist".to_string(),
+                    body: "## Review\n- tests?".to_string(),
+                },
+            ]
+        );
+
+        let err = load_snippets(temp.path(), &["style".to_string()]).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("available: answer-schema, review-checklist"),
+            "{err}"
+        );
+        assert!(load_snippets(temp.path(), &["../secrets".to_string()]).is_err());
+        Ok(())
+    }
+}
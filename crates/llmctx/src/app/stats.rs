@@ -0,0 +1,224 @@
+//! Workspace statistics for planning what to include: languages, token-heavy directories,
+//! excluded files, and recent git activity.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::app::scan::{
+    FileMetadata, IgnoredCounts, ScanResult, Scanner, SkipReason, WorkspaceRoot,
+};
+use crate::app::tokens::{TokenEstimator, TokenModel};
+use crate::infra::config::Config;
+use crate::infra::git::{self, GitActivity};
+
+/// Languages listed before the rest are folded into "other".
+const TOP_LANGUAGES: usize = 8;
+/// Directories listed by estimated tokens.
+const TOP_DIRECTORIES: usize = 8;
+/// Period of git history summarised.
+const ACTIVITY_DAYS: u32 = 30;
+
+/// Files of one language in the workspace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanguageCount {
+    pub language: String,
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// Estimated tokens for the files beneath a top-level directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectoryTokens {
+    /// Display path with a trailing `/`; `./` stands for files at the top of a root.
+    pub path: String,
+    pub files: usize,
+    pub tokens: usize,
+}
+
+/// Summary of a scanned workspace.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WorkspaceStats {
+    pub files: usize,
+    pub bytes: u64,
+    /// Most common languages (file extensions) by file count, with files of other or no
+    /// language folded into a final "other".
+    pub languages: Vec<LanguageCount>,
+    /// Directories with the most estimated tokens, largest first.
+    pub directories: Vec<DirectoryTokens>,
+    pub too_large: usize,
+    pub binary: usize,
+    /// Entries left out by ignore rules; `None` when the scan was truncated.
+    pub ignored: Option<IgnoredCounts>,
+    pub truncated: bool,
+    /// Recent commits; `None` outside a repository.
+    pub activity: Option<GitActivity>,
+}
+
+impl WorkspaceStats {
+    /// Gather statistics for `scan`, estimating tokens with `model` (or the estimator's own).
+    ///
+    /// Reads every scanned file unless `tokens.approximate` estimates them from their sizes, so
+    /// callers should run it off the UI thread. Ignored-entry and git lookups that fail are left
+    /// out rather than failing the whole summary.
+    pub fn collect(
+        scan: &ScanResult,
+        roots: &[WorkspaceRoot],
+        config: &Config,
+        estimator: &TokenEstimator,
+        model: Option<TokenModel>,
+    ) -> Self {
+        let mut stats = Self::from_files(&scan.files);
+        stats.truncated = scan.truncated.is_some();
+
+        let mut by_directory: HashMap<String, Vec<(PathBuf, u64)>> = HashMap::new();
+        for meta in included_files(&scan.files) {
+            by_directory
+                .entry(top_directory(&meta.display_path))
+                .or_default()
+                .push((meta.path.clone(), meta.size.unwrap_or(0)));
+        }
+        let approximate = config.tokens.approximate();
+        stats.directories = by_directory
+            .into_iter()
+            .map(|(path, files)| {
+                let estimate = if approximate {
+                    estimator.approximate_files(model, &files)
+                } else {
+                    let paths: Vec<PathBuf> = files.into_iter().map(|(path, _)| path).collect();
+                    estimator.estimate_files(model, &paths)
+                };
+                DirectoryTokens {
+                    path,
+                    files: estimate.files,
+                    tokens: estimate.tokens,
+                }
+            })
+            .collect();
+        stats
+            .directories
+            .sort_by(|a, b| b.tokens.cmp(&a.tokens).then_with(|| a.path.cmp(&b.path)));
+        stats.directories.truncate(TOP_DIRECTORIES);
+
+        if !stats.truncated {
+            stats.ignored = Scanner::new()
+                .ignored_entries(roots, config, scan)
+                .inspect_err(|err| tracing::debug!(error = %err, "ignored entry count failed"))
+                .ok();
+        }
+        stats.activity = git::activity_for_path(&scan.root, ACTIVITY_DAYS)
+            .inspect_err(|err| tracing::debug!(error = %err, "git activity lookup failed"))
+            .ok()
+            .flatten();
+        stats
+    }
+
+    /// File, size, language, and skip counts, which need no file contents.
+    fn from_files(files: &[FileMetadata]) -> Self {
+        let mut stats = Self::default();
+        let mut languages: HashMap<String, LanguageCount> = HashMap::new();
+        for meta in files.iter().filter(|meta| !meta.is_dir) {
+            stats.files += 1;
+            stats.bytes += meta.size.unwrap_or(0);
+            match meta.skipped {
+                Some(SkipReason::LargeFile) => stats.too_large += 1,
+                Some(SkipReason::BinaryFile) => stats.binary += 1,
+                None => {}
+            }
+            let language = meta.language.as_deref().unwrap_or("other");
+            let count = languages
+                .entry(language.to_string())
+                .or_insert_with(|| LanguageCount {
+                    language: language.to_string(),
+                    files: 0,
+                    bytes: 0,
+                });
+            count.files += 1;
+            count.bytes += meta.size.unwrap_or(0);
+        }
+
+        let mut other = languages.remove("other");
+        let mut languages: Vec<LanguageCount> = languages.into_values().collect();
+        languages.sort_by(|a, b| {
+            b.files
+                .cmp(&a.files)
+                .then_with(|| a.language.cmp(&b.language))
+        });
+        if languages.len() >= TOP_LANGUAGES {
+            let other = other.get_or_insert_with(|| LanguageCount {
+                language: "other".to_string(),
+                files: 0,
+                bytes: 0,
+            });
+            for count in languages.split_off(TOP_LANGUAGES - 1) {
+                other.files += count.files;
+                other.bytes += count.bytes;
+            }
+        }
+        languages.extend(other);
+        stats.languages = languages;
+        stats
+    }
+}
+
+/// Files whose contents can be estimated: not directories, too large, or binary.
+fn included_files(files: &[FileMetadata]) -> impl Iterator<Item = &FileMetadata> {
+    files
+        .iter()
+        .filter(|meta| !meta.is_dir && meta.skipped.is_none())
+}
+
+/// `src/` for `src/app/mod.rs`, `./` for `README.md`.
+fn top_directory(display_path: &str) -> String {
+    match display_path.split_once('/') {
+        Some((first, _)) => format!("{first}/"),
+        None => "./".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::app::scan::FileClass;
+
+    fn file(display_path: &str, language: Option<&str>, size: u64) -> FileMetadata {
+        FileMetadata {
+            path: PathBuf::from("/repo").join(display_path),
+            display_path: display_path.to_string(),
+            is_dir: false,
+            size: Some(size),
+            modified: None,
+            language: language.map(str::to_string),
+            skipped: None,
+            class: FileClass::Source,
+        }
+    }
+
+    #[test]
+    fn counts_languages_and_skipped_files() {
+        let mut files = vec![
+            file("src/lib.rs", Some("rs"), 100),
+            file("src/main.rs", Some("rs"), 50),
+            file("README.md", Some("md"), 30),
+            file("logo.png", None, 2_000),
+        ];
+        files[3].skipped = Some(SkipReason::BinaryFile);
+        files.push(FileMetadata {
+            is_dir: true,
+            size: None,
+            ..file("src", None, 0)
+        });
+
+        let stats = WorkspaceStats::from_files(&files);
+        assert_eq!((stats.files, stats.bytes), (4, 2_180));
+        assert_eq!((stats.too_large, stats.binary), (0, 1));
+        let languages: Vec<_> = stats
+            .languages
+            .iter()
+            .map(|count| (count.language.as_str(), count.files))
+            .collect();
+        assert_eq!(languages, vec![("rs", 2), ("md", 1), ("other", 1)]);
+        assert_eq!(top_directory("src/app/mod.rs"), "src/");
+        assert_eq!(top_directory("README.md"), "./");
+    }
+}
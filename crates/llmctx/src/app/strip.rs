@@ -0,0 +1,357 @@
+//! Export transforms that remove comments, blank runs, and stray whitespace to save tokens.
+//!
+//! Comments are recognised per language from the file extension with a small scanner that
+//! skips string literals on the same line; multi-line strings and nested block comments are not
+//! tracked. Kept lines retain their original line numbers so gutters and ranges stay accurate.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::infra::config::Config;
+
+/// Which parts of selection contents to strip before rendering.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StripOptions {
+    /// Remove comments, dropping lines that held nothing else.
+    pub comments: bool,
+    /// Collapse runs of blank lines into a single blank line.
+    pub blank_lines: bool,
+    /// Remove whitespace at the end of each line.
+    pub trailing_whitespace: bool,
+    /// Expand tabs to spaces at stops this many columns apart; `0` keeps tabs.
+    pub tab_width: usize,
+}
+
+impl StripOptions {
+    /// Parse setting names such as `comments` and `blank-lines`; `none` clears them.
+    ///
+    /// The tab width is not named here and is left at `0`.
+    pub fn from_names<'a>(
+        names: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Self, StripParseError> {
+        let mut options = Self::default();
+        for name in names {
+            match name.trim().to_ascii_lowercase().as_str() {
+                "" | "none" => {}
+                "comments" => options.comments = true,
+                "blank-lines" | "blank_lines" | "blanks" => options.blank_lines = true,
+                "trailing-whitespace" | "trailing_whitespace" => {
+                    options.trailing_whitespace = true;
+                }
+                "all" => {
+                    options.comments = true;
+                    options.blank_lines = true;
+                    options.trailing_whitespace = true;
+                }
+                other => return Err(StripParseError::Unknown(other.to_string())),
+            }
+        }
+        Ok(options)
+    }
+
+    /// Settings from `export.strip` and `export.tab_width`; unknown names fall back to
+    /// stripping nothing.
+    pub fn from_config(config: &Config) -> Self {
+        let names = config.export.strip();
+        Self {
+            tab_width: config.export.tab_width(),
+            ..Self::from_names(names.iter().map(String::as_str)).unwrap_or_default()
+        }
+    }
+
+    /// Whether anything is stripped at all.
+    pub fn is_active(&self) -> bool {
+        self.comments || self.blank_lines || self.trailing_whitespace || self.tab_width > 0
+    }
+}
+
+impl FromStr for StripOptions {
+    type Err = StripParseError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Self::from_names(value.split(','))
+    }
+}
+
+impl fmt::Display for StripOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = [
+            (self.comments, "comments"),
+            (self.blank_lines, "blank-lines"),
+            (self.trailing_whitespace, "trailing-whitespace"),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, name)| enabled.then_some(name))
+        .collect();
+        if names.is_empty() {
+            write!(f, "none")
+        } else {
+            write!(f, "{}", names.join(","))
+        }
+    }
+}
+
+/// Error returned when parsing [`StripOptions`] fails.
+#[derive(Debug, thiserror::Error, Clone, PartialEq, Eq)]
+pub enum StripParseError {
+    #[error(
+        "unknown strip setting '{0}' (expected comments, blank-lines, trailing-whitespace, all, or none)"
+    )]
+    Unknown(String),
+}
+
+/// Comment syntax of a language.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CommentSyntax {
+    line: Option<&'static str>,
+    block: Option<(&'static str, &'static str)>,
+    /// Characters that open a string literal closed by the same character.
+    quotes: &'static str,
+}
+
+impl CommentSyntax {
+    fn for_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        let c_like = |quotes| Self {
+            line: Some("//"),
+            block: Some(("/*", "*/")),
+            quotes,
+        };
+        let hash = |quotes| Self {
+            line: Some("#"),
+            block: None,
+            quotes,
+        };
+        let syntax = match extension.as_str() {
+            // Rust uses `'` for lifetimes as well as characters, so only `"` opens a string.
+            "rs" => c_like("\""),
+            "c" | "h" | "cc" | "cpp" | "cxx" | "hpp" | "hh" | "java" | "kt" | "kts" | "scala"
+            | "cs" | "swift" | "dart" | "proto" | "css" | "scss" | "less" => c_like("\"'"),
+            "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "go" => c_like("\"'`"),
+            "py" | "pyi" | "rb" | "sh" | "bash" | "zsh" | "fish" | "pl" | "r" | "toml" | "yaml"
+            | "yml" | "ini" | "cfg" | "conf" | "mk" | "cmake" | "dockerfile" => hash("\"'"),
+            "sql" => Self {
+                line: Some("--"),
+                block: Some(("/*", "*/")),
+                quotes: "\"'",
+            },
+            "lua" | "hs" => Self {
+                line: Some("--"),
+                block: None,
+                quotes: "\"'",
+            },
+            "html" | "htm" | "xml" | "svg" | "vue" | "svelte" => Self {
+                line: None,
+                block: Some(("<!--", "-->")),
+                quotes: "",
+            },
+            _ => return None,
+        };
+        Some(syntax)
+    }
+}
+
+/// Apply `options` to the `lines` of the file at `path`.
+///
+/// Returns the kept lines with their 1-based line numbers. Comment stripping is skipped for
+/// languages without known comment syntax. Tabs are expanded by column, so indentation lines up
+/// the same way behind a line-number gutter as it does in an editor.
+pub fn strip_lines<'a>(
+    path: &Path,
+    lines: &[&'a str],
+    options: StripOptions,
+) -> Vec<(usize, Cow<'a, str>)> {
+    let syntax = options
+        .comments
+        .then(|| CommentSyntax::for_path(path))
+        .flatten();
+    let mut in_block = false;
+    let mut previous_blank = false;
+    let mut kept = Vec::with_capacity(lines.len());
+    for (idx, &line) in lines.iter().enumerate() {
+        let text = match syntax {
+            // Shebangs look like comments but are needed to run the script.
+            Some(_) if idx == 0 && line.starts_with("#!") => Cow::Borrowed(line),
+            Some(syntax) => match strip_comments(line, syntax, &mut in_block) {
+                Some(text) => text,
+                None => continue,
+            },
+            None => Cow::Borrowed(line),
+        };
+        let text = match text {
+            Cow::Borrowed(line) if options.trailing_whitespace => Cow::Borrowed(line.trim_end()),
+            Cow::Owned(line) if options.trailing_whitespace => {
+                Cow::Owned(line.trim_end().to_string())
+            }
+            text => text,
+        };
+        let text = if options.tab_width > 0 && text.contains('\t') {
+            Cow::Owned(expand_tabs(&text, options.tab_width))
+        } else {
+            text
+        };
+        let blank = text.trim().is_empty();
+        if options.blank_lines && blank && previous_blank {
+            continue;
+        }
+        previous_blank = blank;
+        kept.push((idx + 1, text));
+    }
+    kept
+}
+
+/// Replace each tab in `line` with spaces up to the next multiple of `width` columns.
+fn expand_tabs(line: &str, width: usize) -> String {
+    let mut out = String::with_capacity(line.len() + width);
+    let mut column = 0;
+    for ch in line.chars() {
+        if ch == '\t' {
+            let spaces = width - column % width;
+            out.extend(std::iter::repeat_n(' ', spaces));
+            column += spaces;
+        } else {
+            out.push(ch);
+            column += 1;
+        }
+    }
+    out
+}
+
+/// Remove comments from one line, or `None` when the line held only comments.
+fn strip_comments<'a>(
+    line: &'a str,
+    syntax: CommentSyntax,
+    in_block: &mut bool,
+) -> Option<Cow<'a, str>> {
+    let mut out = String::new();
+    // Lines inside a block comment are comment even when blank.
+    let mut removed = *in_block;
+    let mut quote: Option<char> = None;
+    let mut rest = line;
+    while !rest.is_empty() {
+        if *in_block {
+            let (_, close) = syntax.block.expect("block state without block syntax");
+            removed = true;
+            match rest.find(close) {
+                Some(end) => {
+                    rest = &rest[end + close.len()..];
+                    *in_block = false;
+                }
+                None => rest = "",
+            }
+            continue;
+        }
+        let ch = rest.chars().next().expect("non-empty");
+        if let Some(open) = quote {
+            out.push(ch);
+            rest = &rest[ch.len_utf8()..];
+            if ch == '\\' {
+                if let Some(escaped) = rest.chars().next() {
+                    out.push(escaped);
+                    rest = &rest[escaped.len_utf8()..];
+                }
+            } else if ch == open {
+                quote = None;
+            }
+            continue;
+        }
+        if let Some(marker) = syntax.line
+            && rest.starts_with(marker)
+        {
+            removed = true;
+            break;
+        }
+        if let Some((open, _)) = syntax.block
+            && rest.starts_with(open)
+        {
+            rest = &rest[open.len()..];
+            *in_block = true;
+            continue;
+        }
+        if syntax.quotes.contains(ch) {
+            quote = Some(ch);
+        }
+        out.push(ch);
+        rest = &rest[ch.len_utf8()..];
+    }
+
+    if !removed {
+        return Some(Cow::Borrowed(line));
+    }
+    let trimmed = out.trim_end();
+    (!trimmed.trim_start().is_empty()).then(|| Cow::Owned(trimmed.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stripped(path: &str, source: &str, options: StripOptions) -> Vec<(usize, String)> {
+        let lines: Vec<&str> = source.lines().collect();
+        strip_lines(Path::new(path), &lines, options)
+            .into_iter()
+            .map(|(line, text)| (line, text.into_owned()))
+            .collect()
+    }
+
+    #[test]
+    fn strips_comments_and_blank_runs_keeping_line_numbers() {
+        let options: StripOptions = "comments,blank-lines".parse().unwrap();
+        let source = "//! Crate docs.\nuse std::fs; // imports\n\n\n/* block\n   comment */\nfn main<'a>() {\n    let url = \"http://example.com\"; /* inline */ let x = 1;\n}\n";
+        assert_eq!(
+            stripped("src/main.rs", source, options),
+            vec![
+                (2, "use std::fs;".to_string()),
+                (3, String::new()),
+                (7, "fn main<'a>() {".to_string()),
+                (
+                    8,
+                    "    let url = \"http://example.com\";  let x = 1;".to_string()
+                ),
+                (9, "}".to_string()),
+            ]
+        );
+
+        let script = "#!/usr/bin/env python\n# setup\nname = '#not a comment'  # trailing\n";
+        assert_eq!(
+            stripped("tool.py", script, options),
+            vec![
+                (1, "#!/usr/bin/env python".to_string()),
+                (3, "name = '#not a comment'".to_string()),
+            ]
+        );
+
+        let blank_only = StripOptions {
+            blank_lines: true,
+            ..StripOptions::default()
+        };
+        assert_eq!(
+            stripped("notes.txt", "a\n\n\n\nb // c", blank_only).len(),
+            3
+        );
+        assert_eq!("none".parse::<StripOptions>(), Ok(StripOptions::default()));
+        assert!("docs".parse::<StripOptions>().is_err());
+    }
+
+    #[test]
+    fn expands_tabs_by_column_and_trims_trailing_whitespace() {
+        let mut options: StripOptions = "trailing-whitespace".parse().unwrap();
+        options.tab_width = 4;
+        assert_eq!(options.to_string(), "trailing-whitespace");
+        assert_eq!(
+            stripped(
+                "main.go",
+                "func main() {\t \n\tx := 1\t// one\n\t\tab\tc\n}",
+                options
+            ),
+            vec![
+                (1, "func main() {".to_string()),
+                (2, "    x := 1  // one".to_string()),
+                (3, "        ab  c".to_string()),
+                (4, "}".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,398 @@
+//! Proposing files for a task description from keyword, similarity, and import evidence.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, bail};
+
+use crate::app::deps::DependencyGraph;
+use crate::app::scan::FileMetadata;
+use crate::app::search::Search;
+use crate::app::semantic::{SemanticIndex, identifier_words};
+use crate::app::tokens::TokenEstimator;
+
+/// Words too common in task descriptions to say anything about the code.
+const STOP_WORDS: &[&str] = &[
+    "add", "all", "and", "any", "are", "but", "can", "does", "fix", "for", "from", "get", "has",
+    "have", "how", "into", "make", "new", "not", "now", "should", "that", "the", "then", "this",
+    "when", "where", "which", "while", "why", "will", "with", "use", "using", "want", "need",
+];
+/// Keywords searched for at most; later words in the description are dropped.
+const MAX_KEYWORDS: usize = 12;
+/// Chunks taken from the similarity index.
+const SEMANTIC_CHUNKS: usize = 40;
+/// Similarity below which a chunk is treated as unrelated to the task.
+const SEMANTIC_MIN_SCORE: f32 = 0.25;
+/// Best-ranked candidates whose imports and importers are considered.
+const DEPENDENCY_SEEDS: usize = 8;
+/// Share of a seed's score given to the files it is linked to by imports.
+const DEPENDENCY_DECAY: f32 = 0.5;
+
+/// Why a file was suggested.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SuggestionReason {
+    /// Its path or contents mention words of the task.
+    Keywords { words: Vec<String>, hits: usize },
+    /// One of its chunks shares vocabulary with the task, by the similarity index.
+    Semantic {
+        start: usize,
+        end: usize,
+        score: f32,
+    },
+    /// It imports, or is imported by, these strong candidates.
+    Dependency { of: Vec<String> },
+}
+
+impl fmt::Display for SuggestionReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Keywords { words, hits } => {
+                write!(f, "mentions {} ({hits} line(s))", words.join(", "))
+            }
+            Self::Semantic { start, end, score } => {
+                write!(f, "similar at lines {start}-{end} ({score:.2})")
+            }
+            Self::Dependency { of } => write!(f, "linked by imports to {}", of.join(", ")),
+        }
+    }
+}
+
+/// A file proposed for the bundle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub path: PathBuf,
+    pub display_path: String,
+    pub score: f32,
+    pub tokens: usize,
+    pub reasons: Vec<SuggestionReason>,
+}
+
+/// Ranked suggestions for one task, best first, fitting within `budget` tokens together.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Suggestions {
+    pub task: String,
+    pub keywords: Vec<String>,
+    pub items: Vec<Suggestion>,
+    /// Token budget the items were fitted to; 0 for no limit.
+    pub budget: usize,
+    /// Candidates left out because they would not fit the budget.
+    pub over_budget: usize,
+}
+
+impl Suggestions {
+    pub fn total_tokens(&self) -> usize {
+        self.items.iter().map(|item| item.tokens).sum()
+    }
+}
+
+/// Ranks workspace files against a task description.
+pub struct Suggester<'a> {
+    search: &'a Search,
+    estimator: &'a TokenEstimator,
+    semantic: Option<&'a SemanticIndex>,
+}
+
+impl<'a> Suggester<'a> {
+    pub fn new(search: &'a Search, estimator: &'a TokenEstimator) -> Self {
+        Self {
+            search,
+            estimator,
+            semantic: None,
+        }
+    }
+
+    /// Also rank chunks of `index`, built by `llmctx index` beneath the workspace root.
+    pub fn with_semantic_index(mut self, index: &'a SemanticIndex) -> Self {
+        self.semantic = Some(index);
+        self
+    }
+
+    /// Propose up to `limit` of the scanned `files` for `task`, within `budget` tokens (0 for no
+    /// limit, like `defaults.token_budget`).
+    ///
+    /// Files mentioning the task's words and, with an index, files semantically close to it are
+    /// scored first. The best of those then lend part of their score to the files they import
+    /// or are imported by. Files are taken best first, skipping any that would overflow the
+    /// budget.
+    pub fn suggest(
+        &self,
+        task: &str,
+        files: &[FileMetadata],
+        roots: &[PathBuf],
+        budget: usize,
+        limit: usize,
+    ) -> Result<Suggestions> {
+        let keywords = task_keywords(task);
+        if keywords.is_empty() && self.semantic.is_none() {
+            bail!("the task description has no words to search for");
+        }
+        let metadata: HashMap<&Path, &FileMetadata> = files
+            .iter()
+            .filter(|meta| !meta.is_dir && meta.skipped.is_none())
+            .map(|meta| (meta.path.as_path(), meta))
+            .collect();
+        let mut candidates: HashMap<PathBuf, (f32, Vec<SuggestionReason>)> = HashMap::new();
+
+        if !keywords.is_empty() {
+            let pattern = format!(
+                "(?:{})",
+                keywords
+                    .iter()
+                    .map(|word| regex::escape(word))
+                    .collect::<Vec<_>>()
+                    .join("|")
+            );
+            let mut mentions: HashMap<&Path, (BTreeSet<&str>, usize)> = HashMap::new();
+            let results = self.search.query(files, roots, &pattern)?;
+            for hit in &results.hits {
+                let text = hit.text.to_lowercase();
+                let entry = mentions.entry(hit.path.as_path()).or_default();
+                entry.1 += 1;
+                entry.0.extend(
+                    keywords
+                        .iter()
+                        .map(String::as_str)
+                        .filter(|word| text.contains(word)),
+                );
+            }
+            for (path, meta) in &metadata {
+                let path_words = identifier_words(&meta.display_path);
+                for word in &keywords {
+                    if path_words.contains(word) {
+                        mentions.entry(path).or_default().0.insert(word);
+                    }
+                }
+            }
+            for (path, (words, hits)) in mentions {
+                if words.is_empty() {
+                    continue;
+                }
+                let score = words.len() as f32 / keywords.len() as f32 + hits.min(10) as f32 * 0.02;
+                let (total, reasons) = candidates.entry(path.to_path_buf()).or_default();
+                *total += score;
+                reasons.push(SuggestionReason::Keywords {
+                    words: keywords
+                        .iter()
+                        .filter(|word| words.contains(word.as_str()))
+                        .cloned()
+                        .collect(),
+                    hits,
+                });
+            }
+        }
+
+        if let Some(index) = self.semantic {
+            let root = roots
+                .first()
+                .map(PathBuf::as_path)
+                .unwrap_or(Path::new("."));
+            let embedder = index.embedder()?;
+            let mut best: HashMap<PathBuf, SuggestionReason> = HashMap::new();
+            // Matches arrive best first, so the first chunk seen for a file is its best.
+            for found in index.query(root, embedder.as_ref(), task, SEMANTIC_CHUNKS)? {
+                if found.score >= SEMANTIC_MIN_SCORE && metadata.contains_key(found.path.as_path())
+                {
+                    best.entry(found.path)
+                        .or_insert(SuggestionReason::Semantic {
+                            start: found.start,
+                            end: found.end,
+                            score: found.score,
+                        });
+                }
+            }
+            for (path, reason) in best {
+                let SuggestionReason::Semantic { score, .. } = reason else {
+                    continue;
+                };
+                let (total, reasons) = candidates.entry(path).or_default();
+                *total += score;
+                reasons.push(reason);
+            }
+        }
+
+        let mut seeds: Vec<(PathBuf, f32)> = candidates
+            .iter()
+            .map(|(path, (score, _))| (path.clone(), *score))
+            .collect();
+        seeds.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        seeds.truncate(DEPENDENCY_SEEDS);
+        if !seeds.is_empty() {
+            let graph = DependencyGraph::build(files);
+            for (seed, seed_score) in seeds {
+                let of = metadata
+                    .get(seed.as_path())
+                    .map(|meta| meta.display_path.clone())
+                    .unwrap_or_else(|| seed.display().to_string());
+                let linked: BTreeSet<&Path> =
+                    graph.imports(&seed).chain(graph.importers(&seed)).collect();
+                for path in linked {
+                    if !metadata.contains_key(path) {
+                        continue;
+                    }
+                    let (score, reasons) = candidates.entry(path.to_path_buf()).or_default();
+                    *score = score.max(seed_score * DEPENDENCY_DECAY);
+                    match reasons.iter_mut().find_map(|reason| match reason {
+                        SuggestionReason::Dependency { of } => Some(of),
+                        _ => None,
+                    }) {
+                        Some(linked) => linked.push(of.clone()),
+                        None => reasons.push(SuggestionReason::Dependency {
+                            of: vec![of.clone()],
+                        }),
+                    }
+                }
+            }
+        }
+
+        let mut ranked: Vec<(PathBuf, f32, Vec<SuggestionReason>)> = candidates
+            .into_iter()
+            .map(|(path, (score, reasons))| (path, score, reasons))
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        let mut suggestions = Suggestions {
+            task: task.to_string(),
+            keywords,
+            budget,
+            ..Suggestions::default()
+        };
+        let mut used = 0;
+        for (path, score, reasons) in ranked {
+            if suggestions.items.len() >= limit {
+                break;
+            }
+            let estimate = self
+                .estimator
+                .estimate_files(None, std::slice::from_ref(&path));
+            if estimate.unreadable > 0 {
+                continue;
+            }
+            if budget > 0 && used + estimate.tokens > budget {
+                suggestions.over_budget += 1;
+                continue;
+            }
+            used += estimate.tokens;
+            suggestions.items.push(Suggestion {
+                display_path: metadata
+                    .get(path.as_path())
+                    .map(|meta| meta.display_path.clone())
+                    .unwrap_or_else(|| path.display().to_string()),
+                path,
+                score,
+                tokens: estimate.tokens,
+                reasons,
+            });
+        }
+        Ok(suggestions)
+    }
+}
+
+/// Distinct searchable words of a task description, in the order written.
+fn task_keywords(task: &str) -> Vec<String> {
+    let mut keywords: Vec<String> = Vec::new();
+    for word in identifier_words(task) {
+        if word.chars().count() >= 3
+            && !STOP_WORDS.contains(&word.as_str())
+            && !keywords.contains(&word)
+        {
+            keywords.push(word);
+        }
+    }
+    keywords.truncate(MAX_KEYWORDS);
+    keywords
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+
+    use crate::app::scan::FileClass;
+    use crate::app::search::SearchBackend;
+    use crate::app::tokens::TokenModel;
+
+    #[test]
+    fn ranks_mentions_then_imports_within_budget() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = temp.path().to_path_buf();
+        let files = [
+            ("src/lib.rs", String::new()),
+            (
+                "src/retry.rs",
+                "use crate::client::Client;\n/// Retry with backoff.\npub fn retry(backoff: u32) {}\n"
+                    .to_string(),
+            ),
+            ("src/client.rs", "pub struct Client;\n".to_string()),
+            ("src/huge.rs", format!("// configurable retry backoff\n{}", "let x = 1;\n".repeat(2_000))),
+            ("README.md", "# Unrelated\n".to_string()),
+        ];
+        let mut scanned = Vec::new();
+        for (display, contents) in &files {
+            let path = root.join(display);
+            fs::create_dir_all(path.parent().unwrap())?;
+            fs::write(&path, contents)?;
+            scanned.push(FileMetadata {
+                path,
+                display_path: display.to_string(),
+                is_dir: false,
+                size: None,
+                modified: None,
+                language: Path::new(display)
+                    .extension()
+                    .map(|ext| ext.to_string_lossy().into_owned()),
+                skipped: None,
+                class: FileClass::Source,
+            });
+        }
+
+        assert_eq!(
+            task_keywords("Fix the retry backoff when the retry budget is exceeded"),
+            vec!["retry", "backoff", "budget", "exceeded"]
+        );
+
+        let search = Search::new(SearchBackend::Builtin);
+        let estimator = TokenEstimator::new(TokenModel::CharacterFallback);
+        let suggestions = Suggester::new(&search, &estimator).suggest(
+            "make retry backoff configurable",
+            &scanned,
+            std::slice::from_ref(&root),
+            2_000,
+            10,
+        )?;
+        let paths: Vec<&str> = suggestions
+            .items
+            .iter()
+            .map(|item| item.display_path.as_str())
+            .collect();
+        assert_eq!(paths, vec!["src/retry.rs", "src/client.rs"]);
+        assert_eq!(suggestions.over_budget, 1);
+        assert!(suggestions.total_tokens() <= 2_000);
+
+        let unlimited = Suggester::new(&search, &estimator).suggest(
+            "make retry backoff configurable",
+            &scanned,
+            std::slice::from_ref(&root),
+            0,
+            10,
+        )?;
+        assert_eq!(unlimited.items.len(), 3);
+        assert_eq!(unlimited.items[0].display_path, "src/huge.rs");
+        assert_eq!(unlimited.over_budget, 0);
+        assert_eq!(
+            suggestions.items[0].reasons,
+            vec![SuggestionReason::Keywords {
+                words: vec!["retry".to_string(), "backoff".to_string()],
+                hits: 2,
+            }]
+        );
+        assert_eq!(
+            suggestions.items[1].reasons,
+            vec![SuggestionReason::Dependency {
+                of: vec!["src/retry.rs".to_string()]
+            }]
+        );
+        Ok(())
+    }
+}
@@ -1,19 +1,30 @@
 //! Token estimation services.
 
-use std::collections::HashMap;
+use std::cmp::Reverse;
 use std::fmt;
-use std::fs;
-use std::hash::{Hash, Hasher};
+use std::mem;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::{Arc, Mutex, OnceLock};
-use std::time::{Duration, UNIX_EPOCH};
 
 use anyhow::{Context, Result};
+use lru::LruCache;
+use rayon::prelude::*;
 use tiktoken_rs::{CoreBPE, cl100k_base, o200k_base};
 
-use crate::domain::model::{ContextBundle, SelectionItem};
+use crate::app::cancel::CancellationToken;
+use crate::app::strip::{self, StripOptions};
+use crate::domain::errors::{BudgetOffender, DomainError};
+use crate::domain::model::{ContextBundle, SelectionItem, SelectionSource};
 use crate::infra::config::Config;
+use crate::infra::documents::{self, DocumentKind, ExtractOptions};
+use crate::infra::fs::{ContentFingerprint, DecodedText, RealFs, Vfs};
+use crate::infra::token_api::ApiProvider;
+
+/// Selections named when a bundle is over its token limit.
+const BUDGET_OFFENDERS: usize = 3;
+/// Tokens per character of structured data relative to prose, for size-only estimates.
+const STRUCTURED_TOKEN_RATIO: f32 = 1.4;
 
 /// Supported token estimation models across providers.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
@@ -61,6 +72,32 @@ impl TokenModel {
         }
     }
 
+    /// Provider API and model identifier used to count tokens remotely, if the model has one.
+    pub fn api_model(&self) -> Option<(ApiProvider, &'static str)> {
+        match self {
+            TokenModel::OpenAiGpt4o => Some((ApiProvider::OpenAi, "gpt-4o")),
+            TokenModel::OpenAiGpt4oMini => Some((ApiProvider::OpenAi, "gpt-4o-mini")),
+            TokenModel::AnthropicClaude3Haiku => {
+                Some((ApiProvider::Anthropic, "claude-3-haiku-20240307"))
+            }
+            TokenModel::AnthropicClaude35Sonnet => {
+                Some((ApiProvider::Anthropic, "claude-3-5-sonnet-20241022"))
+            }
+            TokenModel::CharacterFallback => None,
+        }
+    }
+
+    /// `[tokens]` key holding the calibration multiplier for the model's provider.
+    pub fn multiplier_key(&self) -> Option<&'static str> {
+        match self {
+            TokenModel::OpenAiGpt4o | TokenModel::OpenAiGpt4oMini => Some("openai_multiplier"),
+            TokenModel::AnthropicClaude3Haiku | TokenModel::AnthropicClaude35Sonnet => {
+                Some("anthropic_multiplier")
+            }
+            TokenModel::CharacterFallback => None,
+        }
+    }
+
     /// Enumerate all known models in priority order.
     pub fn all() -> &'static [TokenModel] {
         &[
@@ -113,6 +150,10 @@ pub struct HeuristicConfig {
     pub tokens_per_word: f32,
     /// Multiplier applied when a selection is likely source code.
     pub code_token_multiplier: f32,
+    /// Scale applied to every OpenAI estimate, from `tokens.openai_multiplier`.
+    pub openai_multiplier: f32,
+    /// Scale applied to every Anthropic estimate, from `tokens.anthropic_multiplier`.
+    pub anthropic_multiplier: f32,
 }
 
 impl Default for HeuristicConfig {
@@ -122,11 +163,33 @@ impl Default for HeuristicConfig {
             anthropic_chars_per_token: 3.2,
             tokens_per_word: 1.0,
             code_token_multiplier: 1.25,
+            openai_multiplier: 1.0,
+            anthropic_multiplier: 1.0,
         }
     }
 }
 
 impl HeuristicConfig {
+    /// Defaults with the provider multipliers from the `[tokens]` configuration section.
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            openai_multiplier: config.tokens.openai_multiplier(),
+            anthropic_multiplier: config.tokens.anthropic_multiplier(),
+            ..Self::default()
+        }
+    }
+
+    /// Calibration scale for `model`'s provider; heuristic-only models are never scaled.
+    pub fn multiplier_for(&self, model: TokenModel) -> f32 {
+        match model {
+            TokenModel::OpenAiGpt4o | TokenModel::OpenAiGpt4oMini => self.openai_multiplier,
+            TokenModel::AnthropicClaude3Haiku | TokenModel::AnthropicClaude35Sonnet => {
+                self.anthropic_multiplier
+            }
+            TokenModel::CharacterFallback => 1.0,
+        }
+    }
+
     fn chars_per_token_for(&self, model: TokenModel) -> f32 {
         match model {
             TokenModel::AnthropicClaude3Haiku | TokenModel::AnthropicClaude35Sonnet => {
@@ -152,13 +215,55 @@ impl HeuristicConfig {
     }
 }
 
+/// Limits applied to the token estimate cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenCacheConfig {
+    /// Maximum number of cached estimates.
+    pub max_entries: usize,
+    /// Approximate upper bound on memory used by cached estimates.
+    pub max_bytes: usize,
+}
+
+impl Default for TokenCacheConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: 4_096,
+            max_bytes: 4 * 1024 * 1024,
+        }
+    }
+}
+
+impl TokenCacheConfig {
+    /// Read cache limits from the `[tokens]` configuration section.
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            max_entries: config.tokens.cache_max_entries(),
+            max_bytes: config.tokens.cache_max_bytes(),
+        }
+    }
+}
+
+/// Hit/miss counters and occupancy of the token estimate cache.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TokenCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub entries: usize,
+    pub bytes: usize,
+}
+
 /// Token estimation engine with caching and streaming updates.
 #[derive(Debug, Clone)]
 pub struct TokenEstimator {
     model: TokenModel,
     token_budget: u32,
     heuristics: HeuristicConfig,
-    cache: Arc<Mutex<HashMap<CacheKey, ItemTokenEstimate>>>,
+    cache: Arc<Mutex<TokenCache>>,
+    extract_options: ExtractOptions,
+    strip: StripOptions,
+    cancel: CancellationToken,
+    vfs: Arc<dyn Vfs>,
 }
 
 impl Default for TokenEstimator {
@@ -174,7 +279,11 @@ impl TokenEstimator {
             model,
             token_budget: 120_000,
             heuristics: HeuristicConfig::default(),
-            cache: Arc::new(Mutex::new(HashMap::new())),
+            cache: Arc::new(Mutex::new(TokenCache::new(TokenCacheConfig::default()))),
+            extract_options: ExtractOptions::default(),
+            strip: StripOptions::default(),
+            cancel: CancellationToken::default(),
+            vfs: Arc::new(RealFs),
         }
     }
 
@@ -187,15 +296,18 @@ impl TokenEstimator {
             .unwrap_or_else(|_| TokenModel::default());
         let mut estimator = Self::new(model);
         estimator.token_budget = config.defaults.token_budget();
+        estimator.heuristics = HeuristicConfig::from_config(config);
+        estimator.set_cache_config(TokenCacheConfig::from_config(config));
+        estimator.extract_options = ExtractOptions::from_config(config);
+        estimator.strip = StripOptions::from_config(config);
         estimator
     }
 
     /// Override the active model.
+    ///
+    /// Cached estimates are keyed by model, so switching back and forth keeps earlier results.
     pub fn set_model(&mut self, model: TokenModel) {
-        if self.model != model {
-            self.cache.lock().unwrap().clear();
-            self.model = model;
-        }
+        self.model = model;
     }
 
     /// Returns the currently configured model.
@@ -213,28 +325,64 @@ impl TokenEstimator {
         self.token_budget = budget;
     }
 
+    /// Estimate selections as they are exported after `strip` is applied.
+    pub fn set_strip(&mut self, strip: StripOptions) {
+        self.strip = strip;
+    }
+
+    /// Stripping applied before estimating.
+    pub fn strip(&self) -> StripOptions {
+        self.strip
+    }
+
+    /// Stop estimating once `cancel` is cancelled.
+    pub fn set_cancellation(&mut self, cancel: CancellationToken) {
+        self.cancel = cancel;
+    }
+
+    /// Read selections from `vfs` instead of the local disk.
+    pub fn set_vfs(&mut self, vfs: Arc<dyn Vfs>) {
+        self.vfs = vfs;
+    }
+
     /// Replace the heuristic configuration.
     pub fn set_heuristics(&mut self, heuristics: HeuristicConfig) {
         self.heuristics = heuristics;
         self.cache.lock().unwrap().clear();
     }
 
+    /// Apply new cache limits, evicting least recently used entries if necessary.
+    pub fn set_cache_config(&mut self, config: TokenCacheConfig) {
+        self.cache.lock().unwrap().reconfigure(config);
+    }
+
+    /// Snapshot of cache hit/miss counters for diagnostics.
+    pub fn cache_stats(&self) -> TokenCacheStats {
+        self.cache.lock().unwrap().stats()
+    }
+
     /// Estimate tokens for the provided bundle, returning per-item breakdowns.
     pub fn estimate_bundle(&self, bundle: &ContextBundle) -> Result<BundleTokenSummary> {
-        let model = bundle
-            .model
-            .as_deref()
-            .and_then(|value| TokenModel::from_str(value).ok())
-            .unwrap_or(self.model);
+        let model = self.bundle_model(bundle);
 
         let mut items = Vec::with_capacity(bundle.items.len());
         let mut total_tokens = 0usize;
         let mut total_characters = 0usize;
+        let mut total_words = 0usize;
+        let mut total_lines = 0usize;
+        let mut stripped_tokens = 0usize;
 
         for item in &bundle.items {
-            let estimate = self.estimate_item(model, item)?;
+            self.cancel.check()?;
+            let estimate = self.estimate_item(model, item, self.strip)?;
+            if self.strip.is_active() {
+                let unstripped = self.estimate_item(model, item, StripOptions::default())?;
+                stripped_tokens += unstripped.tokens.saturating_sub(estimate.tokens);
+            }
             total_tokens += estimate.tokens;
             total_characters += estimate.characters;
+            total_words += estimate.words;
+            total_lines += estimate.lines;
             items.push(estimate);
         }
 
@@ -243,56 +391,243 @@ impl TokenEstimator {
             token_budget: self.token_budget,
             total_tokens,
             total_characters,
+            total_words,
+            total_lines,
+            stripped_tokens,
             items,
         })
     }
 
+    /// Compare the estimate for each selection in `bundle` with `count`, which returns the
+    /// provider's token count for a text. Empty selections are skipped.
+    pub fn calibrate(
+        &self,
+        bundle: &ContextBundle,
+        mut count: impl FnMut(&str) -> Result<usize>,
+    ) -> Result<CalibrationReport> {
+        let model = self.bundle_model(bundle);
+        let mut samples = Vec::with_capacity(bundle.items.len());
+        for item in &bundle.items {
+            let contents = self
+                .load_selection_contents(item, self.strip)
+                .with_context(|| format!("failed to read selection '{}'", item.path.display()))?;
+            if contents.trim().is_empty() {
+                continue;
+            }
+            let estimated = self.count_tokens(model, is_probably_code(&item.path), &contents);
+            let actual = count(&contents)
+                .with_context(|| format!("failed to count tokens for '{}'", item.path.display()))?;
+            samples.push(CalibrationSample {
+                item: item.clone(),
+                estimated,
+                actual,
+            });
+        }
+        Ok(CalibrationReport {
+            model,
+            multiplier: self.heuristics.multiplier_for(model),
+            samples,
+        })
+    }
+
+    /// Estimate whole-file totals for `paths`, e.g. every file beneath a directory.
+    ///
+    /// Unreadable files are counted rather than failing the whole estimate. `model` overrides the
+    /// estimator's active model when provided. Once the estimator's cancellation token is
+    /// cancelled, the remaining files are skipped and the totals are partial.
+    pub fn estimate_files(
+        &self,
+        model: Option<TokenModel>,
+        paths: &[PathBuf],
+    ) -> AggregateTokenEstimate {
+        let model = model.unwrap_or(self.model);
+        paths
+            .par_iter()
+            .filter(|_| !self.cancel.is_cancelled())
+            .map(|path| {
+                let item = SelectionItem {
+                    path: path.clone(),
+                    range: None,
+                    note: None,
+                    source: SelectionSource::Manual,
+                };
+                match self.estimate_item(model, &item, self.strip) {
+                    Ok(estimate) => AggregateTokenEstimate {
+                        files: 1,
+                        tokens: estimate.tokens,
+                        characters: estimate.characters,
+                        unreadable: 0,
+                    },
+                    Err(_) => AggregateTokenEstimate {
+                        unreadable: 1,
+                        ..Default::default()
+                    },
+                }
+            })
+            .reduce(
+                AggregateTokenEstimate::default,
+                AggregateTokenEstimate::combine,
+            )
+    }
+
+    /// Approximate a file's tokens from its size alone, without reading it.
+    ///
+    /// Bytes stand in for characters, scaled by how densely the file's kind of text tokenizes.
+    /// `model` overrides the estimator's active model when provided.
+    pub fn approximate_size(&self, model: Option<TokenModel>, path: &Path, bytes: u64) -> usize {
+        if bytes == 0 {
+            return 0;
+        }
+        let model = model.unwrap_or(self.model);
+        let tokens = bytes as f32 / self.heuristics.chars_per_token_for(model)
+            * size_ratio(path, &self.heuristics)
+            * self.heuristics.multiplier_for(model);
+        (tokens.ceil() as usize).max(1)
+    }
+
+    /// Size-only totals for `files`, given with their sizes in bytes; see [`Self::estimate_files`]
+    /// for the exact equivalent.
+    pub fn approximate_files(
+        &self,
+        model: Option<TokenModel>,
+        files: &[(PathBuf, u64)],
+    ) -> AggregateTokenEstimate {
+        files
+            .iter()
+            .map(|(path, bytes)| AggregateTokenEstimate {
+                files: 1,
+                tokens: self.approximate_size(model, path, *bytes),
+                characters: *bytes as usize,
+                unreadable: 0,
+            })
+            .fold(
+                AggregateTokenEstimate::default(),
+                AggregateTokenEstimate::combine,
+            )
+    }
+
+    /// Estimate a single selection with the estimator's active model.
+    pub fn estimate_selection(&self, item: &SelectionItem) -> Result<ItemTokenEstimate> {
+        self.estimate_item(self.model, item, self.strip)
+    }
+
     /// Invalidate cached entries for the given path.
     pub fn invalidate_path(&self, path: &Path) {
-        let mut cache = self.cache.lock().unwrap();
-        cache.retain(|key, _| key.path != path);
+        self.cache.lock().unwrap().invalidate_path(path);
+    }
+
+    /// The bundle's model when it names a known one, else the estimator's.
+    fn bundle_model(&self, bundle: &ContextBundle) -> TokenModel {
+        bundle
+            .model
+            .as_deref()
+            .and_then(|value| TokenModel::from_str(value).ok())
+            .unwrap_or(self.model)
+    }
+
+    fn load_selection_contents(&self, item: &SelectionItem, strip: StripOptions) -> Result<String> {
+        // Notebooks are estimated as the flattened cells that export emits, not their raw JSON.
+        let mut text = if DocumentKind::detect(&item.path) == Some(DocumentKind::Notebook) {
+            documents::read_selection_text(self.vfs.as_ref(), &item.path, self.extract_options)?
+        } else {
+            let raw = self.vfs.read(&item.path).map_err(|err| match err.kind() {
+                std::io::ErrorKind::NotFound => anyhow::Error::new(DomainError::FileMissing {
+                    path: item.path.clone(),
+                }),
+                _ => anyhow::Error::new(err)
+                    .context(format!("failed to read file '{}'", item.path.display())),
+            })?;
+            DecodedText::decode(&raw).text
+        };
+        if strip.is_active() {
+            let lines: Vec<&str> = text.lines().collect();
+            let (start, end) = item.range.unwrap_or((1, lines.len()));
+            let kept: Vec<_> = strip::strip_lines(&item.path, &lines, strip)
+                .into_iter()
+                .filter(|(line, _)| (start..=end).contains(line))
+                .map(|(_, line)| line)
+                .collect();
+            return Ok(kept.join("\n"));
+        }
+        if let Some((start, end)) = item.range {
+            let start_idx = start.saturating_sub(1);
+            let end_idx = end.max(start_idx);
+            let lines: Vec<&str> = text.lines().collect();
+            if start_idx >= lines.len() {
+                text.clear();
+            } else {
+                let end_idx = end_idx.min(lines.len());
+                text = lines[start_idx..end_idx].join("\n");
+            }
+        }
+        Ok(text)
     }
 
-    fn estimate_item(&self, model: TokenModel, item: &SelectionItem) -> Result<ItemTokenEstimate> {
-        let fingerprint = file_fingerprint(&item.path);
+    fn estimate_item(
+        &self,
+        model: TokenModel,
+        item: &SelectionItem,
+        strip: StripOptions,
+    ) -> Result<ItemTokenEstimate> {
+        let contents = self
+            .load_selection_contents(item, strip)
+            .with_context(|| format!("failed to read selection '{}'", item.path.display()))?;
+        let is_code = is_probably_code(&item.path);
         let key = CacheKey {
             model,
-            path: item.path.clone(),
-            range: item.range,
-            fingerprint,
+            is_code,
+            content: ContentFingerprint::of_bytes(contents.as_bytes()),
         };
 
-        if let Some(existing) = self.cache.lock().unwrap().get(&key).cloned() {
-            return Ok(existing);
+        if let Some(cached) = self.cache.lock().unwrap().get(&key) {
+            return Ok(ItemTokenEstimate {
+                item: item.clone(),
+                tokens: cached.tokens,
+                characters: cached.characters,
+                words: cached.words,
+                lines: cached.lines,
+            });
         }
 
-        let contents = load_selection_contents(item)
-            .with_context(|| format!("failed to read selection '{}'", item.path.display()))?;
         let characters = contents.chars().count();
-        let tokens = self.count_tokens(model, item, &contents);
+        let words = count_words(&contents);
+        let lines = contents.lines().count();
+        let tokens = self.count_tokens(model, is_code, &contents);
+
+        self.cache.lock().unwrap().insert(
+            key,
+            CacheEntry {
+                path: item.path.clone(),
+                tokens,
+                characters,
+                words,
+                lines,
+            },
+        );
 
-        let estimate = ItemTokenEstimate {
+        Ok(ItemTokenEstimate {
             item: item.clone(),
             tokens,
             characters,
-        };
-
-        self.cache.lock().unwrap().insert(key, estimate.clone());
-
-        Ok(estimate)
+            words,
+            lines,
+        })
     }
 
-    fn count_tokens(&self, model: TokenModel, item: &SelectionItem, contents: &str) -> usize {
+    fn count_tokens(&self, model: TokenModel, is_code: bool, contents: &str) -> usize {
         if contents.trim().is_empty() {
             return 0;
         }
 
-        match tokenizer_for(model) {
+        let raw = match tokenizer_for(model) {
             Ok(Tokenizer::Bpe(core)) => core.lock().unwrap().encode_ordinary(contents).len(),
-            Ok(Tokenizer::Heuristic) | Err(_) => {
-                self.heuristics
-                    .estimate(contents, model, is_probably_code(&item.path))
-            }
+            Ok(Tokenizer::Heuristic) | Err(_) => self.heuristics.estimate(contents, model, is_code),
+        };
+        let multiplier = self.heuristics.multiplier_for(model);
+        if multiplier == 1.0 {
+            raw
+        } else {
+            ((raw as f32) * multiplier).round().max(1.0) as usize
         }
     }
 }
@@ -304,74 +639,234 @@ pub struct BundleTokenSummary {
     pub token_budget: u32,
     pub total_tokens: usize,
     pub total_characters: usize,
+    pub total_words: usize,
+    pub total_lines: usize,
+    /// Tokens saved by [`StripOptions`]; already excluded from `total_tokens`.
+    pub stripped_tokens: usize,
     pub items: Vec<ItemTokenEstimate>,
 }
 
+impl BundleTokenSummary {
+    /// Fail with [`DomainError::BudgetExceeded`], naming the most expensive selections, when the
+    /// bundle needs more than `limit` tokens.
+    pub fn check_limit(&self, limit: usize) -> Result<(), DomainError> {
+        if self.total_tokens <= limit {
+            return Ok(());
+        }
+        let mut ranked: Vec<&ItemTokenEstimate> = self.items.iter().collect();
+        ranked.sort_by_key(|estimate| Reverse(estimate.tokens));
+        Err(DomainError::BudgetExceeded {
+            tokens: self.total_tokens,
+            budget: limit,
+            largest: ranked
+                .into_iter()
+                .take(BUDGET_OFFENDERS)
+                .map(|estimate| BudgetOffender {
+                    path: estimate.item.path.clone(),
+                    range: estimate.item.range,
+                    tokens: estimate.tokens,
+                })
+                .collect(),
+        })
+    }
+}
+
+/// Local estimate and provider count for one selection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CalibrationSample {
+    pub item: SelectionItem,
+    pub estimated: usize,
+    pub actual: usize,
+}
+
+impl CalibrationSample {
+    /// How far the estimate is from the provider count, as a percentage of the count.
+    pub fn delta_percent(&self) -> f64 {
+        if self.actual == 0 {
+            return 0.0;
+        }
+        (self.estimated as f64 - self.actual as f64) / self.actual as f64 * 100.0
+    }
+}
+
+/// Estimates compared against provider counts across a bundle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalibrationReport {
+    pub model: TokenModel,
+    /// Multiplier in effect for the estimates.
+    pub multiplier: f32,
+    pub samples: Vec<CalibrationSample>,
+}
+
+impl CalibrationReport {
+    pub fn estimated_total(&self) -> usize {
+        self.samples.iter().map(|sample| sample.estimated).sum()
+    }
+
+    pub fn actual_total(&self) -> usize {
+        self.samples.iter().map(|sample| sample.actual).sum()
+    }
+
+    /// Multiplier, rounded to two decimals, that would make the bundle's estimate match the
+    /// provider's total.
+    pub fn suggested_multiplier(&self) -> Option<f32> {
+        let estimated = self.estimated_total();
+        if estimated == 0 {
+            return None;
+        }
+        let scale = self.multiplier * self.actual_total() as f32 / estimated as f32;
+        Some((scale * 100.0).round() / 100.0)
+    }
+}
+
+/// Token totals across a set of whole files.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AggregateTokenEstimate {
+    pub files: usize,
+    pub tokens: usize,
+    pub characters: usize,
+    /// Files that could not be read and are excluded from the totals.
+    pub unreadable: usize,
+}
+
+impl AggregateTokenEstimate {
+    fn combine(self, other: Self) -> Self {
+        Self {
+            files: self.files + other.files,
+            tokens: self.tokens + other.tokens,
+            characters: self.characters + other.characters,
+            unreadable: self.unreadable + other.unreadable,
+        }
+    }
+}
+
 /// Per-selection token estimate.
 #[derive(Debug, Clone)]
 pub struct ItemTokenEstimate {
     pub item: SelectionItem,
     pub tokens: usize,
     pub characters: usize,
+    /// Whitespace-separated words, for tools that limit input by words rather than tokens.
+    pub words: usize,
+    pub lines: usize,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// Cache identity: the tokenizer inputs rather than the selection that produced them, so
+/// identical content reached through different paths or ranges shares an entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 struct CacheKey {
     model: TokenModel,
+    is_code: bool,
+    content: ContentFingerprint,
+}
+
+#[derive(Debug, Clone)]
+struct CacheEntry {
     path: PathBuf,
-    range: Option<(usize, usize)>,
-    fingerprint: Option<FileFingerprint>,
+    tokens: usize,
+    characters: usize,
+    words: usize,
+    lines: usize,
 }
 
-impl Hash for CacheKey {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.model.hash(state);
-        self.path.hash(state);
-        self.range.hash(state);
-        self.fingerprint.hash(state);
+impl CacheEntry {
+    fn weight(&self) -> usize {
+        mem::size_of::<CacheKey>() + mem::size_of::<CacheEntry>() + self.path.as_os_str().len()
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct FileFingerprint {
-    len: u64,
-    modified: Option<u128>,
-}
-
-fn file_fingerprint(path: &Path) -> Option<FileFingerprint> {
-    let metadata = fs::metadata(path).ok()?;
-    let modified = metadata
-        .modified()
-        .ok()
-        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
-        .map(duration_to_nanos);
-
-    Some(FileFingerprint {
-        len: metadata.len(),
-        modified,
-    })
-}
-
-fn duration_to_nanos(duration: Duration) -> u128 {
-    duration.as_secs() as u128 * 1_000_000_000u128 + duration.subsec_nanos() as u128
-}
-
-fn load_selection_contents(item: &SelectionItem) -> Result<String> {
-    let raw = fs::read(&item.path)
-        .with_context(|| format!("failed to read file '{}'", item.path.display()))?;
-    let mut text = String::from_utf8_lossy(&raw).into_owned();
-    if let Some((start, end)) = item.range {
-        let start_idx = start.saturating_sub(1);
-        let end_idx = end.max(start_idx);
-        let lines: Vec<&str> = text.lines().collect();
-        if start_idx >= lines.len() {
-            text.clear();
-        } else {
-            let end_idx = end_idx.min(lines.len());
-            text = lines[start_idx..end_idx].join("\n");
+/// Least recently used cache bounded by entry count and approximate byte size.
+#[derive(Debug)]
+struct TokenCache {
+    entries: LruCache<CacheKey, CacheEntry>,
+    config: TokenCacheConfig,
+    bytes: usize,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+impl TokenCache {
+    fn new(config: TokenCacheConfig) -> Self {
+        Self {
+            entries: LruCache::unbounded(),
+            config,
+            bytes: 0,
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+        }
+    }
+
+    fn get(&mut self, key: &CacheKey) -> Option<&CacheEntry> {
+        match self.entries.get(key) {
+            Some(entry) => {
+                self.hits += 1;
+                Some(entry)
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    fn insert(&mut self, key: CacheKey, entry: CacheEntry) {
+        if self.config.max_entries == 0 || entry.weight() > self.config.max_bytes {
+            return;
+        }
+        self.bytes += entry.weight();
+        if let Some(previous) = self.entries.put(key, entry) {
+            self.bytes -= previous.weight();
+        }
+        self.enforce_limits();
+    }
+
+    fn invalidate_path(&mut self, path: &Path) {
+        let stale: Vec<CacheKey> = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.path == path)
+            .map(|(key, _)| *key)
+            .collect();
+        for key in stale {
+            if let Some(entry) = self.entries.pop(&key) {
+                self.bytes -= entry.weight();
+            }
+        }
+    }
+
+    fn reconfigure(&mut self, config: TokenCacheConfig) {
+        self.config = config;
+        self.enforce_limits();
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.bytes = 0;
+    }
+
+    fn stats(&self) -> TokenCacheStats {
+        TokenCacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            evictions: self.evictions,
+            entries: self.entries.len(),
+            bytes: self.bytes,
+        }
+    }
+
+    fn enforce_limits(&mut self) {
+        while self.entries.len() > self.config.max_entries || self.bytes > self.config.max_bytes {
+            match self.entries.pop_lru() {
+                Some((_, entry)) => {
+                    self.bytes -= entry.weight();
+                    self.evictions += 1;
+                }
+                None => break,
+            }
         }
     }
-    Ok(text)
 }
 
 fn count_words(text: &str) -> usize {
@@ -412,20 +907,33 @@ fn is_probably_code(path: &Path) -> bool {
         .unwrap_or(false)
 }
 
+/// Tokens per character of `path`'s kind of text relative to prose.
+fn size_ratio(path: &Path, heuristics: &HeuristicConfig) -> f32 {
+    if is_probably_code(path) {
+        return heuristics.code_token_multiplier;
+    }
+    let structured = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            matches!(
+                ext,
+                "json" | "yaml" | "yml" | "toml" | "xml" | "csv" | "lock" | "svg" | "html"
+            )
+        });
+    if structured {
+        STRUCTURED_TOKEN_RATIO
+    } else {
+        1.0
+    }
+}
+
 enum Tokenizer {
     Bpe(Arc<Mutex<CoreBPE>>),
     Heuristic,
 }
 
-#[derive(Debug, thiserror::Error, Clone, PartialEq, Eq)]
-enum TokenizerInitError {
-    #[error("failed to initialize OpenAI tokenizer: {0}")]
-    OpenAi(String),
-    #[error("failed to initialize Anthropic tokenizer: {0}")]
-    Anthropic(String),
-}
-
-fn tokenizer_for(model: TokenModel) -> Result<Tokenizer, TokenizerInitError> {
+fn tokenizer_for(model: TokenModel) -> Result<Tokenizer, DomainError> {
     match model {
         TokenModel::OpenAiGpt4o | TokenModel::OpenAiGpt4oMini => {
             gpt4o_tokenizer().map(Tokenizer::Bpe)
@@ -437,24 +945,30 @@ fn tokenizer_for(model: TokenModel) -> Result<Tokenizer, TokenizerInitError> {
     }
 }
 
-fn gpt4o_tokenizer() -> Result<Arc<Mutex<CoreBPE>>, TokenizerInitError> {
-    static GPT4O: OnceLock<Result<Arc<Mutex<CoreBPE>>, TokenizerInitError>> = OnceLock::new();
+fn gpt4o_tokenizer() -> Result<Arc<Mutex<CoreBPE>>, DomainError> {
+    static GPT4O: OnceLock<Result<Arc<Mutex<CoreBPE>>, DomainError>> = OnceLock::new();
     GPT4O
         .get_or_init(|| {
             o200k_base()
                 .map(|bpe| Arc::new(Mutex::new(bpe)))
-                .map_err(|err| TokenizerInitError::OpenAi(err.to_string()))
+                .map_err(|err| DomainError::TokenizerUnavailable {
+                    model: "OpenAI".to_string(),
+                    reason: err.to_string(),
+                })
         })
         .clone()
 }
 
-fn claude_tokenizer() -> Result<Arc<Mutex<CoreBPE>>, TokenizerInitError> {
-    static CLAUDE: OnceLock<Result<Arc<Mutex<CoreBPE>>, TokenizerInitError>> = OnceLock::new();
+fn claude_tokenizer() -> Result<Arc<Mutex<CoreBPE>>, DomainError> {
+    static CLAUDE: OnceLock<Result<Arc<Mutex<CoreBPE>>, DomainError>> = OnceLock::new();
     CLAUDE
         .get_or_init(|| {
             cl100k_base()
                 .map(|bpe| Arc::new(Mutex::new(bpe)))
-                .map_err(|err| TokenizerInitError::Anthropic(err.to_string()))
+                .map_err(|err| DomainError::TokenizerUnavailable {
+                    model: "Anthropic".to_string(),
+                    reason: err.to_string(),
+                })
         })
         .clone()
 }
@@ -465,8 +979,35 @@ mod tests {
 
     use std::io::Write;
 
+    use crate::infra::fs::MemoryFs;
+
     use tempfile::NamedTempFile;
 
+    #[test]
+    fn approximates_from_size_by_kind_of_file() {
+        let estimator = TokenEstimator::new(TokenModel::CharacterFallback);
+        let prose = estimator.approximate_size(None, Path::new("notes.md"), 4_000);
+        let data = estimator.approximate_size(None, Path::new("data.json"), 4_000);
+        let code = estimator.approximate_size(None, Path::new("lib.rs"), 4_000);
+        assert_eq!(prose, 1_000);
+        assert!(prose < code && code < data);
+        assert_eq!(
+            estimator.approximate_size(None, Path::new("empty.rs"), 0),
+            0
+        );
+
+        let total = estimator.approximate_files(
+            None,
+            &[
+                (PathBuf::from("notes.md"), 4_000),
+                (PathBuf::from("lib.rs"), 4_000),
+            ],
+        );
+        assert_eq!(total.files, 2);
+        assert_eq!(total.tokens, prose + code);
+        assert_eq!(total.characters, 8_000);
+    }
+
     fn temp_selection(contents: &str) -> (SelectionItem, NamedTempFile) {
         let mut file = NamedTempFile::new().unwrap();
         file.write_all(contents.as_bytes()).unwrap();
@@ -474,10 +1015,63 @@ mod tests {
             path: file.path().to_path_buf(),
             range: None,
             note: None,
+            source: SelectionSource::Manual,
         };
         (item, file)
     }
 
+    #[test]
+    fn limit_errors_name_the_most_expensive_selections() {
+        let estimate = |path: &str, tokens| ItemTokenEstimate {
+            item: SelectionItem {
+                path: path.into(),
+                range: None,
+                note: None,
+                source: SelectionSource::Manual,
+            },
+            tokens,
+            characters: tokens * 4,
+            words: tokens,
+            lines: tokens / 10,
+        };
+        let summary = BundleTokenSummary {
+            model: TokenModel::CharacterFallback,
+            token_budget: 0,
+            total_tokens: 1_000,
+            total_characters: 4_000,
+            total_words: 1_000,
+            total_lines: 100,
+            stripped_tokens: 0,
+            items: vec![
+                estimate("a.rs", 100),
+                estimate("b.rs", 400),
+                estimate("c.rs", 200),
+                estimate("d.rs", 300),
+            ],
+        };
+        assert!(summary.check_limit(1_000).is_ok());
+
+        let Err(DomainError::BudgetExceeded {
+            budget, largest, ..
+        }) = summary.check_limit(900)
+        else {
+            panic!("expected the bundle to be over the limit");
+        };
+        assert_eq!(budget, 900);
+        let paths: Vec<_> = largest
+            .iter()
+            .map(|offender| offender.to_string())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                "b.rs (400 tokens)",
+                "d.rs (300 tokens)",
+                "c.rs (200 tokens)"
+            ]
+        );
+    }
+
     #[test]
     fn parses_token_models_from_strings() {
         assert_eq!(
@@ -501,6 +1095,7 @@ mod tests {
         let bundle = ContextBundle {
             items: vec![selection.clone()],
             model: Some("openai:gpt-4o".into()),
+            attachments: Vec::new(),
         };
         let estimator = TokenEstimator::new(TokenModel::OpenAiGpt4o);
         let summary = estimator.estimate_bundle(&bundle).unwrap();
@@ -515,6 +1110,7 @@ mod tests {
         let bundle = ContextBundle {
             items: vec![selection.clone()],
             model: Some("anthropic:claude-3.5-sonnet".into()),
+            attachments: Vec::new(),
         };
         let estimator = TokenEstimator::new(TokenModel::AnthropicClaude35Sonnet);
         let summary = estimator.estimate_bundle(&bundle).unwrap();
@@ -533,15 +1129,19 @@ mod tests {
             path: file.path().to_path_buf(),
             range: Some((2, 3)),
             note: None,
+            source: SelectionSource::Manual,
         };
         let bundle = ContextBundle {
             items: vec![selection],
             model: Some("openai:gpt-4o-mini".into()),
+            attachments: Vec::new(),
         };
         let estimator = TokenEstimator::new(TokenModel::OpenAiGpt4oMini);
         let summary = estimator.estimate_bundle(&bundle).unwrap();
         assert!(summary.total_tokens > 0);
         assert!(summary.total_characters < "fn main() {}\n// comment\nprintln!(\"done\");\n".len());
+        assert_eq!(summary.total_lines, 2);
+        assert_eq!(summary.total_words, 3);
     }
 
     #[test]
@@ -550,6 +1150,7 @@ mod tests {
         let bundle = ContextBundle {
             items: vec![selection.clone()],
             model: Some("fallback:characters".into()),
+            attachments: Vec::new(),
         };
         let estimator = TokenEstimator::new(TokenModel::CharacterFallback);
         let summary = estimator.estimate_bundle(&bundle).unwrap();
@@ -564,10 +1165,12 @@ mod tests {
             path: file.path().to_path_buf(),
             range: None,
             note: None,
+            source: SelectionSource::Manual,
         };
         let bundle = ContextBundle {
             items: vec![selection.clone()],
             model: Some("openai:gpt-4o".into()),
+            attachments: Vec::new(),
         };
         let estimator = TokenEstimator::new(TokenModel::OpenAiGpt4o);
 
@@ -582,6 +1185,107 @@ mod tests {
         assert!(second.total_tokens >= first.total_tokens);
     }
 
+    #[test]
+    fn cache_records_hits_and_misses() {
+        let (selection, _temp) = temp_selection("Cache me if you can.");
+        let bundle = ContextBundle {
+            items: vec![selection],
+            model: Some("openai:gpt-4o".into()),
+            attachments: Vec::new(),
+        };
+        let estimator = TokenEstimator::new(TokenModel::OpenAiGpt4o);
+
+        estimator.estimate_bundle(&bundle).unwrap();
+        estimator.estimate_bundle(&bundle).unwrap();
+
+        let stats = estimator.cache_stats();
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.entries, 1);
+        assert!(stats.bytes > 0);
+    }
+
+    #[test]
+    fn identical_contents_share_cache_entries() {
+        let vfs = MemoryFs::new();
+        vfs.write("/repo/first.txt", "same text");
+        vfs.write("/repo/second.txt", "same text");
+        let selection = |path: &str| SelectionItem {
+            path: path.into(),
+            range: None,
+            note: None,
+            source: SelectionSource::Manual,
+        };
+        let bundle = ContextBundle {
+            items: vec![selection("/repo/first.txt"), selection("/repo/second.txt")],
+            model: Some("openai:gpt-4o".into()),
+            attachments: Vec::new(),
+        };
+        let mut estimator = TokenEstimator::new(TokenModel::OpenAiGpt4o);
+        estimator.set_vfs(Arc::new(vfs.clone()));
+        let summary = estimator.estimate_bundle(&bundle).unwrap();
+
+        assert_eq!(summary.items[0].tokens, summary.items[1].tokens);
+        let stats = estimator.cache_stats();
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.entries, 1);
+
+        vfs.remove(Path::new("/repo/second.txt"));
+        let missing = estimator.estimate_bundle(&bundle).unwrap_err();
+        assert!(matches!(
+            missing.root_cause().downcast_ref::<DomainError>(),
+            Some(DomainError::FileMissing { .. })
+        ));
+    }
+
+    #[test]
+    fn cache_evicts_least_recently_used_entries() {
+        let selections: Vec<_> = ["alpha", "beta", "gamma"]
+            .iter()
+            .map(|text| temp_selection(text))
+            .collect();
+        let mut estimator = TokenEstimator::new(TokenModel::OpenAiGpt4o);
+        estimator.set_cache_config(TokenCacheConfig {
+            max_entries: 2,
+            max_bytes: usize::MAX,
+        });
+
+        for (selection, _) in &selections {
+            let bundle = ContextBundle {
+                items: vec![selection.clone()],
+                model: None,
+                attachments: Vec::new(),
+            };
+            estimator.estimate_bundle(&bundle).unwrap();
+        }
+
+        let stats = estimator.cache_stats();
+        assert_eq!(stats.entries, 2);
+        assert_eq!(stats.evictions, 1);
+
+        let oldest = ContextBundle {
+            items: vec![selections[0].0.clone()],
+            model: None,
+            attachments: Vec::new(),
+        };
+        estimator.estimate_bundle(&oldest).unwrap();
+        assert_eq!(estimator.cache_stats().misses, 4);
+    }
+
+    #[test]
+    fn estimate_files_aggregates_and_counts_unreadable() {
+        let (first, _first_temp) = temp_selection("Hello world!");
+        let (second, _second_temp) = temp_selection("Hello world!");
+        let missing = PathBuf::from("/definitely/not/here.rs");
+        let estimator = TokenEstimator::new(TokenModel::OpenAiGpt4o);
+
+        let aggregate = estimator.estimate_files(None, &[first.path, second.path, missing]);
+
+        assert_eq!(aggregate.files, 2);
+        assert_eq!(aggregate.tokens, 6);
+        assert_eq!(aggregate.unreadable, 1);
+    }
+
     #[test]
     fn estimator_respects_config_defaults() {
         let config: Config = toml::from_str(
@@ -596,4 +1300,55 @@ mod tests {
         assert_eq!(estimator.model(), TokenModel::AnthropicClaude3Haiku);
         assert_eq!(estimator.token_budget(), 42_000);
     }
+
+    #[test]
+    fn calibration_compares_estimates_and_suggests_a_multiplier() {
+        let (code, _code_temp) = temp_selection("fn main() { println!(\"hi\"); }");
+        let (blank, _blank_temp) = temp_selection("   \n");
+        let bundle = ContextBundle {
+            items: vec![code, blank],
+            model: Some("openai:gpt-4o".to_string()),
+            attachments: Vec::new(),
+        };
+        let estimator = TokenEstimator::new(TokenModel::OpenAiGpt4oMini);
+        let estimated = estimator.estimate_bundle(&bundle).unwrap().total_tokens;
+
+        let report = estimator.calibrate(&bundle, |text| Ok(text.len())).unwrap();
+        assert_eq!(report.model, TokenModel::OpenAiGpt4o);
+        assert_eq!(report.samples.len(), 1);
+        assert_eq!(report.estimated_total(), estimated);
+        let expected = report.actual_total() as f32 / estimated as f32;
+        assert_eq!(
+            report.suggested_multiplier(),
+            Some((expected * 100.0).round() / 100.0)
+        );
+
+        let config: Config = toml::from_str("[tokens]\nopenai_multiplier = 2.0").unwrap();
+        let scaled = TokenEstimator::from_config(&config);
+        assert_eq!(
+            scaled.estimate_bundle(&bundle).unwrap().total_tokens,
+            estimated * 2
+        );
+    }
+
+    #[test]
+    fn estimator_applies_configured_cache_limits() {
+        let config: Config = toml::from_str(
+            r#"
+            [tokens]
+            cache_max_entries = 1
+            "#,
+        )
+        .unwrap();
+        let estimator = TokenEstimator::from_config(&config);
+        let (first, _first_temp) = temp_selection("one");
+        let (second, _second_temp) = temp_selection("two");
+        let bundle = ContextBundle {
+            items: vec![first, second],
+            model: None,
+            attachments: Vec::new(),
+        };
+        estimator.estimate_bundle(&bundle).unwrap();
+        assert_eq!(estimator.cache_stats().entries, 1);
+    }
 }
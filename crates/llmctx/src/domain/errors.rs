@@ -1,9 +1,134 @@
 //! Domain-specific errors.
+//!
+//! App-layer functions return these inside [`anyhow::Error`]; front ends recover them with
+//! [`DomainError::find`] to pair the message with a [`DomainError::hint`].
+
+use std::fmt;
+use std::path::PathBuf;
 
 use thiserror::Error;
 
-#[derive(Debug, Error)]
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
 pub enum DomainError {
     #[error("invalid selection")]
     InvalidSelection,
+    #[error(
+        "lines {start}-{end} are out of bounds for {} ({line_count} lines)",
+        path.display()
+    )]
+    SelectionOutOfBounds {
+        path: PathBuf,
+        start: usize,
+        end: usize,
+        line_count: usize,
+    },
+    #[error("file not found: {}", path.display())]
+    FileMissing { path: PathBuf },
+    #[error(
+        "bundle needs {tokens} tokens, over the {budget}-token budget{}",
+        largest_selections(largest)
+    )]
+    BudgetExceeded {
+        tokens: usize,
+        budget: usize,
+        /// Most expensive selections, largest first.
+        largest: Vec<BudgetOffender>,
+    },
+    #[error("template '{name}' not found (built-in or filesystem)")]
+    TemplateNotFound { name: String },
+    #[error("{model} tokenizer is unavailable: {reason}")]
+    TokenizerUnavailable { model: String, reason: String },
+    #[error("export output already exists: {}", path.display())]
+    OutputExists { path: PathBuf },
+    #[error("operation cancelled")]
+    Cancelled,
+}
+
+impl DomainError {
+    /// Suggested remediation to show alongside the error message.
+    pub fn hint(&self) -> String {
+        match self {
+            Self::InvalidSelection => {
+                "selections look like path, path:START-END, or path:START-END#note".to_string()
+            }
+            Self::SelectionOutOfBounds { line_count, .. } => {
+                format!(
+                    "pick a range within lines 1-{line_count}, or drop the range to select the whole file"
+                )
+            }
+            Self::FileMissing { .. } => {
+                "check the path, or pass --root when it is relative to another workspace"
+                    .to_string()
+            }
+            Self::BudgetExceeded { .. } => {
+                "drop or narrow the largest selections, or raise defaults.token_budget in .llmctx/config.toml (--max-tokens on the command line)"
+                    .to_string()
+            }
+            Self::TemplateNotFound { .. } => {
+                "use a built-in template (concise_context, plain_text, grouped_context) or a path to a template file"
+                    .to_string()
+            }
+            Self::TokenizerUnavailable { .. } => {
+                "token counts fall back to heuristics; choose another model to get exact counts"
+                    .to_string()
+            }
+            Self::OutputExists { .. } => {
+                "pass --force to replace it, or --output-dir to write a numbered file next to it"
+                    .to_string()
+            }
+            Self::Cancelled => "run the command again to start over".to_string(),
+        }
+    }
+
+    /// The first [`DomainError`] in `err`'s chain of causes.
+    pub fn find(err: &anyhow::Error) -> Option<&DomainError> {
+        err.chain().find_map(|cause| cause.downcast_ref())
+    }
+}
+
+/// A selection named in [`DomainError::BudgetExceeded`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BudgetOffender {
+    pub path: PathBuf,
+    pub range: Option<(usize, usize)>,
+    pub tokens: usize,
+}
+
+impl fmt::Display for BudgetOffender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.display())?;
+        if let Some((start, end)) = self.range {
+            write!(f, ":{start}-{end}")?;
+        }
+        write!(f, " ({} tokens)", self.tokens)
+    }
+}
+
+fn largest_selections(largest: &[BudgetOffender]) -> String {
+    if largest.is_empty() {
+        return String::new();
+    }
+    let listed: Vec<String> = largest.iter().map(ToString::to_string).collect();
+    format!("; largest selections: {}", listed.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use anyhow::Context;
+
+    #[test]
+    fn domain_errors_are_found_beneath_context() {
+        let err = Err::<(), _>(DomainError::FileMissing {
+            path: PathBuf::from("src/gone.rs"),
+        })
+        .context("failed to read selection 'src/gone.rs'")
+        .unwrap_err();
+        let found = DomainError::find(&err).expect("domain error in chain");
+        assert!(matches!(found, DomainError::FileMissing { .. }));
+        assert!(found.hint().contains("--root"));
+
+        assert!(DomainError::find(&anyhow::anyhow!("unrelated")).is_none());
+    }
 }
@@ -1,14 +1,346 @@
 //! Domain models for selections, bundles, and exports.
+//!
+//! These types are the canonical serialized form shared by sessions and export manifests. Every
+//! serialized [`ContextBundle`], session, and manifest is tagged with a [`SchemaVersion`].
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Version of the serialized bundle, session, and manifest schema, bumped on incompatible changes.
+pub const SCHEMA_VERSION: u32 = 1;
+/// Number of selections listed in [`BundleStats::largest`].
+const LARGEST_ITEMS: usize = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SelectionItem {
-    pub path: std::path::PathBuf,
+    pub path: PathBuf,
+    /// Inclusive, 1-based line range; `None` selects the whole file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub range: Option<(usize, usize)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub note: Option<String>,
+    /// How the selection entered the bundle; omitted when serialized for manual selections.
+    #[serde(default, skip_serializing_if = "SelectionSource::is_manual")]
+    pub source: SelectionSource,
+}
+
+impl SelectionItem {
+    /// Lines covered in a file of `total` lines, clamping ranges to the end of the file.
+    pub fn selected_lines(&self, total: usize) -> usize {
+        match self.range {
+            Some((start, end)) => end.min(total).saturating_sub(start.saturating_sub(1)),
+            None => total,
+        }
+    }
+}
+
+/// Provenance of a selection, so a bundle's assembly can be audited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SelectionSource {
+    /// Picked by hand in the TUI or listed on the command line.
+    #[default]
+    Manual,
+    /// Matched by a glob, from a recipe or `.llmctx/always-include.toml`.
+    Glob,
+    /// Changed lines of a pull request or diff under review.
+    Diff,
+    /// Matched by the file tree filter or a content search.
+    SearchMatch,
+    /// Restored from a saved session or export manifest.
+    SessionRestore,
+    /// Contributed by a plugin.
+    Plugin,
+    /// Proposed by `llmctx suggest` and accepted in review.
+    Suggested,
+    /// Calls or is called by selected code, added by `:expand-calls`.
+    CallGraph,
+}
+
+impl SelectionSource {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Manual => "manual",
+            Self::Glob => "glob",
+            Self::Diff => "diff",
+            Self::SearchMatch => "search-match",
+            Self::SessionRestore => "session-restore",
+            Self::Plugin => "plugin",
+            Self::Suggested => "suggested",
+            Self::CallGraph => "call-graph",
+        }
+    }
+
+    pub fn is_manual(&self) -> bool {
+        *self == Self::Manual
+    }
+}
+
+/// Free-form document attached to a bundle, such as the issue a change addresses.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Attachment {
+    pub title: String,
+    /// URL or file path the text was read from.
+    pub source: String,
+    pub body: String,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "BundleDocument", from = "BundleDocument")]
 pub struct ContextBundle {
     pub items: Vec<SelectionItem>,
     pub model: Option<String>,
+    /// Non-file documents rendered in the export's task section.
+    pub attachments: Vec<Attachment>,
+}
+
+/// Schema version a document was written with; reading one newer than [`SCHEMA_VERSION`] fails.
+///
+/// Defaults to the current version, which is also what documents from before the tag existed
+/// are read as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "u32", try_from = "u32")]
+pub struct SchemaVersion(u32);
+
+impl Default for SchemaVersion {
+    fn default() -> Self {
+        Self(SCHEMA_VERSION)
+    }
+}
+
+impl From<SchemaVersion> for u32 {
+    fn from(version: SchemaVersion) -> Self {
+        version.0
+    }
+}
+
+impl TryFrom<u32> for SchemaVersion {
+    type Error = SchemaError;
+
+    fn try_from(found: u32) -> Result<Self, Self::Error> {
+        if found > SCHEMA_VERSION {
+            return Err(SchemaError::UnsupportedVersion { found });
+        }
+        Ok(Self(found))
+    }
+}
+
+/// Serialized layout of a [`ContextBundle`], tagged with the schema version that wrote it.
+#[derive(Serialize, Deserialize)]
+struct BundleDocument {
+    schema_version: SchemaVersion,
+    items: Vec<SelectionItem>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    model: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    attachments: Vec<Attachment>,
+}
+
+/// Errors raised when reading a serialized bundle, session, or manifest.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SchemaError {
+    #[error(
+        "schema version {found} is newer than the supported version {SCHEMA_VERSION}; upgrade llmctx to read it"
+    )]
+    UnsupportedVersion { found: u32 },
+}
+
+impl From<ContextBundle> for BundleDocument {
+    fn from(bundle: ContextBundle) -> Self {
+        Self {
+            schema_version: SchemaVersion::default(),
+            items: bundle.items,
+            model: bundle.model,
+            attachments: bundle.attachments,
+        }
+    }
+}
+
+impl From<BundleDocument> for ContextBundle {
+    fn from(document: BundleDocument) -> Self {
+        Self {
+            items: document.items,
+            model: document.model,
+            attachments: document.attachments,
+        }
+    }
+}
+
+impl ContextBundle {
+    /// Tokenizer-independent overview of the bundle, with `lines` giving the lines each selection
+    /// covers (see [`SelectionItem::selected_lines`]).
+    ///
+    /// Nothing is read here; callers count lines through their file source or reuse token
+    /// estimates. Files that cannot be read should report no lines but are still counted.
+    pub fn stats(&self, mut lines: impl FnMut(&SelectionItem) -> usize) -> BundleStats {
+        let mut files = HashSet::new();
+        let mut languages: BTreeMap<String, LanguageStats> = BTreeMap::new();
+        let mut sizes = Vec::with_capacity(self.items.len());
+        for item in &self.items {
+            let lines = lines(item);
+            let language = language_of(&item.path);
+            let entry = languages
+                .entry(language.clone())
+                .or_insert_with(|| LanguageStats {
+                    language,
+                    files: 0,
+                    lines: 0,
+                });
+            if files.insert(item.path.as_path()) {
+                entry.files += 1;
+            }
+            entry.lines += lines;
+            sizes.push(ItemStats {
+                path: item.path.clone(),
+                range: item.range,
+                lines,
+            });
+        }
+
+        let mut languages: Vec<LanguageStats> = languages.into_values().collect();
+        languages.sort_by_key(|language| Reverse(language.lines));
+        sizes.sort_by_key(|size| Reverse(size.lines));
+        sizes.truncate(LARGEST_ITEMS);
+        BundleStats {
+            files: files.len(),
+            total_lines: languages.iter().map(|language| language.lines).sum(),
+            languages,
+            largest: sizes,
+        }
+    }
+}
+
+/// File, line, and language counts for a [`ContextBundle`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleStats {
+    /// Distinct files selected.
+    pub files: usize,
+    /// Lines across all selections.
+    pub total_lines: usize,
+    /// Breakdown by language, most lines first.
+    pub languages: Vec<LanguageStats>,
+    /// Selections with the most lines, largest first.
+    pub largest: Vec<ItemStats>,
+}
+
+/// Files and lines selected for one language, keyed by file extension.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LanguageStats {
+    pub language: String,
+    pub files: usize,
+    pub lines: usize,
+}
+
+/// Line count of a single selection.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItemStats {
+    pub path: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range: Option<(usize, usize)>,
+    pub lines: usize,
+}
+
+/// Language key of `path` in [`BundleStats`]: its lowercase extension, or `other`.
+pub(crate) fn language_of(path: &Path) -> String {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+        .unwrap_or_else(|| "other".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(path: &Path, range: Option<(usize, usize)>) -> SelectionItem {
+        SelectionItem {
+            path: path.to_path_buf(),
+            range,
+            note: None,
+            source: SelectionSource::Manual,
+        }
+    }
+
+    #[test]
+    fn stats_count_files_lines_and_languages() {
+        let lib = Path::new("lib.rs");
+        let main = Path::new("main.RS");
+        let readme = Path::new("README.md");
+        let missing = Path::new("missing.txt");
+        let totals = BTreeMap::from([(lib, 6), (main, 1), (readme, 3)]);
+
+        let bundle = ContextBundle {
+            items: vec![
+                item(lib, Some((2, 3))),
+                item(lib, Some((5, 40))),
+                item(main, None),
+                item(readme, None),
+                item(missing, None),
+            ],
+            model: None,
+            attachments: Vec::new(),
+        };
+        let stats = bundle.stats(|item| {
+            totals
+                .get(item.path.as_path())
+                .map_or(0, |total| item.selected_lines(*total))
+        });
+
+        assert_eq!(stats.files, 4);
+        assert_eq!(stats.total_lines, 8);
+        assert_eq!(
+            stats.languages[..2],
+            [
+                LanguageStats {
+                    language: "rs".to_string(),
+                    files: 2,
+                    lines: 5,
+                },
+                LanguageStats {
+                    language: "md".to_string(),
+                    files: 1,
+                    lines: 3,
+                },
+            ]
+        );
+        assert_eq!(stats.largest.len(), 3);
+        assert_eq!(stats.largest[0].path, readme);
+        assert_eq!(stats.largest[0].lines, 3);
+    }
+
+    #[test]
+    fn bundles_round_trip_with_a_schema_version() {
+        let bundle = ContextBundle {
+            items: vec![
+                item(Path::new("src/lib.rs"), Some((3, 9))),
+                SelectionItem {
+                    note: Some("entry point".to_string()),
+                    source: SelectionSource::Glob,
+                    ..item(Path::new("src/main.rs"), None)
+                },
+            ],
+            model: Some("openai:gpt-4o".to_string()),
+            attachments: Vec::new(),
+        };
+        let json = serde_json::to_value(&bundle).unwrap();
+        assert_eq!(json["schema_version"], SCHEMA_VERSION);
+        assert_eq!(
+            json["items"][0],
+            serde_json::json!({ "path": "src/lib.rs", "range": [3, 9] })
+        );
+        assert_eq!(json["items"][1]["source"], "glob");
+        assert!(json.get("attachments").is_none());
+        assert_eq!(
+            serde_json::from_value::<ContextBundle>(json).unwrap(),
+            bundle
+        );
+
+        let future = serde_json::json!({ "schema_version": SCHEMA_VERSION + 1, "items": [] });
+        let err = serde_json::from_value::<ContextBundle>(future).unwrap_err();
+        assert!(err.to_string().contains("newer than the supported version"));
+    }
 }
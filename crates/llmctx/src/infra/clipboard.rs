@@ -2,6 +2,7 @@
 
 use std::io::Write;
 use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
 
 use anyhow::{Context, Result, anyhow};
 
@@ -37,6 +38,44 @@ impl Default for Clipboard {
     }
 }
 
+/// Destination for copied text; [`Clipboard`] is the system clipboard.
+pub trait ClipboardBackend: Send {
+    fn copy(&mut self, text: &str) -> Result<()>;
+}
+
+impl ClipboardBackend for Clipboard {
+    fn copy(&mut self, text: &str) -> Result<()> {
+        Clipboard::copy(self, text)
+    }
+}
+
+/// Clipboard that keeps every copy in memory instead, for tests and for embedders that show
+/// copied text themselves.
+///
+/// Clones share their copies, so one can be handed over while another is inspected.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryClipboard {
+    copies: Arc<Mutex<Vec<String>>>,
+}
+
+impl MemoryClipboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Everything copied so far, oldest first.
+    pub fn copies(&self) -> Vec<String> {
+        self.copies.lock().unwrap().clone()
+    }
+}
+
+impl ClipboardBackend for MemoryClipboard {
+    fn copy(&mut self, text: &str) -> Result<()> {
+        self.copies.lock().unwrap().push(text.to_owned());
+        Ok(())
+    }
+}
+
 fn fallback_copy(text: &str) -> Result<()> {
     for command in fallback_commands() {
         if try_command_copy(command, text).is_ok() {
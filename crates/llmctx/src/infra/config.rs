@@ -1,21 +1,22 @@
 //! Configuration management utilities.
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, anyhow};
 use dirs_next::config_dir;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
+use toml_edit::{DocumentMut, Item, Table, value};
 
 static DEFAULT_CONFIG: Lazy<&'static str> =
     Lazy::new(|| include_str!("../../assets/default-config.toml"));
 static DEFAULT_WORKSPACE_CONFIG_PATH: &str = ".llmctx/config.toml";
 
 /// Layered configuration loaded from defaults, user, workspace, and env.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 pub struct Config {
     #[serde(default)]
     pub defaults: Defaults,
@@ -24,7 +25,16 @@ pub struct Config {
     #[serde(default)]
     pub export: Export,
     #[serde(default)]
+    pub tokens: Tokens,
+    #[serde(default)]
+    pub preview: Preview,
+    #[serde(default)]
+    pub search: Search,
+    #[serde(default)]
     pub keybindings: Keybindings,
+    /// Named exports run with `llmctx run <name>` or `:recipe <name>` in the TUI.
+    #[serde(default)]
+    pub recipes: BTreeMap<String, Recipe>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -41,6 +51,12 @@ pub struct Defaults {
     preview_max_lines: Option<usize>,
     #[serde(default)]
     show_hidden: Option<bool>,
+    #[serde(default)]
+    include_submodules: Option<bool>,
+    #[serde(default)]
+    max_scan_files: Option<usize>,
+    #[serde(default)]
+    max_scan_bytes: Option<u64>,
 }
 
 impl Defaults {
@@ -64,6 +80,14 @@ impl Defaults {
         400
     }
 
+    fn default_max_scan_files() -> usize {
+        200_000
+    }
+
+    fn default_max_scan_bytes() -> u64 {
+        4 * 1024 * 1024 * 1024
+    }
+
     pub fn model(&self) -> &str {
         self.model.as_deref().unwrap_or(Self::default_model())
     }
@@ -82,6 +106,10 @@ impl Defaults {
         self.theme.as_deref().unwrap_or(Self::default_theme())
     }
 
+    pub fn set_theme(&mut self, theme: impl Into<String>) {
+        self.theme = Some(theme.into());
+    }
+
     pub fn preview_max_lines(&self) -> usize {
         self.preview_max_lines
             .unwrap_or_else(Self::default_preview_max_lines)
@@ -90,6 +118,22 @@ impl Defaults {
     pub fn show_hidden(&self) -> bool {
         self.show_hidden.unwrap_or(false)
     }
+
+    pub fn include_submodules(&self) -> bool {
+        self.include_submodules.unwrap_or(false)
+    }
+
+    /// Entries after which a workspace scan stops; `0` removes the limit.
+    pub fn max_scan_files(&self) -> usize {
+        self.max_scan_files
+            .unwrap_or_else(Self::default_max_scan_files)
+    }
+
+    /// Cumulative file size after which a workspace scan stops; `0` removes the limit.
+    pub fn max_scan_bytes(&self) -> u64 {
+        self.max_scan_bytes
+            .unwrap_or_else(Self::default_max_scan_bytes)
+    }
 }
 
 impl Default for Defaults {
@@ -101,6 +145,9 @@ impl Default for Defaults {
             theme: Some(Self::default_theme().to_owned()),
             preview_max_lines: Some(Self::default_preview_max_lines()),
             show_hidden: Some(false),
+            include_submodules: Some(false),
+            max_scan_files: Some(Self::default_max_scan_files()),
+            max_scan_bytes: Some(Self::default_max_scan_bytes()),
         }
     }
 }
@@ -135,6 +182,35 @@ pub struct Export {
     include_line_numbers: Option<bool>,
     #[serde(default)]
     template: Option<String>,
+    #[serde(default)]
+    include_notebook_outputs: Option<bool>,
+    #[serde(default)]
+    history_commits: Option<usize>,
+    #[serde(default)]
+    include_manifest: Option<bool>,
+    #[serde(default)]
+    merge_gap_lines: Option<usize>,
+    #[serde(default)]
+    context_lines: Option<usize>,
+    #[serde(default)]
+    enforce_budget: Option<bool>,
+    #[serde(default)]
+    strip: Option<Vec<String>>,
+    #[serde(default)]
+    tab_width: Option<usize>,
+    #[serde(default)]
+    filename: Option<String>,
+    #[serde(default)]
+    post_action: Option<String>,
+    #[serde(default)]
+    compare_mode: Option<String>,
+    #[serde(default)]
+    group_by: Option<String>,
+    #[serde(default)]
+    snippets: Option<Vec<String>>,
+    /// Labels such as a project or team name that templates read as `metadata`.
+    #[serde(default)]
+    metadata: BTreeMap<String, String>,
 }
 
 impl Export {
@@ -150,6 +226,10 @@ impl Export {
         "concise_context"
     }
 
+    fn default_filename() -> &'static str {
+        "context-{timestamp}.{ext}"
+    }
+
     pub fn include_git_metadata(&self) -> bool {
         self.include_git_metadata
             .unwrap_or_else(Self::default_include_git_metadata)
@@ -165,6 +245,77 @@ impl Export {
             .clone()
             .unwrap_or_else(|| Self::default_template().to_owned())
     }
+
+    pub fn include_notebook_outputs(&self) -> bool {
+        self.include_notebook_outputs.unwrap_or(false)
+    }
+
+    /// Number of recent commits listed per exported file; `0` disables history.
+    pub fn history_commits(&self) -> usize {
+        self.history_commits.unwrap_or(0)
+    }
+
+    /// Append a machine-readable manifest so the export can be regenerated later.
+    pub fn include_manifest(&self) -> bool {
+        self.include_manifest.unwrap_or(false)
+    }
+
+    /// Ranges of a file separated by fewer than this many lines are exported as one span.
+    pub fn merge_gap_lines(&self) -> usize {
+        self.merge_gap_lines.unwrap_or(0)
+    }
+
+    /// Lines of context exported above and below each ranged selection.
+    pub fn context_lines(&self) -> usize {
+        self.context_lines.unwrap_or(0)
+    }
+
+    /// Refuse to export bundles estimated over `defaults.token_budget`.
+    pub fn enforce_budget(&self) -> bool {
+        self.enforce_budget.unwrap_or(false)
+    }
+
+    /// Transforms applied to selection contents before rendering, e.g. `comments`.
+    pub fn strip(&self) -> Vec<String> {
+        self.strip.clone().unwrap_or_default()
+    }
+
+    /// Columns between tab stops when expanding tabs on export; `0` keeps tabs.
+    pub fn tab_width(&self) -> usize {
+        self.tab_width.unwrap_or(0)
+    }
+
+    /// Pattern for automatically named exports, e.g. `{repo}-{branch}-{timestamp}.{ext}`.
+    pub fn filename(&self) -> String {
+        self.filename
+            .clone()
+            .unwrap_or_else(|| Self::default_filename().to_owned())
+    }
+
+    /// What the TUI does with a written export: `none`, `open-editor`, or `open-pager`.
+    pub fn post_action(&self) -> &str {
+        self.post_action.as_deref().unwrap_or("none")
+    }
+
+    /// How `--compare` shows changed selections: `both` versions, or a `diff` and the new one.
+    pub fn compare_mode(&self) -> &str {
+        self.compare_mode.as_deref().unwrap_or("both")
+    }
+
+    /// What templates group selections by: `directory` or `language`.
+    pub fn group_by(&self) -> &str {
+        self.group_by.as_deref().unwrap_or("directory")
+    }
+
+    /// Names of `.llmctx/snippets/*.md` files appended to every export, in order.
+    pub fn snippets(&self) -> Vec<String> {
+        self.snippets.clone().unwrap_or_default()
+    }
+
+    /// User-defined `[export.metadata]` labels, by key.
+    pub fn metadata(&self) -> &BTreeMap<String, String> {
+        &self.metadata
+    }
 }
 
 impl Default for Export {
@@ -173,10 +324,176 @@ impl Default for Export {
             include_git_metadata: Some(Self::default_include_git_metadata()),
             include_line_numbers: Some(Self::default_include_line_numbers()),
             template: Some(Self::default_template().to_owned()),
+            include_notebook_outputs: Some(false),
+            history_commits: Some(0),
+            include_manifest: Some(false),
+            merge_gap_lines: Some(0),
+            context_lines: Some(0),
+            enforce_budget: Some(false),
+            strip: Some(Vec::new()),
+            tab_width: Some(0),
+            filename: Some(Self::default_filename().to_owned()),
+            post_action: Some("none".to_owned()),
+            compare_mode: Some("both".to_owned()),
+            group_by: Some("directory".to_owned()),
+            snippets: Some(Vec::new()),
+            metadata: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tokens {
+    #[serde(default)]
+    cache_max_entries: Option<usize>,
+    #[serde(default)]
+    cache_max_bytes: Option<usize>,
+    /// Scale applied to OpenAI estimates, tuned by `llmctx tokens --calibrate`.
+    #[serde(default)]
+    openai_multiplier: Option<f32>,
+    /// Scale applied to Anthropic estimates, tuned by `llmctx tokens --calibrate`.
+    #[serde(default)]
+    anthropic_multiplier: Option<f32>,
+    /// Estimate tree annotations and workspace totals from file sizes instead of reading files.
+    #[serde(default)]
+    approximate: Option<bool>,
+}
+
+impl Tokens {
+    fn default_cache_max_entries() -> usize {
+        4_096
+    }
+
+    fn default_cache_max_bytes() -> usize {
+        4 * 1024 * 1024
+    }
+
+    pub fn cache_max_entries(&self) -> usize {
+        self.cache_max_entries
+            .unwrap_or_else(Self::default_cache_max_entries)
+    }
+
+    pub fn cache_max_bytes(&self) -> usize {
+        self.cache_max_bytes
+            .unwrap_or_else(Self::default_cache_max_bytes)
+    }
+
+    pub fn openai_multiplier(&self) -> f32 {
+        self.openai_multiplier.unwrap_or(1.0)
+    }
+
+    pub fn anthropic_multiplier(&self) -> f32 {
+        self.anthropic_multiplier.unwrap_or(1.0)
+    }
+
+    pub fn approximate(&self) -> bool {
+        self.approximate.unwrap_or(true)
+    }
+}
+
+impl Default for Tokens {
+    fn default() -> Self {
+        Self {
+            cache_max_entries: Some(Self::default_cache_max_entries()),
+            cache_max_bytes: Some(Self::default_cache_max_bytes()),
+            openai_multiplier: Some(1.0),
+            anthropic_multiplier: Some(1.0),
+            approximate: Some(true),
         }
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Preview {
+    #[serde(default)]
+    indent_guides: Option<bool>,
+    #[serde(default)]
+    show_trailing_whitespace: Option<bool>,
+    #[serde(default)]
+    show_tabs: Option<bool>,
+    #[serde(default)]
+    tab_width: Option<usize>,
+}
+
+impl Preview {
+    fn default_tab_width() -> usize {
+        4
+    }
+
+    pub fn indent_guides(&self) -> bool {
+        self.indent_guides.unwrap_or(false)
+    }
+
+    pub fn show_trailing_whitespace(&self) -> bool {
+        self.show_trailing_whitespace.unwrap_or(false)
+    }
+
+    pub fn show_tabs(&self) -> bool {
+        self.show_tabs.unwrap_or(false)
+    }
+
+    pub fn tab_width(&self) -> usize {
+        self.tab_width
+            .filter(|width| *width > 0)
+            .unwrap_or_else(Self::default_tab_width)
+    }
+}
+
+impl Default for Preview {
+    fn default() -> Self {
+        Self {
+            indent_guides: Some(false),
+            show_trailing_whitespace: Some(false),
+            show_tabs: Some(false),
+            tab_width: Some(Self::default_tab_width()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Search {
+    /// `auto` (ripgrep when installed), `builtin`, or `ripgrep`.
+    #[serde(default)]
+    backend: Option<String>,
+}
+
+impl Search {
+    fn default_backend() -> &'static str {
+        "auto"
+    }
+
+    pub fn backend(&self) -> &str {
+        self.backend.as_deref().unwrap_or(Self::default_backend())
+    }
+}
+
+impl Default for Search {
+    fn default() -> Self {
+        Self {
+            backend: Some(Self::default_backend().to_string()),
+        }
+    }
+}
+
+/// A routine export: what to select and how to render it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Recipe {
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Selection specs (`path[:start-end][#note]`); paths may be globs such as `src/api/**/*.rs`.
+    #[serde(default)]
+    pub select: Vec<String>,
+    #[serde(default)]
+    pub template: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub format: Option<String>,
+    /// Output file, relative to the workspace root unless absolute.
+    #[serde(default)]
+    pub output: Option<PathBuf>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Keybindings {
     #[serde(default = "Keybindings::default_up")]
@@ -187,6 +504,9 @@ pub struct Keybindings {
     pub select: String,
     #[serde(default = "Keybindings::default_export")]
     pub export: String,
+    /// How long a multi-key sequence such as `g g` waits for its next key.
+    #[serde(default = "Keybindings::default_chord_timeout_ms")]
+    pub chord_timeout_ms: u64,
 }
 
 impl Keybindings {
@@ -205,6 +525,10 @@ impl Keybindings {
     fn default_export() -> String {
         "ctrl+e".into()
     }
+
+    fn default_chord_timeout_ms() -> u64 {
+        500
+    }
 }
 
 impl Default for Keybindings {
@@ -214,6 +538,7 @@ impl Default for Keybindings {
             down: Self::default_down(),
             select: Self::default_select(),
             export: Self::default_export(),
+            chord_timeout_ms: Self::default_chord_timeout_ms(),
         }
     }
 }
@@ -245,9 +570,14 @@ impl EnvOverrides {
 impl Config {
     /// Load configuration from defaults, user/global config, workspace config, and env overrides.
     pub fn load() -> Result<Self> {
+        Self::load_for(&env::current_dir()?)
+    }
+
+    /// Like [`Config::load`], but discover the workspace config from `start` instead of cwd.
+    pub fn load_for(start: &Path) -> Result<Self> {
         let env = EnvOverrides::from_env();
         let global = global_config_path();
-        let workspace = workspace_config_path()?;
+        let workspace = workspace_config_path(start);
         Self::load_with_layers(global, workspace, env)
     }
 
@@ -258,6 +588,34 @@ impl Config {
         Ok(defaults.merge(explicit))
     }
 
+    /// Parse `contents` as one configuration layer on top of defaults, as [`Config::load_from_path`]
+    /// does with a file's contents.
+    pub fn from_toml_layer(contents: &str) -> Result<Self> {
+        let defaults = Self::from_str(&DEFAULT_CONFIG)?;
+        Ok(defaults.merge(Self::from_str(contents)?))
+    }
+
+    /// Stable hash of the effective settings, recorded in export manifests.
+    pub fn fingerprint(&self) -> String {
+        let serialized = serde_json::to_vec(self).unwrap_or_default();
+        format!("{:016x}", xxhash_rust::xxh3::xxh3_64(&serialized))
+    }
+
+    /// Set `[section] key = value` in the user config file, keeping its other contents and
+    /// comments. Returns the path written.
+    pub fn write_user_setting(section: &str, key: &str, number: f64) -> Result<PathBuf> {
+        let path = global_config_path()
+            .ok_or_else(|| anyhow!("no user configuration directory on this platform"))?;
+        write_setting(&path, section, key, number)?;
+        Ok(path)
+    }
+
+    /// Directory holding the workspace `.llmctx/` for `start`: the enclosing git repository,
+    /// or `start` itself outside one.
+    pub fn workspace_dir(start: &Path) -> PathBuf {
+        find_repo_root(start).unwrap_or_else(|| start.to_path_buf())
+    }
+
     /// Merge another configuration on top of this instance, returning the combined result.
     pub fn merge_with(self, other: Config) -> Config {
         self.merge(other)
@@ -301,7 +659,11 @@ impl Config {
             defaults: merge_defaults(self.defaults, other.defaults),
             ignore: merge_ignore(self.ignore, other.ignore),
             export: merge_export(self.export, other.export),
+            tokens: merge_tokens(self.tokens, other.tokens),
+            preview: merge_preview(self.preview, other.preview),
+            search: merge_search(self.search, other.search),
             keybindings: merge_keybindings(self.keybindings, other.keybindings),
+            recipes: merge_recipes(self.recipes, other.recipes),
         }
     }
 }
@@ -325,6 +687,15 @@ fn merge_defaults(mut base: Defaults, overlay: Defaults) -> Defaults {
     if overlay.show_hidden.is_some() {
         base.show_hidden = overlay.show_hidden;
     }
+    if overlay.include_submodules.is_some() {
+        base.include_submodules = overlay.include_submodules;
+    }
+    if overlay.max_scan_files.is_some() {
+        base.max_scan_files = overlay.max_scan_files;
+    }
+    if overlay.max_scan_bytes.is_some() {
+        base.max_scan_bytes = overlay.max_scan_bytes;
+    }
     base
 }
 
@@ -351,6 +722,98 @@ fn merge_export(mut base: Export, overlay: Export) -> Export {
     if let Some(value) = overlay.template {
         base.template = Some(value);
     }
+    if let Some(value) = overlay.include_notebook_outputs {
+        base.include_notebook_outputs = Some(value);
+    }
+    if let Some(value) = overlay.history_commits {
+        base.history_commits = Some(value);
+    }
+    if let Some(value) = overlay.include_manifest {
+        base.include_manifest = Some(value);
+    }
+    if let Some(value) = overlay.merge_gap_lines {
+        base.merge_gap_lines = Some(value);
+    }
+    if let Some(value) = overlay.context_lines {
+        base.context_lines = Some(value);
+    }
+    if let Some(value) = overlay.enforce_budget {
+        base.enforce_budget = Some(value);
+    }
+    if let Some(value) = overlay.strip {
+        base.strip = Some(value);
+    }
+    if let Some(value) = overlay.tab_width {
+        base.tab_width = Some(value);
+    }
+    if let Some(value) = overlay.filename {
+        base.filename = Some(value);
+    }
+    if let Some(value) = overlay.post_action {
+        base.post_action = Some(value);
+    }
+    if let Some(value) = overlay.compare_mode {
+        base.compare_mode = Some(value);
+    }
+    if let Some(value) = overlay.group_by {
+        base.group_by = Some(value);
+    }
+    if let Some(value) = overlay.snippets {
+        base.snippets = Some(value);
+    }
+    // Later layers add labels or replace those with the same key.
+    base.metadata.extend(overlay.metadata);
+    base
+}
+
+fn merge_tokens(mut base: Tokens, overlay: Tokens) -> Tokens {
+    if let Some(value) = overlay.cache_max_entries {
+        base.cache_max_entries = Some(value);
+    }
+    if let Some(value) = overlay.cache_max_bytes {
+        base.cache_max_bytes = Some(value);
+    }
+    if let Some(value) = overlay.openai_multiplier {
+        base.openai_multiplier = Some(value);
+    }
+    if let Some(value) = overlay.anthropic_multiplier {
+        base.anthropic_multiplier = Some(value);
+    }
+    if let Some(value) = overlay.approximate {
+        base.approximate = Some(value);
+    }
+    base
+}
+
+fn merge_preview(mut base: Preview, overlay: Preview) -> Preview {
+    if let Some(value) = overlay.indent_guides {
+        base.indent_guides = Some(value);
+    }
+    if let Some(value) = overlay.show_trailing_whitespace {
+        base.show_trailing_whitespace = Some(value);
+    }
+    if let Some(value) = overlay.show_tabs {
+        base.show_tabs = Some(value);
+    }
+    if let Some(value) = overlay.tab_width {
+        base.tab_width = Some(value);
+    }
+    base
+}
+
+fn merge_search(mut base: Search, overlay: Search) -> Search {
+    if overlay.backend.is_some() {
+        base.backend = overlay.backend;
+    }
+    base
+}
+
+/// Later layers replace recipes of the same name wholesale.
+fn merge_recipes(
+    mut base: BTreeMap<String, Recipe>,
+    overlay: BTreeMap<String, Recipe>,
+) -> BTreeMap<String, Recipe> {
+    base.extend(overlay);
     base
 }
 
@@ -360,6 +823,11 @@ fn merge_keybindings(base: Keybindings, overlay: Keybindings) -> Keybindings {
         down: choose_keybinding(base.down, overlay.down, Keybindings::default_down),
         select: choose_keybinding(base.select, overlay.select, Keybindings::default_select),
         export: choose_keybinding(base.export, overlay.export, Keybindings::default_export),
+        chord_timeout_ms: if overlay.chord_timeout_ms != Keybindings::default_chord_timeout_ms() {
+            overlay.chord_timeout_ms
+        } else {
+            base.chord_timeout_ms
+        },
     }
 }
 
@@ -371,14 +839,38 @@ fn choose_keybinding(base: String, overlay: String, default_fn: fn() -> String)
     }
 }
 
+fn write_setting(path: &Path, section: &str, key: &str, number: f64) -> Result<()> {
+    let existing = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(err) => {
+            return Err(err).with_context(|| format!("failed to read {}", path.display()));
+        }
+    };
+    let mut document: DocumentMut = existing
+        .parse()
+        .with_context(|| format!("failed to parse TOML config {}", path.display()))?;
+    let table = document
+        .entry(section)
+        .or_insert_with(|| Item::Table(Table::new()))
+        .as_table_mut()
+        .ok_or_else(|| anyhow!("[{section}] in {} is not a table", path.display()))?;
+    table[key] = value(number);
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    fs::write(path, document.to_string())
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
 fn global_config_path() -> Option<PathBuf> {
     config_dir().map(|base| base.join("llmctx/config.toml"))
 }
 
-fn workspace_config_path() -> Result<Option<PathBuf>> {
-    let cwd = env::current_dir()?;
-    let root = find_repo_root(&cwd).unwrap_or(cwd);
-    Ok(Some(root.join(DEFAULT_WORKSPACE_CONFIG_PATH)))
+fn workspace_config_path(start: &Path) -> Option<PathBuf> {
+    Some(Config::workspace_dir(start).join(DEFAULT_WORKSPACE_CONFIG_PATH))
 }
 
 fn find_repo_root(start: &Path) -> Option<PathBuf> {
@@ -427,6 +919,13 @@ mod tests {
 model = "anthropic:claude"
 [ignore]
 paths = ["generated/"]
+[recipes.docs]
+select = ["docs/*.md"]
+[recipes.review]
+select = ["src/lib.rs"]
+[export.metadata]
+project = "llmctx"
+team = "tools"
 "#,
         )?;
 
@@ -440,6 +939,11 @@ paths = ["generated/"]
 export_format = "json"
 [ignore]
 globs = ["*.cache"]
+[recipes.review]
+select = ["src/api/**/*.rs"]
+template = "concise_context"
+[export.metadata]
+team = "core"
 "#,
         )?;
 
@@ -453,6 +957,19 @@ globs = ["*.cache"]
         assert_eq!(config.defaults.export_format(), "json");
         assert!(config.ignore.paths.contains(&"generated/".into()));
         assert!(config.ignore.globs.contains(&"*.cache".into()));
+        assert_eq!(config.recipes.len(), 2);
+        assert_eq!(config.recipes["review"].select, vec!["src/api/**/*.rs"]);
+        assert_eq!(
+            config.recipes["review"].template.as_deref(),
+            Some("concise_context")
+        );
+        let metadata: Vec<_> = config
+            .export
+            .metadata()
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect();
+        assert_eq!(metadata, vec![("project", "llmctx"), ("team", "core")]);
 
         Ok(())
     }
@@ -475,4 +992,24 @@ globs = ["*.cache"]
         assert!(result.is_err());
         Ok(())
     }
+
+    #[test]
+    fn written_settings_keep_existing_comments() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let file = temp.path().join("llmctx/config.toml");
+        write_setting(&file, "tokens", "openai_multiplier", 1.25)?;
+        assert_eq!(Config::from_file(&file)?.tokens.openai_multiplier(), 1.25);
+
+        fs::write(
+            &file,
+            "# tuned by hand\n[defaults]\nmodel = \"anthropic:claude-3-haiku\"\n",
+        )?;
+        write_setting(&file, "tokens", "anthropic_multiplier", 0.9)?;
+        let contents = fs::read_to_string(&file)?;
+        assert!(contents.starts_with("# tuned by hand\n"));
+        let config = Config::from_file(&file)?;
+        assert_eq!(config.defaults.model(), "anthropic:claude-3-haiku");
+        assert_eq!(config.tokens.anthropic_multiplier(), 0.9);
+        Ok(())
+    }
 }
@@ -0,0 +1,146 @@
+//! HTTP requests through the `curl` binary.
+//!
+//! Headers, credentials, and bodies are written to curl's stdin as a config file (`--config -`)
+//! rather than passed as arguments, so tokens never show up in `ps` or `/proc/<pid>/cmdline`.
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+use anyhow::{Context, Result};
+
+/// A request to send with curl; only the URL and fixed flags go on the command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct CurlRequest {
+    url: String,
+    headers: Vec<String>,
+    /// `user:password` for basic authentication.
+    user: Option<String>,
+    /// Sent as a POST body when set.
+    body: Option<String>,
+}
+
+impl CurlRequest {
+    pub(crate) fn get(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            headers: Vec::new(),
+            user: None,
+            body: None,
+        }
+    }
+
+    pub(crate) fn post(url: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            body: Some(body.into()),
+            ..Self::get(url)
+        }
+    }
+
+    /// Add a `Name: value` header.
+    pub(crate) fn header(mut self, header: impl Into<String>) -> Self {
+        self.headers.push(header.into());
+        self
+    }
+
+    /// Authenticate with `user:password` basic credentials.
+    pub(crate) fn user(mut self, credentials: impl Into<String>) -> Self {
+        self.user = Some(credentials.into());
+        self
+    }
+
+    /// Arguments curl is started with; nothing secret belongs here.
+    pub(crate) fn args(&self) -> Vec<String> {
+        ["--silent", "--show-error", "--fail", "--location"]
+            .into_iter()
+            .chain(["--config", "-"])
+            .map(str::to_string)
+            .chain([self.url.clone()])
+            .collect()
+    }
+
+    /// The config written to curl's stdin: headers, credentials, and the body.
+    pub(crate) fn config(&self) -> String {
+        let mut config = String::new();
+        for header in &self.headers {
+            config.push_str(&format!("header = {}\n", quote(header)));
+        }
+        if let Some(user) = &self.user {
+            config.push_str(&format!("user = {}\n", quote(user)));
+        }
+        if let Some(body) = &self.body {
+            config.push_str("request = \"POST\"\n");
+            config.push_str(&format!("data-binary = {}\n", quote(body)));
+        }
+        config
+    }
+
+    /// Run curl and collect its output; callers check the exit status.
+    pub(crate) fn send(&self) -> Result<Output> {
+        let mut child = Command::new("curl")
+            .args(self.args())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("failed to run curl")?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(self.config().as_bytes())
+                .context("failed to send request to curl")?;
+        }
+        child.wait_with_output().context("failed to run curl")
+    }
+}
+
+/// `value` as a double-quoted curl config string.
+fn quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for ch in value.chars() {
+        match ch {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            ch => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headers_go_to_the_stdin_config() {
+        let request = CurlRequest::get("https://api.example.com/items")
+            .header("Authorization: Bearer secret-token")
+            .header(r#"X-Note: say "hi" \ bye"#);
+
+        let args = request.args().join(" ");
+        assert!(!args.contains("secret"), "{args}");
+        assert!(args.ends_with("--config - https://api.example.com/items"));
+        assert_eq!(
+            request.config(),
+            "header = \"Authorization: Bearer secret-token\"\n\
+             header = \"X-Note: say \\\"hi\\\" \\\\ bye\"\n"
+        );
+    }
+
+    #[test]
+    fn post_bodies_go_to_the_stdin_config() {
+        let request = CurlRequest::post("https://api.example.com/count", r#"{"text":"a\"b\n"}"#)
+            .user("me@example.com:secret-password");
+
+        assert!(!request.args().join(" ").contains("secret"));
+        assert_eq!(
+            request.config(),
+            "user = \"me@example.com:secret-password\"\n\
+             request = \"POST\"\n\
+             data-binary = \"{\\\"text\\\":\\\"a\\\\\\\"b\\\\n\\\"}\"\n"
+        );
+    }
+}
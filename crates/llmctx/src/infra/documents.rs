@@ -0,0 +1,438 @@
+//! Text extraction for document formats that cannot be previewed as plain text.
+
+use std::io::BufReader;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow};
+use serde_json::Value;
+
+use crate::infra::config::Config;
+use crate::infra::fs::Vfs;
+
+/// Non-text formats with a dedicated extraction path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentKind {
+    Image,
+    Notebook,
+    Pdf,
+}
+
+impl DocumentKind {
+    /// Classify a path by extension; `None` means it should be read as text.
+    pub fn detect(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "png" | "jpg" | "jpeg" | "gif" | "bmp" | "webp" | "tif" | "tiff" | "ico" | "heic"
+            | "heif" | "avif" => Some(Self::Image),
+            "ipynb" => Some(Self::Notebook),
+            "pdf" => Some(Self::Pdf),
+            _ => None,
+        }
+    }
+}
+
+/// Settings shared by preview and export so line numbers agree between them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExtractOptions {
+    /// Append cell outputs (stream text, plain-text results, errors) under code cells.
+    pub include_notebook_outputs: bool,
+}
+
+impl ExtractOptions {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            include_notebook_outputs: config.export.include_notebook_outputs(),
+        }
+    }
+}
+
+/// Text derived from a document along with a hint for syntax highlighting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtractedDocument {
+    pub lines: Vec<String>,
+    /// File name whose extension selects the highlighting syntax, if any.
+    pub syntax_hint: Option<String>,
+    pub notice: String,
+}
+
+/// Extract a readable text rendition of `path` in `vfs` according to `kind`.
+pub fn extract(
+    vfs: &dyn Vfs,
+    path: &Path,
+    kind: DocumentKind,
+    options: ExtractOptions,
+) -> Result<ExtractedDocument> {
+    match kind {
+        DocumentKind::Image => describe_image(vfs, path),
+        DocumentKind::Notebook => {
+            let notebook = Notebook::load(vfs, path)?;
+            let outputs = if options.include_notebook_outputs {
+                "included"
+            } else {
+                "omitted"
+            };
+            Ok(ExtractedDocument {
+                lines: notebook.to_percent_lines(options.include_notebook_outputs),
+                syntax_hint: Some(format!("notebook.{}", notebook.extension())),
+                notice: format!(
+                    "Notebook with {} cells (outputs {outputs}).",
+                    notebook.cells.len()
+                ),
+            })
+        }
+        DocumentKind::Pdf => extract_pdf(vfs, path),
+    }
+}
+
+/// Read `path` from `vfs` as the text that selections, estimates, and exports operate on.
+///
+/// Notebooks are flattened to their cells; every other file is decoded with
+/// [`Vfs::read_text`].
+pub fn read_selection_text(vfs: &dyn Vfs, path: &Path, options: ExtractOptions) -> Result<String> {
+    if DocumentKind::detect(path) == Some(DocumentKind::Notebook) {
+        let notebook = Notebook::load(vfs, path)?;
+        return Ok(notebook
+            .to_percent_lines(options.include_notebook_outputs)
+            .join("\n"));
+    }
+    vfs.read_text(path)
+        .map(|decoded| decoded.text)
+        .with_context(|| format!("failed to read {}", path.display()))
+}
+
+/// Cell type of a notebook cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellKind {
+    Code,
+    Markdown,
+    Raw,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotebookCell {
+    pub kind: CellKind,
+    pub source: String,
+    /// Plain-text renditions of the cell's outputs, in order.
+    pub outputs: Vec<String>,
+}
+
+/// The source cells of a Jupyter notebook.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notebook {
+    pub language: String,
+    pub cells: Vec<NotebookCell>,
+}
+
+impl Notebook {
+    pub fn load(vfs: &dyn Vfs, path: &Path) -> Result<Self> {
+        let contents = vfs
+            .read(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|bytes| Ok(String::from_utf8(bytes)?))
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        Self::parse(&contents)
+            .with_context(|| format!("failed to parse notebook {}", path.display()))
+    }
+
+    pub fn parse(contents: &str) -> Result<Self> {
+        let root: Value = serde_json::from_str(contents)?;
+        let cells = root
+            .get("cells")
+            .and_then(Value::as_array)
+            .ok_or_else(|| anyhow!("notebook has no cells array"))?;
+
+        let language = root
+            .pointer("/metadata/language_info/name")
+            .or_else(|| root.pointer("/metadata/kernelspec/language"))
+            .and_then(Value::as_str)
+            .unwrap_or("python")
+            .to_ascii_lowercase();
+
+        let cells = cells
+            .iter()
+            .map(|cell| {
+                let kind = match cell.get("cell_type").and_then(Value::as_str) {
+                    Some("code") => CellKind::Code,
+                    Some("markdown") => CellKind::Markdown,
+                    _ => CellKind::Raw,
+                };
+                let outputs = cell
+                    .get("outputs")
+                    .and_then(Value::as_array)
+                    .map(|outputs| outputs.iter().filter_map(output_text).collect())
+                    .unwrap_or_default();
+                NotebookCell {
+                    kind,
+                    source: join_source(cell.get("source")),
+                    outputs,
+                }
+            })
+            .collect();
+
+        Ok(Self { language, cells })
+    }
+
+    /// File extension matching the kernel language, used to pick a syntax.
+    pub fn extension(&self) -> &str {
+        match self.language.as_str() {
+            "python" => "py",
+            "r" => "r",
+            "julia" => "jl",
+            "javascript" => "js",
+            "typescript" => "ts",
+            "rust" => "rs",
+            "scala" => "scala",
+            other => other,
+        }
+    }
+
+    /// Render cells in the "percent" script format: `# %%` markers, markdown as comments.
+    ///
+    /// With `include_outputs`, each code cell's outputs follow it as comment lines.
+    pub fn to_percent_lines(&self, include_outputs: bool) -> Vec<String> {
+        let comment = self.comment_prefix();
+        let mut lines = Vec::new();
+        for (idx, cell) in self.cells.iter().enumerate() {
+            if idx > 0 {
+                lines.push(String::new());
+            }
+            match cell.kind {
+                CellKind::Code => {
+                    lines.push(format!("{comment} %%"));
+                    lines.extend(cell.source.lines().map(str::to_string));
+                    if include_outputs {
+                        for output in &cell.outputs {
+                            lines.push(format!("{comment} Out:"));
+                            lines.extend(
+                                output
+                                    .lines()
+                                    .map(|line| format!("{comment} {line}").trim_end().to_string()),
+                            );
+                        }
+                    }
+                }
+                CellKind::Markdown | CellKind::Raw => {
+                    let label = if cell.kind == CellKind::Markdown {
+                        "markdown"
+                    } else {
+                        "raw"
+                    };
+                    lines.push(format!("{comment} %% [{label}]"));
+                    lines.extend(cell.source.lines().map(|line| {
+                        if line.is_empty() {
+                            comment.to_string()
+                        } else {
+                            format!("{comment} {line}")
+                        }
+                    }));
+                }
+            }
+        }
+        lines
+    }
+
+    fn comment_prefix(&self) -> &'static str {
+        match self.language.as_str() {
+            "javascript" | "typescript" | "rust" | "scala" => "//",
+            _ => "#",
+        }
+    }
+}
+
+/// Notebook sources are stored either as a string or as a list of line strings.
+fn join_source(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Array(parts)) => parts.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
+
+/// Plain-text rendition of a code cell output; rich-only outputs (images, HTML) are skipped.
+fn output_text(output: &Value) -> Option<String> {
+    let text = match output.get("output_type").and_then(Value::as_str)? {
+        "stream" => join_source(output.get("text")),
+        "execute_result" | "display_data" => join_source(output.pointer("/data/text~1plain")),
+        "error" => {
+            let name = output
+                .get("ename")
+                .and_then(Value::as_str)
+                .unwrap_or("Error");
+            let value = output.get("evalue").and_then(Value::as_str).unwrap_or("");
+            format!("{name}: {value}")
+        }
+        _ => return None,
+    };
+    let text = text.trim_end().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+fn describe_image(vfs: &dyn Vfs, path: &Path) -> Result<ExtractedDocument> {
+    let open = || {
+        vfs.open(path)
+            .with_context(|| format!("failed to open {}", path.display()))
+    };
+    let size = imagesize::reader_size(BufReader::new(open()?))
+        .map_err(|err| anyhow!("failed to read image header of {}: {err}", path.display()))?;
+    let bytes = vfs.metadata(path)?.len;
+
+    let mut lines = vec![
+        format!("Dimensions: {} × {} px", size.width, size.height),
+        format!("File size:  {}", format_bytes(bytes)),
+    ];
+
+    if let Ok(exif) = exif::Reader::new().read_from_container(&mut BufReader::new(open()?)) {
+        let fields = [
+            (exif::Tag::Make, "Make"),
+            (exif::Tag::Model, "Model"),
+            (exif::Tag::DateTimeOriginal, "Taken"),
+            (exif::Tag::Orientation, "Orientation"),
+            (exif::Tag::ExposureTime, "Exposure"),
+            (exif::Tag::FNumber, "Aperture"),
+            (exif::Tag::PhotographicSensitivity, "ISO"),
+            (exif::Tag::FocalLength, "Focal length"),
+        ];
+        let summary: Vec<String> = fields
+            .iter()
+            .filter_map(|(tag, label)| {
+                let field = exif.get_field(*tag, exif::In::PRIMARY)?;
+                let value = field.display_value().with_unit(&exif).to_string();
+                Some(format!("{label:<12} {}", value.trim_matches('"')))
+            })
+            .collect();
+        if !summary.is_empty() {
+            lines.push(String::new());
+            lines.push("EXIF".into());
+            lines.extend(summary);
+        }
+        if exif
+            .get_field(exif::Tag::GPSLatitude, exif::In::PRIMARY)
+            .is_some()
+        {
+            lines.push("GPS          location recorded".into());
+        }
+    }
+
+    Ok(ExtractedDocument {
+        lines,
+        syntax_hint: None,
+        notice: "Image preview shows metadata only.".into(),
+    })
+}
+
+fn extract_pdf(vfs: &dyn Vfs, path: &Path) -> Result<ExtractedDocument> {
+    let contents = vfs
+        .read(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    // pdf-extract panics on some malformed inputs; treat that as an extraction failure.
+    let pages = panic::catch_unwind(AssertUnwindSafe(|| {
+        pdf_extract::extract_text_from_mem_by_pages(&contents)
+    }))
+    .map_err(|_| anyhow!("PDF text extraction failed for {}", path.display()))?
+    .map_err(|err| anyhow!("failed to extract text from {}: {err}", path.display()))?;
+
+    let mut lines = Vec::new();
+    for (idx, page) in pages.iter().enumerate() {
+        lines.push(format!("── page {} ──", idx + 1));
+        lines.extend(
+            page.lines()
+                .map(str::trim_end)
+                .filter(|line| !line.is_empty())
+                .map(str::to_string),
+        );
+    }
+
+    Ok(ExtractedDocument {
+        lines,
+        syntax_hint: None,
+        notice: format!("Text extracted from {} PDF pages.", pages.len()),
+    })
+}
+
+/// Human-readable byte count using binary units (e.g. `1.5 MiB`).
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infra::fs::MemoryFs;
+
+    const NOTEBOOK: &str = r##"{
+        "metadata": {"language_info": {"name": "python"}},
+        "cells": [
+            {"cell_type": "markdown", "source": ["# Title\n", "\n", "Intro text"]},
+            {"cell_type": "code", "source": "import os\nprint(os.getcwd())",
+             "outputs": [{"output_type": "stream", "text": ["/tmp\n"]}]}
+        ]
+    }"##;
+
+    #[test]
+    fn notebook_renders_percent_cells() -> Result<()> {
+        let notebook = Notebook::parse(NOTEBOOK)?;
+        assert_eq!(notebook.extension(), "py");
+        assert_eq!(
+            notebook.to_percent_lines(false),
+            vec![
+                "# %% [markdown]",
+                "# # Title",
+                "#",
+                "# Intro text",
+                "",
+                "# %%",
+                "import os",
+                "print(os.getcwd())",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn notebook_outputs_are_optional() -> Result<()> {
+        let notebook = Notebook::parse(NOTEBOOK)?;
+        let lines = notebook.to_percent_lines(true);
+        assert_eq!(&lines[lines.len() - 2..], ["# Out:", "# /tmp"]);
+
+        let vfs = MemoryFs::new();
+        let path = Path::new("/repo/analysis.ipynb");
+        vfs.write(path, NOTEBOOK);
+        let text = read_selection_text(&vfs, path, ExtractOptions::default())?;
+        assert!(text.ends_with("print(os.getcwd())"));
+        assert!(!text.contains("\"cells\""));
+        Ok(())
+    }
+
+    #[test]
+    fn image_summary_reports_dimensions() -> Result<()> {
+        let vfs = MemoryFs::new();
+        let path = Path::new("/repo/pixel.png");
+        // 1x1 transparent PNG.
+        let png: [u8; 67] = [
+            0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00, 0x00, 0x0D, 0x49, 0x48,
+            0x44, 0x52, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x06, 0x00, 0x00,
+            0x00, 0x1F, 0x15, 0xC4, 0x89, 0x00, 0x00, 0x00, 0x0A, 0x49, 0x44, 0x41, 0x54, 0x78,
+            0x9C, 0x63, 0x00, 0x01, 0x00, 0x00, 0x05, 0x00, 0x01, 0x0D, 0x0A, 0x2D, 0xB4, 0x00,
+            0x00, 0x00, 0x00, 0x49, 0x45, 0x4E, 0x44, 0xAE, 0x42, 0x60, 0x82,
+        ];
+        vfs.write(path, png);
+
+        let kind = DocumentKind::detect(path).expect("png is an image");
+        let document = extract(&vfs, path, kind, ExtractOptions::default())?;
+        assert_eq!(document.lines[0], "Dimensions: 1 × 1 px");
+        assert_eq!(document.lines[1], "File size:  67 B");
+        Ok(())
+    }
+}
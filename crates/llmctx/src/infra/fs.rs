@@ -1,10 +1,529 @@
 //! File system utilities.
 
-#[derive(Default)]
-pub struct FileSystem;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Cursor, Read, Seek};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
 
-impl FileSystem {
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE};
+use serde::{Deserialize, Serialize};
+use xxhash_rust::xxh3::{Xxh3, xxh3_64};
+
+/// What a [`Vfs`] knows about a file or directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VfsMetadata {
+    pub is_dir: bool,
+    /// Size in bytes; zero for directories.
+    pub len: u64,
+    pub modified: Option<SystemTime>,
+}
+
+impl VfsMetadata {
+    pub fn is_file(&self) -> bool {
+        !self.is_dir
+    }
+}
+
+impl From<&fs::Metadata> for VfsMetadata {
+    fn from(metadata: &fs::Metadata) -> Self {
+        Self {
+            is_dir: metadata.is_dir(),
+            len: if metadata.is_dir() { 0 } else { metadata.len() },
+            modified: metadata.modified().ok(),
+        }
+    }
+}
+
+/// A file opened through a [`Vfs`].
+pub trait VfsFile: Read + Seek + Send {}
+
+impl<T: Read + Seek + Send> VfsFile for T {}
+
+/// Where the scanner, preview, token estimator, and exporter read files from.
+///
+/// [`RealFs`] reads the local disk. [`MemoryFs`] holds files in memory, for tests and for
+/// sources that are not on disk.
+pub trait Vfs: fmt::Debug + Send + Sync {
+    /// Metadata of the file or directory at `path`, following symlinks.
+    fn metadata(&self, path: &Path) -> io::Result<VfsMetadata>;
+
+    fn open(&self, path: &Path) -> io::Result<Box<dyn VfsFile>>;
+
+    /// Paths of the entries directly inside the directory at `path`, in no particular order.
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>>;
+
+    /// Whether paths name files on the local disk.
+    ///
+    /// Gitignore-aware walks and git lookups read the disk themselves, so they only apply to
+    /// local file systems.
+    fn is_local(&self) -> bool {
+        false
+    }
+
+    /// Commit the files are read at, when they come from git history rather than a checkout.
+    fn commit(&self) -> Option<String> {
+        None
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.metadata(path).is_ok()
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let mut contents = Vec::new();
+        self.open(path)?.read_to_end(&mut contents)?;
+        Ok(contents)
+    }
+
+    /// Read `path` as text like [`read_text`].
+    fn read_text(&self, path: &Path) -> io::Result<DecodedText> {
+        Ok(DecodedText::decode(&self.read(path)?))
+    }
+}
+
+/// The local disk.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RealFs;
+
+impl Vfs for RealFs {
+    fn metadata(&self, path: &Path) -> io::Result<VfsMetadata> {
+        fs::metadata(path).map(|metadata| VfsMetadata::from(&metadata))
+    }
+
+    fn open(&self, path: &Path) -> io::Result<Box<dyn VfsFile>> {
+        Ok(Box::new(File::open(path)?))
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect()
+    }
+
+    fn is_local(&self) -> bool {
+        true
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(path)
+    }
+}
+
+/// Files held in memory, keyed by path. Directories exist implicitly above every file.
+///
+/// Clones share the same files, so a test can keep one handle to rewrite files that a service
+/// holding another reads.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryFs {
+    inner: Arc<Mutex<MemoryFiles>>,
+}
+
+#[derive(Debug, Default)]
+struct MemoryFiles {
+    files: BTreeMap<PathBuf, MemoryFile>,
+    /// Writes so far, which stand in for modification times so every write changes one.
+    writes: u64,
+}
+
+#[derive(Debug)]
+struct MemoryFile {
+    contents: Arc<[u8]>,
+    modified: SystemTime,
+}
+
+impl MemoryFs {
     pub fn new() -> Self {
-        Self
+        Self::default()
+    }
+
+    /// Create or replace the file at `path`.
+    pub fn write(&self, path: impl Into<PathBuf>, contents: impl Into<Vec<u8>>) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.writes += 1;
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(inner.writes);
+        inner.files.insert(
+            path.into(),
+            MemoryFile {
+                contents: contents.into().into(),
+                modified,
+            },
+        );
+    }
+
+    /// Delete the file at `path`, returning whether it existed.
+    pub fn remove(&self, path: &Path) -> bool {
+        self.inner.lock().unwrap().files.remove(path).is_some()
+    }
+}
+
+impl Vfs for MemoryFs {
+    fn metadata(&self, path: &Path) -> io::Result<VfsMetadata> {
+        let inner = self.inner.lock().unwrap();
+        if let Some(file) = inner.files.get(path) {
+            return Ok(VfsMetadata {
+                is_dir: false,
+                len: file.contents.len() as u64,
+                modified: Some(file.modified),
+            });
+        }
+        if inner.files.keys().any(|file| file.starts_with(path)) {
+            return Ok(VfsMetadata {
+                is_dir: true,
+                len: 0,
+                modified: None,
+            });
+        }
+        Err(not_found(path))
+    }
+
+    fn open(&self, path: &Path) -> io::Result<Box<dyn VfsFile>> {
+        let inner = self.inner.lock().unwrap();
+        let file = inner.files.get(path).ok_or_else(|| not_found(path))?;
+        Ok(Box::new(Cursor::new(Arc::clone(&file.contents))))
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        let inner = self.inner.lock().unwrap();
+        if inner.files.contains_key(path) {
+            return Err(io::Error::new(
+                io::ErrorKind::NotADirectory,
+                format!("{} is not a directory", path.display()),
+            ));
+        }
+        let entries: BTreeSet<PathBuf> = inner
+            .files
+            .keys()
+            .filter_map(|file| file.strip_prefix(path).ok()?.components().next())
+            .map(|child| path.join(child))
+            .collect();
+        if entries.is_empty() {
+            return Err(not_found(path));
+        }
+        Ok(entries.into_iter().collect())
+    }
+}
+
+pub(crate) fn not_found(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{} does not exist", path.display()),
+    )
+}
+
+/// Identity of file contents that ignores timestamps.
+///
+/// Operations such as `git checkout` rewrite modification times without touching contents, so
+/// caches and session snapshots compare this instead of `mtime`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ContentFingerprint {
+    pub len: u64,
+    pub hash: u64,
+}
+
+impl ContentFingerprint {
+    /// Fingerprint an in-memory buffer.
+    pub fn of_bytes(bytes: &[u8]) -> Self {
+        Self {
+            len: bytes.len() as u64,
+            hash: xxh3_64(bytes),
+        }
+    }
+
+    /// Fingerprint a file by streaming its contents through the hasher.
+    pub fn of_file(path: &Path) -> io::Result<Self> {
+        Self::of_reader(File::open(path)?)
+    }
+
+    /// Fingerprint a file as `vfs` sees it, such as at a git revision.
+    pub fn of_vfs(vfs: &dyn Vfs, path: &Path) -> io::Result<Self> {
+        Self::of_reader(vfs.open(path)?)
+    }
+
+    fn of_reader(mut file: impl Read) -> io::Result<Self> {
+        let mut hasher = Xxh3::new();
+        let mut buf = [0u8; 64 * 1024];
+        let mut len = 0u64;
+        loop {
+            let read = file.read(&mut buf)?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buf[..read]);
+            len += read as u64;
+        }
+        Ok(Self {
+            len,
+            hash: hasher.digest(),
+        })
+    }
+}
+
+impl fmt::Display for ContentFingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.hash)
+    }
+}
+
+/// Bytes examined when guessing a file's encoding.
+const SNIFF_BYTES: usize = 8 * 1024;
+
+/// Text encodings recognised when reading files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TextEncoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    /// A legacy encoding (windows-1252, Shift_JIS, KOI8-R, ...) detected by chardetng for text
+    /// that is not valid UTF-8.
+    ///
+    /// Detection is statistical: short or mixed text can be detected as the wrong encoding and
+    /// still decode without an error.
+    Legacy(&'static Encoding),
+}
+
+impl TextEncoding {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Utf8 => "UTF-8",
+            Self::Utf16Le => "UTF-16LE",
+            Self::Utf16Be => "UTF-16BE",
+            Self::Legacy(encoding) => encoding.name(),
+        }
+    }
+
+    /// Whether the encoding was detected from the bytes, and so may be the wrong one.
+    pub fn is_guess(&self) -> bool {
+        matches!(self, Self::Legacy(_))
+    }
+
+    /// Whether ASCII bytes always mean ASCII characters, so lines can be split on `\n` bytes.
+    pub fn is_ascii_compatible(&self) -> bool {
+        match self {
+            Self::Utf8 => true,
+            Self::Utf16Le | Self::Utf16Be => false,
+            Self::Legacy(encoding) => encoding.is_ascii_compatible(),
+        }
+    }
+
+    /// Guess the encoding from the first bytes of a file; `None` means it looks binary.
+    ///
+    /// Byte order marks win. Otherwise UTF-16 is recognised by the zero high bytes of ASCII
+    /// text, and for anything that is not valid UTF-8 but free of control characters,
+    /// chardetng picks the most likely legacy encoding.
+    pub fn sniff(head: &[u8]) -> Option<Self> {
+        if head.starts_with(&[0xEF, 0xBB, 0xBF]) {
+            return Some(Self::Utf8);
+        }
+        if head.starts_with(&[0xFF, 0xFE]) {
+            return Some(Self::Utf16Le);
+        }
+        if head.starts_with(&[0xFE, 0xFF]) {
+            return Some(Self::Utf16Be);
+        }
+        if head.contains(&0) {
+            let pairs = head.len() / 2;
+            let zeros = |offset: usize| {
+                head.iter()
+                    .skip(offset)
+                    .step_by(2)
+                    .take(pairs)
+                    .filter(|byte| **byte == 0)
+                    .count()
+            };
+            let (even, odd) = (zeros(0), zeros(1));
+            return if even == 0 && odd * 4 >= pairs * 3 {
+                Some(Self::Utf16Le)
+            } else if odd == 0 && even * 4 >= pairs * 3 {
+                Some(Self::Utf16Be)
+            } else {
+                None
+            };
+        }
+        match std::str::from_utf8(head) {
+            Ok(_) => Some(Self::Utf8),
+            // The head may end partway through a character.
+            Err(err) if err.error_len().is_none() => Some(Self::Utf8),
+            Err(_) if head.iter().any(|byte| is_binary_control(*byte)) => None,
+            Err(_) => {
+                let mut detector = EncodingDetector::new();
+                detector.feed(head, false);
+                Some(Self::Legacy(detector.guess(None, false)))
+            }
+        }
+    }
+
+    /// Decode `bytes` without a byte order mark; undecodable sequences become U+FFFD.
+    pub fn decode<'a>(&self, bytes: &'a [u8]) -> Cow<'a, str> {
+        let encoding = match self {
+            Self::Utf8 => return String::from_utf8_lossy(bytes),
+            Self::Utf16Le => UTF_16LE,
+            Self::Utf16Be => UTF_16BE,
+            Self::Legacy(encoding) => encoding,
+        };
+        encoding.decode_without_bom_handling(bytes).0
+    }
+}
+
+fn is_binary_control(byte: u8) -> bool {
+    byte < 0x20 && !matches!(byte, b'\t' | b'\n' | b'\r' | 0x0C | 0x1B)
+}
+
+/// File contents decoded to UTF-8 with `\n` line endings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedText {
+    pub text: String,
+    /// Encoding the bytes were read as.
+    pub encoding: TextEncoding,
+    /// Whether the text may not match the file: an undecodable sequence was replaced, or the
+    /// encoding is only a guess (see [`TextEncoding::is_guess`]).
+    pub lossy: bool,
+}
+
+impl DecodedText {
+    /// Decode `bytes` in their sniffed encoding, dropping any byte order mark and turning CRLF
+    /// into LF. Bytes that look binary are decoded as lossy UTF-8.
+    pub fn decode(bytes: &[u8]) -> Self {
+        let encoding = TextEncoding::sniff(&bytes[..bytes.len().min(SNIFF_BYTES)])
+            .unwrap_or(TextEncoding::Utf8);
+        let body = match encoding {
+            TextEncoding::Utf8 => bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(bytes),
+            TextEncoding::Utf16Le => bytes.strip_prefix(&[0xFF, 0xFE]).unwrap_or(bytes),
+            TextEncoding::Utf16Be => bytes.strip_prefix(&[0xFE, 0xFF]).unwrap_or(bytes),
+            TextEncoding::Legacy(_) => bytes,
+        };
+        let decoded = encoding.decode(body);
+        let lossy = encoding.is_guess()
+            || (encoding == TextEncoding::Utf8 && matches!(decoded, Cow::Owned(_)));
+        let text = if decoded.contains("\r\n") {
+            decoded.replace("\r\n", "\n")
+        } else {
+            decoded.into_owned()
+        };
+        Self {
+            text,
+            encoding,
+            lossy,
+        }
+    }
+}
+
+/// Read `path` as text, decoding UTF-16 and legacy encodings and normalising CRLF line endings.
+pub fn read_text(path: &Path) -> io::Result<DecodedText> {
+    Ok(DecodedText::decode(&fs::read(path)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn file_and_buffer_fingerprints_agree() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("sample.txt");
+        fs::write(&path, b"hello fingerprint")?;
+
+        assert_eq!(
+            ContentFingerprint::of_file(&path)?,
+            ContentFingerprint::of_bytes(b"hello fingerprint")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn decodes_utf16_and_legacy_encodings_and_normalizes_crlf() {
+        let utf16: Vec<u8> = [0xFF, 0xFE]
+            .into_iter()
+            .chain("fn main() {}\r\n".encode_utf16().flat_map(u16::to_le_bytes))
+            .collect();
+        let decoded = DecodedText::decode(&utf16);
+        assert_eq!(decoded.encoding, TextEncoding::Utf16Le);
+        assert_eq!(decoded.text, "fn main() {}\n");
+
+        let no_bom: Vec<u8> = "abc\n".encode_utf16().flat_map(u16::to_be_bytes).collect();
+        assert_eq!(TextEncoding::sniff(&no_bom), Some(TextEncoding::Utf16Be));
+
+        let latin1 = DecodedText::decode(b"caf\xe9\r\nna\xefve");
+        assert_eq!(latin1.encoding.label(), "windows-1252");
+        assert_eq!(latin1.text, "café\nnaïve");
+        let japanese = "日本語のテキストです。ファイルを読み込みます。\n";
+        let (bytes, _, _) = encoding_rs::SHIFT_JIS.encode(japanese);
+        let shift_jis = DecodedText::decode(&bytes);
+        assert_eq!(shift_jis.encoding.label(), "Shift_JIS");
+        assert_eq!(shift_jis.text, japanese);
+        // Detection can be wrong, so legacy encodings are always flagged.
+        assert!(latin1.lossy && shift_jis.lossy);
+
+        let utf8 = DecodedText::decode("\u{feff}héllo".as_bytes());
+        assert_eq!(
+            (utf8.encoding, utf8.text.as_str()),
+            (TextEncoding::Utf8, "héllo")
+        );
+        assert!(!utf8.lossy);
+        assert_eq!(TextEncoding::sniff(b"\x7fELF\x02\x01\x01\x00\x00"), None);
+        assert_eq!(TextEncoding::sniff(b"\x89PNG\r\n\x1a\n"), None);
+    }
+
+    #[test]
+    fn memory_fs_lists_directories_above_its_files() -> io::Result<()> {
+        let vfs = MemoryFs::new();
+        vfs.write("/repo/src/lib.rs", "pub fn lib() {}\n");
+        vfs.write("/repo/src/app/mod.rs", "mod app;\n");
+        vfs.write("/repo/README.md", "# Readme\n");
+
+        let mut root = vfs.read_dir(Path::new("/repo"))?;
+        root.sort();
+        assert_eq!(
+            root,
+            vec![PathBuf::from("/repo/README.md"), PathBuf::from("/repo/src")]
+        );
+        assert!(vfs.metadata(Path::new("/repo/src"))?.is_dir);
+        assert_eq!(vfs.metadata(Path::new("/repo/src/lib.rs"))?.len, 16);
+        assert_eq!(
+            vfs.read_text(Path::new("/repo/README.md"))?.text,
+            "# Readme\n"
+        );
+
+        let mut file = vfs.open(Path::new("/repo/src/lib.rs"))?;
+        file.seek(io::SeekFrom::Start(4))?;
+        let mut rest = String::new();
+        file.read_to_string(&mut rest)?;
+        assert_eq!(rest, "fn lib() {}\n");
+
+        let before = vfs.metadata(Path::new("/repo/README.md"))?.modified;
+        vfs.write("/repo/README.md", "# Readme\n");
+        assert_ne!(vfs.metadata(Path::new("/repo/README.md"))?.modified, before);
+
+        assert!(vfs.remove(Path::new("/repo/README.md")));
+        let missing = vfs.open(Path::new("/repo/README.md")).err().unwrap();
+        assert_eq!(missing.kind(), io::ErrorKind::NotFound);
+        assert!(vfs.read_dir(Path::new("/repo/src/lib.rs")).is_err());
+        assert!(!vfs.exists(Path::new("/elsewhere")));
+        Ok(())
+    }
+
+    #[test]
+    fn fingerprint_ignores_modification_time() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("sample.txt");
+        fs::write(&path, b"stable contents")?;
+        let before = ContentFingerprint::of_file(&path)?;
+
+        let file = File::options().write(true).open(&path)?;
+        file.set_modified(SystemTime::now() + Duration::from_secs(3600))?;
+        drop(file);
+
+        assert_eq!(ContentFingerprint::of_file(&path)?, before);
+
+        fs::write(&path, b"changed contents")?;
+        assert_ne!(ContentFingerprint::of_file(&path)?, before);
+        Ok(())
     }
 }
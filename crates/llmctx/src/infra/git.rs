@@ -1,9 +1,37 @@
 //! Git integration utilities.
 
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::{self, Cursor};
 use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
-use anyhow::Result;
+use anyhow::{Context, Result, anyhow, bail};
+use percent_encoding::{AsciiSet, CONTROLS, utf8_percent_encode};
 use serde::Serialize;
+use time::OffsetDateTime;
+use time::macros::format_description;
+
+use crate::infra::fs::{Vfs, VfsFile, VfsMetadata, not_found};
+
+/// Upper bound on commits inspected per file when collecting history.
+const HISTORY_SCAN_LIMIT: usize = 5_000;
+/// Most-changed entries reported by [`GitClient::recent_activity`].
+const ACTIVE_PATHS_LIMIT: usize = 8;
+/// Characters escaped in a permalink's path segments; the URL path-segment set plus `%`.
+const PATH_SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
 
 /// Lightweight wrapper around [`gix::Repository`] discovery for metadata extraction.
 #[derive(Default)]
@@ -30,12 +58,259 @@ impl GitClient {
             .map(Path::to_path_buf)
             .or_else(|| repo.path().parent().map(Path::to_path_buf))?;
 
+        // Linked worktrees keep a private git dir under `<common>/worktrees/<name>`; objects,
+        // refs, and config live in the common dir shared with the main checkout.
+        let common_dir = repo.common_dir().to_path_buf();
+        let linked_worktree = common_dir != repo.git_dir();
+
+        let remote = repo
+            .find_default_remote(gix::remote::Direction::Fetch)
+            .and_then(Result::ok)
+            .and_then(|remote| {
+                remote
+                    .url(gix::remote::Direction::Fetch)
+                    .and_then(RemoteWeb::from_url)
+            });
+
         Some(GitMetadata {
             branch,
             commit,
             root,
+            common_dir,
+            linked_worktree,
+            remote,
         })
     }
+
+    /// The `limit` most recent commits reachable from HEAD that changed `path`, newest first.
+    ///
+    /// A commit counts as touching the file when its blob differs from the first parent's.
+    /// Empty before the first commit and for paths outside the working tree.
+    pub fn file_history(&self, path: &Path, limit: usize) -> Result<Vec<CommitSummary>> {
+        let Some(repo) = self.repo.as_ref() else {
+            return Ok(Vec::new());
+        };
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+        let relative = &match work_tree_relative(repo, path) {
+            Ok(relative) => relative,
+            Err(err) => {
+                tracing::debug!(path = %path.display(), error = %err, "no history outside the working tree");
+                return Ok(Vec::new());
+            }
+        };
+        let head = match repo.head_id() {
+            Ok(head) => head,
+            Err(err) => {
+                tracing::debug!(path = %path.display(), error = %err, "no history without a HEAD commit");
+                return Ok(Vec::new());
+            }
+        };
+        let blob_at = |commit: &gix::Commit<'_>| -> Result<Option<gix::ObjectId>> {
+            let tree = commit.tree()?;
+            Ok(tree
+                .lookup_entry_by_path(relative)?
+                .map(|entry| entry.object_id()))
+        };
+
+        let mut history = Vec::new();
+        for info in repo.rev_walk([head]).all()?.take(HISTORY_SCAN_LIMIT) {
+            let commit = info?.object()?;
+            let blob = blob_at(&commit)?;
+            if blob.is_none() {
+                continue;
+            }
+            let parent_blob = match commit.parent_ids().next() {
+                Some(parent) => blob_at(&parent.object()?.into_commit())?,
+                None => None,
+            };
+            if blob == parent_blob {
+                continue;
+            }
+
+            history.push(summarize_commit(&commit)?);
+            if history.len() >= limit {
+                break;
+            }
+        }
+        Ok(history)
+    }
+
+    /// Commits made to HEAD's history in the last `days` days, and which entries directly under
+    /// `dir` they changed. Returns `None` outside a repository or before the first commit.
+    pub fn recent_activity(&self, dir: &Path, days: u32) -> Result<Option<GitActivity>> {
+        let Some(repo) = self.repo.as_ref() else {
+            return Ok(None);
+        };
+        let Ok(head) = repo.head_id() else {
+            return Ok(None);
+        };
+        let relative = work_tree_relative(repo, dir)?;
+        // Entry ids directly under `dir`; a subtree's id changes whenever anything inside does.
+        let entries_at = |commit: &gix::Commit<'_>| -> Result<HashMap<String, gix::ObjectId>> {
+            let mut tree = commit.tree()?;
+            if !relative.as_os_str().is_empty() {
+                match tree.lookup_entry_by_path(&relative)? {
+                    Some(entry) if entry.mode().is_tree() => {
+                        tree = entry.object()?.into_tree();
+                    }
+                    _ => return Ok(HashMap::new()),
+                }
+            }
+            Ok(tree
+                .decode()?
+                .entries
+                .iter()
+                .map(|entry| (entry.filename.to_string(), entry.oid.to_owned()))
+                .collect())
+        };
+
+        let cutoff = OffsetDateTime::now_utc().unix_timestamp() - i64::from(days) * 86_400;
+        let walk = repo
+            .rev_walk([head])
+            .sorting(gix::revision::walk::Sorting::ByCommitTimeCutoff {
+                order: gix::traverse::commit::simple::CommitTimeOrder::NewestFirst,
+                seconds: cutoff,
+            })
+            .all()?;
+
+        let mut activity = GitActivity {
+            days,
+            ..GitActivity::default()
+        };
+        let mut authors = HashSet::new();
+        let mut changes: HashMap<String, usize> = HashMap::new();
+        for info in walk.take(HISTORY_SCAN_LIMIT) {
+            let commit = info?.object()?;
+            activity.commits += 1;
+            authors.insert(commit.author()?.name.to_string());
+            if activity.latest.is_none() {
+                activity.latest = Some(summarize_commit(&commit)?);
+            }
+
+            let entries = entries_at(&commit)?;
+            let parent = match commit.parent_ids().next() {
+                Some(parent) => entries_at(&parent.object()?.into_commit())?,
+                None => HashMap::new(),
+            };
+            let removed = parent.keys().filter(|name| !entries.contains_key(*name));
+            let changed = entries
+                .iter()
+                .filter(|(name, id)| parent.get(*name) != Some(id))
+                .map(|(name, _)| name);
+            for name in changed.chain(removed) {
+                *changes.entry(name.clone()).or_default() += 1;
+            }
+        }
+        activity.authors = authors.len();
+        activity.active_paths = changes.into_iter().collect();
+        activity
+            .active_paths
+            .sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        activity.active_paths.truncate(ACTIVE_PATHS_LIMIT);
+        Ok(Some(activity))
+    }
+
+    /// Compare the working-tree contents of `path` with its blob in HEAD.
+    ///
+    /// Returns `None` outside a repository. Checkout filters such as `core.autocrlf` are not
+    /// applied, so line-ending conversions may show up as modifications.
+    pub fn file_status(&self, path: &Path) -> Result<Option<FileStatus>> {
+        let Some(repo) = self.repo.as_ref() else {
+            return Ok(None);
+        };
+        let relative = work_tree_relative(repo, path)?;
+        let entry = match repo.head_commit() {
+            Ok(commit) => commit.tree()?.lookup_entry_by_path(&relative)?,
+            Err(_) => None,
+        };
+        let Some(entry) = entry else {
+            return Ok(Some(FileStatus::Untracked));
+        };
+        let data =
+            std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+        let id = gix::objs::compute_hash(repo.object_hash(), gix::objs::Kind::Blob, &data)?;
+        Ok(Some(if id == entry.object_id() {
+            FileStatus::Unchanged
+        } else {
+            FileStatus::Modified
+        }))
+    }
+}
+
+fn summarize_commit(commit: &gix::Commit<'_>) -> Result<CommitSummary> {
+    let date = commit
+        .time()
+        .ok()
+        .and_then(|time| OffsetDateTime::from_unix_timestamp(time.seconds).ok())
+        .and_then(|date| {
+            date.format(format_description!("[year]-[month]-[day]"))
+                .ok()
+        });
+    Ok(CommitSummary {
+        id: commit.id().shorten_or_id().to_string(),
+        subject: commit.message()?.summary().to_string(),
+        author: commit.author()?.name.to_string(),
+        date,
+    })
+}
+
+/// `path` relative to the repository's working tree.
+fn work_tree_relative(repo: &gix::Repository, path: &Path) -> Result<PathBuf> {
+    let work_dir = repo
+        .work_dir()
+        .ok_or_else(|| anyhow!("repository has no working tree"))?;
+    let work_dir = work_dir
+        .canonicalize()
+        .unwrap_or_else(|_| work_dir.to_path_buf());
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let relative = path
+        .strip_prefix(&work_dir)
+        .with_context(|| format!("{} is outside {}", path.display(), work_dir.display()))?;
+    Ok(relative.to_path_buf())
+}
+
+/// How a working-tree file compares to the HEAD commit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileStatus {
+    /// Not present in HEAD (new or ignored).
+    Untracked,
+    Modified,
+    Unchanged,
+}
+
+impl FileStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Untracked => "untracked",
+            Self::Modified => "modified",
+            Self::Unchanged => "unchanged",
+        }
+    }
+}
+
+/// A commit that changed a file, as shown in export templates.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CommitSummary {
+    pub id: String,
+    pub subject: String,
+    pub author: String,
+    /// Commit date as `YYYY-MM-DD`.
+    pub date: Option<String>,
+}
+
+/// Recent commit activity in a repository.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GitActivity {
+    /// Length of the period, in days, ending now.
+    pub days: u32,
+    pub commits: usize,
+    pub authors: usize,
+    pub latest: Option<CommitSummary>,
+    /// Entries directly under the inspected directory with the number of commits that changed
+    /// them, most changed first and capped at a handful.
+    pub active_paths: Vec<(String, usize)>,
 }
 
 /// Basic information about the repository used in export templates.
@@ -44,11 +319,556 @@ pub struct GitMetadata {
     pub branch: Option<String>,
     pub commit: Option<String>,
     pub root: PathBuf,
+    /// Git directory shared by all worktrees of the repository.
+    pub common_dir: PathBuf,
+    /// Whether `root` is a linked worktree rather than the main checkout.
+    pub linked_worktree: bool,
+    /// Web location of the default remote when it is hosted on a recognized forge.
+    pub remote: Option<RemoteWeb>,
+}
+
+impl GitMetadata {
+    /// Permalink to `path` (optionally a line range) at the current commit, if the remote is known.
+    pub fn permalink(&self, path: &Path, range: Option<(usize, usize)>) -> Option<String> {
+        let remote = self.remote.as_ref()?;
+        let commit = self.commit.as_deref()?;
+        let relative = match path.strip_prefix(&self.root) {
+            Ok(relative) => relative.to_path_buf(),
+            Err(_) => {
+                let root = self.root.canonicalize().ok()?;
+                let path = path.canonicalize().ok()?;
+                path.strip_prefix(root).ok()?.to_path_buf()
+            }
+        };
+        let components: Vec<_> = relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect();
+        Some(remote.permalink(commit, &components.join("/"), range))
+    }
+
+    /// Whether the working-tree copy of `path` differs from HEAD, so a permalink's line anchors
+    /// may point at other code than the file shows.
+    pub fn has_local_edits(&self, path: &Path) -> bool {
+        GitClient::discover(&self.root)
+            .and_then(|client| client.file_status(path))
+            .is_ok_and(|status| status == Some(FileStatus::Modified))
+    }
+}
+
+/// Code hosting service whose URL scheme is used for permalinks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Forge {
+    GitHub,
+    GitLab,
+}
+
+/// Browser-facing base URL of a repository on a recognized forge.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RemoteWeb {
+    pub forge: Forge,
+    /// e.g. `https://github.com/owner/repo`
+    pub url: String,
+}
+
+impl RemoteWeb {
+    /// Derive the web URL from a fetch URL such as `git@github.com:owner/repo.git`.
+    ///
+    /// Hosts are recognized by name, which also covers self-hosted `gitlab.example.com` instances.
+    pub fn from_url(url: &gix::Url) -> Option<Self> {
+        let host = url.host()?.to_ascii_lowercase();
+        let forge = if host.contains("github") {
+            Forge::GitHub
+        } else if host.contains("gitlab") {
+            Forge::GitLab
+        } else {
+            return None;
+        };
+        let path = url.path.to_string();
+        let path = path.trim_matches('/');
+        let path = path.strip_suffix(".git").unwrap_or(path);
+        if path.is_empty() {
+            return None;
+        }
+        let port = match (&url.scheme, url.port) {
+            (gix::url::Scheme::Http | gix::url::Scheme::Https, Some(port)) => format!(":{port}"),
+            _ => String::new(),
+        };
+        let scheme = if url.scheme == gix::url::Scheme::Http {
+            "http"
+        } else {
+            "https"
+        };
+        Some(Self {
+            forge,
+            url: format!("{scheme}://{host}{port}/{path}"),
+        })
+    }
+
+    /// Link to the `/`-separated `path` at `commit`, anchored to the inclusive line `range` when
+    /// given. Each path segment is percent-encoded.
+    pub fn permalink(&self, commit: &str, path: &str, range: Option<(usize, usize)>) -> String {
+        let blob = match self.forge {
+            Forge::GitHub => "blob",
+            Forge::GitLab => "-/blob",
+        };
+        let path = path
+            .split('/')
+            .map(|segment| utf8_percent_encode(segment, PATH_SEGMENT).to_string())
+            .collect::<Vec<_>>()
+            .join("/");
+        let mut link = format!("{}/{blob}/{commit}/{path}", self.url);
+        if let Some((start, end)) = range {
+            link.push_str(&format!("#L{start}"));
+            if end > start {
+                match self.forge {
+                    Forge::GitHub => link.push_str(&format!("-L{end}")),
+                    Forge::GitLab => link.push_str(&format!("-{end}")),
+                }
+            }
+        }
+        link
+    }
+}
+
+/// The files of one commit, read from the object database instead of the working tree.
+///
+/// Paths are the working-tree paths the files have when the commit is checked out; relative
+/// paths are resolved against the current directory. Submodules are left out.
+pub struct GitFileSource {
+    git_dir: PathBuf,
+    work_dir: PathBuf,
+    revision: String,
+    commit: gix::ObjectId,
+    tree: gix::ObjectId,
+    /// Commit time, reported as every file's modification time.
+    committed: Option<SystemTime>,
+}
+
+impl GitFileSource {
+    /// Read files as of `revision`, e.g. a tag, branch, or commit id, in the repository that
+    /// contains `path`.
+    pub fn open(path: &Path, revision: &str) -> Result<Self> {
+        let repo = gix::discover(discovery_start(path))
+            .with_context(|| format!("{} is not inside a git repository", path.display()))?;
+        let work_dir = repo
+            .work_dir()
+            .ok_or_else(|| anyhow!("repository has no working tree"))?;
+        let work_dir = work_dir
+            .canonicalize()
+            .unwrap_or_else(|_| work_dir.to_path_buf());
+        let commit = repo
+            .find_object(resolve_revision(&repo, revision)?)?
+            .peel_to_commit()
+            .with_context(|| format!("'{revision}' does not name a commit"))?;
+        let committed = commit
+            .time()
+            .ok()
+            .and_then(|time| u64::try_from(time.seconds).ok())
+            .map(|seconds| SystemTime::UNIX_EPOCH + Duration::from_secs(seconds));
+        Ok(Self {
+            work_dir,
+            revision: revision.to_string(),
+            commit: commit.id,
+            tree: commit.tree_id()?.detach(),
+            committed,
+            git_dir: repo.git_dir().to_path_buf(),
+        })
+    }
+
+    /// The revision as it was given to [`Self::open`].
+    pub fn revision(&self) -> &str {
+        &self.revision
+    }
+
+    /// Whether `path` lies in the checkout whose history this reads.
+    pub fn contains(&self, path: &Path) -> bool {
+        std::path::absolute(path).is_ok_and(|path| path.starts_with(&self.work_dir))
+    }
+
+    /// Run `read` against this thread's handle on the repository.
+    ///
+    /// Repository handles cannot be shared between threads, and the scanner and estimator read
+    /// from several, so each thread opens the repository once and keeps it.
+    fn with_repo<R>(&self, read: impl FnOnce(&gix::Repository) -> io::Result<R>) -> io::Result<R> {
+        thread_local! {
+            static REPOSITORIES: RefCell<HashMap<PathBuf, gix::Repository>> =
+                RefCell::new(HashMap::new());
+        }
+        REPOSITORIES.with_borrow_mut(|repositories| {
+            let repo = match repositories.entry(self.git_dir.clone()) {
+                std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+                std::collections::hash_map::Entry::Vacant(entry) => {
+                    entry.insert(gix::open(&self.git_dir).map_err(io::Error::other)?)
+                }
+            };
+            read(repo)
+        })
+    }
+
+    /// Mode and object of the tree entry at `path`; the root tree has no mode.
+    fn lookup(
+        &self,
+        repo: &gix::Repository,
+        path: &Path,
+    ) -> io::Result<(Option<gix::objs::tree::EntryMode>, gix::ObjectId)> {
+        let absolute = std::path::absolute(path)?;
+        let relative = absolute
+            .strip_prefix(&self.work_dir)
+            .map_err(|_| not_found(path))?;
+        if relative.as_os_str().is_empty() {
+            return Ok((None, self.tree));
+        }
+        let tree = repo
+            .find_object(self.tree)
+            .map_err(io::Error::other)?
+            .into_tree();
+        match tree
+            .lookup_entry_by_path(relative)
+            .map_err(io::Error::other)?
+        {
+            Some(entry) if !entry.mode().is_commit() => Ok((Some(entry.mode()), entry.object_id())),
+            _ => Err(not_found(path)),
+        }
+    }
+}
+
+/// Object named by `revision`: a branch, tag, or other reference, or a commit id or unique
+/// prefix of one. Expressions such as `HEAD~2` are not supported.
+fn resolve_revision(repo: &gix::Repository, revision: &str) -> Result<gix::ObjectId> {
+    if let Ok(Some(mut reference)) = repo.try_find_reference(revision) {
+        return Ok(reference.peel_to_id()?.detach());
+    }
+    if let Ok(prefix) = gix::hash::Prefix::from_hex(revision) {
+        match repo.objects.lookup_prefix(prefix, None)? {
+            Some(Ok(id)) => return Ok(id),
+            Some(Err(())) => bail!("revision '{revision}' is ambiguous"),
+            None => {}
+        }
+    }
+    bail!("unknown revision '{revision}'")
+}
+
+impl fmt::Debug for GitFileSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GitFileSource")
+            .field("git_dir", &self.git_dir)
+            .field("work_dir", &self.work_dir)
+            .field("revision", &self.revision)
+            .field("commit", &self.commit)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Vfs for GitFileSource {
+    fn metadata(&self, path: &Path) -> io::Result<VfsMetadata> {
+        self.with_repo(|repo| {
+            let (mode, id) = self.lookup(repo, path)?;
+            if mode.is_none_or(|mode| mode.is_tree()) {
+                return Ok(VfsMetadata {
+                    is_dir: true,
+                    len: 0,
+                    modified: self.committed,
+                });
+            }
+            let header = repo.find_header(id).map_err(io::Error::other)?;
+            Ok(VfsMetadata {
+                is_dir: false,
+                len: header.size(),
+                modified: self.committed,
+            })
+        })
+    }
+
+    fn open(&self, path: &Path) -> io::Result<Box<dyn VfsFile>> {
+        self.with_repo(|repo| {
+            let (mode, id) = self.lookup(repo, path)?;
+            if mode.is_none_or(|mode| mode.is_tree()) {
+                return Err(io::Error::new(
+                    io::ErrorKind::IsADirectory,
+                    format!("{} is a directory", path.display()),
+                ));
+            }
+            let object = repo.find_object(id).map_err(io::Error::other)?;
+            Ok(Box::new(Cursor::new(object.detach().data)) as Box<dyn VfsFile>)
+        })
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        self.with_repo(|repo| {
+            let (mode, id) = self.lookup(repo, path)?;
+            if mode.is_some_and(|mode| !mode.is_tree()) {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotADirectory,
+                    format!("{} is not a directory", path.display()),
+                ));
+            }
+            let tree = repo.find_object(id).map_err(io::Error::other)?.into_tree();
+            let entries = tree.decode().map_err(io::Error::other)?.entries;
+            Ok(entries
+                .iter()
+                .filter(|entry| !entry.mode.is_commit())
+                .map(|entry| path.join(gix::path::from_bstr(entry.filename)))
+                .collect())
+        })
+    }
+
+    fn commit(&self) -> Option<String> {
+        Some(self.commit.to_string())
+    }
+}
+
+/// Whether `dir` is the top of a nested repository such as a submodule.
+///
+/// Submodules and linked worktrees have a `.git` file; nested clones have a `.git` directory.
+pub fn is_nested_repository(dir: &Path) -> bool {
+    dir.join(".git").exists()
 }
 
 /// Convenience helper to retrieve metadata directly from a path.
 pub fn metadata_for_path(path: &Path) -> Option<GitMetadata> {
-    GitClient::discover(path)
+    GitClient::discover(discovery_start(path))
         .ok()
         .and_then(|client| client.metadata())
 }
+
+/// Convenience helper returning recent commits touching `path`; empty outside a repository.
+pub fn history_for_path(path: &Path, limit: usize) -> Result<Vec<CommitSummary>> {
+    GitClient::discover(discovery_start(path))?.file_history(path, limit)
+}
+
+/// Convenience helper returning the last `days` days of commit activity under `dir`; `None`
+/// outside a repository.
+pub fn activity_for_path(dir: &Path, days: u32) -> Result<Option<GitActivity>> {
+    GitClient::discover(discovery_start(dir))?.recent_activity(dir, days)
+}
+
+/// Convenience helper returning the working-tree status of `path`; `None` outside a repository.
+pub fn status_for_path(path: &Path) -> Result<Option<FileStatus>> {
+    GitClient::discover(discovery_start(path))?.file_status(path)
+}
+
+/// Directory to start repository discovery from; files (including bare relative names) use
+/// their parent directory.
+fn discovery_start(path: &Path) -> &Path {
+    if path.is_dir() {
+        return path;
+    }
+    path.parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn nested_repositories_are_detected_by_git_entry() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let submodule = temp.path().join("vendor/lib");
+        let clone = temp.path().join("clone");
+        fs::create_dir_all(&submodule)?;
+        fs::create_dir_all(clone.join(".git"))?;
+        fs::write(submodule.join(".git"), "gitdir: ../../.git/modules/lib\n")?;
+
+        assert!(is_nested_repository(&submodule));
+        assert!(is_nested_repository(&clone));
+        assert!(!is_nested_repository(temp.path()));
+        Ok(())
+    }
+
+    #[test]
+    fn file_history_lists_commits_touching_the_file() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let repo = gix::init(temp.path())?;
+        let signature = gix::actor::SignatureRef {
+            name: "Dev".into(),
+            email: "dev@example.com".into(),
+            time: "1700000000 +0000",
+        };
+
+        let mut parent: Option<gix::ObjectId> = None;
+        for (message, notes, readme) in [
+            ("Add notes", "v1", "r1"),
+            ("Update readme", "v1", "r2"),
+            ("Revise notes", "v2", "r2"),
+        ] {
+            let mut entries = Vec::new();
+            for (name, contents) in [("notes.txt", notes), ("readme.md", readme)] {
+                entries.push(gix::objs::tree::Entry {
+                    mode: gix::objs::tree::EntryKind::Blob.into(),
+                    filename: name.into(),
+                    oid: repo.write_blob(contents)?.detach(),
+                });
+            }
+            let tree = repo.write_object(gix::objs::Tree { entries })?.detach();
+            let id = repo.commit_as(signature, signature, "HEAD", message, tree, parent)?;
+            parent = Some(id.detach());
+        }
+        fs::write(temp.path().join("notes.txt"), "v2")?;
+
+        let client = GitClient::discover(temp.path())?;
+        let history = client.file_history(&temp.path().join("notes.txt"), 5)?;
+        let subjects: Vec<_> = history.iter().map(|c| c.subject.as_str()).collect();
+        assert_eq!(subjects, vec!["Revise notes", "Add notes"]);
+        assert_eq!(history[0].author, "Dev");
+        assert_eq!(history[0].date.as_deref(), Some("2023-11-14"));
+
+        assert_eq!(
+            client
+                .file_history(&temp.path().join("notes.txt"), 1)?
+                .len(),
+            1
+        );
+
+        fs::write(temp.path().join("readme.md"), "edited")?;
+        fs::write(temp.path().join("new.txt"), "")?;
+        let status = |name: &str| client.file_status(&temp.path().join(name));
+        assert_eq!(status("notes.txt")?, Some(FileStatus::Unchanged));
+        assert_eq!(status("readme.md")?, Some(FileStatus::Modified));
+        assert_eq!(status("new.txt")?, Some(FileStatus::Untracked));
+
+        let metadata = client.metadata().expect("repository discovered");
+        assert!(metadata.has_local_edits(&temp.path().join("readme.md")));
+        assert!(!metadata.has_local_edits(&temp.path().join("notes.txt")));
+        assert!(!metadata.has_local_edits(&temp.path().join("new.txt")));
+        Ok(())
+    }
+
+    #[test]
+    fn git_file_source_reads_files_as_of_a_revision() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = temp.path().canonicalize()?;
+        let repo = gix::init(&root)?;
+        let signature = gix::actor::SignatureRef {
+            name: "Dev".into(),
+            email: "dev@example.com".into(),
+            time: "1700000000 +0000",
+        };
+        let blob = gix::objs::tree::EntryKind::Blob.into();
+        let src = repo
+            .write_object(gix::objs::Tree {
+                entries: vec![gix::objs::tree::Entry {
+                    mode: blob,
+                    filename: "lib.rs".into(),
+                    oid: repo.write_blob("fn old() {}\n")?.detach(),
+                }],
+            })?
+            .detach();
+
+        let mut commits = Vec::new();
+        for notes in ["v1", "v2"] {
+            let entries = vec![
+                gix::objs::tree::Entry {
+                    mode: blob,
+                    filename: "notes.txt".into(),
+                    oid: repo.write_blob(notes)?.detach(),
+                },
+                gix::objs::tree::Entry {
+                    mode: gix::objs::tree::EntryKind::Tree.into(),
+                    filename: "src".into(),
+                    oid: src,
+                },
+            ];
+            let tree = repo.write_object(gix::objs::Tree { entries })?.detach();
+            let id = repo.commit_as(
+                signature,
+                signature,
+                "HEAD",
+                notes,
+                tree,
+                commits.last().copied(),
+            )?;
+            commits.push(id.detach());
+        }
+        repo.reference(
+            "refs/tags/v1.0",
+            commits[0],
+            gix::refs::transaction::PreviousValue::Any,
+            "tag v1.0",
+        )?;
+        fs::write(root.join("notes.txt"), "edited")?;
+
+        let source = GitFileSource::open(&root, "v1.0")?;
+        assert_eq!(source.read(&root.join("notes.txt"))?, b"v1");
+        assert_eq!(
+            source.read_text(&root.join("src/lib.rs"))?.text,
+            "fn old() {}\n"
+        );
+        assert_eq!(source.metadata(&root.join("notes.txt"))?.len, 2);
+        assert!(source.metadata(&root.join("src"))?.is_dir);
+        assert!(!source.exists(&root.join("missing.txt")));
+        let mut listed = source.read_dir(&root)?;
+        listed.sort();
+        assert_eq!(listed, vec![root.join("notes.txt"), root.join("src")]);
+        assert_eq!(source.commit(), Some(commits[0].to_string()));
+
+        let latest = commits[1].to_hex_with_len(8).to_string();
+        let source = GitFileSource::open(&root, &latest)?;
+        assert_eq!(source.read(&root.join("notes.txt"))?, b"v2");
+        assert!(GitFileSource::open(&root, "v9.9").is_err());
+        Ok(())
+    }
+
+    fn remote(url: &str) -> Option<RemoteWeb> {
+        RemoteWeb::from_url(&gix::url::parse(url.into()).expect("valid url"))
+    }
+
+    #[test]
+    fn remote_urls_map_to_forge_web_urls() {
+        let github = remote("git@github.com:owner/repo.git").expect("github");
+        assert_eq!(github.forge, Forge::GitHub);
+        assert_eq!(github.url, "https://github.com/owner/repo");
+
+        let gitlab = remote("https://gitlab.example.com/group/sub/project.git").expect("gitlab");
+        assert_eq!(gitlab.forge, Forge::GitLab);
+        assert_eq!(gitlab.url, "https://gitlab.example.com/group/sub/project");
+
+        assert!(remote("https://git.example.com/repo.git").is_none());
+    }
+
+    #[test]
+    fn permalinks_follow_each_forge_anchor_style() {
+        let github = remote("ssh://git@github.com/owner/repo").expect("github");
+        assert_eq!(
+            github.permalink("abc123", "docs/read me #2?.md", None),
+            "https://github.com/owner/repo/blob/abc123/docs/read%20me%20%232%3F.md"
+        );
+        assert_eq!(
+            github.permalink("abc123", "100%/ünï.rs", None),
+            "https://github.com/owner/repo/blob/abc123/100%25/%C3%BCn%C3%AF.rs"
+        );
+        assert_eq!(
+            github.permalink("abc123", "src/lib.rs", Some((10, 20))),
+            "https://github.com/owner/repo/blob/abc123/src/lib.rs#L10-L20"
+        );
+        assert_eq!(
+            github.permalink("abc123", "src/lib.rs", Some((7, 7))),
+            "https://github.com/owner/repo/blob/abc123/src/lib.rs#L7"
+        );
+
+        let gitlab = remote("git@gitlab.com:group/project.git").expect("gitlab");
+        assert_eq!(
+            gitlab.permalink("abc123", "README.md", Some((1, 3))),
+            "https://gitlab.com/group/project/-/blob/abc123/README.md#L1-3"
+        );
+        assert_eq!(
+            gitlab.permalink("abc123", "README.md", None),
+            "https://gitlab.com/group/project/-/blob/abc123/README.md"
+        );
+    }
+
+    #[test]
+    fn metadata_reports_main_checkout() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        gix::init(temp.path())?;
+
+        let metadata = metadata_for_path(temp.path()).expect("repository discovered");
+        assert!(!metadata.linked_worktree);
+        assert!(metadata.common_dir.ends_with(".git"));
+        Ok(())
+    }
+}
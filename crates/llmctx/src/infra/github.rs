@@ -0,0 +1,323 @@
+//! GitHub pull request and issue access through the `gh` CLI or the REST API.
+
+use std::io::ErrorKind;
+use std::process::Command;
+
+use anyhow::{Context, Result, anyhow, bail};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+
+use crate::infra::curl::CurlRequest;
+use crate::infra::git::{Forge, RemoteWeb};
+
+/// Files requested per page from the pull request files endpoint (the API maximum).
+const FILES_PER_PAGE: usize = 100;
+/// GitHub stops listing files after 3000, i.e. 30 full pages.
+const MAX_FILE_PAGES: usize = 30;
+
+/// Identifies a pull request on a GitHub host.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PullRequestRef {
+    pub host: String,
+    pub owner: String,
+    pub repo: String,
+    pub number: u64,
+}
+
+impl PullRequestRef {
+    /// Parse a PR number (resolved against `remote`) or a `https://<host>/<owner>/<repo>/pull/<n>` URL.
+    pub fn parse(target: &str, remote: Option<&RemoteWeb>) -> Result<Self> {
+        let target = target.trim().trim_start_matches('#');
+        if let Ok(number) = target.parse::<u64>() {
+            let remote = remote
+                .filter(|remote| remote.forge == Forge::GitHub)
+                .ok_or_else(|| {
+                    anyhow!("no GitHub remote found; pass the full pull request URL instead")
+                })?;
+            let (host, owner, repo) = split_repository_url(&remote.url)
+                .ok_or_else(|| anyhow!("unrecognized GitHub remote {}", remote.url))?;
+            return Ok(Self {
+                host,
+                owner,
+                repo,
+                number,
+            });
+        }
+
+        let (host, owner, repo, rest) = split_repository_url(target)
+            .and_then(|(host, owner, repo)| {
+                let prefix = format!("{host}/{owner}/{repo}/");
+                let rest = target.split_once(&prefix)?.1.to_string();
+                Some((host, owner, repo, rest))
+            })
+            .ok_or_else(|| anyhow!("expected a pull request number or URL, got '{target}'"))?;
+        let number = number_after(&rest, "pull/")
+            .ok_or_else(|| anyhow!("'{target}' is not a pull request URL"))?;
+        Ok(Self {
+            host,
+            owner,
+            repo,
+            number,
+        })
+    }
+
+    fn endpoint(&self) -> String {
+        format!("repos/{}/{}/pulls/{}", self.owner, self.repo, self.number)
+    }
+}
+
+/// Parse the number following `kind` in a URL tail such as `pull/12/files`.
+fn number_after(rest: &str, kind: &str) -> Option<u64> {
+    rest.strip_prefix(kind)?
+        .split(['/', '#', '?'])
+        .next()?
+        .parse()
+        .ok()
+}
+
+fn api_base(host: &str) -> String {
+    if host == "github.com" {
+        "https://api.github.com".to_string()
+    } else {
+        format!("https://{host}/api/v3")
+    }
+}
+
+/// Split `https://host/owner/repo[/...]` into its host, owner, and repository name.
+fn split_repository_url(url: &str) -> Option<(String, String, String)> {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))?;
+    let mut parts = rest.split('/');
+    let host = parts.next().filter(|host| !host.is_empty())?;
+    let owner = parts.next().filter(|owner| !owner.is_empty())?;
+    let repo = parts.next().filter(|repo| !repo.is_empty())?;
+    let repo = repo.strip_suffix(".git").unwrap_or(repo);
+    Some((host.to_string(), owner.to_string(), repo.to_string()))
+}
+
+/// Pull request details needed to build review selections.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PullRequest {
+    pub number: u64,
+    pub title: String,
+    pub body: Option<String>,
+    pub url: String,
+    pub head_sha: String,
+    pub files: Vec<PullRequestFile>,
+}
+
+/// A file changed by a pull request.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct PullRequestFile {
+    #[serde(rename = "filename")]
+    pub path: String,
+    /// `added`, `modified`, `removed`, `renamed`, ...
+    pub status: String,
+    /// Unified diff of the file; absent for binary or very large changes.
+    #[serde(default)]
+    pub patch: Option<String>,
+}
+
+impl PullRequestFile {
+    /// Line ranges of the new file covered by the diff hunks.
+    pub fn hunk_ranges(&self) -> Vec<(usize, usize)> {
+        self.patch.as_deref().map(hunk_ranges).unwrap_or_default()
+    }
+}
+
+#[derive(Deserialize)]
+struct PullRequestResponse {
+    number: u64,
+    title: String,
+    #[serde(default)]
+    body: Option<String>,
+    html_url: String,
+    head: HeadResponse,
+}
+
+#[derive(Deserialize)]
+struct HeadResponse {
+    sha: String,
+}
+
+/// Fetch the pull request and its changed files.
+///
+/// Uses `gh api` when the GitHub CLI is installed (so its login is reused), otherwise calls the
+/// REST API with `curl` and a `GH_TOKEN`/`GITHUB_TOKEN` token.
+pub fn fetch_pull_request(reference: &PullRequestRef) -> Result<PullRequest> {
+    let host = reference.host.as_str();
+    let pull: PullRequestResponse = fetch_json(host, &reference.endpoint())?;
+    let mut files = Vec::new();
+    for page in 1..=MAX_FILE_PAGES {
+        let endpoint = format!(
+            "{}/files?per_page={FILES_PER_PAGE}&page={page}",
+            reference.endpoint()
+        );
+        let batch: Vec<PullRequestFile> = fetch_json(host, &endpoint)?;
+        let done = batch.len() < FILES_PER_PAGE;
+        files.extend(batch);
+        if done {
+            break;
+        }
+    }
+
+    Ok(PullRequest {
+        number: pull.number,
+        title: pull.title,
+        body: pull.body.filter(|body| !body.trim().is_empty()),
+        url: pull.html_url,
+        head_sha: pull.head.sha,
+        files,
+    })
+}
+
+/// An issue (or pull request) title and description.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Issue {
+    pub number: u64,
+    pub title: String,
+    #[serde(default)]
+    pub body: Option<String>,
+}
+
+/// Whether `url` points at a GitHub issue or pull request page.
+pub fn is_issue_url(url: &str) -> bool {
+    issue_endpoint(url).is_some()
+}
+
+/// Fetch the issue or pull request at a `https://<host>/<owner>/<repo>/issues/<n>` URL.
+pub fn fetch_issue(url: &str) -> Result<Issue> {
+    let (host, endpoint) =
+        issue_endpoint(url).ok_or_else(|| anyhow!("'{url}' is not a GitHub issue URL"))?;
+    fetch_json(&host, &endpoint)
+}
+
+fn issue_endpoint(url: &str) -> Option<(String, String)> {
+    let (host, owner, repo) = split_repository_url(url)?;
+    if !host.contains("github") {
+        return None;
+    }
+    let rest = url.split_once(&format!("{host}/{owner}/{repo}/"))?.1;
+    let number = number_after(rest, "issues/").or_else(|| number_after(rest, "pull/"))?;
+    // The issues endpoint also serves pull requests, so one request shape covers both.
+    Some((host, format!("repos/{owner}/{repo}/issues/{number}")))
+}
+
+fn fetch_json<T: DeserializeOwned>(host: &str, endpoint: &str) -> Result<T> {
+    let output = match Command::new("gh")
+        .args(["api", "--hostname", host, endpoint])
+        .output()
+    {
+        Ok(output) => output,
+        Err(err) if err.kind() == ErrorKind::NotFound => curl_request(host, endpoint)?,
+        Err(err) => return Err(err).context("failed to run gh"),
+    };
+    if !output.status.success() {
+        bail!(
+            "GitHub request for {endpoint} failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    serde_json::from_slice(&output.stdout)
+        .with_context(|| format!("unexpected GitHub response for {endpoint}"))
+}
+
+fn curl_request(host: &str, endpoint: &str) -> Result<std::process::Output> {
+    let token = std::env::var("GH_TOKEN")
+        .or_else(|_| std::env::var("GITHUB_TOKEN"))
+        .map_err(|_| {
+            anyhow!("install the gh CLI or set GH_TOKEN/GITHUB_TOKEN to read from GitHub")
+        })?;
+    api_request(host, endpoint, &token).send()
+}
+
+fn api_request(host: &str, endpoint: &str, token: &str) -> CurlRequest {
+    CurlRequest::get(format!("{}/{endpoint}", api_base(host)))
+        .header("Accept: application/vnd.github+json")
+        .header(format!("Authorization: Bearer {token}"))
+}
+
+/// New-file line ranges from the `@@ -a,b +c,d @@` headers of a unified diff.
+///
+/// Hunks that only delete lines have no new-file lines and are skipped.
+pub fn hunk_ranges(patch: &str) -> Vec<(usize, usize)> {
+    patch
+        .lines()
+        .filter_map(|line| {
+            let header = line.strip_prefix("@@ ")?;
+            let new = header
+                .split_whitespace()
+                .find(|part| part.starts_with('+'))?;
+            let (start, count) = match new[1..].split_once(',') {
+                Some((start, count)) => (start.parse::<usize>().ok()?, count.parse().ok()?),
+                None => (new[1..].parse().ok()?, 1),
+            };
+            (count > 0).then(|| (start.max(1), start.max(1) + count - 1))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn github_remote() -> RemoteWeb {
+        RemoteWeb {
+            forge: Forge::GitHub,
+            url: "https://github.com/owner/repo".to_string(),
+        }
+    }
+
+    #[test]
+    fn references_parse_from_numbers_and_urls() -> Result<()> {
+        let remote = github_remote();
+        let by_number = PullRequestRef::parse("#42", Some(&remote))?;
+        assert_eq!(by_number.endpoint(), "repos/owner/repo/pulls/42");
+        assert_eq!(api_base(&by_number.host), "https://api.github.com");
+
+        let by_url = PullRequestRef::parse("https://ghe.example.com/team/tool/pull/7/files", None)?;
+        assert_eq!(by_url.host, "ghe.example.com");
+        assert_eq!(by_url.endpoint(), "repos/team/tool/pulls/7");
+        assert_eq!(api_base(&by_url.host), "https://ghe.example.com/api/v3");
+
+        assert!(PullRequestRef::parse("42", None).is_err());
+        assert!(PullRequestRef::parse("https://github.com/owner/repo/issues/3", None).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn issue_urls_map_to_the_issues_endpoint() {
+        assert_eq!(
+            issue_endpoint("https://github.com/owner/repo/issues/15#issuecomment-1"),
+            Some((
+                "github.com".to_string(),
+                "repos/owner/repo/issues/15".to_string()
+            ))
+        );
+        assert!(is_issue_url("https://github.com/owner/repo/pull/3"));
+        assert!(!is_issue_url("https://github.com/owner/repo/tree/main"));
+        assert!(!is_issue_url("https://gitlab.com/owner/repo/issues/3"));
+    }
+
+    #[test]
+    fn api_tokens_stay_off_the_command_line() {
+        let request = api_request("github.com", "repos/owner/repo/issues/1", "ghp_secret");
+        assert!(
+            request.args().iter().all(|arg| !arg.contains("ghp_secret")),
+            "{:?}",
+            request.args()
+        );
+        assert!(
+            request
+                .config()
+                .contains("header = \"Authorization: Bearer ghp_secret\"\n")
+        );
+    }
+
+    #[test]
+    fn hunk_headers_map_to_new_file_ranges() {
+        let patch = "@@ -1,3 +1,4 @@\n line\n+added\n@@ -20,2 +21 @@ fn main() {\n-gone\n@@ -40,3 +40,0 @@\n-x\n";
+        assert_eq!(hunk_ranges(patch), vec![(1, 4), (21, 21)]);
+    }
+}
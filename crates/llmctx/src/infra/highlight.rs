@@ -11,10 +11,26 @@ use syntect::easy::HighlightLines;
 use syntect::highlighting::{FontStyle, Style as SyntectStyle, Theme, ThemeSet};
 use syntect::parsing::{SyntaxReference, SyntaxSet};
 
+use crate::infra::highlight_assets::{EMBEDDED_SYNTAXES, EMBEDDED_THEMES};
+
 const DEFAULT_THEME: &str = "base16-ocean.dark";
 
+/// Short names accepted for bundled themes, including syntect's spaced Solarized names.
+const THEME_ALIASES: &[(&str, &str)] = &[
+    ("solarized-dark", "Solarized (dark)"),
+    ("solarized-light", "Solarized (light)"),
+    ("gruvbox", "gruvbox-dark"),
+    ("catppuccin", "catppuccin-mocha"),
+];
+
+/// Syntaxes come from two-face, which packages syntect's defaults together with the upstream
+/// grammars bat bundles (TypeScript, TOML, Dockerfile, and more); `cargo xtask assets` compiles
+/// any further ones on top of that set.
 static DEFAULT_ASSETS: Lazy<(Arc<SyntaxSet>, Arc<ThemeSet>)> = Lazy::new(|| {
-    let syntax_set = SyntaxSet::load_defaults_newlines();
+    let syntax_set = match EMBEDDED_SYNTAXES {
+        Some(dump) => syntect::dumps::from_binary(dump),
+        None => two_face::syntax::extra_newlines(),
+    };
     let mut theme_set = ThemeSet::load_defaults();
 
     for (name, source) in EMBEDDED_THEMES {
@@ -35,12 +51,6 @@ static DEFAULT_ASSETS: Lazy<(Arc<SyntaxSet>, Arc<ThemeSet>)> = Lazy::new(|| {
     (Arc::new(syntax_set), Arc::new(theme_set))
 });
 
-/// Embedded themes bundled with the binary.
-static EMBEDDED_THEMES: &[(&str, &str)] = &[(
-    "dracula",
-    include_str!("../../assets/themes/dracula.tmTheme"),
-)];
-
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct RgbColor {
     pub r: u8,
@@ -133,6 +143,18 @@ impl Highlighter {
         themes
     }
 
+    /// Whether `theme` names a bundled theme or alias, ignoring case.
+    pub fn has_theme(&self, theme: &str) -> bool {
+        THEME_ALIASES
+            .iter()
+            .any(|(alias, _)| alias.eq_ignore_ascii_case(theme))
+            || self
+                .theme_set
+                .themes
+                .keys()
+                .any(|name| name.eq_ignore_ascii_case(theme))
+    }
+
     pub fn highlight(&self, path: &Path, lines: &[String], theme: &str) -> HighlightResult {
         let resolved_theme = self.resolve_theme(theme);
         let theme_name = resolved_theme.name.to_string();
@@ -189,6 +211,10 @@ impl Highlighter {
     }
 
     fn resolve_theme<'a>(&'a self, requested: &'a str) -> ResolvedTheme<'a> {
+        let requested = THEME_ALIASES
+            .iter()
+            .find(|(alias, _)| alias.eq_ignore_ascii_case(requested))
+            .map_or(requested, |(_, name)| *name);
         if let Some(theme) = self.theme_set.themes.get(requested) {
             return ResolvedTheme {
                 name: Cow::Borrowed(requested),
@@ -289,6 +315,59 @@ mod tests {
         );
     }
 
+    #[test]
+    fn bundled_themes_resolve_by_name_and_alias() {
+        let highlighter = Highlighter::new();
+        let lines = vec!["let x = 1;".to_string()];
+        for (requested, resolved) in [
+            ("gruvbox", "gruvbox-dark"),
+            ("gruvbox-light", "gruvbox-light"),
+            ("catppuccin-mocha", "catppuccin-mocha"),
+            ("solarized-dark", "Solarized (dark)"),
+            ("Solarized-Light", "Solarized (light)"),
+        ] {
+            assert!(highlighter.has_theme(requested));
+            let result = highlighter.highlight(Path::new("main.rs"), &lines, requested);
+            assert_eq!(result.theme, resolved);
+        }
+        assert!(!highlighter.has_theme("not-a-theme"));
+    }
+
+    #[test]
+    fn bundled_syntaxes_highlight_modern_languages() {
+        let highlighter = Highlighter::new();
+        for (file, language, source) in [
+            ("src/api.ts", "TypeScript", "export const x: number = 1;"),
+            (
+                "src/App.tsx",
+                "TypeScriptReact",
+                "const a = <div id=\"x\">{a}</div>;",
+            ),
+            (
+                "src/App.svelte",
+                "Svelte",
+                "<button on:click={go}>{n}</button>",
+            ),
+            ("build.zig", "Zig", "pub fn main() void {}"),
+            ("Cargo.toml", "TOML", "name = \"llmctx\""),
+            ("Dockerfile", "Dockerfile", "FROM rust:1 AS build"),
+            ("main.tf", "Terraform", "resource \"aws_s3_bucket\" \"b\" {"),
+            ("Main.kt", "Kotlin", "fun main() = println(\"$x\")"),
+        ] {
+            let result =
+                highlighter.highlight(Path::new(file), &[format!("{source}\n")], "gruvbox-dark");
+            assert_eq!(result.language.as_deref(), Some(language), "{file}");
+            assert_eq!(result.mode, HighlightMode::Highlighted, "{file}");
+            let spans = &result.lines[0].spans;
+            assert!(
+                spans
+                    .iter()
+                    .any(|span| span.style.foreground != spans[0].style.foreground),
+                "{file} rendered in a single colour"
+            );
+        }
+    }
+
     #[test]
     fn highlight_rust_file_produces_segments() -> Result<()> {
         let dir = tempdir()?;
@@ -0,0 +1,26 @@
+//! Syntax and theme assets embedded in the binary.
+//!
+//! @generated by `cargo xtask assets`; edit `assets/sources.toml` and rerun it instead.
+
+/// Bundled tmThemes, by name.
+pub static EMBEDDED_THEMES: &[(&str, &str)] = &[
+    (
+        "catppuccin-mocha",
+        include_str!("../../assets/themes/catppuccin-mocha.tmTheme"),
+    ),
+    (
+        "dracula",
+        include_str!("../../assets/themes/dracula.tmTheme"),
+    ),
+    (
+        "gruvbox-dark",
+        include_str!("../../assets/themes/gruvbox-dark.tmTheme"),
+    ),
+    (
+        "gruvbox-light",
+        include_str!("../../assets/themes/gruvbox-light.tmTheme"),
+    ),
+];
+
+/// Compressed syntax set (two-face's syntaxes plus bundled ones), when any are bundled.
+pub static EMBEDDED_SYNTAXES: Option<&[u8]> = None;
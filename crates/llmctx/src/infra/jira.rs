@@ -0,0 +1,148 @@
+//! Jira issue access through the REST API.
+
+use anyhow::{Context, Result, anyhow, bail};
+use serde::Deserialize;
+
+use crate::infra::curl::CurlRequest;
+
+/// A Jira issue's summary and description.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JiraIssue {
+    pub key: String,
+    pub summary: String,
+    pub description: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct IssueResponse {
+    key: String,
+    fields: IssueFields,
+}
+
+#[derive(Deserialize)]
+struct IssueFields {
+    summary: String,
+    #[serde(default)]
+    description: Option<String>,
+}
+
+/// Whether `url` points at a Jira issue (`https://<host>/browse/<KEY-123>`).
+pub fn is_issue_url(url: &str) -> bool {
+    issue_location(url).is_some()
+}
+
+/// Fetch the issue at a `/browse/<KEY>` URL.
+///
+/// Authenticates with `JIRA_EMAIL` and `JIRA_API_TOKEN` (Jira Cloud) or a `JIRA_TOKEN` personal
+/// access token (Data Center).
+pub fn fetch_issue(url: &str) -> Result<JiraIssue> {
+    let (base, key) =
+        issue_location(url).ok_or_else(|| anyhow!("'{url}' is not a Jira issue URL"))?;
+    // API v2 returns descriptions as wiki-markup text; v3 would return a JSON document tree.
+    let endpoint = format!("{base}/rest/api/2/issue/{key}?fields=summary,description");
+
+    let request = issue_request(
+        &endpoint,
+        std::env::var("JIRA_EMAIL").ok(),
+        std::env::var("JIRA_API_TOKEN").ok(),
+        std::env::var("JIRA_TOKEN").ok(),
+    )?;
+    let output = request.send()?;
+    if !output.status.success() {
+        bail!(
+            "Jira request for {key} failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let response: IssueResponse = serde_json::from_slice(&output.stdout)
+        .with_context(|| format!("unexpected Jira response for {key}"))?;
+    Ok(JiraIssue {
+        key: response.key,
+        summary: response.fields.summary,
+        description: response
+            .fields
+            .description
+            .filter(|text| !text.trim().is_empty()),
+    })
+}
+
+/// A request for `endpoint` authenticated with Jira Cloud basic credentials or, failing that, a
+/// Data Center personal access token.
+fn issue_request(
+    endpoint: &str,
+    email: Option<String>,
+    api_token: Option<String>,
+    personal_token: Option<String>,
+) -> Result<CurlRequest> {
+    let request = CurlRequest::get(endpoint).header("Accept: application/json");
+    match (email, api_token, personal_token) {
+        (Some(email), Some(token), _) => Ok(request.user(format!("{email}:{token}"))),
+        (_, _, Some(token)) => Ok(request.header(format!("Authorization: Bearer {token}"))),
+        _ => bail!("set JIRA_EMAIL and JIRA_API_TOKEN, or JIRA_TOKEN, to read Jira issues"),
+    }
+}
+
+/// Split a browse URL into the site base URL and the issue key.
+fn issue_location(url: &str) -> Option<(String, String)> {
+    if !url.starts_with("https://") && !url.starts_with("http://") {
+        return None;
+    }
+    let (base, rest) = url.split_once("/browse/")?;
+    let key = rest.split(['/', '?', '#']).next()?;
+    let (project, number) = key.split_once('-')?;
+    let valid = !project.is_empty()
+        && project
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+        && !number.is_empty()
+        && number.chars().all(|ch| ch.is_ascii_digit());
+    valid.then(|| (base.to_string(), key.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn browse_urls_split_into_site_and_key() {
+        assert_eq!(
+            issue_location("https://acme.atlassian.net/browse/OPS-42?focusedCommentId=1"),
+            Some((
+                "https://acme.atlassian.net".to_string(),
+                "OPS-42".to_string()
+            ))
+        );
+        assert!(is_issue_url("https://jira.example.com/jira/browse/APP_2-7"));
+        assert!(!is_issue_url("https://acme.atlassian.net/browse/OPS"));
+        assert!(!is_issue_url("docs/browse/OPS-1.md"));
+    }
+
+    #[test]
+    fn credentials_stay_off_the_command_line() -> Result<()> {
+        let endpoint = "https://acme.atlassian.net/rest/api/2/issue/OPS-1";
+        let cloud = issue_request(
+            endpoint,
+            Some("me@acme.test".to_string()),
+            Some("cloud-secret".to_string()),
+            None,
+        )?;
+        assert!(cloud.args().iter().all(|arg| !arg.contains("secret")));
+        assert!(
+            cloud
+                .config()
+                .contains("user = \"me@acme.test:cloud-secret\"\n")
+        );
+
+        let data_center = issue_request(endpoint, None, None, Some("pat-secret".to_string()))?;
+        assert!(data_center.args().iter().all(|arg| !arg.contains("secret")));
+        assert!(
+            data_center
+                .config()
+                .contains("header = \"Authorization: Bearer pat-secret\"\n")
+        );
+
+        assert!(issue_request(endpoint, Some("me@acme.test".to_string()), None, None).is_err());
+        Ok(())
+    }
+}
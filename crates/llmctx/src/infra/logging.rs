@@ -1,5 +1,21 @@
 //! Logging initialization helpers.
 
+use tracing::{Dispatch, Level};
+
+/// Install a stderr subscriber at `INFO` as the process-wide default, unless one is already set.
 pub fn init_tracing() {
-    // TODO: implement tracing subscriber setup
+    let _ = tracing::dispatcher::set_global_default(dispatch(Level::INFO));
+}
+
+/// A stderr subscriber up to `max_level`, without installing it anywhere.
+///
+/// Embedders can scope it to their own calls with [`tracing::dispatcher::with_default`] or hand
+/// it to [`crate::app::pipeline::PipelineBuilder::tracing`].
+pub fn dispatch(max_level: Level) -> Dispatch {
+    Dispatch::new(
+        tracing_subscriber::fmt()
+            .with_max_level(max_level)
+            .with_writer(std::io::stderr)
+            .finish(),
+    )
 }
@@ -2,8 +2,15 @@
 
 pub mod clipboard;
 pub mod config;
+mod curl;
+pub mod documents;
 pub mod fs;
 pub mod git;
+pub mod github;
 pub mod highlight;
+mod highlight_assets;
+pub mod jira;
 pub mod logging;
 pub mod plugins;
+pub mod ripgrep;
+pub mod token_api;
@@ -0,0 +1,118 @@
+//! Content search through an installed ripgrep, read from its `--json` output.
+
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+
+/// One matching line reported by ripgrep.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RipgrepMatch {
+    pub path: PathBuf,
+    /// 1-based line number.
+    pub line: usize,
+    /// The matching line without its line terminator.
+    pub text: String,
+}
+
+#[derive(Deserialize)]
+struct Message {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    data: Option<MatchData>,
+}
+
+#[derive(Deserialize)]
+struct MatchData {
+    path: Data,
+    lines: Data,
+    line_number: Option<usize>,
+}
+
+/// ripgrep reports valid UTF-8 as `text` and anything else base64-encoded as `bytes`.
+#[derive(Deserialize)]
+struct Data {
+    text: Option<String>,
+}
+
+/// Search `roots` for lines matching the regular expression `pattern`.
+///
+/// Hidden files are searched while `.gitignore` and friends are honoured; callers filter the
+/// matches down to the files they scanned. Returns `None` when `rg` is not installed.
+pub fn search(
+    roots: &[PathBuf],
+    pattern: &str,
+    case_insensitive: bool,
+) -> Result<Option<Vec<RipgrepMatch>>> {
+    let output = match Command::new("rg")
+        .args(["--json", "--no-config", "--no-messages", "--hidden"])
+        .args(["--glob", "!.git"])
+        .arg(if case_insensitive {
+            "--ignore-case"
+        } else {
+            "--case-sensitive"
+        })
+        .args(["--regexp", pattern, "--"])
+        .args(roots)
+        .output()
+    {
+        Ok(output) => output,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err).context("failed to run rg"),
+    };
+    // Exit status 1 means no matches; 2 with nothing on stderr means some files were unreadable.
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !matches!(output.status.code(), Some(0 | 1)) && !stderr.trim().is_empty() {
+        bail!("ripgrep search for '{pattern}' failed: {}", stderr.trim());
+    }
+    Ok(Some(
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(parse_match)
+            .collect(),
+    ))
+}
+
+/// Parse one line of `rg --json` output, keeping only matches with UTF-8 paths and lines.
+fn parse_match(line: &str) -> Option<RipgrepMatch> {
+    let message: Message = serde_json::from_str(line).ok()?;
+    if message.kind != "match" {
+        return None;
+    }
+    let data = message.data?;
+    let text = data.lines.text?;
+    Some(RipgrepMatch {
+        path: PathBuf::from(data.path.text?),
+        line: data.line_number?,
+        text: text.trim_end_matches(['\n', '\r']).to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_match_messages_only() {
+        let output = [
+            r#"{"type":"begin","data":{"path":{"text":"/repo/src/lib.rs"}}}"#,
+            r#"{"type":"match","data":{"path":{"text":"/repo/src/lib.rs"},"lines":{"text":"    run();\r\n"},"line_number":3,"absolute_offset":20,"submatches":[{"match":{"text":"run("},"start":4,"end":8}]}}"#,
+            r#"{"type":"match","data":{"path":{"bytes":"L3JlcG8vZv9v"},"lines":{"text":"run();\n"},"line_number":1,"absolute_offset":0,"submatches":[]}}"#,
+            r#"{"type":"match","data":{"path":{"text":"/repo/bin.dat"},"lines":{"bytes":"cnVu/yg="},"line_number":7,"absolute_offset":0,"submatches":[]}}"#,
+            r#"{"type":"end","data":{"path":{"text":"/repo/src/lib.rs"},"binary_offset":null,"stats":{}}}"#,
+            r#"{"data":{"elapsed_total":{"human":"0.01s"},"stats":{}},"type":"summary"}"#,
+        ];
+        let matches: Vec<RipgrepMatch> = output.into_iter().filter_map(parse_match).collect();
+        assert_eq!(
+            matches,
+            vec![RipgrepMatch {
+                path: PathBuf::from("/repo/src/lib.rs"),
+                line: 3,
+                text: "    run();".to_string(),
+            }]
+        );
+    }
+}
@@ -0,0 +1,178 @@
+//! Token counts from provider count-tokens endpoints, used to calibrate local estimates.
+
+use anyhow::{Context, Result, anyhow, bail};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::infra::curl::CurlRequest;
+
+const OPENAI_ENDPOINT: &str = "https://api.openai.com/v1/responses/input_tokens";
+const ANTHROPIC_ENDPOINT: &str = "https://api.anthropic.com/v1/messages/count_tokens";
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// Provider whose count-tokens endpoint is queried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiProvider {
+    OpenAi,
+    Anthropic,
+}
+
+impl ApiProvider {
+    /// Environment variable holding the provider's API key.
+    pub fn key_variable(self) -> &'static str {
+        match self {
+            Self::OpenAi => "OPENAI_API_KEY",
+            Self::Anthropic => "ANTHROPIC_API_KEY",
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct CountResponse {
+    input_tokens: usize,
+}
+
+/// Counts tokens for one provider model through its API.
+#[derive(Debug)]
+pub struct TokenCountClient {
+    provider: ApiProvider,
+    model: String,
+    api_key: String,
+    /// Tokens the request framing adds around the text, measured on first use.
+    overhead: Option<usize>,
+}
+
+impl TokenCountClient {
+    /// Client for `model` authenticated with the provider's API key from the environment.
+    pub fn from_env(provider: ApiProvider, model: impl Into<String>) -> Result<Self> {
+        let variable = provider.key_variable();
+        let api_key = std::env::var(variable)
+            .ok()
+            .filter(|key| !key.trim().is_empty())
+            .ok_or_else(|| anyhow!("set {variable} to count tokens with the provider API"))?;
+        Ok(Self {
+            provider,
+            model: model.into(),
+            api_key,
+            overhead: None,
+        })
+    }
+
+    /// Tokens the provider counts for `text` sent as a single user message, without the framing
+    /// the request itself adds.
+    pub fn count(&mut self, text: &str) -> Result<usize> {
+        let overhead = match self.overhead {
+            Some(overhead) => overhead,
+            None => {
+                // A one-character message costs one token plus the framing.
+                let overhead = self.request(".")?.saturating_sub(1);
+                *self.overhead.insert(overhead)
+            }
+        };
+        Ok(self.request(text)?.saturating_sub(overhead))
+    }
+
+    fn request(&self, text: &str) -> Result<usize> {
+        let output = self.count_request(text).send()?;
+        if !output.status.success() {
+            bail!(
+                "token count request for {} failed: {}",
+                self.model,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        parse_count(&output.stdout)
+            .with_context(|| format!("unexpected token count response for {}", self.model))
+    }
+
+    /// The count-tokens request for `text`; the API key goes in curl's stdin config.
+    fn count_request(&self, text: &str) -> CurlRequest {
+        match self.provider {
+            ApiProvider::OpenAi => {
+                let body = json!({ "model": self.model, "input": text });
+                CurlRequest::post(OPENAI_ENDPOINT, body.to_string())
+                    .header("Content-Type: application/json")
+                    .header(format!("Authorization: Bearer {}", self.api_key))
+            }
+            ApiProvider::Anthropic => {
+                let body = json!({
+                    "model": self.model,
+                    "messages": [{ "role": "user", "content": text }],
+                });
+                CurlRequest::post(ANTHROPIC_ENDPOINT, body.to_string())
+                    .header("Content-Type: application/json")
+                    .header(format!("x-api-key: {}", self.api_key))
+                    .header(format!("anthropic-version: {ANTHROPIC_VERSION}"))
+            }
+        }
+    }
+}
+
+fn parse_count(body: &[u8]) -> serde_json::Result<usize> {
+    serde_json::from_slice::<CountResponse>(body).map(|response| response.input_tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client(provider: ApiProvider, model: &str) -> TokenCountClient {
+        TokenCountClient {
+            provider,
+            model: model.to_string(),
+            api_key: "sk-secret".to_string(),
+            overhead: None,
+        }
+    }
+
+    #[test]
+    fn requests_keep_the_api_key_off_the_command_line() {
+        let openai = client(ApiProvider::OpenAi, "gpt-4o").count_request("say \"hi\"");
+        assert!(
+            openai.args().iter().all(|arg| !arg.contains("sk-secret")),
+            "{:?}",
+            openai.args()
+        );
+        assert_eq!(
+            openai.args().last().map(String::as_str),
+            Some(OPENAI_ENDPOINT)
+        );
+        assert_eq!(
+            openai.config(),
+            "header = \"Content-Type: application/json\"\n\
+             header = \"Authorization: Bearer sk-secret\"\n\
+             request = \"POST\"\n\
+             data-binary = \"{\\\"input\\\":\\\"say \\\\\\\"hi\\\\\\\"\\\",\\\"model\\\":\\\"gpt-4o\\\"}\"\n"
+        );
+
+        let anthropic = client(ApiProvider::Anthropic, "claude-sonnet-4-5").count_request("text");
+        assert!(
+            anthropic
+                .args()
+                .iter()
+                .all(|arg| !arg.contains("sk-secret"))
+        );
+        assert_eq!(
+            anthropic.args().last().map(String::as_str),
+            Some(ANTHROPIC_ENDPOINT)
+        );
+        let config = anthropic.config();
+        assert!(
+            config.contains("header = \"x-api-key: sk-secret\"\n"),
+            "{config}"
+        );
+        assert!(config.contains(&format!(
+            "header = \"anthropic-version: {ANTHROPIC_VERSION}\"\n"
+        )));
+        assert!(config.contains(r#"\"messages\":[{\"content\":\"text\",\"role\":\"user\"}]"#));
+    }
+
+    #[test]
+    fn responses_parse_to_the_input_token_count() {
+        assert_eq!(
+            parse_count(br#"{"input_tokens": 42, "object": "response.input_tokens"}"#).unwrap(),
+            42
+        );
+        assert!(parse_count(br#"{"error": {"message": "invalid model"}}"#).is_err());
+    }
+}
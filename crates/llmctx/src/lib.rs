@@ -1,8 +1,18 @@
+//! Build LLM context bundles from a workspace: scan files, select ranges, estimate tokens, and
+//! export the result.
+//!
+//! The [`prelude`] is the supported entry point for embedding; the other modules may change
+//! between releases.
+
 pub mod app;
 pub mod domain;
 pub mod infra;
+pub mod prelude;
 pub mod ui;
 
+/// Install the process-wide tracing subscriber used by the `llmctx` binary.
+///
+/// Libraries embedding llmctx should not call this; see [`prelude::Pipeline`] instead.
 pub fn init() {
     tracing_subscriber::fmt::init();
 }
@@ -1,74 +1,780 @@
-use std::path::PathBuf;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use anyhow::{Context, Result, anyhow};
 use clap::{Args as ClapArgs, Parser, Subcommand, ValueHint};
+use time::OffsetDateTime;
 
-use llmctx::app::export::{ExportFormat, ExportOptions, Exporter};
-use llmctx::app::selection::SelectionManager;
-use llmctx::app::tokens::TokenEstimator;
+use llmctx::app::attachments::load_attachment;
+use llmctx::app::export::{
+    CompareMode, ExportFormat, ExportOptions, Exporter, GroupBy, expand_filename,
+    numbered_output_path, unused_output_path,
+};
+use llmctx::app::manifest::Replay;
+use llmctx::app::recipes::{RecipeSelections, apply_recipe_options};
+use llmctx::app::review::ReviewSelections;
+use llmctx::app::scan::{FileClass, Scanner, WorkspaceRoot};
+use llmctx::app::search::{Search, SearchBackend};
+use llmctx::app::selection::{
+    self, SelectionIssue, SelectionManager, SelectionSpec, SelectionWarning,
+};
+use llmctx::app::semantic::{SemanticIndex, SemanticIndexStore, default_embedder};
+use llmctx::app::session::SessionStore;
+use llmctx::app::snippets::load_snippets;
+use llmctx::app::strip::StripOptions;
+use llmctx::app::suggest::Suggester;
+use llmctx::app::tokens::{BundleTokenSummary, TokenEstimator, TokenModel};
+use llmctx::domain::errors::DomainError;
+use llmctx::domain::model::SelectionItem;
 use llmctx::infra::config::Config;
+use llmctx::infra::fs::{RealFs, Vfs};
+use llmctx::infra::git::{self, GitFileSource};
+use llmctx::infra::github::{self, PullRequestRef};
+use llmctx::infra::token_api::TokenCountClient;
 
-fn main() -> Result<()> {
+fn main() {
     llmctx::init();
 
     let cli = Cli::parse();
-    match cli.command.unwrap_or_default() {
-        Command::Export(args) => run_export(args),
-        Command::Tui => run_tui(),
+    let result = match cli.command {
+        Some(Command::Export(args)) => run_export(*args, cli.roots),
+        Some(Command::Pr(args)) => run_pr(args, cli.roots),
+        Some(Command::Run(args)) => run_recipe(args, cli.roots),
+        Some(Command::Tokens(args)) => run_tokens(args, cli.roots),
+        Some(Command::Index(args)) => run_index(args, cli.roots),
+        Some(Command::Suggest(args)) => run_suggest(args, cli.roots),
+        Some(Command::Script(args)) => run_script(args, cli.roots),
+        Some(Command::Tui) => run_tui(Vec::new(), cli.roots),
+        None => run_tui(cli.paths, cli.roots),
+    };
+    if let Err(err) = result {
+        eprintln!("Error: {err:?}");
+        if let Some(domain) = DomainError::find(&err) {
+            eprintln!("\nhint: {}", domain.hint());
+        }
+        std::process::exit(1);
+    }
+}
+
+fn run_tui(paths: Vec<PathBuf>, roots: Vec<PathBuf>) -> Result<()> {
+    let mut app = llmctx::ui::app::UiApp::new()?
+        .with_roots(roots)
+        .with_files(paths);
+    app.run()
+}
+
+fn run_script(args: ScriptArgs, roots: Vec<PathBuf>) -> Result<()> {
+    let script = if args.file == Path::new("-") {
+        std::io::read_to_string(std::io::stdin()).context("failed to read script from stdin")?
+    } else {
+        std::fs::read_to_string(&args.file)
+            .with_context(|| format!("failed to read script {}", args.file.display()))?
+    };
+    let mut app = llmctx::ui::app::UiApp::new()?.with_roots(roots);
+    app.bootstrap()?;
+    app.run_script(&script, |line, message| eprintln!("{line}: {message}"))
+}
+
+fn run_pr(args: PrArgs, roots: Vec<PathBuf>) -> Result<()> {
+    let start = match roots.first() {
+        Some(root) => root.clone(),
+        None => std::env::current_dir().context("unable to determine working directory")?,
+    };
+    let metadata = git::metadata_for_path(&start);
+    let reference = PullRequestRef::parse(
+        &args.target,
+        metadata
+            .as_ref()
+            .and_then(|metadata| metadata.remote.as_ref()),
+    )?;
+    let pull = github::fetch_pull_request(&reference)
+        .with_context(|| format!("failed to fetch pull request #{}", reference.number))?;
+
+    let checkout = metadata
+        .as_ref()
+        .map(|metadata| metadata.root.clone())
+        .unwrap_or(start);
+    let checkout = checkout.canonicalize().unwrap_or(checkout);
+    let selections = ReviewSelections::from_pull_request(&pull, &checkout);
+    if selections.items.is_empty() {
+        return Err(anyhow!(
+            "none of the files changed by {} exist in {}",
+            pull.url,
+            checkout.display()
+        ));
+    }
+
+    let mut origin = format!("PR #{}", pull.number);
+    if !selections.missing.is_empty() {
+        origin.push_str(&format!(
+            "; {} changed file(s) not found locally",
+            selections.missing.len()
+        ));
+    }
+    let local_head = metadata.and_then(|metadata| metadata.commit);
+    if local_head.as_deref() != Some(pull.head_sha.as_str()) {
+        let short = &pull.head_sha[..pull.head_sha.len().min(7)];
+        origin.push_str(&format!("; check out {short} for exact line ranges"));
+    }
+
+    let mut app = llmctx::ui::app::UiApp::new()?
+        .with_roots(roots)
+        .with_selections(selections.items, origin);
+    app.run()
+}
+
+fn run_recipe(args: RunArgs, roots: Vec<PathBuf>) -> Result<()> {
+    let mut roots = workspace_roots(&roots)?;
+    if roots.is_empty() {
+        let cwd = std::env::current_dir().context("unable to determine working directory")?;
+        roots = WorkspaceRoot::from_paths(&[cwd]);
+    }
+    let config = Config::load_for(&roots[0].path)?;
+    let Some(name) = args.recipe else {
+        if config.recipes.is_empty() {
+            println!("No recipes configured; add [recipes.<name>] to .llmctx/config.toml");
+        }
+        for (name, recipe) in &config.recipes {
+            match &recipe.description {
+                Some(description) => println!("{name}\t{description}"),
+                None => println!("{name}"),
+            }
+        }
+        return Ok(());
+    };
+    let recipe = config.recipes.get(&name).ok_or_else(|| {
+        let known: Vec<&str> = config.recipes.keys().map(String::as_str).collect();
+        anyhow!("unknown recipe '{name}' (configured: {})", known.join(", "))
+    })?;
+
+    let selections = RecipeSelections::resolve(recipe, &roots, &config)?;
+    for spec in &selections.unmatched {
+        eprintln!("warning: recipe selection '{spec}' matched no files");
+    }
+    if selections.items.is_empty() {
+        return Err(anyhow!("recipe '{name}' selected no files"));
+    }
+
+    let mut manager = SelectionManager::new();
+    manager.set_model(
+        recipe
+            .model
+            .clone()
+            .unwrap_or_else(|| config.defaults.model().to_string()),
+    );
+    for item in selections.items {
+        manager.add_item(item);
+    }
+    let warnings = if args.check {
+        selection_warnings(Arc::new(RealFs), &manager, &roots, &config)?
+    } else {
+        Vec::new()
+    };
+    drop_deleted(&mut manager, &warnings);
+    manager.normalize(config.export.merge_gap_lines());
+    let estimator = TokenEstimator::from_config(&config);
+    let summary = manager.summarize_tokens(&estimator)?;
+
+    let mut options = ExportOptions::from_config(&config);
+    if args.check {
+        return check_bundle(&warnings, summary.as_ref(), check_limit(&options, &config));
+    }
+    apply_recipe_options(recipe, &mut options, &roots[0].path)?;
+    if args.output.is_some() {
+        options.output_path = args.output;
+    }
+    options.roots = roots;
+    options.copy_to_clipboard = args.copy;
+
+    let exporter = Exporter::new()?;
+    let result = exporter.export(&manager.to_bundle(), summary.as_ref(), &options)?;
+    if args.print || (result.output_path.is_none() && !args.copy) {
+        println!("{}", result.rendered);
+    }
+    if let Some(path) = &result.output_path {
+        eprintln!("Wrote recipe '{name}' to {}", path.display());
+    }
+    Ok(())
+}
+
+fn run_tokens(args: TokensArgs, roots: Vec<PathBuf>) -> Result<()> {
+    let mut roots = workspace_roots(&roots)?;
+    if roots.is_empty() {
+        let cwd = std::env::current_dir().context("unable to determine working directory")?;
+        roots = WorkspaceRoot::from_paths(&[cwd]);
+    }
+    let root = roots[0].path.clone();
+    let config = Config::load_for(&root)?;
+
+    let mut manager = SelectionManager::new();
+    for spec in &args.selections {
+        let path = WorkspaceRoot::resolve(&roots, &spec.path);
+        manager.add_selection(path, spec.range, spec.note.clone());
+    }
+    for path in &args.paths {
+        manager.add_selection(WorkspaceRoot::resolve(&roots, path), None, None);
     }
+    // Without explicit selections, measure the bundle saved by the TUI.
+    let store = SessionStore::new(&root);
+    if manager.is_empty() && store.path().exists() {
+        let replay = Replay::load(store.path())?;
+        for record in replay.selections.iter().cloned() {
+            let path = replay.resolve(&record.item.path, |path| {
+                WorkspaceRoot::resolve(&roots, path)
+            });
+            let mut item = record.into_selection_item();
+            item.path = path;
+            manager.add_item(item);
+        }
+    }
+    if manager.is_empty() {
+        return Err(anyhow!(
+            "nothing to measure: pass paths or --select, or save a session in the TUI"
+        ));
+    }
+    manager.set_model(
+        args.model
+            .clone()
+            .unwrap_or_else(|| config.defaults.model().to_string()),
+    );
+    let mut estimator = TokenEstimator::from_config(&config);
+    estimator.set_vfs(file_source(args.at_rev.as_deref(), &root)?);
+
+    if !args.calibrate {
+        let Some(summary) = manager.summarize_tokens(&estimator)? else {
+            return Ok(());
+        };
+        for estimate in &summary.items {
+            println!(
+                "{:>8}  {}",
+                estimate.tokens,
+                selection_label(&estimate.item, &root)
+            );
+        }
+        println!("{:>8}  total ({})", summary.total_tokens, summary.model);
+        return Ok(());
+    }
+
+    let model: TokenModel = manager
+        .model()
+        .unwrap_or_default()
+        .parse()
+        .map_err(|err| anyhow!("{err}"))?;
+    let (provider, default_api_model) = model.api_model().ok_or_else(|| {
+        anyhow!("{model} has no provider token count API; pass --model with an OpenAI or Anthropic model")
+    })?;
+    let api_model = args.api_model.as_deref().unwrap_or(default_api_model);
+    let mut client = TokenCountClient::from_env(provider, api_model)?;
+    let report = estimator.calibrate(&manager.to_bundle(), |text| client.count(text))?;
+
+    println!(
+        "{:>8}  {:>8}  {:>7}  selection",
+        "local", "provider", "delta"
+    );
+    for sample in &report.samples {
+        println!(
+            "{:>8}  {:>8}  {:>+6.1}%  {}",
+            sample.estimated,
+            sample.actual,
+            sample.delta_percent(),
+            selection_label(&sample.item, &root)
+        );
+    }
+    println!(
+        "{:>8}  {:>8}  {:>7}  total ({model} via {api_model})",
+        report.estimated_total(),
+        report.actual_total(),
+        ""
+    );
+
+    let (Some(suggested), Some(key)) = (report.suggested_multiplier(), model.multiplier_key())
+    else {
+        return Ok(());
+    };
+    println!(
+        "\nSuggested tokens.{key} = {suggested:.2} (currently {:.2})",
+        report.multiplier
+    );
+    if args.write {
+        let value = (f64::from(suggested) * 100.0).round() / 100.0;
+        let path = Config::write_user_setting("tokens", key, value)?;
+        println!("Wrote tokens.{key} to {}", path.display());
+    } else {
+        println!("Pass --write to save it to the user configuration.");
+    }
+    Ok(())
 }
 
-fn run_tui() -> Result<()> {
-    let mut app = llmctx::ui::app::UiApp::default();
+/// `path[:start-end]`, relative to `root` when inside it.
+fn selection_label(item: &SelectionItem, root: &Path) -> String {
+    let path = item.path.strip_prefix(root).unwrap_or(&item.path);
+    match item.range {
+        Some((start, end)) => format!("{}:{start}-{end}", path.display()),
+        None => path.display().to_string(),
+    }
+}
+
+fn run_index(args: IndexArgs, roots: Vec<PathBuf>) -> Result<()> {
+    let mut roots = workspace_roots(&roots)?;
+    if roots.is_empty() {
+        let cwd = std::env::current_dir().context("unable to determine working directory")?;
+        roots = WorkspaceRoot::from_paths(&[cwd]);
+    }
+    let root = roots[0].path.clone();
+    let config = Config::load_for(&root)?;
+    let scan = Scanner::new().scan_roots(&roots, &config)?;
+    if let Some(limit) = scan.truncated {
+        eprintln!("warning: {}", limit.describe());
+    }
+
+    let store = SemanticIndexStore::new(&root);
+    let mut index = match store.load()? {
+        Some(index) if !args.rebuild => index,
+        _ => SemanticIndex::default(),
+    };
+    let embedder = default_embedder()?;
+    let update = index.update(&root, &scan.files, embedder.as_ref())?;
+    store.save(&index)?;
+    println!(
+        "Indexed {} file(s) in {} {} chunk(s): {} added, {} updated, {} removed, {} unchanged ({})",
+        index.files.len(),
+        embedder.kind(),
+        index.chunk_count(),
+        update.added,
+        update.updated,
+        update.removed,
+        update.unchanged,
+        store.path().display()
+    );
+    Ok(())
+}
+
+fn run_suggest(args: SuggestArgs, cli_roots: Vec<PathBuf>) -> Result<()> {
+    let mut roots = workspace_roots(&cli_roots)?;
+    if roots.is_empty() {
+        let cwd = std::env::current_dir().context("unable to determine working directory")?;
+        roots = WorkspaceRoot::from_paths(&[cwd]);
+    }
+    let root = roots[0].path.clone();
+    let config = Config::load_for(&root)?;
+    let scan = Scanner::new().scan_roots(&roots, &config)?;
+    if let Some(limit) = scan.truncated {
+        eprintln!("warning: {}", limit.describe());
+    }
+
+    let backend: SearchBackend = config.search.backend().parse()?;
+    let search = Search::new(backend);
+    let estimator = TokenEstimator::from_config(&config);
+    let index = SemanticIndexStore::new(&root).load()?;
+    let mut suggester = Suggester::new(&search, &estimator);
+    match &index {
+        Some(index) => suggester = suggester.with_semantic_index(index),
+        None => eprintln!("note: run `llmctx index` to also rank files by similarity"),
+    }
+    let budget = args.budget.unwrap_or(estimator.token_budget() as usize);
+    let paths: Vec<PathBuf> = roots.iter().map(|root| root.path.clone()).collect();
+    let suggestions = suggester.suggest(&args.task, &scan.files, &paths, budget, args.limit)?;
+    if suggestions.items.is_empty() {
+        return Err(anyhow!("nothing in the workspace matched '{}'", args.task));
+    }
+
+    if args.print {
+        for item in &suggestions.items {
+            let reasons: Vec<String> = item.reasons.iter().map(ToString::to_string).collect();
+            println!(
+                "{}\t{} tokens\t{}",
+                item.display_path,
+                item.tokens,
+                reasons.join("; ")
+            );
+        }
+        let budget = match suggestions.budget {
+            0 => "no budget".to_string(),
+            budget => format!("budget {budget}"),
+        };
+        eprintln!(
+            "{} file(s), {} tokens ({budget}){}",
+            suggestions.items.len(),
+            suggestions.total_tokens(),
+            if suggestions.over_budget > 0 {
+                format!("; {} more did not fit", suggestions.over_budget)
+            } else {
+                String::new()
+            }
+        );
+        return Ok(());
+    }
+
+    let mut app = llmctx::ui::app::UiApp::new()?
+        .with_roots(cli_roots)
+        .with_suggestions(suggestions);
     app.run()
 }
 
-fn run_export(args: ExportArgs) -> Result<()> {
-    let mut config = Config::load()?;
+/// Remove selections of the file classes excluded by `--no-tests`, `--no-generated`, and
+/// `--no-vendored`, noting each on stderr.
+fn drop_excluded_classes(
+    vfs: &dyn Vfs,
+    manager: &mut SelectionManager,
+    args: &ExportArgs,
+    roots: &[WorkspaceRoot],
+) {
+    let excluded: Vec<FileClass> = [
+        (args.no_tests, FileClass::Test),
+        (args.no_generated, FileClass::Generated),
+        (args.no_vendored, FileClass::Vendored),
+    ]
+    .into_iter()
+    .filter_map(|(flag, class)| flag.then_some(class))
+    .collect();
+    if excluded.is_empty() {
+        return;
+    }
+    let paths: BTreeSet<PathBuf> = manager
+        .items()
+        .iter()
+        .map(|item| item.path.clone())
+        .collect();
+    for path in paths {
+        let class = FileClass::of_file(vfs, &path, roots);
+        if excluded.contains(&class) {
+            manager.remove_selection(&path, None);
+            eprintln!(
+                "Left out {} ({})",
+                path.display(),
+                class.label().unwrap_or_default()
+            );
+        }
+    }
+}
+
+fn workspace_roots(roots: &[PathBuf]) -> Result<Vec<WorkspaceRoot>> {
+    let roots = roots
+        .iter()
+        .map(|root| {
+            root.canonicalize()
+                .with_context(|| format!("invalid root {}", root.display()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(WorkspaceRoot::from_paths(&roots))
+}
+
+fn run_export(args: ExportArgs, roots: Vec<PathBuf>) -> Result<()> {
+    let roots = workspace_roots(&roots)?;
+    let mut config = match roots.first() {
+        Some(root) => Config::load_for(&root.path)?,
+        None => Config::load()?,
+    };
     if let Some(path) = &args.config {
         let overlay = Config::load_from_path(path)
             .with_context(|| format!("failed to load configuration from {}", path.display()))?;
         config = config.merge_with(overlay);
     }
 
+    let replay = args
+        .from_manifest
+        .as_deref()
+        .map(Replay::load)
+        .transpose()?;
+    let vfs = match roots.first() {
+        Some(root) => file_source(args.at_rev.as_deref(), &root.path)?,
+        None => {
+            let cwd = std::env::current_dir().context("unable to determine working directory")?;
+            file_source(args.at_rev.as_deref(), &cwd)?
+        }
+    };
     let selections = build_selection_manager(&args)?;
-    if selections.is_empty() {
+    if selections.is_empty()
+        && replay
+            .as_ref()
+            .is_none_or(|replay| replay.selections.is_empty())
+    {
         return Err(anyhow!("at least one selection must be provided"));
     }
 
     let mut manager = SelectionManager::new();
     let model = args
         .model
+        .clone()
+        .or_else(|| replay.as_ref().and_then(|replay| replay.model.clone()))
         .unwrap_or_else(|| config.defaults.model().to_string());
     manager.set_model(model);
+    if let Some(replay) = &replay {
+        warn_replay_drift(replay, &config, &roots);
+        for record in &replay.selections {
+            let recorded = &record.item.path;
+            let path = replay.resolve(recorded, |path| WorkspaceRoot::resolve(&roots, path));
+            if !vfs.exists(&path) {
+                eprintln!("warning: {} no longer exists", recorded.display());
+            } else if vfs.is_local() && record.is_stale(vfs.as_ref(), &path) {
+                eprintln!(
+                    "warning: {} changed since the manifest was written",
+                    recorded.display()
+                );
+            }
+            let mut item = record.clone().into_selection_item();
+            item.path = path;
+            manager.add_item(item);
+        }
+    }
     for selection in selections {
-        manager.add_selection(selection.path, selection.range, selection.note);
+        let path = WorkspaceRoot::resolve(&roots, &selection.path);
+        // Check mode reports bad ranges together with the other problems below.
+        if !args.force && !args.check {
+            selection::check_bounds(vfs.as_ref(), &path, selection.range)?;
+        }
+        manager.add_selection(path, selection.range, selection.note);
+    }
+    drop_excluded_classes(vfs.as_ref(), &mut manager, &args, &roots);
+    if manager.items().is_empty() {
+        return Err(anyhow!(
+            "every selection was left out by --no-tests/--no-generated/--no-vendored"
+        ));
     }
+    let warnings = selection_warnings(vfs.clone(), &manager, &roots, &config)?;
+    if args.check {
+        drop_deleted(&mut manager, &warnings);
+    } else {
+        refuse_selection_warnings(&warnings, args.force)?;
+    }
+    manager.normalize(config.export.merge_gap_lines());
 
-    let estimator = TokenEstimator::from_config(&config);
+    let mut strip = args
+        .strip
+        .unwrap_or_else(|| StripOptions::from_config(&config));
+    strip.tab_width = args.tab_width.unwrap_or(config.export.tab_width());
+    let mut estimator = TokenEstimator::from_config(&config);
+    estimator.set_strip(strip);
+    estimator.set_vfs(vfs.clone());
     let summary = manager.summarize_tokens(&estimator)?;
 
     let mut options = ExportOptions::from_config(&config);
+    options.strip = strip;
+    if let Some(replay) = &replay {
+        if let Some(format) = replay
+            .format
+            .as_deref()
+            .and_then(|format| format.parse().ok())
+        {
+            options.format = format;
+        }
+        if let Some(template) = &replay.template {
+            options.template = template.clone();
+        }
+    }
     if let Some(format) = args.format {
         options.format = format;
     }
     if let Some(template) = args.template {
         options.template = template;
     }
-    options.output_path = args.output.clone();
+    if args.manifest {
+        options.include_manifest = true;
+    }
+    if let Some(history) = args.history {
+        options.history_commits = history;
+    }
+    if let Some(context) = args.context {
+        options.context_lines = context;
+    }
+    if let Some(revision) = args.compare {
+        options.compare = Some(revision);
+    }
+    if let Some(mode) = args.compare_mode {
+        options.compare_mode = mode;
+    }
+    if let Some(group_by) = args.group_by {
+        options.group_by = group_by;
+    }
+    if args.enforce_budget && config.defaults.token_budget() > 0 {
+        options.token_limit = Some(config.defaults.token_budget() as usize);
+    }
+    if let Some(max_tokens) = args.max_tokens {
+        options.token_limit = Some(max_tokens);
+    }
+    if args.check {
+        return check_bundle(&warnings, summary.as_ref(), check_limit(&options, &config));
+    }
+    options.output_path = match (&args.output_dir, &args.filename) {
+        (Some(dir), Some(pattern)) => {
+            let pattern = if pattern == "config" {
+                config.export.filename()
+            } else {
+                pattern.clone()
+            };
+            let root = roots
+                .first()
+                .map_or(Path::new("."), |root| root.path.as_path());
+            let name = expand_filename(&pattern, root, options.format, OffsetDateTime::now_utc())?;
+            Some(unused_output_path(dir.join(name))?)
+        }
+        (Some(dir), None) => Some(numbered_output_path(
+            dir,
+            "context",
+            options.format.extension(),
+        )?),
+        (None, _) => args.output.clone(),
+    };
+    let workspace = match roots.first() {
+        Some(root) => Config::workspace_dir(&root.path),
+        None => Config::workspace_dir(
+            &std::env::current_dir().context("unable to determine working directory")?,
+        ),
+    };
+    let snippets: Vec<String> = replay
+        .iter()
+        .flat_map(|replay| replay.snippets.clone())
+        .chain(config.export.snippets())
+        .chain(args.snippets)
+        .collect();
+    options.snippets = load_snippets(&workspace, &snippets)?;
+    options.overwrite = args.force;
+    options.roots = roots;
     options.copy_to_clipboard = args.copy;
 
-    let exporter = Exporter::new()?;
-    let bundle = manager.to_bundle();
+    let exporter = Exporter::new()?.with_vfs(vfs);
+    let mut bundle = manager.to_bundle();
+    let replayed_attachments = replay.iter().flat_map(|replay| &replay.attachments);
+    bundle.attachments = replayed_attachments
+        .chain(&args.attachments)
+        .map(|source| load_attachment(source))
+        .collect::<Result<_>>()?;
     let result = exporter.export(&bundle, summary.as_ref(), &options)?;
 
     if args.print {
         println!("{}", result.rendered);
     }
+    if args.output_dir.is_some()
+        && let Some(path) = &result.output_path
+    {
+        eprintln!("Wrote {}", path.display());
+    }
 
     Ok(())
 }
 
+/// Problems with the selections in `vfs`, checked against the ignore rules of `roots` (or the
+/// working directory) without scanning them.
+fn selection_warnings(
+    vfs: Arc<dyn Vfs>,
+    manager: &SelectionManager,
+    roots: &[WorkspaceRoot],
+    config: &Config,
+) -> Result<Vec<SelectionWarning>> {
+    let roots = if roots.is_empty() {
+        let cwd = std::env::current_dir().context("unable to determine working directory")?;
+        WorkspaceRoot::from_paths(&[cwd.canonicalize().unwrap_or(cwd)])
+    } else {
+        roots.to_vec()
+    };
+    let rules = Scanner::new()
+        .with_vfs(vfs.clone())
+        .ignore_rules(&roots, config)?;
+    Ok(manager.validate(vfs.as_ref(), &rules))
+}
+
+/// Where selected files are read from: the working tree, or with `revision` the commit it names
+/// in the repository containing `start`.
+fn file_source(revision: Option<&str>, start: &Path) -> Result<Arc<dyn Vfs>> {
+    Ok(match revision {
+        Some(revision) => Arc::new(GitFileSource::open(start, revision)?),
+        None => Arc::new(RealFs),
+    })
+}
+
+/// Fail with the full list of selection problems unless `force` is set, which only warns.
+fn refuse_selection_warnings(warnings: &[SelectionWarning], force: bool) -> Result<()> {
+    if warnings.is_empty() {
+        return Ok(());
+    }
+    let list: Vec<String> = warnings
+        .iter()
+        .map(|warning| format!("  - {warning}"))
+        .collect();
+    if force {
+        eprintln!(
+            "warning: exporting despite selection problems:\n{}",
+            list.join("\n")
+        );
+        return Ok(());
+    }
+    Err(anyhow!(
+        "{} selection(s) need attention:\n{}\nfix them or pass --force to export anyway",
+        warnings.len(),
+        list.join("\n")
+    ))
+}
+
+/// Leave out selections of deleted files, which `--check` lists among the problems, so the
+/// estimate covers the files that remain.
+fn drop_deleted(manager: &mut SelectionManager, warnings: &[SelectionWarning]) {
+    for warning in warnings {
+        if warning.issue == SelectionIssue::Deleted {
+            manager.remove_selection(&warning.path, None);
+        }
+    }
+}
+
+/// Token limit for `--check`: the export's own limit, else a non-zero `defaults.token_budget`.
+fn check_limit(options: &ExportOptions, config: &Config) -> Option<usize> {
+    let budget = config.defaults.token_budget() as usize;
+    options.token_limit.or((budget > 0).then_some(budget))
+}
+
+/// `--check`: report every problem with the bundle without rendering it, failing when there are any.
+fn check_bundle(
+    warnings: &[SelectionWarning],
+    summary: Option<&BundleTokenSummary>,
+    limit: Option<usize>,
+) -> Result<()> {
+    let mut problems: Vec<String> = warnings.iter().map(ToString::to_string).collect();
+    if let (Some(summary), Some(limit)) = (summary, limit)
+        && let Err(err) = summary.check_limit(limit)
+    {
+        problems.push(err.to_string());
+    }
+    if !problems.is_empty() {
+        let list: Vec<String> = problems
+            .iter()
+            .map(|problem| format!("  - {problem}"))
+            .collect();
+        return Err(anyhow!(
+            "bundle check found {} problem(s):\n{}",
+            problems.len(),
+            list.join("\n")
+        ));
+    }
+
+    let (selections, tokens) = summary.map_or((0, 0), |summary| {
+        (summary.items.len(), summary.total_tokens)
+    });
+    match limit {
+        Some(limit) => println!("ok: {selections} selection(s), {tokens}/{limit} tokens"),
+        None => println!("ok: {selections} selection(s), {tokens} tokens (no budget set)"),
+    }
+    Ok(())
+}
+
+/// Warn when settings recorded in the manifest differ from the current environment.
+fn warn_replay_drift(replay: &Replay, config: &Config, roots: &[WorkspaceRoot]) {
+    if let Some(hash) = &replay.config_hash
+        && *hash != config.fingerprint()
+    {
+        eprintln!("warning: configuration differs from the one used for the original export");
+    }
+    if let Some(commit) = &replay.git_commit {
+        let start = roots
+            .first()
+            .map(|root| root.path.clone())
+            .unwrap_or_else(|| PathBuf::from("."));
+        let current = git::metadata_for_path(&start).and_then(|metadata| metadata.commit);
+        if current.as_ref() != Some(commit) {
+            eprintln!("warning: original export was made at commit {commit}");
+        }
+    }
+}
+
 fn build_selection_manager(args: &ExportArgs) -> Result<Vec<SelectionSpec>> {
     let mut selections = Vec::new();
 
@@ -91,20 +797,121 @@ fn build_selection_manager(args: &ExportArgs) -> Result<Vec<SelectionSpec>> {
 #[command(
     name = "llmctx",
     version,
-    about = "Curate and export context for LLM prompts"
+    about = "Curate and export context for LLM prompts",
+    args_conflicts_with_subcommands = true
 )]
 struct Cli {
     #[command(subcommand)]
     command: Option<Command>,
+    /// Open these files directly in the preview, preselected, without scanning the workspace.
+    #[arg(value_name = "PATH", value_hint = ValueHint::FilePath)]
+    paths: Vec<PathBuf>,
+    /// Workspace directory to scan instead of the current directory (repeatable).
+    #[arg(long = "root", value_name = "DIR", value_hint = ValueHint::DirPath, global = true)]
+    roots: Vec<PathBuf>,
 }
 
-#[derive(Subcommand, Default)]
+#[derive(Subcommand)]
 enum Command {
     /// Launch the interactive terminal UI.
-    #[default]
     Tui,
     /// Export selections without launching the UI.
-    Export(ExportArgs),
+    Export(Box<ExportArgs>),
+    /// Open the TUI with a GitHub pull request's changed hunks selected.
+    Pr(PrArgs),
+    /// Run a recipe defined under `[recipes]` in the configuration.
+    Run(RunArgs),
+    /// Estimate tokens per selection, or compare estimates with a provider's counts.
+    Tokens(TokensArgs),
+    /// Build or update the similarity index in `.llmctx/index/`.
+    Index(IndexArgs),
+    /// Propose files for a task description and review them in the TUI.
+    Suggest(SuggestArgs),
+    /// Run command palette commands from a file against the TUI's state, without a terminal.
+    Script(ScriptArgs),
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+struct ScriptArgs {
+    /// File with one palette command per line, such as `filter src`, `select-filtered`, or
+    /// `export out.md`; `-` reads standard input.
+    #[arg(value_name = "FILE", value_hint = ValueHint::FilePath)]
+    file: PathBuf,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+struct SuggestArgs {
+    /// What you are about to work on, e.g. "make retry backoff configurable".
+    task: String,
+    /// Token budget for the suggested files, 0 for no limit; defaults to `defaults.token_budget`.
+    #[arg(long)]
+    budget: Option<usize>,
+    /// Most files to suggest.
+    #[arg(long, default_value_t = 20)]
+    limit: usize,
+    /// Print the suggestions instead of opening the TUI.
+    #[arg(long)]
+    print: bool,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+struct IndexArgs {
+    /// Discard the existing index and embed every file again.
+    #[arg(long)]
+    rebuild: bool,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+struct TokensArgs {
+    /// Compare local estimates with the provider's count-tokens API (needs OPENAI_API_KEY or
+    /// ANTHROPIC_API_KEY).
+    #[arg(long)]
+    calibrate: bool,
+    /// Save the suggested multiplier to the user configuration after calibrating.
+    #[arg(long, requires = "calibrate")]
+    write: bool,
+    /// Override the token model used for estimation.
+    #[arg(long)]
+    model: Option<String>,
+    /// Provider model identifier to count with, e.g. a newer snapshot of the same family.
+    #[arg(long, value_name = "ID", requires = "calibrate")]
+    api_model: Option<String>,
+    /// Explicit selections with optional ranges and notes (path[:start-end][#note]).
+    #[arg(long = "select", value_name = "SPEC", value_parser = SelectionSpec::parse)]
+    selections: Vec<SelectionSpec>,
+    /// Measure the files as of a git revision (tag, branch, or commit id) instead of the
+    /// working tree.
+    #[arg(long, value_name = "REV")]
+    at_rev: Option<String>,
+    /// Entire files to measure; defaults to the selections of the saved session.
+    #[arg(value_name = "PATH", value_hint = ValueHint::FilePath)]
+    paths: Vec<PathBuf>,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+struct RunArgs {
+    /// Recipe name; lists the configured recipes when omitted.
+    recipe: Option<String>,
+    /// Write to this path instead of the recipe's output.
+    #[arg(long, value_hint = ValueHint::FilePath)]
+    output: Option<PathBuf>,
+    /// Copy the rendered export to the system clipboard.
+    #[arg(long)]
+    copy: bool,
+    /// Print the rendered output to stdout (default when the recipe has no output path).
+    #[arg(long)]
+    print: bool,
+    /// Check the recipe's bundle against the token budget and for selection problems without
+    /// exporting it; exits non-zero when anything is wrong.
+    #[arg(long, conflicts_with_all = ["output", "copy", "print"])]
+    check: bool,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+struct PrArgs {
+    /// Pull request number (resolved against the origin remote) or URL.
+    #[arg(value_name = "NUMBER|URL")]
+    target: String,
 }
 
 #[derive(ClapArgs, Debug, Clone)]
@@ -112,15 +919,22 @@ struct ExportArgs {
     /// Additional configuration file layered on top of defaults.
     #[arg(long, value_hint = ValueHint::FilePath)]
     config: Option<PathBuf>,
-    /// Override the export format (markdown/plain).
+    /// Override the export format (markdown/plain, or a coding-agent handoff format).
     #[arg(long)]
     format: Option<ExportFormat>,
     /// Override the template name or path.
     #[arg(long)]
     template: Option<String>,
-    /// Path to write the export contents to.
+    /// Path to write the export contents to; an existing file is kept unless `--force` is given.
     #[arg(long, value_hint = ValueHint::FilePath)]
     output: Option<PathBuf>,
+    /// Write the export to the next numbered `context-NNN` file in DIR.
+    #[arg(long, value_name = "DIR", value_hint = ValueHint::DirPath, conflicts_with = "output")]
+    output_dir: Option<PathBuf>,
+    /// Name the `--output-dir` file with a pattern such as `{repo}-{branch}-{timestamp}.{ext}`
+    /// instead of numbering it (`config` uses `export.filename`).
+    #[arg(long, value_name = "PATTERN", requires = "output_dir")]
+    filename: Option<String>,
     /// Copy the rendered export to the system clipboard.
     #[arg(long)]
     copy: bool,
@@ -130,65 +944,75 @@ struct ExportArgs {
     /// Override the token model used for estimation.
     #[arg(long)]
     model: Option<String>,
+    /// Attach a task description: a GitHub issue/PR URL, a Jira issue URL, or a local file.
+    #[arg(long = "attach-issue", value_name = "URL|PATH")]
+    attachments: Vec<String>,
+    /// Append `.llmctx/snippets/NAME.md` after the selections, following any `export.snippets`
+    /// (repeatable).
+    #[arg(long = "with-snippet", value_name = "NAME")]
+    snippets: Vec<String>,
+    /// Regenerate selections and settings from an export manifest or session file.
+    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    from_manifest: Option<PathBuf>,
+    /// Append a manifest footer recording how to reproduce this export.
+    #[arg(long)]
+    manifest: bool,
+    /// List the last N commits touching each selected file (0 disables).
+    #[arg(long, value_name = "N")]
+    history: Option<usize>,
+    /// Include N lines of surrounding context above and below each ranged selection.
+    #[arg(long, value_name = "N")]
+    context: Option<usize>,
+    /// Also include the version at REV of every selection changed since, to reason about
+    /// regressions.
+    #[arg(long, value_name = "REV")]
+    compare: Option<String>,
+    /// Show compared selections as both versions or as a diff plus the current version
+    /// (default `export.compare_mode`).
+    #[arg(long, value_name = "MODE", requires = "compare")]
+    compare_mode: Option<CompareMode>,
+    /// Group selections by top-level directory or by language in the template context, as the
+    /// `grouped_context` template shows them (default `export.group_by`).
+    #[arg(long, value_name = "KEY")]
+    group_by: Option<GroupBy>,
+    /// Export the files as of a git revision (tag, branch, or commit id) instead of the working
+    /// tree.
+    #[arg(long, value_name = "REV")]
+    at_rev: Option<String>,
+    /// Fail instead of exporting when the bundle is over `defaults.token_budget`.
+    #[arg(long)]
+    enforce_budget: bool,
+    /// Fail instead of exporting when the bundle is estimated over N tokens.
+    #[arg(long, value_name = "N")]
+    max_tokens: Option<usize>,
+    /// Export even when selections point at deleted, ignored, or binary files or bad ranges,
+    /// and replace an existing `--output` file.
+    #[arg(long)]
+    force: bool,
+    /// Check the bundle against the token budget and for selection problems without rendering
+    /// it; exits non-zero when anything is wrong.
+    #[arg(long, conflicts_with_all = ["output", "output_dir", "copy", "print", "force"])]
+    check: bool,
+    /// Strip comments, blank runs, or trailing whitespace before rendering
+    /// (comments,blank-lines,trailing-whitespace, all, or none).
+    #[arg(long, value_name = "WHAT")]
+    strip: Option<StripOptions>,
+    /// Expand tabs to spaces at stops N columns apart (0 keeps tabs).
+    #[arg(long, value_name = "N")]
+    tab_width: Option<usize>,
+    /// Leave out test files and test data.
+    #[arg(long)]
+    no_tests: bool,
+    /// Leave out generated files such as protobuf output, lockfiles, and `@generated` files.
+    #[arg(long)]
+    no_generated: bool,
+    /// Leave out vendored third-party code.
+    #[arg(long)]
+    no_vendored: bool,
     /// Explicit selections with optional ranges and notes (path[:start-end][#note]).
-    #[arg(long = "select", value_name = "SPEC", value_parser = parse_selection_spec)]
+    #[arg(long = "select", value_name = "SPEC", value_parser = SelectionSpec::parse)]
     selections: Vec<SelectionSpec>,
     /// Entire file selections provided as positional arguments.
     #[arg(value_name = "PATH", value_hint = ValueHint::FilePath)]
     paths: Vec<PathBuf>,
 }
-
-#[derive(Debug, Clone)]
-struct SelectionSpec {
-    path: PathBuf,
-    range: Option<(usize, usize)>,
-    note: Option<String>,
-}
-
-fn parse_selection_spec(value: &str) -> Result<SelectionSpec, String> {
-    let (target, note) = match value.split_once('#') {
-        Some((target, note)) => (target.trim(), clean_note_string(note)),
-        None => (value.trim(), None),
-    };
-
-    if target.is_empty() {
-        return Err("selection specification is empty".to_string());
-    }
-
-    let mut path_part = target;
-    let mut range = None;
-
-    if let Some(colon_idx) = target.rfind(':') {
-        let (candidate_path, candidate_range) = target.split_at(colon_idx);
-        if let Some(parsed_range) = parse_range(&candidate_range[1..]) {
-            path_part = candidate_path;
-            range = Some(parsed_range);
-        }
-    }
-
-    if path_part.is_empty() {
-        return Err("selection path is empty".to_string());
-    }
-
-    Ok(SelectionSpec {
-        path: PathBuf::from(path_part),
-        range,
-        note,
-    })
-}
-
-fn parse_range(spec: &str) -> Option<(usize, usize)> {
-    let (start, end) = spec.split_once('-')?;
-    let start = start.trim().parse::<usize>().ok()?;
-    let end = end.trim().parse::<usize>().ok()?;
-    Some((start, end))
-}
-
-fn clean_note_string(note: &str) -> Option<String> {
-    let trimmed = note.trim();
-    if trimmed.is_empty() {
-        None
-    } else {
-        Some(trimmed.to_string())
-    }
-}
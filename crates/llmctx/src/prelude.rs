@@ -0,0 +1,21 @@
+//! Types most embedders need, importable in one line with `use llmctx::prelude::*;`.
+//!
+//! Start with [`Pipeline::builder`]; the re-exported building blocks are there for tools that
+//! drive scanning, selection, estimation, and export themselves.
+
+pub use crate::app::cancel::CancellationToken;
+pub use crate::app::export::{
+    CompareMode, ExportFormat, ExportOptions, ExportResult, Exporter, GroupBy,
+};
+pub use crate::app::pipeline::{Pipeline, PipelineBuilder};
+pub use crate::app::scan::{FileMetadata, ScanResult, Scanner, ScannerConfig, WorkspaceRoot};
+pub use crate::app::selection::{SelectionManager, SharedSelection};
+pub use crate::app::snippets::{Snippet, load_snippets};
+pub use crate::app::strip::StripOptions;
+pub use crate::app::tokens::{BundleTokenSummary, TokenEstimator, TokenModel};
+pub use crate::domain::errors::DomainError;
+pub use crate::domain::model::{ContextBundle, SelectionItem};
+pub use crate::infra::config::Config;
+pub use crate::infra::fs::{MemoryFs, RealFs, Vfs, VfsFile, VfsMetadata};
+pub use crate::infra::git::GitFileSource;
+pub use crate::infra::logging::dispatch as tracing_dispatch;
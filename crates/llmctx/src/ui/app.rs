@@ -1,19 +1,19 @@
 //! Application loop for the TUI.
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
-use std::io;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
 use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result, anyhow};
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
-use crossterm::execute;
-use crossterm::terminal::{
-    EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
-};
-use ratatui::backend::CrosstermBackend;
-use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::backend::Backend;
+use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Paragraph, Wrap};
@@ -21,128 +21,600 @@ use ratatui::{Frame, Terminal};
 use time::OffsetDateTime;
 use time::macros::format_description;
 
-use crate::app::export::{ExportOptions, Exporter};
+use crate::app::callgraph::{CallDirection, CallExpansion, SymbolIndex};
+use crate::app::cancel::CancellationToken;
+use crate::app::defaults::{ALWAYS_INCLUDE_FILE, AlwaysInclude};
+use crate::app::export::{
+    ExportFormat, ExportOptions, Exporter, PostAction, expand_filename, unused_output_path,
+};
+use crate::app::fit::{FitHint, fit_hints, strip_target};
+use crate::app::frecency::{FileUse, FrecencyIndex, FrecencyStore};
 use crate::app::preview::{PreviewSegment, PreviewService};
-use crate::app::scan::{ScanResult, Scanner, ScannerConfig};
-use crate::app::selection::SelectionManager;
-use crate::app::session::{SelectionRecord, SessionSnapshot, SessionStore};
-use crate::app::tokens::{BundleTokenSummary, TokenEstimator};
+use crate::app::recipes::{RecipeSelections, apply_recipe_options};
+use crate::app::scan::{
+    FileMetadata, IgnoreRules, ScanResult, Scanner, ScannerConfig, SkipReason, WorkspaceRoot,
+    native_path, slash_path,
+};
+use crate::app::search::{Search, SearchBackend, SearchHit, SearchResults, hit_selections};
+use crate::app::selection::{SelectionSpec, SharedSelection};
+use crate::app::semantic::SemanticIndexStore;
+use crate::app::session::{JumpMark, SelectionRecord, SessionSnapshot, SessionStore};
+use crate::app::snippets::load_snippets;
+use crate::app::stats::WorkspaceStats;
+use crate::app::suggest::Suggestions;
+use crate::app::tokens::{AggregateTokenEstimate, BundleTokenSummary, TokenEstimator, TokenModel};
+use crate::domain::errors::DomainError;
+use crate::domain::model::{SelectionItem, SelectionSource};
+use crate::infra::clipboard::ClipboardBackend;
 use crate::infra::config::Config;
+use crate::infra::documents::format_bytes;
+use crate::infra::fs::{RealFs, Vfs};
+use crate::infra::git;
+use crate::ui::chords::{Chord, ChordKey, ChordState};
+use crate::ui::clock::{Clock, SystemClock};
+use crate::ui::components::breadcrumb::{Breadcrumb, BreadcrumbState};
 use crate::ui::components::command_palette::{CommandPalette, CommandPaletteState};
-use crate::ui::components::file_tree::{FileTree, FileTreeState};
-use crate::ui::components::preview::Preview;
-use crate::ui::components::summary::Summary;
+use crate::ui::components::confirm::{Choice, ConfirmDialog, ConfirmState};
+use crate::ui::components::file_info::{FileInfo, FileInfoPopup};
+use crate::ui::components::file_tree::{DirectoryEstimate, FileTree, FileTreeState};
+use crate::ui::components::messages::{MessageCenter, MessagesPane, StatusLevel};
+use crate::ui::components::preview::{Preview, PreviewLayout, WhitespaceOptions};
+use crate::ui::components::recent_files::{RecentFilesPicker, RecentFilesState};
+use crate::ui::components::search_results::{SearchResultsPane, SearchResultsState};
+use crate::ui::components::suggestions::{SuggestionsPane, SuggestionsState};
+use crate::ui::components::summary::{Summary, SummaryView};
+use crate::ui::components::theme_picker::{ThemePicker, ThemePickerState};
+use crate::ui::components::which_key::WhichKeyPopup;
+use crate::ui::components::workspace_stats::WorkspaceStatsScreen;
+use crate::ui::layout::{MIN_HEIGHT, MIN_WIDTH, PaneLayout};
+use crate::ui::macros::{MacroKey, Macros};
+use crate::ui::prefetch::PrefetchWorker;
+use crate::ui::terminal::TerminalGuard;
 
 const TICK_RATE: Duration = Duration::from_millis(120);
+/// Columns moved per horizontal scroll step in the preview.
+const HORIZONTAL_STEP: isize = 4;
+/// Distance from the end of the loaded segment at which the next chunk is prefetched.
+const PREFETCH_MARGIN: usize = 40;
+/// Files offered by the `ctrl+r` picker.
+const RECENT_FILES_LIMIT: usize = 20;
+/// Chunks listed by `:similar`.
+const SEMANTIC_RESULTS: usize = 30;
+
+/// One input to [`UiApp::update`], the only way app state changes in response to the outside.
+///
+/// Each message carries the time it happened, so a recorded sequence replays the same way
+/// however fast it is fed back in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AppEvent {
+    /// A key was pressed at `at`.
+    Key { key: KeyEvent, at: Instant },
+    /// The terminal changed size.
+    Resize,
+    /// Time moved on to the given instant with no input, expiring chords and status messages.
+    Tick(Instant),
+    /// Text was pasted into the terminal in one piece.
+    Paste(String),
+}
 
 /// Primary entry point for running the interactive TUI.
 pub struct UiApp {
     config: Config,
+    /// Whether bootstrap loads the workspace's configuration over `config`; off once one is
+    /// injected through [`UiAppBuilder::config`].
+    load_config: bool,
     scanner: Scanner,
     scan: Option<ScanResult>,
+    /// Ignore rules of the scanned roots, for flagging selections they exclude.
+    ignore_rules: Option<IgnoreRules>,
     tree: FileTreeState,
     file_tree: FileTree,
-    preview_service: PreviewService,
+    preview_service: Arc<PreviewService>,
+    /// The focused half of the preview when it is split.
     preview: PreviewState,
-    selection: SelectionManager,
+    /// The other half of a split preview.
+    split: Option<PreviewState>,
+    /// Whether the focused half of a split preview is the right one.
+    split_active_right: bool,
+    /// Focus mode: the preview fills the screen and the other panes are hidden.
+    zen: bool,
+    preview_component: Preview,
+    /// Directory being picked from the path bar above the preview.
+    breadcrumb: BreadcrumbState,
+    breadcrumb_component: Breadcrumb,
+    selection: SharedSelection,
+    /// Selection revision the summary and tree last reflected.
+    shown_revision: u64,
     token_estimator: TokenEstimator,
     summary_component: Summary,
     last_summary: Option<BundleTokenSummary>,
     session_store: SessionStore,
+    /// Files previewed and selected across sessions, offered by the `ctrl+r` picker.
+    frecency: FrecencyIndex,
+    frecency_store: FrecencyStore,
+    recent_files: RecentFilesState,
+    recent_files_component: RecentFilesPicker,
+    /// Hits of the last `:search`, shown until dismissed.
+    search_results: SearchResultsState,
+    search_results_component: SearchResultsPane,
+    suggestions: SuggestionsState,
+    suggestions_component: SuggestionsPane,
     palette_state: CommandPaletteState,
     palette_component: CommandPalette,
+    /// Metadata popup opened with `i`; any key dismisses it.
+    file_info: Option<FileInfo>,
+    file_info_component: FileInfoPopup,
+    confirm: ConfirmState<ConfirmedAction>,
+    confirm_component: ConfirmDialog,
+    /// Tree filter as last saved or restored; the selection tracks its own changes.
+    saved_filter: String,
+    /// Jump marks by letter, saved with the session.
+    marks: BTreeMap<char, JumpMark>,
+    saved_marks: BTreeMap<char, JumpMark>,
+    /// `m` or `'` waiting for the mark's letter.
+    pending_mark: Option<MarkPrefix>,
+    /// Statistics screen opened with `:stats`; any key dismisses it.
+    workspace_stats: Option<WorkspaceStats>,
+    workspace_stats_component: WorkspaceStatsScreen,
+    theme_picker: ThemePickerState,
+    theme_picker_component: ThemePicker,
     exporter: Exporter,
     selected_paths: HashSet<String>,
+    /// Large files the user chose to preview and select despite the scan's size limit.
+    forced: HashSet<PathBuf>,
     path_lookup: HashMap<PathBuf, String>,
-    status: Option<StatusMessage>,
+    messages: MessageCenter,
+    messages_component: MessagesPane,
+    /// `.` repeat and `q<reg>`/`@<reg>` macros.
+    macros: Macros,
+    /// Multi-key sequences such as `g g` typed so far.
+    chords: ChordState,
+    which_key: WhichKeyPopup,
     focus: FocusTarget,
+    /// Files given on the command line; when set, the workspace walk and session restore are
+    /// skipped and these files open preselected in the preview.
+    quick_open: Vec<PathBuf>,
+    /// Selections handed over by the caller (e.g. PR review) and where they came from.
+    preselected: Option<(Vec<SelectionItem>, String)>,
+    /// Directories to scan instead of the working directory.
+    roots: Vec<PathBuf>,
+    workspace_roots: Vec<WorkspaceRoot>,
+    /// Whether the last frame had room for the summary pane.
+    summary_visible: bool,
+    /// Pane areas of the last frame, which size half-page moves.
+    layout: Option<PaneLayout>,
+    /// Vim-style count typed before a motion, e.g. the `10` in `10j`.
+    count: Option<usize>,
+    /// Set on resize so stale cells from the old size are wiped before the next draw.
+    needs_clear: bool,
     should_quit: bool,
+    /// Time of the message being handled, which chords and status messages read instead of the clock.
+    now: Instant,
+    /// Stamps the keys and ticks the event loop feeds to [`Self::update`].
+    clock: Arc<dyn Clock>,
+    /// Export to open once the event loop can hand the terminal to another program.
+    pending_open: Option<(PostAction, PathBuf)>,
+    /// Set while a script runs: exports skip the clipboard and never open another program.
+    headless: bool,
+    /// Cancels the searches and directory estimates running in the background.
+    background: CancellationToken,
+    /// Background searches and directory estimates whose results have not arrived.
+    background_jobs: usize,
+    directory_estimates_tx: Sender<(String, AggregateTokenEstimate)>,
+    directory_estimates_rx: Receiver<(String, AggregateTokenEstimate)>,
+    /// Loads the next preview chunk ahead of the cursor on one background thread.
+    prefetch: PrefetchWorker<PreviewSegment>,
+    stats_tx: Sender<WorkspaceStats>,
+    stats_rx: Receiver<WorkspaceStats>,
+    search_tx: Sender<Result<SearchResults>>,
+    search_rx: Receiver<Result<SearchResults>>,
+    expansion_tx: Sender<Vec<CallExpansion>>,
+    expansion_rx: Receiver<Vec<CallExpansion>>,
+    /// Where workspace files are read from: the local disk unless [`UiAppBuilder::vfs`] says
+    /// otherwise.
+    vfs: Arc<dyn Vfs>,
 }
 
-impl Default for UiApp {
-    fn default() -> Self {
-        Self {
-            config: Config::default(),
-            scanner: Scanner::new(),
+/// Assembles a [`UiApp`] with some of its services swapped out, e.g. for fakes in tests or
+/// for an application embedding the TUI.
+#[derive(Default)]
+pub struct UiAppBuilder {
+    config: Option<Config>,
+    scanner: Option<Scanner>,
+    clock: Option<Arc<dyn Clock>>,
+    clipboard: Option<Box<dyn ClipboardBackend>>,
+    vfs: Option<Arc<dyn Vfs>>,
+}
+
+impl UiAppBuilder {
+    /// Use `config` instead of loading the workspace's configuration files.
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Walk the workspace with `scanner`, e.g. one with its own cancellation token.
+    pub fn scanner(mut self, scanner: Scanner) -> Self {
+        self.scanner = Some(scanner);
+        self
+    }
+
+    /// Stamp keys and ticks with `clock` instead of the system clock.
+    pub fn clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Some(Arc::new(clock));
+        self
+    }
+
+    /// Copy exports, paths, and links to `clipboard` instead of the system clipboard.
+    pub fn clipboard(mut self, clipboard: impl ClipboardBackend + 'static) -> Self {
+        self.clipboard = Some(Box::new(clipboard));
+        self
+    }
+
+    /// Read the workspace from `vfs` instead of the local disk: scans, previews, token
+    /// estimates, exports, and session fingerprints all go through it.
+    ///
+    /// Sessions, exports, and the frecency index are still written to the local disk.
+    pub fn vfs(mut self, vfs: Arc<dyn Vfs>) -> Self {
+        self.vfs = Some(vfs);
+        self
+    }
+
+    pub fn build(self) -> Result<UiApp> {
+        let Self {
+            config,
+            scanner,
+            clock,
+            clipboard,
+            vfs,
+        } = self;
+        let clock = clock.unwrap_or_else(|| Arc::new(SystemClock));
+        let mut scanner = scanner.unwrap_or_default();
+        if let Some(vfs) = &vfs {
+            scanner = scanner.with_vfs(vfs.clone());
+        }
+        let vfs = vfs.unwrap_or_else(|| Arc::new(RealFs));
+        let mut exporter = Exporter::new()?.with_vfs(vfs.clone());
+        if let Some(clipboard) = clipboard {
+            exporter = exporter.with_clipboard(clipboard);
+        }
+        let (directory_estimates_tx, directory_estimates_rx) = mpsc::channel();
+        let (stats_tx, stats_rx) = mpsc::channel();
+        let (search_tx, search_rx) = mpsc::channel();
+        let (expansion_tx, expansion_rx) = mpsc::channel();
+        Ok(UiApp {
+            load_config: config.is_none(),
+            config: config.unwrap_or_default(),
+            scanner,
             scan: None,
+            ignore_rules: None,
             tree: FileTreeState::default(),
             file_tree: FileTree,
-            preview_service: PreviewService::new(),
+            preview_service: Arc::new(PreviewService::new().with_vfs(vfs.clone())),
             preview: PreviewState::default(),
-            selection: SelectionManager::new(),
+            split: None,
+            split_active_right: false,
+            zen: false,
+            preview_component: Preview::default(),
+            breadcrumb: BreadcrumbState::default(),
+            breadcrumb_component: Breadcrumb,
+            selection: SharedSelection::default(),
+            shown_revision: 0,
             token_estimator: TokenEstimator::default(),
             summary_component: Summary::new(),
             last_summary: None,
             session_store: SessionStore::new(PathBuf::from(".")),
+            frecency: FrecencyIndex::default(),
+            frecency_store: FrecencyStore::new("."),
+            recent_files: RecentFilesState::default(),
+            recent_files_component: RecentFilesPicker,
+            search_results: SearchResultsState::default(),
+            search_results_component: SearchResultsPane,
+            suggestions: SuggestionsState::default(),
+            suggestions_component: SuggestionsPane,
             palette_state: CommandPaletteState::default(),
             palette_component: CommandPalette,
-            exporter: Exporter::new().expect("exporter available"),
+            file_info: None,
+            file_info_component: FileInfoPopup,
+            confirm: ConfirmState::default(),
+            confirm_component: ConfirmDialog,
+            saved_filter: String::new(),
+            marks: BTreeMap::new(),
+            saved_marks: BTreeMap::new(),
+            pending_mark: None,
+            workspace_stats: None,
+            workspace_stats_component: WorkspaceStatsScreen,
+            theme_picker: ThemePickerState::default(),
+            theme_picker_component: ThemePicker,
+            exporter,
             selected_paths: HashSet::new(),
+            forced: HashSet::new(),
             path_lookup: HashMap::new(),
-            status: None,
+            messages: MessageCenter::default(),
+            messages_component: MessagesPane,
+            macros: Macros::default(),
+            chords: ChordState::default(),
+            which_key: WhichKeyPopup,
             focus: FocusTarget::FileTree,
+            quick_open: Vec::new(),
+            preselected: None,
+            roots: Vec::new(),
+            workspace_roots: Vec::new(),
+            summary_visible: true,
+            layout: None,
+            count: None,
+            needs_clear: false,
             should_quit: false,
-        }
+            now: clock.now(),
+            clock,
+            pending_open: None,
+            headless: false,
+            background: CancellationToken::new(),
+            background_jobs: 0,
+            directory_estimates_tx,
+            directory_estimates_rx,
+            prefetch: PrefetchWorker::new(),
+            stats_tx,
+            stats_rx,
+            search_tx,
+            search_rx,
+            expansion_tx,
+            expansion_rx,
+            vfs,
+        })
     }
 }
 
 impl UiApp {
-    /// Launch the terminal UI and enter the event loop.
-    pub fn run(&mut self) -> Result<()> {
-        self.bootstrap()?;
+    /// An app with the system's clipboard and clock that loads the workspace's configuration.
+    pub fn new() -> Result<Self> {
+        Self::builder().build()
+    }
+
+    pub fn builder() -> UiAppBuilder {
+        UiAppBuilder::default()
+    }
+
+    /// Open `paths` directly in the preview with each file preselected, skipping the tree walk.
+    pub fn with_files(mut self, paths: Vec<PathBuf>) -> Self {
+        self.quick_open = paths;
+        self
+    }
+
+    /// Start with `items` selected instead of restoring the saved session.
+    ///
+    /// `origin` describes where they came from in the startup status message.
+    pub fn with_selections(mut self, items: Vec<SelectionItem>, origin: impl Into<String>) -> Self {
+        self.preselected = Some((items, origin.into()));
+        self
+    }
 
-        enable_raw_mode().context("failed to enable raw mode")?;
-        let mut stdout = io::stdout();
-        execute!(stdout, EnterAlternateScreen).context("failed to enter alternate screen")?;
+    /// Handle to the selections, for tasks that change them while the TUI runs; the panes catch
+    /// up on the next tick.
+    pub fn shared_selection(&self) -> SharedSelection {
+        self.selection.clone()
+    }
 
-        let backend = CrosstermBackend::new(stdout);
-        let mut terminal = Terminal::new(backend).context("failed to initialize terminal")?;
-        terminal.hide_cursor().ok();
+    /// Start by reviewing `suggestions` instead of restoring the saved session.
+    pub fn with_suggestions(mut self, suggestions: Suggestions) -> Self {
+        self.suggestions.open(suggestions);
+        self
+    }
 
-        let event_loop_result = self.event_loop(&mut terminal);
+    /// Scan `roots` instead of the working directory; several roots share one combined tree.
+    pub fn with_roots(mut self, roots: Vec<PathBuf>) -> Self {
+        self.roots = roots;
+        self
+    }
 
-        disable_raw_mode().ok();
-        let _ = execute!(terminal.backend_mut(), LeaveAlternateScreen);
-        let _ = terminal.show_cursor();
+    /// Launch the terminal UI and enter the event loop.
+    pub fn run(&mut self) -> Result<()> {
+        self.bootstrap()?;
 
-        event_loop_result
+        let mut terminal = TerminalGuard::enter()?;
+        self.event_loop(&mut terminal)
     }
 
-    fn bootstrap(&mut self) -> Result<()> {
-        self.config = Config::load()?;
-        let root = std::env::current_dir().context("unable to determine working directory")?;
+    /// Scan the workspace and restore the session without touching the terminal.
+    ///
+    /// [`Self::run`] starts with this; headless drivers such as tests call it and then feed
+    /// [`AppEvent`]s to [`Self::update`] and draw with [`Self::draw`].
+    pub fn bootstrap(&mut self) -> Result<()> {
+        let root_paths = if self.roots.is_empty() {
+            vec![std::env::current_dir().context("unable to determine working directory")?]
+        } else {
+            self.roots
+                .iter()
+                .map(|root| {
+                    if !self.vfs.is_local() {
+                        return Ok(root.clone());
+                    }
+                    root.canonicalize()
+                        .with_context(|| format!("invalid root {}", root.display()))
+                })
+                .collect::<Result<Vec<_>>>()?
+        };
+        let root = root_paths[0].clone();
+        if self.load_config {
+            self.config = Config::load_for(&root)?;
+        }
+        self.chords = ChordState::with_timeout(Duration::from_millis(
+            self.config.keybindings.chord_timeout_ms,
+        ));
         self.session_store = SessionStore::new(&root);
+        self.frecency_store = FrecencyStore::new(&root);
+        self.frecency = self.frecency_store.load().unwrap_or_else(|err| {
+            tracing::debug!(error = %err, "failed to load frecency index");
+            FrecencyIndex::default()
+        });
+        self.workspace_roots = WorkspaceRoot::from_paths(&root_paths);
 
-        let mut scanner_cfg = ScannerConfig::from_root(root.clone(), self.config.clone());
-        scanner_cfg = scanner_cfg.with_max_file_size(2 * 1024 * 1024);
-        let scan = self
-            .scanner
-            .scan(&scanner_cfg)
-            .context("failed to scan workspace")?;
+        let scan = if self.quick_open.is_empty() {
+            let mut results = Vec::with_capacity(self.workspace_roots.len());
+            for workspace_root in &self.workspace_roots {
+                let scanner_cfg =
+                    ScannerConfig::from_root(workspace_root.path.clone(), self.config.clone())
+                        .with_max_file_size(2 * 1024 * 1024);
+                results.push(self.scanner.scan(&scanner_cfg).with_context(|| {
+                    format!("failed to scan {}", workspace_root.path.display())
+                })?);
+            }
+            ScanResult::combine(&self.workspace_roots, results)
+        } else {
+            let scanner_cfg = ScannerConfig::from_root(root.clone(), self.config.clone());
+            self.scanner.scan_paths(&scanner_cfg, &self.quick_open)?
+        };
         self.path_lookup = scan
             .files
             .iter()
             .map(|meta| (meta.path.clone(), meta.display_path.clone()))
             .collect();
         self.tree = FileTreeState::from_scan(&scan);
+        self.summary_component.set_root(scan.root.clone());
         self.scan = Some(scan);
+        self.ignore_rules = Some(
+            self.scanner
+                .ignore_rules(&self.workspace_roots, &self.config)?,
+        );
 
         self.token_estimator = TokenEstimator::from_config(&self.config);
-        self.preview_service = PreviewService::new();
-        self.exporter = Exporter::new()?;
+        self.token_estimator.set_vfs(self.vfs.clone());
+        self.approximate_tree_tokens();
+        self.preview_service = Arc::new(PreviewService::new().with_vfs(self.vfs.clone()));
+        self.preview_component = Preview::new(WhitespaceOptions::from_config(&self.config.preview));
 
-        if let Some(snapshot) = self.session_store.load()? {
+        let defaults = self.load_always_include(&root)?;
+        if !self.quick_open.is_empty() {
+            self.open_quick_files()?;
+        } else if let Some((items, origin)) = self.preselected.take() {
+            self.add_defaults(&defaults);
+            self.open_preselected(items, &origin)?;
+        } else if self.suggestions.is_open() {
+            self.add_defaults(&defaults);
+            self.set_status(
+                StatusLevel::Info,
+                format!(
+                    "Review {} suggested file(s): space prunes, Enter accepts",
+                    self.suggestions.kept().len()
+                ),
+            );
+        } else if let Some(snapshot) = self.session_store.load()? {
             self.restore_session(snapshot)?;
+        } else if !defaults.is_empty() {
+            self.add_defaults(&defaults);
+            self.set_status(
+                StatusLevel::Success,
+                format!(
+                    "Added {} default selection(s) from {ALWAYS_INCLUDE_FILE}",
+                    defaults.len()
+                ),
+            );
         }
 
+        self.mark_session_saved();
         self.refresh_selection_state()?;
+        if let Some(limit) = self.scan.as_ref().and_then(|scan| scan.truncated) {
+            self.set_status(StatusLevel::Error, limit.describe());
+        }
+        Ok(())
+    }
+
+    /// Resolve `.llmctx/always-include.toml` against the scan and mark the matches as defaults.
+    fn load_always_include(&mut self, root: &Path) -> Result<Vec<PathBuf>> {
+        let Some(defaults) = AlwaysInclude::load(root)? else {
+            return Ok(Vec::new());
+        };
+        let files = self
+            .scan
+            .as_ref()
+            .map(|scan| defaults.matching(root, &scan.files))
+            .transpose()?
+            .unwrap_or_default();
+        self.tree.set_default_paths(
+            files
+                .iter()
+                .map(|path| self.display_path_for(path))
+                .collect(),
+        );
+        self.summary_component
+            .set_defaults(files.iter().cloned().collect());
+        Ok(files)
+    }
+
+    fn add_defaults(&mut self, files: &[PathBuf]) {
+        for path in files {
+            self.selection.write().add_item(SelectionItem {
+                path: path.clone(),
+                range: None,
+                note: None,
+                source: SelectionSource::Glob,
+            });
+        }
+    }
+
+    /// Preselect every command-line file and focus the preview on the first one.
+    fn open_quick_files(&mut self) -> Result<()> {
+        let files: Vec<(PathBuf, String)> = self
+            .scan
+            .as_ref()
+            .map(|scan| {
+                scan.files
+                    .iter()
+                    .filter(|meta| !meta.is_dir)
+                    .map(|meta| (meta.path.clone(), meta.display_path.clone()))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        for (path, _) in &files {
+            self.selection
+                .write()
+                .add_selection(path.clone(), None, None);
+        }
+        if let Some((path, _)) = self.quick_open.first().and_then(|first| {
+            let first = self.workspace_root().join(first);
+            files.iter().find(|(path, _)| *path == first)
+        }) {
+            let path = path.clone();
+            self.jump_to_selection(&path, None)?;
+        }
+        self.set_status(
+            StatusLevel::Success,
+            format!("Opened {} file(s) with selections", files.len()),
+        );
+        Ok(())
+    }
+
+    fn open_preselected(&mut self, items: Vec<SelectionItem>, origin: &str) -> Result<()> {
+        let first = items.first().map(|item| (item.path.clone(), item.range));
+        let count = items.len();
+        for item in items {
+            self.selection.write().add_item(item);
+        }
+        if let Some((path, range)) = first {
+            self.jump_to_selection(&path, range)?;
+        }
+        self.set_status(
+            StatusLevel::Success,
+            format!("Loaded {count} selection(s) from {origin}"),
+        );
         Ok(())
     }
 
-    fn event_loop(&mut self, terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<()> {
+    fn workspace_root(&self) -> PathBuf {
+        self.scan
+            .as_ref()
+            .map(|scan| scan.root.clone())
+            .unwrap_or_else(|| PathBuf::from("."))
+    }
+
+    fn event_loop(&mut self, terminal: &mut TerminalGuard) -> Result<()> {
         loop {
+            if std::mem::take(&mut self.needs_clear) {
+                terminal.clear()?;
+            }
             terminal.draw(|frame| self.render(frame))?;
-            self.tick();
+            self.update(AppEvent::Tick(self.clock.now()))?;
 
             if self.should_quit {
                 break;
@@ -152,75 +624,164 @@ impl UiApp {
                 let ev = event::read()?;
                 self.handle_event(ev)?;
             }
+            if let Some((action, path)) = self.pending_open.take() {
+                self.open_externally(terminal, action, &path);
+            }
         }
         Ok(())
     }
 
+    /// Suspend the UI while `action`'s program runs on `path`, then restore it.
+    fn open_externally(&mut self, terminal: &mut TerminalGuard, action: PostAction, path: &Path) {
+        let Some((program, args)) = action
+            .command()
+            .and_then(|command| command.split_first().map(|(p, a)| (p.clone(), a.to_vec())))
+        else {
+            return;
+        };
+        terminal.suspend();
+
+        let status = process::Command::new(&program)
+            .args(&args)
+            .arg(path)
+            .status();
+
+        terminal.resume();
+        self.needs_clear = true;
+        match status {
+            Ok(status) if status.success() => {}
+            Ok(status) => self.set_status(
+                StatusLevel::Error,
+                format!("{program} exited with {status}"),
+            ),
+            Err(err) => self.set_status(
+                StatusLevel::Error,
+                format!("Failed to run {program}: {err}"),
+            ),
+        }
+    }
+
     fn render(&mut self, frame: &mut Frame<'_>) {
         let size = frame.size();
-        let layout = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([Constraint::Min(3), Constraint::Length(1)])
-            .split(size);
-
-        let main_chunks = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([
-                Constraint::Length(32),
-                Constraint::Min(50),
-                Constraint::Length(36),
-            ])
-            .split(layout[0]);
-
-        let right_chunks = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([Constraint::Min(10), Constraint::Length(5)])
-            .split(main_chunks[2]);
+        let layout = if self.zen {
+            PaneLayout::focused(size)
+        } else {
+            PaneLayout::compute(size)
+        };
+        let Some(layout) = layout else {
+            self.summary_visible = false;
+            render_too_small(frame, size);
+            return;
+        };
+        self.summary_visible = layout.summary.is_some();
+        self.layout = Some(layout);
+        if self.zen && matches!(self.focus, FocusTarget::FileTree | FocusTarget::Summary) {
+            self.focus = FocusTarget::Preview;
+        } else if !self.summary_visible && matches!(self.focus, FocusTarget::Summary) {
+            self.focus = FocusTarget::FileTree;
+        }
 
         let focus_tree = matches!(self.focus, FocusTarget::FileTree);
         let focus_preview = matches!(self.focus, FocusTarget::Preview);
 
-        let selected_paths = &self.selected_paths;
-        self.file_tree.render(
-            frame,
-            main_chunks[0],
-            &self.tree,
-            focus_tree,
-            selected_paths,
-        );
+        if !self.zen {
+            let selected_paths = &self.selected_paths;
+            self.file_tree
+                .render(frame, layout.tree, &self.tree, focus_tree, selected_paths);
+        }
 
-        if let Some(segment) = self.preview.segment() {
-            self.preview_component().render(
-                segment,
-                self.preview.highlight_ranges(),
+        match &self.split {
+            Some(other) => {
+                let halves = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+                    .split(layout.preview);
+                let (active, inactive) = if self.split_active_right {
+                    (halves[1], halves[0])
+                } else {
+                    (halves[0], halves[1])
+                };
+                render_preview_pane(
+                    &self.preview_component,
+                    frame,
+                    &self.preview,
+                    focus_preview,
+                    active,
+                );
+                render_preview_pane(&self.preview_component, frame, other, false, inactive);
+            }
+            None => render_preview_pane(
+                &self.preview_component,
+                frame,
+                &self.preview,
                 focus_preview,
-                main_chunks[1],
-                frame.buffer_mut(),
+                layout.preview,
+            ),
+        }
+
+        if !self.zen {
+            let focused = self.current_location().map(|location| location.path);
+            self.breadcrumb_component.render(
+                frame,
+                layout.breadcrumb,
+                focused.as_deref(),
+                &self.breadcrumb,
             );
-        } else {
-            let block = Block::default()
-                .title("Preview")
-                .borders(Borders::ALL)
-                .border_style(Style::default().fg(if focus_preview {
-                    Color::Cyan
-                } else {
-                    Color::DarkGray
-                }));
-            let inner = block.inner(main_chunks[1]);
-            frame.render_widget(block, main_chunks[1]);
-            let placeholder = Paragraph::new("Select a file to preview")
-                .style(
-                    Style::default()
-                        .fg(Color::DarkGray)
-                        .add_modifier(Modifier::ITALIC),
-                )
-                .wrap(Wrap { trim: true });
-            frame.render_widget(placeholder, inner);
         }
 
-        self.summary_component.render(frame, right_chunks[0]);
+        if let Some(area) = layout.summary {
+            let focus_summary = matches!(self.focus, FocusTarget::Summary);
+            self.summary_component.render(frame, area, focus_summary);
+        }
+
+        if let Some(area) = layout.hints {
+            self.render_hints(frame, area);
+        }
+
+        self.render_status(frame, layout.status);
+        self.palette_component
+            .render(frame, size, &self.palette_state);
+        self.theme_picker_component
+            .render(frame, size, &self.theme_picker);
+        self.recent_files_component
+            .render(frame, size, &self.recent_files);
+        self.search_results_component
+            .render(frame, size, &self.search_results);
+        self.suggestions_component
+            .render(frame, size, &self.suggestions);
+        self.messages_component.render(frame, size, &self.messages);
+        let above_status = Rect {
+            height: layout.status.y.saturating_sub(size.y),
+            ..size
+        };
+        self.which_key
+            .render(frame, above_status, &self.chords, &self.chord_bindings());
+        self.confirm_component.render(frame, size, &self.confirm);
+        if let Some(info) = &self.file_info {
+            self.file_info_component.render(frame, size, info);
+        }
+        if let Some(stats) = &self.workspace_stats {
+            self.workspace_stats_component.render(frame, size, stats);
+        }
+    }
 
-        let hints = Paragraph::new(Line::from(vec![
+    fn render_hints(&self, frame: &mut Frame<'_>, area: Rect) {
+        let mut spans = Vec::new();
+        if let Some(count) = self.count {
+            spans.push(Span::styled(
+                format!("{count} "),
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ));
+        }
+        if let Some(register) = self.macros.recording() {
+            spans.push(Span::styled(
+                format!("recording @{register} "),
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            ));
+        }
+        spans.extend([
             Span::styled("j/k", Style::default().fg(Color::Cyan)),
             Span::raw(" move "),
             Span::styled("↵", Style::default().fg(Color::Cyan)),
@@ -235,208 +796,1042 @@ impl UiApp {
             Span::raw(" save · "),
             Span::styled("ctrl+e", Style::default().fg(Color::Cyan)),
             Span::raw(" export"),
-        ]))
-        .wrap(Wrap { trim: true })
-        .style(Style::default().fg(Color::Gray));
-        frame.render_widget(hints, right_chunks[1]);
-
-        self.render_status(frame, layout[1]);
-        self.palette_component
-            .render(frame, size, &self.palette_state);
-    }
-
-    fn preview_component(&self) -> &Preview {
-        static PREVIEW: Preview = Preview;
-        &PREVIEW
+        ]);
+        let hints = Paragraph::new(Line::from(spans))
+            .wrap(Wrap { trim: true })
+            .style(Style::default().fg(Color::Gray));
+        frame.render_widget(hints, area);
     }
 
     fn render_status(&mut self, frame: &mut Frame<'_>, area: Rect) {
-        let message = self.status.as_ref().map(|status| {
-            let style = match status.level {
-                StatusLevel::Info => Style::default().fg(Color::Gray),
-                StatusLevel::Success => Style::default().fg(Color::Green),
-                StatusLevel::Error => Style::default().fg(Color::Red),
-            };
-            Line::styled(status.text.clone(), style)
-        });
-
         let block = Block::default().borders(Borders::TOP);
         frame.render_widget(block.clone(), area);
         let inner = block.inner(area);
 
-        let line = message.unwrap_or_else(|| {
-            Line::styled(
-                "Ready · press : for commands",
-                Style::default().fg(Color::DarkGray),
-            )
-        });
+        let idle = self
+            .highlighted_add_cost()
+            .map(|tokens| {
+                format!(
+                    "Ready · space adds ~{tokens} tokens{}",
+                    self.budget_share(self.total_tokens() + tokens)
+                )
+            })
+            .unwrap_or_else(|| "Ready · press : for commands".to_string());
+        let line = MessagesPane::status_line(&self.messages, &idle);
         frame.render_widget(Paragraph::new(line), inner);
     }
 
-    fn tick(&mut self) {
-        if let Some(status) = &self.status
-            && status.is_expired()
+    /// Approximate cost of selecting the highlighted tree file, if it is not selected yet.
+    ///
+    /// Runs on every draw, so it goes by the scanned size instead of reading the file.
+    fn highlighted_add_cost(&self) -> Option<usize> {
+        if !matches!(self.focus, FocusTarget::FileTree) {
+            return None;
+        }
+        let metadata = self.tree.selected_metadata()?;
+        if metadata.is_dir || (metadata.skipped.is_some() && !self.forced.contains(&metadata.path))
         {
-            self.status = None;
+            return None;
         }
-        self.palette_state.purge_expired_messages();
+        let selection = self.selection.read();
+        let selected = selection
+            .items()
+            .iter()
+            .any(|item| item.path == metadata.path && item.range.is_none());
+        if selected {
+            return None;
+        }
+        let model = selection
+            .model()
+            .and_then(|model| model.parse::<TokenModel>().ok());
+        Some(self.token_estimator.approximate_size(
+            model,
+            &metadata.path,
+            metadata.size.unwrap_or(0),
+        ))
     }
 
-    fn handle_event(&mut self, event: Event) -> Result<()> {
-        match event {
-            Event::Key(key) => self.handle_key_event(key)?,
-            Event::Resize(..) => {}
-            Event::Mouse(_) => {}
-            Event::FocusGained | Event::FocusLost | Event::Paste(_) => {}
+    /// Estimated tokens of the whole selection.
+    fn total_tokens(&self) -> usize {
+        self.last_summary
+            .as_ref()
+            .map_or(0, |summary| summary.total_tokens)
+    }
+
+    /// " (87% of budget)" for a bundle of `total` tokens, or nothing without a budget.
+    fn budget_share(&self, total: usize) -> String {
+        match self.token_estimator.token_budget() {
+            0 => String::new(),
+            budget => format!(" ({:.0}% of budget)", total as f64 / budget as f64 * 100.0),
         }
-        Ok(())
     }
 
-    fn handle_key_event(&mut self, key: KeyEvent) -> Result<()> {
-        if self.palette_state.is_open() {
-            return self.handle_palette_key(key);
+    /// " · +4210 tokens (87% of budget)" for the change from `before` to the current total.
+    fn token_delta(&self, before: usize) -> String {
+        let after = self.total_tokens();
+        let delta = after as isize - before as isize;
+        if delta == 0 {
+            return String::new();
         }
+        format!(" · {delta:+} tokens{}", self.budget_share(after))
+    }
 
-        if key.modifiers.contains(KeyModifiers::CONTROL) {
-            match key.code {
-                KeyCode::Char('c') | KeyCode::Char('q') => {
-                    self.should_quit = true;
-                    return Ok(());
-                }
-                KeyCode::Char('s') => {
-                    self.save_session()?;
-                    return Ok(());
+    fn tick(&mut self, now: Instant) {
+        self.now = now;
+        self.messages.expire(now);
+        let timed_out = self.chords.expire(now);
+        if !timed_out.is_empty() {
+            self.dispatch_plain_keys(timed_out);
+        }
+        self.palette_state.purge_expired_messages();
+        self.prefetch_preview();
+        if self.selection.read().revision() != self.shown_revision
+            && let Err(err) = self.refresh_selection_state()
+        {
+            self.report_error(&err);
+        }
+
+        while let Ok((display_path, estimate)) = self.directory_estimates_rx.try_recv() {
+            self.background_jobs = self.background_jobs.saturating_sub(1);
+            self.tree
+                .set_directory_estimate(&display_path, DirectoryEstimate::Ready(estimate));
+            let budget = self.token_estimator.token_budget();
+            let share = if budget == 0 {
+                String::new()
+            } else {
+                format!(
+                    " ({:.0}% of budget)",
+                    estimate.tokens as f64 / budget as f64 * 100.0
+                )
+            };
+            self.set_status(
+                StatusLevel::Info,
+                format!(
+                    "{display_path}: ~{} tokens across {} files{share}",
+                    estimate.tokens, estimate.files
+                ),
+            );
+        }
+        if let Ok(stats) = self.stats_rx.try_recv() {
+            self.messages.dismiss();
+            self.workspace_stats = Some(stats);
+        }
+        if let Ok(results) = self.search_rx.try_recv() {
+            self.background_jobs = self.background_jobs.saturating_sub(1);
+            match results {
+                Ok(results) if results.hits.is_empty() => {
+                    self.set_status(
+                        StatusLevel::Info,
+                        format!("No matches for '{}'", results.pattern),
+                    );
                 }
-                KeyCode::Char('e') => {
-                    self.perform_export(None, true)?;
-                    return Ok(());
+                Ok(results) => {
+                    self.messages.dismiss();
+                    self.search_results.open(results);
                 }
-                _ => {}
+                Err(err) => self.report_error(&err),
             }
         }
-
-        match self.focus {
-            FocusTarget::FileTree => self.handle_tree_key(key),
-            FocusTarget::Preview => self.handle_preview_key(key),
-            FocusTarget::CommandPalette => Ok(()),
+        if let Ok(expansions) = self.expansion_rx.try_recv()
+            && let Err(err) = self.add_call_expansions(expansions)
+        {
+            self.report_error(&err);
         }
     }
 
-    fn handle_tree_key(&mut self, key: KeyEvent) -> Result<()> {
-        if self.tree.is_filter_active() {
-            return self.handle_filter_input(key);
+    fn handle_event(&mut self, event: Event) -> Result<()> {
+        match event {
+            Event::Key(key) => self.update(AppEvent::Key {
+                key,
+                at: self.clock.now(),
+            }),
+            Event::Resize(..) => self.update(AppEvent::Resize),
+            Event::Paste(text) => self.update(AppEvent::Paste(text)),
+            Event::Mouse(_) => Ok(()),
+            Event::FocusGained | Event::FocusLost => Ok(()),
         }
+    }
 
-        match key.code {
-            KeyCode::Esc => {
-                self.should_quit = true;
+    /// Apply one message to the app state.
+    ///
+    /// Keys and ticks read time only from their messages, so the same sequence leaves the
+    /// same state behind. Work handed to background threads (prefetching, directory
+    /// estimates, workspace stats) is the exception: its results land on whichever tick
+    /// follows their arrival.
+    pub fn update(&mut self, event: AppEvent) -> Result<()> {
+        match event {
+            AppEvent::Key { key, at } => {
+                self.now = at;
+                self.handle_key_event(key)
             }
-            KeyCode::Char('/') => {
-                self.tree.begin_filter();
+            AppEvent::Resize => {
+                self.needs_clear = true;
+                Ok(())
             }
-            KeyCode::Char(':') => {
-                self.palette_state.open();
-                self.focus = FocusTarget::CommandPalette;
+            AppEvent::Tick(now) => {
+                self.tick(now);
+                Ok(())
             }
-            KeyCode::Char('j') | KeyCode::Down => {
-                self.tree.select_next();
-                self.preview_current(false)?;
+            AppEvent::Paste(text) => {
+                self.handle_paste(&text);
+                Ok(())
             }
-            KeyCode::Char('k') | KeyCode::Up => {
-                self.tree.select_previous();
-                self.preview_current(false)?;
+        }
+    }
+
+    /// Insert pasted text into the palette (and so a note being written) or the tree filter,
+    /// whichever is taking input.
+    ///
+    /// Pasting into the tree while it is not filtering starts a filter with the text, so a
+    /// copied path can be found without typing `/` first.
+    fn handle_paste(&mut self, text: &str) {
+        // A copied line usually brings its line break along.
+        let text = text.trim_end_matches(['\r', '\n']);
+        if text.is_empty() {
+            return;
+        }
+        if self.palette_state.is_open() {
+            self.palette_state.edit_input(|input| input.insert(text));
+        } else if self.focus == FocusTarget::FileTree
+            && (self.tree.is_filter_active() || self.at_rest())
+        {
+            if !self.tree.is_filter_active() {
+                self.tree.clear_filter();
+                self.tree.begin_filter();
             }
-            KeyCode::Char('h') | KeyCode::Left => {
-                self.tree.collapse_or_parent();
+            self.tree.edit_filter(|filter| filter.insert(text));
+        }
+    }
+
+    /// Feed `events` to [`Self::update`] in order, stopping early once the app quits.
+    pub fn replay(&mut self, events: impl IntoIterator<Item = AppEvent>) -> Result<()> {
+        for event in events {
+            if self.should_quit {
+                break;
             }
-            KeyCode::Char('l') | KeyCode::Right => {
-                if self.preview_current(true)? {
-                    self.focus = FocusTarget::Preview;
-                } else {
-                    self.tree.expand_or_open();
-                }
+            self.update(event)?;
+        }
+        Ok(())
+    }
+
+    /// Draw one frame on any ratatui backend, e.g. a `TestBackend` for snapshots.
+    pub fn draw<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<()> {
+        terminal.draw(|frame| self.render(frame))?;
+        Ok(())
+    }
+
+    /// Whether a quit key has been handled.
+    pub fn should_quit(&self) -> bool {
+        self.should_quit
+    }
+
+    /// Run palette commands from `script`, one per line, without a terminal.
+    ///
+    /// Call [`Self::bootstrap`] first. Blank lines and lines starting with `#` are skipped,
+    /// prompts are answered yes, and exports skip the clipboard. `report` receives the line
+    /// number and status message of each command that posted one. The first command that fails
+    /// or posts an error stops the script.
+    pub fn run_script(&mut self, script: &str, mut report: impl FnMut(usize, &str)) -> Result<()> {
+        self.headless = true;
+        for (index, line) in script.lines().enumerate() {
+            let number = index + 1;
+            let command = line.trim();
+            if command.is_empty() || command.starts_with('#') {
+                continue;
             }
-            KeyCode::Enter => {
-                if self.preview_current(true)? {
-                    self.focus = FocusTarget::Preview;
+            self.messages.dismiss();
+            self.execute_command(command)
+                .and_then(|()| self.answer_prompt())
+                .with_context(|| format!("line {number}: {command}"))?;
+            self.pending_open = None;
+            if let Some(message) = self.messages.current() {
+                if message.level == StatusLevel::Error {
+                    return Err(anyhow!("line {number}: {command}: {}", message.text));
                 }
+                report(number, &message.text);
             }
-            KeyCode::Char(' ') => {
-                self.toggle_current_selection()?;
-            }
-            KeyCode::Tab => {
-                self.focus = FocusTarget::Preview;
-            }
-            KeyCode::Char('q') => {
-                self.should_quit = true;
-            }
-            _ => {}
         }
         Ok(())
     }
 
-    fn handle_preview_key(&mut self, key: KeyEvent) -> Result<()> {
-        match key.code {
-            KeyCode::Esc => {
-                self.preview.clear_anchor();
-                self.focus = FocusTarget::FileTree;
-            }
-            KeyCode::Char(':') => {
-                self.palette_state.open();
-                self.focus = FocusTarget::CommandPalette;
-            }
-            KeyCode::Char(' ') => {
-                self.toggle_current_selection()?;
+    /// Answer yes to a prompt a script command opened.
+    fn answer_prompt(&mut self) -> Result<()> {
+        if !self.confirm.is_open() {
+            return Ok(());
+        }
+        match self.confirm.answer('y') {
+            Some(action) => self.run_confirmed(action),
+            None => {
+                self.confirm.cancel();
+                Err(anyhow!(
+                    "the command asks a question a script cannot answer"
+                ))
             }
-            KeyCode::Tab | KeyCode::Left => {
+        }
+    }
+
+    fn handle_key_event(&mut self, key: KeyEvent) -> Result<()> {
+        if self.at_rest() {
+            match self.macros.intercept(key) {
+                MacroKey::Pass => {}
+                MacroKey::Pending => {
+                    let hint = if key.code == KeyCode::Char('q') {
+                        "q… a–z records a macro, q quits"
+                    } else {
+                        "@… a–z plays a macro, @ replays the last one"
+                    };
+                    self.set_status(StatusLevel::Info, hint);
+                    return Ok(());
+                }
+                MacroKey::Quit => {
+                    self.request_quit();
+                    return Ok(());
+                }
+                MacroKey::Cancelled => {
+                    self.set_status(StatusLevel::Info, "No register given");
+                    return Ok(());
+                }
+                MacroKey::Recording(register) => {
+                    self.set_status(
+                        StatusLevel::Info,
+                        format!("Recording @{register} · q stops"),
+                    );
+                    return Ok(());
+                }
+                MacroKey::Recorded(register, len) => {
+                    self.set_status(
+                        StatusLevel::Success,
+                        format!("Recorded {len} key(s) into @{register}"),
+                    );
+                    return Ok(());
+                }
+                MacroKey::Replay(keys) => {
+                    self.replay_keys(keys);
+                    return Ok(());
+                }
+                MacroKey::Empty(Some(register)) => {
+                    self.set_status(StatusLevel::Info, format!("Register @{register} is empty"));
+                    return Ok(());
+                }
+                MacroKey::Empty(None) => {
+                    self.set_status(StatusLevel::Info, "Nothing to repeat yet");
+                    return Ok(());
+                }
+                MacroKey::Nested => {
+                    self.set_status(StatusLevel::Error, "Macros cannot run other macros");
+                    return Ok(());
+                }
+            }
+        }
+
+        self.macros.begin_key(key, self.selection.read().revision());
+        let result = self.dispatch_key_event(key);
+        if self.at_rest() {
+            self.macros.end_action(self.selection.read().revision());
+        }
+        result
+    }
+
+    /// Whether no prompt, overlay, or text input is taking keys, so an action has finished and
+    /// `q`, `@`, and `.` mean macros rather than text.
+    fn at_rest(&self) -> bool {
+        !self.confirm.is_open()
+            && self.file_info.is_none()
+            && self.workspace_stats.is_none()
+            && !self.palette_state.is_open()
+            && !self.theme_picker.is_open()
+            && !self.recent_files.is_open()
+            && !self.search_results.is_open()
+            && !self.suggestions.is_open()
+            && !self.messages.is_open()
+            && !self.breadcrumb.is_open()
+            && self.focus != FocusTarget::CommandPalette
+            && !self.tree.is_filter_active()
+            && !self.preview.is_scroll_mode()
+            && !self.chords.is_pending()
+            && self.pending_mark.is_none()
+    }
+
+    /// Feed recorded keys back through the key handler, stopping at the first error.
+    fn replay_keys(&mut self, keys: Vec<KeyEvent>) {
+        self.macros.replay_started();
+        for key in keys {
+            if self.should_quit {
+                break;
+            }
+            if let Err(err) = self.handle_key_event(key) {
+                self.report_error(&err);
+                break;
+            }
+        }
+        let unfinished = self.chords.flush();
+        self.dispatch_plain_keys(unfinished);
+        self.macros.replay_finished();
+    }
+
+    fn dispatch_key_event(&mut self, key: KeyEvent) -> Result<()> {
+        if self.confirm.is_open() {
+            return self.handle_confirm_key(key);
+        }
+        if self.file_info.take().is_some() || self.workspace_stats.take().is_some() {
+            return Ok(());
+        }
+        if self.palette_state.is_open() {
+            return self.handle_palette_key(key);
+        }
+        if self.theme_picker.is_open() {
+            return self.handle_theme_picker_key(key);
+        }
+        if self.recent_files.is_open() {
+            return self.handle_recent_files_key(key);
+        }
+        if self.search_results.is_open() {
+            return self.handle_search_results_key(key);
+        }
+        if self.suggestions.is_open() {
+            return self.handle_suggestions_key(key);
+        }
+        if self.messages.is_open() {
+            self.handle_messages_key(key);
+            return Ok(());
+        }
+        if self.breadcrumb.is_open() {
+            return self.handle_breadcrumb_key(key);
+        }
+
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            match key.code {
+                KeyCode::Char('c') | KeyCode::Char('q') => {
+                    self.request_quit();
+                    return Ok(());
+                }
+                KeyCode::Char('s') => {
+                    self.save_session()?;
+                    return Ok(());
+                }
+                KeyCode::Char('e') => {
+                    if let Err(err) = self.perform_export(None, true, false, false) {
+                        self.report_error(&err);
+                    }
+                    return Ok(());
+                }
+                KeyCode::Char('a') => {
+                    self.select_filtered()?;
+                    return Ok(());
+                }
+                KeyCode::Char('r') => {
+                    self.open_recent_files();
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(prefix) = self.pending_mark.take() {
+            if let Err(err) = self.finish_mark(prefix, key) {
+                self.report_error(&err);
+            }
+            return Ok(());
+        }
+
+        match self.chords.feed(key, &self.chord_bindings(), self.now) {
+            ChordKey::Pass => self.dispatch_focused_key(key),
+            ChordKey::Pending | ChordKey::Cancelled => Ok(()),
+            ChordKey::Run(action) => self.run_chord(action),
+            ChordKey::Unmatched(earlier) => {
+                for earlier in earlier {
+                    self.dispatch_focused_key(earlier)?;
+                }
+                self.dispatch_key_event(key)
+            }
+        }
+    }
+
+    fn dispatch_focused_key(&mut self, key: KeyEvent) -> Result<()> {
+        match self.focus {
+            FocusTarget::FileTree => self.handle_tree_key(key),
+            FocusTarget::Preview => self.handle_preview_key(key),
+            FocusTarget::Summary => self.handle_summary_key(key),
+            FocusTarget::CommandPalette => Ok(()),
+        }
+    }
+
+    /// Handle keys left over from an unfinished chord as if each was typed on its own.
+    fn dispatch_plain_keys(&mut self, keys: Vec<KeyEvent>) {
+        for key in keys {
+            if let Err(err) = self.dispatch_focused_key(key) {
+                self.report_error(&err);
+                break;
+            }
+        }
+        if self.at_rest() {
+            self.macros.end_action(self.selection.read().revision());
+        }
+    }
+
+    fn push_count_digit(&mut self, digit: usize) {
+        let count = self.count.unwrap_or(0);
+        self.count = Some(count.saturating_mul(10).saturating_add(digit));
+    }
+
+    fn take_count(&mut self) -> Option<usize> {
+        self.count.take()
+    }
+
+    /// Half the rows of a pane's list or text, as moved by `ctrl+d` and `ctrl+u`.
+    fn half_page(&self, pane: FocusTarget) -> usize {
+        let rows = match (self.layout, pane) {
+            // Borders plus the filter line.
+            (Some(layout), FocusTarget::FileTree) => layout.tree.height.saturating_sub(3),
+            (Some(layout), _) => layout.preview.height.saturating_sub(2),
+            (None, _) => 0,
+        };
+        (rows as usize / 2).max(1)
+    }
+
+    /// Move the preview cursor by `delta` lines, loading the next chunk when moving down from
+    /// the bottom of the loaded one; `extend` grows the range selection instead.
+    fn move_preview_cursor(&mut self, delta: isize, extend: bool) -> Result<()> {
+        if delta > 0
+            && !extend
+            && self.preview.at_bottom()
+            && self
+                .preview
+                .load_more(&self.preview_service, &self.config)?
+        {
+            self.refresh_preview_highlights();
+        }
+        if let Some(change) = self.preview.move_cursor(delta, extend)? {
+            self.apply_range_change(change)?;
+        }
+        Ok(())
+    }
+
+    /// Wait for the letter of a mark to set or jump to.
+    fn begin_mark(&mut self, prefix: MarkPrefix) {
+        let hint = match prefix {
+            MarkPrefix::Set => "m… a–z sets a mark here".to_string(),
+            MarkPrefix::Jump if self.marks.is_empty() => {
+                "'… no marks set yet (m<letter> sets one)".to_string()
+            }
+            MarkPrefix::Jump => {
+                let marks: Vec<String> = self
+                    .marks
+                    .iter()
+                    .map(|(letter, mark)| match mark.line {
+                        Some(line) => format!("{letter} {}:{line}", mark.path),
+                        None => format!("{letter} {}", mark.path),
+                    })
+                    .collect();
+                format!("'… {}", marks.join(" · "))
+            }
+        };
+        self.set_status(StatusLevel::Info, hint);
+        self.pending_mark = Some(prefix);
+    }
+
+    fn finish_mark(&mut self, prefix: MarkPrefix, key: KeyEvent) -> Result<()> {
+        let letter = match key.code {
+            KeyCode::Char(ch)
+                if ch.is_ascii_alphabetic() || (ch == '\'' && prefix == MarkPrefix::Jump) =>
+            {
+                ch
+            }
+            KeyCode::Esc => {
+                self.messages.dismiss();
+                return Ok(());
+            }
+            _ => return Err(anyhow!("marks are named by a letter")),
+        };
+        match prefix {
+            MarkPrefix::Set => {
+                let mark = self
+                    .current_location()
+                    .ok_or_else(|| anyhow!("nothing to mark here"))?;
+                let place = match mark.line {
+                    Some(line) => format!("{}:{line}", mark.path),
+                    None => mark.path.clone(),
+                };
+                self.marks.insert(letter, mark);
+                self.set_status(
+                    StatusLevel::Success,
+                    format!("Mark {letter} set at {place}"),
+                );
+            }
+            MarkPrefix::Jump => {
+                let mark = self
+                    .marks
+                    .get(&letter)
+                    .cloned()
+                    .ok_or_else(|| anyhow!("mark {letter} is not set"))?;
+                if let Some(here) = self.current_location() {
+                    self.marks.insert('\'', here);
+                }
+                self.jump_to_mark(&mark)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The previewed file and cursor line when the preview has focus, otherwise the tree entry.
+    fn current_location(&self) -> Option<JumpMark> {
+        if self.focus == FocusTarget::Preview
+            && let Some(path) = self.preview.path()
+        {
+            return Some(JumpMark {
+                path: self.display_path_for(path),
+                line: self.preview.cursor(),
+            });
+        }
+        self.tree.selected_metadata().map(|meta| JumpMark {
+            path: meta.display_path.clone(),
+            line: None,
+        })
+    }
+
+    fn jump_to_mark(&mut self, mark: &JumpMark) -> Result<()> {
+        let Some(line) = mark.line else {
+            self.tree.focus_path(&mark.path);
+            self.preview_current(false)?;
+            self.focus = FocusTarget::FileTree;
+            return Ok(());
+        };
+        let path = self
+            .path_lookup
+            .iter()
+            .find(|(_, display)| **display == mark.path)
+            .map(|(path, _)| path.clone())
+            .unwrap_or_else(|| self.workspace_root().join(&mark.path));
+        if !self.vfs.metadata(&path).is_ok_and(|meta| meta.is_file()) {
+            return Err(anyhow!("{} no longer exists", mark.path));
+        }
+        self.jump_to_selection(&path, Some((line, line)))
+    }
+
+    /// Chords available in the focused pane; none while text is being typed.
+    fn chord_bindings(&self) -> Vec<Chord<ChordAction>> {
+        let mut chords = Vec::new();
+        match self.focus {
+            FocusTarget::FileTree if !self.tree.is_filter_active() => {
+                chords.extend_from_slice(TREE_CHORDS);
+            }
+            FocusTarget::Preview if !self.preview.is_scroll_mode() => {
+                chords.extend_from_slice(PREVIEW_CHORDS);
+            }
+            FocusTarget::Summary => {}
+            _ => return chords,
+        }
+        chords.extend_from_slice(LEADER_CHORDS);
+        chords
+    }
+
+    fn run_chord(&mut self, action: ChordAction) -> Result<()> {
+        match action {
+            ChordAction::Top => match self.focus {
+                FocusTarget::Preview => {
+                    let line = self.take_count().unwrap_or(1);
+                    self.goto_line(line)?;
+                }
+                _ => {
+                    let entry = self.take_count().unwrap_or(1);
+                    self.tree.select_first();
+                    self.tree.move_by(entry.saturating_sub(1) as isize);
+                    self.preview_current(false)?;
+                }
+            },
+            ChordAction::ToggleDirectory => self.tree.toggle_expansion(),
+            ChordAction::CollapseAll => {
+                self.tree.collapse_all();
+                self.preview_current(false)?;
+            }
+            ChordAction::Filter => {
+                self.focus = FocusTarget::FileTree;
+                self.tree.begin_filter();
+            }
+            ChordAction::RecentFiles => self.open_recent_files(),
+            ChordAction::Save => self.save_session()?,
+            ChordAction::Export => {
+                if let Err(err) = self.perform_export(None, true, false, false) {
+                    self.report_error(&err);
+                }
+            }
+            ChordAction::Messages => self.messages.open(),
+            ChordAction::Themes => self.choose_theme("")?,
+        }
+        Ok(())
+    }
+
+    fn handle_tree_key(&mut self, key: KeyEvent) -> Result<()> {
+        if self.tree.is_filter_active() {
+            return self.handle_filter_input(key);
+        }
+
+        if let KeyCode::Char(ch) = key.code
+            && let Some(digit) = ch.to_digit(10)
+        {
+            self.push_count_digit(digit as usize);
+            return Ok(());
+        }
+        let steps = self.take_count().unwrap_or(1);
+
+        match key.code {
+            KeyCode::Esc if self.background_jobs > 0 => self.cancel_background(),
+            KeyCode::Esc if self.tree.has_marks() => {
+                self.tree.clear_marks();
+                self.set_status(StatusLevel::Info, "Marks cleared");
+            }
+            KeyCode::Esc => {
+                self.request_quit();
+            }
+            KeyCode::Char('/') => {
+                self.tree.begin_filter();
+            }
+            KeyCode::Char(':') => {
+                self.palette_state.open();
+                self.focus = FocusTarget::CommandPalette;
+            }
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.tree.move_by(steps as isize);
+                self.preview_current(false)?;
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.tree.move_by(-(steps as isize));
+                self.preview_current(false)?;
+            }
+            KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                let rows = self.half_page(FocusTarget::FileTree).saturating_mul(steps);
+                self.tree.move_by(rows as isize);
+                self.preview_current(false)?;
+            }
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                let rows = self.half_page(FocusTarget::FileTree).saturating_mul(steps);
+                self.tree.move_by(-(rows as isize));
+                self.preview_current(false)?;
+            }
+            KeyCode::Char('h') | KeyCode::Left => {
+                self.tree.collapse_or_parent();
+            }
+            KeyCode::Char('l') | KeyCode::Right => {
+                if self.preview_current(true)? {
+                    self.focus = FocusTarget::Preview;
+                } else {
+                    self.tree.expand_or_open();
+                }
+            }
+            KeyCode::Enter if self.preview_current(true)? => {
+                self.focus = FocusTarget::Preview;
+            }
+            KeyCode::Char(' ') => {
+                self.toggle_current_selection()?;
+            }
+            KeyCode::Char('T') => {
+                self.estimate_selected_directory();
+            }
+            KeyCode::Char('F') => {
+                self.force_current()?;
+            }
+            KeyCode::Char('i') => {
+                self.show_file_info();
+            }
+            KeyCode::Char('*') => {
+                self.tree.toggle_mark();
+                self.tree.select_next();
+                self.preview_current(false)?;
+            }
+            KeyCode::Char('V') => {
+                self.tree.toggle_visual();
+            }
+            KeyCode::Char('a') => {
+                self.apply_to_marked(MarkedOperation::Select(SelectionSource::Manual))?;
+            }
+            KeyCode::Char('x') => {
+                self.apply_to_marked(MarkedOperation::Deselect)?;
+            }
+            KeyCode::Char('n') => {
+                self.palette_state.open_with("note ");
+                self.focus = FocusTarget::CommandPalette;
+            }
+            KeyCode::Char('m') => self.begin_mark(MarkPrefix::Set),
+            KeyCode::Char('\'') => self.begin_mark(MarkPrefix::Jump),
+            KeyCode::Char('y') => self.copy_focused_path(),
+            KeyCode::Char('b') => self.open_breadcrumb(),
+            KeyCode::Char('|') => self.toggle_split(),
+            KeyCode::Char('W') => self.toggle_summary_view(),
+            KeyCode::Char('R') => {
+                self.run_recipe("")?;
+                self.palette_state.open_with("recipe ");
+                self.focus = FocusTarget::CommandPalette;
+            }
+            // `za` and `zM` make `z` a chord prefix here, so it lands once the timeout passes.
+            KeyCode::Char('z') => {
+                self.preview_current(true)?;
+                self.toggle_zen();
+            }
+            KeyCode::Tab => {
+                self.focus = FocusTarget::Preview;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn handle_preview_key(&mut self, key: KeyEvent) -> Result<()> {
+        if self.preview.is_scroll_mode() {
+            return self.handle_preview_scroll_key(key);
+        }
+
+        if let KeyCode::Char(ch) = key.code
+            && let Some(digit) = ch.to_digit(10)
+        {
+            self.push_count_digit(digit as usize);
+            return Ok(());
+        }
+        let count = self.take_count();
+        let steps = count.unwrap_or(1);
+
+        match key.code {
+            KeyCode::Esc if self.zen => self.toggle_zen(),
+            KeyCode::Esc => {
                 self.preview.clear_anchor();
                 self.focus = FocusTarget::FileTree;
             }
-            KeyCode::Right => {
+            KeyCode::Char(':') => {
+                self.palette_state.open();
+                self.focus = FocusTarget::CommandPalette;
+            }
+            KeyCode::Char(' ') => {
+                self.toggle_current_selection()?;
+            }
+            KeyCode::Tab => {
+                self.preview.clear_anchor();
+                self.focus = if self.summary_visible {
+                    FocusTarget::Summary
+                } else {
+                    FocusTarget::FileTree
+                };
+            }
+            KeyCode::Left => {
+                self.preview.clear_anchor();
+                self.focus = FocusTarget::FileTree;
+            }
+            KeyCode::Right
                 if self
                     .preview
-                    .load_more(&self.preview_service, &self.config)?
-                {
-                    self.refresh_preview_highlights();
-                }
+                    .load_more(&self.preview_service, &self.config)? =>
+            {
+                self.refresh_preview_highlights();
             }
-            KeyCode::Char('q') => {
-                self.should_quit = true;
+            KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                let rows = self.half_page(FocusTarget::Preview).saturating_mul(steps);
+                self.move_preview_cursor(rows as isize, false)?;
+            }
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                let rows = self.half_page(FocusTarget::Preview).saturating_mul(steps);
+                self.move_preview_cursor(-(rows as isize), false)?;
             }
             KeyCode::Up | KeyCode::Char('k') => {
-                if let Some(change) = self
-                    .preview
-                    .move_cursor(-1, key.modifiers.contains(KeyModifiers::SHIFT))?
-                {
-                    self.apply_range_change(change)?;
-                }
+                let extend = key.modifiers.contains(KeyModifiers::SHIFT);
+                self.move_preview_cursor(-(steps as isize), extend)?;
             }
             KeyCode::Down | KeyCode::Char('j') => {
-                if key.modifiers.contains(KeyModifiers::SHIFT) {
-                    if let Some(change) = self.preview.move_cursor(1, true)? {
-                        self.apply_range_change(change)?;
-                    }
-                } else {
-                    if self.preview.at_bottom()
-                        && self
-                            .preview
-                            .load_more(&self.preview_service, &self.config)?
-                    {
-                        self.refresh_preview_highlights();
-                    }
-                    if let Some(change) = self.preview.move_cursor(1, false)? {
-                        self.apply_range_change(change)?;
-                    }
-                }
+                let extend = key.modifiers.contains(KeyModifiers::SHIFT);
+                self.move_preview_cursor(steps as isize, extend)?;
             }
             KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
                 self.save_session()?;
             }
+            KeyCode::Char('w') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.swap_split();
+            }
+            KeyCode::Char('G') => {
+                self.goto_line(count.unwrap_or(usize::MAX))?;
+            }
+            KeyCode::Char('E') => {
+                if let Some(change) = self.preview.select_to_end() {
+                    self.apply_range_change(change)?;
+                }
+            }
+            KeyCode::Char('V') => {
+                if let Some(change) = self.preview.select_chunk() {
+                    self.apply_range_change(change)?;
+                }
+            }
+            KeyCode::Char('p') => match self.preview.select_paragraph() {
+                Some(change) => self.apply_range_change(change)?,
+                None => self.set_status(StatusLevel::Info, "Cursor is not inside a block"),
+            },
+            KeyCode::Char('w') => {
+                let wrap = self.preview.toggle_wrap();
+                self.set_status(
+                    StatusLevel::Info,
+                    if wrap {
+                        "Soft wrap enabled"
+                    } else {
+                        "Soft wrap disabled"
+                    },
+                );
+            }
+            KeyCode::Char('m') => self.begin_mark(MarkPrefix::Set),
+            KeyCode::Char('\'') => self.begin_mark(MarkPrefix::Jump),
+            KeyCode::Char('y') => self.copy_focused_path(),
+            KeyCode::Char('b') => self.open_breadcrumb(),
+            KeyCode::Char('|') => self.toggle_split(),
+            KeyCode::Char('W') => self.toggle_summary_view(),
+            KeyCode::Char('z') => self.toggle_zen(),
+            KeyCode::Char('H') => {
+                self.preview.enter_scroll_mode();
+                self.set_status(
+                    StatusLevel::Info,
+                    "Scroll mode: h/l scroll, 0 resets, esc exits",
+                );
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn handle_preview_scroll_key(&mut self, key: KeyEvent) -> Result<()> {
+        match key.code {
+            KeyCode::Char('h') | KeyCode::Left => self.preview.scroll_horizontal(-HORIZONTAL_STEP),
+            KeyCode::Char('l') | KeyCode::Right => self.preview.scroll_horizontal(HORIZONTAL_STEP),
+            KeyCode::Char('0') | KeyCode::Home => self.preview.reset_horizontal_scroll(),
+            KeyCode::Esc | KeyCode::Char('H') => {
+                self.preview.exit_scroll_mode();
+                self.set_status(StatusLevel::Info, "Scroll mode off");
+            }
             _ => {}
         }
         Ok(())
     }
 
+    fn handle_summary_key(&mut self, key: KeyEvent) -> Result<()> {
+        match key.code {
+            KeyCode::Esc | KeyCode::Tab => {
+                self.focus = FocusTarget::FileTree;
+            }
+            KeyCode::Char(':') => {
+                self.palette_state.open();
+                self.focus = FocusTarget::CommandPalette;
+            }
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.summary_component.select_next();
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.summary_component.select_previous();
+            }
+            KeyCode::Char('s') => {
+                self.summary_component.cycle_sort();
+            }
+            KeyCode::Char('W') => self.toggle_summary_view(),
+            KeyCode::Char(ch) if ch.is_ascii_digit() && ch != '0' => {
+                let index = ch as usize - '1' as usize;
+                if let Some(hint) = self.summary_component.hint(index).cloned() {
+                    self.apply_fit_hint(hint)?;
+                }
+            }
+            KeyCode::Enter => {
+                if let Some(item) = self.summary_component.selected_item().cloned() {
+                    self.jump_to_selection(&item.path, item.range)?;
+                }
+            }
+            KeyCode::Char('x') => {
+                if let Some(item) = self.summary_component.selected_item().cloned() {
+                    self.selection
+                        .write()
+                        .remove_selection(&item.path, item.range);
+                    self.set_status(
+                        StatusLevel::Info,
+                        format!("Removed {}", self.display_path_for(&item.path)),
+                    );
+                    self.refresh_selection_state()?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Apply one of the summary's suggestions for getting back under budget.
+    fn apply_fit_hint(&mut self, hint: FitHint) -> Result<()> {
+        let message = match hint {
+            FitHint::Drop { item, tokens } => {
+                self.selection
+                    .write()
+                    .remove_selection(&item.path, item.range);
+                format!(
+                    "Dropped {}, saving {tokens} tokens",
+                    self.display_path_for(&item.path)
+                )
+            }
+            FitHint::Strip { options, tokens } => {
+                self.token_estimator.set_strip(options);
+                format!("Stripping comments and blank lines from exports, saving {tokens} tokens")
+            }
+        };
+        self.refresh_selection_state()?;
+        self.set_status(StatusLevel::Success, message);
+        Ok(())
+    }
+
+    /// Open the preview at a selection and move focus there.
+    fn jump_to_selection(&mut self, path: &Path, range: Option<(usize, usize)>) -> Result<()> {
+        let display = self.display_path_for(path);
+        self.tree.focus_path(&display);
+
+        let window = range.map(|(start, _)| {
+            let start = start.saturating_sub(1);
+            start..start + self.config.defaults.preview_max_lines().max(1)
+        });
+        let segment = self
+            .preview_service
+            .preview(path, window, &self.config)
+            .with_context(|| format!("failed to preview {display}"))?;
+        self.preview.set_segment(segment);
+        self.refresh_preview_highlights();
+        self.focus = FocusTarget::Preview;
+        Ok(())
+    }
+
+    /// Load a chunk around `line` (clamped to the file) and put the cursor on it.
+    ///
+    /// The chunk reaches back half its length, or ends at the end of the file, so the cursor
+    /// can move up from the target as well as down.
+    fn goto_line(&mut self, line: usize) -> Result<()> {
+        let segment = self
+            .preview
+            .segment()
+            .ok_or_else(|| anyhow!("open a preview first"))?;
+        let path = segment.path.clone();
+        let last = segment.total_lines.max(1);
+        let line = line.clamp(1, last);
+
+        let max_lines = self.config.defaults.preview_max_lines().max(1);
+        let start = (line - 1)
+            .saturating_sub(max_lines / 2)
+            .min(last.saturating_sub(max_lines));
+        let segment = self
+            .preview_service
+            .preview(&path, Some(start..start + max_lines), &self.config)
+            .with_context(|| format!("failed to preview {}", path.display()))?;
+        self.preview.set_segment(segment);
+        self.preview.restore_cursor(line);
+        self.refresh_preview_highlights();
+        self.set_status(StatusLevel::Info, format!("Line {line} of {last}"));
+        Ok(())
+    }
+
+    fn display_path_for(&self, path: &Path) -> String {
+        self.path_lookup
+            .get(path)
+            .cloned()
+            .or_else(|| {
+                self.workspace_roots
+                    .iter()
+                    .find_map(|root| root.display_path(path))
+            })
+            .unwrap_or_else(|| path_relative_to(path, &self.workspace_root()))
+    }
+
     fn handle_palette_key(&mut self, key: KeyEvent) -> Result<()> {
         match key.code {
             KeyCode::Esc => {
@@ -448,21 +1843,12 @@ impl UiApp {
                 self.palette_state.close();
                 self.focus = FocusTarget::FileTree;
                 if let Err(err) = self.execute_command(command.trim()) {
-                    self.set_status(StatusLevel::Error, err.to_string());
+                    self.report_error(&err);
                 }
             }
-            KeyCode::Backspace => {
-                self.palette_state.pop_char();
-            }
-            KeyCode::Char(ch) => {
-                if !key
-                    .modifiers
-                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
-                {
-                    self.palette_state.push_char(ch);
-                }
+            _ => {
+                self.palette_state.edit_input(|input| input.handle_key(key));
             }
-            _ => {}
         }
         Ok(())
     }
@@ -475,74 +1861,509 @@ impl UiApp {
             KeyCode::Enter => {
                 self.tree.end_filter();
             }
-            KeyCode::Backspace => {
-                self.tree.pop_filter_char();
+            _ => {
+                self.tree.edit_filter(|filter| filter.handle_key(key));
+            }
+        }
+        Ok(())
+    }
+
+    fn preview_current(&mut self, force: bool) -> Result<bool> {
+        let metadata = match self.tree.selected_metadata() {
+            Some(meta) => meta,
+            None => return Ok(false),
+        };
+        if metadata.is_dir {
+            return Ok(false);
+        }
+
+        if metadata.skipped.is_some() && !self.forced.contains(&metadata.path) {
+            self.set_status(StatusLevel::Info, skipped_hint(metadata));
+            return Ok(false);
+        }
+
+        let already_previewing = self
+            .preview
+            .path()
+            .map(|path| path == metadata.path)
+            .unwrap_or(false);
+        if already_previewing && !force {
+            return Ok(true);
+        }
+
+        self.preview.remember_position();
+        let remembered = self.preview.remembered_position(&metadata.path);
+        // An empty window loads the configured chunk size from `start`.
+        let window = remembered.map(|(start, _)| {
+            let start = start.saturating_sub(1);
+            start..start + self.config.defaults.preview_max_lines()
+        });
+        let segment = self
+            .preview_service
+            .preview(&metadata.path, window, &self.config)
+            .with_context(|| format!("failed to preview {}", metadata.display_path))?;
+
+        self.preview.set_segment(segment);
+        if let Some((_, cursor)) = remembered {
+            self.preview.restore_cursor(cursor);
+        }
+        self.refresh_preview_highlights();
+        if force {
+            self.focus = FocusTarget::Preview;
+            let path = self.preview.path().map(Path::to_path_buf);
+            if let Some(path) = path {
+                self.record_use(&path, FileUse::Preview);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Opt the focused large file into previewing and selection, then open it in chunks.
+    fn force_current(&mut self) -> Result<()> {
+        let Some(metadata) = self.tree.selected_metadata() else {
+            return Ok(());
+        };
+        match metadata.skipped {
+            Some(SkipReason::LargeFile) => {}
+            Some(SkipReason::BinaryFile) => {
+                return Err(anyhow!(
+                    "{} is binary and cannot be included",
+                    metadata.display_path
+                ));
+            }
+            None => return Ok(()),
+        }
+        let (path, display_path) = (metadata.path.clone(), metadata.display_path.clone());
+        self.forced.insert(path);
+        if self.preview_current(true)? {
+            self.set_status(
+                StatusLevel::Success,
+                format!("Loaded large file {display_path}; more lines load as you scroll"),
+            );
+        }
+        Ok(())
+    }
+
+    fn show_file_info(&mut self) {
+        let Some(metadata) = self.tree.selected_metadata() else {
+            return;
+        };
+        if metadata.is_dir {
+            return;
+        }
+        let metadata = metadata.clone();
+        let path = &metadata.path;
+        let mut rows = vec![(
+            "Path",
+            fs::canonicalize(path)
+                .unwrap_or_else(|_| path.clone())
+                .display()
+                .to_string(),
+        )];
+        rows.push((
+            "Size",
+            metadata
+                .size
+                .map(|size| format!("{} ({size} bytes)", format_bytes(size)))
+                .unwrap_or_else(|| "unknown".to_string()),
+        ));
+        rows.push((
+            "Modified",
+            metadata
+                .modified
+                .and_then(|modified| {
+                    modified
+                        .format(format_description!(
+                            "[year]-[month]-[day] [hour]:[minute]:[second] UTC"
+                        ))
+                        .ok()
+                })
+                .unwrap_or_else(|| "unknown".to_string()),
+        ));
+        rows.push((
+            "Language",
+            metadata
+                .language
+                .clone()
+                .unwrap_or_else(|| "unknown".to_string()),
+        ));
+
+        let status = git::status_for_path(path);
+        rows.push((
+            "Git status",
+            match &status {
+                Ok(Some(status)) => status.label().to_string(),
+                Ok(None) => "not in a repository".to_string(),
+                Err(err) => format!("unavailable ({err})"),
+            },
+        ));
+        if matches!(status, Ok(Some(_))) {
+            let commit = match git::history_for_path(path, 1) {
+                Ok(history) => history.into_iter().next().map_or_else(
+                    || "none".to_string(),
+                    |commit| match commit.date {
+                        Some(date) => format!(
+                            "{} {} ({}, {date})",
+                            commit.id, commit.subject, commit.author
+                        ),
+                        None => format!("{} {} ({})", commit.id, commit.subject, commit.author),
+                    },
+                ),
+                Err(err) => format!("unavailable ({err})"),
+            };
+            rows.push(("Last commit", commit));
+        }
+
+        let forced = self.forced.contains(path);
+        if metadata.skipped.is_none() || forced {
+            let model = self
+                .selection
+                .read()
+                .model()
+                .and_then(|model| model.parse::<TokenModel>().ok());
+            let estimate = self
+                .token_estimator
+                .estimate_files(model, std::slice::from_ref(path));
+            rows.push((
+                "Tokens",
+                if estimate.unreadable > 0 {
+                    "unreadable".to_string()
+                } else {
+                    format!("~{} ({} characters)", estimate.tokens, estimate.characters)
+                },
+            ));
+        }
+        if let Some(reason) = metadata.skipped {
+            let reason = match (reason, forced) {
+                (SkipReason::LargeFile, true) => "larger than the size limit; force-included",
+                (SkipReason::LargeFile, false) => {
+                    "larger than the size limit; press F to include it"
+                }
+                (SkipReason::BinaryFile, _) => "binary content",
+            };
+            rows.push(("Skipped", reason.to_string()));
+        }
+
+        self.file_info = Some(FileInfo {
+            title: metadata.display_path.clone(),
+            rows,
+        });
+    }
+
+    /// Open the theme picker, or switch straight to `name` when one is given.
+    fn choose_theme(&mut self, name: &str) -> Result<()> {
+        if !name.is_empty() {
+            if !self.preview_service.has_theme(name) {
+                return Err(anyhow!("unknown theme '{name}'"));
+            }
+            self.apply_theme(name)?;
+            self.set_status(StatusLevel::Success, format!("Theme set to {name}"));
+            return Ok(());
+        }
+        let themes = self.preview_service.available_themes();
+        let current = self.config.defaults.theme().to_string();
+        self.theme_picker.open(themes, &current);
+        self.set_status(
+            StatusLevel::Info,
+            "j/k to preview themes, Enter to keep, Esc to cancel",
+        );
+        Ok(())
+    }
+
+    fn handle_theme_picker_key(&mut self, key: KeyEvent) -> Result<()> {
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.theme_picker.select_next();
+                self.preview_picked_theme()?;
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.theme_picker.select_previous();
+                self.preview_picked_theme()?;
+            }
+            KeyCode::Enter => {
+                let theme = self.config.defaults.theme().to_string();
+                self.theme_picker.close();
+                self.set_status(StatusLevel::Success, format!("Theme set to {theme}"));
+            }
+            KeyCode::Esc | KeyCode::Char('q') => {
+                if let Some(original) = self.theme_picker.close() {
+                    self.apply_theme(&original)?;
+                }
+                self.set_status(StatusLevel::Info, "Theme unchanged");
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Open the `ctrl+r` picker with the best ranked files that still exist.
+    fn open_recent_files(&mut self) {
+        let root = self.workspace_root();
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        let entries: Vec<(PathBuf, String)> = self
+            .frecency
+            .ranked(now)
+            .into_iter()
+            .map(|entry| root.join(&entry.path))
+            .filter(|path| self.vfs.metadata(path).is_ok_and(|meta| meta.is_file()))
+            .take(RECENT_FILES_LIMIT)
+            .map(|path| {
+                let display = self.display_path_for(&path);
+                (path, display)
+            })
+            .collect();
+        if entries.is_empty() {
+            self.set_status(
+                StatusLevel::Info,
+                "No recent files yet; files you open or select show up here",
+            );
+            return;
+        }
+        self.recent_files.open(entries);
+    }
+
+    fn handle_confirm_key(&mut self, key: KeyEvent) -> Result<()> {
+        let action = match key.code {
+            KeyCode::Esc => {
+                self.confirm.cancel();
+                None
+            }
+            KeyCode::Char(ch) => self.confirm.answer(ch),
+            _ => None,
+        };
+        match action {
+            Some(action) => self.run_confirmed(action),
+            None => Ok(()),
+        }
+    }
+
+    fn run_confirmed(&mut self, action: ConfirmedAction) -> Result<()> {
+        match action {
+            ConfirmedAction::ClearSelections => {
+                let count = self.selection.read().len();
+                self.selection.write().clear();
+                self.refresh_selection_state()?;
+                self.set_status(
+                    StatusLevel::Success,
+                    format!("Cleared {count} selection(s)"),
+                );
             }
-            KeyCode::Char(ch) => {
-                if !key
-                    .modifiers
-                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
-                {
-                    self.tree.push_filter_char(ch);
+            ConfirmedAction::RunRecipe(name) => self.apply_recipe(&name)?,
+            ConfirmedAction::OverwriteExport { path, open } => {
+                self.perform_export(Some(path), true, open, true)?;
+            }
+            ConfirmedAction::DeleteSession => {
+                self.session_store.delete()?;
+                self.set_status(StatusLevel::Success, "Deleted the saved session");
+            }
+            ConfirmedAction::SaveAndQuit => {
+                self.save_session()?;
+                self.should_quit = true;
+            }
+            ConfirmedAction::Quit => self.should_quit = true,
+        }
+        Ok(())
+    }
+
+    /// Quit, or first offer to save when the session has unsaved changes.
+    fn request_quit(&mut self) {
+        if !self.session_dirty() {
+            self.should_quit = true;
+            return;
+        }
+        self.confirm.ask(
+            "Unsaved session",
+            format!(
+                "{} selection(s) or the filter changed since the session was last saved.",
+                self.selection.read().len()
+            ),
+            vec![
+                Choice::new('s', "save and quit", ConfirmedAction::SaveAndQuit),
+                Choice::new('d', "discard and quit", ConfirmedAction::Quit),
+            ],
+        );
+    }
+
+    /// Whether quitting now would lose selections, the model, or the filter.
+    fn session_dirty(&self) -> bool {
+        self.selection.read().is_dirty()
+            || self.tree.filter() != self.saved_filter
+            || self.marks != self.saved_marks
+    }
+
+    fn mark_session_saved(&mut self) {
+        self.selection.write().mark_saved();
+        self.saved_filter = self.tree.filter().to_string();
+        self.saved_marks = self.marks.clone();
+    }
+
+    fn handle_messages_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Char('k') | KeyCode::Up => self.messages.scroll_up(1),
+            KeyCode::Char('j') | KeyCode::Down => self.messages.scroll_down(1),
+            KeyCode::PageUp => self.messages.scroll_up(10),
+            KeyCode::PageDown => self.messages.scroll_down(10),
+            KeyCode::Esc | KeyCode::Char('q') => self.messages.close(),
+            _ => {}
+        }
+    }
+
+    fn handle_breadcrumb_key(&mut self, key: KeyEvent) -> Result<()> {
+        match key.code {
+            KeyCode::Char('h') | KeyCode::Left => self.breadcrumb.select_previous(),
+            KeyCode::Char('l') | KeyCode::Right => self.breadcrumb.select_next(),
+            KeyCode::Enter => {
+                if let Some(directory) = self.breadcrumb.picked_directory() {
+                    self.tree.focus_path(&directory);
+                    self.focus = FocusTarget::FileTree;
+                    self.set_status(StatusLevel::Info, format!("Jumped to {directory}"));
+                }
+                self.breadcrumb.close();
+            }
+            KeyCode::Esc | KeyCode::Char('b') | KeyCode::Char('q') => self.breadcrumb.close(),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Pick a directory above the focused file from the path bar.
+    fn open_breadcrumb(&mut self) {
+        let Some(location) = self.current_location() else {
+            return;
+        };
+        if !self.breadcrumb.open(&location.path) {
+            self.set_status(StatusLevel::Info, "Already at the top of the workspace");
+        }
+    }
+
+    /// Copy the focused file's workspace-relative path.
+    fn copy_focused_path(&mut self) {
+        let Some(location) = self.current_location() else {
+            return;
+        };
+        match self.exporter.copy_to_clipboard(&location.path) {
+            Ok(()) => self.set_status(StatusLevel::Success, format!("Copied {}", location.path)),
+            Err(err) => self.report_error(&err),
+        }
+    }
+
+    fn handle_recent_files_key(&mut self, key: KeyEvent) -> Result<()> {
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => self.recent_files.select_next(),
+            KeyCode::Char('k') | KeyCode::Up => self.recent_files.select_previous(),
+            KeyCode::Enter => {
+                let path = self.recent_files.selected_path().map(Path::to_path_buf);
+                self.recent_files.close();
+                if let Some(path) = path {
+                    self.jump_to_selection(&path, None)?;
+                    self.record_use(&path, FileUse::Preview);
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('q') => self.recent_files.close(),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Count a use of `path` towards its frecency and persist the index right away, so the
+    /// ranking survives a crash.
+    fn record_use(&mut self, path: &Path, kind: FileUse) {
+        let root = self.workspace_root();
+        let path = path.strip_prefix(&root).unwrap_or(path);
+        self.frecency
+            .record(path, kind, OffsetDateTime::now_utc().unix_timestamp());
+        if let Err(err) = self.frecency_store.save(&self.frecency) {
+            tracing::debug!(error = %err, "failed to save frecency index");
+        }
+    }
+
+    fn preview_picked_theme(&mut self) -> Result<()> {
+        if let Some(theme) = self.theme_picker.selected_theme().map(str::to_string) {
+            self.apply_theme(&theme)?;
+        }
+        Ok(())
+    }
+
+    /// Use `theme` for previews and re-render the current preview with it.
+    fn apply_theme(&mut self, theme: &str) -> Result<()> {
+        self.config.defaults.set_theme(theme);
+        if let Some(other) = &mut self.split {
+            other.rehighlight(&self.preview_service, &self.config)?;
+        }
+        self.preview
+            .rehighlight(&self.preview_service, &self.config)
+    }
+
+    /// Highlight the selected ranges in both halves of the preview.
+    fn refresh_preview_highlights(&mut self) {
+        let selection = self.selection.read();
+        for preview in std::iter::once(&mut self.preview).chain(self.split.as_mut()) {
+            if let Some(path) = preview.path().map(PathBuf::from) {
+                let mut ranges = Vec::new();
+                for item in selection.items() {
+                    if item.path == path {
+                        if let Some(range) = item.range {
+                            ranges.push(range);
+                        } else {
+                            ranges.push((1, usize::MAX));
+                        }
+                    }
                 }
+                preview.set_highlights(ranges);
             }
-            _ => {}
         }
-        Ok(())
     }
 
-    fn preview_current(&mut self, force: bool) -> Result<bool> {
-        let metadata = match self.tree.selected_metadata() {
-            Some(meta) => meta,
-            None => return Ok(false),
-        };
-        if metadata.is_dir {
-            return Ok(false);
+    /// Split the preview into two halves showing the current file, or close the other half.
+    fn toggle_split(&mut self) {
+        if self.split.take().is_some() {
+            self.split_active_right = false;
+            self.set_status(StatusLevel::Info, "Split closed");
+            return;
         }
+        let mut other = self.preview.clone();
+        other.clear_anchor();
+        other.prefetched = None;
+        other.prefetch_pending = None;
+        self.split = Some(other);
+        self.split_active_right = false;
+        self.set_status(
+            StatusLevel::Info,
+            "Preview split: open another file here, ctrl+w switches halves, | closes the other one",
+        );
+    }
 
-        if metadata.skipped.is_some() {
+    /// Hide every pane but the preview, or bring them back.
+    fn toggle_zen(&mut self) {
+        self.zen = !self.zen;
+        if self.zen {
+            self.focus = FocusTarget::Preview;
             self.set_status(
                 StatusLevel::Info,
-                format!("{} skipped during scan", metadata.display_path),
+                "Focus mode: z or esc brings the panes back",
             );
-            return Ok(false);
-        }
-
-        let already_previewing = self
-            .preview
-            .path()
-            .map(|path| path == metadata.path)
-            .unwrap_or(false);
-        if already_previewing && !force {
-            return Ok(true);
+        } else {
+            self.set_status(StatusLevel::Info, "Focus mode off");
         }
+    }
 
-        let segment = self
-            .preview_service
-            .preview(&metadata.path, None, &self.config)
-            .with_context(|| format!("failed to preview {}", metadata.display_path))?;
-
-        self.preview.set_segment(segment);
-        self.refresh_preview_highlights();
-        if force {
-            self.focus = FocusTarget::Preview;
-        }
-        Ok(true)
+    /// Switch the right pane between the token summary and the working set list.
+    fn toggle_summary_view(&mut self) {
+        let message = match self.summary_component.toggle_view() {
+            SummaryView::WorkingSet => "Summary pane shows the working set; W switches back",
+            SummaryView::Summary => "Summary pane shows the token summary",
+        };
+        self.set_status(StatusLevel::Info, message);
     }
 
-    fn refresh_preview_highlights(&mut self) {
-        if let Some(path) = self.preview.path().map(PathBuf::from) {
-            let mut ranges = Vec::new();
-            for item in self.selection.items() {
-                if item.path == path {
-                    if let Some(range) = item.range {
-                        ranges.push(range);
-                    } else {
-                        ranges.push((1, usize::MAX));
-                    }
-                }
-            }
-            self.preview.set_highlights(ranges);
+    /// Move focus to the other half of a split preview.
+    fn swap_split(&mut self) {
+        if let Some(other) = &mut self.split {
+            self.preview.clear_anchor();
+            std::mem::swap(&mut self.preview, other);
+            // A prefetch in flight only lands in the focused half, so let the parked one ask again.
+            other.prefetch_pending = None;
+            self.split_active_right = !self.split_active_right;
         }
     }
 
@@ -553,10 +2374,11 @@ impl UiApp {
             added,
         } = change;
         if let Some(range) = removed {
-            self.selection.remove_selection(&path, Some(range));
+            self.selection.write().remove_selection(&path, Some(range));
         }
         if let Some(range) = added {
             self.selection
+                .write()
                 .add_selection(path.clone(), Some(range), None);
         }
         self.refresh_selection_state()
@@ -570,25 +2392,545 @@ impl UiApp {
         if metadata.is_dir {
             return Ok(());
         }
+        if metadata.skipped.is_some() && !self.forced.contains(&metadata.path) {
+            self.set_status(StatusLevel::Info, skipped_hint(metadata));
+            return Ok(());
+        }
 
-        let existed = self.selection.remove_selection(&metadata.path, None);
-        if !existed {
+        let before = self.total_tokens();
+        let existed = self
+            .selection
+            .write()
+            .remove_selection(&metadata.path, None);
+        let (level, message) = if !existed {
+            let (path, message) = (
+                metadata.path.clone(),
+                format!("Added {}", metadata.display_path),
+            );
             self.selection
-                .add_selection(metadata.path.clone(), None, None);
+                .write()
+                .add_selection(path.clone(), None, None);
+            self.record_use(&path, FileUse::Selection);
+            (StatusLevel::Success, message)
+        } else {
+            (
+                StatusLevel::Info,
+                format!("Removed {}", metadata.display_path),
+            )
+        };
+        self.refresh_selection_state()?;
+        let delta = self.token_delta(before);
+        self.set_status(level, format!("{message}{delta}"));
+        Ok(())
+    }
+
+    /// Select every file currently visible through the tree filter.
+    fn select_filtered(&mut self) -> Result<()> {
+        if self.tree.filter().is_empty() {
             self.set_status(
-                StatusLevel::Success,
-                format!("Added {}", metadata.display_path),
+                StatusLevel::Info,
+                "No filter active; press / to filter first",
             );
+            return Ok(());
+        }
+        let targets: Vec<FileMetadata> = self.tree.filtered_files().into_iter().cloned().collect();
+        if targets.is_empty() {
+            self.set_status(StatusLevel::Info, "No files match the filter");
+            return Ok(());
+        }
+        self.apply_to_files(
+            &targets,
+            MarkedOperation::Select(SelectionSource::SearchMatch),
+        )
+    }
+
+    /// Apply `operation` to the marked files, or to the highlighted file when nothing is marked.
+    fn apply_to_marked(&mut self, operation: MarkedOperation) -> Result<()> {
+        let targets: Vec<FileMetadata> = if self.tree.has_marks() {
+            self.tree.marked_files().into_iter().cloned().collect()
         } else {
+            self.tree
+                .selected_metadata()
+                .filter(|meta| !meta.is_dir)
+                .cloned()
+                .into_iter()
+                .collect()
+        };
+        if targets.is_empty() {
             self.set_status(
                 StatusLevel::Info,
-                format!("Removed {}", metadata.display_path),
+                "No files marked; use * or V to mark entries",
             );
+            return Ok(());
+        }
+        self.tree.clear_marks();
+        self.apply_to_files(&targets, operation)
+    }
+
+    fn apply_to_files(
+        &mut self,
+        targets: &[FileMetadata],
+        operation: MarkedOperation,
+    ) -> Result<()> {
+        let before = self.total_tokens();
+        let mut applied = 0;
+        let mut skipped = 0;
+        for meta in targets {
+            if meta.skipped.is_some() && !self.forced.contains(&meta.path) {
+                skipped += 1;
+                continue;
+            }
+            let changed = match &operation {
+                MarkedOperation::Select(source) => {
+                    let selected = self
+                        .selection
+                        .read()
+                        .items()
+                        .iter()
+                        .any(|item| item.path == meta.path && item.range.is_none());
+                    if !selected {
+                        self.selection.write().add_item(SelectionItem {
+                            path: meta.path.clone(),
+                            range: None,
+                            note: None,
+                            source: *source,
+                        });
+                    }
+                    !selected
+                }
+                MarkedOperation::Deselect => {
+                    self.selection.write().remove_selection(&meta.path, None)
+                }
+                MarkedOperation::Note(note) => {
+                    let ranges: Vec<_> = self
+                        .selection
+                        .read()
+                        .items()
+                        .iter()
+                        .filter(|item| item.path == meta.path)
+                        .map(|item| item.range)
+                        .collect();
+                    if ranges.is_empty() {
+                        self.selection.write().add_selection(
+                            meta.path.clone(),
+                            None,
+                            Some(note.clone()),
+                        );
+                    }
+                    for range in ranges {
+                        self.selection
+                            .write()
+                            .set_note(&meta.path, range, Some(note.clone()));
+                    }
+                    true
+                }
+            };
+            if changed {
+                applied += 1;
+            }
         }
+
         self.refresh_selection_state()?;
+        let verb = match operation {
+            MarkedOperation::Select(_) => "Added",
+            MarkedOperation::Deselect => "Removed",
+            MarkedOperation::Note(_) => "Annotated",
+        };
+        let mut message = format!("{verb} {applied} of {} files", targets.len());
+        message.push_str(&self.token_delta(before));
+        if skipped > 0 {
+            message.push_str(&format!(" ({skipped} skipped; press F on them to include)"));
+        }
+        self.set_status(StatusLevel::Success, message);
+        Ok(())
+    }
+
+    fn estimate_selected_directory(&mut self) {
+        let (dir, display_path) = match self.tree.selected_metadata() {
+            Some(meta) if meta.is_dir => (meta.path.clone(), meta.display_path.clone()),
+            _ => {
+                self.set_status(StatusLevel::Info, "Select a directory to estimate");
+                return;
+            }
+        };
+        let files: Vec<PathBuf> = self
+            .scan
+            .as_ref()
+            .map(|scan| {
+                scan.files
+                    .iter()
+                    .filter(|meta| {
+                        !meta.is_dir
+                            && (meta.skipped.is_none() || self.forced.contains(&meta.path))
+                            && meta.path.starts_with(&dir)
+                    })
+                    .map(|meta| meta.path.clone())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let model = self
+            .selection
+            .read()
+            .model()
+            .and_then(|model| model.parse::<TokenModel>().ok());
+        let mut estimator = self.token_estimator.clone();
+        let cancel = self.background.clone();
+        estimator.set_cancellation(cancel.clone());
+        let sender = self.directory_estimates_tx.clone();
+        self.background_jobs += 1;
+        self.tree
+            .set_directory_estimate(&display_path, DirectoryEstimate::Pending);
+        self.set_status(
+            StatusLevel::Info,
+            format!("Estimating tokens for {display_path}… (esc cancels)"),
+        );
+        thread::spawn(move || {
+            let estimate = estimator.estimate_files(model, &files);
+            if !cancel.is_cancelled() {
+                let _ = sender.send((display_path, estimate));
+            }
+        });
+    }
+
+    /// Annotate the tree with size-based token estimates, when `tokens.approximate` is on.
+    fn approximate_tree_tokens(&mut self) {
+        if !self.config.tokens.approximate() {
+            return;
+        }
+        let Some(scan) = &self.scan else {
+            return;
+        };
+        let model = self
+            .selection
+            .read()
+            .model()
+            .and_then(|model| model.parse::<TokenModel>().ok());
+        let estimator = &self.token_estimator;
+        let files: Vec<(String, usize)> = scan
+            .files
+            .iter()
+            .filter(|meta| !meta.is_dir && meta.skipped.is_none())
+            .map(|meta| {
+                let tokens = estimator.approximate_size(model, &meta.path, meta.size.unwrap_or(0));
+                (meta.display_path.clone(), tokens)
+            })
+            .collect();
+        self.tree.set_approximate_tokens(files);
+    }
+
+    /// Abandon the background searches and directory estimates still running.
+    fn cancel_background(&mut self) {
+        self.background.cancel();
+        self.background = CancellationToken::new();
+        self.background_jobs = 0;
+        self.tree.clear_pending_estimates();
+        self.set_status(StatusLevel::Info, "Cancelled background work");
+    }
+
+    /// Gather workspace statistics in the background; `tick` opens the screen when they arrive.
+    fn collect_workspace_stats(&mut self) -> Result<()> {
+        if !self.quick_open.is_empty() {
+            return Err(anyhow!(
+                "stats need a workspace scan; start llmctx without file arguments"
+            ));
+        }
+        let scan = self
+            .scan
+            .as_ref()
+            .ok_or_else(|| anyhow!("workspace has not been scanned yet"))?;
+        let scan = ScanResult {
+            files: scan.files.clone(),
+            root: scan.root.clone(),
+            truncated: scan.truncated,
+        };
+        let roots = self.workspace_roots.clone();
+        let config = self.config.clone();
+        let estimator = self.token_estimator.clone();
+        let model = self
+            .selection
+            .read()
+            .model()
+            .and_then(|model| model.parse::<TokenModel>().ok());
+        let sender = self.stats_tx.clone();
+        self.set_status(StatusLevel::Info, "Collecting workspace statistics…");
+        thread::spawn(move || {
+            let stats = WorkspaceStats::collect(&scan, &roots, &config, &estimator, model);
+            let _ = sender.send(stats);
+        });
+        Ok(())
+    }
+
+    /// Find the callers and callees of the selected functions in the background; `tick` adds
+    /// them within the token budget.
+    fn start_call_expansion(&mut self) -> Result<()> {
+        let selections = self.selection.read().items().to_vec();
+        if selections.is_empty() {
+            return Err(anyhow!("select some code before expanding its calls"));
+        }
+        let files: Vec<FileMetadata> = self
+            .scan
+            .as_ref()
+            .map(|scan| scan.files.clone())
+            .unwrap_or_default();
+        let sender = self.expansion_tx.clone();
+        self.set_status(
+            StatusLevel::Info,
+            "Following calls from the selection by name (heuristic)…",
+        );
+        thread::spawn(move || {
+            let index = SymbolIndex::build(&files);
+            let _ = sender.send(index.expand(&selections));
+        });
+        Ok(())
+    }
+
+    /// Select `expansions` in order while they fit the token budget, or all of them without one.
+    fn add_call_expansions(&mut self, expansions: Vec<CallExpansion>) -> Result<()> {
+        if expansions.is_empty() {
+            self.set_status(
+                StatusLevel::Info,
+                "No callers or callees found outside the selection",
+            );
+            return Ok(());
+        }
+        let budget = self.token_estimator.token_budget() as usize;
+        let mut used = self
+            .last_summary
+            .as_ref()
+            .map(|summary| summary.total_tokens)
+            .unwrap_or_default();
+        let (mut callees, mut callers, mut added_tokens, mut over_budget) = (0, 0, 0, 0);
+        for expansion in expansions {
+            let (path, range) = expansion.selection();
+            let item = SelectionItem {
+                path,
+                range: Some(range),
+                note: None,
+                source: SelectionSource::CallGraph,
+            };
+            let Ok(estimate) = self.token_estimator.estimate_selection(&item) else {
+                continue;
+            };
+            if budget > 0 && used + estimate.tokens > budget {
+                over_budget += 1;
+                continue;
+            }
+            used += estimate.tokens;
+            added_tokens += estimate.tokens;
+            match expansion.direction {
+                CallDirection::Callee => callees += 1,
+                CallDirection::Caller => callers += 1,
+            }
+            self.selection.write().add_item(item);
+        }
+        let mut message = format!(
+            "Added {callees} callee(s) and {callers} caller(s), about {added_tokens} tokens"
+        );
+        if over_budget > 0 {
+            message.push_str(&format!("; {over_budget} more would exceed the budget"));
+        }
+        self.set_status(StatusLevel::Success, message);
+        self.refresh_selection_state()
+    }
+
+    /// Search the contents of scanned files in the background; `tick` opens the results pane.
+    fn start_search(&mut self, pattern: &str) -> Result<()> {
+        let backend: SearchBackend = self.config.search.backend().parse()?;
+        let files: Vec<FileMetadata> = self
+            .scan
+            .as_ref()
+            .map(|scan| {
+                scan.files
+                    .iter()
+                    .filter(|meta| !meta.is_dir)
+                    .filter(|meta| meta.skipped.is_none() || self.forced.contains(&meta.path))
+                    .map(|meta| FileMetadata {
+                        skipped: None,
+                        ..meta.clone()
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let roots: Vec<PathBuf> = self
+            .workspace_roots
+            .iter()
+            .map(|root| root.path.clone())
+            .collect();
+        let pattern = pattern.to_string();
+        let sender = self.search_tx.clone();
+        let cancel = self.background.clone();
+        self.background_jobs += 1;
+        self.set_status(
+            StatusLevel::Info,
+            format!("Searching for '{pattern}'… (esc cancels)"),
+        );
+        thread::spawn(move || {
+            let search = Search::new(backend).with_cancellation(cancel.clone());
+            let results = search.query(&files, &roots, &pattern);
+            if !cancel.is_cancelled() {
+                let _ = sender.send(results);
+            }
+        });
         Ok(())
     }
 
+    /// Rank indexed chunks against `query` in the background, showing them like search hits.
+    fn start_semantic_search(&mut self, query: &str) {
+        let root = self.workspace_root();
+        let store = SemanticIndexStore::new(&root);
+        let display_paths = self.path_lookup.clone();
+        let query = query.to_string();
+        let sender = self.search_tx.clone();
+        let cancel = self.background.clone();
+        self.background_jobs += 1;
+        self.set_status(StatusLevel::Info, format!("Ranking chunks for '{query}'…"));
+        thread::spawn(move || {
+            let results = store.load().and_then(|index| {
+                let index = index.ok_or_else(|| {
+                    anyhow!("no similarity index yet; run `llmctx index` to build it")
+                })?;
+                let embedder = index.embedder()?;
+                let hits = index
+                    .query(&root, embedder.as_ref(), &query, SEMANTIC_RESULTS)?
+                    .into_iter()
+                    .map(|found| SearchHit {
+                        display_path: display_paths
+                            .get(&found.path)
+                            .cloned()
+                            .unwrap_or_else(|| path_relative_to(&found.path, &root)),
+                        line: found.start,
+                        end: found.end,
+                        text: format!("{:.2}  {}", found.score, found.summary),
+                        path: found.path,
+                    })
+                    .collect();
+                Ok(SearchResults {
+                    pattern: format!("similar: {query}"),
+                    hits,
+                    truncated: false,
+                })
+            });
+            if !cancel.is_cancelled() {
+                let _ = sender.send(results);
+            }
+        });
+    }
+
+    fn handle_search_results_key(&mut self, key: KeyEvent) -> Result<()> {
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => self.search_results.select_next(),
+            KeyCode::Char('k') | KeyCode::Up => self.search_results.select_previous(),
+            KeyCode::Char(' ') | KeyCode::Char('*') => {
+                self.search_results.toggle_mark();
+                self.search_results.select_next();
+            }
+            KeyCode::Char('A') => self.search_results.toggle_all(),
+            KeyCode::Char('a') => self.add_marked_hits()?,
+            KeyCode::Enter => {
+                let hit = self
+                    .search_results
+                    .selected_hit()
+                    .map(|hit| (hit.path.clone(), hit.line));
+                self.search_results.close();
+                if let Some((path, line)) = hit {
+                    self.jump_to_selection(&path, Some((line, line)))?;
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('q') => self.search_results.close(),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn handle_suggestions_key(&mut self, key: KeyEvent) -> Result<()> {
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => self.suggestions.select_next(),
+            KeyCode::Char('k') | KeyCode::Up => self.suggestions.select_previous(),
+            KeyCode::Char(' ') | KeyCode::Char('x') => {
+                self.suggestions.toggle_pruned();
+                self.suggestions.select_next();
+            }
+            KeyCode::Enter | KeyCode::Char('a') => self.accept_suggestions()?,
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.suggestions.close();
+                self.set_status(StatusLevel::Info, "Discarded the suggestions");
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Select every suggestion that was not pruned as a whole file.
+    fn accept_suggestions(&mut self) -> Result<()> {
+        let kept: Vec<(PathBuf, usize)> = self
+            .suggestions
+            .kept()
+            .into_iter()
+            .map(|item| (item.path.clone(), item.tokens))
+            .collect();
+        self.suggestions.close();
+        let tokens: usize = kept.iter().map(|(_, tokens)| tokens).sum();
+        let first = kept.first().map(|(path, _)| path.clone());
+        let count = kept.len();
+        for (path, _) in kept {
+            self.selection.write().add_item(SelectionItem {
+                path,
+                range: None,
+                note: None,
+                source: SelectionSource::Suggested,
+            });
+        }
+        if let Some(path) = first {
+            self.jump_to_selection(&path, None)?;
+        }
+        self.set_status(
+            StatusLevel::Success,
+            format!("Added {count} suggested file(s), about {tokens} tokens"),
+        );
+        self.refresh_selection_state()
+    }
+
+    /// Add the marked hits (or the one under the cursor) as merged range selections per file.
+    fn add_marked_hits(&mut self) -> Result<()> {
+        let hits = self.search_results.marked_hits();
+        let count = hits.len();
+        let items = hit_selections(hits, self.config.export.merge_gap_lines());
+        let files: HashSet<&Path> = items.iter().map(|item| item.path.as_path()).collect();
+        let message = format!(
+            "Added {count} hit(s) as {} selection(s) across {} file(s)",
+            items.len(),
+            files.len()
+        );
+        for item in items {
+            self.selection.write().add_item(item);
+        }
+        self.search_results.close();
+        self.set_status(StatusLevel::Success, message);
+        self.refresh_selection_state()
+    }
+
+    /// Collect finished prefetches and start loading the next chunk once the cursor nears the end
+    /// of the loaded segment, so `load_more` does not block on IO and highlighting.
+    fn prefetch_preview(&mut self) {
+        while let Some(segment) = self.prefetch.try_recv() {
+            self.preview.store_prefetched(segment);
+        }
+        let Some((path, range)) = self.preview.prefetch_request(&self.config) else {
+            return;
+        };
+        let service = Arc::clone(&self.preview_service);
+        let config = self.config.clone();
+        self.prefetch
+            .request(move || match service.preview(&path, Some(range), &config) {
+                Ok(segment) => Some(segment),
+                Err(err) => {
+                    tracing::debug!(path = %path.display(), error = %err, "preview prefetch failed");
+                    None
+                }
+            });
+    }
+
     fn execute_command(&mut self, command: &str) -> Result<()> {
         if command.is_empty() {
             return Ok(());
@@ -603,19 +2945,49 @@ impl UiApp {
                 self.tree.set_filter(rest);
                 self.set_status(StatusLevel::Success, "Filter applied");
             }
-            "clear" => {
-                if rest == "filter" || rest.is_empty() {
+            "clear" => match rest {
+                "" | "filter" => {
                     self.tree.clear_filter();
                     self.set_status(StatusLevel::Info, "Filter cleared");
                 }
+                "selections" | "selection" | "all" => {
+                    if self.selection.read().is_empty() {
+                        return Err(anyhow!("nothing is selected"));
+                    }
+                    self.confirm.confirm(
+                        "Clear selections?",
+                        format!("Remove all {} selection(s)?", self.selection.read().len()),
+                        ConfirmedAction::ClearSelections,
+                    );
+                }
+                other => {
+                    return Err(anyhow!(
+                        "cannot clear '{other}' (expected filter or selections)"
+                    ));
+                }
+            },
+            "select"
+                if !rest.is_empty()
+                    && !rest.starts_with("--force")
+                    && parse_range(rest).is_none() =>
+            {
+                self.select_spec(rest)?;
             }
             "select" => {
+                let rest = match rest.strip_prefix("--force") {
+                    Some(range) => {
+                        self.force_current()?;
+                        range.trim()
+                    }
+                    None => rest,
+                };
                 let range = parse_range(rest).ok_or_else(|| anyhow!("invalid range"))?;
                 let segment = self
                     .preview
                     .segment()
                     .ok_or_else(|| anyhow!("open a preview first"))?;
                 self.selection
+                    .write()
                     .add_selection(segment.path.clone(), Some(range), None);
                 self.set_status(
                     StatusLevel::Success,
@@ -628,28 +3000,109 @@ impl UiApp {
                 );
                 self.refresh_selection_state()?;
             }
-            "export" => {
+            "goto" => {
+                let line: usize = rest
+                    .parse()
+                    .map_err(|_| anyhow!("goto requires a line number"))?;
+                self.goto_line(line)?;
+                self.focus = FocusTarget::Preview;
+            }
+            "recipe" => {
+                self.run_recipe(rest)?;
+            }
+            "select-filtered" => {
+                self.select_filtered()?;
+            }
+            "note" => {
                 if rest.is_empty() {
-                    self.perform_export(None, true)?;
-                } else {
-                    self.perform_export(Some(PathBuf::from(rest)), true)?;
+                    return Err(anyhow!("note requires text"));
+                }
+                self.apply_to_marked(MarkedOperation::Note(rest.to_string()))?;
+            }
+            "copy-link" => {
+                self.copy_permalink()?;
+            }
+            "copy-mentions" => {
+                self.copy_mentions(rest == "inline")?;
+            }
+            "stats" => {
+                self.collect_workspace_stats()?;
+            }
+            "messages" => {
+                self.messages.open();
+            }
+            "expand-calls" => {
+                self.start_call_expansion()?;
+            }
+            "search" => {
+                if rest.is_empty() {
+                    return Err(anyhow!("search requires a pattern"));
+                }
+                self.start_search(rest)?;
+            }
+            // `semantic` was the command's first name.
+            "similar" | "semantic" => {
+                if rest.is_empty() {
+                    return Err(anyhow!("similar requires a query"));
+                }
+                self.start_semantic_search(rest);
+            }
+            "export" => {
+                let (open, rest) = match rest.strip_prefix("--open") {
+                    Some(path) => (true, path.trim()),
+                    None => (false, rest),
+                };
+                let target = (!rest.is_empty()).then(|| PathBuf::from(rest));
+                match target {
+                    Some(path) if path.exists() => self.confirm.confirm(
+                        "Overwrite export?",
+                        format!("{} already exists. Replace it?", path.display()),
+                        ConfirmedAction::OverwriteExport { path, open },
+                    ),
+                    target => self.perform_export(target, true, open, false)?,
                 }
             }
             "save" => {
                 self.save_session()?;
             }
+            "delete-session" => {
+                if !self.session_store.path().exists() {
+                    return Err(anyhow!("no saved session to delete"));
+                }
+                self.confirm.confirm(
+                    "Delete session?",
+                    format!(
+                        "Delete {}? The current selection stays until you quit.",
+                        self.session_store.path().display()
+                    ),
+                    ConfirmedAction::DeleteSession,
+                );
+            }
             "model" => {
                 if rest.is_empty() {
                     return Err(anyhow!("model command requires an identifier"));
                 }
-                self.selection.set_model(rest.to_string());
+                self.selection.write().set_model(rest.to_string());
+                self.approximate_tree_tokens();
                 self.refresh_selection_state()?;
                 self.set_status(StatusLevel::Success, format!("Model set to {rest}"));
             }
+            "theme" => {
+                self.choose_theme(rest)?;
+            }
+            "split" => {
+                self.toggle_split();
+            }
+            "working-set" => {
+                self.toggle_summary_view();
+            }
+            "zen" => {
+                self.toggle_zen();
+            }
             "help" => {
                 self.set_status(
                     StatusLevel::Info,
-                    "Commands: filter, select <start-end>, export [path], save, model <id>",
+                    "Commands: filter, select [--force] <start-end>, select <path[:start-end]>, select-filtered, expand-calls (heuristic: calls matched by name), search <pattern>, similar <query>, note <text>, goto <line>, copy-link, copy-mentions [inline], split, working-set, zen, stats, messages, export [--open] [path], recipe [name], theme [name], save, delete-session, clear [filter|selections], model <id>",
                 );
             }
             other => {
@@ -659,16 +3112,104 @@ impl UiApp {
         Ok(())
     }
 
-    fn perform_export(&mut self, target: Option<PathBuf>, copy: bool) -> Result<()> {
-        if self.selection.is_empty() {
+    /// Copy a forge permalink for the previewed file at the active range or cursor line.
+    fn copy_permalink(&mut self) -> Result<()> {
+        let path = self
+            .preview
+            .path()
+            .ok_or_else(|| anyhow!("open a preview first"))?
+            .to_path_buf();
+        let metadata = git::metadata_for_path(&path)
+            .ok_or_else(|| anyhow!("{} is not inside a git repository", path.display()))?;
+        let link = metadata
+            .permalink(&path, self.preview.link_range())
+            .ok_or_else(|| anyhow!("no GitHub or GitLab remote to link to"))?;
+        self.exporter.copy_to_clipboard(&link)?;
+        if metadata.has_local_edits(&path) {
+            self.set_status(
+                StatusLevel::Error,
+                format!("Copied {link}, but the file has local edits the link does not show"),
+            );
+        } else {
+            self.set_status(StatusLevel::Success, format!("Copied {link}"));
+        }
+        Ok(())
+    }
+
+    /// Copy an `@path` mention for each selected file, one per line or space-separated.
+    fn copy_mentions(&mut self, inline: bool) -> Result<()> {
+        if self.selection.read().is_empty() {
+            self.set_status(StatusLevel::Error, "No selections to copy");
+            return Ok(());
+        }
+        let mut options = ExportOptions::from_config(&self.config);
+        options.format = if inline {
+            ExportFormat::MentionsInline
+        } else {
+            ExportFormat::Mentions
+        };
+        options.roots = self.workspace_roots.clone();
+        let bundle = self.selection.read().to_bundle();
+        let mentions = self.exporter.render_bundle(&bundle, None, &options)?;
+        self.exporter.copy_to_clipboard(&mentions)?;
+        // One mention per file, however many ranges of it are selected.
+        let count = bundle
+            .items
+            .iter()
+            .map(|item| &item.path)
+            .collect::<HashSet<_>>()
+            .len();
+        self.set_status(
+            StatusLevel::Success,
+            format!("Copied {count} file mention(s)"),
+        );
+        Ok(())
+    }
+
+    /// Export the selection, then open it per `export.post_action`; `open` picks the editor
+    /// when no post action is configured.
+    fn perform_export(
+        &mut self,
+        target: Option<PathBuf>,
+        copy: bool,
+        open: bool,
+        overwrite: bool,
+    ) -> Result<()> {
+        if self.selection.read().is_empty() {
             self.set_status(StatusLevel::Error, "No selections to export");
             return Ok(());
         }
 
         let mut options = ExportOptions::from_config(&self.config);
-        options.copy_to_clipboard = copy;
+        options.copy_to_clipboard = copy && !self.headless;
+        options.output_path = target;
+        options.overwrite = overwrite;
+        let path = self.export_with(options)?;
+        self.set_status(
+            StatusLevel::Success,
+            format!("Exported selection to {}", path.display()),
+        );
+        let action = match self.config.export.post_action().parse()? {
+            PostAction::None if open => PostAction::OpenEditor,
+            action => action,
+        };
+        if action != PostAction::None {
+            self.pending_open = Some((action, path));
+        }
+        Ok(())
+    }
 
-        let path = if let Some(path) = target {
+    /// Export the selection with `options`, defaulting the output to a file under
+    /// `.llmctx/exports/` named by `export.filename`. Returns the written path.
+    fn export_with(&mut self, mut options: ExportOptions) -> Result<PathBuf> {
+        options.roots = self.workspace_roots.clone();
+        // Stripping may have been turned on from the summary's suggestions.
+        options.strip = self.token_estimator.strip();
+        options.snippets = load_snippets(
+            &Config::workspace_dir(&self.workspace_root()),
+            &self.config.export.snippets(),
+        )?;
+        let path = if let Some(path) = options.output_path.take() {
             path
         } else {
             let snapshot = self
@@ -678,46 +3219,147 @@ impl UiApp {
                 .map(|dir| dir.join("exports"))
                 .unwrap_or_else(|| PathBuf::from(".llmctx/exports"));
             fs::create_dir_all(&snapshot).context("failed to create export directory")?;
-            let timestamp = OffsetDateTime::now_utc().format(format_description!(
-                "[year][month][day]-[hour][minute][second]"
-            ))?;
-            snapshot.join(format!(
-                "context-{timestamp}.{}",
-                options.format.extension()
-            ))
+            let name = expand_filename(
+                &self.config.export.filename(),
+                &self.workspace_root(),
+                options.format,
+                OffsetDateTime::now_utc(),
+            )?;
+            unused_output_path(snapshot.join(name))?
         };
         options.output_path = Some(path.clone());
 
-        let summary = self.selection.summarize_tokens(&self.token_estimator)?;
+        let summary = self
+            .selection
+            .read()
+            .summarize_tokens(&self.token_estimator)?;
         if let Some(ref data) = summary {
             self.summary_component.update(data.clone());
             self.last_summary = Some(data.clone());
         }
 
-        let bundle = self.selection.to_bundle();
-        self.exporter.export(&bundle, summary.as_ref(), &options)?;
+        // Coalesce a copy so the spans exported don't rewrite the ranges being edited.
+        let mut exported = self.selection.snapshot();
+        exported.normalize(self.config.export.merge_gap_lines());
+        let summary = exported.summarize_tokens(&self.token_estimator)?;
+        self.exporter
+            .export(&exported.to_bundle(), summary.as_ref(), &options)?;
+        Ok(path)
+    }
+
+    /// Select `spec`, a `path[:start-end][#note]` relative to the workspace root or as the tree
+    /// shows it.
+    fn select_spec(&mut self, spec: &str) -> Result<()> {
+        let spec = SelectionSpec::parse(spec).map_err(|err| anyhow!(err))?;
+        let root = self.workspace_root();
+        let path = self
+            .path_lookup
+            .iter()
+            .find(|(path, display)| {
+                **path == root.join(&spec.path) || Path::new(display.as_str()) == spec.path
+            })
+            .map(|(path, _)| path.clone())
+            .ok_or_else(|| anyhow!("{} is not in the workspace tree", spec.path.display()))?;
 
+        let before = self.total_tokens();
+        self.selection
+            .write()
+            .add_selection(path.clone(), spec.range, spec.note);
+        self.record_use(&path, FileUse::Selection);
+        self.refresh_selection_state()?;
+        let range = spec
+            .range
+            .map(|(start, end)| format!(":{start}-{end}"))
+            .unwrap_or_default();
+        let delta = self.token_delta(before);
         self.set_status(
             StatusLevel::Success,
-            format!("Exported selection to {}", path.display()),
+            format!("Selected {}{range}{delta}", spec.path.display()),
         );
         Ok(())
     }
 
+    /// Replace the selection with a configured recipe's and export it with the recipe's settings.
+    fn run_recipe(&mut self, name: &str) -> Result<()> {
+        if name.is_empty() {
+            let names: Vec<&str> = self.config.recipes.keys().map(String::as_str).collect();
+            let message = if names.is_empty() {
+                "No recipes configured; add [recipes.<name>] to .llmctx/config.toml".to_string()
+            } else {
+                format!("Recipes: {}", names.join(", "))
+            };
+            self.set_status(StatusLevel::Info, message);
+            return Ok(());
+        }
+        if !self.config.recipes.contains_key(name) {
+            return Err(anyhow!("unknown recipe '{name}'"));
+        }
+        let count = self.selection.read().len();
+        if count == 0 {
+            return self.apply_recipe(name);
+        }
+        self.confirm.confirm(
+            "Replace selection?",
+            format!("Recipe {name} replaces all {count} selection(s). Run it?"),
+            ConfirmedAction::RunRecipe(name.to_string()),
+        );
+        Ok(())
+    }
+
+    /// Replace the selection with the recipe's and export it.
+    fn apply_recipe(&mut self, name: &str) -> Result<()> {
+        let recipe = self
+            .config
+            .recipes
+            .get(name)
+            .cloned()
+            .ok_or_else(|| anyhow!("unknown recipe '{name}'"))?;
+        let selections = RecipeSelections::resolve(&recipe, &self.workspace_roots, &self.config)?;
+        if selections.items.is_empty() {
+            return Err(anyhow!("recipe '{name}' selected no files"));
+        }
+
+        let model = recipe
+            .model
+            .clone()
+            .or_else(|| self.selection.read().model().map(ToString::to_string));
+        self.selection.write().clear();
+        if let Some(model) = model {
+            self.selection.write().set_model(model);
+        }
+        for item in selections.items {
+            self.selection.write().add_item(item);
+        }
+        self.refresh_selection_state()?;
+
+        let mut options = ExportOptions::from_config(&self.config);
+        apply_recipe_options(&recipe, &mut options, &self.workspace_root())?;
+        let path = self.export_with(options)?;
+        let mut message = format!("Ran recipe {name}: exported to {}", path.display());
+        if !selections.unmatched.is_empty() {
+            message.push_str(&format!(
+                " ({} selection(s) matched nothing: {})",
+                selections.unmatched.len(),
+                selections.unmatched.join(", ")
+            ));
+        }
+        self.set_status(StatusLevel::Success, message);
+        Ok(())
+    }
+
     fn save_session(&mut self) -> Result<()> {
-        let root = self
-            .scan
-            .as_ref()
-            .map(|scan| scan.root.clone())
-            .unwrap_or_else(|| PathBuf::from("."));
+        let root = self.workspace_root();
         let selections: Vec<SelectionRecord> = self
             .selection
+            .read()
             .items()
             .iter()
             .map(|item| {
                 let mut record = SelectionRecord::from(item);
+                record.capture_fingerprint(self.vfs.as_ref(), &item.path);
+                // Saved with `/` so a session checked in on one platform restores on another.
                 if let Ok(relative) = item.path.strip_prefix(&root) {
-                    record.path = relative.display().to_string();
+                    record.item.path = PathBuf::from(slash_path(relative));
                 }
                 record
             })
@@ -735,33 +3377,46 @@ impl UiApp {
             selections,
             focused_path: focused,
             filter,
-            model: self.selection.model().map(ToString::to_string),
+            model: self.selection.read().model().map(ToString::to_string),
+            marks: self.marks.clone(),
+            ..SessionSnapshot::default()
         };
         self.session_store.save(&snapshot)?;
+        self.mark_session_saved();
         self.set_status(StatusLevel::Success, "Session saved");
         Ok(())
     }
 
     fn restore_session(&mut self, snapshot: SessionSnapshot) -> Result<()> {
         if let Some(model) = snapshot.model {
-            self.selection.set_model(model);
+            self.selection.write().set_model(model);
+            self.approximate_tree_tokens();
         }
-        let root = self
-            .scan
-            .as_ref()
-            .map(|scan| scan.root.clone())
-            .unwrap_or_else(|| PathBuf::from("."));
+        let root = self.workspace_root();
+        let mut stale = 0usize;
         for record in snapshot.selections {
-            let mut item = record.into_selection_item();
-            if item.path.is_relative() {
-                item.path = root.join(item.path);
+            let path = if record.item.path.is_relative() {
+                root.join(native_path(&record.item.path))
+            } else {
+                record.item.path.clone()
+            };
+            if record.is_stale(self.vfs.as_ref(), &path) {
+                stale += 1;
             }
-            self.selection
-                .add_selection(item.path.clone(), item.range, item.note.clone());
+            let mut item = record.into_selection_item();
+            item.path = path;
+            self.selection.write().add_item(item);
+        }
+        if stale > 0 {
+            self.set_status(
+                StatusLevel::Info,
+                format!("{stale} restored selection(s) changed since the session was saved"),
+            );
         }
         if let Some(filter) = snapshot.filter {
             self.tree.set_filter(filter);
         }
+        self.marks = snapshot.marks;
         if let Some(path) = snapshot.focused_path {
             self.tree.focus_path(&path);
             self.preview_current(false)?;
@@ -770,11 +3425,34 @@ impl UiApp {
     }
 
     fn refresh_selection_state(&mut self) -> Result<()> {
+        self.shown_revision = self.selection.read().revision();
         self.rebuild_selected_paths();
         self.refresh_preview_highlights();
 
-        match self.selection.summarize_tokens(&self.token_estimator)? {
+        let summary = self
+            .selection
+            .read()
+            .summarize_tokens(&self.token_estimator)?;
+        match summary {
             Some(summary) => {
+                // Line counts come from the estimates rather than reading every selection again.
+                let stats = self.selection.read().to_bundle().stats(|item| {
+                    summary
+                        .items
+                        .iter()
+                        .find(|entry| {
+                            entry.item.path == item.path && entry.item.range == item.range
+                        })
+                        .map_or(0, |entry| entry.lines)
+                });
+                self.summary_component.set_stats(stats);
+                let warnings = match &self.ignore_rules {
+                    Some(rules) => self.selection.read().validate(self.vfs.as_ref(), rules),
+                    None => Vec::new(),
+                };
+                self.summary_component.set_warnings(warnings);
+                let hints = self.fit_hints(&summary)?;
+                self.summary_component.set_hints(hints);
                 self.summary_component.update(summary.clone());
                 self.last_summary = Some(summary);
             }
@@ -786,33 +3464,61 @@ impl UiApp {
         Ok(())
     }
 
-    fn rebuild_selected_paths(&mut self) {
-        self.selected_paths.clear();
-        let root = self
-            .scan
-            .as_ref()
-            .map(|scan| scan.root.clone())
-            .unwrap_or_else(|| PathBuf::from("."));
-        for item in self.selection.items() {
-            let display = self
-                .path_lookup
-                .get(&item.path)
-                .cloned()
-                .unwrap_or_else(|| path_relative_to(&item.path, &root));
-            self.selected_paths.insert(display);
+    /// Suggestions for `summary`, estimating what stripping would save only when over budget.
+    fn fit_hints(&self, summary: &BundleTokenSummary) -> Result<Vec<FitHint>> {
+        let budget = summary.token_budget as usize;
+        if budget == 0 || summary.total_tokens <= budget {
+            return Ok(Vec::new());
         }
+        let strip = match strip_target(self.token_estimator.strip()) {
+            Some(options) => {
+                let mut estimator = self.token_estimator.clone();
+                estimator.set_strip(options);
+                let stripped = self.selection.read().summarize_tokens(&estimator)?;
+                stripped.map(|stripped| (options, stripped.total_tokens))
+            }
+            None => None,
+        };
+        Ok(fit_hints(summary, strip))
+    }
+
+    fn rebuild_selected_paths(&mut self) {
+        self.selected_paths = self
+            .selection
+            .read()
+            .items()
+            .iter()
+            .map(|item| self.display_path_for(&item.path))
+            .collect();
     }
 
     fn set_status<S: Into<String>>(&mut self, level: StatusLevel, message: S) {
-        self.status = Some(StatusMessage::new(level, message.into()));
+        self.messages.post_at(level, message.into(), self.now);
+    }
+
+    /// Show `err` in the status bar, followed by a remediation hint for domain errors.
+    fn report_error(&mut self, err: &anyhow::Error) {
+        let message = match DomainError::find(err) {
+            Some(domain) => format!("{domain} — {}", domain.hint()),
+            None => err.to_string(),
+        };
+        self.set_status(StatusLevel::Error, message);
+    }
+}
+
+/// Status text for a file the scan skipped, pointing large files at the `F` override.
+fn skipped_hint(metadata: &FileMetadata) -> String {
+    match metadata.skipped {
+        Some(SkipReason::LargeFile) => format!(
+            "{} is large and was skipped; press F to include it anyway",
+            metadata.display_path
+        ),
+        _ => format!("{} skipped during scan", metadata.display_path),
     }
 }
 
 fn path_relative_to(path: &Path, root: &Path) -> String {
-    path.strip_prefix(root)
-        .unwrap_or(path)
-        .display()
-        .to_string()
+    slash_path(path.strip_prefix(root).unwrap_or(path))
 }
 
 fn parse_range(input: &str) -> Option<(usize, usize)> {
@@ -822,42 +3528,113 @@ fn parse_range(input: &str) -> Option<(usize, usize)> {
     Some((start, end))
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum FocusTarget {
-    FileTree,
-    Preview,
-    CommandPalette,
+/// Destructive action waiting on the confirmation prompt.
+#[derive(Debug, Clone)]
+enum ConfirmedAction {
+    ClearSelections,
+    RunRecipe(String),
+    OverwriteExport { path: PathBuf, open: bool },
+    DeleteSession,
+    SaveAndQuit,
+    Quit,
 }
 
-#[derive(Debug)]
-struct StatusMessage {
-    level: StatusLevel,
-    text: String,
-    expires_at: Instant,
+/// Batch operation applied to the files marked in the tree.
+#[derive(Debug, Clone)]
+enum MarkedOperation {
+    Select(SelectionSource),
+    Deselect,
+    Note(String),
 }
 
-impl StatusMessage {
-    fn new(level: StatusLevel, text: String) -> Self {
-        Self {
-            level,
-            text,
-            expires_at: Instant::now() + Duration::from_secs(4),
-        }
-    }
+/// Which of `m` and `'` is waiting for a mark's letter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MarkPrefix {
+    Set,
+    Jump,
+}
 
-    fn is_expired(&self) -> bool {
-        Instant::now() >= self.expires_at
-    }
+/// Actions reachable through multi-key sequences.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChordAction {
+    Top,
+    ToggleDirectory,
+    CollapseAll,
+    Filter,
+    RecentFiles,
+    Save,
+    Export,
+    Messages,
+    Themes,
 }
 
-#[derive(Debug, Clone, Copy)]
-enum StatusLevel {
-    Info,
-    Success,
-    Error,
+const TREE_CHORDS: &[Chord<ChordAction>] = &[
+    Chord {
+        keys: "gg",
+        label: "first entry",
+        action: ChordAction::Top,
+    },
+    Chord {
+        keys: "za",
+        label: "toggle directory",
+        action: ChordAction::ToggleDirectory,
+    },
+    Chord {
+        keys: "zM",
+        label: "collapse all",
+        action: ChordAction::CollapseAll,
+    },
+];
+
+const PREVIEW_CHORDS: &[Chord<ChordAction>] = &[Chord {
+    keys: "gg",
+    label: "first line (or line n with a count)",
+    action: ChordAction::Top,
+}];
+
+/// `space` chords, available in every pane.
+const LEADER_CHORDS: &[Chord<ChordAction>] = &[
+    Chord {
+        keys: " f",
+        label: "filter files",
+        action: ChordAction::Filter,
+    },
+    Chord {
+        keys: " r",
+        label: "recent files",
+        action: ChordAction::RecentFiles,
+    },
+    Chord {
+        keys: " s",
+        label: "save session",
+        action: ChordAction::Save,
+    },
+    Chord {
+        keys: " e",
+        label: "export",
+        action: ChordAction::Export,
+    },
+    Chord {
+        keys: " m",
+        label: "messages",
+        action: ChordAction::Messages,
+    },
+    Chord {
+        keys: " t",
+        label: "themes",
+        action: ChordAction::Themes,
+    },
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FocusTarget {
+    FileTree,
+    Preview,
+    Summary,
+    CommandPalette,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 struct PreviewState {
     segment: Option<PreviewSegment>,
     cursor: Option<usize>,
@@ -865,19 +3642,62 @@ struct PreviewState {
     highlights: Vec<(usize, usize)>,
     active_range: Option<(usize, usize)>,
     active_path: Option<PathBuf>,
+    layout: PreviewLayout,
+    scroll_mode: bool,
+    /// Next chunk loaded in the background, consumed by `load_more`.
+    prefetched: Option<PreviewSegment>,
+    /// Path and zero-based start line of the prefetch in flight.
+    prefetch_pending: Option<(PathBuf, usize)>,
+    /// First loaded line and cursor line last seen in each file this pane showed.
+    positions: HashMap<PathBuf, (usize, usize)>,
 }
 
 impl PreviewState {
+    fn layout(&self) -> PreviewLayout {
+        self.layout
+    }
+
+    fn toggle_wrap(&mut self) -> bool {
+        self.layout.wrap = !self.layout.wrap;
+        self.layout.horizontal_offset = 0;
+        self.layout.wrap
+    }
+
+    fn is_scroll_mode(&self) -> bool {
+        self.scroll_mode
+    }
+
+    /// Horizontal scrolling only makes sense for clipped lines, so wrapping is switched off.
+    fn enter_scroll_mode(&mut self) {
+        self.scroll_mode = true;
+        self.layout.wrap = false;
+    }
+
+    fn exit_scroll_mode(&mut self) {
+        self.scroll_mode = false;
+    }
+
+    fn scroll_horizontal(&mut self, delta: isize) {
+        self.layout.horizontal_offset = self.layout.horizontal_offset.saturating_add_signed(delta);
+    }
+
+    fn reset_horizontal_scroll(&mut self) {
+        self.layout.horizontal_offset = 0;
+    }
+
     fn segment(&self) -> Option<&PreviewSegment> {
         self.segment.as_ref()
     }
 
     fn set_segment(&mut self, segment: PreviewSegment) {
+        self.remember_position();
         self.cursor = Some(segment.start_line);
         self.anchor = None;
         self.segment = Some(segment);
         self.active_range = None;
         self.active_path = None;
+        self.prefetched = None;
+        self.prefetch_pending = None;
     }
 
     fn set_highlights(&mut self, highlights: Vec<(usize, usize)>) {
@@ -892,21 +3712,103 @@ impl PreviewState {
         self.segment.as_ref().map(|segment| segment.path.as_path())
     }
 
-    fn load_more(&mut self, service: &PreviewService, config: &Config) -> Result<bool> {
-        let segment = match &self.segment {
-            Some(segment) => segment.clone(),
-            None => return Ok(false),
-        };
-        let token = match segment.continuation.clone() {
-            Some(token) => token,
-            None => return Ok(false),
+    fn cursor(&self) -> Option<usize> {
+        self.cursor
+    }
+
+    /// Note where the loaded file is scrolled to, so reopening it returns there.
+    fn remember_position(&mut self) {
+        if let (Some(segment), Some(cursor)) = (&self.segment, self.cursor) {
+            self.positions
+                .insert(segment.path.clone(), (segment.start_line, cursor));
+        }
+    }
+
+    /// First loaded line and cursor line `path` was left at.
+    fn remembered_position(&self, path: &Path) -> Option<(usize, usize)> {
+        self.positions.get(path).copied()
+    }
+
+    /// Put the cursor back on `line`, as far as the loaded chunk allows.
+    fn restore_cursor(&mut self, line: usize) {
+        if let Some(segment) = &self.segment {
+            let max = segment.end_line.max(segment.start_line);
+            self.cursor = Some(line.clamp(segment.start_line, max));
+        }
+    }
+
+    /// Reload the visible lines so they pick up the configured theme, keeping cursor and range.
+    fn rehighlight(&mut self, service: &PreviewService, config: &Config) -> Result<()> {
+        let Some(segment) = &self.segment else {
+            return Ok(());
         };
+        let start = segment.start_line.saturating_sub(1);
+        let next = service.preview(&segment.path, Some(start..segment.end_line), config)?;
+        if let Some(segment) = &mut self.segment {
+            segment.highlighted = next.highlighted;
+        }
+        // A prefetched chunk would still carry the previous theme.
+        self.prefetched = None;
+        self.prefetch_pending = None;
+        Ok(())
+    }
+
+    /// Range of the chunk after the loaded segment, if the file continues.
+    fn next_chunk(&self, config: &Config) -> Option<(PathBuf, Range<usize>)> {
+        let segment = self.segment.as_ref()?;
+        let token = segment.continuation.as_ref()?;
         let mut step = config.defaults.preview_max_lines();
         if step == 0 {
             step = 200;
         }
-        let range = token.start_line..token.start_line + step;
-        let next = service.preview(&segment.path, Some(range), config)?;
+        Some((
+            segment.path.clone(),
+            token.start_line..token.start_line + step,
+        ))
+    }
+
+    /// The next chunk to load in the background, once the cursor is within [`PREFETCH_MARGIN`]
+    /// lines of the end and no prefetch for it is pending or done.
+    fn prefetch_request(&mut self, config: &Config) -> Option<(PathBuf, Range<usize>)> {
+        let segment = self.segment.as_ref()?;
+        let cursor = self.cursor?;
+        if cursor + PREFETCH_MARGIN < segment.end_line {
+            return None;
+        }
+        let (path, range) = self.next_chunk(config)?;
+        let key = (path.clone(), range.start);
+        let prefetched = self
+            .prefetched
+            .as_ref()
+            .is_some_and(|next| next.path == key.0 && next.start_line == range.start + 1);
+        if prefetched || self.prefetch_pending.as_ref() == Some(&key) {
+            return None;
+        }
+        self.prefetch_pending = Some(key);
+        Some((path, range))
+    }
+
+    /// Keep a finished prefetch if it still continues the loaded segment.
+    fn store_prefetched(&mut self, next: PreviewSegment) {
+        let expected = self
+            .prefetch_pending
+            .as_ref()
+            .is_some_and(|(path, start)| *path == next.path && start + 1 == next.start_line);
+        if expected {
+            self.prefetch_pending = None;
+            self.prefetched = Some(next);
+        }
+    }
+
+    fn load_more(&mut self, service: &PreviewService, config: &Config) -> Result<bool> {
+        let Some((path, range)) = self.next_chunk(config) else {
+            return Ok(false);
+        };
+        let next = match self.prefetched.take() {
+            Some(next) if next.path == path && next.start_line == range.start + 1 => next,
+            _ => service.preview(&path, Some(range), config)?,
+        };
+        self.prefetch_pending = None;
         self.cursor = Some(next.start_line);
         self.anchor = None;
         self.segment = Some(next);
@@ -935,19 +3837,8 @@ impl PreviewState {
             } else {
                 (cursor, anchor)
             };
-            let path = segment.path.clone();
-            if self.active_path.as_ref() != Some(&path) {
-                self.active_path = Some(path.clone());
-                self.active_range = None;
-            }
-            let change = RangeChange {
-                path,
-                removed: self.active_range,
-                added: Some(range),
-            };
-            self.active_range = Some(range);
             self.cursor = Some(cursor);
-            return Ok(Some(change));
+            return Ok(Some(self.replace_active_range(segment.path.clone(), range)));
         } else {
             self.anchor = None;
             self.active_range = None;
@@ -957,6 +3848,82 @@ impl PreviewState {
         Ok(None)
     }
 
+    /// Swap the in-progress range selection for `range`, reporting what to remove and add.
+    fn replace_active_range(&mut self, path: PathBuf, range: (usize, usize)) -> RangeChange {
+        if self.active_path.as_ref() != Some(&path) {
+            self.active_path = Some(path.clone());
+            self.active_range = None;
+        }
+        let change = RangeChange {
+            path,
+            removed: self.active_range,
+            added: Some(range),
+        };
+        self.active_range = Some(range);
+        change
+    }
+
+    /// Select `range` as if it had been extended from its first line to its last.
+    fn select_range(&mut self, range: (usize, usize)) -> Option<RangeChange> {
+        let path = self.segment.as_ref()?.path.clone();
+        self.anchor = Some(range.0);
+        self.cursor = Some(range.1);
+        Some(self.replace_active_range(path, range))
+    }
+
+    /// Select from the cursor through the last line of the file, loaded or not.
+    fn select_to_end(&mut self) -> Option<RangeChange> {
+        let segment = self.segment.as_ref()?;
+        let cursor = self.cursor.unwrap_or(segment.start_line);
+        let last = segment.total_lines.max(segment.end_line);
+        if last < cursor {
+            return None;
+        }
+        self.select_range((cursor, last))
+    }
+
+    /// Select every line of the currently loaded chunk.
+    fn select_chunk(&mut self) -> Option<RangeChange> {
+        let segment = self.segment.as_ref()?;
+        if segment.end_line < segment.start_line {
+            return None;
+        }
+        self.select_range((segment.start_line, segment.end_line))
+    }
+
+    /// Select the blank-line-delimited block around the cursor within the loaded chunk.
+    fn select_paragraph(&mut self) -> Option<RangeChange> {
+        let segment = self.segment.as_ref()?;
+        let cursor = self.cursor.unwrap_or(segment.start_line);
+        let blank: Vec<bool> = segment
+            .highlighted
+            .lines
+            .iter()
+            .map(|line| line.spans.iter().all(|span| span.content.trim().is_empty()))
+            .collect();
+        let offset = cursor.checked_sub(segment.start_line)?;
+        if blank.get(offset).is_none_or(|is_blank| *is_blank) {
+            return None;
+        }
+        let first = blank[..offset]
+            .iter()
+            .rposition(|is_blank| *is_blank)
+            .map_or(0, |idx| idx + 1);
+        let last = blank[offset..]
+            .iter()
+            .position(|is_blank| *is_blank)
+            .map_or(blank.len(), |idx| offset + idx)
+            - 1;
+        let start = segment.start_line;
+        self.select_range((start + first, start + last))
+    }
+
+    /// Lines a permalink should cover: the active range, otherwise the cursor line.
+    fn link_range(&self) -> Option<(usize, usize)> {
+        self.active_range
+            .or_else(|| self.cursor.map(|cursor| (cursor, cursor)))
+    }
+
     fn clear_anchor(&mut self) {
         self.anchor = None;
         self.active_range = None;
@@ -970,6 +3937,71 @@ impl PreviewState {
     }
 }
 
+/// Draw one preview pane, or a placeholder when it has no file yet.
+fn render_preview_pane(
+    component: &Preview,
+    frame: &mut Frame<'_>,
+    state: &PreviewState,
+    focused: bool,
+    area: Rect,
+) {
+    if let Some(segment) = state.segment() {
+        component.render(
+            segment,
+            state.highlight_ranges(),
+            state.layout(),
+            focused,
+            area,
+            frame.buffer_mut(),
+        );
+        return;
+    }
+    let block = Block::default()
+        .title("Preview")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(if focused {
+            Color::Cyan
+        } else {
+            Color::DarkGray
+        }));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    let placeholder = Paragraph::new("Select a file to preview")
+        .style(
+            Style::default()
+                .fg(Color::DarkGray)
+                .add_modifier(Modifier::ITALIC),
+        )
+        .wrap(Wrap { trim: true });
+    frame.render_widget(placeholder, inner);
+}
+
+/// Placeholder drawn instead of the panes when the terminal is below the minimum size.
+fn render_too_small(frame: &mut Frame<'_>, area: Rect) {
+    let message = vec![
+        Line::styled(
+            "Terminal too small",
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Line::raw(format!(
+            "{}×{}, need at least {MIN_WIDTH}×{MIN_HEIGHT}",
+            area.width, area.height
+        )),
+    ];
+    let top = area.height.saturating_sub(message.len() as u16) / 2;
+    let centered = Rect {
+        y: area.y + top,
+        height: area.height - top,
+        ..area
+    };
+    let paragraph = Paragraph::new(message)
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true });
+    frame.render_widget(paragraph, centered);
+}
+
 #[derive(Debug)]
 struct RangeChange {
     path: PathBuf,
@@ -982,3 +4014,511 @@ impl TokenEstimator {
         TokenEstimator::from_config(&Config::default())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ratatui::backend::TestBackend;
+
+    use crate::infra::clipboard::MemoryClipboard;
+    use crate::infra::fs::MemoryFs;
+    use crate::ui::clock::ManualClock;
+
+    fn key(ch: char, at: Instant) -> AppEvent {
+        AppEvent::Key {
+            key: KeyEvent::from(KeyCode::Char(ch)),
+            at,
+        }
+    }
+
+    /// An app over `dir` with the default configuration, a memory clipboard, and a manual
+    /// clock, so nothing on the machine running the tests changes its behaviour.
+    fn app_in(dir: &Path) -> UiApp {
+        let mut app = UiApp::builder()
+            .config(Config::default())
+            .clipboard(MemoryClipboard::new())
+            .clock(ManualClock::default())
+            .build()
+            .unwrap()
+            .with_roots(vec![dir.to_path_buf()]);
+        app.bootstrap().unwrap();
+        app
+    }
+
+    /// Every row of the app drawn on a 120×30 test terminal.
+    fn screen(app: &mut UiApp) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(120, 30)).unwrap();
+        app.draw(&mut terminal).unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer
+            .content
+            .chunks(buffer.area.width as usize)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn replayed_keys_resolve_chords_by_their_own_timestamps() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("notes.txt"), "hello world\n").unwrap();
+        let mut app = app_in(dir.path());
+
+        let start = Instant::now();
+        app.replay([key(' ', start), AppEvent::Tick(start)])
+            .unwrap();
+        assert!(app.selection.read().is_empty());
+        let name = dir.path().file_name().unwrap().to_string_lossy();
+        let border = "─".repeat(18usize.saturating_sub(name.chars().count()));
+        let title = format!(
+            "┌Workspace · {name}{border}┐ notes.txt  y copy · b jump to a directory          ┌Selection Summary · by added──────┐"
+        );
+        assert_eq!(
+            screen(&mut app),
+            vec![
+                title.as_str(),
+                "│⌕ filter (press /)            │┌Preview───────────────────────────────────────────┐│No selections                     │",
+                "│▸ • notes.txt ≈3              ││Select a file to preview                          ││                                  │",
+                "│                              ││                                                  ││                                  │",
+                "│                              ││                                                  ││                                  │",
+                "│                              ││                                                  ││                                  │",
+                "│                              ││                                                  ││                                  │",
+                "│                              ││                                                  ││                                  │",
+                "│                              ││                                                  ││                                  │",
+                "│                              ││                                                  ││                                  │",
+                "│                              ││                                                  ││                                  │",
+                "│                              ││                                                  ││                                  │",
+                "│                              ││                                                  ││                                  │",
+                "│                              ││                                                  ││                                  │",
+                "│                              ││                                                  ││                                  │",
+                "│                              ││                                                  ││                                  │",
+                "│                              ││                                                  ││                                  │",
+                "│                              ││                                                  ││                                  │",
+                "│                              ││                                                  ││                                  │",
+                "│                              ││                                                  ││                                  │",
+                "│                              ││                                                  ││                                  │",
+                "│                              ││                                                  ││                ┌space …──────────┐",
+                "│                              ││                                                  ││                │f  filter files  │",
+                "│                              ││                                                  │└────────────────│r  recent files  │",
+                "│                              ││                                                  │j/k move ↵ previe│s  save session  │",
+                "│                              ││                                                  │select · / filter│e  export        │",
+                "│                              ││                                                  │ctrl+s save · ctr│m  messages      │",
+                "│                              ││                                                  │                 │t  themes        │",
+                "└──────────────────────────────┘└──────────────────────────────────────────────────┘                 └─────────────────┘",
+                "────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────",
+            ]
+        );
+
+        app.update(AppEvent::Tick(start + Duration::from_secs(1)))
+            .unwrap();
+        assert_eq!(app.selection.read().len(), 1);
+        assert_eq!(
+            app.messages.current().map(|message| message.text.as_str()),
+            Some("Added notes.txt · +3 tokens (0% of budget)")
+        );
+        // The summary lists the selection by absolute path, cut at the pane's edge.
+        let path = dir.path().join("notes.txt");
+        let largest: String = format!("▲1 {} – 3 tokens", path.display())
+            .chars()
+            .take(34)
+            .collect();
+        let summary_row = format!(
+            "│                              ││                                                  ││{largest:<34}│"
+        );
+        assert_eq!(
+            screen(&mut app),
+            vec![
+                title.as_str(),
+                "│⌕ filter (press /)            │┌Preview───────────────────────────────────────────┐│Model: OpenAI · openai:gpt-4o-mini│",
+                "│▸ • notes.txt ≈3              ││Select a file to preview                          ││Usage: 3 tokens / 120000 tokens   │",
+                "│                              ││                                                  ││(0%)                              │",
+                "│                              ││                                                  ││Characters: 12 · 2 words · 1 lines│",
+                "│                              ││                                                  ││Files: 1 · 1 lines · txt 1        │",
+                "│                              ││                                                  ││█·································│",
+                "│                              ││                                                  ││■ ./ 0% · 3 tokens                │",
+                summary_row.as_str(),
+                "│                              ││                                                  ││                                  │",
+                "│                              ││                                                  ││                                  │",
+                "│                              ││                                                  ││                                  │",
+                "│                              ││                                                  ││                                  │",
+                "│                              ││                                                  ││                                  │",
+                "│                              ││                                                  ││                                  │",
+                "│                              ││                                                  ││                                  │",
+                "│                              ││                                                  ││                                  │",
+                "│                              ││                                                  ││                                  │",
+                "│                              ││                                                  ││                                  │",
+                "│                              ││                                                  ││                                  │",
+                "│                              ││                                                  ││                                  │",
+                "│                              ││                                                  ││                                  │",
+                "│                              ││                                                  ││                                  │",
+                "│                              ││                                                  │└──────────────────────────────────┘",
+                "│                              ││                                                  │j/k move ↵ preview · space toggle   ",
+                "│                              ││                                                  │select · / filter · : palette ·     ",
+                "│                              ││                                                  │ctrl+s save · ctrl+e export         ",
+                "│                              ││                                                  │                                    ",
+                "└──────────────────────────────┘└──────────────────────────────────────────────────┘                                    ",
+                "────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────",
+            ]
+        );
+    }
+
+    #[test]
+    fn scripts_run_palette_commands_and_stop_at_the_first_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("notes.txt"), "one\ntwo\nthree\n").unwrap();
+        let out = dir.path().join("out.md");
+        fs::write(&out, "old").unwrap();
+        let mut app = app_in(dir.path());
+
+        let script = format!(
+            "# comment\n\nselect notes.txt:2-3\nexport {}\nselect missing.rs\nsave\n",
+            out.display()
+        );
+        let mut reported = Vec::new();
+        let err = app
+            .run_script(&script, |line, message| {
+                reported.push((line, message.to_string()))
+            })
+            .unwrap_err();
+
+        assert_eq!(err.to_string(), "line 5: select missing.rs");
+        assert_eq!(
+            reported,
+            vec![
+                (
+                    3,
+                    "Selected notes.txt:2-3 · +3 tokens (0% of budget)".to_string()
+                ),
+                (4, format!("Exported selection to {}", out.display())),
+            ]
+        );
+        assert_ne!(fs::read_to_string(&out).unwrap(), "old");
+        assert_eq!(app.selection.read().items()[0].range, Some((2, 3)));
+    }
+
+    #[test]
+    fn pasted_text_lands_at_the_filter_cursor() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("notes.txt"), "").unwrap();
+        fs::write(dir.path().join("日本語.md"), "").unwrap();
+        let mut app = app_in(dir.path());
+
+        let at = Instant::now();
+        let press = |code| AppEvent::Key {
+            key: KeyEvent::from(code),
+            at,
+        };
+        app.replay([
+            key('/', at),
+            AppEvent::Paste("日本\n".into()),
+            press(KeyCode::Left),
+            press(KeyCode::Backspace),
+            press(KeyCode::End),
+            key('語', at),
+        ])
+        .unwrap();
+        assert_eq!(app.tree.filter(), "本語");
+        let matches: Vec<_> = app
+            .tree
+            .filtered_files()
+            .iter()
+            .map(|meta| meta.display_path.clone())
+            .collect();
+        assert_eq!(matches, vec!["日本語.md"]);
+    }
+
+    #[test]
+    fn pasting_fills_notes_and_starts_a_tree_filter() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("notes.txt"), "").unwrap();
+        fs::write(dir.path().join("readme.md"), "").unwrap();
+        let mut app = app_in(dir.path());
+
+        let at = Instant::now();
+        app.replay([AppEvent::Paste("notes.txt\n".into())]).unwrap();
+        assert!(app.tree.is_filter_active());
+        assert_eq!(app.tree.filter(), "notes.txt");
+
+        app.replay([
+            AppEvent::Key {
+                key: KeyEvent::from(KeyCode::Enter),
+                at,
+            },
+            key('n', at),
+            AppEvent::Paste("check the\r\n\r\nencoding\n".into()),
+            AppEvent::Key {
+                key: KeyEvent::from(KeyCode::Enter),
+                at,
+            },
+        ])
+        .unwrap();
+        let items = app.selection.read().items().to_vec();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].note.as_deref(), Some("check the encoding"));
+    }
+
+    #[test]
+    fn forcing_includes_large_files_but_not_binary_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let large = "log line\n".repeat(233_100);
+        fs::write(dir.path().join("a.log"), &large).unwrap();
+        fs::write(dir.path().join("b.log"), &large).unwrap();
+        fs::write(dir.path().join("c.bin"), [0u8, 159, 146, 150, 0, 1]).unwrap();
+        let mut app = app_in(dir.path());
+
+        app.tree.focus_path("a.log");
+        app.toggle_current_selection().unwrap();
+        assert!(app.selection.read().is_empty());
+        app.force_current().unwrap();
+        app.toggle_current_selection().unwrap();
+        let items = app.selection.read().items().to_vec();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].path, dir.path().join("a.log"));
+
+        app.tree.focus_path("b.log");
+        app.run_script("select --force 2-3\n", |_, _| {}).unwrap();
+        let items = app.selection.read().items().to_vec();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[1].path, dir.path().join("b.log"));
+        assert_eq!(items[1].range, Some((2, 3)));
+
+        app.tree.focus_path("c.bin");
+        let err = app.force_current().unwrap_err();
+        assert!(err.to_string().contains("binary"), "{err}");
+        app.toggle_current_selection().unwrap();
+        assert_eq!(app.selection.read().len(), 2);
+        assert!(!app.forced.contains(&dir.path().join("c.bin")));
+    }
+
+    #[test]
+    fn builder_injects_config_clipboard_and_clock() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("notes.txt"), "hello\n").unwrap();
+        let clipboard = MemoryClipboard::new();
+        let clock = ManualClock::default();
+        let config = Config::from_toml_layer("[keybindings]\nchord_timeout_ms = 0\n").unwrap();
+        let mut app = UiApp::builder()
+            .config(config)
+            .clipboard(clipboard.clone())
+            .clock(clock.clone())
+            .build()
+            .unwrap()
+            .with_roots(vec![dir.path().to_path_buf()]);
+        app.bootstrap().unwrap();
+
+        app.handle_event(Event::Key(KeyEvent::from(KeyCode::Char('y'))))
+            .unwrap();
+        assert_eq!(clipboard.copies(), vec!["notes.txt"]);
+        assert_eq!(app.messages.current().unwrap().at, clock.now());
+
+        // Chords are off in the injected config, so space selects without waiting for a tick.
+        clock.advance(Duration::from_secs(10));
+        app.handle_event(Event::Key(KeyEvent::from(KeyCode::Char(' '))))
+            .unwrap();
+        assert_eq!(app.selection.read().len(), 1);
+        assert_eq!(app.messages.current().unwrap().at, clock.now());
+    }
+
+    #[test]
+    fn builder_reads_the_workspace_from_an_injected_vfs() {
+        // Files live only in memory; the empty directory takes the frecency index.
+        let dir = tempfile::tempdir().unwrap();
+        let vfs = MemoryFs::new();
+        vfs.write(
+            dir.path().join("src/lib.rs"),
+            "pub fn add(a: u32, b: u32) -> u32 {\n    a + b\n}\n",
+        );
+        vfs.write(dir.path().join("notes.txt"), "one\ntwo\nthree\n");
+        let mut app = UiApp::builder()
+            .config(Config::default())
+            .clipboard(MemoryClipboard::new())
+            .vfs(Arc::new(vfs))
+            .build()
+            .unwrap()
+            .with_roots(vec![dir.path().to_path_buf()]);
+        app.bootstrap().unwrap();
+
+        let files: Vec<_> = app
+            .tree
+            .filtered_files()
+            .iter()
+            .map(|meta| meta.display_path.clone())
+            .collect();
+        assert_eq!(files, vec!["notes.txt", "src/lib.rs"]);
+
+        app.tree.focus_path("src/lib.rs");
+        app.preview_current(false).unwrap();
+        assert_eq!(app.preview.segment().unwrap().total_lines, 3);
+
+        let bundle = dir.path().join("bundle.md");
+        app.run_script(
+            &format!("select src/lib.rs:2-2\nexport {}\n", bundle.display()),
+            |_, _| {},
+        )
+        .unwrap();
+        let summary = app
+            .selection
+            .read()
+            .summarize_tokens(&app.token_estimator)
+            .unwrap()
+            .unwrap();
+        assert!(summary.total_tokens > 0);
+        let exported = fs::read_to_string(&bundle).unwrap();
+        assert!(exported.contains("2 │     a + b"), "{exported}");
+    }
+
+    #[test]
+    fn z_toggles_focus_mode_and_shift_h_scrolls() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("notes.txt"), "hello\n").unwrap();
+        let config = Config::from_toml_layer("[keybindings]\nchord_timeout_ms = 0\n").unwrap();
+        let mut app = UiApp::builder()
+            .config(config)
+            .clipboard(MemoryClipboard::new())
+            .build()
+            .unwrap()
+            .with_roots(vec![dir.path().to_path_buf()]);
+        app.bootstrap().unwrap();
+        let press = |app: &mut UiApp, ch: char| {
+            app.handle_event(Event::Key(KeyEvent::from(KeyCode::Char(ch))))
+                .unwrap();
+        };
+
+        press(&mut app, 'z');
+        assert!(app.zen);
+        assert!(matches!(app.focus, FocusTarget::Preview));
+        press(&mut app, 'H');
+        assert!(app.preview.is_scroll_mode());
+        press(&mut app, 'H');
+        press(&mut app, 'z');
+        assert!(!app.zen);
+        assert!(!app.preview.is_scroll_mode());
+    }
+
+    #[test]
+    fn goto_keeps_lines_above_the_target_loaded() {
+        let dir = tempfile::tempdir().unwrap();
+        let content: String = (1..=1_000).map(|i| format!("line {i}\n")).collect();
+        fs::write(dir.path().join("long.txt"), content).unwrap();
+        let mut app = UiApp::builder()
+            .config(Config::default())
+            .clipboard(MemoryClipboard::new())
+            .build()
+            .unwrap()
+            .with_roots(vec![dir.path().to_path_buf()]);
+        app.bootstrap().unwrap();
+        app.preview_current(true).unwrap();
+        app.focus = FocusTarget::Preview;
+        let press = |app: &mut UiApp, ch: char| {
+            app.handle_event(Event::Key(KeyEvent::from(KeyCode::Char(ch))))
+                .unwrap();
+        };
+
+        press(&mut app, 'G');
+        assert_eq!(app.preview.cursor(), Some(1_000));
+        press(&mut app, 'k');
+        assert_eq!(app.preview.cursor(), Some(999));
+
+        app.run_script("goto 600\n", |_, _| {}).unwrap();
+        assert_eq!(app.preview.cursor(), Some(600));
+        press(&mut app, 'k');
+        assert_eq!(app.preview.cursor(), Some(599));
+    }
+
+    #[test]
+    fn recipes_ask_before_replacing_the_selection() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("lib.rs"), "fn a() {}\n").unwrap();
+        fs::write(dir.path().join("guide.md"), "# Guide\n").unwrap();
+        let config = Config::from_toml_layer(
+            "[recipes.docs]\nselect = [\"guide.md\"]\noutput = \"out.md\"\n",
+        )
+        .unwrap();
+        let mut app = UiApp::builder()
+            .config(config)
+            .clipboard(MemoryClipboard::new())
+            .build()
+            .unwrap()
+            .with_roots(vec![dir.path().to_path_buf()]);
+        app.bootstrap().unwrap();
+        let selected = |app: &UiApp| -> Vec<PathBuf> {
+            let selection = app.selection.read();
+            selection
+                .items()
+                .iter()
+                .map(|item| item.path.clone())
+                .collect()
+        };
+
+        app.execute_command("select lib.rs").unwrap();
+        app.execute_command("recipe docs").unwrap();
+        assert!(app.confirm.is_open());
+        assert_eq!(selected(&app), vec![dir.path().join("lib.rs")]);
+
+        let action = app.confirm.answer('y').expect("confirmed");
+        app.run_confirmed(action).unwrap();
+        assert_eq!(selected(&app), vec![dir.path().join("guide.md")]);
+        assert!(dir.path().join("out.md").exists());
+    }
+
+    #[test]
+    fn mentions_count_files_and_quote_spaced_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("lib.rs"), "a\nb\nc\n").unwrap();
+        fs::write(dir.path().join("read me.md"), "# Docs\n").unwrap();
+        let clipboard = MemoryClipboard::new();
+        let mut app = UiApp::builder()
+            .config(Config::default())
+            .clipboard(clipboard.clone())
+            .build()
+            .unwrap()
+            .with_roots(vec![dir.path().to_path_buf()]);
+        app.bootstrap().unwrap();
+        app.run_script(
+            "select lib.rs:1-1\nselect lib.rs:3-3\nselect read me.md\n",
+            |_, _| {},
+        )
+        .unwrap();
+        assert_eq!(app.selection.read().len(), 3);
+
+        app.copy_mentions(true).unwrap();
+        // Outside a git repository the mentions keep absolute paths.
+        let root = dir.path().display();
+        assert_eq!(
+            clipboard.copies(),
+            vec![format!("@{root}/lib.rs @\"{root}/read me.md\"\n")]
+        );
+        assert_eq!(
+            app.messages.current().unwrap().text,
+            "Copied 2 file mention(s)"
+        );
+    }
+
+    #[test]
+    fn call_expansion_without_a_budget_adds_everything() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("lib.rs"),
+            "fn run() {\n    helper();\n}\n\nfn helper() {\n    let total = 1 + 2;\n}\n",
+        )
+        .unwrap();
+        let config = Config::from_toml_layer("[defaults]\ntoken_budget = 0\n").unwrap();
+        let mut app = UiApp::builder()
+            .config(config)
+            .clipboard(MemoryClipboard::new())
+            .build()
+            .unwrap()
+            .with_roots(vec![dir.path().to_path_buf()]);
+        app.bootstrap().unwrap();
+        app.run_script("select lib.rs:1-3\n", |_, _| {}).unwrap();
+
+        let files = app.scan.as_ref().unwrap().files.clone();
+        let expansions = SymbolIndex::build(&files).expand(app.selection.read().items());
+        assert_eq!(expansions.len(), 1);
+        app.add_call_expansions(expansions).unwrap();
+        let items = app.selection.read().items().to_vec();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[1].range, Some((5, 7)));
+        assert_eq!(items[1].source, SelectionSource::CallGraph);
+    }
+}
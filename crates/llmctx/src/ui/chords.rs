@@ -0,0 +1,214 @@
+//! Multi-key bindings such as `g g` or `space f`, typed as a sequence within a timeout.
+
+use std::time::{Duration, Instant};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// A key sequence bound to an action of type `A`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Chord<A> {
+    /// Keys to type in order; `' '` is the space bar.
+    pub keys: &'static str,
+    pub label: &'static str,
+    pub action: A,
+}
+
+/// What the app should do with a key after [`ChordState::feed`] saw it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChordKey<A> {
+    /// Not part of a chord; handle it as usual.
+    Pass,
+    /// Swallowed as the start of one or more chords.
+    Pending,
+    /// `Esc` dropped the keys typed so far.
+    Cancelled,
+    /// The key completed a chord.
+    Run(A),
+    /// The key continues no chord: handle these earlier keys as usual, then the key itself.
+    Unmatched(Vec<KeyEvent>),
+}
+
+/// Keys typed toward a chord and when they stop waiting for the next one.
+#[derive(Debug)]
+pub struct ChordState {
+    pending: Vec<KeyEvent>,
+    typed: String,
+    deadline: Option<Instant>,
+    /// Zero turns chords off, so every key acts on its own immediately.
+    timeout: Duration,
+}
+
+impl Default for ChordState {
+    fn default() -> Self {
+        Self::with_timeout(Duration::from_millis(500))
+    }
+}
+
+impl ChordState {
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Self {
+            pending: Vec::new(),
+            typed: String::new(),
+            deadline: None,
+            timeout,
+        }
+    }
+
+    /// Match `key` against `chords`, given the keys typed before it.
+    pub fn feed<A: Clone>(
+        &mut self,
+        key: KeyEvent,
+        chords: &[Chord<A>],
+        now: Instant,
+    ) -> ChordKey<A> {
+        if self.timeout.is_zero() {
+            return ChordKey::Pass;
+        }
+        if !self.pending.is_empty() && key.code == KeyCode::Esc {
+            self.flush();
+            return ChordKey::Cancelled;
+        }
+        let ch = match key.code {
+            KeyCode::Char(ch)
+                if !key
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+            {
+                Some(ch)
+            }
+            _ => None,
+        };
+
+        let Some(ch) = ch else {
+            return self.unmatched();
+        };
+        let mut candidate = self.typed.clone();
+        candidate.push(ch);
+        if let Some(chord) = chords.iter().find(|chord| chord.keys == candidate) {
+            self.flush();
+            return ChordKey::Run(chord.action.clone());
+        }
+        if chords
+            .iter()
+            .any(|chord| chord.keys.starts_with(&candidate))
+        {
+            self.pending.push(key);
+            self.typed = candidate;
+            self.deadline = Some(now + self.timeout);
+            return ChordKey::Pending;
+        }
+        self.unmatched()
+    }
+
+    fn unmatched<A>(&mut self) -> ChordKey<A> {
+        if self.pending.is_empty() {
+            ChordKey::Pass
+        } else {
+            ChordKey::Unmatched(self.flush())
+        }
+    }
+
+    /// Keys typed so far, to hand back as ordinary keys once the timeout has passed.
+    pub fn expire(&mut self, now: Instant) -> Vec<KeyEvent> {
+        if self.deadline.is_some_and(|deadline| now >= deadline) {
+            self.flush()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Drop the keys typed so far and return them.
+    pub fn flush(&mut self) -> Vec<KeyEvent> {
+        self.typed.clear();
+        self.deadline = None;
+        std::mem::take(&mut self.pending)
+    }
+
+    /// Whether a chord has been started but not finished.
+    pub fn is_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Keys typed so far, as characters.
+    pub fn typed(&self) -> &str {
+        &self.typed
+    }
+
+    /// The chords that can still complete, with the keys each one needs next.
+    pub fn continuations<'a, A>(
+        &'a self,
+        chords: &'a [Chord<A>],
+    ) -> impl Iterator<Item = (&'a str, &'a Chord<A>)> + 'a {
+        chords.iter().filter_map(|chord| {
+            chord
+                .keys
+                .strip_prefix(self.typed.as_str())
+                .filter(|rest| !rest.is_empty())
+                .map(|rest| (rest, chord))
+        })
+    }
+}
+
+/// Readable name for a chord's keys, e.g. `space f`.
+pub fn describe_keys(keys: &str) -> String {
+    keys.chars()
+        .map(|ch| match ch {
+            ' ' => "space".to_string(),
+            ch => ch.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHORDS: &[Chord<&str>] = &[
+        Chord {
+            keys: "gg",
+            label: "top",
+            action: "top",
+        },
+        Chord {
+            keys: " f",
+            label: "filter",
+            action: "filter",
+        },
+        Chord {
+            keys: " r",
+            label: "recent",
+            action: "recent",
+        },
+    ];
+
+    fn key(ch: char) -> KeyEvent {
+        KeyEvent::from(KeyCode::Char(ch))
+    }
+
+    #[test]
+    fn sequences_complete_fall_through_or_time_out() {
+        let now = Instant::now();
+        let mut state = ChordState::default();
+        assert_eq!(state.feed(key('j'), CHORDS, now), ChordKey::Pass);
+        assert_eq!(state.feed(key('g'), CHORDS, now), ChordKey::Pending);
+        assert_eq!(state.feed(key('g'), CHORDS, now), ChordKey::Run("top"));
+
+        assert_eq!(state.feed(key(' '), CHORDS, now), ChordKey::Pending);
+        let next: Vec<&str> = state.continuations(CHORDS).map(|(rest, _)| rest).collect();
+        assert_eq!(next, vec!["f", "r"]);
+        assert_eq!(describe_keys(state.typed()), "space");
+        assert_eq!(
+            state.feed(key('j'), CHORDS, now),
+            ChordKey::Unmatched(vec![key(' ')])
+        );
+        assert!(!state.is_pending());
+
+        state.feed(key(' '), CHORDS, now);
+        assert!(state.expire(now).is_empty());
+        assert_eq!(state.expire(now + Duration::from_secs(1)), vec![key(' ')]);
+
+        let mut off = ChordState::with_timeout(Duration::ZERO);
+        assert_eq!(off.feed(key(' '), CHORDS, now), ChordKey::Pass);
+    }
+}
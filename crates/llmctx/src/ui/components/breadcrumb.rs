@@ -0,0 +1,155 @@
+//! Path bar above the preview showing where the focused file lives.
+
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::Paragraph;
+
+const SEPARATOR: &str = " › ";
+
+/// Directory being picked from the path bar with `b`.
+#[derive(Debug, Default, Clone)]
+pub struct BreadcrumbState {
+    /// Path being picked from, as displayed in the tree.
+    path: String,
+    /// Index of the highlighted segment while picking.
+    picked: Option<usize>,
+}
+
+impl BreadcrumbState {
+    /// Start picking one of the directories above `path`, beginning with its parent.
+    ///
+    /// Returns `false` when `path` has no directory to pick.
+    pub fn open(&mut self, path: &str) -> bool {
+        let segments = segments(path).len();
+        if segments < 2 {
+            return false;
+        }
+        self.path = path.to_string();
+        self.picked = Some(segments - 2);
+        true
+    }
+
+    /// Stop picking.
+    pub fn close(&mut self) {
+        self.picked = None;
+    }
+
+    /// Whether a directory is being picked.
+    pub fn is_open(&self) -> bool {
+        self.picked.is_some()
+    }
+
+    /// Highlight the directory closer to the root.
+    pub fn select_previous(&mut self) {
+        if let Some(picked) = &mut self.picked {
+            *picked = picked.saturating_sub(1);
+        }
+    }
+
+    /// Highlight the directory closer to the file, stopping at its parent.
+    pub fn select_next(&mut self) {
+        let last_dir = segments(&self.path).len().saturating_sub(2);
+        if let Some(picked) = &mut self.picked {
+            *picked = (*picked + 1).min(last_dir);
+        }
+    }
+
+    /// Display path of the highlighted directory.
+    pub fn picked_directory(&self) -> Option<String> {
+        let picked = self.picked?;
+        Some(segments(&self.path)[..=picked].join("/"))
+    }
+}
+
+/// Components of a display path, ignoring empty ones.
+fn segments(path: &str) -> Vec<&str> {
+    path.split(['/', '\\'])
+        .filter(|segment| !segment.is_empty())
+        .collect()
+}
+
+/// Visual component that renders the path bar.
+#[derive(Debug, Default)]
+pub struct Breadcrumb;
+
+impl Breadcrumb {
+    /// Draw `path`, or the path being picked from with its highlighted segment.
+    pub fn render(
+        &self,
+        frame: &mut Frame<'_>,
+        area: Rect,
+        path: Option<&str>,
+        state: &BreadcrumbState,
+    ) {
+        let (path, picked) = match state.picked {
+            Some(picked) => (Some(state.path.as_str()), Some(picked)),
+            None => (path, None),
+        };
+        let Some(path) = path else {
+            return;
+        };
+
+        let segments = segments(path);
+        let last = segments.len().saturating_sub(1);
+        let mut spans = vec![Span::raw(" ")];
+        for (index, segment) in segments.iter().enumerate() {
+            if index > 0 {
+                spans.push(Span::styled(
+                    SEPARATOR,
+                    Style::default().fg(Color::DarkGray),
+                ));
+            }
+            let style = if Some(index) == picked {
+                Style::default()
+                    .fg(Color::Black)
+                    .bg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD)
+            } else if index == last {
+                Style::default()
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::Gray)
+            };
+            spans.push(Span::styled(segment.to_string(), style));
+        }
+        let hint = if picked.is_some() {
+            "  h/l pick · ↵ jump · esc cancel"
+        } else {
+            "  y copy · b jump to a directory"
+        };
+        spans.push(Span::styled(hint, Style::default().fg(Color::DarkGray)));
+        frame.render_widget(Paragraph::new(Line::from(spans)), area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picking_walks_the_directories_above_the_file() {
+        let mut state = BreadcrumbState::default();
+        assert!(!state.open("README.md"));
+        assert!(!state.is_open());
+
+        assert!(state.open("crates/llmctx/src/lib.rs"));
+        assert_eq!(
+            state.picked_directory().as_deref(),
+            Some("crates/llmctx/src")
+        );
+        state.select_next();
+        assert_eq!(
+            state.picked_directory().as_deref(),
+            Some("crates/llmctx/src")
+        );
+        state.select_previous();
+        state.select_previous();
+        state.select_previous();
+        assert_eq!(state.picked_directory().as_deref(), Some("crates"));
+        state.close();
+        assert_eq!(state.picked_directory(), None);
+    }
+}
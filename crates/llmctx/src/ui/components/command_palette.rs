@@ -8,11 +8,13 @@ use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::Line;
 use ratatui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
 
+use crate::ui::line_editor::LineEditor;
+
 /// Interactive state backing the command palette overlay.
 #[derive(Debug, Default, Clone)]
 pub struct CommandPaletteState {
     visible: bool,
-    input: String,
+    input: LineEditor,
     message: Option<PaletteMessage>,
 }
 
@@ -26,7 +28,7 @@ impl CommandPaletteState {
     /// Reveal the palette with an initial command prefilled.
     pub fn open_with<S: Into<String>>(&mut self, content: S) {
         self.visible = true;
-        self.input = content.into();
+        self.input.set(content);
     }
 
     /// Hide the palette.
@@ -41,27 +43,22 @@ impl CommandPaletteState {
 
     /// Access the current input buffer.
     pub fn input(&self) -> &str {
-        &self.input
+        self.input.text()
     }
 
     /// Replace the current input contents.
     pub fn set_input<S: Into<String>>(&mut self, content: S) {
-        self.input = content.into();
+        self.input.set(content);
     }
 
     /// Consume the current input, leaving the buffer empty.
     pub fn take_input(&mut self) -> String {
-        std::mem::take(&mut self.input)
-    }
-
-    /// Append a character to the buffer.
-    pub fn push_char(&mut self, ch: char) {
-        self.input.push(ch);
+        self.input.take()
     }
 
-    /// Remove the most recently appended character if present.
-    pub fn pop_char(&mut self) {
-        self.input.pop();
+    /// Edit the input buffer in place.
+    pub fn edit_input<R>(&mut self, edit: impl FnOnce(&mut LineEditor) -> R) -> R {
+        edit(&mut self.input)
     }
 
     /// Record a status message to display beneath the input field.
@@ -117,7 +114,11 @@ impl CommandPalette {
             .constraints([Constraint::Length(2), Constraint::Min(0)])
             .split(inner);
 
-        let prompt = Paragraph::new(format!(":{}", state.input()))
+        let (shown, cursor) = state
+            .input
+            .view((layout[0].width as usize).saturating_sub(1));
+        frame.set_cursor(layout[0].x + 1 + cursor as u16, layout[0].y);
+        let prompt = Paragraph::new(format!(":{shown}"))
             .style(Style::default().fg(Color::White))
             .block(Block::default());
         frame.render_widget(prompt, layout[0]);
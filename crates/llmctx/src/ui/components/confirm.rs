@@ -0,0 +1,191 @@
+//! Modal prompt asking the user to confirm a destructive action before it runs.
+
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
+
+/// One answer to a prompt, picked by pressing `key`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Choice<A> {
+    pub key: char,
+    pub label: String,
+    pub action: A,
+}
+
+impl<A> Choice<A> {
+    pub fn new(key: char, label: impl Into<String>, action: A) -> Self {
+        Self {
+            key,
+            label: label.into(),
+            action,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Prompt<A> {
+    title: String,
+    message: String,
+    choices: Vec<Choice<A>>,
+}
+
+/// Pending prompt whose answer carries an action of type `A`.
+///
+/// `Esc` always cancels, and so does `n` unless a choice claims it; there is no default answer,
+/// so a stray `Enter` never confirms anything.
+#[derive(Debug, Clone)]
+pub struct ConfirmState<A> {
+    prompt: Option<Prompt<A>>,
+}
+
+impl<A> Default for ConfirmState<A> {
+    fn default() -> Self {
+        Self { prompt: None }
+    }
+}
+
+impl<A> ConfirmState<A> {
+    /// Ask a yes/no question that runs `action` on `y`.
+    pub fn confirm(&mut self, title: impl Into<String>, message: impl Into<String>, action: A) {
+        self.ask(title, message, vec![Choice::new('y', "yes", action)]);
+    }
+
+    /// Ask a question with several answers.
+    pub fn ask(
+        &mut self,
+        title: impl Into<String>,
+        message: impl Into<String>,
+        choices: Vec<Choice<A>>,
+    ) {
+        self.prompt = Some(Prompt {
+            title: title.into(),
+            message: message.into(),
+            choices,
+        });
+    }
+
+    /// Whether a prompt is displayed.
+    pub fn is_open(&self) -> bool {
+        self.prompt.is_some()
+    }
+
+    /// Dismiss the prompt without an answer.
+    pub fn cancel(&mut self) {
+        self.prompt = None;
+    }
+
+    /// Close the prompt and return the action answered by `key`, if any choice uses it.
+    ///
+    /// Keys that answer nothing leave the prompt open, except `n`, which cancels it.
+    pub fn answer(&mut self, key: char) -> Option<A> {
+        let prompt = self.prompt.as_mut()?;
+        match prompt.choices.iter().position(|choice| choice.key == key) {
+            Some(index) => {
+                let choice = prompt.choices.swap_remove(index);
+                self.prompt = None;
+                Some(choice.action)
+            }
+            None => {
+                if key == 'n' {
+                    self.prompt = None;
+                }
+                None
+            }
+        }
+    }
+}
+
+/// Visual component that renders the confirmation prompt.
+#[derive(Debug, Default)]
+pub struct ConfirmDialog;
+
+impl ConfirmDialog {
+    /// Draw the prompt centered within `area`.
+    pub fn render<A>(&self, frame: &mut Frame<'_>, area: Rect, state: &ConfirmState<A>) {
+        let Some(prompt) = &state.prompt else {
+            return;
+        };
+
+        let mut keys: Vec<Span> = Vec::new();
+        for choice in &prompt.choices {
+            keys.push(Span::styled(
+                choice.key.to_string(),
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ));
+            keys.push(Span::raw(format!(" {} · ", choice.label)));
+        }
+        let cancel_keys = if prompt.choices.iter().any(|choice| choice.key == 'n') {
+            "esc"
+        } else {
+            "n/esc"
+        };
+        keys.push(Span::styled(
+            cancel_keys,
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        ));
+        keys.push(Span::raw(" cancel"));
+        let lines = vec![
+            Line::raw(prompt.message.clone()),
+            Line::default(),
+            Line::from(keys),
+        ];
+
+        let width = area.width.saturating_sub(4).min(70);
+        let text_width = width.saturating_sub(2).max(1) as usize;
+        let message_rows = prompt
+            .message
+            .lines()
+            .map(|line| line.chars().count().div_ceil(text_width).max(1))
+            .sum::<usize>() as u16;
+        let height = (message_rows + 4).min(area.height);
+        let popup = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+        frame.render_widget(Clear, popup);
+        let paragraph = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+            Block::default()
+                .title(prompt.title.clone())
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow)),
+        );
+        frame.render_widget(paragraph, popup);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_listed_keys_answer_and_n_cancels() {
+        let mut state = ConfirmState::default();
+        state.confirm("Clear selections?", "Remove all 3 selections?", "clear");
+        assert_eq!(state.answer('\n'), None);
+        assert!(state.is_open());
+        assert_eq!(state.answer('y'), Some("clear"));
+        assert!(!state.is_open());
+
+        state.confirm("Clear selections?", "Remove all 3 selections?", "clear");
+        assert_eq!(state.answer('n'), None);
+        assert!(!state.is_open());
+
+        state.ask(
+            "Quit?",
+            "Unsaved changes",
+            vec![
+                Choice::new('s', "save", "save"),
+                Choice::new('n', "no", "discard"),
+            ],
+        );
+        assert_eq!(state.answer('n'), Some("discard"));
+    }
+}
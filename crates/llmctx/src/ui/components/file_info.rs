@@ -0,0 +1,71 @@
+//! Popup listing detailed metadata for a single file.
+
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
+
+/// Labelled rows describing one file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileInfo {
+    pub title: String,
+    pub rows: Vec<(&'static str, String)>,
+}
+
+/// Visual component that renders the file info overlay.
+#[derive(Debug, Default)]
+pub struct FileInfoPopup;
+
+impl FileInfoPopup {
+    /// Draw `info` centered within `area`.
+    pub fn render(&self, frame: &mut Frame<'_>, area: Rect, info: &FileInfo) {
+        let label_width = info
+            .rows
+            .iter()
+            .map(|(label, _)| label.len())
+            .max()
+            .unwrap_or(0);
+        let mut lines: Vec<Line> = info
+            .rows
+            .iter()
+            .map(|(label, value)| {
+                Line::from(vec![
+                    Span::styled(
+                        format!("{label:<label_width$}  "),
+                        Style::default()
+                            .fg(Color::Cyan)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    Span::raw(value.clone()),
+                ])
+            })
+            .collect();
+        lines.push(Line::default());
+        lines.push(Line::styled(
+            "Press any key to close",
+            Style::default()
+                .fg(Color::DarkGray)
+                .add_modifier(Modifier::ITALIC),
+        ));
+
+        let width = area.width.saturating_sub(4).min(90);
+        let height = (lines.len() as u16 + 2).min(area.height);
+        let popup = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+
+        frame.render_widget(Clear, popup);
+        let block = Block::default()
+            .title(info.title.as_str())
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan));
+        let paragraph = Paragraph::new(lines)
+            .block(block)
+            .wrap(Wrap { trim: false });
+        frame.render_widget(paragraph, popup);
+    }
+}
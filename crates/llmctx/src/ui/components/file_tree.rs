@@ -9,6 +9,16 @@ use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, List, ListItem, Paragraph};
 
 use crate::app::scan::{FileMetadata, ScanResult, SkipReason};
+use crate::app::tokens::AggregateTokenEstimate;
+use crate::ui::line_editor::LineEditor;
+use crate::ui::text::{display_width, truncate_end};
+
+/// Drawn before the filter text.
+const FILTER_PROMPT: &str = "⌕ ";
+/// Marker drawn before the highlighted row.
+const HIGHLIGHT_SYMBOL: &str = "▸ ";
+/// Columns of a name kept visible however much its row has to shrink.
+const MIN_NAME_WIDTH: usize = 8;
 
 /// Maintains the navigable state of the file tree.
 #[derive(Debug, Default, Clone)]
@@ -17,9 +27,25 @@ pub struct FileTreeState {
     visible: Vec<usize>,
     selected: usize,
     expanded: HashSet<String>,
-    filter: String,
+    filter: LineEditor,
     filter_active: bool,
     root_label: String,
+    directory_estimates: HashMap<String, DirectoryEstimate>,
+    /// Size-based token estimates by display path, for files and the directories above them.
+    approximate_tokens: HashMap<String, usize>,
+    /// Entries marked with `*` for a batch operation.
+    marked: HashSet<usize>,
+    /// Entry where visual mode (`V`) started; the range runs to the cursor.
+    visual_anchor: Option<usize>,
+    /// Display paths selected automatically from `.llmctx/always-include.toml`.
+    default_paths: HashSet<String>,
+}
+
+/// Progress of an on-demand token estimate for a directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DirectoryEstimate {
+    Pending,
+    Ready(AggregateTokenEstimate),
 }
 
 impl FileTreeState {
@@ -30,13 +56,18 @@ impl FileTreeState {
             visible: Vec::new(),
             selected: 0,
             expanded: HashSet::new(),
-            filter: String::new(),
+            filter: LineEditor::default(),
             filter_active: false,
             root_label: result
                 .root
                 .file_name()
                 .map(|name| name.to_string_lossy().to_string())
                 .unwrap_or_else(|| result.root.display().to_string()),
+            directory_estimates: HashMap::new(),
+            approximate_tokens: HashMap::new(),
+            marked: HashSet::new(),
+            visual_anchor: None,
+            default_paths: HashSet::new(),
         };
         state.rebuild_entries(result);
         state
@@ -80,6 +111,7 @@ impl FileTreeState {
         }
 
         self.entries = entries;
+        self.clear_marks();
         self.visible.clear();
         self.selected = 0;
         self.refresh_visible();
@@ -138,6 +170,32 @@ impl FileTreeState {
         }
     }
 
+    /// Move selection by `delta` entries, stopping at either end.
+    pub fn move_by(&mut self, delta: isize) {
+        let last = self.visible.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
+
+    /// Move selection to the first visible entry.
+    pub fn select_first(&mut self) {
+        self.selected = 0;
+    }
+
+    /// Collapse every directory, keeping the selection on the top-level entry that held it.
+    pub fn collapse_all(&mut self) {
+        let mut top = self.selected_entry_index();
+        while let Some(parent) = top.and_then(|index| self.entries[index].parent) {
+            top = Some(parent);
+        }
+        self.expanded.clear();
+        self.refresh_visible();
+        if let Some(top) = top
+            && let Some(pos) = self.visible.iter().position(|index| *index == top)
+        {
+            self.selected = pos;
+        }
+    }
+
     /// Expand the currently selected directory or activate its first child.
     pub fn expand_or_open(&mut self) {
         if let Some(index) = self.selected_entry_index()
@@ -200,16 +258,14 @@ impl FileTreeState {
         self.filter_active
     }
 
-    /// Append a character to the filter string and refresh visibility.
-    pub fn push_filter_char(&mut self, ch: char) {
-        self.filter.push(ch);
-        self.refresh_visible();
-    }
-
-    /// Remove the most recent filter character.
-    pub fn pop_filter_char(&mut self) {
-        self.filter.pop();
-        self.refresh_visible();
+    /// Edit the filter input, refreshing visibility when its text changed.
+    pub fn edit_filter<R>(&mut self, edit: impl FnOnce(&mut LineEditor) -> R) -> R {
+        let before = self.filter.text().to_string();
+        let result = edit(&mut self.filter);
+        if self.filter.text() != before {
+            self.refresh_visible();
+        }
+        result
     }
 
     /// Clear the active filter.
@@ -222,13 +278,13 @@ impl FileTreeState {
 
     /// Replace the filter contents.
     pub fn set_filter<S: Into<String>>(&mut self, pattern: S) {
-        self.filter = pattern.into();
+        self.filter.set(pattern);
         self.refresh_visible();
     }
 
     /// Retrieve the active filter string.
     pub fn filter(&self) -> &str {
-        &self.filter
+        self.filter.text()
     }
 
     fn refresh_visible(&mut self) {
@@ -237,7 +293,7 @@ impl FileTreeState {
             return;
         }
 
-        let lower_filter = self.filter.to_ascii_lowercase();
+        let lower_filter = self.filter.text().to_ascii_lowercase();
         let mut matches = vec![lower_filter.is_empty(); self.entries.len()];
 
         if !lower_filter.is_empty() {
@@ -335,6 +391,132 @@ impl FileTreeState {
         self.expanded.contains(path)
     }
 
+    /// Record the token estimate for a directory so it renders inline.
+    pub fn set_directory_estimate(&mut self, display_path: &str, estimate: DirectoryEstimate) {
+        self.directory_estimates
+            .insert(display_path.to_string(), estimate);
+    }
+
+    /// Replace the size-based estimates, given per file, adding each to every directory above it.
+    pub fn set_approximate_tokens(&mut self, files: impl IntoIterator<Item = (String, usize)>) {
+        self.approximate_tokens.clear();
+        for (display_path, tokens) in files {
+            let mut path = display_path.as_str();
+            while let Some((parent, _)) = path.rsplit_once('/') {
+                *self
+                    .approximate_tokens
+                    .entry(parent.to_string())
+                    .or_default() += tokens;
+                path = parent;
+            }
+            *self.approximate_tokens.entry(display_path).or_default() += tokens;
+        }
+    }
+
+    /// Forget estimates that were requested but never arrived, e.g. after cancelling them.
+    pub fn clear_pending_estimates(&mut self) {
+        self.directory_estimates
+            .retain(|_, estimate| !matches!(estimate, DirectoryEstimate::Pending));
+    }
+
+    /// Retrieve the most recent estimate for a directory, if one was requested.
+    pub fn directory_estimate(&self, display_path: &str) -> Option<&DirectoryEstimate> {
+        self.directory_estimates.get(display_path)
+    }
+
+    /// Mark or unmark the highlighted entry for a batch operation.
+    pub fn toggle_mark(&mut self) {
+        if let Some(index) = self.selected_entry_index()
+            && !self.marked.remove(&index)
+        {
+            self.marked.insert(index);
+        }
+    }
+
+    /// Start visual mode at the cursor, or end it and keep the covered range marked.
+    pub fn toggle_visual(&mut self) {
+        match self.visual_range() {
+            Some((start, end)) => {
+                self.marked
+                    .extend(self.visible[start..=end].iter().copied());
+                self.visual_anchor = None;
+            }
+            None => self.visual_anchor = self.selected_entry_index(),
+        }
+    }
+
+    /// Whether visual mode is extending a range from its anchor.
+    pub fn is_visual_active(&self) -> bool {
+        self.visual_anchor.is_some()
+    }
+
+    /// Whether any entry is marked or covered by the visual range.
+    pub fn has_marks(&self) -> bool {
+        !self.marked.is_empty() || self.visual_anchor.is_some()
+    }
+
+    /// Drop all marks and leave visual mode.
+    pub fn clear_marks(&mut self) {
+        self.marked.clear();
+        self.visual_anchor = None;
+    }
+
+    /// Files covered by the marks and visual range, in tree order; marked directories contribute
+    /// every file beneath them.
+    pub fn marked_files(&self) -> Vec<&FileMetadata> {
+        let mut roots: Vec<usize> = self.marked.iter().copied().collect();
+        if let Some((start, end)) = self.visual_range() {
+            roots.extend(self.visible[start..=end].iter().copied());
+        }
+        self.entries
+            .iter()
+            .enumerate()
+            .filter(|(idx, entry)| {
+                !entry.metadata.is_dir && roots.iter().any(|root| self.is_within(*idx, *root))
+            })
+            .map(|(_, entry)| &entry.metadata)
+            .collect()
+    }
+
+    /// Files currently visible in the tree, i.e. those matching the filter.
+    pub fn filtered_files(&self) -> Vec<&FileMetadata> {
+        self.iter_visible()
+            .filter(|(_, _, entry)| !entry.metadata.is_dir)
+            .map(|(_, _, entry)| &entry.metadata)
+            .collect()
+    }
+
+    fn is_marked(&self, display_idx: usize, entry_idx: usize) -> bool {
+        self.marked.contains(&entry_idx)
+            || self
+                .visual_range()
+                .is_some_and(|(start, end)| (start..=end).contains(&display_idx))
+    }
+
+    /// Visible positions spanned by visual mode, if its anchor is still visible.
+    fn visual_range(&self) -> Option<(usize, usize)> {
+        let anchor = self.visual_anchor?;
+        let anchor = self.visible.iter().position(|idx| *idx == anchor)?;
+        Some((anchor.min(self.selected), anchor.max(self.selected)))
+    }
+
+    /// Whether entry `idx` is `root` or one of its descendants.
+    fn is_within(&self, idx: usize, root: usize) -> bool {
+        let mut cursor = Some(idx);
+        while let Some(current) = cursor {
+            if current == root {
+                return true;
+            }
+            cursor = self.entries[current].parent;
+        }
+        false
+    }
+
+    /// Record which display paths are workspace defaults so they render distinctly.
+    pub fn set_default_paths(&mut self, paths: HashSet<String>) {
+        self.default_paths = paths;
+    }
+
     /// Expose the root label for rendering.
     pub fn root_label(&self) -> &str {
         &self.root_label
@@ -364,9 +546,13 @@ impl FileTree {
         has_focus: bool,
         selected_paths: &HashSet<String>,
     ) {
-        let block = Block::default()
-            .borders(Borders::ALL)
-            .title(format!("Workspace · {}", state.root_label()));
+        let mut title = format!("Workspace · {}", state.root_label());
+        if state.is_visual_active() {
+            title.push_str(" · VISUAL");
+        } else if !state.marked.is_empty() {
+            title.push_str(&format!(" · {} marked", state.marked.len()));
+        }
+        let block = Block::default().borders(Borders::ALL).title(title);
         frame.render_widget(block.clone(), area);
 
         let inner = block.inner(area);
@@ -375,10 +561,17 @@ impl FileTree {
             .constraints([Constraint::Length(1), Constraint::Min(1)])
             .split(inner);
 
-        let filter_text = if state.filter().is_empty() {
-            "⌕ filter (press /)".to_string()
+        let filter_text = if state.is_filter_active() {
+            let prompt_width = display_width(FILTER_PROMPT);
+            let (shown, cursor) = state
+                .filter
+                .view((layout[0].width as usize).saturating_sub(prompt_width));
+            frame.set_cursor(layout[0].x + (prompt_width + cursor) as u16, layout[0].y);
+            format!("{FILTER_PROMPT}{shown}")
+        } else if state.filter().is_empty() {
+            format!("{FILTER_PROMPT}filter (press /)")
         } else {
-            format!("⌕ {}", state.filter())
+            format!("{FILTER_PROMPT}{}", state.filter())
         };
 
         let mut filter_style = Style::default().fg(Color::Gray);
@@ -399,10 +592,19 @@ impl FileTree {
             return;
         }
 
+        let row_width = (layout[1].width as usize).saturating_sub(display_width(HIGHLIGHT_SYMBOL));
         let mut items = Vec::with_capacity(state.visible_len());
-        for (display_idx, _index, entry) in state.iter_visible() {
+        for (display_idx, index, entry) in state.iter_visible() {
             let mut spans = Vec::new();
             spans.push(Span::raw("  ".repeat(entry.depth)));
+            if state.is_marked(display_idx, index) {
+                spans.push(Span::styled(
+                    "* ",
+                    Style::default()
+                        .fg(Color::Magenta)
+                        .add_modifier(Modifier::BOLD),
+                ));
+            }
 
             if entry.metadata.is_dir {
                 let symbol = if state.is_path_expanded(&entry.metadata.display_path) {
@@ -421,10 +623,16 @@ impl FileTree {
             }
 
             let mut name_style = Style::default();
-            if selected_paths.contains(&entry.metadata.display_path) {
+            let is_selected = selected_paths.contains(&entry.metadata.display_path);
+            let is_default =
+                is_selected && state.default_paths.contains(&entry.metadata.display_path);
+            if is_default {
+                name_style = name_style.fg(Color::Green).add_modifier(Modifier::BOLD);
+            } else if is_selected {
                 name_style = name_style.fg(Color::Cyan).add_modifier(Modifier::BOLD);
             }
 
+            let name_index = spans.len();
             if let Some(reason) = entry.metadata.skipped {
                 let label = match reason {
                     SkipReason::LargeFile => "(large)",
@@ -436,10 +644,43 @@ impl FileTree {
                 ));
                 spans.push(Span::raw(" "));
                 spans.push(Span::styled(label, Style::default().fg(Color::Yellow)));
+            } else if let Some(label) = entry.metadata.class.label() {
+                spans.push(Span::styled(
+                    entry.name.clone(),
+                    name_style.fg(Color::DarkGray),
+                ));
+                spans.push(Span::styled(
+                    format!(" ({label})"),
+                    Style::default().fg(Color::DarkGray),
+                ));
             } else {
                 spans.push(Span::styled(entry.name.clone(), name_style));
             }
 
+            if is_default {
+                spans.push(Span::styled(
+                    " (default)",
+                    Style::default().fg(Color::DarkGray),
+                ));
+            }
+
+            if let Some(estimate) = state.directory_estimate(&entry.metadata.display_path) {
+                let label = match estimate {
+                    DirectoryEstimate::Pending => " (estimating…)".to_string(),
+                    DirectoryEstimate::Ready(estimate) => {
+                        format!(" ~{} tok", format_token_count(estimate.tokens))
+                    }
+                };
+                spans.push(Span::styled(label, Style::default().fg(Color::Magenta)));
+            } else if let Some(tokens) = state.approximate_tokens.get(&entry.metadata.display_path)
+            {
+                spans.push(Span::styled(
+                    format!(" ≈{}", format_token_count(*tokens)),
+                    Style::default().fg(Color::DarkGray),
+                ));
+            }
+
+            fit_name(&mut spans, name_index, row_width);
             let line = Line::from(spans);
             let mut item = ListItem::new(line);
             if display_idx % 2 == 1 {
@@ -468,29 +709,55 @@ impl FileTree {
         let list = List::new(items)
             .block(Block::default())
             .highlight_style(highlight_style)
-            .highlight_symbol("▸ ");
+            .highlight_symbol(HIGHLIGHT_SYMBOL);
 
         frame.render_stateful_widget(list, layout[1], &mut list_state);
     }
 }
 
+/// Shorten the name at `spans[name]` so the row fits `width` columns with its markers and
+/// token counts still visible, keeping at least [`MIN_NAME_WIDTH`] columns of the name.
+fn fit_name(spans: &mut [Span<'static>], name: usize, width: usize) {
+    let overflow = spans
+        .iter()
+        .map(Span::width)
+        .sum::<usize>()
+        .saturating_sub(width);
+    if overflow == 0 {
+        return;
+    }
+    let name_width = spans[name].width();
+    let target = name_width
+        .saturating_sub(overflow)
+        .max(MIN_NAME_WIDTH.min(name_width));
+    let shortened = truncate_end(&spans[name].content, target).into_owned();
+    spans[name].content = shortened.into();
+}
+
+fn format_token_count(tokens: usize) -> String {
+    if tokens >= 1_000_000 {
+        format!("{:.1}M", tokens as f64 / 1_000_000.0)
+    } else if tokens >= 1_000 {
+        format!("{:.1}k", tokens as f64 / 1_000.0)
+    } else {
+        tokens.to_string()
+    }
+}
+
+/// Last component of a display path, which always separates components with `/`.
 fn display_name(display_path: &str) -> String {
-    std::path::Path::new(display_path)
-        .file_name()
-        .map(|name| name.to_string_lossy().to_string())
-        .unwrap_or_else(|| display_path.to_string())
+    display_path
+        .rsplit('/')
+        .next()
+        .unwrap_or(display_path)
+        .to_string()
 }
 
 fn parent_key(display_path: &str) -> Option<String> {
-    std::path::Path::new(display_path)
-        .parent()
-        .and_then(|parent| {
-            if parent.as_os_str().is_empty() {
-                None
-            } else {
-                Some(parent.to_string_lossy().to_string())
-            }
-        })
+    display_path
+        .rsplit_once('/')
+        .map(|(parent, _)| parent.to_string())
+        .filter(|parent| !parent.is_empty())
 }
 
 #[cfg(test)]
@@ -503,7 +770,7 @@ mod tests {
     use ratatui::Terminal;
     use ratatui::backend::TestBackend;
 
-    use crate::app::scan::{FileMetadata, ScanResult};
+    use crate::app::scan::{FileClass, FileMetadata, ScanResult};
 
     #[test]
     fn renders_tree_for_basic_scan() {
@@ -523,6 +790,179 @@ mod tests {
             .unwrap();
     }
 
+    #[test]
+    fn renders_directory_estimates_inline() {
+        let backend = TestBackend::new(60, 20);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        let scan = sample_scan();
+        let mut state = FileTreeState::from_scan(&scan);
+        state.set_directory_estimate(
+            "src",
+            DirectoryEstimate::Ready(AggregateTokenEstimate {
+                files: 1,
+                tokens: 12_345,
+                characters: 50_000,
+                unreadable: 0,
+            }),
+        );
+
+        terminal
+            .draw(|frame| {
+                let area = frame.size();
+                FileTree.render(frame, area, &state, true, &HashSet::new());
+            })
+            .unwrap();
+
+        let rendered: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(rendered.contains("~12.3k tok"));
+    }
+
+    #[test]
+    fn approximate_tokens_add_up_to_directories() {
+        let mut state = FileTreeState::from_scan(&sample_scan());
+        state.set_approximate_tokens([
+            ("src/app/mod.rs".to_string(), 1_500),
+            ("src/lib.rs".to_string(), 500),
+            ("README.md".to_string(), 20),
+        ]);
+        assert_eq!(state.approximate_tokens["src"], 2_000);
+        assert_eq!(state.approximate_tokens["src/app"], 1_500);
+        assert_eq!(state.approximate_tokens["README.md"], 20);
+
+        let mut terminal = Terminal::new(TestBackend::new(60, 20)).unwrap();
+        terminal
+            .draw(|frame| {
+                let area = frame.size();
+                FileTree.render(frame, area, &state, true, &HashSet::new());
+            })
+            .unwrap();
+        let rendered: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(rendered.contains("src ≈2.0k"));
+    }
+
+    #[test]
+    fn long_wide_names_shrink_to_keep_token_counts_visible() {
+        let root = PathBuf::from("/tmp/workspace");
+        let scan = ScanResult {
+            files: vec![FileMetadata {
+                path: root.join("設計メモと議事録のまとめ.md"),
+                display_path: "設計メモと議事録のまとめ.md".into(),
+                is_dir: false,
+                size: Some(4_800),
+                modified: None,
+                language: Some("markdown".into()),
+                skipped: None,
+                class: FileClass::Source,
+            }],
+            root,
+            truncated: None,
+        };
+        let mut state = FileTreeState::from_scan(&scan);
+        state.set_approximate_tokens([("設計メモと議事録のまとめ.md".to_string(), 1_200)]);
+
+        let mut terminal = Terminal::new(TestBackend::new(26, 6)).unwrap();
+        terminal
+            .draw(|frame| {
+                let area = frame.size();
+                FileTree.render(frame, area, &state, true, &HashSet::new());
+            })
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        // A wide character's second cell is left blank, so step over it.
+        let mut row = String::new();
+        let mut x = 0;
+        while x < buffer.area.width {
+            let symbol = buffer.get(x, 2).symbol();
+            row.push_str(symbol);
+            x += display_width(symbol).max(1) as u16;
+        }
+        assert!(row.contains("• 設計メモと議…"), "{row}");
+        assert!(row.contains(" ≈1.2k"), "{row}");
+    }
+
+    #[test]
+    fn marks_and_visual_ranges_collect_files() {
+        let mut state = FileTreeState::from_scan(&sample_scan());
+        // Visible order: src, src/lib.rs, README.md.
+        state.toggle_mark();
+        let marked: Vec<_> = state
+            .marked_files()
+            .iter()
+            .map(|meta| meta.display_path.as_str())
+            .collect();
+        assert_eq!(marked, vec!["src/lib.rs"]);
+
+        state.clear_marks();
+        state.select_next();
+        state.toggle_visual();
+        state.select_next();
+        assert!(state.is_visual_active());
+        let marked: Vec<_> = state
+            .marked_files()
+            .iter()
+            .map(|meta| meta.display_path.as_str())
+            .collect();
+        assert_eq!(marked, vec!["src/lib.rs", "README.md"]);
+
+        state.toggle_visual();
+        assert!(!state.is_visual_active());
+        assert_eq!(state.marked_files().len(), 2);
+        state.clear_marks();
+        assert!(!state.has_marks());
+    }
+
+    #[test]
+    fn filtered_files_lists_visible_matches() {
+        let mut state = FileTreeState::from_scan(&sample_scan());
+        state.set_filter("lib");
+        let files: Vec<_> = state
+            .filtered_files()
+            .iter()
+            .map(|meta| meta.display_path.as_str())
+            .collect();
+        assert_eq!(files, vec!["src/lib.rs"]);
+    }
+
+    #[test]
+    fn collapse_all_keeps_the_selection_on_its_top_level_entry() {
+        let mut state = FileTreeState::from_scan(&sample_scan());
+        state.select_next();
+        assert_eq!(
+            state.selected_metadata().unwrap().display_path,
+            "src/lib.rs"
+        );
+        state.collapse_all();
+        assert_eq!(state.visible_len(), 2);
+        assert_eq!(state.selected_metadata().unwrap().display_path, "src");
+    }
+
+    #[test]
+    fn move_by_stops_at_either_end() {
+        let mut state = FileTreeState::from_scan(&sample_scan());
+        state.move_by(10);
+        assert_eq!(state.selected_metadata().unwrap().display_path, "README.md");
+        state.move_by(-1);
+        assert_eq!(
+            state.selected_metadata().unwrap().display_path,
+            "src/lib.rs"
+        );
+        state.move_by(-10);
+        assert_eq!(state.selected_index(), Some(0));
+    }
+
     fn sample_scan() -> ScanResult {
         let root = PathBuf::from("/tmp/workspace");
         let files = vec![
@@ -534,6 +974,7 @@ mod tests {
                 modified: None,
                 language: None,
                 skipped: None,
+                class: FileClass::Source,
             },
             FileMetadata {
                 path: root.join("src/lib.rs"),
@@ -543,6 +984,7 @@ mod tests {
                 modified: None,
                 language: Some("rust".into()),
                 skipped: None,
+                class: FileClass::Source,
             },
             FileMetadata {
                 path: root.join("README.md"),
@@ -552,9 +994,14 @@ mod tests {
                 modified: None,
                 language: Some("markdown".into()),
                 skipped: None,
+                class: FileClass::Source,
             },
         ];
 
-        ScanResult { files, root }
+        ScanResult {
+            files,
+            root,
+            truncated: None,
+        }
     }
 }
@@ -0,0 +1,302 @@
+//! Status bar messages and the `:messages` history they are kept in.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+
+/// Messages kept in the history; older ones are dropped.
+const MAX_HISTORY: usize = 200;
+/// How long a message stays in the status bar.
+const DISPLAY_FOR: Duration = Duration::from_secs(4);
+/// Errors stay in the status bar longer, since they usually need reading.
+const ERROR_DISPLAY_FOR: Duration = Duration::from_secs(8);
+/// A repeat of the latest message within this window is counted instead of added.
+const BATCH_WINDOW: Duration = Duration::from_secs(2);
+
+/// Severity of a message, which picks its colour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusLevel {
+    Info,
+    Success,
+    Error,
+}
+
+impl StatusLevel {
+    fn style(self) -> Style {
+        match self {
+            Self::Info => Style::default().fg(Color::Gray),
+            Self::Success => Style::default().fg(Color::Green),
+            Self::Error => Style::default().fg(Color::Red),
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Info => "info",
+            Self::Success => "ok",
+            Self::Error => "error",
+        }
+    }
+}
+
+/// One message, possibly spanning several lines.
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub level: StatusLevel,
+    pub text: String,
+    /// How many times the message was posted in a row.
+    pub count: usize,
+    /// When the message was last posted.
+    pub at: Instant,
+}
+
+impl Message {
+    /// First line of the text, with the repeat count and a pointer to the rest.
+    fn summary(&self) -> String {
+        let mut lines = self.text.lines();
+        let mut summary = lines.next().unwrap_or_default().to_string();
+        if self.count > 1 {
+            summary.push_str(&format!(" (×{})", self.count));
+        }
+        let more = lines.count();
+        if more > 0 {
+            summary.push_str(&format!(" (+{more} more lines, :messages)"));
+        }
+        summary
+    }
+}
+
+/// The message shown in the status bar plus the history behind `:messages`.
+#[derive(Debug, Default)]
+pub struct MessageCenter {
+    history: VecDeque<Message>,
+    /// When the latest message leaves the status bar; `None` once it has.
+    visible_until: Option<Instant>,
+    open: bool,
+    /// Lines scrolled up from the newest message in the history pane.
+    scroll: usize,
+}
+
+impl MessageCenter {
+    /// Show `text` in the status bar and record it.
+    pub fn post(&mut self, level: StatusLevel, text: String) {
+        self.post_at(level, text, Instant::now());
+    }
+
+    /// [`Self::post`] with the clock reading supplied by the caller.
+    pub fn post_at(&mut self, level: StatusLevel, text: String, now: Instant) {
+        let repeat = self.history.back_mut().filter(|last| {
+            last.level == level && last.text == text && now - last.at < BATCH_WINDOW
+        });
+        match repeat {
+            Some(last) => {
+                last.count += 1;
+                last.at = now;
+            }
+            None => {
+                if self.history.len() == MAX_HISTORY {
+                    self.history.pop_front();
+                }
+                self.history.push_back(Message {
+                    level,
+                    text,
+                    count: 1,
+                    at: now,
+                });
+            }
+        }
+        let display_for = match level {
+            StatusLevel::Error => ERROR_DISPLAY_FOR,
+            _ => DISPLAY_FOR,
+        };
+        self.visible_until = Some(now + display_for);
+    }
+
+    /// Clear the status bar; the history keeps the message.
+    pub fn dismiss(&mut self) {
+        self.visible_until = None;
+    }
+
+    /// Clear the status bar once the latest message has been shown long enough.
+    pub fn expire(&mut self, now: Instant) {
+        if self.visible_until.is_some_and(|until| now >= until) {
+            self.visible_until = None;
+        }
+    }
+
+    /// Message currently in the status bar.
+    pub fn current(&self) -> Option<&Message> {
+        self.visible_until.and(self.history.back())
+    }
+
+    /// Recorded messages, oldest first.
+    pub fn history(&self) -> impl Iterator<Item = &Message> {
+        self.history.iter()
+    }
+
+    /// Show the history pane scrolled to the newest message.
+    pub fn open(&mut self) {
+        self.open = true;
+        self.scroll = 0;
+    }
+
+    /// Hide the history pane.
+    pub fn close(&mut self) {
+        self.open = false;
+    }
+
+    /// Whether the history pane is displayed.
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Scroll toward older messages, stopping at the oldest.
+    pub fn scroll_up(&mut self, lines: usize) {
+        let total: usize = self
+            .history
+            .iter()
+            .map(|message| message.text.lines().count().max(1))
+            .sum();
+        self.scroll = (self.scroll + lines).min(total.saturating_sub(1));
+    }
+
+    /// Scroll toward newer messages.
+    pub fn scroll_down(&mut self, lines: usize) {
+        self.scroll = self.scroll.saturating_sub(lines);
+    }
+}
+
+/// Visual component for the status bar line and the history pane.
+#[derive(Debug, Default)]
+pub struct MessagesPane;
+
+impl MessagesPane {
+    /// Status bar line for the current message, or `placeholder` without one.
+    pub fn status_line(center: &MessageCenter, placeholder: &str) -> Line<'static> {
+        match center.current() {
+            Some(message) => Line::styled(message.summary(), message.level.style()),
+            None => Line::styled(
+                placeholder.to_string(),
+                Style::default().fg(Color::DarkGray),
+            ),
+        }
+    }
+
+    /// Draw the history over most of `area`, newest at the bottom.
+    pub fn render(&self, frame: &mut Frame<'_>, area: Rect, center: &MessageCenter) {
+        if !center.is_open() {
+            return;
+        }
+
+        let width = area.width.saturating_sub(4).min(120);
+        let height = area.height.saturating_sub(2);
+        let popup = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+        frame.render_widget(Clear, popup);
+
+        let now = Instant::now();
+        let mut lines: Vec<Line> = Vec::new();
+        for message in center.history() {
+            let mut text = message.text.lines();
+            let mut first = vec![
+                Span::styled(
+                    format!("{:>4} ", age(now - message.at)),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                Span::styled(
+                    format!("{:<5} ", message.level.label()),
+                    message.level.style().add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(text.next().unwrap_or_default().to_string()),
+            ];
+            if message.count > 1 {
+                first.push(Span::styled(
+                    format!(" (×{})", message.count),
+                    Style::default().fg(Color::DarkGray),
+                ));
+            }
+            lines.push(Line::from(first));
+            lines.extend(text.map(|line| Line::raw(format!("{:11}{line}", ""))));
+        }
+        if lines.is_empty() {
+            lines.push(Line::styled(
+                "No messages yet",
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+
+        let visible = popup.height.saturating_sub(2) as usize;
+        let bottom = lines.len().saturating_sub(center.scroll.min(lines.len()));
+        let top = bottom.saturating_sub(visible);
+        let paragraph = Paragraph::new(lines[top..bottom].to_vec()).block(
+            Block::default()
+                .title(format!(
+                    "Messages · {} · j/k scroll · esc close",
+                    center.history.len()
+                ))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        );
+        frame.render_widget(paragraph, popup);
+    }
+}
+
+/// Compact age such as `12s`, `5m`, or `2h`.
+fn age(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    match secs {
+        0..60 => format!("{secs}s"),
+        60..3600 => format!("{}m", secs / 60),
+        _ => format!("{}h", secs / 3600),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeats_are_batched_and_history_outlives_the_status_bar() {
+        let mut center = MessageCenter::default();
+        center.post(
+            StatusLevel::Error,
+            "search failed\nregex parse error".into(),
+        );
+        center.post(
+            StatusLevel::Error,
+            "search failed\nregex parse error".into(),
+        );
+        center.post(StatusLevel::Info, "Saved session".into());
+        center.post(
+            StatusLevel::Error,
+            "search failed\nregex parse error".into(),
+        );
+
+        let history: Vec<usize> = center.history().map(|message| message.count).collect();
+        assert_eq!(history, vec![2, 1, 1]);
+        let current = center.current().unwrap();
+        assert_eq!(
+            current.summary(),
+            "search failed (+1 more lines, :messages)"
+        );
+
+        center.expire(Instant::now() + ERROR_DISPLAY_FOR);
+        assert!(center.current().is_none());
+        assert_eq!(center.history().count(), 3);
+
+        for index in 0..MAX_HISTORY {
+            center.post(StatusLevel::Info, format!("message {index}"));
+        }
+        assert_eq!(center.history().count(), MAX_HISTORY);
+        assert_eq!(center.history().next().unwrap().text, "message 0");
+    }
+}
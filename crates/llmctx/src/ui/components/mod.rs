@@ -1,6 +1,16 @@
 //! Collection of reusable TUI components.
 
+pub mod breadcrumb;
 pub mod command_palette;
+pub mod confirm;
+pub mod file_info;
 pub mod file_tree;
+pub mod messages;
 pub mod preview;
+pub mod recent_files;
+pub mod search_results;
+pub mod suggestions;
 pub mod summary;
+pub mod theme_picker;
+pub mod which_key;
+pub mod workspace_stats;
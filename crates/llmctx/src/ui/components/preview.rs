@@ -2,29 +2,102 @@
 
 use ratatui::prelude::*;
 use ratatui::widgets::{Block, Borders, Paragraph, Wrap};
+use unicode_width::UnicodeWidthChar;
 
 use crate::app::preview::PreviewSegment;
+use crate::infra::config::Preview as PreviewConfig;
 use crate::infra::highlight::HighlightSpan;
+use crate::ui::text::{display_width, graphemes};
+
+/// Width of the coverage minimap drawn along the preview's right edge.
+const MINIMAP_WIDTH: u16 = 1;
+
+/// How long lines are presented in the preview.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PreviewLayout {
+    /// Soft-wrap long lines under the gutter instead of clipping them.
+    pub wrap: bool,
+    /// Columns skipped from the start of each line when wrapping is off.
+    pub horizontal_offset: usize,
+}
+
+impl Default for PreviewLayout {
+    fn default() -> Self {
+        Self {
+            wrap: true,
+            horizontal_offset: 0,
+        }
+    }
+}
+
+/// Optional markers for whitespace that is otherwise invisible in the preview.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WhitespaceOptions {
+    /// Draw a guide at each indentation stop within leading whitespace.
+    pub indent_guides: bool,
+    /// Mark spaces and tabs that trail the last visible character.
+    pub trailing_whitespace: bool,
+    /// Expand tabs to the next tab stop and mark them with an arrow.
+    pub tabs: bool,
+    /// Columns per tab stop and per indentation level.
+    pub tab_width: usize,
+}
+
+impl WhitespaceOptions {
+    pub fn from_config(config: &PreviewConfig) -> Self {
+        Self {
+            indent_guides: config.indent_guides(),
+            trailing_whitespace: config.show_trailing_whitespace(),
+            tabs: config.show_tabs(),
+            tab_width: config.tab_width(),
+        }
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.indent_guides || self.trailing_whitespace || self.tabs
+    }
+}
+
+impl Default for WhitespaceOptions {
+    fn default() -> Self {
+        Self {
+            indent_guides: false,
+            trailing_whitespace: false,
+            tabs: false,
+            tab_width: 4,
+        }
+    }
+}
 
 /// Ratatui component responsible for displaying file previews with line numbers.
 #[derive(Debug, Default)]
-pub struct Preview;
+pub struct Preview {
+    whitespace: WhitespaceOptions,
+}
 
 impl Preview {
+    pub fn new(whitespace: WhitespaceOptions) -> Self {
+        Self { whitespace }
+    }
+
     pub fn render(
         &self,
         segment: &PreviewSegment,
         selected_ranges: &[(usize, usize)],
+        layout: PreviewLayout,
         has_focus: bool,
         area: Rect,
         buf: &mut Buffer,
     ) {
-        let title = format!(
+        let mut title = format!(
             "{} ({}-{})",
             segment.path.display(),
             segment.start_line,
             segment.end_line
         );
+        if !layout.wrap {
+            title.push_str(&format!(" [nowrap +{}]", layout.horizontal_offset));
+        }
 
         let border_color = if has_focus {
             Color::Cyan
@@ -38,23 +111,68 @@ impl Preview {
         let inner = block.inner(area);
         block.render(area, buf);
 
+        let inner = if segment.total_lines > 0 && inner.width > MINIMAP_WIDTH + 1 {
+            let columns = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Min(1), Constraint::Length(MINIMAP_WIDTH)])
+                .split(inner);
+            let viewport_end = segment
+                .end_line
+                .min(segment.start_line + (inner.height as usize).saturating_sub(1));
+            render_minimap(
+                minimap_rows(
+                    segment.total_lines,
+                    columns[1].height as usize,
+                    selected_ranges,
+                    (segment.start_line, viewport_end),
+                ),
+                columns[1],
+                buf,
+            );
+            columns[0]
+        } else {
+            inner
+        };
+
+        let number_width = segment.end_line.max(1).to_string().len().max(4);
+        let gutter_width = number_width + 3;
+        let content_width = (inner.width as usize).saturating_sub(gutter_width);
+
         let mut lines = Vec::with_capacity(segment.highlighted.lines.len());
         for (idx, line) in segment.highlighted.lines.iter().enumerate() {
             let line_number = segment.start_line + idx;
-            let prefix = format!("{:>4} │ ", line_number);
             let selected = is_line_selected(line_number, selected_ranges);
-            let mut spans = vec![Span::styled(
-                prefix,
-                Style::default()
-                    .fg(Color::DarkGray)
-                    .bg(selection_background(selected)),
-            )];
-            spans.extend(
-                line.spans
-                    .iter()
-                    .map(|span| highlight_span_to_span(span, selected)),
-            );
-            lines.push(Line::from(spans));
+            let gutter_style = Style::default()
+                .fg(Color::DarkGray)
+                .bg(selection_background(selected));
+            let mut content: Vec<Span<'static>> = line
+                .spans
+                .iter()
+                .map(|span| highlight_span_to_span(span, selected))
+                .collect();
+            if self.whitespace.is_enabled() {
+                content = decorate_whitespace(&content, &self.whitespace);
+            }
+            let rows = if layout.wrap {
+                wrap_spans(&content, content_width)
+            } else {
+                vec![slice_spans(
+                    &content,
+                    layout.horizontal_offset,
+                    content_width,
+                )]
+            };
+
+            for (row_idx, row) in rows.into_iter().enumerate() {
+                let prefix = if row_idx == 0 {
+                    format!("{line_number:>number_width$} │ ")
+                } else {
+                    format!("{:>number_width$} │ ", "↳")
+                };
+                let mut spans = vec![Span::styled(prefix, gutter_style)];
+                spans.extend(row);
+                lines.push(Line::from(spans));
+            }
         }
 
         if let Some(notice) = &segment.notice {
@@ -95,7 +213,183 @@ impl Preview {
     }
 }
 
-fn highlight_span_to_span(span: &HighlightSpan, selected: bool) -> Span<'_> {
+/// What a single minimap row summarizes about its slice of the file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct MinimapRow {
+    selected: bool,
+    in_viewport: bool,
+}
+
+/// Bucket the file's lines into `height` rows and mark selection and viewport coverage.
+fn minimap_rows(
+    total_lines: usize,
+    height: usize,
+    selected_ranges: &[(usize, usize)],
+    viewport: (usize, usize),
+) -> Vec<MinimapRow> {
+    if height == 0 || total_lines == 0 {
+        return Vec::new();
+    }
+    (0..height)
+        .map(|row| {
+            let first = row * total_lines / height + 1;
+            let last = ((row + 1) * total_lines / height).max(first);
+            let overlaps = |(start, end): (usize, usize)| start <= last && end >= first;
+            MinimapRow {
+                selected: selected_ranges.iter().copied().any(overlaps),
+                in_viewport: overlaps(viewport),
+            }
+        })
+        .collect()
+}
+
+fn render_minimap(rows: Vec<MinimapRow>, area: Rect, buf: &mut Buffer) {
+    for (offset, row) in rows.into_iter().enumerate() {
+        let (symbol, fg) = if row.selected {
+            ("█", selection_background(true))
+        } else {
+            ("│", Color::DarkGray)
+        };
+        let bg = if row.in_viewport {
+            Color::Rgb(60, 60, 60)
+        } else {
+            Color::Reset
+        };
+        buf.set_string(
+            area.x,
+            area.y + offset as u16,
+            symbol,
+            Style::default().fg(fg).bg(bg),
+        );
+    }
+}
+
+/// Take `take` columns of `spans` starting at column `skip`, preserving styles.
+///
+/// Columns are terminal cells, so wide characters count twice; one cut by either edge shows as
+/// blanks for its visible half rather than shifting the rest of the row.
+fn slice_spans(spans: &[Span<'static>], skip: usize, take: usize) -> Vec<Span<'static>> {
+    let end = skip.saturating_add(take);
+    let mut sliced = Vec::new();
+    let mut column = 0;
+    for span in spans {
+        let mut text = String::new();
+        for (grapheme, width) in graphemes(&span.content) {
+            let start = column;
+            column += width;
+            if start >= end {
+                break;
+            }
+            if start >= skip && column <= end {
+                text.push_str(grapheme);
+            } else if column > skip {
+                text.push_str(&" ".repeat(column.min(end) - start.max(skip)));
+            }
+        }
+        if !text.is_empty() {
+            sliced.push(Span::styled(text, span.style));
+        }
+    }
+    sliced
+}
+
+/// Break `spans` into rows of at most `width` columns; always yields at least one row.
+///
+/// A wide character that does not fit at the end of a row moves to the next one whole.
+fn wrap_spans(spans: &[Span<'static>], width: usize) -> Vec<Vec<Span<'static>>> {
+    let total: usize = spans.iter().map(|span| display_width(&span.content)).sum();
+    if width == 0 || total <= width {
+        return vec![spans.to_vec()];
+    }
+    let mut rows = vec![Vec::new()];
+    let mut column = 0;
+    for span in spans {
+        let mut text = String::new();
+        for (grapheme, columns) in graphemes(&span.content) {
+            if column > 0 && column + columns > width {
+                if !text.is_empty() {
+                    let row = rows.last_mut().expect("rows start non-empty");
+                    row.push(Span::styled(std::mem::take(&mut text), span.style));
+                }
+                rows.push(Vec::new());
+                column = 0;
+            }
+            text.push_str(grapheme);
+            column += columns;
+        }
+        if !text.is_empty() {
+            let row = rows.last_mut().expect("rows start non-empty");
+            row.push(Span::styled(text, span.style));
+        }
+    }
+    rows
+}
+
+/// Rewrite leading, trailing, and tab whitespace according to `options`.
+fn decorate_whitespace(spans: &[Span<'static>], options: &WhitespaceOptions) -> Vec<Span<'static>> {
+    let marker = Style::default().fg(Color::DarkGray);
+    let tab_width = options.tab_width.max(1);
+
+    let mut cells: Vec<(char, Style)> = Vec::new();
+    // Tab stops are measured in terminal columns, which wide characters take two of.
+    let mut column = 0;
+    for span in spans {
+        for ch in span.content.chars() {
+            if ch == '\t' && options.tabs {
+                let pad = tab_width - column % tab_width;
+                cells.push(('→', span.style.patch(marker)));
+                cells.extend(std::iter::repeat_n((' ', span.style), pad - 1));
+                column += pad;
+            } else {
+                cells.push((ch, span.style));
+                column += ch.width().unwrap_or(0);
+            }
+        }
+    }
+
+    let indent = cells
+        .iter()
+        .take_while(|(ch, _)| *ch == ' ' || *ch == '\t')
+        .count();
+    let content_end = cells
+        .iter()
+        .rposition(|(ch, _)| !matches!(ch, ' ' | '\t' | '→'))
+        .map_or(0, |idx| idx + 1);
+
+    if options.trailing_whitespace && content_end > 0 {
+        for (ch, style) in &mut cells[content_end..] {
+            if *ch == ' ' {
+                *ch = '·';
+            }
+            *style = style.patch(Style::default().fg(Color::Red));
+        }
+    }
+    if options.indent_guides && content_end > 0 {
+        for (column, (ch, style)) in cells.iter_mut().enumerate().take(indent) {
+            if column % tab_width == 0 && *ch == ' ' {
+                *ch = '│';
+                *style = style.patch(marker);
+            }
+        }
+    }
+
+    let mut decorated: Vec<Span<'static>> = Vec::new();
+    let mut text = String::new();
+    let mut current: Option<Style> = None;
+    for (ch, style) in cells {
+        if current.is_some_and(|existing| existing != style) {
+            decorated.push(Span::styled(std::mem::take(&mut text), current.unwrap()));
+        }
+        current = Some(style);
+        text.push(ch);
+    }
+    if let Some(style) = current {
+        decorated.push(Span::styled(text, style));
+    }
+    decorated
+}
+
+fn highlight_span_to_span(span: &HighlightSpan, selected: bool) -> Span<'static> {
     let mut style = Style::default().bg(selection_background(selected));
 
     if let Some(color) = span.style.foreground {
@@ -131,3 +425,109 @@ fn selection_background(selected: bool) -> Color {
         Color::Reset
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minimap_marks_selected_rows_and_viewport() {
+        let rows = minimap_rows(100, 10, &[(45, 55)], (1, 20));
+
+        let selected: Vec<usize> = rows
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| row.selected)
+            .map(|(idx, _)| idx)
+            .collect();
+        assert_eq!(selected, vec![4, 5]);
+
+        let viewport: Vec<usize> = rows
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| row.in_viewport)
+            .map(|(idx, _)| idx)
+            .collect();
+        assert_eq!(viewport, vec![0, 1]);
+    }
+
+    fn text(spans: &[Span<'_>]) -> String {
+        spans.iter().map(|span| span.content.as_ref()).collect()
+    }
+
+    #[test]
+    fn slicing_spans_crosses_style_boundaries() {
+        let spans = vec![
+            Span::styled("let ", Style::default().fg(Color::Red)),
+            Span::raw("value = 1;"),
+        ];
+        let sliced = slice_spans(&spans, 2, 6);
+        assert_eq!(text(&sliced), "t valu");
+        assert_eq!(sliced[0].style.fg, Some(Color::Red));
+        assert!(slice_spans(&spans, 40, 10).is_empty());
+    }
+
+    #[test]
+    fn wrapping_splits_rows_at_width() {
+        let spans = vec![Span::raw("abcdefghij")];
+        let rows = wrap_spans(&spans, 4);
+        let rendered: Vec<String> = rows.iter().map(|row| text(row)).collect();
+        assert_eq!(rendered, vec!["abcd", "efgh", "ij"]);
+        assert_eq!(wrap_spans(&[], 4).len(), 1);
+    }
+
+    #[test]
+    fn wide_characters_slice_and_wrap_by_column() {
+        let spans = vec![
+            Span::styled("// ", Style::default().fg(Color::Red)),
+            Span::raw("日本語 ok 🦀"),
+        ];
+        assert_eq!(text(&slice_spans(&spans, 3, 4)), "日本");
+        assert_eq!(text(&slice_spans(&spans, 4, 4)), " 本 ");
+        assert_eq!(text(&slice_spans(&spans, 10, 10)), "ok 🦀");
+
+        let rows: Vec<String> = wrap_spans(&spans, 6).iter().map(|row| text(row)).collect();
+        assert_eq!(rows, vec!["// 日", "本語 o", "k 🦀"]);
+        assert!(rows.iter().all(|row| display_width(row) <= 6));
+
+        let options = WhitespaceOptions {
+            tabs: true,
+            ..WhitespaceOptions::default()
+        };
+        let spans = vec![Span::raw("名\tx")];
+        assert_eq!(text(&decorate_whitespace(&spans, &options)), "名→ x");
+    }
+
+    #[test]
+    fn whitespace_markers_follow_options() {
+        let options = WhitespaceOptions {
+            indent_guides: true,
+            trailing_whitespace: true,
+            tabs: true,
+            tab_width: 4,
+        };
+        let spans = vec![Span::raw("        value:\tx  ")];
+        assert_eq!(
+            text(&decorate_whitespace(&spans, &options)),
+            "│   │   value:→ x··"
+        );
+
+        let tabs_only = WhitespaceOptions {
+            tabs: true,
+            ..WhitespaceOptions::default()
+        };
+        let spans = vec![Span::raw("\tindented  ")];
+        assert_eq!(
+            text(&decorate_whitespace(&spans, &tabs_only)),
+            "→   indented  "
+        );
+    }
+
+    #[test]
+    fn minimap_handles_files_shorter_than_the_pane() {
+        let rows = minimap_rows(3, 6, &[(2, 2)], (1, 3));
+        assert_eq!(rows.len(), 6);
+        assert!(rows.iter().all(|row| row.in_viewport));
+        assert!(rows.iter().any(|row| row.selected));
+    }
+}
@@ -0,0 +1,138 @@
+//! Picker listing recently and frequently used files, opened with `ctrl+r`.
+
+use std::path::{Path, PathBuf};
+
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState};
+
+use crate::ui::text::display_width;
+
+/// Interactive state backing the recent files picker.
+#[derive(Debug, Default, Clone)]
+pub struct RecentFilesState {
+    /// Absolute path and display path of each entry, best ranked first.
+    entries: Vec<(PathBuf, String)>,
+    selected: usize,
+}
+
+impl RecentFilesState {
+    /// Show `entries`, starting at the best ranked.
+    pub fn open(&mut self, entries: Vec<(PathBuf, String)>) {
+        self.entries = entries;
+        self.selected = 0;
+    }
+
+    /// Hide the picker.
+    pub fn close(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Whether the picker is currently displayed.
+    pub fn is_open(&self) -> bool {
+        !self.entries.is_empty()
+    }
+
+    /// Move the cursor down, wrapping to the top.
+    pub fn select_next(&mut self) {
+        if !self.entries.is_empty() {
+            self.selected = (self.selected + 1) % self.entries.len();
+        }
+    }
+
+    /// Move the cursor up, wrapping to the bottom.
+    pub fn select_previous(&mut self) {
+        if !self.entries.is_empty() {
+            self.selected = self
+                .selected
+                .checked_sub(1)
+                .unwrap_or(self.entries.len() - 1);
+        }
+    }
+
+    /// File under the cursor.
+    pub fn selected_path(&self) -> Option<&Path> {
+        self.entries
+            .get(self.selected)
+            .map(|(path, _)| path.as_path())
+    }
+}
+
+/// Visual component that renders the recent files picker overlay.
+#[derive(Debug, Default)]
+pub struct RecentFilesPicker;
+
+impl RecentFilesPicker {
+    /// Draw the picker centered within `area`.
+    pub fn render(&self, frame: &mut Frame<'_>, area: Rect, state: &RecentFilesState) {
+        if !state.is_open() {
+            return;
+        }
+
+        let width = state
+            .entries
+            .iter()
+            .map(|(_, display)| display_width(display) as u16 + 6)
+            .max()
+            .unwrap_or(0)
+            .max(40)
+            .min(area.width);
+        let height = (state.entries.len() as u16 + 2).min(area.height);
+        let popup = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+        frame.render_widget(Clear, popup);
+
+        let items: Vec<ListItem> = state
+            .entries
+            .iter()
+            .map(|(_, display)| ListItem::new(Line::from(display.clone())))
+            .collect();
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .title("Recent & frequent · ↵ open · esc close")
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Cyan)),
+            )
+            .highlight_style(
+                Style::default()
+                    .fg(Color::Black)
+                    .bg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol("▸ ");
+        let mut list_state = ListState::default();
+        list_state.select(Some(state.selected));
+        frame.render_stateful_widget(list, popup, &mut list_state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursor_starts_on_best_ranked_and_wraps() {
+        let mut state = RecentFilesState::default();
+        state.open(vec![
+            (PathBuf::from("/repo/src/lib.rs"), "src/lib.rs".to_string()),
+            (PathBuf::from("/repo/README.md"), "README.md".to_string()),
+        ]);
+        assert_eq!(state.selected_path(), Some(Path::new("/repo/src/lib.rs")));
+
+        state.select_previous();
+        assert_eq!(state.selected_path(), Some(Path::new("/repo/README.md")));
+        state.select_next();
+        assert_eq!(state.selected_path(), Some(Path::new("/repo/src/lib.rs")));
+
+        state.close();
+        assert!(!state.is_open());
+        assert_eq!(state.selected_path(), None);
+    }
+}
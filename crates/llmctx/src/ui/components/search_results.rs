@@ -0,0 +1,221 @@
+//! Pane listing content search hits, where hits can be marked and added to the bundle together.
+
+use std::collections::BTreeSet;
+
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState};
+
+use crate::app::search::{SearchHit, SearchResults};
+
+/// Interactive state backing the search results pane.
+#[derive(Debug, Default, Clone)]
+pub struct SearchResultsState {
+    results: Option<SearchResults>,
+    selected: usize,
+    /// Indices of marked hits.
+    marked: BTreeSet<usize>,
+}
+
+impl SearchResultsState {
+    /// Show `results` with the cursor on the first hit and nothing marked.
+    pub fn open(&mut self, results: SearchResults) {
+        self.results = Some(results);
+        self.selected = 0;
+        self.marked.clear();
+    }
+
+    /// Hide the pane.
+    pub fn close(&mut self) {
+        self.results = None;
+        self.marked.clear();
+    }
+
+    /// Whether the pane is currently displayed.
+    pub fn is_open(&self) -> bool {
+        self.results.is_some()
+    }
+
+    fn hits(&self) -> &[SearchHit] {
+        self.results
+            .as_ref()
+            .map(|results| results.hits.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Move the cursor down, stopping at the last hit.
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.hits().len() {
+            self.selected += 1;
+        }
+    }
+
+    /// Move the cursor up, stopping at the first hit.
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    /// Hit under the cursor.
+    pub fn selected_hit(&self) -> Option<&SearchHit> {
+        self.hits().get(self.selected)
+    }
+
+    /// Mark or unmark the hit under the cursor.
+    pub fn toggle_mark(&mut self) {
+        if self.selected_hit().is_none() {
+            return;
+        }
+        if !self.marked.remove(&self.selected) {
+            self.marked.insert(self.selected);
+        }
+    }
+
+    /// Mark every hit, or clear the marks when all are marked already.
+    pub fn toggle_all(&mut self) {
+        let count = self.hits().len();
+        if self.marked.len() == count {
+            self.marked.clear();
+        } else {
+            self.marked = (0..count).collect();
+        }
+    }
+
+    /// Marked hits in result order, or the hit under the cursor when nothing is marked.
+    pub fn marked_hits(&self) -> Vec<&SearchHit> {
+        let hits = self.hits();
+        if self.marked.is_empty() {
+            return self.selected_hit().into_iter().collect();
+        }
+        self.marked
+            .iter()
+            .filter_map(|&index| hits.get(index))
+            .collect()
+    }
+}
+
+/// Visual component that renders the search results pane.
+#[derive(Debug, Default)]
+pub struct SearchResultsPane;
+
+impl SearchResultsPane {
+    /// Draw the hits over most of `area`.
+    pub fn render(&self, frame: &mut Frame<'_>, area: Rect, state: &SearchResultsState) {
+        let Some(results) = &state.results else {
+            return;
+        };
+
+        let width = area.width.saturating_sub(4).min(120);
+        let height = area.height.saturating_sub(2);
+        let popup = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+        frame.render_widget(Clear, popup);
+
+        let items: Vec<ListItem> = results
+            .hits
+            .iter()
+            .enumerate()
+            .map(|(index, hit)| {
+                let mark = if state.marked.contains(&index) {
+                    Span::styled("● ", Style::default().fg(Color::Yellow))
+                } else {
+                    Span::raw("  ")
+                };
+                ListItem::new(Line::from(vec![
+                    mark,
+                    Span::styled(
+                        if hit.end > hit.line {
+                            format!("{}:{}-{}", hit.display_path, hit.line, hit.end)
+                        } else {
+                            format!("{}:{}", hit.display_path, hit.line)
+                        },
+                        Style::default().fg(Color::Cyan),
+                    ),
+                    Span::raw("  "),
+                    Span::raw(hit.text.clone()),
+                ]))
+            })
+            .collect();
+        let title = format!(
+            "Search '{}' · {} hit(s){}{} · space mark · A mark all · a add · ↵ open · esc close",
+            results.pattern,
+            results.hits.len(),
+            if results.truncated {
+                " (truncated)"
+            } else {
+                ""
+            },
+            if state.marked.is_empty() {
+                String::new()
+            } else {
+                format!(" · {} marked", state.marked.len())
+            },
+        );
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .title(title)
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Cyan)),
+            )
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("▸ ");
+        let mut list_state = ListState::default();
+        list_state.select(Some(state.selected));
+        frame.render_stateful_widget(list, popup, &mut list_state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    fn hit(line: usize) -> SearchHit {
+        SearchHit {
+            path: PathBuf::from("/repo/src/lib.rs"),
+            display_path: "src/lib.rs".to_string(),
+            line,
+            end: line,
+            text: "run();".to_string(),
+        }
+    }
+
+    #[test]
+    fn marked_hits_fall_back_to_cursor() {
+        let mut state = SearchResultsState::default();
+        state.open(SearchResults {
+            pattern: "run".to_string(),
+            hits: vec![hit(3), hit(8), hit(12)],
+            truncated: false,
+        });
+        let lines = |state: &SearchResultsState| -> Vec<usize> {
+            state.marked_hits().iter().map(|hit| hit.line).collect()
+        };
+        assert_eq!(lines(&state), vec![3]);
+
+        state.select_next();
+        state.select_next();
+        state.select_next();
+        state.toggle_mark();
+        state.select_previous();
+        state.select_previous();
+        state.toggle_mark();
+        assert_eq!(lines(&state), vec![3, 12]);
+
+        state.toggle_all();
+        assert_eq!(lines(&state), vec![3, 8, 12]);
+        state.toggle_all();
+        assert_eq!(lines(&state), vec![3]);
+
+        state.close();
+        assert!(!state.is_open());
+        assert!(state.marked_hits().is_empty());
+    }
+}
@@ -0,0 +1,226 @@
+//! Review pane for `llmctx suggest`, where proposed files are pruned before being accepted.
+
+use std::collections::BTreeSet;
+
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState};
+
+use crate::app::suggest::{Suggestion, Suggestions};
+
+/// Interactive state backing the suggestions pane.
+#[derive(Debug, Default, Clone)]
+pub struct SuggestionsState {
+    suggestions: Option<Suggestions>,
+    selected: usize,
+    /// Indices of suggestions pruned from the set.
+    pruned: BTreeSet<usize>,
+}
+
+impl SuggestionsState {
+    /// Show `suggestions` with every one kept.
+    pub fn open(&mut self, suggestions: Suggestions) {
+        self.suggestions = Some(suggestions);
+        self.selected = 0;
+        self.pruned.clear();
+    }
+
+    /// Hide the pane.
+    pub fn close(&mut self) {
+        self.suggestions = None;
+        self.pruned.clear();
+    }
+
+    /// Whether the pane is currently displayed.
+    pub fn is_open(&self) -> bool {
+        self.suggestions.is_some()
+    }
+
+    fn items(&self) -> &[Suggestion] {
+        self.suggestions
+            .as_ref()
+            .map(|suggestions| suggestions.items.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Move the cursor down, stopping at the last suggestion.
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.items().len() {
+            self.selected += 1;
+        }
+    }
+
+    /// Move the cursor up, stopping at the first suggestion.
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    /// Prune the suggestion under the cursor, or keep it again.
+    pub fn toggle_pruned(&mut self) {
+        if self.selected >= self.items().len() {
+            return;
+        }
+        if !self.pruned.remove(&self.selected) {
+            self.pruned.insert(self.selected);
+        }
+    }
+
+    /// Suggestions still kept, best first.
+    pub fn kept(&self) -> Vec<&Suggestion> {
+        self.items()
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !self.pruned.contains(index))
+            .map(|(_, item)| item)
+            .collect()
+    }
+
+    /// Suggestion under the cursor.
+    pub fn selected_suggestion(&self) -> Option<&Suggestion> {
+        self.items().get(self.selected)
+    }
+}
+
+/// Visual component that renders the suggestions pane.
+#[derive(Debug, Default)]
+pub struct SuggestionsPane;
+
+impl SuggestionsPane {
+    /// Draw the suggestions over most of `area`.
+    pub fn render(&self, frame: &mut Frame<'_>, area: Rect, state: &SuggestionsState) {
+        let Some(suggestions) = &state.suggestions else {
+            return;
+        };
+
+        let width = area.width.saturating_sub(4).min(120);
+        let height = area.height.saturating_sub(2);
+        let popup = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+        frame.render_widget(Clear, popup);
+
+        let items: Vec<ListItem> = suggestions
+            .items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| {
+                let pruned = state.pruned.contains(&index);
+                let (mark, path_style) = if pruned {
+                    (
+                        Span::styled("✗ ", Style::default().fg(Color::DarkGray)),
+                        Style::default()
+                            .fg(Color::DarkGray)
+                            .add_modifier(Modifier::CROSSED_OUT),
+                    )
+                } else {
+                    (
+                        Span::styled("✓ ", Style::default().fg(Color::Green)),
+                        Style::default().fg(Color::Cyan),
+                    )
+                };
+                let reasons: Vec<String> = item.reasons.iter().map(ToString::to_string).collect();
+                ListItem::new(Line::from(vec![
+                    mark,
+                    Span::styled(item.display_path.clone(), path_style),
+                    Span::raw(format!("  {} tok  ", item.tokens)),
+                    Span::styled(reasons.join("; "), Style::default().fg(Color::Gray)),
+                ]))
+            })
+            .collect();
+        let kept = state.kept();
+        let tokens: usize = kept.iter().map(|item| item.tokens).sum();
+        let tokens = match suggestions.budget {
+            0 => format!("{tokens} tokens"),
+            budget => format!("{tokens}/{budget} tokens"),
+        };
+        let title = format!(
+            "Suggested for '{}' · {}/{} kept · {tokens}{} · space prune · ↵ accept · esc discard",
+            suggestions.task,
+            kept.len(),
+            suggestions.items.len(),
+            if suggestions.over_budget > 0 {
+                format!(" · {} over budget", suggestions.over_budget)
+            } else {
+                String::new()
+            },
+        );
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .title(title)
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Cyan)),
+            )
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("▸ ");
+        let mut list_state = ListState::default();
+        list_state.select(Some(state.selected));
+        frame.render_stateful_widget(list, popup, &mut list_state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    fn suggestion(display_path: &str) -> Suggestion {
+        Suggestion {
+            path: PathBuf::from("/repo").join(display_path),
+            display_path: display_path.to_string(),
+            score: 1.0,
+            tokens: 100,
+            reasons: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn pruned_suggestions_are_left_out() {
+        let mut state = SuggestionsState::default();
+        state.open(Suggestions {
+            task: "retry".to_string(),
+            items: vec![
+                suggestion("src/retry.rs"),
+                suggestion("src/client.rs"),
+                suggestion("src/lib.rs"),
+            ],
+            budget: 1_000,
+            ..Suggestions::default()
+        });
+        let kept = |state: &SuggestionsState| -> Vec<String> {
+            state
+                .kept()
+                .iter()
+                .map(|item| item.display_path.clone())
+                .collect()
+        };
+        assert_eq!(kept(&state).len(), 3);
+
+        state.select_next();
+        state.toggle_pruned();
+        assert_eq!(kept(&state), vec!["src/retry.rs", "src/lib.rs"]);
+        state.toggle_pruned();
+        assert_eq!(kept(&state).len(), 3);
+
+        state.select_next();
+        state.select_next();
+        state.toggle_pruned();
+        assert_eq!(
+            state
+                .selected_suggestion()
+                .map(|item| item.display_path.as_str()),
+            Some("src/lib.rs")
+        );
+        assert_eq!(kept(&state), vec!["src/retry.rs", "src/client.rs"]);
+
+        state.close();
+        assert!(!state.is_open());
+        assert!(state.kept().is_empty());
+    }
+}
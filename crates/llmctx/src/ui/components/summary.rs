@@ -1,17 +1,85 @@
 //! Selection summary component.
 
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+
 use ratatui::Frame;
 use ratatui::layout::{Constraint, Direction, Layout, Rect};
-use ratatui::style::{Color, Style};
+use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
-use ratatui::widgets::{Block, Borders, List, ListItem, Paragraph, Wrap};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
 
+use crate::app::fit::FitHint;
+use crate::app::scan::slash_path;
+use crate::app::selection::SelectionWarning;
 use crate::app::tokens::{BundleTokenSummary, ItemTokenEstimate};
+use crate::domain::model::{BundleStats, SelectionItem};
+use crate::ui::text::truncate_start;
+
+/// Number of directory groups listed individually before folding the rest into "other".
+const MAX_ALLOCATION_GROUPS: usize = 4;
+/// Number of most expensive selections flagged in the item list.
+const TOP_SELECTIONS: usize = 3;
+/// Number of languages named in the header before the rest are counted as "+N".
+const HEADER_LANGUAGES: usize = 3;
+const GROUP_COLORS: [Color; MAX_ALLOCATION_GROUPS] =
+    [Color::Cyan, Color::Magenta, Color::Yellow, Color::Green];
+
+/// Ordering applied to the selection list.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SummarySort {
+    /// Order in which selections were added.
+    #[default]
+    Selection,
+    /// Most expensive selections first.
+    Tokens,
+    /// Alphabetical by path, then by range.
+    Path,
+}
+
+impl SummarySort {
+    fn next(self) -> Self {
+        match self {
+            SummarySort::Selection => SummarySort::Tokens,
+            SummarySort::Tokens => SummarySort::Path,
+            SummarySort::Path => SummarySort::Selection,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            SummarySort::Selection => "added",
+            SummarySort::Tokens => "tokens",
+            SummarySort::Path => "path",
+        }
+    }
+}
+
+/// What the summary pane shows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SummaryView {
+    /// Budget, allocation by directory, and the selection list.
+    #[default]
+    Summary,
+    /// Only the selection list with each selection's tokens, for short panes.
+    WorkingSet,
+}
 
 /// Displays aggregated selection statistics including token usage.
 #[derive(Debug, Default)]
 pub struct Summary {
     latest: Option<BundleTokenSummary>,
+    stats: Option<BundleStats>,
+    /// Problems found by the latest selection validation pass.
+    warnings: Vec<SelectionWarning>,
+    /// Ways to get back under budget, applied with the number keys.
+    hints: Vec<FitHint>,
+    root: Option<PathBuf>,
+    /// Paths added automatically from `.llmctx/always-include.toml`.
+    defaults: HashSet<PathBuf>,
+    sort: SummarySort,
+    selected: usize,
+    view: SummaryView,
 }
 
 impl Summary {
@@ -21,24 +89,136 @@ impl Summary {
 
     /// Replace the stored summary with fresh data from the estimator.
     pub fn update(&mut self, summary: BundleTokenSummary) {
+        self.selected = self.selected.min(summary.items.len().saturating_sub(1));
         self.latest = Some(summary);
     }
 
+    /// Replace the file, line, and language counts shown in the header.
+    pub fn set_stats(&mut self, stats: BundleStats) {
+        self.stats = Some(stats);
+    }
+
+    /// Replace the validation warnings flagged beside their selections.
+    pub fn set_warnings(&mut self, warnings: Vec<SelectionWarning>) {
+        self.warnings = warnings;
+    }
+
+    /// Replace the suggestions for getting back under budget.
+    pub fn set_hints(&mut self, hints: Vec<FitHint>) {
+        self.hints = hints;
+    }
+
+    /// Suggestion shown as number `index + 1`.
+    pub fn hint(&self, index: usize) -> Option<&FitHint> {
+        self.hints.get(index)
+    }
+
     /// Clear the rendered state when selections are emptied.
     pub fn clear(&mut self) {
         self.latest = None;
+        self.stats = None;
+        self.warnings.clear();
+        self.hints.clear();
+        self.selected = 0;
+    }
+
+    /// Workspace root used to group selections by top-level directory.
+    pub fn set_root(&mut self, root: PathBuf) {
+        self.root = Some(root);
+    }
+
+    /// Paths to flag as workspace defaults rather than manual picks.
+    pub fn set_defaults(&mut self, defaults: HashSet<PathBuf>) {
+        self.defaults = defaults;
+    }
+
+    /// Whether any selections are listed.
+    pub fn has_items(&self) -> bool {
+        self.latest
+            .as_ref()
+            .is_some_and(|summary| !summary.items.is_empty())
+    }
+
+    /// Move the list cursor down.
+    pub fn select_next(&mut self) {
+        let len = self
+            .latest
+            .as_ref()
+            .map_or(0, |summary| summary.items.len());
+        if self.selected + 1 < len {
+            self.selected += 1;
+        }
+    }
+
+    /// Move the list cursor up.
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    /// Advance to the next sort order, keeping the cursor on the same selection.
+    pub fn cycle_sort(&mut self) {
+        let current = self.selected_position();
+        self.sort = self.sort.next();
+        if let Some(position) = current
+            && let Some(summary) = &self.latest
+        {
+            let order = sorted_indices(&summary.items, self.sort);
+            self.selected = order.iter().position(|idx| *idx == position).unwrap_or(0);
+        }
+    }
+
+    /// Active sort order.
+    pub fn sort(&self) -> SummarySort {
+        self.sort
+    }
+
+    /// Switch between the full summary and the working set list.
+    pub fn toggle_view(&mut self) -> SummaryView {
+        self.view = match self.view {
+            SummaryView::Summary => SummaryView::WorkingSet,
+            SummaryView::WorkingSet => SummaryView::Summary,
+        };
+        self.view
+    }
+
+    /// What the pane shows.
+    pub fn view(&self) -> SummaryView {
+        self.view
+    }
+
+    /// Selection under the list cursor.
+    pub fn selected_item(&self) -> Option<&SelectionItem> {
+        let summary = self.latest.as_ref()?;
+        let position = self.selected_position()?;
+        summary.items.get(position).map(|estimate| &estimate.item)
+    }
+
+    fn selected_position(&self) -> Option<usize> {
+        let summary = self.latest.as_ref()?;
+        sorted_indices(&summary.items, self.sort)
+            .get(self.selected)
+            .copied()
     }
 
     /// Render the summary inside the provided area.
-    pub fn render(&self, frame: &mut Frame<'_>, area: Rect) {
+    pub fn render(&self, frame: &mut Frame<'_>, area: Rect, has_focus: bool) {
+        let border_color = if has_focus { Color::Cyan } else { Color::Reset };
+        let title = match self.view {
+            SummaryView::Summary => "Selection Summary",
+            SummaryView::WorkingSet => "Working Set",
+        };
         let block = Block::default()
-            .title("Selection Summary")
-            .borders(Borders::ALL);
+            .title(format!("{title} · by {}", self.sort.label()))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(border_color));
         frame.render_widget(block.clone(), area);
 
         let inner = block.inner(area);
         match &self.latest {
-            Some(summary) => self.render_summary(frame, inner, summary),
+            Some(summary) if self.view == SummaryView::WorkingSet => {
+                self.render_working_set(frame, inner, summary, has_focus)
+            }
+            Some(summary) => self.render_summary(frame, inner, summary, has_focus),
             None => {
                 let placeholder = Paragraph::new("No selections")
                     .wrap(Wrap { trim: true })
@@ -48,27 +228,267 @@ impl Summary {
         }
     }
 
-    fn render_summary(&self, frame: &mut Frame<'_>, area: Rect, summary: &BundleTokenSummary) {
+    fn render_summary(
+        &self,
+        frame: &mut Frame<'_>,
+        area: Rect,
+        summary: &BundleTokenSummary,
+        has_focus: bool,
+    ) {
+        let groups = allocation_groups(summary, self.root.as_deref());
+        let allocation_height = if groups.is_empty() {
+            0
+        } else {
+            groups.len() as u16 + 1
+        };
+        let hints_height = if self.hints.is_empty() {
+            0
+        } else {
+            self.hints.len() as u16 + 1
+        };
         let layout = Layout::default()
             .direction(Direction::Vertical)
-            .constraints([Constraint::Length(4), Constraint::Min(1)])
+            .constraints([
+                Constraint::Length(5),
+                Constraint::Length(allocation_height),
+                Constraint::Length(hints_height),
+                Constraint::Min(1),
+            ])
             .split(area);
 
-        let header = Paragraph::new(header_lines(summary)).wrap(Wrap { trim: true });
+        let header =
+            Paragraph::new(header_lines(summary, self.stats.as_ref())).wrap(Wrap { trim: true });
         frame.render_widget(header, layout[0]);
 
-        let items = build_item_list(&summary.items);
+        if !groups.is_empty() {
+            let lines = allocation_lines(summary, &groups, layout[1].width);
+            frame.render_widget(Paragraph::new(lines), layout[1]);
+        }
+
+        if !self.hints.is_empty() {
+            let lines = hint_lines(summary, &self.hints, self.root.as_deref());
+            frame.render_widget(Paragraph::new(lines), layout[2]);
+        }
+
+        let order = sorted_indices(&summary.items, self.sort);
+        let items = build_item_list(&summary.items, &order, &self.defaults, &self.warnings);
+        self.render_list(frame, layout[3], items, has_focus);
+    }
+
+    /// Selections one per line with their token counts, above a single usage line.
+    fn render_working_set(
+        &self,
+        frame: &mut Frame<'_>,
+        area: Rect,
+        summary: &BundleTokenSummary,
+        has_focus: bool,
+    ) {
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(1)])
+            .split(area);
+
+        let order = sorted_indices(&summary.items, self.sort);
+        let items = working_set_items(
+            &summary.items,
+            &order,
+            self.root.as_deref(),
+            &self.warnings,
+            layout[0].width as usize,
+        );
+        self.render_list(frame, layout[0], items, has_focus);
+
+        let budget = if summary.token_budget == 0 {
+            "unbounded".to_string()
+        } else {
+            summary.token_budget.to_string()
+        };
+        let total = Line::from(vec![
+            Span::styled(
+                format!("{} selections", summary.items.len()),
+                Style::default().fg(Color::Gray),
+            ),
+            Span::raw(format!(" · {} / {budget} tokens", summary.total_tokens)),
+        ]);
+        frame.render_widget(Paragraph::new(total), layout[1]);
+    }
+
+    fn render_list(
+        &self,
+        frame: &mut Frame<'_>,
+        area: Rect,
+        items: Vec<ListItem<'static>>,
+        has_focus: bool,
+    ) {
         if items.is_empty() {
             let empty = Paragraph::new("No files selected").wrap(Wrap { trim: true });
-            frame.render_widget(empty, layout[1]);
+            frame.render_widget(empty, area);
+            return;
+        }
+        let mut list_state = ListState::default();
+        if has_focus {
+            list_state.select(Some(self.selected));
+        }
+        let list = List::new(items).block(Block::default()).highlight_style(
+            Style::default()
+                .fg(Color::Black)
+                .bg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        );
+        frame.render_stateful_widget(list, area, &mut list_state);
+    }
+}
+
+/// Indices into `items` in display order for the given sort.
+fn sorted_indices(items: &[ItemTokenEstimate], sort: SummarySort) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..items.len()).collect();
+    match sort {
+        SummarySort::Selection => {}
+        SummarySort::Tokens => {
+            order.sort_by(|a, b| items[*b].tokens.cmp(&items[*a].tokens).then(a.cmp(b)));
+        }
+        SummarySort::Path => order.sort_by(|a, b| {
+            let left = &items[*a].item;
+            let right = &items[*b].item;
+            left.path
+                .cmp(&right.path)
+                .then(left.range.cmp(&right.range))
+        }),
+    }
+    order
+}
+
+/// Token usage attributed to a top-level directory of the workspace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct AllocationGroup {
+    label: String,
+    tokens: usize,
+    color: Color,
+}
+
+fn allocation_groups(summary: &BundleTokenSummary, root: Option<&Path>) -> Vec<AllocationGroup> {
+    let mut totals: HashMap<String, usize> = HashMap::new();
+    for item in &summary.items {
+        *totals
+            .entry(group_label(&item.item.path, root))
+            .or_default() += item.tokens;
+    }
+
+    let mut ranked: Vec<(String, usize)> = totals.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    let mut groups = Vec::new();
+    let mut other = 0usize;
+    for (idx, (label, tokens)) in ranked.into_iter().enumerate() {
+        if idx < MAX_ALLOCATION_GROUPS {
+            groups.push(AllocationGroup {
+                label,
+                tokens,
+                color: GROUP_COLORS[idx],
+            });
         } else {
-            let list = List::new(items).block(Block::default());
-            frame.render_widget(list, layout[1]);
+            other += tokens;
         }
     }
+    if other > 0 {
+        groups.push(AllocationGroup {
+            label: "other".into(),
+            tokens: other,
+            color: Color::DarkGray,
+        });
+    }
+    groups
+}
+
+fn group_label(path: &Path, root: Option<&Path>) -> String {
+    let relative = root
+        .and_then(|root| path.strip_prefix(root).ok())
+        .unwrap_or(path);
+    let mut components = relative
+        .components()
+        .filter(|component| matches!(component, Component::Normal(_)));
+    match (components.next(), components.next()) {
+        (Some(first), Some(_)) => format!("{}/", first.as_os_str().to_string_lossy()),
+        _ => "./".to_string(),
+    }
 }
 
-fn header_lines(summary: &BundleTokenSummary) -> Vec<Line<'static>> {
+fn allocation_lines(
+    summary: &BundleTokenSummary,
+    groups: &[AllocationGroup],
+    width: u16,
+) -> Vec<Line<'static>> {
+    // The bar spans the budget; when the bundle overflows it spans the total instead.
+    let scale = (summary.token_budget as usize)
+        .max(summary.total_tokens)
+        .max(1);
+    let width = width as usize;
+
+    let mut bar = Vec::with_capacity(groups.len() + 1);
+    let mut used = 0usize;
+    for group in groups {
+        let cells = (group.tokens * width)
+            .div_ceil(scale)
+            .min(width.saturating_sub(used));
+        used += cells;
+        bar.push(Span::styled(
+            "█".repeat(cells),
+            Style::default().fg(group.color),
+        ));
+    }
+    bar.push(Span::styled(
+        "·".repeat(width.saturating_sub(used)),
+        Style::default().fg(Color::DarkGray),
+    ));
+
+    let mut lines = vec![Line::from(bar)];
+    for group in groups {
+        let percent = group.tokens as f64 / scale as f64 * 100.0;
+        lines.push(Line::from(vec![
+            Span::styled("■ ", Style::default().fg(group.color)),
+            Span::raw(group.label.clone()),
+            Span::styled(
+                format!(" {percent:.0}% · {} tokens", group.tokens),
+                Style::default().fg(Color::Gray),
+            ),
+        ]));
+    }
+    lines
+}
+
+/// "Over budget by N" followed by the numbered suggestions.
+fn hint_lines(
+    summary: &BundleTokenSummary,
+    hints: &[FitHint],
+    root: Option<&Path>,
+) -> Vec<Line<'static>> {
+    let overflow = summary
+        .total_tokens
+        .saturating_sub(summary.token_budget as usize);
+    let mut lines = vec![Line::from(Span::styled(
+        format!("Over budget by {overflow} tokens; to fit:"),
+        Style::default().fg(Color::Red),
+    ))];
+    for (index, hint) in hints.iter().enumerate() {
+        let style = if hint.tokens() >= overflow {
+            Style::default().fg(Color::Green)
+        } else {
+            Style::default().fg(Color::Gray)
+        };
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("{} ", index + 1),
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(hint.describe(root), style),
+        ]));
+    }
+    lines
+}
+
+fn header_lines(summary: &BundleTokenSummary, stats: Option<&BundleStats>) -> Vec<Line<'static>> {
     let usage_ratio = if summary.token_budget == 0 {
         0.0
     } else {
@@ -97,7 +517,7 @@ fn header_lines(summary: &BundleTokenSummary) -> Vec<Line<'static>> {
         format!("{:.0}%", (usage_ratio * 100.0).clamp(0.0, 999.0))
     };
 
-    vec![
+    let mut lines = vec![
         Line::from(vec![
             Span::styled("Model", Style::default().fg(Color::Gray)),
             Span::raw(": "),
@@ -112,25 +532,88 @@ fn header_lines(summary: &BundleTokenSummary) -> Vec<Line<'static>> {
             Span::raw(" ("),
             Span::styled(percent, Style::default().fg(status_color)),
             Span::raw(")"),
+            Span::styled(
+                if summary.stripped_tokens > 0 {
+                    format!(" · {} stripped", summary.stripped_tokens)
+                } else {
+                    String::new()
+                },
+                Style::default().fg(Color::Gray),
+            ),
         ]),
         Line::from(vec![
             Span::styled("Characters", Style::default().fg(Color::Gray)),
             Span::raw(": "),
-            Span::raw(format!("{}", summary.total_characters)),
+            Span::raw(format!(
+                "{} · {} words · {} lines",
+                summary.total_characters, summary.total_words, summary.total_lines
+            )),
         ]),
-    ]
+    ];
+    if let Some(stats) = stats {
+        lines.push(stats_line(stats));
+    }
+    lines
+}
+
+/// "Files: 3 · 420 lines · rs 2, md 1" line summarising the bundle without tokens.
+fn stats_line(stats: &BundleStats) -> Line<'static> {
+    let mut spans = vec![
+        Span::styled("Files", Style::default().fg(Color::Gray)),
+        Span::raw(format!(": {} · {} lines", stats.files, stats.total_lines)),
+    ];
+    if !stats.languages.is_empty() {
+        let mut languages: Vec<String> = stats
+            .languages
+            .iter()
+            .take(HEADER_LANGUAGES)
+            .map(|language| format!("{} {}", language.language, language.files))
+            .collect();
+        if stats.languages.len() > HEADER_LANGUAGES {
+            languages.push(format!("+{}", stats.languages.len() - HEADER_LANGUAGES));
+        }
+        spans.push(Span::raw(" · "));
+        spans.push(Span::styled(
+            languages.join(", "),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    Line::from(spans)
 }
 
-fn build_item_list(items: &[ItemTokenEstimate]) -> Vec<ListItem<'static>> {
-    items
+fn build_item_list(
+    items: &[ItemTokenEstimate],
+    order: &[usize],
+    defaults: &HashSet<PathBuf>,
+    warnings: &[SelectionWarning],
+) -> Vec<ListItem<'static>> {
+    let ranks = top_item_ranks(items);
+    order
         .iter()
-        .map(|item| {
+        .map(|idx| (*idx, &items[*idx]))
+        .map(|(idx, item)| {
             let mut label = item.item.path.display().to_string();
             if let Some((start, end)) = item.item.range {
                 label.push_str(&format!(" [{start}-{end}]"));
             }
             label.push_str(&format!(" – {} tokens", item.tokens));
-            let mut spans = vec![Span::raw(label)];
+            let mut spans = Vec::new();
+            if let Some(rank) = ranks.get(&idx) {
+                spans.push(Span::styled(
+                    format!("▲{rank} "),
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                ));
+            }
+            if defaults.contains(&item.item.path) {
+                spans.push(Span::styled("◇ ", Style::default().fg(Color::Green)));
+            }
+            spans.push(Span::raw(label));
+            if !item.item.source.is_manual() {
+                spans.push(Span::styled(
+                    format!(" [{}]", item.item.source.label()),
+                    Style::default().fg(Color::Blue),
+                ));
+            }
             if let Some(note) = &item.item.note {
                 spans.push(Span::raw(" "));
                 spans.push(Span::styled(
@@ -138,11 +621,80 @@ fn build_item_list(items: &[ItemTokenEstimate]) -> Vec<ListItem<'static>> {
                     Style::default().fg(Color::DarkGray),
                 ));
             }
+            if let Some(warning) = warnings
+                .iter()
+                .find(|warning| warning.concerns(&item.item.path, item.item.range))
+            {
+                spans.insert(0, Span::styled("⚠ ", Style::default().fg(Color::Yellow)));
+                spans.push(Span::styled(
+                    format!(" – {}", warning.issue),
+                    Style::default().fg(Color::Yellow),
+                ));
+            }
             ListItem::new(Line::from(spans))
         })
         .collect()
 }
 
+/// Compact rows for the working set: token count first, then the workspace-relative path.
+///
+/// Paths too long for `width` columns lose their start, so file names stay readable.
+fn working_set_items(
+    items: &[ItemTokenEstimate],
+    order: &[usize],
+    root: Option<&Path>,
+    warnings: &[SelectionWarning],
+    width: usize,
+) -> Vec<ListItem<'static>> {
+    let tokens_width = order
+        .iter()
+        .map(|idx| items[*idx].tokens.to_string().len())
+        .max()
+        .unwrap_or(0);
+    order
+        .iter()
+        .map(|idx| &items[*idx])
+        .map(|item| {
+            let path = root
+                .and_then(|root| item.item.path.strip_prefix(root).ok())
+                .unwrap_or(&item.item.path);
+            let mut label = slash_path(path);
+            if let Some((start, end)) = item.item.range {
+                label.push_str(&format!(":{start}-{end}"));
+            }
+            let warned = warnings
+                .iter()
+                .any(|warning| warning.concerns(&item.item.path, item.item.range));
+            let label_width = width.saturating_sub(tokens_width + 1 + if warned { 2 } else { 0 });
+            let mut spans = vec![
+                Span::styled(
+                    format!("{:>tokens_width$} ", item.tokens),
+                    Style::default().fg(Color::Cyan),
+                ),
+                Span::raw(truncate_start(&label, label_width).into_owned()),
+            ];
+            if warned {
+                spans.push(Span::styled(" ⚠", Style::default().fg(Color::Yellow)));
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect()
+}
+
+/// Map item indices of the most expensive selections to their 1-based rank.
+fn top_item_ranks(items: &[ItemTokenEstimate]) -> HashMap<usize, usize> {
+    let mut order: Vec<usize> = (0..items.len())
+        .filter(|idx| items[*idx].tokens > 0)
+        .collect();
+    order.sort_by(|a, b| items[*b].tokens.cmp(&items[*a].tokens).then(a.cmp(b)));
+    order
+        .into_iter()
+        .take(TOP_SELECTIONS)
+        .enumerate()
+        .map(|(rank, idx)| (idx, rank + 1))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -151,7 +703,7 @@ mod tests {
     use ratatui::backend::TestBackend;
 
     use crate::app::tokens::TokenModel;
-    use crate::domain::model::SelectionItem;
+    use crate::domain::model::{LanguageStats, SelectionItem, SelectionSource};
 
     #[test]
     fn renders_empty_state_without_summary() {
@@ -161,7 +713,7 @@ mod tests {
         terminal
             .draw(|frame| {
                 let area = frame.size();
-                summary.render(frame, area);
+                summary.render(frame, area, false);
             })
             .unwrap();
     }
@@ -177,14 +729,20 @@ mod tests {
             token_budget: 1_000,
             total_tokens: 120,
             total_characters: 480,
+            total_words: 60,
+            total_lines: 5,
+            stripped_tokens: 0,
             items: vec![ItemTokenEstimate {
                 item: SelectionItem {
                     path: "path/to/file.rs".into(),
                     range: Some((1, 5)),
                     note: Some("example".into()),
+                    source: SelectionSource::Glob,
                 },
                 tokens: 120,
                 characters: 480,
+                words: 60,
+                lines: 5,
             }],
         };
         summary.update(data);
@@ -192,8 +750,173 @@ mod tests {
         terminal
             .draw(|frame| {
                 let area = frame.size();
-                summary.render(frame, area);
+                summary.render(frame, area, false);
             })
             .unwrap();
     }
+
+    #[test]
+    fn stats_line_folds_extra_languages() {
+        let language = |name: &str, files| LanguageStats {
+            language: name.to_string(),
+            files,
+            lines: files * 10,
+        };
+        let stats = BundleStats {
+            files: 7,
+            total_lines: 70,
+            languages: vec![
+                language("rs", 3),
+                language("md", 2),
+                language("toml", 1),
+                language("sql", 1),
+            ],
+            largest: Vec::new(),
+        };
+        let text: String = stats_line(&stats)
+            .spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect();
+        assert_eq!(text, "Files: 7 · 70 lines · rs 3, md 2, toml 1, +1");
+    }
+
+    fn estimate(path: &str, tokens: usize) -> ItemTokenEstimate {
+        ItemTokenEstimate {
+            item: SelectionItem {
+                path: path.into(),
+                range: None,
+                note: None,
+                source: SelectionSource::Manual,
+            },
+            tokens,
+            characters: tokens * 4,
+            words: tokens,
+            lines: tokens / 10,
+        }
+    }
+
+    #[test]
+    fn groups_allocation_by_top_level_directory() {
+        let items = vec![
+            estimate("/repo/src/lib.rs", 300),
+            estimate("/repo/src/app/mod.rs", 200),
+            estimate("/repo/docs/guide.md", 100),
+            estimate("/repo/README.md", 50),
+        ];
+        let summary = BundleTokenSummary {
+            model: TokenModel::CharacterFallback,
+            token_budget: 1_000,
+            total_tokens: 650,
+            total_characters: 2_600,
+            total_words: 650,
+            total_lines: 65,
+            stripped_tokens: 0,
+            items,
+        };
+
+        let groups = allocation_groups(&summary, Some(Path::new("/repo")));
+        let labels: Vec<_> = groups
+            .iter()
+            .map(|group| (group.label.as_str(), group.tokens))
+            .collect();
+        assert_eq!(labels, vec![("src/", 500), ("docs/", 100), ("./", 50)]);
+    }
+
+    #[test]
+    fn flags_three_most_expensive_selections() {
+        let items = vec![
+            estimate("a.rs", 10),
+            estimate("b.rs", 40),
+            estimate("c.rs", 30),
+            estimate("d.rs", 20),
+        ];
+        let ranks = top_item_ranks(&items);
+        assert_eq!(ranks.get(&1), Some(&1));
+        assert_eq!(ranks.get(&2), Some(&2));
+        assert_eq!(ranks.get(&3), Some(&3));
+        assert!(!ranks.contains_key(&0));
+    }
+
+    #[test]
+    fn sorting_keeps_cursor_on_same_selection() {
+        let mut summary = Summary::new();
+        summary.update(BundleTokenSummary {
+            model: TokenModel::CharacterFallback,
+            token_budget: 1_000,
+            total_tokens: 60,
+            total_characters: 240,
+            total_words: 60,
+            total_lines: 6,
+            stripped_tokens: 0,
+            items: vec![
+                estimate("b.rs", 10),
+                estimate("a.rs", 30),
+                estimate("c.rs", 20),
+            ],
+        });
+
+        summary.select_next();
+        assert_eq!(summary.selected_item().unwrap().path, Path::new("a.rs"));
+
+        summary.cycle_sort();
+        assert_eq!(summary.sort(), SummarySort::Tokens);
+        assert_eq!(summary.selected_item().unwrap().path, Path::new("a.rs"));
+
+        summary.select_next();
+        assert_eq!(summary.selected_item().unwrap().path, Path::new("c.rs"));
+
+        summary.cycle_sort();
+        assert_eq!(summary.sort(), SummarySort::Path);
+        assert_eq!(summary.selected_item().unwrap().path, Path::new("c.rs"));
+        summary.select_previous();
+        assert_eq!(summary.selected_item().unwrap().path, Path::new("b.rs"));
+    }
+
+    #[test]
+    fn working_set_lists_relative_paths_with_tokens() {
+        let mut summary = Summary::new();
+        summary.set_root(PathBuf::from("/repo"));
+        summary.update(BundleTokenSummary {
+            model: TokenModel::CharacterFallback,
+            token_budget: 1_000,
+            total_tokens: 1_250,
+            total_characters: 5_000,
+            total_words: 1_250,
+            total_lines: 125,
+            stripped_tokens: 0,
+            items: vec![
+                estimate("/repo/src/lib.rs", 1_200),
+                estimate("/repo/README.md", 50),
+                estimate("/repo/docs/仕様書/とても長い設計メモとレビュー記録.md", 0),
+            ],
+        });
+        assert_eq!(summary.toggle_view(), SummaryView::WorkingSet);
+
+        let mut terminal = Terminal::new(TestBackend::new(40, 8)).unwrap();
+        terminal
+            .draw(|frame| {
+                let area = frame.size();
+                summary.render(frame, area, false);
+            })
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        let rows: Vec<String> = (0..buffer.area.height)
+            .map(|y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer.get(x, y).symbol())
+                    .collect::<String>()
+            })
+            .collect();
+        assert!(rows[0].contains("Working Set · by added"));
+        assert!(rows[1].contains("1200 src/lib.rs"));
+        assert!(rows[2].contains("  50 README.md"));
+        assert!(
+            rows[3].contains("   0 …も") && rows[3].contains(".md"),
+            "{rows:?}"
+        );
+        assert!(rows[6].contains("3 selections · 1250 / 1000 tokens"));
+
+        assert_eq!(summary.toggle_view(), SummaryView::Summary);
+    }
 }
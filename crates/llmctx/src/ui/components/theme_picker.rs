@@ -0,0 +1,135 @@
+//! Theme picker overlay that previews highlight themes as the cursor moves.
+
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState};
+
+/// Interactive state backing the theme picker.
+#[derive(Debug, Default, Clone)]
+pub struct ThemePickerState {
+    themes: Vec<String>,
+    selected: usize,
+    /// Theme active when the picker opened, restored on cancel.
+    original: Option<String>,
+}
+
+impl ThemePickerState {
+    /// Show `themes`, starting at `current` when it is listed.
+    pub fn open(&mut self, themes: Vec<String>, current: &str) {
+        self.selected = themes
+            .iter()
+            .position(|theme| theme.eq_ignore_ascii_case(current))
+            .unwrap_or(0);
+        self.themes = themes;
+        self.original = Some(current.to_string());
+    }
+
+    /// Hide the picker, returning the theme that was active when it opened.
+    pub fn close(&mut self) -> Option<String> {
+        self.themes.clear();
+        self.original.take()
+    }
+
+    /// Whether the picker is currently displayed.
+    pub fn is_open(&self) -> bool {
+        self.original.is_some()
+    }
+
+    /// Move the cursor down, wrapping to the top.
+    pub fn select_next(&mut self) {
+        if !self.themes.is_empty() {
+            self.selected = (self.selected + 1) % self.themes.len();
+        }
+    }
+
+    /// Move the cursor up, wrapping to the bottom.
+    pub fn select_previous(&mut self) {
+        if !self.themes.is_empty() {
+            self.selected = self
+                .selected
+                .checked_sub(1)
+                .unwrap_or(self.themes.len() - 1);
+        }
+    }
+
+    /// Theme under the cursor.
+    pub fn selected_theme(&self) -> Option<&str> {
+        self.themes.get(self.selected).map(String::as_str)
+    }
+}
+
+/// Visual component that renders the theme picker overlay.
+#[derive(Debug, Default)]
+pub struct ThemePicker;
+
+impl ThemePicker {
+    /// Draw the picker in the top-right corner of `area`, leaving the preview visible beside it.
+    pub fn render(&self, frame: &mut Frame<'_>, area: Rect, state: &ThemePickerState) {
+        if !state.is_open() {
+            return;
+        }
+
+        let width = state
+            .themes
+            .iter()
+            .map(|theme| theme.chars().count() as u16 + 6)
+            .max()
+            .unwrap_or(0)
+            .max(28)
+            .min(area.width);
+        let height = (state.themes.len() as u16 + 3).min(area.height);
+        let popup = Rect {
+            x: area.x + area.width - width,
+            y: area.y,
+            width,
+            height,
+        };
+        frame.render_widget(Clear, popup);
+
+        let items: Vec<ListItem> = state
+            .themes
+            .iter()
+            .map(|theme| ListItem::new(Line::from(theme.clone())))
+            .collect();
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .title("Theme · ↵ keep · esc cancel")
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Cyan)),
+            )
+            .highlight_style(
+                Style::default()
+                    .fg(Color::Black)
+                    .bg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol("▸ ");
+        let mut list_state = ListState::default();
+        list_state.select(Some(state.selected));
+        frame.render_stateful_widget(list, popup, &mut list_state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursor_starts_on_current_theme_and_wraps() {
+        let mut state = ThemePickerState::default();
+        let themes = vec!["dracula".to_string(), "gruvbox-dark".to_string()];
+        state.open(themes, "Gruvbox-Dark");
+        assert_eq!(state.selected_theme(), Some("gruvbox-dark"));
+
+        state.select_next();
+        assert_eq!(state.selected_theme(), Some("dracula"));
+        state.select_previous();
+        assert_eq!(state.selected_theme(), Some("gruvbox-dark"));
+
+        assert_eq!(state.close().as_deref(), Some("Gruvbox-Dark"));
+        assert!(!state.is_open());
+    }
+}
@@ -0,0 +1,68 @@
+//! Popup listing the keys that can finish a partly typed chord.
+
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+
+use crate::ui::chords::{Chord, ChordState, describe_keys};
+use crate::ui::text::display_width;
+
+/// Visual component that renders the which-key hints.
+#[derive(Debug, Default)]
+pub struct WhichKeyPopup;
+
+impl WhichKeyPopup {
+    /// Draw the continuations of the pending chord in the bottom-right corner of `area`.
+    pub fn render<A>(
+        &self,
+        frame: &mut Frame<'_>,
+        area: Rect,
+        state: &ChordState,
+        chords: &[Chord<A>],
+    ) {
+        if !state.is_pending() {
+            return;
+        }
+
+        let rows: Vec<(String, &str)> = state
+            .continuations(chords)
+            .map(|(rest, chord)| (describe_keys(rest), chord.label))
+            .collect();
+        let key_width = rows.iter().map(|(keys, _)| keys.len()).max().unwrap_or(0);
+        let lines: Vec<Line> = rows
+            .iter()
+            .map(|(keys, label)| {
+                Line::from(vec![
+                    Span::styled(
+                        format!("{keys:<key_width$}  "),
+                        Style::default()
+                            .fg(Color::Yellow)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    Span::raw(label.to_string()),
+                ])
+            })
+            .collect();
+
+        let title = format!("{} …", describe_keys(state.typed()));
+        let content_width = lines.iter().map(Line::width).max().unwrap_or(0);
+        let width = (content_width.max(display_width(&title)) as u16 + 4).min(area.width);
+        let height = (lines.len() as u16 + 2).min(area.height);
+        let popup = Rect {
+            x: area.x + area.width - width,
+            y: area.y + area.height - height,
+            width,
+            height,
+        };
+        frame.render_widget(Clear, popup);
+        let paragraph = Paragraph::new(lines).block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow)),
+        );
+        frame.render_widget(paragraph, popup);
+    }
+}
@@ -0,0 +1,208 @@
+//! Full-screen overlay summarizing the scanned workspace, opened with `:stats`.
+
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
+
+use crate::app::stats::WorkspaceStats;
+use crate::infra::documents::format_bytes;
+
+/// Visual component that renders the workspace statistics overlay.
+#[derive(Debug, Default)]
+pub struct WorkspaceStatsScreen;
+
+impl WorkspaceStatsScreen {
+    /// Draw `stats` over most of `area`.
+    pub fn render(&self, frame: &mut Frame<'_>, area: Rect, stats: &WorkspaceStats) {
+        let lines = stats_lines(stats);
+        let width = area.width.saturating_sub(4).min(100);
+        let height = (lines.len() as u16 + 2).min(area.height);
+        let popup = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+
+        frame.render_widget(Clear, popup);
+        let block = Block::default()
+            .title("Workspace statistics")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan));
+        let paragraph = Paragraph::new(lines)
+            .block(block)
+            .wrap(Wrap { trim: false });
+        frame.render_widget(paragraph, popup);
+    }
+}
+
+fn heading(text: &str) -> Line<'static> {
+    Line::styled(
+        text.to_string(),
+        Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD),
+    )
+}
+
+fn row(label: String, value: String) -> Line<'static> {
+    Line::from(vec![
+        Span::raw(format!("  {label:<24} ")),
+        Span::styled(value, Style::default().fg(Color::Gray)),
+    ])
+}
+
+/// Sections of the overlay: totals, languages, directories, exclusions, and git activity.
+fn stats_lines(stats: &WorkspaceStats) -> Vec<Line<'static>> {
+    let mut lines = vec![Line::from(format!(
+        "{} files · {}{}",
+        stats.files,
+        format_bytes(stats.bytes),
+        if stats.truncated {
+            " · scan truncated"
+        } else {
+            ""
+        }
+    ))];
+
+    lines.push(Line::default());
+    lines.push(heading("Languages"));
+    for count in &stats.languages {
+        lines.push(row(
+            count.language.clone(),
+            format!("{} files · {}", count.files, format_bytes(count.bytes)),
+        ));
+    }
+
+    lines.push(Line::default());
+    lines.push(heading("Largest directories"));
+    if stats.directories.is_empty() {
+        lines.push(row("none".to_string(), String::new()));
+    }
+    for directory in &stats.directories {
+        lines.push(row(
+            directory.path.clone(),
+            format!("~{} tokens · {} files", directory.tokens, directory.files),
+        ));
+    }
+
+    lines.push(Line::default());
+    lines.push(heading("Left out"));
+    lines.push(row("too large".to_string(), stats.too_large.to_string()));
+    lines.push(row("binary".to_string(), stats.binary.to_string()));
+    match &stats.ignored {
+        Some(ignored) => {
+            lines.push(row(
+                "ignore rules".to_string(),
+                ignored.ignore_rules.to_string(),
+            ));
+            lines.push(row("gitignore".to_string(), ignored.gitignore.to_string()));
+            lines.push(row("hidden".to_string(), ignored.hidden.to_string()));
+            lines.push(row(
+                "nested repositories".to_string(),
+                ignored.nested_repositories.to_string(),
+            ));
+        }
+        None if stats.truncated => lines.push(row(
+            "ignored".to_string(),
+            "unavailable: scan truncated".to_string(),
+        )),
+        None => lines.push(row("ignored".to_string(), "unavailable".to_string())),
+    }
+
+    lines.push(Line::default());
+    match &stats.activity {
+        Some(activity) => {
+            lines.push(heading(&format!(
+                "Git activity (last {} days)",
+                activity.days
+            )));
+            lines.push(row(
+                "commits".to_string(),
+                format!("{} by {} authors", activity.commits, activity.authors),
+            ));
+            if let Some(latest) = &activity.latest {
+                lines.push(row(
+                    "latest".to_string(),
+                    format!(
+                        "{} {} ({}, {})",
+                        latest.id,
+                        latest.subject,
+                        latest.author,
+                        latest.date.as_deref().unwrap_or("unknown date")
+                    ),
+                ));
+            }
+            for (path, commits) in &activity.active_paths {
+                lines.push(row(path.clone(), format!("{commits} commits")));
+            }
+        }
+        None => lines.push(heading("Git activity: not a git repository")),
+    }
+
+    lines.push(Line::default());
+    lines.push(Line::styled(
+        "Press any key to close",
+        Style::default()
+            .fg(Color::DarkGray)
+            .add_modifier(Modifier::ITALIC),
+    ));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::app::stats::{DirectoryTokens, LanguageCount};
+
+    fn text(lines: &[Line<'_>]) -> Vec<String> {
+        lines
+            .iter()
+            .map(|line| {
+                line.spans
+                    .iter()
+                    .map(|span| span.content.as_ref())
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lists_sections_and_marks_missing_data() {
+        let stats = WorkspaceStats {
+            files: 3,
+            bytes: 2_048,
+            languages: vec![LanguageCount {
+                language: "rs".to_string(),
+                files: 3,
+                bytes: 2_048,
+            }],
+            directories: vec![DirectoryTokens {
+                path: "src/".to_string(),
+                files: 3,
+                tokens: 512,
+            }],
+            truncated: true,
+            ..WorkspaceStats::default()
+        };
+
+        let lines = text(&stats_lines(&stats));
+        assert!(lines[0].starts_with("3 files · ") && lines[0].ends_with(" · scan truncated"));
+        assert!(
+            lines
+                .iter()
+                .any(|line| line.starts_with("  src/") && line.ends_with("~512 tokens · 3 files"))
+        );
+        assert!(
+            lines
+                .iter()
+                .any(|line| line.ends_with("unavailable: scan truncated"))
+        );
+        assert!(lines.contains(&"Git activity: not a git repository".to_string()));
+    }
+}
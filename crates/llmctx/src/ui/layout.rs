@@ -0,0 +1,137 @@
+//! Screen layout for the TUI, adapting pane visibility to the terminal size.
+
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+
+/// Smallest terminal the TUI will draw its panes into.
+pub const MIN_WIDTH: u16 = 60;
+pub const MIN_HEIGHT: u16 = 12;
+
+const TREE_WIDTH: u16 = 32;
+const PREVIEW_MIN_WIDTH: u16 = 50;
+const SUMMARY_WIDTH: u16 = 36;
+const HINTS_HEIGHT: u16 = 5;
+
+/// Areas assigned to each pane for one frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PaneLayout {
+    pub tree: Rect,
+    /// One row above the preview showing the focused file's path.
+    pub breadcrumb: Rect,
+    pub preview: Rect,
+    /// `None` when the terminal is too narrow to fit the summary column.
+    pub summary: Option<Rect>,
+    pub hints: Option<Rect>,
+    pub status: Rect,
+}
+
+impl PaneLayout {
+    /// Split `size` into panes, or return `None` when it is below the minimum size.
+    pub fn compute(size: Rect) -> Option<Self> {
+        if size.width < MIN_WIDTH || size.height < MIN_HEIGHT {
+            return None;
+        }
+
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(3), Constraint::Length(1)])
+            .split(size);
+
+        if size.width < TREE_WIDTH + PREVIEW_MIN_WIDTH + SUMMARY_WIDTH {
+            let tree_width = TREE_WIDTH.min(size.width / 3);
+            let columns = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Length(tree_width), Constraint::Min(1)])
+                .split(rows[0]);
+            let (breadcrumb, preview) = split_breadcrumb(columns[1]);
+            return Some(Self {
+                tree: columns[0],
+                breadcrumb,
+                preview,
+                summary: None,
+                hints: None,
+                status: rows[1],
+            });
+        }
+
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Length(TREE_WIDTH),
+                Constraint::Min(PREVIEW_MIN_WIDTH),
+                Constraint::Length(SUMMARY_WIDTH),
+            ])
+            .split(rows[0]);
+        let right = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(10), Constraint::Length(HINTS_HEIGHT)])
+            .split(columns[2]);
+
+        let (breadcrumb, preview) = split_breadcrumb(columns[1]);
+        Some(Self {
+            tree: columns[0],
+            breadcrumb,
+            preview,
+            summary: Some(right[0]),
+            hints: Some(right[1]),
+            status: rows[1],
+        })
+    }
+
+    /// Layout for focus mode: the preview fills everything above the status line.
+    pub fn focused(size: Rect) -> Option<Self> {
+        let mut layout = Self::compute(size)?;
+        let preview = Rect {
+            height: layout.status.y.saturating_sub(size.y),
+            ..size
+        };
+        layout.tree = Rect::new(size.x, size.y, 0, preview.height);
+        layout.breadcrumb = Rect::new(size.x, size.y, preview.width, 0);
+        layout.preview = preview;
+        layout.summary = None;
+        layout.hints = None;
+        Some(layout)
+    }
+}
+
+fn split_breadcrumb(column: Rect) -> (Rect, Rect) {
+    let parts = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(1)])
+        .split(column);
+    (parts[0], parts[1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wide_terminals_show_every_pane() {
+        let layout = PaneLayout::compute(Rect::new(0, 0, 160, 40)).expect("fits");
+        assert_eq!(layout.tree.width, TREE_WIDTH);
+        assert_eq!(layout.summary.map(|area| area.width), Some(SUMMARY_WIDTH));
+        assert_eq!(layout.status.y, 39);
+        assert_eq!(layout.breadcrumb.height, 1);
+        assert_eq!(layout.breadcrumb.y + 1, layout.preview.y);
+    }
+
+    #[test]
+    fn focus_mode_gives_the_preview_everything_but_the_status_line() {
+        let layout = PaneLayout::focused(Rect::new(0, 0, 160, 40)).expect("fits");
+        assert_eq!(layout.preview, Rect::new(0, 0, 160, 39));
+        assert_eq!(layout.tree.width, 0);
+        assert_eq!(layout.breadcrumb.height, 0);
+        assert!(layout.summary.is_none() && layout.hints.is_none());
+        assert!(PaneLayout::focused(Rect::new(0, 0, 59, 30)).is_none());
+    }
+
+    #[test]
+    fn narrow_terminals_hide_summary_then_refuse() {
+        let layout = PaneLayout::compute(Rect::new(0, 0, 90, 30)).expect("fits");
+        assert!(layout.summary.is_none());
+        assert_eq!(layout.tree.width + layout.preview.width, 90);
+
+        assert!(PaneLayout::compute(Rect::new(0, 0, 59, 30)).is_none());
+        assert!(PaneLayout::compute(Rect::new(0, 0, 120, 11)).is_none());
+    }
+}
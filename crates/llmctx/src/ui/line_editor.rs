@@ -0,0 +1,311 @@
+//! Single-line text input shared by the tree filter and the command palette.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::ui::text::display_width;
+
+/// A line of text with a cursor that moves and deletes whole grapheme clusters, so accented
+/// letters, emoji sequences, and text committed by an input method edit as one character.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LineEditor {
+    text: String,
+    /// Byte offset of the cursor, always on a grapheme boundary.
+    cursor: usize,
+}
+
+impl LineEditor {
+    /// The text being edited.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Byte offset of the cursor within [`Self::text`].
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Replace the text, leaving the cursor at its end.
+    pub fn set<S: Into<String>>(&mut self, text: S) {
+        self.text = text.into();
+        self.cursor = self.text.len();
+    }
+
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.cursor = 0;
+    }
+
+    /// Take the text, leaving the line empty.
+    pub fn take(&mut self) -> String {
+        self.cursor = 0;
+        std::mem::take(&mut self.text)
+    }
+
+    /// Insert `text` at the cursor and move past it.
+    ///
+    /// Each run of line breaks becomes one space, tabs become spaces, and other control
+    /// characters are dropped, so pasted text stays on one line.
+    pub fn insert(&mut self, text: &str) {
+        let mut cleaned = String::with_capacity(text.len());
+        let mut after_break = false;
+        for ch in text.chars() {
+            match ch {
+                '\r' | '\n' => {
+                    if !after_break {
+                        cleaned.push(' ');
+                    }
+                    after_break = true;
+                    continue;
+                }
+                '\t' => cleaned.push(' '),
+                ch if ch.is_control() => {}
+                ch => cleaned.push(ch),
+            }
+            after_break = false;
+        }
+        self.text.insert_str(self.cursor, &cleaned);
+        self.cursor += cleaned.len();
+        // The inserted text can fuse with the character after it, e.g. through a zero-width
+        // joiner; keep the cursor out of the middle of the resulting cluster.
+        self.cursor = self
+            .text
+            .grapheme_indices(true)
+            .map(|(index, _)| index)
+            .find(|index| *index >= self.cursor)
+            .unwrap_or(self.text.len());
+    }
+
+    pub fn move_left(&mut self) {
+        self.cursor = self.previous_boundary();
+    }
+
+    pub fn move_right(&mut self) {
+        self.cursor = self.next_boundary();
+    }
+
+    /// Move to the start of the word before the cursor.
+    pub fn move_word_left(&mut self) {
+        self.cursor = self.previous_word();
+    }
+
+    /// Move past the end of the word after the cursor.
+    pub fn move_word_right(&mut self) {
+        self.cursor = self.next_word();
+    }
+
+    pub fn move_home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn move_end(&mut self) {
+        self.cursor = self.text.len();
+    }
+
+    /// Delete the character before the cursor.
+    pub fn delete_before(&mut self) {
+        let start = self.previous_boundary();
+        self.delete(start, self.cursor);
+    }
+
+    /// Delete the character under the cursor.
+    pub fn delete_after(&mut self) {
+        let end = self.next_boundary();
+        self.delete(self.cursor, end);
+    }
+
+    /// Delete back to the start of the word before the cursor.
+    pub fn delete_word_before(&mut self) {
+        let start = self.previous_word();
+        self.delete(start, self.cursor);
+    }
+
+    pub fn delete_to_start(&mut self) {
+        self.delete(0, self.cursor);
+    }
+
+    pub fn delete_to_end(&mut self) {
+        self.delete(self.cursor, self.text.len());
+    }
+
+    /// Apply an editing key, returning `false` for keys that are not editing keys.
+    ///
+    /// `←`/`→` move by character and with `ctrl` or `alt` by word, as do `alt+b`/`alt+f`;
+    /// `home`/`end` jump to either end; `backspace` and `delete` remove a character, and
+    /// `ctrl+w` or `alt+backspace` a word; `ctrl+u` and `ctrl+k` delete to the start or end.
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        match key.code {
+            KeyCode::Left if ctrl || alt => self.move_word_left(),
+            KeyCode::Right if ctrl || alt => self.move_word_right(),
+            KeyCode::Left => self.move_left(),
+            KeyCode::Right => self.move_right(),
+            KeyCode::Home => self.move_home(),
+            KeyCode::End => self.move_end(),
+            KeyCode::Backspace if ctrl || alt => self.delete_word_before(),
+            KeyCode::Backspace => self.delete_before(),
+            KeyCode::Delete => self.delete_after(),
+            KeyCode::Char('w') if ctrl => self.delete_word_before(),
+            KeyCode::Char('u') if ctrl => self.delete_to_start(),
+            KeyCode::Char('k') if ctrl => self.delete_to_end(),
+            KeyCode::Char('b') if alt => self.move_word_left(),
+            KeyCode::Char('f') if alt => self.move_word_right(),
+            KeyCode::Char(ch) if !ctrl && !alt => self.insert(ch.encode_utf8(&mut [0; 4])),
+            _ => return false,
+        }
+        true
+    }
+
+    /// The tail of the text to draw in `width` columns so the cursor stays in view, and the
+    /// column the cursor sits at within it.
+    pub fn view(&self, width: usize) -> (&str, usize) {
+        let mut start = 0;
+        let mut column = display_width(&self.text[..self.cursor]);
+        // The cursor needs a column of its own past the last character before it.
+        for grapheme in self.text[..self.cursor].graphemes(true) {
+            if column < width {
+                break;
+            }
+            start += grapheme.len();
+            column -= display_width(grapheme);
+        }
+        (&self.text[start..], column)
+    }
+
+    fn delete(&mut self, start: usize, end: usize) {
+        self.text.replace_range(start..end, "");
+        self.cursor = start;
+    }
+
+    fn previous_boundary(&self) -> usize {
+        self.text[..self.cursor]
+            .grapheme_indices(true)
+            .next_back()
+            .map_or(0, |(index, _)| index)
+    }
+
+    fn next_boundary(&self) -> usize {
+        self.text[self.cursor..]
+            .graphemes(true)
+            .next()
+            .map_or(self.cursor, |grapheme| self.cursor + grapheme.len())
+    }
+
+    /// Start of the word before the cursor, skipping separators next to the cursor first.
+    fn previous_word(&self) -> usize {
+        let mut start = self.cursor;
+        let mut in_word = false;
+        for (index, grapheme) in self.text[..self.cursor].grapheme_indices(true).rev() {
+            let word = is_word(grapheme);
+            if in_word && !word {
+                break;
+            }
+            in_word |= word;
+            start = index;
+        }
+        start
+    }
+
+    /// End of the word after the cursor, skipping separators next to the cursor first.
+    fn next_word(&self) -> usize {
+        let mut in_word = false;
+        for (index, grapheme) in self.text[self.cursor..].grapheme_indices(true) {
+            let word = is_word(grapheme);
+            if in_word && !word {
+                return self.cursor + index;
+            }
+            in_word |= word;
+        }
+        self.text.len()
+    }
+}
+
+/// Words are runs of letters, digits, and underscores, so `/`, `.`, and spaces separate them.
+fn is_word(grapheme: &str) -> bool {
+    grapheme
+        .chars()
+        .next()
+        .is_some_and(|ch| ch.is_alphanumeric() || ch == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    fn typed(text: &str) -> LineEditor {
+        let mut editor = LineEditor::default();
+        for ch in text.chars() {
+            assert!(editor.handle_key(KeyEvent::from(KeyCode::Char(ch))));
+        }
+        editor
+    }
+
+    #[test]
+    fn cursor_edits_whole_graphemes_and_words() {
+        let mut editor = typed("select src/日本/cafe\u{301}.rs");
+        editor.move_left();
+        editor.move_left();
+        editor.move_left();
+        editor.delete_before();
+        assert_eq!(editor.text(), "select src/日本/caf.rs");
+
+        editor.handle_key(key(KeyCode::Char('w'), KeyModifiers::CONTROL));
+        assert_eq!(editor.text(), "select src/日本/.rs");
+        editor.handle_key(key(KeyCode::Left, KeyModifiers::CONTROL));
+        assert_eq!(&editor.text()[editor.cursor()..], "日本/.rs");
+        editor.handle_key(key(KeyCode::Delete, KeyModifiers::NONE));
+        assert_eq!(editor.text(), "select src/本/.rs");
+
+        editor.insert("👩‍💻\r\n\r\nx\u{7}");
+        assert_eq!(editor.text(), "select src/👩‍💻 x本/.rs");
+        editor.move_left();
+        editor.move_left();
+        editor.delete_before();
+        assert_eq!(editor.text(), "select src/ x本/.rs");
+
+        editor.handle_key(key(KeyCode::Char('k'), KeyModifiers::CONTROL));
+        editor.handle_key(key(KeyCode::Home, KeyModifiers::NONE));
+        editor.handle_key(key(KeyCode::Char('f'), KeyModifiers::ALT));
+        editor.handle_key(key(KeyCode::Char('u'), KeyModifiers::CONTROL));
+        assert_eq!(editor.text(), " src/");
+        assert!(!editor.handle_key(key(KeyCode::Char('a'), KeyModifiers::CONTROL)));
+        assert!(!editor.handle_key(key(KeyCode::Enter, KeyModifiers::NONE)));
+    }
+
+    #[test]
+    fn insertions_keep_the_cursor_between_graphemes() {
+        let mut editor = typed("ab");
+        editor.move_left();
+        editor.insert("\u{301}");
+        assert_eq!(editor.text(), "a\u{301}b");
+        editor.move_home();
+        editor.move_right();
+        assert_eq!(&editor.text()[editor.cursor()..], "b");
+
+        // A zero-width joiner fuses the inserted emoji with the one after the cursor.
+        let mut editor = typed("💻");
+        editor.move_home();
+        editor.insert("👩\u{200d}");
+        assert_eq!(editor.cursor(), editor.text().len());
+        editor.delete_before();
+        assert!(editor.is_empty());
+    }
+
+    #[test]
+    fn view_scrolls_to_keep_the_cursor_visible() {
+        let mut editor = typed("日本語のファイル");
+        assert_eq!(editor.view(20), ("日本語のファイル", 16));
+        assert_eq!(editor.view(10), ("ファイル", 8));
+        editor.move_home();
+        assert_eq!(editor.view(4), ("日本語のファイル", 0));
+    }
+}
@@ -0,0 +1,270 @@
+//! Vim-style `.` repeat and `q<reg>` / `@<reg>` keyboard macros.
+//!
+//! Both work on raw key events: a macro is the keys typed while it was recorded, and the last
+//! change is the keys of the last action that changed the selection, from its first key until
+//! the UI was back at rest (so `n` plus the typed note plus `Enter` repeat as one change).
+
+use std::collections::HashMap;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// What the app should do with a key after [`Macros::intercept`] looked at it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MacroKey {
+    /// Not a macro key; handle it as usual.
+    Pass,
+    /// Swallowed while waiting for a register name.
+    Pending,
+    /// `q` twice: quit.
+    Quit,
+    /// A prefix was followed by a key that names no register.
+    Cancelled,
+    /// Recording into the register started.
+    Recording(char),
+    /// Recording into the register stopped after this many keys.
+    Recorded(char, usize),
+    /// Replay these keys.
+    Replay(Vec<KeyEvent>),
+    /// The register, or the last change for `None`, holds nothing yet.
+    Empty(Option<char>),
+    /// `@` while a macro plays; macros do not run other macros.
+    Nested,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Prefix {
+    Record,
+    Play,
+}
+
+/// Registers, the recording in progress, and the last change.
+#[derive(Debug, Default)]
+pub struct Macros {
+    registers: HashMap<char, Vec<KeyEvent>>,
+    recording: Option<(char, Vec<KeyEvent>)>,
+    prefix: Option<Prefix>,
+    /// Register replayed by `@@`.
+    last_played: Option<char>,
+    last_change: Vec<KeyEvent>,
+    /// Keys of the action in progress and the selection revision before its first key.
+    action: Vec<KeyEvent>,
+    action_start: u64,
+    /// Nested replays in progress; `.` may run inside a macro.
+    replaying: usize,
+    /// Whether one of the replays in progress is a macro rather than `.`.
+    playing_macro: bool,
+}
+
+impl Macros {
+    /// Handle `q`, `@`, and `.` and the register names that follow them.
+    ///
+    /// Only called while the UI is at rest, so these keys still reach text inputs and overlays.
+    pub fn intercept(&mut self, key: KeyEvent) -> MacroKey {
+        let ch = match key.code {
+            KeyCode::Char(ch)
+                if !key
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+            {
+                Some(ch)
+            }
+            _ => None,
+        };
+
+        if let Some(prefix) = self.prefix.take() {
+            return match (prefix, ch) {
+                (Prefix::Record, Some('q')) => MacroKey::Quit,
+                (Prefix::Record, Some(register)) if register.is_ascii_lowercase() => {
+                    self.recording = Some((register, Vec::new()));
+                    MacroKey::Recording(register)
+                }
+                (Prefix::Play, Some(register))
+                    if register.is_ascii_lowercase() || register == '@' =>
+                {
+                    self.play(register)
+                }
+                _ => MacroKey::Cancelled,
+            };
+        }
+
+        match ch {
+            Some('q') => match self.recording.take() {
+                Some((register, keys)) => {
+                    let len = keys.len();
+                    self.registers.insert(register, keys);
+                    MacroKey::Recorded(register, len)
+                }
+                None => {
+                    self.prefix = Some(Prefix::Record);
+                    MacroKey::Pending
+                }
+            },
+            Some('@') => {
+                self.prefix = Some(Prefix::Play);
+                MacroKey::Pending
+            }
+            Some('.') => {
+                self.record(key);
+                if self.last_change.is_empty() {
+                    MacroKey::Empty(None)
+                } else {
+                    MacroKey::Replay(self.last_change.clone())
+                }
+            }
+            _ => MacroKey::Pass,
+        }
+    }
+
+    fn play(&mut self, register: char) -> MacroKey {
+        if self.playing_macro {
+            return MacroKey::Nested;
+        }
+        let register = match register {
+            '@' => match self.last_played {
+                Some(register) => register,
+                None => return MacroKey::Empty(None),
+            },
+            register => register,
+        };
+        let Some(keys) = self
+            .registers
+            .get(&register)
+            .filter(|keys| !keys.is_empty())
+        else {
+            return MacroKey::Empty(Some(register));
+        };
+        let keys = keys.clone();
+        self.last_played = Some(register);
+        self.record(KeyEvent::from(KeyCode::Char('@')));
+        self.record(KeyEvent::from(KeyCode::Char(register)));
+        self.playing_macro = true;
+        MacroKey::Replay(keys)
+    }
+
+    /// Register being recorded into, if any.
+    pub fn recording(&self) -> Option<char> {
+        self.recording.as_ref().map(|(register, _)| *register)
+    }
+
+    /// Note a key that is about to be handled as usual.
+    ///
+    /// `revision` is the selection's revision before the key, which only matters for the first
+    /// key of an action.
+    pub fn begin_key(&mut self, key: KeyEvent, revision: u64) {
+        if self.action.is_empty() {
+            self.action_start = revision;
+        }
+        self.action.push(key);
+        self.record(key);
+    }
+
+    /// Close the action once the UI is back at rest; it becomes the last change if the
+    /// selection's revision moved.
+    pub fn end_action(&mut self, revision: u64) {
+        let keys = std::mem::take(&mut self.action);
+        if !keys.is_empty() && revision != self.action_start {
+            self.last_change = keys;
+        }
+    }
+
+    /// Mark the start of replaying keys returned in [`MacroKey::Replay`].
+    pub fn replay_started(&mut self) {
+        self.replaying += 1;
+    }
+
+    /// Mark the end of the innermost replay.
+    pub fn replay_finished(&mut self) {
+        self.replaying = self.replaying.saturating_sub(1);
+        if self.replaying == 0 {
+            self.playing_macro = false;
+        }
+    }
+
+    /// Append to the recording, unless the key comes from a replay, which records as `@` or `.`.
+    fn record(&mut self, key: KeyEvent) {
+        if self.replaying == 0
+            && let Some((_, keys)) = &mut self.recording
+        {
+            keys.push(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(ch: char) -> KeyEvent {
+        KeyEvent::from(KeyCode::Char(ch))
+    }
+
+    /// Feed `keys` the way the app does, treating `changes` as the keys that edit the selection.
+    fn type_keys(macros: &mut Macros, keys: &str, revision: &mut u64, changes: &str) {
+        for ch in keys.chars() {
+            match macros.intercept(key(ch)) {
+                MacroKey::Pass => {
+                    macros.begin_key(key(ch), *revision);
+                    if changes.contains(ch) {
+                        *revision += 1;
+                    }
+                    macros.end_action(*revision);
+                }
+                MacroKey::Replay(replayed) => {
+                    macros.replay_started();
+                    let replayed: String = replayed
+                        .iter()
+                        .filter_map(|event| match event.code {
+                            KeyCode::Char(ch) => Some(ch),
+                            _ => None,
+                        })
+                        .collect();
+                    type_keys(macros, &replayed, revision, changes);
+                    macros.replay_finished();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn dot_repeats_the_last_change_and_macros_replay_their_keys() {
+        let mut macros = Macros::default();
+        let mut revision = 0;
+        assert_eq!(macros.intercept(key('.')), MacroKey::Empty(None));
+
+        type_keys(&mut macros, "j jx", &mut revision, " x");
+        assert_eq!(macros.last_change, vec![key('x')]);
+        type_keys(&mut macros, ".", &mut revision, " x");
+        assert_eq!(revision, 3);
+
+        type_keys(&mut macros, "qaj .q", &mut revision, " x");
+        assert_eq!(macros.recording(), None);
+        assert_eq!(macros.registers[&'a'], vec![key('j'), key(' '), key('.')]);
+        assert_eq!(revision, 5);
+
+        type_keys(&mut macros, "@a@@", &mut revision, " x");
+        assert_eq!(revision, 9);
+        assert_eq!(macros.intercept(key('@')), MacroKey::Pending);
+        assert_eq!(macros.intercept(key('b')), MacroKey::Empty(Some('b')));
+        assert_eq!(macros.intercept(key('q')), MacroKey::Pending);
+        assert_eq!(macros.intercept(key('q')), MacroKey::Quit);
+    }
+
+    #[test]
+    fn macros_do_not_run_other_macros() {
+        let mut macros = Macros::default();
+        macros.intercept(key('q'));
+        macros.intercept(key('a'));
+        macros.intercept(key('@'));
+        assert_eq!(macros.intercept(key('a')), MacroKey::Empty(Some('a')));
+        macros.intercept(key('q'));
+
+        macros.registers.insert('b', vec![key('@'), key('a')]);
+        macros.intercept(key('@'));
+        assert!(matches!(macros.intercept(key('b')), MacroKey::Replay(_)));
+        macros.replay_started();
+        macros.intercept(key('@'));
+        assert_eq!(macros.intercept(key('a')), MacroKey::Nested);
+        macros.replay_finished();
+    }
+}
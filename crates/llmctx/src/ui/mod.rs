@@ -1,4 +1,12 @@
 //! ratatui-based user interface components and application loop.
 
 pub mod app;
+pub mod chords;
+pub mod clock;
 pub mod components;
+pub mod layout;
+pub mod line_editor;
+pub mod macros;
+pub mod prefetch;
+pub mod terminal;
+pub mod text;
@@ -0,0 +1,141 @@
+//! A single background thread that loads work ahead of the user, such as the next preview chunk.
+//!
+//! At most one job runs and one waits. A new request replaces the waiting job, so scrolling
+//! quickly keeps only the latest request instead of starting a thread for each one.
+
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+
+type Job<T> = Box<dyn FnOnce() -> Option<T> + Send>;
+
+struct Slot<T> {
+    job: Option<Job<T>>,
+    closed: bool,
+}
+
+/// Runs prefetch jobs on one lazily started thread; results are collected with
+/// [`PrefetchWorker::try_recv`].
+pub struct PrefetchWorker<T> {
+    shared: Arc<(Mutex<Slot<T>>, Condvar)>,
+    results_tx: Sender<T>,
+    results_rx: Receiver<T>,
+    started: bool,
+}
+
+impl<T: Send + 'static> PrefetchWorker<T> {
+    pub fn new() -> Self {
+        let (results_tx, results_rx) = mpsc::channel();
+        Self {
+            shared: Arc::new((
+                Mutex::new(Slot {
+                    job: None,
+                    closed: false,
+                }),
+                Condvar::new(),
+            )),
+            results_tx,
+            results_rx,
+            started: false,
+        }
+    }
+
+    /// Queue `job`, dropping a queued job that has not started yet. Jobs returning `None`
+    /// (e.g. after a failure) send nothing.
+    pub fn request(&mut self, job: impl FnOnce() -> Option<T> + Send + 'static) {
+        if !self.started {
+            self.started = true;
+            let shared = Arc::clone(&self.shared);
+            let results = self.results_tx.clone();
+            thread::spawn(move || run(&shared, &results));
+        }
+        let (slot, ready) = &*self.shared;
+        slot.lock().unwrap_or_else(|err| err.into_inner()).job = Some(Box::new(job));
+        ready.notify_one();
+    }
+
+    /// A finished result, if one is waiting.
+    pub fn try_recv(&self) -> Option<T> {
+        self.results_rx.try_recv().ok()
+    }
+}
+
+impl<T: Send + 'static> Default for PrefetchWorker<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for PrefetchWorker<T> {
+    fn drop(&mut self) {
+        let (slot, ready) = &*self.shared;
+        slot.lock().unwrap_or_else(|err| err.into_inner()).closed = true;
+        ready.notify_one();
+    }
+}
+
+fn run<T>(shared: &(Mutex<Slot<T>>, Condvar), results: &Sender<T>) {
+    let (slot, ready) = shared;
+    loop {
+        let job = {
+            let mut slot = slot.lock().unwrap_or_else(|err| err.into_inner());
+            loop {
+                if slot.closed {
+                    return;
+                }
+                if let Some(job) = slot.job.take() {
+                    break job;
+                }
+                slot = ready.wait(slot).unwrap_or_else(|err| err.into_inner());
+            }
+        };
+        if let Some(result) = job()
+            && results.send(result).is_err()
+        {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::{Duration, Instant};
+
+    fn recv(worker: &PrefetchWorker<(usize, thread::ThreadId)>) -> (usize, thread::ThreadId) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            if let Some(result) = worker.try_recv() {
+                return result;
+            }
+            assert!(Instant::now() < deadline, "prefetch never finished");
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn requests_made_while_busy_collapse_to_the_latest() {
+        let mut worker = PrefetchWorker::new();
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        let (started_tx, started_rx) = mpsc::channel::<()>();
+        worker.request(move || {
+            started_tx.send(()).unwrap();
+            release_rx.recv().unwrap();
+            Some((0, thread::current().id()))
+        });
+        started_rx.recv().unwrap();
+        for request in 1..=50 {
+            worker.request(move || Some((request, thread::current().id())));
+        }
+        release_tx.send(()).unwrap();
+
+        let (first, first_thread) = recv(&worker);
+        let (second, second_thread) = recv(&worker);
+        assert_eq!((first, second), (0, 50));
+        assert_eq!(first_thread, second_thread);
+        assert_ne!(first_thread, thread::current().id());
+        thread::sleep(Duration::from_millis(20));
+        assert!(worker.try_recv().is_none());
+    }
+}
@@ -0,0 +1,109 @@
+//! Terminal setup that is undone on every exit path, panics included.
+
+use std::io::{self, Stdout};
+use std::ops::{Deref, DerefMut};
+use std::panic;
+use std::sync::Once;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::{Context, Result};
+use crossterm::cursor::Show;
+use crossterm::event::{DisableBracketedPaste, EnableBracketedPaste};
+use crossterm::execute;
+use crossterm::terminal::{
+    EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
+};
+use ratatui::Terminal;
+use ratatui::backend::CrosstermBackend;
+
+/// Whether raw mode and the alternate screen are currently in effect.
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+static PANIC_HOOK: Once = Once::new();
+
+/// Raw-mode, alternate-screen terminal that restores the shell's terminal when dropped.
+///
+/// A panic hook restores it as well, before the panic message is printed, so the message lands
+/// on the normal screen instead of being wiped with the alternate one.
+pub struct TerminalGuard {
+    terminal: Terminal<CrosstermBackend<Stdout>>,
+}
+
+impl TerminalGuard {
+    /// Enter raw mode and the alternate screen.
+    pub fn enter() -> Result<Self> {
+        install_panic_hook();
+        enable_raw_mode().context("failed to enable raw mode")?;
+        ACTIVE.store(true, Ordering::SeqCst);
+        // From here on, an early return drops the guard and undoes what was done so far.
+        let mut guard = Self {
+            terminal: Terminal::new(CrosstermBackend::new(io::stdout()))
+                .context("failed to initialize terminal")?,
+        };
+        execute!(guard.terminal.backend_mut(), EnterAlternateScreen)
+            .context("failed to enter alternate screen")?;
+        // Pastes then arrive as one event instead of as keys, which could trigger bindings.
+        let _ = execute!(guard.terminal.backend_mut(), EnableBracketedPaste);
+        guard.terminal.hide_cursor().ok();
+        Ok(guard)
+    }
+
+    /// Hand the terminal back to the shell, e.g. while another program runs in it.
+    pub fn suspend(&mut self) {
+        restore();
+    }
+
+    /// Take the terminal over again after [`Self::suspend`]; the next draw must clear it.
+    pub fn resume(&mut self) {
+        enable_raw_mode().ok();
+        ACTIVE.store(true, Ordering::SeqCst);
+        let _ = execute!(
+            self.terminal.backend_mut(),
+            EnterAlternateScreen,
+            EnableBracketedPaste
+        );
+        self.terminal.hide_cursor().ok();
+    }
+}
+
+impl Deref for TerminalGuard {
+    type Target = Terminal<CrosstermBackend<Stdout>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.terminal
+    }
+}
+
+impl DerefMut for TerminalGuard {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.terminal
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+/// Leave the alternate screen and raw mode if they are active; safe to call repeatedly.
+fn restore() {
+    if ACTIVE.swap(false, Ordering::SeqCst) {
+        let _ = disable_raw_mode();
+        let _ = execute!(
+            io::stdout(),
+            DisableBracketedPaste,
+            LeaveAlternateScreen,
+            Show
+        );
+    }
+}
+
+fn install_panic_hook() {
+    PANIC_HOOK.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            restore();
+            previous(info);
+        }));
+    });
+}
@@ -0,0 +1,84 @@
+//! Terminal column widths for text holding wide (CJK, emoji) or combining characters.
+
+use std::borrow::Cow;
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Marks where truncated text was cut; one column wide.
+const ELLIPSIS: &str = "…";
+
+/// Columns `text` occupies in a terminal.
+pub fn display_width(text: &str) -> usize {
+    text.width()
+}
+
+/// Grapheme clusters of `text`, each with the columns it occupies.
+pub fn graphemes(text: &str) -> impl DoubleEndedIterator<Item = (&str, usize)> {
+    text.graphemes(true)
+        .map(|grapheme| (grapheme, grapheme.width()))
+}
+
+/// `text` cut to at most `width` columns, ending in `…` when anything was dropped.
+///
+/// Grapheme clusters are kept whole, so the result may fall a column short of `width`.
+pub fn truncate_end(text: &str, width: usize) -> Cow<'_, str> {
+    if display_width(text) <= width {
+        return Cow::Borrowed(text);
+    }
+    if width == 0 {
+        return Cow::Borrowed("");
+    }
+    let kept = fitting(graphemes(text), width - 1);
+    Cow::Owned(kept.into_iter().chain([ELLIPSIS]).collect())
+}
+
+/// Like [`truncate_end`], but drops the start of `text` so the file name of a path stays visible.
+pub fn truncate_start(text: &str, width: usize) -> Cow<'_, str> {
+    if display_width(text) <= width {
+        return Cow::Borrowed(text);
+    }
+    if width == 0 {
+        return Cow::Borrowed("");
+    }
+    let kept = fitting(graphemes(text).rev(), width - 1);
+    Cow::Owned(
+        [ELLIPSIS]
+            .into_iter()
+            .chain(kept.into_iter().rev())
+            .collect(),
+    )
+}
+
+/// Leading graphemes of `graphemes` that fit in `width` columns.
+fn fitting<'a>(graphemes: impl Iterator<Item = (&'a str, usize)>, width: usize) -> Vec<&'a str> {
+    let mut used = 0;
+    graphemes
+        .take_while(|(_, columns)| {
+            used += columns;
+            used <= width
+        })
+        .map(|(grapheme, _)| grapheme)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncation_counts_columns_and_keeps_graphemes_whole() {
+        assert_eq!(display_width("日本語.rs"), 9);
+        assert_eq!(display_width("cafe\u{301}"), 4);
+
+        assert_eq!(truncate_end("main.rs", 7), "main.rs");
+        assert_eq!(truncate_end("日本語.rs", 6), "日本…");
+        assert_eq!(truncate_end("日本語.rs", 4), "日…");
+        assert_eq!(truncate_end("cafe\u{301}s.md", 5), "cafe\u{301}…");
+        assert_eq!(truncate_end("abc", 0), "");
+
+        assert_eq!(truncate_start("src/日本語.rs", 8), "…本語.rs");
+        assert_eq!(truncate_start("src/日本語.rs", 7), "…語.rs");
+        assert_eq!(truncate_start("abc", 0), "");
+    }
+}
@@ -0,0 +1,69 @@
+//! `llmctx export --check` run as a subprocess, the way CI gates use it.
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+fn llmctx(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_llmctx"))
+        .args(args)
+        .current_dir(dir)
+        // Keep user-level configuration out of the run.
+        .env("HOME", dir)
+        .env("XDG_CONFIG_HOME", dir.join(".config"))
+        .output()
+        .expect("failed to run llmctx")
+}
+
+#[test]
+fn check_passes_a_clean_bundle_without_exporting() {
+    let temp = tempfile::tempdir().unwrap();
+    fs::write(temp.path().join("main.rs"), "fn main() {\n    run();\n}\n").unwrap();
+
+    let output = llmctx(
+        temp.path(),
+        &["export", "--select", "main.rs:1-2", "--check"],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{output:?}");
+    assert!(stdout.starts_with("ok: 1 selection(s), "), "{stdout}");
+    assert!(stdout.trim_end().ends_with("/120000 tokens"), "{stdout}");
+    let written: Vec<_> = fs::read_dir(temp.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert_eq!(written, ["main.rs"]);
+}
+
+#[test]
+fn check_lists_every_problem_and_fails() {
+    let temp = tempfile::tempdir().unwrap();
+    fs::write(temp.path().join("main.rs"), "fn main() {\n    run();\n}\n").unwrap();
+
+    let output = llmctx(
+        temp.path(),
+        &[
+            "export",
+            "--select",
+            "main.rs:2-9",
+            "--select",
+            "gone.rs",
+            "--max-tokens",
+            "1",
+            "--check",
+        ],
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{stderr}");
+    assert!(output.stdout.is_empty());
+    assert!(
+        stderr.contains("bundle check found 3 problem(s)"),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains("main.rs:2-9: range ends past the last line (3)"),
+        "{stderr}"
+    );
+    assert!(stderr.contains("gone.rs: file was deleted"), "{stderr}");
+    assert!(stderr.contains("over the 1-token budget"), "{stderr}");
+}
@@ -0,0 +1,14 @@
+//! Configuration files, parsed as a layer over the defaults and merged again.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use llmctx::infra::config::Config;
+
+fuzz_target!(|data: &str| {
+    let Ok(config) = Config::from_toml_layer(data) else {
+        return;
+    };
+    // Stacked as both the user and the workspace layer, as `load_for` would.
+    config.clone().merge_with(config).fingerprint();
+});
@@ -0,0 +1,58 @@
+//! Command palette input, run headlessly against an app scanned from a small workspace.
+
+#![no_main]
+
+use std::cell::RefCell;
+use std::fs;
+
+use libfuzzer_sys::fuzz_target;
+use llmctx::infra::clipboard::MemoryClipboard;
+use llmctx::infra::config::Config;
+use llmctx::ui::app::UiApp;
+use llmctx::ui::clock::ManualClock;
+use tempfile::TempDir;
+
+/// Commands that write files, which a fuzzer must not run; copies land in a memory clipboard.
+const SKIPPED: &[&str] = &["export", "save", "delete-session", "recipe"];
+
+thread_local! {
+    static APP: RefCell<Option<(TempDir, UiApp)>> = const { RefCell::new(None) };
+}
+
+fn workspace() -> (TempDir, UiApp) {
+    let dir = tempfile::tempdir().expect("temporary workspace");
+    fs::create_dir_all(dir.path().join("src")).expect("src directory");
+    fs::write(
+        dir.path().join("src/lib.rs"),
+        "pub fn add(a: u32, b: u32) -> u32 {\n    a + b\n}\n",
+    )
+    .expect("lib.rs");
+    fs::write(dir.path().join("README.md"), "# fuzz\n\nworkspace\n").expect("README.md");
+    // The default configuration and a frozen clock, so a crash replays on any machine.
+    let mut app = UiApp::builder()
+        .config(Config::default())
+        .clipboard(MemoryClipboard::new())
+        .clock(ManualClock::default())
+        .build()
+        .expect("app")
+        .with_roots(vec![dir.path().to_path_buf()]);
+    app.bootstrap().expect("bootstrap");
+    (dir, app)
+}
+
+fuzz_target!(|data: &str| {
+    let script: String = data
+        .lines()
+        .filter(|line| {
+            let verb = line.split_whitespace().next().unwrap_or("");
+            !SKIPPED.contains(&verb)
+        })
+        .map(|line| format!("{line}\n"))
+        .collect();
+    APP.with(|app| {
+        let mut app = app.borrow_mut();
+        let (_, app) = app.get_or_insert_with(workspace);
+        // Errors are expected for most inputs; only panics are findings.
+        let _ = app.run_script(&script, |_, _| {});
+    });
+});
@@ -0,0 +1,19 @@
+//! `path[:start-end][#note]` specs as given to `--select`, recipes, and the palette's `select`.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use llmctx::app::selection::{SelectionManager, SelectionSpec};
+
+fuzz_target!(|data: &str| {
+    let Ok(spec) = SelectionSpec::parse(data) else {
+        return;
+    };
+    assert!(!spec.path.as_os_str().is_empty());
+
+    let mut manager = SelectionManager::new();
+    let item = manager.add_selection(spec.path, spec.range, spec.note);
+    if let Some((start, end)) = item.range {
+        assert!(1 <= start && start <= end, "{data:?} gave {start}-{end}");
+    }
+});
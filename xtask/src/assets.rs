@@ -0,0 +1,281 @@
+//! `cargo xtask assets`: fetch syntaxes and themes listed in `assets/sources.toml`, compile them,
+//! and regenerate the index that embeds them into the llmctx binary.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+use clap::Args;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use syntect::highlighting::ThemeSet;
+
+/// Asset directory of the llmctx crate, relative to the workspace root.
+const ASSETS_DIR: &str = "crates/llmctx/assets";
+/// Generated Rust module listing the embedded assets, relative to the workspace root.
+const INDEX_FILE: &str = "crates/llmctx/src/infra/highlight_assets.rs";
+const SOURCES_FILE: &str = "sources.toml";
+const SYNTAX_DIR: &str = "syntaxes";
+const THEME_DIR: &str = "themes";
+/// Compiled syntax set: two-face's syntaxes plus every bundled `.sublime-syntax`.
+const SYNTAX_DUMP: &str = "syntaxes.packdump";
+
+#[derive(Args, Debug)]
+pub struct AssetsArgs {
+    /// Rebuild from files already in the assets directory instead of downloading sources.
+    #[arg(long)]
+    offline: bool,
+}
+
+/// Contents of `assets/sources.toml`.
+#[derive(Debug, Default, Deserialize)]
+struct Sources {
+    #[serde(default)]
+    syntax: Vec<Source>,
+    #[serde(default)]
+    theme: Vec<Source>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Source {
+    /// File stem the asset is stored under; themes are selected by this name.
+    name: String,
+    /// Download URL pinned to a commit, e.g. a raw file URL with the commit SHA in its path.
+    url: String,
+    /// Hex SHA-256 of the file, checked before anything is written.
+    sha256: String,
+}
+
+pub fn run(args: &AssetsArgs) -> Result<()> {
+    let workspace = crate::workspace_root();
+    let assets = workspace.join(ASSETS_DIR);
+    let sources_path = assets.join(SOURCES_FILE);
+    let sources: Sources = toml::from_str(
+        &fs::read_to_string(&sources_path)
+            .with_context(|| format!("failed to read {}", sources_path.display()))?,
+    )
+    .with_context(|| format!("invalid {}", sources_path.display()))?;
+
+    if !args.offline {
+        for source in &sources.syntax {
+            let target = asset_path(&assets.join(SYNTAX_DIR), &source.name, "sublime-syntax")?;
+            download(source, &target)?;
+        }
+        for source in &sources.theme {
+            let target = asset_path(&assets.join(THEME_DIR), &source.name, "tmTheme")?;
+            download(source, &target)?;
+        }
+    }
+
+    let has_syntaxes = compile_syntaxes(&assets)?;
+    let themes = check_themes(&assets.join(THEME_DIR))?;
+    let index = workspace.join(INDEX_FILE);
+    fs::write(&index, render_index(&themes, has_syntaxes))
+        .with_context(|| format!("failed to write {}", index.display()))?;
+    // Keep the generated file in the shape `cargo fmt --check` expects; skip if rustfmt is absent.
+    let _ = Command::new("rustfmt")
+        .args(["--edition", "2024"])
+        .arg(&index)
+        .status();
+
+    println!(
+        "Embedded {} theme(s){}; index written to {INDEX_FILE}",
+        themes.len(),
+        if has_syntaxes {
+            format!(" and compiled syntaxes into {SYNTAX_DUMP}")
+        } else {
+            String::new()
+        }
+    );
+    Ok(())
+}
+
+/// `dir/<name>.<extension>`, rejecting names that would escape the asset directory.
+fn asset_path(dir: &Path, name: &str, extension: &str) -> Result<PathBuf> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '.'))
+        || name.starts_with('.')
+    {
+        bail!("invalid asset name '{name}'");
+    }
+    Ok(dir.join(format!("{name}.{extension}")))
+}
+
+/// Fetch `source` into `target` once its checksum matches; nothing is written otherwise.
+fn download(source: &Source, target: &Path) -> Result<()> {
+    let url = source.url.as_str();
+    if !is_pinned(url) {
+        bail!("{url} is not pinned to a commit; use a URL containing the full commit SHA");
+    }
+    println!("Fetching {url}");
+    let output = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--location"])
+        .arg(url)
+        .output()
+        .context("failed to run curl")?;
+    if !output.status.success() {
+        bail!(
+            "download of {url} failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    verify_checksum(&output.stdout, &source.sha256)
+        .with_context(|| format!("refusing to write {}", target.display()))?;
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    fs::write(target, &output.stdout)
+        .with_context(|| format!("failed to write {}", target.display()))
+}
+
+/// Whether a path segment of `url` is a full 40-character commit SHA, so the content can't move.
+fn is_pinned(url: &str) -> bool {
+    url.split(['/', '?', '#', '='])
+        .any(|segment| segment.len() == 40 && segment.chars().all(|ch| ch.is_ascii_hexdigit()))
+}
+
+fn verify_checksum(contents: &[u8], expected: &str) -> Result<()> {
+    let actual: String = Sha256::digest(contents)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    if !actual.eq_ignore_ascii_case(expected.trim()) {
+        bail!("checksum mismatch: expected sha256 {expected}, got {actual}");
+    }
+    Ok(())
+}
+
+/// Compile two-face's syntaxes (syntect's defaults plus bat's upstream grammars) and the bundled
+/// ones into a binary dump.
+///
+/// Returns `false` (and removes any stale dump) when no extra syntaxes are bundled, so the binary
+/// keeps using two-face's set.
+fn compile_syntaxes(assets: &Path) -> Result<bool> {
+    let dir = assets.join(SYNTAX_DIR);
+    let dump = assets.join(SYNTAX_DUMP);
+    let bundled = dir.is_dir()
+        && fs::read_dir(&dir)?
+            .filter_map(|entry| entry.ok())
+            .any(|entry| {
+                entry
+                    .path()
+                    .extension()
+                    .is_some_and(|ext| ext == "sublime-syntax")
+            });
+    if !bundled {
+        if dump.exists() {
+            fs::remove_file(&dump)
+                .with_context(|| format!("failed to remove {}", dump.display()))?;
+        }
+        return Ok(false);
+    }
+
+    let mut builder = two_face::syntax::extra_newlines().into_builder();
+    builder
+        .add_from_folder(&dir, true)
+        .with_context(|| format!("failed to load syntaxes from {}", dir.display()))?;
+    let set = builder.build();
+    syntect::dumps::dump_to_file(&set, &dump)
+        .with_context(|| format!("failed to write {}", dump.display()))?;
+    Ok(true)
+}
+
+/// Names of the `.tmTheme` files in `dir`, sorted, after checking that each one parses.
+fn check_themes(dir: &Path) -> Result<Vec<String>> {
+    let mut themes = Vec::new();
+    for entry in fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "tmTheme") {
+            continue;
+        }
+        ThemeSet::get_theme(&path).with_context(|| format!("invalid theme {}", path.display()))?;
+        if let Some(stem) = path.file_stem() {
+            themes.push(stem.to_string_lossy().into_owned());
+        }
+    }
+    themes.sort();
+    Ok(themes)
+}
+
+fn render_index(themes: &[String], has_syntaxes: bool) -> String {
+    let mut out = String::from(
+        "//! Syntax and theme assets embedded in the binary.\n\
+         //!\n\
+         //! @generated by `cargo xtask assets`; edit `assets/sources.toml` and rerun it instead.\n\
+         \n\
+         /// Bundled tmThemes, by name.\n\
+         pub static EMBEDDED_THEMES: &[(&str, &str)] = &[\n",
+    );
+    for theme in themes {
+        out.push_str(&format!(
+            "    (\"{theme}\", include_str!(\"../../assets/{THEME_DIR}/{theme}.tmTheme\")),\n"
+        ));
+    }
+    out.push_str("];\n\n");
+    out.push_str(
+        "/// Compressed syntax set (two-face's syntaxes plus bundled ones), when any are bundled.\n",
+    );
+    if has_syntaxes {
+        out.push_str(&format!(
+            "pub static EMBEDDED_SYNTAXES: Option<&[u8]> =\n    Some(include_bytes!(\"../../assets/{SYNTAX_DUMP}\"));\n"
+        ));
+    } else {
+        out.push_str("pub static EMBEDDED_SYNTAXES: Option<&[u8]> = None;\n");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_lists_themes_and_optional_syntax_dump() {
+        let index = render_index(&["dracula".to_string(), "nord".to_string()], false);
+        assert!(index.contains("(\"nord\", include_str!(\"../../assets/themes/nord.tmTheme\")),"));
+        assert!(index.contains("EMBEDDED_SYNTAXES: Option<&[u8]> = None;"));
+
+        let index = render_index(&[], true);
+        assert!(index.contains("include_bytes!(\"../../assets/syntaxes.packdump\")"));
+    }
+
+    #[test]
+    fn asset_names_stay_inside_the_asset_directory() {
+        let dir = Path::new("assets/themes");
+        assert_eq!(
+            asset_path(dir, "one-dark", "tmTheme").unwrap(),
+            dir.join("one-dark.tmTheme")
+        );
+        assert!(asset_path(dir, "../escape", "tmTheme").is_err());
+        assert!(asset_path(dir, "", "tmTheme").is_err());
+    }
+
+    #[test]
+    fn sources_must_be_pinned_and_match_their_checksum() {
+        assert!(is_pinned(
+            "https://raw.githubusercontent.com/dracula/textmate/0123456789abcdef0123456789abcdef01234567/Dracula.tmTheme"
+        ));
+        assert!(!is_pinned(
+            "https://raw.githubusercontent.com/dracula/textmate/master/Dracula.tmTheme"
+        ));
+
+        // sha256("abc")
+        let digest = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        assert!(verify_checksum(b"abc", digest).is_ok());
+        assert!(verify_checksum(b"abd", digest).is_err());
+    }
+
+    #[test]
+    fn offline_rebuild_drops_stale_syntax_dump() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        fs::create_dir(temp.path().join(SYNTAX_DIR))?;
+        fs::write(temp.path().join(SYNTAX_DUMP), b"stale")?;
+        assert!(!compile_syntaxes(temp.path())?);
+        assert!(!temp.path().join(SYNTAX_DUMP).exists());
+        Ok(())
+    }
+}
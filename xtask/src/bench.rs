@@ -0,0 +1,183 @@
+//! `cargo xtask bench`: run the llmctx criterion benchmarks in `crates/llmctx/benches/` and
+//! fail when any of them is slower than a saved baseline.
+//!
+//! Timings only compare on the same machine, so the baseline is saved locally with
+//! `--save-baseline` (e.g. on the main branch) before comparing a change with it.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+use clap::Args;
+use serde::Deserialize;
+
+#[derive(Args, Debug)]
+pub struct BenchArgs {
+    /// Only run benchmarks whose names contain one of these, e.g. `scan` or `export`.
+    filters: Vec<String>,
+    /// Record this run as the baseline instead of comparing with it.
+    #[arg(long)]
+    save_baseline: bool,
+    /// Name of the baseline to save or compare with.
+    #[arg(long, default_value = "main")]
+    baseline: String,
+    /// Slowdown of a median over the baseline, in percent, that fails the comparison.
+    #[arg(long, default_value_t = 10.0)]
+    threshold: f64,
+}
+
+pub fn run(args: &BenchArgs) -> Result<()> {
+    let criterion = target_dir().join("criterion");
+    if !args.save_baseline {
+        // Changes left by earlier runs would be reported again for benchmarks filtered out.
+        clear_changes(&criterion)?;
+    }
+
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let mut command = Command::new(cargo);
+    command.current_dir(crate::workspace_root()).args([
+        "bench",
+        "--package",
+        "llmctx",
+        "--bench",
+        "perf",
+        "--",
+    ]);
+    if !args.filters.is_empty() {
+        command.arg(args.filters.join("|"));
+    }
+    if args.save_baseline {
+        command.args(["--save-baseline", &args.baseline]);
+    } else {
+        command.args(["--baseline", &args.baseline]);
+    }
+    let status = command.status().context("failed to run cargo bench")?;
+    if !status.success() {
+        if args.save_baseline {
+            bail!("benchmarks failed");
+        }
+        bail!(
+            "benchmarks failed; without a saved baseline, record one first with \
+             `cargo xtask bench --save-baseline --baseline {}`",
+            args.baseline
+        );
+    }
+    if args.save_baseline {
+        println!("Saved baseline '{}'", args.baseline);
+        return Ok(());
+    }
+
+    let changes = changes(&criterion)?;
+    let mut slower = 0;
+    for (name, change) in &changes {
+        let percent = change * 100.0;
+        let verdict = if percent > args.threshold {
+            slower += 1;
+            "SLOWER"
+        } else {
+            "ok"
+        };
+        println!(
+            "{name:<20} median {percent:>+7.1}% vs '{}'  {verdict}",
+            args.baseline
+        );
+    }
+    if slower > 0 {
+        bail!(
+            "{slower} benchmark(s) more than {}% slower than baseline '{}'",
+            args.threshold,
+            args.baseline
+        );
+    }
+    Ok(())
+}
+
+fn target_dir() -> PathBuf {
+    std::env::var_os("CARGO_TARGET_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| crate::workspace_root().join("target"))
+}
+
+/// Criterion's `<benchmark>/change/` directories, one per benchmark compared with a baseline.
+fn change_dirs(criterion: &Path) -> Result<Vec<(String, PathBuf)>> {
+    let Ok(entries) = fs::read_dir(criterion) else {
+        return Ok(Vec::new());
+    };
+    let mut dirs = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let change = entry.path().join("change");
+        if change.is_dir() {
+            dirs.push((entry.file_name().to_string_lossy().into_owned(), change));
+        }
+    }
+    dirs.sort();
+    Ok(dirs)
+}
+
+fn clear_changes(criterion: &Path) -> Result<()> {
+    for (_, dir) in change_dirs(criterion)? {
+        fs::remove_dir_all(&dir).with_context(|| format!("failed to remove {}", dir.display()))?;
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct ChangeEstimates {
+    median: Estimate,
+}
+
+#[derive(Deserialize)]
+struct Estimate {
+    point_estimate: f64,
+}
+
+/// Relative change of each compared benchmark's median, e.g. `0.05` for 5% slower.
+fn changes(criterion: &Path) -> Result<Vec<(String, f64)>> {
+    change_dirs(criterion)?
+        .into_iter()
+        .map(|(name, dir)| {
+            let path = dir.join("estimates.json");
+            let text = fs::read_to_string(&path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            let estimates: ChangeEstimates = serde_json::from_str(&text)
+                .with_context(|| format!("failed to parse {}", path.display()))?;
+            Ok((name, estimates.median.point_estimate))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_median_changes_and_clears_them() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let criterion = dir.path();
+        for (name, median) in [("scan_50k_files", 0.25), ("export_500_files", -0.03)] {
+            let change = criterion.join(name).join("change");
+            fs::create_dir_all(&change)?;
+            fs::write(
+                change.join("estimates.json"),
+                format!(
+                    r#"{{"mean":{{"point_estimate":0.5}},"median":{{"point_estimate":{median}}}}}"#
+                ),
+            )?;
+        }
+        fs::create_dir_all(criterion.join("estimate_500_files").join("new"))?;
+
+        assert_eq!(
+            changes(criterion)?,
+            vec![
+                ("export_500_files".to_string(), -0.03),
+                ("scan_50k_files".to_string(), 0.25),
+            ]
+        );
+        clear_changes(criterion)?;
+        assert!(changes(criterion)?.is_empty());
+        assert!(criterion.join("scan_50k_files").is_dir());
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+//! `cargo xtask fuzz`: run the `cargo fuzz` targets in `fuzz/` against llmctx's parsers.
+//!
+//! Needs `cargo install cargo-fuzz` and a nightly toolchain.
+
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+use clap::Args;
+
+/// Targets defined in `fuzz/Cargo.toml`.
+const TARGETS: &[&str] = &["selection_spec", "palette", "config"];
+
+#[derive(Args, Debug)]
+pub struct FuzzArgs {
+    /// Target to run; runs each target in turn when omitted.
+    #[arg(value_parser = clap::builder::PossibleValuesParser::new(TARGETS))]
+    target: Option<String>,
+    /// Seconds to fuzz each target for.
+    #[arg(long, default_value_t = 60)]
+    seconds: u64,
+}
+
+pub fn run(args: &FuzzArgs) -> Result<()> {
+    let targets: Vec<&str> = match &args.target {
+        Some(target) => vec![target.as_str()],
+        None => TARGETS.to_vec(),
+    };
+    for target in targets {
+        println!("Fuzzing {target} for {}s", args.seconds);
+        let status = Command::new("cargo")
+            .current_dir(crate::workspace_root())
+            .args(["+nightly", "fuzz", "run", target, "--"])
+            .arg(format!("-max_total_time={}", args.seconds))
+            .status()
+            .context("failed to run cargo fuzz; install it with `cargo install cargo-fuzz`")?;
+        if !status.success() {
+            bail!(
+                "fuzz target {target} failed; the crashing input is under fuzz/artifacts/{target}/"
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,42 @@
+//! Maintainer tasks for the llmctx workspace, run with `cargo xtask <command>`.
+
+mod assets;
+mod bench;
+mod fuzz;
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+
+#[derive(Parser)]
+#[command(name = "xtask", about = "Maintainer tasks for the llmctx workspace")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Refresh bundled syntax definitions and themes and regenerate the embedded index.
+    Assets(assets::AssetsArgs),
+    /// Run the scan, estimate, and export benchmarks and compare them with a saved baseline.
+    Bench(bench::BenchArgs),
+    /// Fuzz the selection-spec, palette, and config parsers with `cargo fuzz`.
+    Fuzz(fuzz::FuzzArgs),
+}
+
+fn main() -> Result<()> {
+    match Cli::parse().command {
+        Command::Assets(args) => assets::run(&args),
+        Command::Bench(args) => bench::run(&args),
+        Command::Fuzz(args) => fuzz::run(&args),
+    }
+}
+
+fn workspace_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .expect("xtask lives inside the workspace")
+        .to_path_buf()
+}